@@ -0,0 +1,20 @@
+-- Transactional outbox: domain events are written in the same transaction as
+-- the mutation that produced them and delivered asynchronously by the relay.
+CREATE TABLE outbox (
+    id BIGSERIAL PRIMARY KEY,
+    event_type TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_error TEXT,
+    delivered_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_outbox_pending
+    ON outbox(next_attempt_at, id)
+    WHERE delivered_at IS NULL;
+
+CREATE INDEX idx_outbox_delivered
+    ON outbox(delivered_at)
+    WHERE delivered_at IS NOT NULL;
@@ -0,0 +1,8 @@
+-- Postgres full-text search fallback used when no external search backend is configured.
+CREATE INDEX IF NOT EXISTS idx_threads_search
+    ON threads USING GIN (to_tsvector('simple', subject || ' ' || body))
+    WHERE deleted_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_replies_search
+    ON replies USING GIN (to_tsvector('simple', content))
+    WHERE deleted_at IS NULL;
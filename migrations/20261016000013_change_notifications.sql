@@ -0,0 +1,25 @@
+-- LISTEN/NOTIFY hooks: replicas invalidate in-process caches when cached
+-- tables change and wake the outbox relay as soon as an event is committed.
+-- Notifications are only delivered on commit, so listeners never observe
+-- rolled-back writes.
+CREATE OR REPLACE FUNCTION rib_notify_cache() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('rib_cache', TG_TABLE_NAME);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER boards_notify_cache
+    AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON boards
+    FOR EACH STATEMENT EXECUTE FUNCTION rib_notify_cache();
+
+CREATE OR REPLACE FUNCTION rib_notify_outbox() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('rib_outbox', '');
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER outbox_notify_relay
+    AFTER INSERT ON outbox
+    FOR EACH STATEMENT EXECUTE FUNCTION rib_notify_outbox();
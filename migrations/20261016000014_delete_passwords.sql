@@ -0,0 +1,4 @@
+-- Optional per-post deletion passwords (argon2 PHC strings) so anonymous
+-- posters can remove their own posts without an account.
+ALTER TABLE threads ADD COLUMN delete_password_hash TEXT;
+ALTER TABLE replies ADD COLUMN delete_password_hash TEXT;
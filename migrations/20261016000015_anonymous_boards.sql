@@ -0,0 +1,3 @@
+-- Boards that accept posts without a signed-in account (proof of work and
+-- stricter rate limits apply instead).
+ALTER TABLE boards ADD COLUMN anonymous_posting BOOLEAN NOT NULL DEFAULT FALSE;
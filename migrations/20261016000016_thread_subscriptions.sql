@@ -0,0 +1,21 @@
+CREATE TABLE thread_subscriptions (
+    subject TEXT NOT NULL,
+    thread_id BIGINT NOT NULL REFERENCES threads(id) ON DELETE CASCADE,
+    -- Replies up to this id predate the subscription or were already notified.
+    last_notified_reply_id BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (subject, thread_id)
+);
+
+CREATE TABLE notification_settings (
+    subject TEXT PRIMARY KEY,
+    email TEXT,
+    email_verified BOOLEAN NOT NULL DEFAULT FALSE,
+    digest TEXT NOT NULL DEFAULT 'off' CHECK (digest IN ('off', 'immediate', 'daily')),
+    last_digest_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_notification_settings_due
+    ON notification_settings(digest, last_digest_at)
+    WHERE email_verified;
@@ -0,0 +1,5 @@
+CREATE TABLE user_preferences (
+    subject TEXT PRIMARY KEY,
+    preferences JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
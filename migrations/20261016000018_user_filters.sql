@@ -0,0 +1,10 @@
+CREATE TABLE user_filters (
+    id BIGSERIAL PRIMARY KEY,
+    subject TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('thread', 'subject', 'tripcode', 'keyword')),
+    value TEXT NOT NULL,
+    -- For subject mutes: the author resolved from the post in `value`.
+    target_subject TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (subject, kind, value)
+);
@@ -0,0 +1,24 @@
+CREATE TABLE user_profiles (
+    subject TEXT PRIMARY KEY,
+    display_name TEXT,
+    avatar_hash TEXT,
+    avatar_mime TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Display names are unique regardless of case.
+CREATE UNIQUE INDEX idx_user_profiles_display_name
+    ON user_profiles(lower(display_name))
+    WHERE display_name IS NOT NULL;
+
+-- Public profile of a post's author, NULL when they have not set one.
+CREATE FUNCTION author_profile(created_by JSONB) RETURNS JSONB
+LANGUAGE sql STABLE AS $$
+    SELECT jsonb_strip_nulls(jsonb_build_object(
+        'display_name', p.display_name,
+        'avatar_hash', p.avatar_hash
+    ))
+    FROM user_profiles p
+    WHERE p.subject = created_by->>'subject'
+      AND (p.display_name IS NOT NULL OR p.avatar_hash IS NOT NULL)
+$$;
@@ -0,0 +1,18 @@
+-- Thread creators may delete replies in and close their own threads.
+ALTER TABLE boards ADD COLUMN op_moderation BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Closed threads stay readable but take no new replies.
+ALTER TABLE threads ADD COLUMN closed_at TIMESTAMPTZ;
+
+-- No foreign keys: entries outlive hard-deleted threads and replies.
+CREATE TABLE moderation_log (
+    id BIGSERIAL PRIMARY KEY,
+    actor TEXT NOT NULL,
+    actor_role TEXT NOT NULL CHECK (actor_role IN ('op', 'moderator', 'admin')),
+    action TEXT NOT NULL CHECK (action IN ('delete_reply', 'close_thread', 'reopen_thread')),
+    thread_id BIGINT NOT NULL,
+    reply_id BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_moderation_log_thread ON moderation_log(thread_id, id);
@@ -0,0 +1,11 @@
+-- Page limit: beyond max_threads active threads (0 = unlimited) the least
+-- recently bumped ones are archived, or soft-deleted when prune_overflow is set.
+ALTER TABLE boards
+    ADD COLUMN max_threads INTEGER NOT NULL DEFAULT 0 CHECK (max_threads >= 0),
+    ADD COLUMN prune_overflow BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Archived threads stay readable but leave the board index and take no replies.
+ALTER TABLE threads ADD COLUMN archived_at TIMESTAMPTZ;
+
+CREATE INDEX idx_threads_board_unarchived ON threads(board_id, bump_time DESC)
+    WHERE deleted_at IS NULL AND archived_at IS NULL;
@@ -0,0 +1,6 @@
+-- Minimum seconds between one poster's replies in the same thread; 0 disables.
+ALTER TABLE boards
+    ADD COLUMN reply_cooldown_secs INTEGER NOT NULL DEFAULT 0 CHECK (reply_cooldown_secs >= 0);
+
+CREATE INDEX idx_replies_thread_subject
+    ON replies(thread_id, (created_by->>'subject'), created_at DESC);
@@ -0,0 +1,18 @@
+-- Threads staff queue up to be posted later, optionally repeating.
+CREATE TABLE scheduled_threads (
+    id BIGSERIAL PRIMARY KEY,
+    board_id BIGINT NOT NULL REFERENCES boards(id) ON DELETE CASCADE,
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    author_name TEXT,
+    publish_at TIMESTAMPTZ NOT NULL,
+    repeat_days INTEGER CHECK (repeat_days > 0),
+    created_by JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_thread_id BIGINT REFERENCES threads(id) ON DELETE SET NULL,
+    -- Set once a one-off schedule has posted its thread.
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_scheduled_threads_due ON scheduled_threads(publish_at)
+    WHERE completed_at IS NULL;
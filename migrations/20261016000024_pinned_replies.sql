@@ -0,0 +1,8 @@
+-- One reply per thread shown above the rest, chosen by staff or the thread creator.
+ALTER TABLE threads
+    ADD COLUMN pinned_reply_id BIGINT REFERENCES replies(id) ON DELETE SET NULL;
+
+ALTER TABLE moderation_log DROP CONSTRAINT moderation_log_action_check;
+ALTER TABLE moderation_log ADD CONSTRAINT moderation_log_action_check CHECK (
+    action IN ('delete_reply', 'close_thread', 'reopen_thread', 'pin_reply', 'unpin_reply')
+);
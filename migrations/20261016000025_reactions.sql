@@ -0,0 +1,23 @@
+-- Emoji a board accepts as reactions; an empty set turns reactions off.
+ALTER TABLE boards ADD COLUMN reactions TEXT[] NOT NULL DEFAULT '{}';
+
+-- One reaction per subject per reply; reacting again replaces it.
+CREATE TABLE reply_reactions (
+    reply_id BIGINT NOT NULL REFERENCES replies(id) ON DELETE CASCADE,
+    subject TEXT NOT NULL,
+    emoji TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (reply_id, subject)
+);
+
+-- Reaction counts of a reply, most used first; an empty array when it has none.
+CREATE FUNCTION reaction_counts(reply BIGINT) RETURNS JSONB
+LANGUAGE sql STABLE AS $$
+    SELECT COALESCE(
+        jsonb_agg(jsonb_build_object('emoji', c.emoji, 'count', c.n) ORDER BY c.n DESC, c.emoji),
+        '[]'::jsonb
+    )
+    FROM (
+        SELECT emoji, count(*) AS n FROM reply_reactions WHERE reply_id = reply GROUP BY emoji
+    ) c
+$$;
@@ -0,0 +1,7 @@
+-- Tags a board's threads may carry; an empty vocabulary accepts any tag.
+ALTER TABLE boards ADD COLUMN tag_vocabulary TEXT[] NOT NULL DEFAULT '{}';
+
+ALTER TABLE threads ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+-- Serves the `tags @> ARRAY[tag]` filter on board listings.
+CREATE INDEX idx_threads_tags ON threads USING GIN (tags);
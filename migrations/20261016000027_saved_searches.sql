@@ -0,0 +1,29 @@
+-- Queries a subject wants to hear about. The `last_*_id` columns mark the
+-- newest posts already evaluated, so the matcher only reads content posted
+-- since its previous pass.
+CREATE TABLE saved_searches (
+    id BIGSERIAL PRIMARY KEY,
+    subject TEXT NOT NULL,
+    query TEXT NOT NULL,
+    board_id BIGINT REFERENCES boards(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_thread_id BIGINT NOT NULL DEFAULT 0,
+    last_reply_id BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX idx_saved_searches_subject ON saved_searches(subject);
+
+-- Posts a saved search matched; `notified_at` is set once a digest lists them.
+CREATE TABLE saved_search_matches (
+    id BIGSERIAL PRIMARY KEY,
+    search_id BIGINT NOT NULL REFERENCES saved_searches(id) ON DELETE CASCADE,
+    thread_id BIGINT NOT NULL REFERENCES threads(id) ON DELETE CASCADE,
+    reply_id BIGINT REFERENCES replies(id) ON DELETE CASCADE,
+    matched_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    notified_at TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX idx_saved_search_matches_post
+    ON saved_search_matches(search_id, thread_id, COALESCE(reply_id, 0));
+CREATE INDEX idx_saved_search_matches_pending
+    ON saved_search_matches(search_id) WHERE notified_at IS NULL;
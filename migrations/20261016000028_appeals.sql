@@ -0,0 +1,22 @@
+-- Users contest a ban or the deletion of one of their posts, once per action.
+-- No foreign keys: appeals outlive hard-deleted posts.
+CREATE TABLE appeals (
+    id BIGSERIAL PRIMARY KEY,
+    subject TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('ban', 'thread', 'reply')),
+    target_id BIGINT,
+    message TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'accepted', 'denied')),
+    -- Ban creation or post deletion time: a new ban or a second deletion is a new action.
+    action_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    decided_by TEXT,
+    decided_at TIMESTAMPTZ,
+    note TEXT,
+    CHECK ((kind = 'ban') = (target_id IS NULL))
+);
+
+CREATE UNIQUE INDEX idx_appeals_action
+    ON appeals(subject, kind, COALESCE(target_id, 0), action_at);
+CREATE INDEX idx_appeals_subject ON appeals(subject, id);
+CREATE INDEX idx_appeals_pending ON appeals(id) WHERE status = 'pending';
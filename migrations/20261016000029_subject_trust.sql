@@ -0,0 +1,88 @@
+-- History behind per-subject trust scores, kept current by the triggers below.
+CREATE TABLE subject_trust (
+    subject TEXT PRIMARY KEY,
+    first_seen_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    posts BIGINT NOT NULL DEFAULT 0,
+    -- Posts staff deleted or rejected from review and did not restore.
+    removals BIGINT NOT NULL DEFAULT 0,
+    bans BIGINT NOT NULL DEFAULT 0
+);
+
+-- Posts held for review stay hidden like deleted posts until staff decide.
+ALTER TABLE threads ADD COLUMN held_at TIMESTAMPTZ;
+ALTER TABLE replies ADD COLUMN held_at TIMESTAMPTZ;
+CREATE INDEX idx_threads_held ON threads(held_at) WHERE held_at IS NOT NULL;
+CREATE INDEX idx_replies_held ON replies(held_at) WHERE held_at IS NOT NULL;
+
+CREATE FUNCTION trust_count_post() RETURNS trigger AS $$
+BEGIN
+    IF NEW.created_by->>'subject' IS NOT NULL THEN
+        INSERT INTO subject_trust (subject, first_seen_at, posts)
+        VALUES (NEW.created_by->>'subject', NEW.created_at, 1)
+        ON CONFLICT (subject) DO UPDATE SET
+            posts = subject_trust.posts + 1,
+            first_seen_at = LEAST(subject_trust.first_seen_at, EXCLUDED.first_seen_at);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Holding a post is not a removal, rejecting it is. Deletions in a
+-- transaction that set rib.trust_exempt (an author deleting their own post,
+-- thread limit pruning) are not held against the author.
+CREATE FUNCTION trust_count_removal() RETURNS trigger AS $$
+DECLARE
+    delta BIGINT := 0;
+BEGIN
+    IF NEW.created_by->>'subject' IS NULL
+        OR current_setting('rib.trust_exempt', true) = 'on' THEN
+        RETURN NULL;
+    END IF;
+    IF NEW.deleted_at IS NOT NULL AND NEW.held_at IS NULL
+        AND (OLD.deleted_at IS NULL OR OLD.held_at IS NOT NULL) THEN
+        delta := 1;
+    ELSIF NEW.deleted_at IS NULL AND OLD.deleted_at IS NOT NULL AND OLD.held_at IS NULL THEN
+        delta := -1;
+    END IF;
+    IF delta <> 0 THEN
+        UPDATE subject_trust SET removals = GREATEST(removals + delta, 0)
+        WHERE subject = NEW.created_by->>'subject';
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION trust_count_ban() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO subject_trust (subject, bans) VALUES (NEW.subject, 1)
+    ON CONFLICT (subject) DO UPDATE SET bans = subject_trust.bans + 1;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER threads_trust_post AFTER INSERT ON threads
+    FOR EACH ROW EXECUTE FUNCTION trust_count_post();
+CREATE TRIGGER replies_trust_post AFTER INSERT ON replies
+    FOR EACH ROW EXECUTE FUNCTION trust_count_post();
+CREATE TRIGGER threads_trust_removal AFTER UPDATE OF deleted_at, held_at ON threads
+    FOR EACH ROW EXECUTE FUNCTION trust_count_removal();
+CREATE TRIGGER replies_trust_removal AFTER UPDATE OF deleted_at, held_at ON replies
+    FOR EACH ROW EXECUTE FUNCTION trust_count_removal();
+CREATE TRIGGER subject_bans_trust AFTER INSERT OR UPDATE ON subject_bans
+    FOR EACH ROW EXECUTE FUNCTION trust_count_ban();
+
+-- Backfill existing history. Earlier deletions cannot be told apart, so all
+-- of them count as removals.
+INSERT INTO subject_trust (subject, first_seen_at, posts, removals)
+SELECT subject, min(created_at), count(*), count(*) FILTER (WHERE deleted_at IS NOT NULL)
+FROM (
+    SELECT created_by->>'subject' AS subject, created_at, deleted_at FROM threads
+    UNION ALL
+    SELECT created_by->>'subject', created_at, deleted_at FROM replies
+) posts
+WHERE subject IS NOT NULL
+GROUP BY subject;
+
+INSERT INTO subject_trust (subject, first_seen_at, bans)
+SELECT subject, created_at, 1 FROM subject_bans
+ON CONFLICT (subject) DO UPDATE SET bans = 1;
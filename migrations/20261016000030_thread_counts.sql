@@ -0,0 +1,74 @@
+-- Visible replies per thread and how many of them carry an image, kept
+-- current by the triggers below so listings do not count per request.
+ALTER TABLE threads
+    ADD COLUMN reply_count INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN image_count INTEGER NOT NULL DEFAULT 0;
+
+-- Full recount of one thread, for the rare paths where a delta is unknown.
+CREATE FUNCTION refresh_thread_counts(target BIGINT) RETURNS void AS $$
+    UPDATE threads t SET
+        reply_count = (SELECT count(*) FROM replies r
+                       WHERE r.thread_id = target AND r.deleted_at IS NULL),
+        image_count = (SELECT count(*) FROM replies r JOIN images i ON i.reply_id = r.id
+                       WHERE r.thread_id = target AND r.deleted_at IS NULL)
+    WHERE t.id = target;
+$$ LANGUAGE sql;
+
+CREATE FUNCTION thread_counts_reply() RETURNS trigger AS $$
+DECLARE
+    has_image INTEGER;
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        -- Images are attached after the reply row; the images trigger counts them.
+        IF NEW.deleted_at IS NULL THEN
+            UPDATE threads SET reply_count = reply_count + 1 WHERE id = NEW.thread_id;
+        END IF;
+    ELSIF TG_OP = 'DELETE' THEN
+        -- Cascades may already have removed the reply's image.
+        PERFORM refresh_thread_counts(OLD.thread_id);
+    ELSIF (OLD.deleted_at IS NULL) IS DISTINCT FROM (NEW.deleted_at IS NULL)
+        OR OLD.thread_id <> NEW.thread_id THEN
+        has_image := (SELECT count(*) FROM images WHERE reply_id = NEW.id);
+        IF OLD.deleted_at IS NULL THEN
+            UPDATE threads SET reply_count = reply_count - 1, image_count = image_count - has_image
+            WHERE id = OLD.thread_id;
+        END IF;
+        IF NEW.deleted_at IS NULL THEN
+            UPDATE threads SET reply_count = reply_count + 1, image_count = image_count + has_image
+            WHERE id = NEW.thread_id;
+        END IF;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION thread_counts_image() RETURNS trigger AS $$
+DECLARE
+    image_reply BIGINT := CASE WHEN TG_OP = 'INSERT' THEN NEW.reply_id ELSE OLD.reply_id END;
+    delta INTEGER := CASE WHEN TG_OP = 'INSERT' THEN 1 ELSE -1 END;
+BEGIN
+    IF image_reply IS NOT NULL THEN
+        UPDATE threads t SET image_count = t.image_count + delta
+        FROM replies r
+        WHERE r.id = image_reply AND r.deleted_at IS NULL AND t.id = r.thread_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER replies_thread_counts AFTER INSERT OR DELETE OR UPDATE OF deleted_at, thread_id
+    ON replies FOR EACH ROW EXECUTE FUNCTION thread_counts_reply();
+CREATE TRIGGER images_thread_counts AFTER INSERT OR DELETE ON images
+    FOR EACH ROW EXECUTE FUNCTION thread_counts_image();
+
+UPDATE threads t SET
+    reply_count = c.replies,
+    image_count = c.images
+FROM (
+    SELECT r.thread_id, count(*) AS replies, count(i.id) AS images
+    FROM replies r
+    LEFT JOIN images i ON i.reply_id = r.id
+    WHERE r.deleted_at IS NULL
+    GROUP BY r.thread_id
+) c
+WHERE t.id = c.thread_id;
@@ -0,0 +1,16 @@
+-- Blobs accepted by the upload endpoint, one row per uploader, so sizes are
+-- known without asking object storage and usage can be summed per subject.
+CREATE TABLE uploads (
+    hash TEXT NOT NULL CHECK (hash ~ '^[0-9a-f]{64}$'),
+    uploaded_by TEXT NOT NULL,
+    mime TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL CHECK (size_bytes >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (hash, uploaded_by)
+);
+
+CREATE INDEX idx_uploads_uploader ON uploads(uploaded_by, created_at);
+
+-- Copied from `uploads` when a post attaches the blob; NULL for attachments
+-- that predate this column or arrived through imports.
+ALTER TABLE images ADD COLUMN size_bytes BIGINT;
@@ -0,0 +1,7 @@
+-- Client-supplied file name next to the sniffed type, and why the upload was
+-- flagged when the two disagree.
+ALTER TABLE uploads
+    ADD COLUMN file_name TEXT,
+    ADD COLUMN flag_reason TEXT;
+
+CREATE INDEX idx_uploads_flagged ON uploads(created_at) WHERE flag_reason IS NOT NULL;
@@ -0,0 +1,3 @@
+-- Attachment types a board accepts: MIME types, type/* wildcards or category
+-- names. Empty defers to the deployment's UPLOAD_ALLOWED_MIME list.
+ALTER TABLE boards ADD COLUMN allowed_mime TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,12 @@
+-- Blobs held back from public serving until staff review them. `source` names
+-- what flagged the blob: the upload checks, a scanner, or a moderator. Rows
+-- stay after a blob is destroyed so the same bytes cannot be uploaded again.
+CREATE TABLE image_quarantine (
+    hash TEXT PRIMARY KEY CHECK (hash ~ '^[0-9a-f]{64}$'),
+    reason TEXT NOT NULL,
+    source TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    destroyed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_image_quarantine_pending ON image_quarantine(created_at) WHERE destroyed_at IS NULL;
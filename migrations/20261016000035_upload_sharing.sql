@@ -0,0 +1,3 @@
+-- Posts may attach a blob only when the poster uploaded it, or when one of its
+-- uploaders marked it shareable.
+ALTER TABLE uploads ADD COLUMN shareable BOOLEAN NOT NULL DEFAULT false;
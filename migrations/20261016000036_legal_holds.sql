@@ -0,0 +1,49 @@
+-- Legal holds keep a thread, reply or blob from being hard-deleted or
+-- garbage-collected while a DMCA or law-enforcement process runs. Rows stay
+-- after release: with the reasons and actors they are the hold audit trail.
+-- No foreign keys: a hold names its target by id or hash only.
+CREATE TABLE legal_holds (
+    id BIGSERIAL PRIMARY KEY,
+    thread_id BIGINT,
+    reply_id BIGINT,
+    image_hash TEXT CHECK (image_hash ~ '^[0-9a-f]{64}$'),
+    reason TEXT NOT NULL,
+    placed_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    released_at TIMESTAMPTZ,
+    released_by TEXT,
+    release_reason TEXT,
+    CHECK (num_nonnulls(thread_id, reply_id, image_hash) = 1),
+    CHECK (num_nulls(released_at, released_by, release_reason) IN (0, 3))
+);
+
+CREATE UNIQUE INDEX idx_legal_holds_thread ON legal_holds(thread_id) WHERE released_at IS NULL;
+CREATE UNIQUE INDEX idx_legal_holds_reply ON legal_holds(reply_id) WHERE released_at IS NULL;
+CREATE UNIQUE INDEX idx_legal_holds_image ON legal_holds(image_hash) WHERE released_at IS NULL;
+
+-- Refuse hard deletes of held posts, cascades from boards and threads
+-- included. A thread hold covers its replies.
+CREATE FUNCTION refuse_held_delete() RETURNS trigger AS $$
+DECLARE
+    held BOOLEAN;
+BEGIN
+    IF TG_TABLE_NAME = 'threads' THEN
+        held := EXISTS (SELECT 1 FROM legal_holds WHERE released_at IS NULL AND thread_id = OLD.id);
+    ELSE
+        held := EXISTS (
+            SELECT 1 FROM legal_holds
+            WHERE released_at IS NULL AND (reply_id = OLD.id OR thread_id = OLD.thread_id)
+        );
+    END IF;
+    IF held THEN
+        RAISE EXCEPTION '% % is under legal hold', TG_TABLE_NAME, OLD.id
+            USING ERRCODE = 'restrict_violation', CONSTRAINT = 'legal_holds';
+    END IF;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER threads_legal_hold BEFORE DELETE ON threads
+    FOR EACH ROW EXECUTE FUNCTION refuse_held_delete();
+CREATE TRIGGER replies_legal_hold BEFORE DELETE ON replies
+    FOR EACH ROW EXECUTE FUNCTION refuse_held_delete();
@@ -0,0 +1,12 @@
+-- Short-lived, single-use values (sign-in challenges, redeemed proof of work,
+-- used OAuth states and magic-link nonces) for CHALLENGE_STORE=postgres.
+-- Rows past expires_at are ignored and deleted as new ones are written.
+CREATE TABLE challenges (
+    namespace TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (namespace, key)
+);
+
+CREATE INDEX idx_challenges_expires_at ON challenges(expires_at);
@@ -0,0 +1,23 @@
+-- Outbound bridges announce new threads on a board to a Discord webhook, a
+-- Telegram chat or a Nostr relay. `last_thread_id` is the cursor: threads up
+-- to it were announced (or predate the bridge). A runner leases a bridge
+-- while it posts, so replicas never announce the same thread twice, and the
+-- window columns count posts sent in the current minute for rate limiting.
+CREATE TABLE board_bridges (
+    id BIGSERIAL PRIMARY KEY,
+    board_id BIGINT NOT NULL REFERENCES boards(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('discord', 'telegram', 'nostr')),
+    target TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_thread_id BIGINT NOT NULL DEFAULT 0,
+    last_sent_at TIMESTAMPTZ,
+    last_error TEXT,
+    window_started_at TIMESTAMPTZ,
+    window_sent INT NOT NULL DEFAULT 0,
+    leased_until TIMESTAMPTZ,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (board_id, kind, target)
+);
+
+CREATE INDEX idx_board_bridges_due ON board_bridges(leased_until) WHERE enabled;
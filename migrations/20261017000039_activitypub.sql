@@ -0,0 +1,31 @@
+-- ActivityPub federation: remote actors following a board, the newest thread
+-- each followed board has fanned out, and the queue of signed deliveries to
+-- remote inboxes. Deliveries are retried with backoff and dropped after too
+-- many failures; `next_attempt_at` doubles as the lease while one is sent.
+CREATE TABLE ap_followers (
+    id BIGSERIAL PRIMARY KEY,
+    board_id BIGINT NOT NULL REFERENCES boards(id) ON DELETE CASCADE,
+    actor TEXT NOT NULL,
+    inbox TEXT NOT NULL,
+    shared_inbox TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (board_id, actor)
+);
+
+CREATE TABLE ap_board_cursors (
+    board_id BIGINT PRIMARY KEY REFERENCES boards(id) ON DELETE CASCADE,
+    last_thread_id BIGINT NOT NULL
+);
+
+CREATE TABLE ap_deliveries (
+    id BIGSERIAL PRIMARY KEY,
+    board_id BIGINT NOT NULL REFERENCES boards(id) ON DELETE CASCADE,
+    inbox TEXT NOT NULL,
+    activity JSONB NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_ap_deliveries_due ON ap_deliveries(next_attempt_at);
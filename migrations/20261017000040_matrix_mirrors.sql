@@ -0,0 +1,37 @@
+-- Matrix rooms mirroring a whole board or a single thread. New threads (for
+-- board mirrors) and replies are posted to the room after the two cursors,
+-- and `matrix_events` maps each mirrored post to its room event so that
+-- deleting the post redacts the event. A runner leases a mirror while it
+-- posts, so replicas never post the same reply twice.
+CREATE TABLE matrix_mirrors (
+    id BIGSERIAL PRIMARY KEY,
+    board_id BIGINT REFERENCES boards(id) ON DELETE CASCADE,
+    thread_id BIGINT REFERENCES threads(id) ON DELETE CASCADE,
+    room_id TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_thread_id BIGINT NOT NULL DEFAULT 0,
+    last_reply_id BIGINT NOT NULL DEFAULT 0,
+    last_sent_at TIMESTAMPTZ,
+    last_error TEXT,
+    leased_until TIMESTAMPTZ,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK ((board_id IS NULL) <> (thread_id IS NULL))
+);
+
+CREATE UNIQUE INDEX idx_matrix_mirrors_board_room ON matrix_mirrors(board_id, room_id)
+    WHERE board_id IS NOT NULL;
+CREATE UNIQUE INDEX idx_matrix_mirrors_thread_room ON matrix_mirrors(thread_id, room_id)
+    WHERE thread_id IS NOT NULL;
+CREATE INDEX idx_matrix_mirrors_due ON matrix_mirrors(leased_until) WHERE enabled;
+
+CREATE TABLE matrix_events (
+    mirror_id BIGINT NOT NULL REFERENCES matrix_mirrors(id) ON DELETE CASCADE,
+    event_id TEXT NOT NULL,
+    thread_id BIGINT NOT NULL REFERENCES threads(id) ON DELETE CASCADE,
+    reply_id BIGINT REFERENCES replies(id) ON DELETE CASCADE,
+    redacted_at TIMESTAMPTZ,
+    PRIMARY KEY (mirror_id, event_id)
+);
+
+CREATE INDEX idx_matrix_events_live ON matrix_events(mirror_id) WHERE redacted_at IS NULL;
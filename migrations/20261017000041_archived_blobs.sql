@@ -0,0 +1,9 @@
+-- Blobs moved to the archive storage class because every post attaching
+-- them sits in an archived thread. A blob attached again to a live post is
+-- moved back and its row removed.
+CREATE TABLE archived_blobs (
+    hash TEXT PRIMARY KEY CHECK (hash ~ '^[0-9a-f]{64}$'),
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_threads_archived ON threads(archived_at) WHERE archived_at IS NOT NULL;
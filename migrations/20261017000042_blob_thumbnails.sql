@@ -0,0 +1,7 @@
+-- First-page previews rendered for document uploads. The preview image is a
+-- blob of its own in the image store.
+CREATE TABLE blob_thumbnails (
+    hash TEXT PRIMARY KEY CHECK (hash ~ '^[0-9a-f]{64}$'),
+    thumbnail_hash TEXT NOT NULL CHECK (thumbnail_hash ~ '^[0-9a-f]{64}$'),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
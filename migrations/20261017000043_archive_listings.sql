@@ -0,0 +1,6 @@
+-- Files inside uploaded archives, listed when they were inspected.
+CREATE TABLE archive_listings (
+    hash TEXT PRIMARY KEY CHECK (hash ~ '^[0-9a-f]{64}$'),
+    listing JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,10 @@
+-- Opening lines of text and code uploads, kept so clients can preview them
+-- without downloading the file.
+CREATE TABLE text_excerpts (
+    hash TEXT PRIMARY KEY CHECK (hash ~ '^[0-9a-f]{64}$'),
+    language TEXT,
+    excerpt TEXT NOT NULL,
+    line_count BIGINT NOT NULL,
+    truncated BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,25 @@
+-- Staff give a reason for every removal; boards may show it where the post was.
+ALTER TABLE boards ADD COLUMN tombstones BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- No foreign keys: entries outlive hard-deleted boards, threads and replies.
+CREATE TABLE deletion_log (
+    id BIGSERIAL PRIMARY KEY,
+    kind TEXT NOT NULL CHECK (kind IN ('board', 'thread', 'reply')),
+    target_id BIGINT NOT NULL,
+    hard BOOLEAN NOT NULL,
+    actor TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_deletion_log_target ON deletion_log(kind, target_id, id);
+
+-- The reason logged with a reply's current soft deletion; NULL while it is
+-- visible or when its poster deleted it.
+CREATE FUNCTION removal_reason(reply BIGINT, deleted TIMESTAMPTZ) RETURNS TEXT
+LANGUAGE sql STABLE AS $$
+    SELECT reason FROM deletion_log
+    WHERE kind = 'reply' AND target_id = reply AND NOT hard AND created_at >= deleted
+    ORDER BY id
+    LIMIT 1
+$$;
@@ -0,0 +1,4 @@
+-- Temporary roles lapse back to 'user'; NULL keeps the role until changed.
+ALTER TABLE user_roles ADD COLUMN expires_at TIMESTAMPTZ;
+
+CREATE INDEX idx_user_roles_expiry ON user_roles(expires_at) WHERE expires_at IS NOT NULL;
@@ -0,0 +1,14 @@
+-- Who changed a subject's role, from what to what, and why. A NULL
+-- `changed_by` is the role expiry job; a NULL role means no assignment.
+CREATE TABLE role_changes (
+    id BIGSERIAL PRIMARY KEY,
+    subject TEXT NOT NULL,
+    changed_by TEXT,
+    from_role TEXT CHECK (from_role IN ('user', 'moderator', 'admin')),
+    to_role TEXT CHECK (to_role IN ('user', 'moderator', 'admin')),
+    expires_at TIMESTAMPTZ,
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_role_changes_subject ON role_changes(subject, id);
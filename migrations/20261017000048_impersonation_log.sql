@@ -0,0 +1,17 @@
+-- Impersonation tokens admins issued (`token`, with the reason and expiry)
+-- and each state-changing request made with one (`request`). `actor` is the
+-- admin; `subject` the subject key acted as.
+CREATE TABLE impersonation_log (
+    id BIGSERIAL PRIMARY KEY,
+    actor TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('token', 'request')),
+    method TEXT,
+    route TEXT,
+    status INTEGER,
+    reason TEXT,
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_impersonation_log_subject ON impersonation_log(subject, id);
@@ -0,0 +1,12 @@
+-- Automation credentials, separate from user sessions. Only the SHA-256 of
+-- each token is kept; a revoked account stops authenticating at once.
+CREATE TABLE service_accounts (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    scopes TEXT[] NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
@@ -0,0 +1,25 @@
+-- Users flag threads and replies for moderator review. The initial reports
+-- table had no target kind, reporter or outcome and nothing wrote to it.
+DROP TABLE reports;
+
+-- No foreign keys: reports outlive hard-deleted posts.
+CREATE TABLE reports (
+    id BIGSERIAL PRIMARY KEY,
+    kind TEXT NOT NULL CHECK (kind IN ('thread', 'reply')),
+    target_id BIGINT NOT NULL,
+    -- The thread itself, or the reply's thread, for linking from the queue.
+    thread_id BIGINT NOT NULL,
+    reporter TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'resolved', 'dismissed')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    decided_by TEXT,
+    decided_at TIMESTAMPTZ,
+    note TEXT
+);
+
+-- One open report per reporter and post.
+CREATE UNIQUE INDEX idx_reports_reporter
+    ON reports(reporter, kind, target_id) WHERE status = 'open';
+CREATE INDEX idx_reports_target ON reports(kind, target_id);
+CREATE INDEX idx_reports_open ON reports(id) WHERE status = 'open';
@@ -0,0 +1,16 @@
+-- Bans by client address or range, checked with subject bans when posting
+-- and uploading. `public_reason` is shown to the banned client; `reason`
+-- stays with staff.
+CREATE TABLE ip_bans (
+    id BIGSERIAL PRIMARY KEY,
+    network CIDR NOT NULL,
+    reason TEXT NOT NULL,
+    public_reason TEXT,
+    banned_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_ip_bans_network ON ip_bans USING gist (network inet_ops);
+
+ALTER TABLE subject_bans ADD COLUMN public_reason TEXT;
@@ -0,0 +1,14 @@
+-- A beta board is soft-launched: only staff and the subjects listed in
+-- `board_testers` see it in listings or can post to it. Clearing the flag
+-- launches the board; the tester list is kept for the next trial.
+ALTER TABLE boards ADD COLUMN beta BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE board_testers (
+    board_id BIGINT NOT NULL REFERENCES boards(id) ON DELETE CASCADE,
+    subject TEXT NOT NULL,
+    added_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (board_id, subject)
+);
+
+CREATE INDEX idx_board_testers_subject ON board_testers(subject);
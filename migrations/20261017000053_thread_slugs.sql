@@ -0,0 +1,42 @@
+-- Threads get a URL slug derived from their subject: lowercase ASCII letters
+-- and digits, other runs collapsed to '-', at most 60 characters. Every slug
+-- a thread has carried is kept in `thread_slugs`, so links made before a
+-- subject change still resolve (with a redirect to the current slug).
+CREATE FUNCTION thread_slug(subject TEXT) RETURNS TEXT AS $$
+    SELECT trim(BOTH '-' FROM left(
+        trim(BOTH '-' FROM regexp_replace(lower(subject), '[^a-z0-9]+', '-', 'g')),
+        60
+    ));
+$$ LANGUAGE sql IMMUTABLE;
+
+ALTER TABLE threads ADD COLUMN slug TEXT NOT NULL DEFAULT '';
+UPDATE threads SET slug = thread_slug(subject);
+
+CREATE TABLE thread_slugs (
+    thread_id BIGINT NOT NULL REFERENCES threads(id) ON DELETE CASCADE,
+    slug TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (thread_id, slug)
+);
+
+INSERT INTO thread_slugs (thread_id, slug) SELECT id, slug FROM threads;
+
+CREATE FUNCTION set_thread_slug() RETURNS trigger AS $$
+BEGIN
+    NEW.slug := thread_slug(NEW.subject);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION record_thread_slug() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO thread_slugs (thread_id, slug) VALUES (NEW.id, NEW.slug)
+    ON CONFLICT DO NOTHING;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER threads_slug BEFORE INSERT OR UPDATE OF subject ON threads
+    FOR EACH ROW EXECUTE FUNCTION set_thread_slug();
+CREATE TRIGGER threads_slug_history AFTER INSERT OR UPDATE OF subject ON threads
+    FOR EACH ROW EXECUTE FUNCTION record_thread_slug();
@@ -0,0 +1,24 @@
+-- Slugs boards gave up, so links made before a rename still resolve (with a
+-- redirect to the board's current slug). A slug names at most one board: the
+-- latest to drop it. A board currently using a slug always wins over this
+-- history.
+CREATE TABLE board_slugs (
+    slug TEXT PRIMARY KEY,
+    board_id BIGINT NOT NULL REFERENCES boards(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_board_slugs_board_id ON board_slugs(board_id);
+
+CREATE FUNCTION record_board_slug() RETURNS trigger AS $$
+BEGIN
+    IF NEW.slug IS DISTINCT FROM OLD.slug THEN
+        INSERT INTO board_slugs (slug, board_id) VALUES (OLD.slug, OLD.id)
+        ON CONFLICT (slug) DO UPDATE SET board_id = EXCLUDED.board_id, created_at = now();
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER boards_slug_history AFTER UPDATE OF slug ON boards
+    FOR EACH ROW EXECUTE FUNCTION record_board_slug();
@@ -0,0 +1,706 @@
+//! Country, ASN, Tor and trust based access policy.
+//!
+//! A policy is two rule lists, one for reads (`GET`, `HEAD`, `OPTIONS`) and
+//! one for writes, loaded from the JSON file named by `ACCESS_POLICY_FILE`.
+//! The first rule whose conditions all match decides the request: `allow`,
+//! `challenge` (the request must carry a solved [`crate::pow`] challenge in
+//! `x-proof-of-work` or a clearance cookie bought with one) or `deny` (`403`);
+//! requests no rule matches are allowed. With `ACCESS_POLICY_INTERSTITIAL`
+//! on, challenged page loads get an HTML page that solves the challenge in
+//! the browser and reloads once cleared.
+//! Country, ASN and the Tor flag come from headers set by a trusted edge such
+//! as Cloudflare and are ignored unless `TRUST_PROXY_HEADERS` is on. Trust
+//! scores come from [`crate::trust`]. The file is re-read when it changes; a
+//! file that no longer parses leaves the previous policy in force.
+//!
+//! ```json
+//! {
+//!   "read": [
+//!     { "countries": ["KP"], "action": "deny" },
+//!     { "paths": ["/api/v1/search"], "tor": true, "action": "challenge" }
+//!   ],
+//!   "write": [
+//!     { "tor": true, "action": "challenge" },
+//!     { "asns": [64496], "trust_below": 0.2, "action": "deny" }
+//!   ]
+//! }
+//! ```
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::{header, Method};
+use actix_web::{web, Error, HttpResponse};
+use askama::Template;
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+
+use crate::auth::{decode_jwt, Role, AUTH_COOKIE_NAME};
+use crate::error::ApiErrorBody;
+use crate::pow::{CLEARANCE_COOKIE, POW_HEADER};
+use crate::routes::{
+    anonymous_author_attribution, extract_client_ip, role_subject_key, trust_proxy_headers,
+    AppState,
+};
+
+/// Paths never subject to the policy; challenged clients fetch their
+/// challenge from `/api/v1/pow` and trade its solution for a clearance.
+const EXEMPT_PATHS: &[&str] = &[
+    "/healthz",
+    "/readyz",
+    "/metrics",
+    "/api/v1/pow",
+    "/api/v1/pow/clearance",
+];
+
+/// Cloudflare's country code for Tor exit nodes.
+const TOR_COUNTRY: &str = "T1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    Allow,
+    Challenge,
+    Deny,
+}
+
+impl Decision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Decision::Allow => "allow",
+            Decision::Challenge => "challenge",
+            Decision::Deny => "deny",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    Read,
+    Write,
+}
+
+impl RequestKind {
+    pub fn of(method: &Method) -> Self {
+        if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+            RequestKind::Read
+        } else {
+            RequestKind::Write
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            RequestKind::Read => "read",
+            RequestKind::Write => "write",
+        }
+    }
+}
+
+/// One rule; every condition set must match. A rule without conditions
+/// matches everything.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyRule {
+    /// Path prefixes, e.g. `/api/v1/search`
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// ISO 3166 country codes
+    #[serde(default)]
+    pub countries: Vec<String>,
+    #[serde(default)]
+    pub asns: Vec<u32>,
+    pub tor: Option<bool>,
+    /// Matches callers scoring below this; callers without history score 0,
+    /// staff 1, and the rule never matches when the score is unavailable.
+    pub trust_below: Option<f64>,
+    pub action: Decision,
+}
+
+impl PolicyRule {
+    fn matches(&self, attrs: &RequestAttributes) -> bool {
+        (self.paths.is_empty()
+            || self
+                .paths
+                .iter()
+                .any(|prefix| attrs.path.starts_with(prefix.as_str())))
+            && (self.countries.is_empty()
+                || attrs.country.as_deref().is_some_and(|country| {
+                    self.countries
+                        .iter()
+                        .any(|c| c.eq_ignore_ascii_case(country))
+                }))
+            && (self.asns.is_empty() || attrs.asn.is_some_and(|asn| self.asns.contains(&asn)))
+            && self.tor.is_none_or(|tor| tor == attrs.tor)
+            && self
+                .trust_below
+                .is_none_or(|below| attrs.trust.is_some_and(|score| score < below))
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicySet {
+    #[serde(default)]
+    pub read: Vec<PolicyRule>,
+    #[serde(default)]
+    pub write: Vec<PolicyRule>,
+}
+
+impl PolicySet {
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let policy: PolicySet = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        for rule in policy.read.iter().chain(&policy.write) {
+            if rule.paths.iter().any(|p| !p.starts_with('/')) {
+                return Err("paths must start with /".to_string());
+            }
+            if rule.countries.iter().any(|c| c.len() != 2) {
+                return Err("countries must be two-letter codes".to_string());
+            }
+            if rule.trust_below.is_some_and(|t| !(0.0..=1.0).contains(&t)) {
+                return Err("trust_below must be between 0 and 1".to_string());
+            }
+        }
+        Ok(policy)
+    }
+
+    pub fn rules(&self, kind: RequestKind) -> &[PolicyRule] {
+        match kind {
+            RequestKind::Read => &self.read,
+            RequestKind::Write => &self.write,
+        }
+    }
+
+    /// The first matching rule's action, or `Allow`.
+    pub fn decide(&self, kind: RequestKind, attrs: &RequestAttributes) -> Decision {
+        self.rules(kind)
+            .iter()
+            .find(|rule| rule.matches(attrs))
+            .map_or(Decision::Allow, |rule| rule.action)
+    }
+}
+
+/// What the policy knows about a request.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestAttributes {
+    pub path: String,
+    /// Upper-case country code
+    pub country: Option<String>,
+    pub asn: Option<u32>,
+    pub tor: bool,
+    pub trust: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PolicyConfig {
+    /// Policy file; unset allows everything.
+    pub file: Option<PathBuf>,
+    /// How often the file is checked for changes.
+    pub reload_interval: Duration,
+    pub country_header: String,
+    pub asn_header: String,
+    pub tor_header: String,
+    /// Answer challenged page loads with the solving page instead of JSON.
+    pub interstitial: bool,
+}
+
+impl PolicyConfig {
+    pub fn from_env() -> Self {
+        let header = |name: &str, default: &str| {
+            std::env::var(name)
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .unwrap_or_else(|| default.to_string())
+                .to_ascii_lowercase()
+        };
+        Self {
+            file: std::env::var("ACCESS_POLICY_FILE")
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .map(PathBuf::from),
+            reload_interval: Duration::from_secs(
+                std::env::var("ACCESS_POLICY_RELOAD_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(10u64)
+                    .max(1),
+            ),
+            country_header: header("ACCESS_POLICY_COUNTRY_HEADER", "cf-ipcountry"),
+            asn_header: header("ACCESS_POLICY_ASN_HEADER", "x-client-asn"),
+            tor_header: header("ACCESS_POLICY_TOR_HEADER", "x-client-tor"),
+            interstitial: std::env::var("ACCESS_POLICY_INTERSTITIAL")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Middleware applying the current [`PolicySet`]. Build it once and clone it
+/// into each worker's app so reloads reach every worker.
+#[derive(Clone)]
+pub struct AccessPolicy {
+    cfg: Arc<PolicyConfig>,
+    policy: Arc<RwLock<Arc<PolicySet>>>,
+    /// Modification time of the file last read, parsed or not.
+    loaded: Arc<Mutex<Option<SystemTime>>>,
+}
+
+impl AccessPolicy {
+    /// Load the configured file; an unreadable or invalid file is an error.
+    pub fn new(cfg: PolicyConfig) -> Result<Self, String> {
+        let policy = Self::with_policy(cfg, PolicySet::default());
+        if policy.cfg.file.is_some() {
+            policy.reload()?;
+        }
+        Ok(policy)
+    }
+
+    pub fn with_policy(cfg: PolicyConfig, policy: PolicySet) -> Self {
+        Self {
+            cfg: Arc::new(cfg),
+            policy: Arc::new(RwLock::new(Arc::new(policy))),
+            loaded: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn current(&self) -> Arc<PolicySet> {
+        self.policy.read().unwrap().clone()
+    }
+
+    /// Re-read the file if it changed since the last read; `Ok(true)` when a
+    /// new policy took effect.
+    pub fn reload(&self) -> Result<bool, String> {
+        let Some(path) = &self.cfg.file else {
+            return Ok(false);
+        };
+        let read_error = |e: std::io::Error| format!("{}: {e}", path.display());
+        let modified = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .map_err(read_error)?;
+        {
+            let mut loaded = self.loaded.lock().unwrap();
+            if *loaded == Some(modified) {
+                return Ok(false);
+            }
+            *loaded = Some(modified);
+        }
+        let policy = PolicySet::parse(&std::fs::read_to_string(path).map_err(read_error)?)
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        *self.policy.write().unwrap() = Arc::new(policy);
+        Ok(true)
+    }
+
+    /// Watch the file until the task is aborted; does nothing without one.
+    pub fn spawn_reloader(&self) -> Option<JoinHandle<()>> {
+        self.cfg.file.as_ref()?;
+        let policy = self.clone();
+        Some(actix_web::rt::spawn(async move {
+            loop {
+                tokio::time::sleep(policy.cfg.reload_interval).await;
+                match policy.reload() {
+                    Ok(true) => {
+                        metrics::increment_counter!("access_policy_reloads", "result" => "ok");
+                        log::info!("access policy reloaded");
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        metrics::increment_counter!("access_policy_reloads", "result" => "error");
+                        log::error!("access policy not reloaded, keeping the previous one: {e}");
+                    }
+                }
+            }
+        }))
+    }
+
+    fn attributes(&self, req: &ServiceRequest) -> RequestAttributes {
+        let path = req.path().to_string();
+        if !trust_proxy_headers() {
+            return RequestAttributes {
+                path,
+                ..RequestAttributes::default()
+            };
+        }
+        let header = |name: &str| {
+            req.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+        let country = header(&self.cfg.country_header).map(str::to_ascii_uppercase);
+        let asn = header(&self.cfg.asn_header).and_then(|v| {
+            v.strip_prefix("AS")
+                .or_else(|| v.strip_prefix("as"))
+                .unwrap_or(v)
+                .parse()
+                .ok()
+        });
+        let tor = country.as_deref() == Some(TOR_COUNTRY)
+            || header(&self.cfg.tor_header)
+                .is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true"));
+        RequestAttributes {
+            path,
+            country,
+            asn,
+            tor,
+            trust: None,
+        }
+    }
+
+    /// `None` lets the request through; otherwise the refusal to send.
+    async fn check(&self, req: &ServiceRequest) -> Option<HttpResponse> {
+        if EXEMPT_PATHS.contains(&req.path()) {
+            return None;
+        }
+        let policy = self.current();
+        let kind = RequestKind::of(req.method());
+        let rules = policy.rules(kind);
+        if rules.is_empty() {
+            return None;
+        }
+        let state = req.app_data::<web::Data<AppState>>();
+        let mut attrs = self.attributes(req);
+        if rules.iter().any(|rule| rule.trust_below.is_some()) {
+            if let Some(state) = state {
+                attrs.trust = trust_score(state, req).await;
+            }
+        }
+        let decision = policy.decide(kind, &attrs);
+        metrics::increment_counter!(
+            "access_policy_decisions",
+            "kind" => kind.as_str(),
+            "decision" => decision.as_str()
+        );
+        let refusal = match decision {
+            Decision::Allow => return None,
+            Decision::Challenge if solved_challenge(state, req) => return None,
+            Decision::Challenge => {
+                if let Some(page) = self.interstitial(state, req, kind) {
+                    return Some(page);
+                }
+                "solve the proof of work from /api/v1/pow and resend it in x-proof-of-work, \
+                 or trade it for a clearance cookie at /api/v1/pow/clearance"
+            }
+            Decision::Deny => "access denied by policy",
+        };
+        Some(HttpResponse::Forbidden().json(ApiErrorBody {
+            error: refusal.to_string(),
+            code: None,
+        }))
+    }
+}
+
+/// A solved challenge clears the client until it expires, and a clearance
+/// cookie until the cookie does; anonymous posts still redeem their own.
+fn solved_challenge(state: Option<&web::Data<AppState>>, req: &ServiceRequest) -> bool {
+    let Some(state) = state else {
+        return false;
+    };
+    req.headers()
+        .get(POW_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|token| state.pow.verify(token).is_ok())
+        || req.cookie(CLEARANCE_COOKIE).is_some_and(|cookie| {
+            state
+                .pow
+                .is_cleared(cookie.value(), &extract_client_ip(req.request()))
+        })
+}
+
+#[derive(Template)]
+#[template(path = "challenge.html")]
+struct ChallengePage {
+    nonce: String,
+    challenge: String,
+    difficulty: u32,
+}
+
+impl AccessPolicy {
+    /// The solving page, for reads from browsers when enabled.
+    fn interstitial(
+        &self,
+        state: Option<&web::Data<AppState>>,
+        req: &ServiceRequest,
+        kind: RequestKind,
+    ) -> Option<HttpResponse> {
+        let wants_html = req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|accept| accept.contains("text/html"));
+        if !self.cfg.interstitial || kind != RequestKind::Read || !wants_html {
+            return None;
+        }
+        let issued = state?.pow.issue().ok()?;
+        let mut nonce = [0u8; 16];
+        rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut nonce);
+        let page = ChallengePage {
+            nonce: hex::encode(nonce),
+            challenge: issued.challenge,
+            difficulty: issued.difficulty,
+        };
+        let html = page.render().ok()?;
+        Some(
+            HttpResponse::Forbidden()
+                .content_type("text/html; charset=utf-8")
+                .insert_header((header::CACHE_CONTROL, "no-store"))
+                .insert_header((
+                    header::CONTENT_SECURITY_POLICY,
+                    format!(
+                        "default-src 'none'; script-src 'nonce-{}'; style-src 'unsafe-inline'; \
+                         connect-src 'self'; base-uri 'none'; frame-ancestors 'none'",
+                        page.nonce
+                    ),
+                ))
+                .body(html),
+        )
+    }
+}
+
+/// The caller's trust score: staff score 1, signed-in callers by their
+/// subject, everyone else by their anonymous posting subject.
+async fn trust_score(state: &AppState, req: &ServiceRequest) -> Option<f64> {
+    let claims = req
+        .headers()
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string)
+        .or_else(|| req.cookie(AUTH_COOKIE_NAME).map(|c| c.value().to_string()))
+        .and_then(|token| decode_jwt(&token).ok());
+    let subject = match claims {
+        Some(claims)
+            if claims
+                .roles
+                .iter()
+                .any(|r| matches!(r, Role::Moderator | Role::Admin)) =>
+        {
+            return Some(1.0)
+        }
+        Some(claims) => role_subject_key(&claims.sub)?,
+        None => {
+            anonymous_author_attribution(&extract_client_ip(req.request()))
+                .ok()?
+                .0
+        }
+    };
+    match state.repo.subject_trust(&subject).await {
+        Ok(history) => Some(state.trust.score(history.as_ref(), chrono::Utc::now())),
+        Err(e) => {
+            log::warn!("access policy could not score {subject}: {e}");
+            None
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for AccessPolicy
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AccessPolicyMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AccessPolicyMiddleware {
+            service: Rc::new(service),
+            policy: self.clone(),
+        }))
+    }
+}
+
+pub struct AccessPolicyMiddleware<S> {
+    service: Rc<S>,
+    policy: AccessPolicy,
+}
+
+impl<S, B> Service<ServiceRequest> for AccessPolicyMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let policy = self.policy.clone();
+        Box::pin(async move {
+            if let Some(response) = policy.check(&req).await {
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+            service
+                .call(req)
+                .await
+                .map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test as web_test, App};
+
+    fn config(file: Option<PathBuf>) -> PolicyConfig {
+        PolicyConfig {
+            file,
+            reload_interval: Duration::from_secs(1),
+            country_header: "cf-ipcountry".to_string(),
+            asn_header: "x-client-asn".to_string(),
+            tor_header: "x-client-tor".to_string(),
+            interstitial: false,
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_decides_per_request_kind() {
+        let policy = PolicySet::parse(
+            r#"{
+                "read": [{ "countries": ["kp"], "action": "deny" }],
+                "write": [
+                    { "tor": true, "action": "challenge" },
+                    { "asns": [64496], "trust_below": 0.5, "action": "deny" },
+                    { "asns": [64496], "action": "challenge" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let attrs = |country: Option<&str>, asn: Option<u32>, tor: bool, trust: Option<f64>| {
+            RequestAttributes {
+                path: "/api/v1/threads".to_string(),
+                country: country.map(str::to_string),
+                asn,
+                tor,
+                trust,
+            }
+        };
+        let north_korea = attrs(Some("KP"), None, false, None);
+        assert_eq!(
+            policy.decide(RequestKind::Read, &north_korea),
+            Decision::Deny
+        );
+        assert_eq!(
+            policy.decide(RequestKind::Write, &north_korea),
+            Decision::Allow
+        );
+        let tor = attrs(Some("DE"), Some(64496), true, Some(0.0));
+        assert_eq!(policy.decide(RequestKind::Write, &tor), Decision::Challenge);
+        let untrusted = attrs(None, Some(64496), false, Some(0.1));
+        assert_eq!(
+            policy.decide(RequestKind::Write, &untrusted),
+            Decision::Deny
+        );
+        let unscored = attrs(None, Some(64496), false, None);
+        assert_eq!(
+            policy.decide(RequestKind::Write, &unscored),
+            Decision::Challenge
+        );
+        assert_eq!(
+            policy.decide(RequestKind::Write, &RequestAttributes::default()),
+            Decision::Allow
+        );
+
+        assert!(
+            PolicySet::parse(r#"{"read": [{"countries": ["KPX"], "action": "deny"}]}"#).is_err()
+        );
+        assert!(PolicySet::parse(r#"{"read": [{"trust_below": 2, "action": "deny"}]}"#).is_err());
+        assert!(PolicySet::parse(r#"{"read": [{"action": "block"}]}"#).is_err());
+
+        let search =
+            PolicySet::parse(r#"{"read": [{"paths": ["/api/v1/search"], "action": "challenge"}]}"#)
+                .unwrap();
+        let at = |path: &str| RequestAttributes {
+            path: path.to_string(),
+            ..RequestAttributes::default()
+        };
+        assert_eq!(
+            search.decide(RequestKind::Read, &at("/api/v1/search")),
+            Decision::Challenge
+        );
+        assert_eq!(
+            search.decide(RequestKind::Read, &at("/api/v1/boards")),
+            Decision::Allow
+        );
+        assert!(PolicySet::parse(r#"{"read": [{"paths": ["api"], "action": "deny"}]}"#).is_err());
+    }
+
+    #[actix_web::test]
+    async fn edge_headers_count_only_behind_a_trusted_proxy() {
+        let policy = AccessPolicy::with_policy(
+            config(None),
+            PolicySet::parse(r#"{"read": [{"countries": ["KP"], "action": "deny"}, {"tor": true, "action": "deny"}]}"#)
+                .unwrap(),
+        );
+        let ok = || async { HttpResponse::Ok().finish() };
+        let app = web_test::init_service(
+            App::new()
+                .wrap(policy)
+                .route("/api/v1/boards", web::get().to(ok))
+                .route("/healthz", web::get().to(ok)),
+        )
+        .await;
+        let status = |uri: &str, header: (&'static str, &'static str)| {
+            let req = web_test::TestRequest::get()
+                .uri(uri)
+                .insert_header(header)
+                .to_request();
+            let app = &app;
+            async move { web_test::call_service(app, req).await.status() }
+        };
+        std::env::remove_var("TRUST_PROXY_HEADERS");
+        assert_eq!(status("/api/v1/boards", ("cf-ipcountry", "KP")).await, 200);
+        std::env::set_var("TRUST_PROXY_HEADERS", "true");
+        assert_eq!(status("/api/v1/boards", ("cf-ipcountry", "KP")).await, 403);
+        assert_eq!(status("/api/v1/boards", ("cf-ipcountry", "T1")).await, 403);
+        assert_eq!(status("/api/v1/boards", ("x-client-tor", "1")).await, 403);
+        assert_eq!(status("/api/v1/boards", ("cf-ipcountry", "FR")).await, 200);
+        assert_eq!(status("/healthz", ("cf-ipcountry", "KP")).await, 200);
+        std::env::remove_var("TRUST_PROXY_HEADERS");
+    }
+
+    #[test]
+    fn changed_files_are_reloaded_and_bad_ones_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("policy.json");
+        std::fs::write(&path, r#"{"write": [{"action": "deny"}]}"#).unwrap();
+        let policy = AccessPolicy::new(config(Some(path.clone()))).unwrap();
+        assert_eq!(policy.current().write.len(), 1);
+        assert!(!policy.reload().unwrap());
+
+        let touch = |contents: &str, secs: u64| {
+            std::fs::write(&path, contents).unwrap();
+            let file = std::fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+                .unwrap();
+        };
+        touch(r#"{"read": [{"action": "challenge"}]}"#, 1);
+        assert!(policy.reload().unwrap());
+        assert_eq!(policy.current().read[0].action, Decision::Challenge);
+        assert!(policy.current().write.is_empty());
+
+        touch("{not json", 2);
+        assert!(policy.reload().is_err());
+        assert_eq!(policy.current().read.len(), 1);
+
+        std::fs::write(&path, "{not json").unwrap();
+        assert!(AccessPolicy::new(config(Some(path))).is_err());
+    }
+}
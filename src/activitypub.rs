@@ -0,0 +1,807 @@
+//! Read-only ActivityPub federation: every board is a `Group` actor that
+//! fediverse users can follow, and new threads are delivered to followers
+//! as `Create` activities wrapping a `Note`.
+//!
+//! Served routes, all 404 while `ACTIVITYPUB_ENABLED` is off:
+//! `/.well-known/webfinger`, the actor at `/ap/boards/{slug}` with its
+//! inbox, outbox and followers, and notes at `/ap/threads/{id}`. The inbox
+//! only understands `Follow` and `Undo` of a follow; inbound replies are not
+//! federated yet. Requests and deliveries carry HTTP signatures (rsa-sha256)
+//! made with the instance key in `ACTIVITYPUB_PRIVATE_KEY`.
+//!
+//! [`FederationRunner`] fans new threads out into a delivery queue, one item
+//! per follower inbox (shared inboxes once), and sends it with backoff.
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use aws_lc_rs::encoding::AsDer;
+use aws_lc_rs::rand::SystemRandom;
+use aws_lc_rs::signature::{self, KeyPair, RsaKeyPair, UnparsedPublicKey};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::ApiError;
+use crate::models::{
+    ApDelivery, Board, Id, NewApFollower, SortOrder, Thread, ThreadOrder, ThreadSort,
+};
+use crate::repo::Repo;
+use crate::routes::AppState;
+use crate::service;
+
+const ACTIVITY_JSON: &str = "application/activity+json";
+const PUBLIC: &str = "https://www.w3.org/ns/activitystreams#Public";
+/// Threads listed in a board's outbox, newest first.
+const OUTBOX_THREADS: usize = 20;
+/// Largest clock difference accepted in a signed request's `Date`.
+const MAX_CLOCK_SKEW_SECS: i64 = 3600;
+/// How long a claimed delivery stays with one runner.
+const DELIVERY_LEASE_SECS: i64 = 300;
+/// Deliveries sent at once.
+const DELIVERY_CONCURRENCY: usize = 8;
+/// Longest wait between two attempts of one delivery.
+const MAX_RETRY_SECS: i64 = 6 * 3600;
+
+#[derive(Clone, Debug)]
+pub struct FederationConfig {
+    pub enabled: bool,
+    /// Public origin of this API, where actors and notes live.
+    pub base_url: String,
+    /// PKCS#8 PEM RSA key signing requests and deliveries.
+    pub private_key: Option<String>,
+    pub poll_interval: Duration,
+    /// Threads fanned out and deliveries sent per poll at most.
+    pub batch_size: i64,
+    /// Age a thread must reach before it is fanned out, so threads still
+    /// being committed are not skipped.
+    pub settle: Duration,
+    /// Wait before the first retry of a failed delivery; doubles each time.
+    pub retry: Duration,
+    /// Failed attempts after which a delivery is dropped.
+    pub max_attempts: i32,
+}
+
+impl FederationConfig {
+    pub fn from_env() -> Self {
+        fn u64_env(name: &str, default: u64) -> u64 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        fn opt_env(name: &str) -> Option<String> {
+            std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+        }
+        Self {
+            enabled: std::env::var("ACTIVITYPUB_ENABLED")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            base_url: opt_env("ACTIVITYPUB_BASE_URL")
+                .map(|url| url.trim_end_matches('/').to_string())
+                .unwrap_or_else(crate::sitemap::site_url),
+            // Allow the PEM on one line with escaped newlines.
+            private_key: opt_env("ACTIVITYPUB_PRIVATE_KEY").map(|key| key.replace("\\n", "\n")),
+            poll_interval: Duration::from_secs(u64_env("ACTIVITYPUB_POLL_SECS", 10).max(1)),
+            batch_size: u64_env("ACTIVITYPUB_BATCH_SIZE", 50).max(1) as i64,
+            settle: Duration::from_secs(u64_env("ACTIVITYPUB_SETTLE_SECS", 5)),
+            retry: Duration::from_secs(u64_env("ACTIVITYPUB_RETRY_SECS", 60).max(1)),
+            max_attempts: u64_env("ACTIVITYPUB_MAX_ATTEMPTS", 8).clamp(1, 100) as i32,
+        }
+    }
+
+    /// The federation endpoint, or `None` while federation is disabled.
+    pub fn build(&self) -> anyhow::Result<Option<Arc<Federation>>> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        let pem = self
+            .private_key
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("ACTIVITYPUB_PRIVATE_KEY is not set"))?;
+        let der = pem_decode(pem, "PRIVATE KEY").ok_or_else(|| {
+            anyhow::anyhow!("ACTIVITYPUB_PRIVATE_KEY must be a PKCS#8 PEM private key")
+        })?;
+        let key = RsaKeyPair::from_pkcs8(&der)
+            .map_err(|e| anyhow::anyhow!("ACTIVITYPUB_PRIVATE_KEY is not a usable RSA key: {e}"))?;
+        Ok(Some(Arc::new(Federation::new(&self.base_url, key)?)))
+    }
+}
+
+/// Signing key and URL scheme of this instance's actors.
+pub struct Federation {
+    base_url: String,
+    host: String,
+    key: RsaKeyPair,
+    public_key_pem: String,
+    client: reqwest::Client,
+}
+
+impl Federation {
+    pub fn new(base_url: &str, key: RsaKeyPair) -> anyhow::Result<Self> {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        let url = reqwest::Url::parse(&base_url)?;
+        let host = authority(&url).ok_or_else(|| anyhow::anyhow!("{base_url} has no host"))?;
+        let public_key_pem = pem_encode(
+            "PUBLIC KEY",
+            key.public_key()
+                .as_der()
+                .map_err(|_| anyhow::anyhow!("cannot export the ActivityPub public key"))?
+                .as_ref(),
+        );
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(3))
+            .timeout(Duration::from_secs(10))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        Ok(Self {
+            base_url,
+            host,
+            key,
+            public_key_pem,
+            client,
+        })
+    }
+
+    pub fn actor_id(&self, slug: &str) -> String {
+        format!("{}/ap/boards/{slug}", self.base_url)
+    }
+
+    fn key_id(&self, slug: &str) -> String {
+        format!("{}#main-key", self.actor_id(slug))
+    }
+
+    pub fn note_id(&self, thread_id: Id) -> String {
+        format!("{}/ap/threads/{thread_id}", self.base_url)
+    }
+
+    pub fn actor(&self, board: &Board) -> Value {
+        let id = self.actor_id(&board.slug);
+        json!({
+            "@context": ["https://www.w3.org/ns/activitystreams", "https://w3id.org/security/v1"],
+            "id": id,
+            "type": "Group",
+            "preferredUsername": board.slug,
+            "name": board.title,
+            "url": format!("{}/{}", crate::sitemap::site_url(), board.slug),
+            "inbox": format!("{id}/inbox"),
+            "outbox": format!("{id}/outbox"),
+            "followers": format!("{id}/followers"),
+            "publicKey": {
+                "id": self.key_id(&board.slug),
+                "owner": id,
+                "publicKeyPem": self.public_key_pem,
+            },
+        })
+    }
+
+    pub fn note(&self, board: &Board, thread: &Thread) -> Value {
+        let actor = self.actor_id(&board.slug);
+        let mut content = format!("<p><strong>{}</strong></p>", html_escape(&thread.subject));
+        for paragraph in thread.body.split("\n\n").filter(|p| !p.trim().is_empty()) {
+            let lines: Vec<String> = paragraph.lines().map(html_escape).collect();
+            content.push_str(&format!("<p>{}</p>", lines.join("<br>")));
+        }
+        let mut note = json!({
+            "id": self.note_id(thread.id),
+            "type": "Note",
+            "attributedTo": actor,
+            "content": content,
+            "url": format!("{}/thread/{}", crate::sitemap::site_url(), thread.id),
+            "published": thread.created_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "to": [PUBLIC],
+            "cc": [format!("{actor}/followers")],
+            "tag": thread.tags.iter().map(|tag| json!({"type": "Hashtag", "name": format!("#{tag}")})).collect::<Vec<_>>(),
+        });
+        if let (Some(hash), Some(mime)) = (&thread.image_hash, &thread.mime) {
+            note["attachment"] = json!([{
+                "type": "Document",
+                "mediaType": mime,
+                "url": format!("{}/images/{hash}", self.base_url),
+            }]);
+        }
+        note
+    }
+
+    pub fn create_activity(&self, board: &Board, thread: &Thread) -> Value {
+        let note = self.note(board, thread);
+        json!({
+            "@context": "https://www.w3.org/ns/activitystreams",
+            "id": format!("{}/activity", self.note_id(thread.id)),
+            "type": "Create",
+            "actor": note["attributedTo"],
+            "published": note["published"],
+            "to": note["to"],
+            "cc": note["cc"],
+            "object": note,
+        })
+    }
+
+    /// POST a signed activity to a remote inbox as the activity's actor.
+    pub async fn deliver(&self, inbox: &str, activity: &Value) -> anyhow::Result<()> {
+        let actor = activity["actor"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("activity has no actor"))?;
+        let url = reqwest::Url::parse(inbox)?;
+        let body = serde_json::to_vec(activity)?;
+        let headers = sign_request(
+            &self.key,
+            &format!("{actor}#main-key"),
+            "POST",
+            &url,
+            Some(&body),
+        )?;
+        let mut request = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, ACTIVITY_JSON)
+            .body(body);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let status = request.send().await?.status();
+        if !status.is_success() {
+            anyhow::bail!("responded with {status}");
+        }
+        Ok(())
+    }
+
+    /// GET a remote object, signed as `signer` for servers that want
+    /// authorized fetches.
+    async fn fetch(&self, url: &str, signer: &str) -> anyhow::Result<Value> {
+        let url = reqwest::Url::parse(url)?;
+        if !matches!(url.scheme(), "https" | "http") {
+            anyhow::bail!("unsupported scheme {}", url.scheme());
+        }
+        let headers = sign_request(&self.key, &self.key_id(signer), "GET", &url, None)?;
+        let mut request = self
+            .client
+            .get(url)
+            .header(reqwest::header::ACCEPT, ACTIVITY_JSON);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("responded with {status}");
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Check the HTTP signature of an inbox POST and return the signing
+    /// actor's document. `slug` is the board whose key signs the key fetch.
+    async fn verify(&self, req: &HttpRequest, body: &[u8], slug: &str) -> Result<Value, String> {
+        let signature = req
+            .headers()
+            .get("signature")
+            .and_then(|v| v.to_str().ok())
+            .ok_or("missing Signature header")?;
+        let params = parse_signature(signature);
+        let key_id = params.get("keyId").ok_or("signature has no keyId")?;
+        if let Some(algorithm) = params.get("algorithm") {
+            if algorithm != "rsa-sha256" && algorithm != "hs2019" {
+                return Err(format!("unsupported algorithm {algorithm}"));
+            }
+        }
+        let signed: Vec<&str> = params
+            .get("headers")
+            .map_or("date", String::as_str)
+            .split_whitespace()
+            .collect();
+        for required in ["(request-target)", "host", "date", "digest"] {
+            if !signed.contains(&required) {
+                return Err(format!("{required} is not signed"));
+            }
+        }
+        let date = header_value(req, "date").ok_or("missing Date header")?;
+        let date = DateTime::parse_from_rfc2822(&date).map_err(|_| "malformed Date header")?;
+        if (Utc::now() - date.with_timezone(&Utc)).num_seconds().abs() > MAX_CLOCK_SKEW_SECS {
+            return Err("Date is too far from now".into());
+        }
+        let digest = header_value(req, "digest").ok_or("missing Digest header")?;
+        if digest != body_digest(body) {
+            return Err("Digest does not match the body".into());
+        }
+        let sig = base64::engine::general_purpose::STANDARD
+            .decode(params.get("signature").ok_or("signature is empty")?)
+            .map_err(|_| "signature is not base64")?;
+        let mut lines = Vec::with_capacity(signed.len());
+        for name in &signed {
+            let value = if *name == "(request-target)" {
+                let target = req.uri().path_and_query().map_or("/", |pq| pq.as_str());
+                format!("{} {target}", req.method().as_str().to_lowercase())
+            } else {
+                header_value(req, name).ok_or_else(|| format!("missing signed header {name}"))?
+            };
+            lines.push(format!("{name}: {value}"));
+        }
+
+        let owner = key_id.split('#').next().unwrap_or(key_id);
+        let actor = self
+            .fetch(owner, slug)
+            .await
+            .map_err(|e| format!("cannot fetch {owner}: {e}"))?;
+        let key = match &actor["publicKey"] {
+            Value::Array(keys) => keys.iter().find(|k| k["id"] == key_id.as_str()),
+            key if key["id"] == key_id.as_str() => Some(key),
+            _ => None,
+        }
+        .ok_or("actor does not publish the signing key")?;
+        if key["owner"] != actor["id"] {
+            return Err("signing key is not owned by the actor".into());
+        }
+        let der = key["publicKeyPem"]
+            .as_str()
+            .and_then(|pem| pem_decode(pem, "PUBLIC KEY"))
+            .ok_or("actor key is not a PEM public key")?;
+        UnparsedPublicKey::new(&signature::RSA_PKCS1_2048_8192_SHA256, der)
+            .verify(lines.join("\n").as_bytes(), &sig)
+            .map_err(|_| "signature does not verify")?;
+        Ok(actor)
+    }
+}
+
+/// Headers (`Host`, `Date`, `Digest` for bodies and `Signature`) signing a
+/// request with `key` under `key_id`.
+pub fn sign_request(
+    key: &RsaKeyPair,
+    key_id: &str,
+    method: &str,
+    url: &reqwest::Url,
+    body: Option<&[u8]>,
+) -> anyhow::Result<Vec<(&'static str, String)>> {
+    let host = authority(url).ok_or_else(|| anyhow::anyhow!("{url} has no host"))?;
+    let date = Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+    let target = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_string(),
+    };
+    let mut headers = vec![("host", host), ("date", date)];
+    if let Some(body) = body {
+        headers.push(("digest", body_digest(body)));
+    }
+    let mut lines = vec![format!(
+        "(request-target): {} {target}",
+        method.to_lowercase()
+    )];
+    lines.extend(
+        headers
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}")),
+    );
+    let mut sig = vec![0; key.public_modulus_len()];
+    key.sign(
+        &signature::RSA_PKCS1_SHA256,
+        &SystemRandom::new(),
+        lines.join("\n").as_bytes(),
+        &mut sig,
+    )
+    .map_err(|_| anyhow::anyhow!("signing failed"))?;
+    let names: Vec<&str> = headers.iter().map(|(name, _)| *name).collect();
+    let signature = format!(
+        r#"keyId="{key_id}",algorithm="rsa-sha256",headers="(request-target) {}",signature="{}""#,
+        names.join(" "),
+        base64::engine::general_purpose::STANDARD.encode(sig)
+    );
+    headers.push(("signature", signature));
+    Ok(headers)
+}
+
+/// `host[:port]` as sent in the `Host` header.
+fn authority(url: &reqwest::Url) -> Option<String> {
+    let host = url.host_str()?;
+    Some(match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    })
+}
+
+fn body_digest(body: &[u8]) -> String {
+    format!(
+        "SHA-256={}",
+        base64::engine::general_purpose::STANDARD.encode(Sha256::digest(body))
+    )
+}
+
+fn header_value(req: &HttpRequest, name: &str) -> Option<String> {
+    let values: Vec<&str> = req
+        .headers()
+        .get_all(name)
+        .filter_map(|v| v.to_str().ok())
+        .collect();
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+/// `key="value"` pairs of a `Signature` header.
+fn parse_signature(header: &str) -> HashMap<String, String> {
+    header
+        .split(',')
+        .filter_map(|pair| {
+            let (key, value) = pair.trim().split_once('=')?;
+            Some((key.to_string(), value.trim_matches('"').to_string()))
+        })
+        .collect()
+}
+
+pub fn pem_encode(label: &str, der: &[u8]) -> String {
+    let b64 = base64::engine::general_purpose::STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {label}-----\n");
+    for line in b64.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap_or_default());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {label}-----\n"));
+    pem
+}
+
+fn pem_decode(pem: &str, label: &str) -> Option<Vec<u8>> {
+    let body = pem
+        .trim()
+        .strip_prefix(&format!("-----BEGIN {label}-----"))?
+        .strip_suffix(&format!("-----END {label}-----"))?;
+    let b64: String = body.split_whitespace().collect();
+    base64::engine::general_purpose::STANDARD.decode(b64).ok()
+}
+
+fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Federation routes. Part of the public router: remote servers expect
+/// them at the origin root.
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("/.well-known/webfinger", web::get().to(webfinger))
+        .service(
+            web::scope("/ap")
+                .route("/boards/{slug}", web::get().to(actor))
+                .route("/boards/{slug}/inbox", web::post().to(inbox))
+                .route("/boards/{slug}/outbox", web::get().to(outbox))
+                .route("/boards/{slug}/followers", web::get().to(followers))
+                .route("/threads/{id}", web::get().to(note)),
+        );
+}
+
+fn federation(data: &AppState) -> Result<&Federation, ApiError> {
+    data.federation.as_deref().ok_or(ApiError::NotFound)
+}
+
+async fn find_board(data: &AppState, slug: &str) -> Result<Board, ApiError> {
+    service::list_boards(data, false, None)
+        .await?
+        .into_iter()
+        .find(|b| b.slug == slug)
+        .ok_or(ApiError::NotFound)
+}
+
+fn activity_json(value: Value) -> HttpResponse {
+    HttpResponse::Ok().content_type(ACTIVITY_JSON).json(value)
+}
+
+#[derive(Deserialize)]
+struct WebfingerQuery {
+    resource: Option<String>,
+}
+
+async fn webfinger(
+    data: web::Data<AppState>,
+    query: web::Query<WebfingerQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let fed = federation(&data)?;
+    let resource = query.resource.as_deref().ok_or(ApiError::BadRequest)?;
+    let slug = match resource.strip_prefix("acct:") {
+        Some(acct) => {
+            let (slug, host) = acct.split_once('@').ok_or(ApiError::BadRequest)?;
+            if !host.eq_ignore_ascii_case(&fed.host) {
+                return Err(ApiError::NotFound);
+            }
+            slug
+        }
+        None => resource
+            .strip_prefix(&fed.actor_id(""))
+            .ok_or(ApiError::NotFound)?,
+    };
+    let board = find_board(&data, slug).await?;
+    let actor_id = fed.actor_id(&board.slug);
+    Ok(HttpResponse::Ok()
+        .content_type("application/jrd+json")
+        .json(json!({
+            "subject": format!("acct:{}@{}", board.slug, fed.host),
+            "aliases": [actor_id],
+            "links": [
+                {"rel": "self", "type": ACTIVITY_JSON, "href": actor_id},
+                {
+                    "rel": "http://webfinger.net/rel/profile-page",
+                    "type": "text/html",
+                    "href": format!("{}/{}", crate::sitemap::site_url(), board.slug),
+                },
+            ],
+        })))
+}
+
+async fn actor(
+    data: web::Data<AppState>,
+    slug: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let fed = federation(&data)?;
+    let board = find_board(&data, &slug).await?;
+    Ok(activity_json(fed.actor(&board)))
+}
+
+async fn outbox(
+    data: web::Data<AppState>,
+    slug: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let fed = federation(&data)?;
+    let board = find_board(&data, &slug).await?;
+    let newest_first = ThreadOrder {
+        sort: ThreadSort::CreatedAt,
+        order: SortOrder::Desc,
+    };
+    let mut threads = service::list_threads(&data, board.id, false, newest_first, None).await?;
+    let total = threads.len();
+    threads.truncate(OUTBOX_THREADS);
+    let items: Vec<Value> = threads
+        .iter()
+        .map(|thread| fed.create_activity(&board, thread))
+        .collect();
+    Ok(activity_json(json!({
+        "@context": "https://www.w3.org/ns/activitystreams",
+        "id": format!("{}/outbox", fed.actor_id(&board.slug)),
+        "type": "OrderedCollection",
+        "totalItems": total,
+        "orderedItems": items,
+    })))
+}
+
+async fn followers(
+    data: web::Data<AppState>,
+    slug: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let fed = federation(&data)?;
+    let board = find_board(&data, &slug).await?;
+    let total = data.repo.count_ap_followers(board.id).await?;
+    // Only the count: who follows a board is not published.
+    Ok(activity_json(json!({
+        "@context": "https://www.w3.org/ns/activitystreams",
+        "id": format!("{}/followers", fed.actor_id(&board.slug)),
+        "type": "OrderedCollection",
+        "totalItems": total,
+    })))
+}
+
+async fn note(data: web::Data<AppState>, id: web::Path<Id>) -> Result<HttpResponse, ApiError> {
+    let fed = federation(&data)?;
+    let thread = service::get_thread(&data, id.into_inner(), false, None).await?;
+    let board = service::get_board(&data, thread.board_id, false, None).await?;
+    let mut note = fed.note(&board, &thread);
+    note["@context"] = json!("https://www.w3.org/ns/activitystreams");
+    Ok(activity_json(note))
+}
+
+/// `id` of an object given inline or by reference.
+fn object_id(value: &Value) -> Option<&str> {
+    value.as_str().or_else(|| value["id"].as_str())
+}
+
+async fn inbox(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    slug: web::Path<String>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let fed = federation(&data)?;
+    let board = find_board(&data, &slug).await?;
+    let activity: Value = serde_json::from_slice(&body).map_err(|_| ApiError::BadRequest)?;
+    let remote = fed.verify(&req, &body, &board.slug).await.map_err(|e| {
+        log::info!("rejected inbox POST for /{}/: {e}", board.slug);
+        ApiError::Unauthorized
+    })?;
+    let remote_id = remote["id"].as_str().ok_or(ApiError::Unauthorized)?;
+    if object_id(&activity["actor"]) != Some(remote_id) {
+        return Err(ApiError::Unauthorized);
+    }
+    let actor_id = fed.actor_id(&board.slug);
+    match activity["type"].as_str() {
+        Some("Follow") if object_id(&activity["object"]) == Some(actor_id.as_str()) => {
+            let inbox = remote["inbox"]
+                .as_str()
+                .ok_or_else(|| ApiError::Invalid("actor has no inbox".into()))?;
+            data.repo
+                .add_ap_follower(
+                    board.id,
+                    NewApFollower {
+                        actor: remote_id.to_string(),
+                        inbox: inbox.to_string(),
+                        shared_inbox: remote["endpoints"]["sharedInbox"]
+                            .as_str()
+                            .map(str::to_string),
+                    },
+                )
+                .await?;
+            let accept = json!({
+                "@context": "https://www.w3.org/ns/activitystreams",
+                "id": format!("{actor_id}#accepts/{}", uuid::Uuid::new_v4()),
+                "type": "Accept",
+                "actor": actor_id,
+                "object": activity,
+            });
+            data.repo
+                .enqueue_ap_delivery(board.id, inbox, accept)
+                .await?;
+            metrics::increment_counter!("activitypub_follows");
+        }
+        Some("Undo")
+            if activity["object"]["type"] == "Follow"
+                && object_id(&activity["object"]["object"]) == Some(actor_id.as_str()) =>
+        {
+            data.repo.remove_ap_follower(board.id, remote_id).await?;
+        }
+        _ => {}
+    }
+    Ok(HttpResponse::Accepted().finish())
+}
+
+/// Fans new threads out to followers and sends queued deliveries.
+#[derive(Clone)]
+pub struct FederationRunner {
+    repo: Arc<dyn Repo>,
+    federation: Arc<Federation>,
+    cfg: FederationConfig,
+}
+
+impl FederationRunner {
+    pub fn new(repo: Arc<dyn Repo>, federation: Arc<Federation>, cfg: FederationConfig) -> Self {
+        Self {
+            repo,
+            federation,
+            cfg,
+        }
+    }
+
+    /// Fan out pending threads, then send due deliveries; returns the
+    /// deliveries accepted by remote inboxes.
+    pub async fn run_once(&self) -> usize {
+        self.fan_out().await;
+        let deliveries = match self
+            .repo
+            .claim_ap_deliveries(self.cfg.batch_size, DELIVERY_LEASE_SECS)
+            .await
+        {
+            Ok(deliveries) => deliveries,
+            Err(e) => {
+                log::error!("activitypub delivery claim failed: {e}");
+                return 0;
+            }
+        };
+        futures_util::stream::iter(deliveries)
+            .map(|delivery| self.send(delivery))
+            .buffer_unordered(DELIVERY_CONCURRENCY)
+            .filter(|delivered| std::future::ready(*delivered))
+            .count()
+            .await
+    }
+
+    async fn fan_out(&self) {
+        let threads = match self
+            .repo
+            .pending_ap_threads(self.cfg.batch_size, self.cfg.settle.as_secs() as i64)
+            .await
+        {
+            Ok(threads) => threads,
+            Err(e) => {
+                log::error!("activitypub thread lookup failed: {e}");
+                return;
+            }
+        };
+        let mut boards: HashMap<Id, Board> = HashMap::new();
+        for thread in threads {
+            if !boards.contains_key(&thread.board_id) {
+                match self.repo.get_board(thread.board_id).await {
+                    Ok(board) => boards.insert(board.id, board),
+                    Err(e) => {
+                        log::error!("activitypub board lookup failed: {e}");
+                        return;
+                    }
+                };
+            }
+            let activity = self
+                .federation
+                .create_activity(&boards[&thread.board_id], &thread);
+            if let Err(e) = self.repo.fan_out_ap_thread(&thread, activity).await {
+                log::error!("activitypub fan-out of thread {} failed: {e}", thread.id);
+                return;
+            }
+        }
+    }
+
+    async fn send(&self, delivery: ApDelivery) -> bool {
+        let result = self
+            .federation
+            .deliver(&delivery.inbox, &delivery.activity)
+            .await;
+        let error = result.as_ref().err().map(|e| e.to_string());
+        match &error {
+            None => metrics::increment_counter!("activitypub_deliveries"),
+            Some(e) => {
+                metrics::increment_counter!("activitypub_deliveries_failed");
+                log::warn!(
+                    "activitypub delivery {} to {} failed: {e}",
+                    delivery.id,
+                    delivery.inbox
+                );
+            }
+        }
+        let retry = (self.cfg.retry.as_secs() as i64)
+            .saturating_mul(1 << delivery.attempts.clamp(0, 20))
+            .min(MAX_RETRY_SECS);
+        if let Err(e) = self
+            .repo
+            .finish_ap_delivery(delivery.id, error, retry, self.cfg.max_attempts)
+            .await
+        {
+            log::error!("activitypub delivery {} update failed: {e}", delivery.id);
+        }
+        result.is_ok()
+    }
+
+    /// Spawn the polling loop on the current runtime.
+    pub fn spawn(self) {
+        crate::system::job_started("activitypub", self.cfg.poll_interval);
+        actix_web::rt::spawn(async move {
+            loop {
+                self.run_once().await;
+                crate::system::job_ran("activitypub");
+                tokio::time::sleep(self.cfg.poll_interval).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_lc_rs::rsa::KeySize;
+
+    #[test]
+    fn signatures_verify_with_the_published_key() {
+        let key = RsaKeyPair::generate(KeySize::Rsa2048).unwrap();
+        let fed = Federation::new("https://rib.example/", key).unwrap();
+        assert_eq!(fed.host, "rib.example");
+        let url = reqwest::Url::parse("https://remote.example:8443/inbox?x=1").unwrap();
+        let headers = sign_request(&fed.key, "k", "POST", &url, Some(b"{}")).unwrap();
+        let get = |name: &str| headers.iter().find(|(n, _)| *n == name).unwrap().1.clone();
+        assert_eq!(get("host"), "remote.example:8443");
+        let params = parse_signature(&get("signature"));
+        assert_eq!(params["headers"], "(request-target) host date digest");
+        let signed = format!(
+            "(request-target): post /inbox?x=1\nhost: {}\ndate: {}\ndigest: {}",
+            get("host"),
+            get("date"),
+            body_digest(b"{}")
+        );
+        let der = pem_decode(&fed.public_key_pem, "PUBLIC KEY").unwrap();
+        let sig = base64::engine::general_purpose::STANDARD
+            .decode(&params["signature"])
+            .unwrap();
+        UnparsedPublicKey::new(&signature::RSA_PKCS1_2048_8192_SHA256, der)
+            .verify(signed.as_bytes(), &sig)
+            .unwrap();
+        assert_eq!(html_escape("<b>&'x'"), "&lt;b&gt;&amp;&#39;x&#39;");
+    }
+}
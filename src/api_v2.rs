@@ -0,0 +1,575 @@
+use actix_web::body::BoxBody;
+use actix_web::http::{header, StatusCode};
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::Auth;
+use crate::error::ApiError;
+use crate::models::*;
+use crate::routes::{
+    allow, extract_client_ip, http_poster, include_deleted, reply_order, requested_filters,
+    thread_order, AppState,
+};
+use crate::service;
+
+pub const API_VERSION: &str = "v2";
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 200;
+const MAX_SEARCH_WINDOW: usize = 100;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/v2")
+            // Extractor failures use the same error envelope as handler errors.
+            .app_data(
+                web::JsonConfig::default()
+                    .error_handler(|err, _| V2Error(ApiError::Invalid(err.to_string())).into()),
+            )
+            .app_data(
+                web::PathConfig::default()
+                    .error_handler(|err, _| V2Error(ApiError::Invalid(err.to_string())).into()),
+            )
+            .app_data(
+                web::QueryConfig::default()
+                    .error_handler(|err, _| V2Error(ApiError::Invalid(err.to_string())).into()),
+            )
+            .service(
+                web::resource("/boards")
+                    .route(web::get().to(list_boards))
+                    .route(web::post().to(create_board)),
+            )
+            .service(web::resource("/boards/{id}").route(web::get().to(get_board)))
+            .service(
+                web::resource("/boards/{id}/threads")
+                    .route(web::get().to(list_threads))
+                    .route(web::head().to(list_threads))
+                    .route(allow("GET, HEAD, OPTIONS")),
+            )
+            .service(web::resource("/threads").route(web::post().to(create_thread)))
+            .service(
+                web::resource("/threads/{id}")
+                    .route(web::get().to(get_thread))
+                    .route(web::head().to(get_thread))
+                    .route(allow("GET, HEAD, OPTIONS")),
+            )
+            .service(
+                web::resource("/threads/{id}/replies")
+                    .route(web::get().to(list_replies))
+                    .route(web::head().to(list_replies))
+                    .route(allow("GET, HEAD, OPTIONS")),
+            )
+            .service(web::resource("/replies").route(web::post().to(create_reply)))
+            .service(web::resource("/replies/{id}").route(web::get().to(get_reply)))
+            .service(web::resource("/search").route(web::get().to(search)))
+            .default_service(web::to(|| async {
+                Err::<HttpResponse, _>(V2Error(ApiError::NotFound))
+            })),
+    );
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+pub struct PageQuery {
+    /// Page size (default 50, max 200)
+    limit: Option<usize>,
+    /// Items to skip
+    offset: Option<usize>,
+}
+
+impl PageQuery {
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+
+    fn offset(&self) -> usize {
+        self.offset.unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct Pagination {
+    pub limit: usize,
+    pub offset: usize,
+    /// Total matching items, when known.
+    pub total: Option<usize>,
+    /// Offset of the next page; absent on the last page.
+    pub next_offset: Option<usize>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct Meta {
+    pub api_version: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_backend: Option<String>,
+}
+
+impl Default for Meta {
+    fn default() -> Self {
+        Self {
+            api_version: API_VERSION,
+            search_backend: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[aliases(
+    BoardList = ListEnvelope<Board>,
+    ThreadList = ListEnvelope<Thread>,
+    ReplyList = ListEnvelope<Reply>,
+    SearchHitList = ListEnvelope<SearchHit>
+)]
+pub struct ListEnvelope<T> {
+    pub data: Vec<T>,
+    pub pagination: Pagination,
+    pub meta: Meta,
+}
+
+impl<T> ListEnvelope<T> {
+    /// Slice one page out of a fully loaded, already ordered list.
+    fn page(items: Vec<T>, query: &PageQuery) -> Self {
+        let (limit, offset) = (query.limit(), query.offset());
+        let total = items.len();
+        let data: Vec<T> = items.into_iter().skip(offset).take(limit).collect();
+        let next_offset = (offset + data.len() < total).then_some(offset + data.len());
+        Self {
+            data,
+            pagination: Pagination {
+                limit,
+                offset,
+                total: Some(total),
+                next_offset,
+            },
+            meta: Meta::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[aliases(
+    BoardData = DataEnvelope<Board>,
+    ThreadData = DataEnvelope<Thread>,
+    ReplyData = DataEnvelope<Reply>
+)]
+pub struct DataEnvelope<T> {
+    pub data: T,
+    pub meta: Meta,
+}
+
+impl<T: Serialize> DataEnvelope<T> {
+    fn ok(data: T) -> HttpResponse {
+        HttpResponse::Ok().json(Self {
+            data,
+            meta: Meta::default(),
+        })
+    }
+
+    fn created(location: String, data: T) -> HttpResponse {
+        HttpResponse::Created()
+            .insert_header((header::LOCATION, location))
+            .json(Self {
+                data,
+                meta: Meta::default(),
+            })
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ErrorDetail {
+    /// Stable identifier, e.g. `not_found` or `rate_limited`
+    pub code: &'static str,
+    pub message: String,
+    pub status: u16,
+    /// Public reason and expiry when the code is `banned`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ban: Option<crate::models::BanNotice>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ErrorEnvelope {
+    pub error: ErrorDetail,
+    pub meta: Meta,
+}
+
+/// [`ApiError`] rendered as an [`ErrorEnvelope`]; status and headers such as
+/// `Retry-After` match v1.
+#[derive(Debug, thiserror::Error)]
+#[error(transparent)]
+pub struct V2Error(#[from] pub ApiError);
+
+impl ResponseError for V2Error {
+    fn status_code(&self) -> StatusCode {
+        self.0.status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let res = self.0.error_response();
+        let envelope = ErrorEnvelope {
+            error: ErrorDetail {
+                code: self.0.code(),
+                message: self.0.to_string(),
+                status: res.status().as_u16(),
+                ban: match &self.0 {
+                    ApiError::Banned(ban) => Some(ban.clone()),
+                    _ => None,
+                },
+            },
+            meta: Meta::default(),
+        };
+        let body = serde_json::to_vec(&envelope).unwrap_or_default();
+        res.set_body(BoxBody::new(body))
+    }
+}
+
+type V2Result = Result<HttpResponse, V2Error>;
+
+/// Authentication failures become enveloped 401s instead of plain text.
+fn require_auth(auth: Result<Auth, actix_web::Error>) -> Result<Auth, V2Error> {
+    auth.map_err(|_| V2Error(ApiError::Unauthorized))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/boards",
+    tag = "v2",
+    params(PageQuery, ("include_deleted" = Option<bool>, Query, description = "Admin only: include soft-deleted")),
+    responses((status = 200, description = "Boards", body = BoardList)),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn list_boards(
+    req: HttpRequest,
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    page: web::Query<PageQuery>,
+) -> V2Result {
+    let boards =
+        service::list_boards(&data, include_deleted(&req, auth.as_ref()), auth.as_ref()).await?;
+    Ok(HttpResponse::Ok().json(ListEnvelope::page(boards, &page)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/boards/{id}",
+    tag = "v2",
+    params(("id" = Id, Path, description = "Board id")),
+    responses(
+        (status = 200, description = "Board", body = BoardData),
+        (status = 404, description = "Board not found", body = ErrorEnvelope)
+    ),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn get_board(
+    req: HttpRequest,
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> V2Result {
+    let board = service::get_board(
+        &data,
+        path.into_inner(),
+        include_deleted(&req, auth.as_ref()),
+        auth.as_ref(),
+    )
+    .await?;
+    Ok(DataEnvelope::ok(board))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/boards",
+    tag = "v2",
+    request_body = NewBoard,
+    responses(
+        (status = 201, description = "Board created; `Location` points at it", body = BoardData),
+        (status = 401, description = "Sign-in required", body = ErrorEnvelope),
+        (status = 403, description = "Admins only", body = ErrorEnvelope),
+        (status = 409, description = "Slug taken", body = ErrorEnvelope),
+        (status = 503, description = "Board creation disabled for maintenance (`feature_disabled`)", body = ErrorEnvelope)
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_board(
+    auth: Result<Auth, actix_web::Error>,
+    data: web::Data<AppState>,
+    payload: web::Json<NewBoard>,
+) -> V2Result {
+    let auth = require_auth(auth)?;
+    let board = service::create_board(&data, &auth, payload.into_inner()).await?;
+    Ok(DataEnvelope::created(
+        format!("/api/v2/boards/{}", board.id),
+        board,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/boards/{id}/threads",
+    tag = "v2",
+    params(
+        ("id" = Id, Path, description = "Board id"),
+        PageQuery,
+        ("apply_filters" = Option<bool>, Query, description = "Signed-in callers: leave out threads matching their mute list"),
+        ("sort" = Option<ThreadSort>, Query, description = "Order by `bump_time` (default), `created_at` or `reply_count`; ties go by thread id"),
+        ("order" = Option<SortOrder>, Query, description = "`desc` (default) or `asc`")
+    ),
+    responses(
+        (status = 200, description = "Threads in the requested order, most recently bumped first by default", body = ThreadList),
+        (status = 400, description = "Unknown `sort` or `order`", body = ErrorEnvelope),
+        (status = 404, description = "Board not found", body = ErrorEnvelope)
+    ),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn list_threads(
+    req: HttpRequest,
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    page: web::Query<PageQuery>,
+) -> V2Result {
+    let mut threads = service::list_threads(
+        &data,
+        path.into_inner(),
+        include_deleted(&req, auth.as_ref()),
+        thread_order(&req)?,
+        auth.as_ref(),
+    )
+    .await?;
+    if let Some(filters) = requested_filters(&req, auth.as_ref(), &data).await? {
+        threads.retain(|thread| !filters.hides_thread(thread));
+    }
+    Ok(HttpResponse::Ok().json(ListEnvelope::page(threads, &page)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/threads",
+    tag = "v2",
+    request_body = NewThread,
+    responses(
+        (status = 201, description = "Thread created; `Location` points at it", body = ThreadData),
+        (status = 401, description = "Invalid bearer token or session", body = ErrorEnvelope),
+        (status = 404, description = "Board not found", body = ErrorEnvelope)
+    ),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn create_thread(
+    auth: Result<Auth, actix_web::Error>,
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    payload: web::Json<NewThread>,
+) -> V2Result {
+    let client_ip = extract_client_ip(&req);
+    let poster = http_poster(&req, auth.as_ref().ok(), &client_ip)?;
+    let thread = service::create_thread(&data, poster, payload.into_inner()).await?;
+    Ok(DataEnvelope::created(
+        format!("/api/v2/threads/{}", thread.id),
+        thread,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/threads/{id}",
+    tag = "v2",
+    params(("id" = Id, Path, description = "Thread id")),
+    responses(
+        (status = 200, description = "Thread", body = ThreadData),
+        (status = 404, description = "Thread not found", body = ErrorEnvelope)
+    ),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn get_thread(
+    req: HttpRequest,
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> V2Result {
+    let thread = service::get_thread(
+        &data,
+        path.into_inner(),
+        include_deleted(&req, auth.as_ref()),
+        auth.as_ref(),
+    )
+    .await?;
+    Ok(DataEnvelope::ok(thread))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/threads/{id}/replies",
+    tag = "v2",
+    params(
+        ("id" = Id, Path, description = "Thread id"),
+        PageQuery,
+        ("apply_filters" = Option<bool>, Query, description = "Signed-in callers: leave out replies matching their mute list"),
+        ("sort" = Option<ReplySort>, Query, description = "Order by `created_at`, the only key for replies; ties go by reply id"),
+        ("order" = Option<SortOrder>, Query, description = "`asc` (default) or `desc`")
+    ),
+    responses(
+        (status = 200, description = "Replies in the requested order, oldest first by default", body = ReplyList),
+        (status = 400, description = "Unknown `sort` or `order`", body = ErrorEnvelope),
+        (status = 404, description = "Thread not found", body = ErrorEnvelope)
+    ),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn list_replies(
+    req: HttpRequest,
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    page: web::Query<PageQuery>,
+) -> V2Result {
+    let mut replies = service::list_replies(
+        &data,
+        path.into_inner(),
+        include_deleted(&req, auth.as_ref()),
+        reply_order(&req)?,
+        auth.as_ref(),
+    )
+    .await?;
+    if let Some(filters) = requested_filters(&req, auth.as_ref(), &data).await? {
+        replies.retain(|reply| !filters.hides_reply(reply));
+    }
+    Ok(HttpResponse::Ok().json(ListEnvelope::page(replies, &page)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/replies",
+    tag = "v2",
+    request_body = NewReply,
+    responses(
+        (status = 201, description = "Reply created; `Location` points at it", body = ReplyData),
+        (status = 401, description = "Invalid bearer token or session", body = ErrorEnvelope),
+        (status = 404, description = "Thread not found", body = ErrorEnvelope)
+    ),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn create_reply(
+    auth: Result<Auth, actix_web::Error>,
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    payload: web::Json<NewReply>,
+) -> V2Result {
+    let client_ip = extract_client_ip(&req);
+    let poster = http_poster(&req, auth.as_ref().ok(), &client_ip)?;
+    let reply = service::create_reply(&data, poster, payload.into_inner()).await?;
+    Ok(DataEnvelope::created(
+        format!("/api/v2/replies/{}", reply.id),
+        reply,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/replies/{id}",
+    tag = "v2",
+    params(("id" = Id, Path, description = "Reply id")),
+    responses(
+        (status = 200, description = "Reply", body = ReplyData),
+        (status = 404, description = "Reply not found", body = ErrorEnvelope)
+    ),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn get_reply(
+    req: HttpRequest,
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> V2Result {
+    let reply = service::get_reply(
+        &data,
+        path.into_inner(),
+        include_deleted(&req, auth.as_ref()),
+        auth.as_ref(),
+    )
+    .await?;
+    Ok(DataEnvelope::ok(reply))
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+pub struct SearchQuery {
+    /// Search terms (web-search syntax: quoted phrases, `-exclusion`, `or`)
+    q: String,
+    board_id: Option<Id>,
+    /// Page size (default 25); offset + limit is capped at 100
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/search",
+    tag = "v2",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Matching threads and replies; `meta.search_backend` names the engine", body = SearchHitList),
+        (status = 400, description = "Missing or oversized query", body = ErrorEnvelope),
+        (status = 503, description = "Search disabled for maintenance (`feature_disabled`)", body = ErrorEnvelope)
+    )
+)]
+pub async fn search(
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    query: web::Query<SearchQuery>,
+) -> V2Result {
+    let offset = query.offset.unwrap_or(0).min(MAX_SEARCH_WINDOW - 1);
+    let limit = query
+        .limit
+        .unwrap_or(25)
+        .clamp(1, MAX_SEARCH_WINDOW - offset);
+    // Backends rank without a stable cursor, so fetch the window and slice it.
+    let results = service::search(
+        &data,
+        &query.q,
+        query.board_id,
+        Some((offset + limit) as i64),
+        auth.as_ref(),
+    )
+    .await?;
+    let window = results.hits.len();
+    let data: Vec<SearchHit> = results.hits.into_iter().skip(offset).collect();
+    let next_offset =
+        (window == offset + limit && offset + limit < MAX_SEARCH_WINDOW).then_some(offset + limit);
+    Ok(HttpResponse::Ok().json(ListEnvelope {
+        data,
+        pagination: Pagination {
+            limit,
+            offset,
+            total: None,
+            next_offset,
+        },
+        meta: Meta {
+            api_version: API_VERSION,
+            search_backend: Some(results.backend),
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_report_next_offset_until_exhausted() {
+        let query = |limit, offset| PageQuery {
+            limit: Some(limit),
+            offset: Some(offset),
+        };
+        let first = ListEnvelope::page((0..5).collect(), &query(2, 0));
+        assert_eq!(first.data, vec![0, 1]);
+        assert_eq!(first.pagination.next_offset, Some(2));
+        assert_eq!(first.pagination.total, Some(5));
+        let last = ListEnvelope::page((0..5).collect(), &query(2, 4));
+        assert_eq!(last.data, vec![4]);
+        assert_eq!(last.pagination.next_offset, None);
+        let capped = ListEnvelope::page((0..5).collect::<Vec<i32>>(), &query(10_000, 0));
+        assert_eq!(capped.pagination.limit, MAX_LIMIT);
+    }
+
+    #[test]
+    fn errors_keep_status_and_headers() {
+        let res = V2Error(ApiError::RateLimited { retry_after: 7 }).error_response();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers().get("retry-after").unwrap(), "7");
+    }
+}
@@ -0,0 +1,99 @@
+//! Appeals against bans and post deletions.
+//!
+//! A signed-in user files one appeal per moderation action with
+//! `POST /api/v1/appeals`; even banned users can, since bans only stop
+//! posting. Staff work the queue under `/api/v1/admin/appeals`. Accepting
+//! lifts the ban or restores the post, and either decision is mailed to the
+//! user when they have a confirmed notification address.
+
+use crate::mailer::Email;
+use crate::models::{Appeal, AppealKind, AppealStatus};
+
+pub const MAX_APPEAL_CHARS: usize = 2000;
+pub const MAX_NOTE_CHARS: usize = 1000;
+/// Appeals returned per queue page.
+pub const MAX_APPEAL_QUEUE: i64 = 100;
+
+fn action(appeal: &Appeal) -> String {
+    match (appeal.kind, appeal.target_id) {
+        (AppealKind::Thread, Some(id)) => format!("the deletion of your thread #{id}"),
+        (AppealKind::Reply, Some(id)) => format!("the deletion of your reply #{id}"),
+        _ => "your ban".into(),
+    }
+}
+
+/// Decision notice for a decided appeal; `None` while it is pending.
+pub fn decision_email(appeal: &Appeal, to: &str) -> Option<Email> {
+    let (subject, outcome) = match appeal.status {
+        AppealStatus::Pending => return None,
+        AppealStatus::Accepted => (
+            "Your appeal was accepted",
+            match appeal.kind {
+                AppealKind::Ban => "The ban has been lifted.",
+                AppealKind::Thread | AppealKind::Reply => "The post has been restored.",
+            },
+        ),
+        AppealStatus::Denied => ("Your appeal was denied", "The decision stands."),
+    };
+    let mut body = format!(
+        "Staff reviewed your appeal against {}. {outcome}\n",
+        action(appeal)
+    );
+    if let Some(note) = appeal.note.as_deref() {
+        body.push_str(&format!("\nNote from the moderator:\n{note}\n"));
+    }
+    Some(Email {
+        to: to.into(),
+        subject: subject.into(),
+        body,
+        unsubscribe_url: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn appeal(kind: AppealKind, target_id: Option<i64>, status: AppealStatus) -> Appeal {
+        Appeal {
+            id: 1,
+            subject: "discord:1".into(),
+            kind,
+            target_id,
+            message: "please".into(),
+            status,
+            action_at: Utc::now(),
+            created_at: Utc::now(),
+            decided_by: Some("discord:2".into()),
+            decided_at: Some(Utc::now()),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn decisions_describe_the_action_and_outcome() {
+        assert!(decision_email(
+            &appeal(AppealKind::Ban, None, AppealStatus::Pending),
+            "a@b.c"
+        )
+        .is_none());
+        let email = decision_email(
+            &appeal(AppealKind::Ban, None, AppealStatus::Accepted),
+            "a@b.c",
+        )
+        .unwrap();
+        assert_eq!(email.subject, "Your appeal was accepted");
+        assert!(email
+            .body
+            .contains("against your ban. The ban has been lifted."));
+        let mut denied = appeal(AppealKind::Reply, Some(9), AppealStatus::Denied);
+        denied.note = Some("spam is spam".into());
+        let email = decision_email(&denied, "a@b.c").unwrap();
+        assert_eq!(email.to, "a@b.c");
+        assert!(email.body.contains("your reply #9. The decision stands."));
+        assert!(email
+            .body
+            .ends_with("Note from the moderator:\nspam is spam\n"));
+    }
+}
@@ -0,0 +1,501 @@
+use chrono::{DateTime, TimeZone, Utc};
+use futures_util::StreamExt as _;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::models::{Id, NewBoard};
+use crate::storage::{ImageStore, ImageStoreError};
+use crate::transfer::{Dump, DumpBoard, DumpImage, DumpReply, DumpThread, ImportReport};
+use crate::uploads::MimeAllowlist;
+
+const MAX_SUBJECT_CHARS: usize = 200;
+const MAX_BODY_CHARS: usize = 2000;
+const MEDIA_CONCURRENCY: usize = 4;
+const MEDIA_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Where to find post fields in an archive. Defaults match the 4chan API
+/// (`https://a.4cdn.org/{board}/thread/{no}.json`); other archives override
+/// individual keys. Keys starting with `/` are JSON pointers.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct ArchiveMapping {
+    /// JSON pointer to the post array inside each thread document
+    pub posts: String,
+    pub id: String,
+    pub subject: String,
+    pub body: String,
+    pub author_name: String,
+    pub tripcode: String,
+    /// Unix seconds or an RFC 3339 string
+    pub time: String,
+    /// Bodies are HTML (4chan `com`) and are converted to plain text
+    pub html: bool,
+    /// Names equal to this are imported as anonymous
+    pub default_name: Option<String>,
+    /// Media URL template; `{board}` and `{<post key>}` are substituted.
+    /// Posts missing any referenced key have no media.
+    pub media_url: Option<String>,
+}
+
+impl Default for ArchiveMapping {
+    fn default() -> Self {
+        Self {
+            posts: "/posts".into(),
+            id: "no".into(),
+            subject: "sub".into(),
+            body: "com".into(),
+            author_name: "name".into(),
+            tripcode: "trip".into(),
+            time: "time".into(),
+            html: true,
+            default_name: Some("Anonymous".into()),
+            media_url: Some("https://i.4cdn.org/{board}/{tim}{ext}".into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ArchiveImportRequest {
+    /// Board receiving the threads; combine with `on_conflict=merge` to import into an existing board
+    pub board: NewBoard,
+    /// Board name on the source site, used for `{board}` in media URLs; defaults to `board.slug`
+    #[serde(default)]
+    pub source_board: Option<String>,
+    #[serde(default)]
+    pub mapping: ArchiveMapping,
+    /// Thread documents, one per archived thread; the first post of each is the OP
+    #[schema(value_type = Vec<Object>)]
+    pub threads: Vec<Value>,
+    #[serde(default = "default_true")]
+    pub download_media: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct MediaSummary {
+    pub downloaded: u64,
+    /// Not fetched because of a dry run or `download_media=false`
+    pub skipped: u64,
+    pub failed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ArchiveImportReport {
+    pub import: ImportReport,
+    pub media: MediaSummary,
+    /// Subjects or bodies shortened to fit rib's limits
+    pub truncated_posts: u64,
+}
+
+/// A post's media reference, resolved once the blob is stored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaRef {
+    pub url: String,
+    pub thread_id: Option<Id>,
+    pub reply_id: Option<Id>,
+}
+
+/// Archive converted to a dump, plus media still to be downloaded.
+#[derive(Debug, Clone, Default)]
+pub struct ConvertedArchive {
+    pub dump: Dump,
+    pub media: Vec<MediaRef>,
+    pub truncated_posts: u64,
+}
+
+fn field<'a>(post: &'a Value, key: &str) -> Option<&'a Value> {
+    if key.starts_with('/') {
+        post.pointer(key)
+    } else {
+        post.get(key)
+    }
+    .filter(|v| !v.is_null())
+}
+
+fn text_field(post: &Value, key: &str) -> Option<String> {
+    match field(post, key)? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn time_field(post: &Value, key: &str) -> Option<DateTime<Utc>> {
+    match field(post, key)? {
+        Value::Number(n) => Utc.timestamp_opt(n.as_i64()?, 0).single(),
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|t| t.with_timezone(&Utc)),
+        _ => None,
+    }
+}
+
+/// Convert 4chan comment HTML to plain text: `<br>` becomes a newline, other
+/// tags are dropped, and entities are decoded.
+pub fn html_to_text(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            out.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim().to_ascii_lowercase();
+        if tag == "br" || tag.starts_with("br ") || tag == "br/" {
+            out.push('\n');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    decode_entities(&out)
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        let tail = &rest[amp..];
+        let decoded = tail.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let entity = &tail[1..end];
+            let ch = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            }?;
+            Some((ch, end + 1))
+        });
+        match decoded {
+            Some((ch, len)) => {
+                out.push(ch);
+                rest = &tail[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn truncate(text: String, max: usize, truncated: &mut bool) -> String {
+    if text.chars().count() <= max {
+        return text;
+    }
+    *truncated = true;
+    text.chars().take(max).collect()
+}
+
+fn media_url(template: &str, board: &str, post: &Value) -> Option<String> {
+    let mut url = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        url.push_str(&rest[..open]);
+        let close = rest[open..].find('}')? + open;
+        let key = &rest[open + 1..close];
+        if key == "board" {
+            url.push_str(board);
+        } else {
+            url.push_str(&text_field(post, key)?);
+        }
+        rest = &rest[close + 1..];
+    }
+    url.push_str(rest);
+    Some(url)
+}
+
+struct Post {
+    id: Id,
+    subject: Option<String>,
+    body: String,
+    author_name: Option<String>,
+    tripcode: Option<String>,
+    created_at: DateTime<Utc>,
+    media: Option<String>,
+}
+
+impl ArchiveImportRequest {
+    fn post(&self, raw: &Value, board: &str) -> Result<Post, String> {
+        let m = &self.mapping;
+        let id = field(raw, &m.id)
+            .and_then(|v| v.as_i64().or_else(|| v.as_str()?.parse().ok()))
+            .ok_or_else(|| format!("post without numeric '{}'", m.id))?;
+        let created_at =
+            time_field(raw, &m.time).ok_or_else(|| format!("post {id} has no valid time"))?;
+        let body = text_field(raw, &m.body).unwrap_or_default();
+        let body = if m.html { html_to_text(&body) } else { body };
+        let subject = text_field(raw, &m.subject)
+            .map(|s| if m.html { html_to_text(&s) } else { s })
+            .filter(|s| !s.trim().is_empty());
+        let author_name = text_field(raw, &m.author_name)
+            .map(|s| if m.html { html_to_text(&s) } else { s })
+            .filter(|name| !name.trim().is_empty() && Some(name) != m.default_name.as_ref());
+        Ok(Post {
+            id,
+            subject,
+            body,
+            author_name,
+            tripcode: text_field(raw, &m.tripcode),
+            created_at,
+            media: m
+                .media_url
+                .as_deref()
+                .and_then(|template| media_url(template, board, raw)),
+        })
+    }
+
+    /// Map thread documents onto a single-board dump. Source post numbers
+    /// become dump ids, which the importer remaps to fresh ones.
+    pub fn convert(&self) -> Result<ConvertedArchive, String> {
+        let source_board = self.source_board.as_deref().unwrap_or(&self.board.slug);
+        let mut out = ConvertedArchive::default();
+        out.dump.boards.push(DumpBoard {
+            id: 0,
+            slug: self.board.slug.clone(),
+            title: self.board.title.clone(),
+            created_at: Utc::now(),
+            deleted_at: None,
+        });
+        for (n, document) in self.threads.iter().enumerate() {
+            let posts = document
+                .pointer(&self.mapping.posts)
+                .and_then(Value::as_array)
+                .filter(|posts| !posts.is_empty())
+                .ok_or_else(|| format!("thread {n}: no posts at {}", self.mapping.posts))?;
+            let op = self.post(&posts[0], source_board)?;
+            let created_by = |post: &Post| {
+                serde_json::json!({
+                    "provider": "archive",
+                    "subject": format!("archive:{source_board}"),
+                    "source_post": post.id,
+                })
+            };
+            let mut truncated = false;
+            let subject = op.subject.clone().unwrap_or_else(|| {
+                let first_line = op.body.lines().find(|l| !l.trim().is_empty());
+                first_line.unwrap_or("(no subject)").trim().to_string()
+            });
+            let last_post = posts[1..]
+                .iter()
+                .filter_map(|p| time_field(p, &self.mapping.time))
+                .max();
+            out.dump.threads.push(DumpThread {
+                id: op.id,
+                board_id: 0,
+                subject: truncate(subject, MAX_SUBJECT_CHARS, &mut truncated),
+                body: truncate(op.body.clone(), MAX_BODY_CHARS, &mut truncated),
+                author_name: op.author_name.clone(),
+                tripcode: op.tripcode.clone(),
+                created_at: op.created_at,
+                bump_time: last_post.unwrap_or(op.created_at).max(op.created_at),
+                deleted_at: None,
+                created_by: created_by(&op),
+            });
+            out.truncated_posts += u64::from(truncated);
+            if let Some(url) = op.media.clone() {
+                out.media.push(MediaRef {
+                    url,
+                    thread_id: Some(op.id),
+                    reply_id: None,
+                });
+            }
+            for raw in &posts[1..] {
+                let post = self.post(raw, source_board)?;
+                let mut truncated = false;
+                let mut content = post.body.clone();
+                if let Some(subject) = &post.subject {
+                    content = format!("{subject}\n{content}");
+                }
+                out.dump.replies.push(DumpReply {
+                    id: post.id,
+                    thread_id: op.id,
+                    content: truncate(content, MAX_BODY_CHARS, &mut truncated),
+                    author_name: post.author_name.clone(),
+                    tripcode: post.tripcode.clone(),
+                    created_at: post.created_at,
+                    deleted_at: None,
+                    created_by: created_by(&post),
+                });
+                out.truncated_posts += u64::from(truncated);
+                if let Some(url) = post.media {
+                    out.media.push(MediaRef {
+                        url,
+                        thread_id: None,
+                        reply_id: Some(post.id),
+                    });
+                }
+            }
+        }
+        out.dump.validate().map_err(|e| e.to_string())?;
+        Ok(out)
+    }
+}
+
+async fn fetch_media(
+    client: &reqwest::Client,
+    store: &dyn ImageStore,
+    allowed: &MimeAllowlist,
+    url: &str,
+) -> Result<(String, String), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("unsupported URL scheme".into());
+    }
+    let mut response = client
+        .get(parsed)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?;
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        if bytes.len() + chunk.len() > crate::routes::FILE_SIZE_LIMIT {
+            return Err("file too large".into());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    let hash = format!("{:x}", Sha256::digest(&bytes));
+    let mime = crate::routes::detect_upload_mime(&bytes);
+    if !allowed.allows(&mime) {
+        return Err(format!("unsupported media type {mime}"));
+    }
+    match store.save(&hash, &mime, &bytes).await {
+        Ok(()) | Err(ImageStoreError::Duplicate) => Ok((hash, mime)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Download referenced media into the image store and attach it to the dump.
+/// Failures are reported per URL; the post is imported without media.
+pub async fn download_media(
+    store: &dyn ImageStore,
+    allowed: &MimeAllowlist,
+    archive: &mut ConvertedArchive,
+) -> MediaSummary {
+    let mut summary = MediaSummary::default();
+    let client = match reqwest::Client::builder().timeout(MEDIA_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            summary.failed = archive
+                .media
+                .iter()
+                .map(|m| format!("{}: {e}", m.url))
+                .collect();
+            return summary;
+        }
+    };
+    let results: Vec<_> = futures_util::stream::iter(archive.media.iter())
+        .map(|media| {
+            let client = &client;
+            async move { (media, fetch_media(client, store, allowed, &media.url).await) }
+        })
+        .buffer_unordered(MEDIA_CONCURRENCY)
+        .collect()
+        .await;
+    for (media, result) in results {
+        match result {
+            Ok((hash, mime)) => {
+                summary.downloaded += 1;
+                archive.dump.images.push(DumpImage {
+                    hash,
+                    mime,
+                    thread_id: media.thread_id,
+                    reply_id: media.reply_id,
+                });
+            }
+            Err(e) => {
+                log::warn!("archive media {} not imported: {e}", media.url);
+                summary.failed.push(format!("{}: {e}", media.url));
+            }
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(threads: Vec<Value>) -> ArchiveImportRequest {
+        ArchiveImportRequest {
+            board: NewBoard {
+                slug: "g".into(),
+                title: "Technology".into(),
+            },
+            source_board: None,
+            mapping: ArchiveMapping::default(),
+            threads,
+            download_media: false,
+        }
+    }
+
+    #[test]
+    fn converts_4chan_thread_json() {
+        let thread = serde_json::json!({"posts": [
+            {"no": 100, "resto": 0, "time": 1_700_000_000, "name": "Anonymous",
+             "sub": "Rust &amp; you", "com": "first<br>line &gt;quote", "tim": 1_700_000_000_123_i64, "ext": ".png"},
+            {"no": 101, "resto": 100, "time": 1_700_000_060, "name": "kim", "trip": "!abc",
+             "com": "<a href=\"#p100\" class=\"quotelink\">&gt;&gt;100</a><br>agreed"}
+        ]});
+        let converted = request(vec![thread]).convert().unwrap();
+        let op = &converted.dump.threads[0];
+        assert_eq!(op.subject, "Rust & you");
+        assert_eq!(op.body, "first\nline >quote");
+        assert_eq!(op.author_name, None);
+        assert_eq!(op.bump_time.timestamp(), 1_700_000_060);
+        let reply = &converted.dump.replies[0];
+        assert_eq!(reply.content, ">>100\nagreed");
+        assert_eq!(reply.author_name.as_deref(), Some("kim"));
+        assert_eq!(reply.tripcode.as_deref(), Some("!abc"));
+        assert_eq!(
+            converted.media,
+            vec![MediaRef {
+                url: "https://i.4cdn.org/g/1700000000123.png".into(),
+                thread_id: Some(100),
+                reply_id: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn custom_mapping_and_missing_subject() {
+        let mut req = request(vec![serde_json::json!({"thread": {"items": [
+            {"num": "7", "posted": "2024-01-02T03:04:05Z", "text": "no subject here\nmore"}
+        ]}})]);
+        req.mapping = ArchiveMapping {
+            posts: "/thread/items".into(),
+            id: "num".into(),
+            body: "text".into(),
+            time: "posted".into(),
+            html: false,
+            media_url: None,
+            ..Default::default()
+        };
+        let converted = req.convert().unwrap();
+        assert_eq!(converted.dump.threads[0].id, 7);
+        assert_eq!(converted.dump.threads[0].subject, "no subject here");
+        assert!(converted.media.is_empty());
+    }
+
+    #[test]
+    fn decodes_numeric_entities_and_keeps_stray_ampersands() {
+        assert_eq!(decode_entities("&#039;a&#x41; & b&c;"), "'aA & b&c;");
+    }
+}
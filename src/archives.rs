@@ -0,0 +1,589 @@
+//! Inspecting uploaded archives.
+//!
+//! Zip, 7z, tar and gzip uploads are opened before they are stored, without
+//! writing anything to disk. An archive is dangerous when it would unpack to
+//! far more than it weighs (a zip bomb), holds more entries or bytes than
+//! [`ArchivePolicy`] allows, carries executables or scripts by extension, or
+//! names paths outside the folder it is unpacked into. Zip and 7z entries are
+//! judged by their declared sizes; only a 7z header is ever decompressed, and
+//! only after its declared size passes the same cap. Gzip streams are
+//! decompressed under a byte cap and given up on as soon as they pass it.
+//! RAR, bzip2 and xz archives are not opened, so by default they are
+//! dangerous (`require_listing`).
+
+use std::io::{Cursor, Read};
+use std::path::{Component, Path};
+
+use crate::models::{ArchiveEntry, ArchiveListing};
+use crate::uploads::ArchivePolicy;
+
+/// Most entries kept in a stored listing.
+pub const MAX_LISTED_ENTRIES: usize = 1000;
+/// Unpacked size below which compression ratios are not checked; small
+/// files of zeros compress well without being bombs.
+const RATIO_FLOOR: u64 = 1024 * 1024;
+
+/// What inspecting an upload found.
+#[derive(Debug, Default)]
+pub struct Inspection {
+    /// The files inside, when the archive could be read.
+    pub listing: Option<ArchiveListing>,
+    /// Why the archive is dangerous, if it is.
+    pub danger: Option<String>,
+}
+
+/// Whether uploads of `mime` are inspected as archives.
+pub fn inspects(mime: &str) -> bool {
+    matches!(
+        mime,
+        "application/zip"
+            | "application/x-tar"
+            | "application/gzip"
+            | "application/x-7z-compressed"
+            | "application/x-rar-compressed"
+            | "application/x-bzip2"
+            | "application/x-xz"
+    )
+}
+
+/// Inspect an upload of type `mime` under `policy`.
+pub fn inspect(bytes: &[u8], mime: &str, policy: &ArchivePolicy) -> Inspection {
+    let mut lister = Lister::new(bytes.len() as u64, policy);
+    let read = match mime {
+        "application/zip" => list_zip(bytes, &mut lister),
+        "application/x-7z-compressed" => list_7z(bytes, &mut lister),
+        "application/x-tar" => list_tar(bytes, &mut lister).map(|()| "tar"),
+        "application/gzip" => list_gzip(bytes, &mut lister),
+        _ => Err("archives of this type are not opened".to_string()),
+    };
+    match read {
+        Ok(format) => {
+            let danger = lister.danger.take();
+            Inspection {
+                listing: Some(lister.listing(format)),
+                danger,
+            }
+        }
+        // A bomb caught mid-stream cannot be listed in full.
+        Err(_) if lister.danger.is_some() => Inspection {
+            listing: None,
+            danger: lister.danger,
+        },
+        Err(e) => Inspection {
+            listing: None,
+            danger: policy
+                .require_listing
+                .then(|| format!("the archive could not be inspected: {e}")),
+        },
+    }
+}
+
+/// Collects entries and the first reason an archive is dangerous.
+struct Lister<'a> {
+    policy: &'a ArchivePolicy,
+    packed: u64,
+    entries: Vec<ArchiveEntry>,
+    entry_count: u64,
+    unpacked: u64,
+    danger: Option<String>,
+}
+
+impl<'a> Lister<'a> {
+    fn new(packed: u64, policy: &'a ArchivePolicy) -> Self {
+        Self {
+            policy,
+            packed,
+            entries: Vec::new(),
+            entry_count: 0,
+            unpacked: 0,
+            danger: None,
+        }
+    }
+
+    /// Most bytes a stream of this archive may decompress to.
+    fn unpack_limit(&self) -> u64 {
+        self.packed
+            .saturating_mul(self.policy.max_ratio)
+            .max(RATIO_FLOOR)
+            .min(self.policy.max_unpacked_bytes)
+    }
+
+    fn flag(&mut self, reason: String) {
+        self.danger.get_or_insert(reason);
+    }
+
+    /// Record one entry; `packed` is its compressed size when known.
+    fn add(&mut self, path: &str, size: u64, packed: Option<u64>, dir: bool) {
+        self.entry_count += 1;
+        self.unpacked = self.unpacked.saturating_add(size);
+        if self.entry_count > self.policy.max_entries as u64 {
+            self.flag(format!(
+                "the archive holds more than {} entries",
+                self.policy.max_entries
+            ));
+        }
+        if self.unpacked > self.policy.max_unpacked_bytes {
+            self.flag(format!(
+                "the archive unpacks to more than {} bytes",
+                self.policy.max_unpacked_bytes
+            ));
+        }
+        if let Some(packed) = packed {
+            if size > RATIO_FLOOR && size / packed.max(1) > self.policy.max_ratio {
+                self.flag(format!(
+                    "{path} expands more than {}-fold",
+                    self.policy.max_ratio
+                ));
+            }
+        }
+        if self.unpacked > RATIO_FLOOR && self.unpacked / self.packed.max(1) > self.policy.max_ratio
+        {
+            self.flag(format!(
+                "the archive expands more than {}-fold",
+                self.policy.max_ratio
+            ));
+        }
+        if escapes(path) {
+            self.flag(format!("{path} points outside the archive"));
+        }
+        if !dir {
+            if let Some(extension) = blocked_extension(path, &self.policy.blocked_extensions) {
+                self.flag(format!("the archive contains a .{extension} file ({path})"));
+            }
+        }
+        if self.entries.len() < MAX_LISTED_ENTRIES {
+            self.entries.push(ArchiveEntry {
+                path: path.to_string(),
+                size: size.min(i64::MAX as u64) as i64,
+                dir,
+            });
+        }
+    }
+
+    fn listing(self, format: &str) -> ArchiveListing {
+        ArchiveListing {
+            format: format.to_string(),
+            truncated: (self.entries.len() as u64) < self.entry_count,
+            entries: self.entries,
+            entry_count: self.entry_count.min(i64::MAX as u64) as i64,
+            unpacked_bytes: self.unpacked.min(i64::MAX as u64) as i64,
+        }
+    }
+}
+
+/// Whether unpacking `path` as written would leave the target folder.
+fn escapes(path: &str) -> bool {
+    let path = path.replace('\\', "/");
+    let drive = path.as_bytes().get(1) == Some(&b':');
+    drive
+        || Path::new(&path).components().any(|c| {
+            matches!(
+                c,
+                Component::RootDir | Component::ParentDir | Component::Prefix(_)
+            )
+        })
+}
+
+fn blocked_extension<'e>(path: &str, blocked: &'e [String]) -> Option<&'e str> {
+    let name = path.rsplit(['/', '\\']).next()?;
+    let (_, extension) = name.rsplit_once('.')?;
+    blocked
+        .iter()
+        .find(|b| b.eq_ignore_ascii_case(extension))
+        .map(String::as_str)
+}
+
+fn list_zip(bytes: &[u8], lister: &mut Lister) -> Result<&'static str, String> {
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    if zip.len() > lister.policy.max_entries {
+        lister.flag(format!(
+            "the archive holds more than {} entries",
+            lister.policy.max_entries
+        ));
+        return Err("too many entries".to_string());
+    }
+    for index in 0..zip.len() {
+        // Raw access reads the central directory only; nothing is inflated.
+        let file = zip.by_index_raw(index).map_err(|e| e.to_string())?;
+        lister.add(
+            file.name(),
+            file.size(),
+            Some(file.compressed_size()),
+            file.is_dir(),
+        );
+    }
+    Ok("zip")
+}
+
+fn list_7z(bytes: &[u8], lister: &mut Lister) -> Result<&'static str, String> {
+    // The file list may sit in a compressed header; refuse to inflate one
+    // that claims to be larger than the archive may unpack to.
+    let limit = lister.unpack_limit();
+    if let Some(size) = sevenz_header_size(bytes).filter(|size| *size > limit) {
+        lister.flag(format!("the archive header unpacks to {size} bytes"));
+        return Err("header too large".to_string());
+    }
+    let archive =
+        sevenz_rust2::Archive::read(&mut Cursor::new(bytes), &sevenz_rust2::Password::empty())
+            .map_err(|e| e.to_string())?;
+    for file in &archive.files {
+        lister.add(&file.name, file.size, None, file.is_directory);
+        if lister.entry_count > lister.policy.max_entries as u64 {
+            return Err("too many entries".to_string());
+        }
+    }
+    Ok("7z")
+}
+
+/// The largest size a 7z archive's compressed header declares it unpacks
+/// to, or `None` when the header is stored plainly or cannot be parsed.
+fn sevenz_header_size(bytes: &[u8]) -> Option<u64> {
+    const SIGNATURE_HEADER: usize = 32;
+    const ENCODED_HEADER: u8 = 0x17;
+    let start = bytes.get(12..20)?;
+    let offset = u64::from_le_bytes(start.try_into().ok()?);
+    let at = usize::try_from(offset)
+        .ok()?
+        .checked_add(SIGNATURE_HEADER)?;
+    let mut header = SevenZipHeader {
+        bytes: bytes.get(at..)?,
+    };
+    if header.byte()? != ENCODED_HEADER {
+        return None;
+    }
+    // Pack info: position, stream count, sizes and optional digests.
+    let mut nid = header.byte()?;
+    if nid == 0x06 {
+        header.number()?;
+        let streams = header.number()?;
+        loop {
+            match header.byte()? {
+                0x00 => break,
+                0x09 => (0..streams).try_for_each(|_| header.number().map(drop))?,
+                0x0A => header.skip_digests(streams)?,
+                _ => return None,
+            }
+        }
+        nid = header.byte()?;
+    }
+    // Unpack info: the folders' coders, then each coder output's size.
+    if nid != 0x07 || header.byte()? != 0x0B {
+        return None;
+    }
+    let folders = header.number()?;
+    if header.byte()? != 0 || folders > 64 {
+        return None;
+    }
+    let mut outputs = 0;
+    for _ in 0..folders {
+        let coders = header.number()?;
+        if coders > 64 {
+            return None;
+        }
+        let (mut ins, mut outs) = (0u64, 0u64);
+        for _ in 0..coders {
+            let flags = header.byte()?;
+            header.skip((flags & 0x0F) as u64)?;
+            if flags & 0x10 != 0 {
+                ins += header.number()?;
+                outs += header.number()?;
+            } else {
+                ins += 1;
+                outs += 1;
+            }
+            if flags & 0x20 != 0 {
+                let properties = header.number()?;
+                header.skip(properties)?;
+            }
+        }
+        if outs == 0 || outs > 64 || ins > 64 {
+            return None;
+        }
+        let bind_pairs = outs - 1;
+        (0..bind_pairs * 2).try_for_each(|_| header.number().map(drop))?;
+        let packed = ins.checked_sub(bind_pairs)?;
+        if packed > 1 {
+            (0..packed).try_for_each(|_| header.number().map(drop))?;
+        }
+        outputs += outs;
+    }
+    if header.byte()? != 0x0C {
+        return None;
+    }
+    (0..outputs)
+        .map(|_| header.number())
+        .try_fold(0, |max, size| Some(size?.max(max)))
+}
+
+/// Reads the fields of a 7z header.
+struct SevenZipHeader<'a> {
+    bytes: &'a [u8],
+}
+
+impl SevenZipHeader<'_> {
+    fn byte(&mut self) -> Option<u8> {
+        let (first, rest) = self.bytes.split_first()?;
+        self.bytes = rest;
+        Some(*first)
+    }
+
+    fn skip(&mut self, n: u64) -> Option<()> {
+        self.bytes = self.bytes.get(usize::try_from(n).ok()?..)?;
+        Some(())
+    }
+
+    /// A 7z variable-length number: the leading one bits of the first byte
+    /// count the little-endian bytes that follow.
+    fn number(&mut self) -> Option<u64> {
+        let first = self.byte()? as u64;
+        let mut value = 0;
+        let mut mask = 0x80;
+        for i in 0..8 {
+            if first & mask == 0 {
+                return Some(value | ((first & (mask - 1)) << (8 * i)));
+            }
+            value |= (self.byte()? as u64) << (8 * i);
+            mask >>= 1;
+        }
+        Some(value)
+    }
+
+    /// CRCs of `count` streams, each present unless a bit vector says not.
+    fn skip_digests(&mut self, count: u64) -> Option<()> {
+        let defined = if self.byte()? != 0 {
+            count
+        } else {
+            let bits = self.bytes.get(..usize::try_from(count.div_ceil(8)).ok()?)?;
+            self.skip(bits.len() as u64)?;
+            bits.iter().map(|b| b.count_ones() as u64).sum()
+        };
+        self.skip(defined.checked_mul(4)?)
+    }
+}
+
+fn list_tar(reader: impl Read, lister: &mut Lister) -> Result<(), String> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path().map_err(|e| e.to_string())?;
+        let path = path.to_string_lossy().into_owned();
+        let header = entry.header();
+        lister.add(
+            &path,
+            header.size().unwrap_or(0),
+            None,
+            header.entry_type().is_dir(),
+        );
+        if let Ok(Some(target)) = entry.link_name() {
+            let target = target.to_string_lossy();
+            if escapes(&target) {
+                lister.flag(format!("{path} links outside the archive"));
+            }
+        }
+        if lister.entry_count > lister.policy.max_entries as u64 {
+            return Err("too many entries".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// A gzip stream, listed as a tarball when it holds one.
+fn list_gzip(bytes: &[u8], lister: &mut Lister) -> Result<&'static str, String> {
+    let decoder = flate2::read::GzDecoder::new(bytes);
+    let name = decoder
+        .header()
+        .and_then(|h| h.filename())
+        .map(|n| String::from_utf8_lossy(n).into_owned());
+    let limit = lister.unpack_limit();
+    let mut stream = decoder.take(limit + 1);
+    let mut head = Vec::with_capacity(512);
+    (&mut stream)
+        .take(512)
+        .read_to_end(&mut head)
+        .map_err(|e| e.to_string())?;
+    let is_tar = head.get(257..262) == Some(b"ustar".as_slice());
+    let mut counted = Counted {
+        inner: Cursor::new(head).chain(stream),
+        read: 0,
+    };
+    let result = if is_tar {
+        list_tar(&mut counted, lister).map(|()| "tar.gz")
+    } else {
+        std::io::copy(&mut counted, &mut std::io::sink())
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+            .map(|()| "gzip")
+    };
+    if counted.read > limit {
+        lister.flag(format!("the archive unpacks to more than {limit} bytes"));
+        return Err("decompression limit reached".to_string());
+    }
+    let format = result?;
+    if !is_tar {
+        lister.add(name.as_deref().unwrap_or(""), counted.read, None, false);
+    }
+    Ok(format)
+}
+
+/// Counts the bytes read through it.
+struct Counted<R> {
+    inner: R,
+    read: u64,
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn zip_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, bytes) in files {
+            zip.start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(bytes).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn zips_are_listed_and_bombs_and_executables_flagged() {
+        let policy = ArchivePolicy::default();
+        let safe = zip_of(&[("docs/readme.txt", b"hello"), ("docs/data.csv", b"a,b")]);
+        let found = inspect(&safe, "application/zip", &policy);
+        assert!(found.danger.is_none());
+        let listing = found.listing.unwrap();
+        assert_eq!(listing.format, "zip");
+        assert_eq!(listing.entry_count, 2);
+        assert_eq!(listing.unpacked_bytes, 8);
+        assert_eq!(listing.entries[0].path, "docs/readme.txt");
+
+        let bomb = zip_of(&[("zeros.bin", &vec![0u8; 8 * 1024 * 1024])]);
+        let found = inspect(&bomb, "application/zip", &policy);
+        assert!(found.danger.unwrap().contains("fold"));
+
+        let found = inspect(
+            &zip_of(&[("setup/Setup.EXE", b"MZ")]),
+            "application/zip",
+            &policy,
+        );
+        assert!(found.danger.unwrap().contains(".exe"));
+        let found = inspect(
+            &zip_of(&[("../../etc/cron.d/x", b"* * * * *")]),
+            "application/zip",
+            &policy,
+        );
+        assert!(found.danger.unwrap().contains("outside"));
+    }
+
+    #[test]
+    fn tarballs_are_decompressed_under_a_cap() {
+        let mut tar = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_cksum();
+        tar.append_data(&mut header, "notes.txt", &b"hello"[..])
+            .unwrap();
+        let tar = tar.into_inner().unwrap();
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&tar).unwrap();
+        let gz = gz.finish().unwrap();
+        let found = inspect(&gz, "application/gzip", &ArchivePolicy::default());
+        assert!(found.danger.is_none());
+        let listing = found.listing.unwrap();
+        assert_eq!(listing.format, "tar.gz");
+        assert_eq!(listing.entries[0].path, "notes.txt");
+
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        gz.write_all(&vec![0u8; 16 * 1024 * 1024]).unwrap();
+        let found = inspect(
+            &gz.finish().unwrap(),
+            "application/gzip",
+            &ArchivePolicy::default(),
+        );
+        assert!(found.listing.is_none());
+        assert!(found.danger.unwrap().contains("unpacks to more than"));
+
+        // Archives that cannot be opened are refused unless allowed.
+        let rar = b"Rar!\x1a\x07\x01\x00";
+        let unreadable = inspect(
+            rar,
+            "application/x-rar-compressed",
+            &ArchivePolicy::default(),
+        );
+        assert!(unreadable.listing.is_none());
+        assert!(unreadable
+            .danger
+            .unwrap()
+            .contains("could not be inspected"));
+        let lenient = ArchivePolicy {
+            require_listing: false,
+            ..ArchivePolicy::default()
+        };
+        assert!(inspect(rar, "application/x-rar-compressed", &lenient)
+            .danger
+            .is_none());
+    }
+
+    fn sevenz_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = sevenz_rust2::ArchiveWriter::new(Cursor::new(Vec::new())).unwrap();
+        for (name, bytes) in files {
+            writer
+                .push_archive_entry(sevenz_rust2::ArchiveEntry::new_file(name), Some(*bytes))
+                .unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn sevenz_archives_are_listed_from_their_headers() {
+        let policy = ArchivePolicy::default();
+        let safe = sevenz_of(&[("docs/readme.txt", b"hello"), ("docs/data.csv", b"a,b")]);
+        // The writer compresses its header, so the size check runs first.
+        assert!(sevenz_header_size(&safe).is_some());
+        let found = inspect(&safe, "application/x-7z-compressed", &policy);
+        assert!(found.danger.is_none());
+        let listing = found.listing.unwrap();
+        assert_eq!(listing.format, "7z");
+        assert_eq!(listing.entry_count, 2);
+        assert_eq!(listing.unpacked_bytes, 8);
+        assert_eq!(listing.entries[0].path, "docs/readme.txt");
+
+        let found = inspect(
+            &sevenz_of(&[("setup/Setup.EXE", b"MZ")]),
+            "application/x-7z-compressed",
+            &policy,
+        );
+        assert!(found.danger.unwrap().contains(".exe"));
+        let bomb = sevenz_of(&[("zeros.bin", &vec![0u8; 8 * 1024 * 1024])]);
+        let found = inspect(&bomb, "application/x-7z-compressed", &policy);
+        assert!(found.danger.unwrap().contains("fold"));
+    }
+
+    #[test]
+    fn sevenz_headers_claiming_huge_sizes_are_not_inflated() {
+        let mut bomb = b"7z\xbc\xaf\x27\x1c\x00\x04".to_vec();
+        bomb.resize(32, 0);
+        // Encoded header: one packed stream of 16 bytes, one LZMA folder
+        // declaring an unpacked size of 2^40 bytes.
+        bomb.extend_from_slice(&[0x17, 0x06, 0x00, 0x01, 0x09, 0x10, 0x00]);
+        bomb.extend_from_slice(&[0x07, 0x0B, 0x01, 0x00, 0x01, 0x03, 0x03, 0x01, 0x01]);
+        bomb.extend_from_slice(&[0x0C, 0xFF, 0, 0, 0, 0, 0, 0x01, 0, 0, 0x00]);
+        assert_eq!(sevenz_header_size(&bomb), Some(1 << 40));
+        let found = inspect(
+            &bomb,
+            "application/x-7z-compressed",
+            &ArchivePolicy::default(),
+        );
+        assert!(found.listing.is_none());
+        assert!(found.danger.unwrap().contains("header unpacks to"));
+    }
+}
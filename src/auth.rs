@@ -11,7 +11,7 @@ use std::future::{ready, Ready};
 
 pub const AUTH_COOKIE_NAME: &str = "rib_session";
 pub const OAUTH_TRANSACTION_COOKIE_NAME: &str = "rib_oauth_transaction";
-const OAUTH_TRANSACTION_TTL_MINUTES: i64 = 10;
+pub(crate) const OAUTH_TRANSACTION_TTL_MINUTES: i64 = 10;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -26,6 +26,17 @@ pub struct Claims {
     pub sub: String,
     pub exp: usize,
     pub roles: Vec<Role>,
+    /// Bitcoin sessions: when the address last met the balance threshold
+    /// (unix seconds).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance_verified_at: Option<usize>,
+    /// Impersonation tokens: the subject the admin in `sub` acts as.
+    /// [`decode_jwt`] moves it into `sub`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act_as: Option<String>,
+    /// Set by [`decode_jwt`] on impersonation tokens: the admin behind them.
+    #[serde(skip)]
+    pub impersonated_by: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -115,6 +126,119 @@ pub fn consume_oauth_transaction(
     Ok(transaction.pkce_verifier)
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EmailLoginClaims {
+    /// Always `"email_login"`, so a session token can never stand in for a link.
+    purpose: String,
+    email: String,
+    nonce: String,
+    exp: usize,
+}
+
+/// A verified magic-link token.
+#[derive(Debug, Clone)]
+pub struct EmailLogin {
+    pub email: String,
+    /// Unique per link; callers must reject a nonce they have already accepted.
+    pub nonce: String,
+    pub exp: usize,
+}
+
+/// Sign a one-time login link token for `email`, valid for `ttl`.
+pub fn create_email_login_token(
+    email: &str,
+    ttl: std::time::Duration,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let exp = (chrono::Utc::now().timestamp() as u64 + ttl.as_secs()) as usize;
+    encode(
+        &Header::default(),
+        &EmailLoginClaims {
+            purpose: "email_login".into(),
+            email: email.to_string(),
+            nonce: random_urlsafe(16),
+            exp,
+        },
+        &EncodingKey::from_secret(jwt_secret().as_bytes()),
+    )
+}
+
+pub fn decode_email_login_token(token: &str) -> Result<EmailLogin, jsonwebtoken::errors::Error> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.validate_exp = true;
+    let claims = decode::<EmailLoginClaims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret().as_bytes()),
+        &validation,
+    )?
+    .claims;
+    if claims.purpose != "email_login" {
+        return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+    }
+    Ok(EmailLogin {
+        email: claims.email,
+        nonce: claims.nonce,
+        exp: claims.exp,
+    })
+}
+
+/// What a mailed notification link may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationAction {
+    /// Confirm `email` as the subject's digest address.
+    ConfirmEmail,
+    /// Turn the subject's digests off.
+    Unsubscribe,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationClaims {
+    pub purpose: String,
+    pub action: NotificationAction,
+    pub sub: String,
+    pub email: String,
+    pub exp: usize,
+}
+
+/// Sign a link token letting whoever holds it act on `subject`'s notifications.
+pub fn create_notification_token(
+    action: NotificationAction,
+    subject: &str,
+    email: &str,
+    ttl: std::time::Duration,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let exp = (chrono::Utc::now().timestamp() as u64 + ttl.as_secs()) as usize;
+    encode(
+        &Header::default(),
+        &NotificationClaims {
+            purpose: "notification".into(),
+            action,
+            sub: subject.to_string(),
+            email: email.to_string(),
+            exp,
+        },
+        &EncodingKey::from_secret(jwt_secret().as_bytes()),
+    )
+}
+
+pub fn decode_notification_token(
+    token: &str,
+    action: NotificationAction,
+) -> Result<NotificationClaims, jsonwebtoken::errors::Error> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.validate_exp = true;
+    let claims = decode::<NotificationClaims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret().as_bytes()),
+        &validation,
+    )?
+    .claims;
+    if claims.purpose != "notification" || claims.action != action {
+        return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+    }
+    Ok(claims)
+}
+
 pub fn session_cookie(token: &str) -> Cookie<'static> {
     Cookie::build(AUTH_COOKIE_NAME, token.to_owned())
         .http_only(true)
@@ -125,6 +249,17 @@ pub fn session_cookie(token: &str) -> Cookie<'static> {
         .finish()
 }
 
+/// Access policy clearance bought with a solved proof of work.
+pub fn clearance_cookie(value: &str, ttl: std::time::Duration) -> Cookie<'static> {
+    Cookie::build(crate::pow::CLEARANCE_COOKIE, value.to_owned())
+        .http_only(true)
+        .secure(cookies_secure())
+        .same_site(SameSite::Lax)
+        .path("/")
+        .max_age(CookieDuration::seconds(ttl.as_secs() as i64))
+        .finish()
+}
+
 pub fn clear_session_cookie() -> Cookie<'static> {
     Cookie::build(AUTH_COOKIE_NAME, "")
         .http_only(true)
@@ -145,17 +280,37 @@ pub fn clear_oauth_transaction_cookie() -> Cookie<'static> {
         .finish()
 }
 
-/// Validate a JWT and return its claims.
-fn decode_jwt(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+/// Validate a JWT and return its claims. Impersonation tokens come back
+/// acting as their subject, with the admin in `impersonated_by`.
+pub fn decode_jwt(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
     let secret = jwt_secret();
     let mut validation = Validation::new(Algorithm::HS256);
     validation.validate_exp = true;
-    let data = decode::<Claims>(
+    let mut claims = decode::<Claims>(
         token,
         &DecodingKey::from_secret(secret.as_bytes()),
         &validation,
-    )?;
-    Ok(data.claims)
+    )?
+    .claims;
+    if let Some(subject) = claims.act_as.take() {
+        claims.impersonated_by = Some(std::mem::replace(&mut claims.sub, subject));
+    }
+    Ok(claims)
+}
+
+/// Raw bearer token or session cookie of a request, unverified.
+pub(crate) fn request_token(req: &actix_web::dev::ServiceRequest) -> Option<String> {
+    req.headers()
+        .get(actix_web::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string)
+        .or_else(|| req.cookie(AUTH_COOKIE_NAME).map(|c| c.value().to_string()))
+}
+
+/// Subject of a valid token, for attributing log lines; `None` if it does not verify.
+pub fn subject_from_token(token: &str) -> Option<String> {
+    decode_jwt(token).ok().map(|claims| claims.sub)
 }
 
 /// Extractor yielding validated `Claims`.
@@ -195,27 +350,25 @@ macro_rules! require_role {
     };
 }
 
-/// Create a JWT for a user
-pub fn create_jwt(
-    user_id: &str,
-    username: &str,
+fn encode_session(
+    sub: String,
     roles: Vec<Role>,
+    ttl: chrono::Duration,
+    balance_verified_at: Option<usize>,
 ) -> Result<String, jsonwebtoken::errors::Error> {
     let secret = jwt_secret();
     let expiration = chrono::Utc::now()
-        .checked_add_signed(chrono::Duration::hours(24))
+        .checked_add_signed(ttl)
         .expect("valid timestamp")
         .timestamp() as usize;
 
     let claims = Claims {
-        // If user_id already contains a colon we assume caller provided a composite subject (e.g. "btc:addr")
-        sub: if user_id.contains(':') {
-            user_id.to_string()
-        } else {
-            format!("{}:{}", user_id, username)
-        },
+        sub,
         exp: expiration,
         roles,
+        balance_verified_at,
+        act_as: None,
+        impersonated_by: None,
     };
 
     encode(
@@ -225,11 +378,75 @@ pub fn create_jwt(
     )
 }
 
+/// Create a JWT for a user
+pub fn create_jwt(
+    user_id: &str,
+    username: &str,
+    roles: Vec<Role>,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    // If user_id already contains a colon we assume caller provided a composite subject (e.g. "btc:addr")
+    let sub = if user_id.contains(':') {
+        user_id.to_string()
+    } else {
+        format!("{}:{}", user_id, username)
+    };
+    encode_session(sub, roles, chrono::Duration::hours(24), None)
+}
+
+/// Sign a token letting admin `actor` act as the JWT subject `subject`
+/// with `roles`, until `expires_at`.
+pub fn create_impersonation_jwt(
+    actor: &str,
+    subject: &str,
+    roles: Vec<Role>,
+    expires_at: chrono::DateTime<chrono::Utc>,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    encode(
+        &Header::default(),
+        &Claims {
+            sub: actor.to_string(),
+            exp: expires_at.timestamp() as usize,
+            roles,
+            balance_verified_at: None,
+            act_as: Some(subject.to_string()),
+            impersonated_by: None,
+        },
+        &EncodingKey::from_secret(jwt_secret().as_bytes()),
+    )
+}
+
+/// Bitcoin sessions are short-lived (`BTC_TOKEN_TTL_SECS`, default one hour)
+/// so the balance behind them is looked at again on refresh.
+pub fn bitcoin_token_ttl() -> chrono::Duration {
+    chrono::Duration::seconds(
+        env::var("BTC_TOKEN_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .unwrap_or(3600)
+            .clamp(60, 24 * 3600),
+    )
+}
+
 /// Convenience for Bitcoin auth where we just have an address (no username) and want provider prefix
 pub fn create_bitcoin_jwt(
     address: &str,
     roles: Vec<Role>,
 ) -> Result<String, jsonwebtoken::errors::Error> {
     // Subject shape: "btc:<address>"
-    create_jwt(&format!("btc:{}", address), address, roles)
+    let now = chrono::Utc::now().timestamp() as usize;
+    reissue_bitcoin_jwt(&format!("btc:{}", address), roles, now)
+}
+
+/// A fresh Bitcoin session carrying forward when the balance was last verified.
+pub fn reissue_bitcoin_jwt(
+    subject: &str,
+    roles: Vec<Role>,
+    balance_verified_at: usize,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    encode_session(
+        subject.to_string(),
+        roles,
+        bitcoin_token_ttl(),
+        Some(balance_verified_at),
+    )
 }
@@ -0,0 +1,82 @@
+//! Soft-launched boards.
+//!
+//! An admin sets `beta` on a board to trial it before launch. Until the flag
+//! is cleared, the board and its posts are listed, readable and open for
+//! posting only to moderators, admins and the subjects on its tester list;
+//! everyone else gets `404` for it, as if it did not exist, and its posts
+//! are left out of their search results.
+
+use std::collections::HashSet;
+
+use crate::auth::{Auth, Role};
+use crate::error::ApiError;
+use crate::models::{Board, Id};
+use crate::routes::{role_subject_key, AppState};
+
+fn is_staff(auth: &Auth) -> bool {
+    auth.0
+        .roles
+        .iter()
+        .any(|r| matches!(r, Role::Moderator | Role::Admin))
+}
+
+/// Whether `viewer` may see and post to `board`.
+pub async fn can_access(
+    data: &AppState,
+    board: &Board,
+    viewer: Option<&Auth>,
+) -> Result<bool, ApiError> {
+    if !board.beta {
+        return Ok(true);
+    }
+    let Some(auth) = viewer else {
+        return Ok(false);
+    };
+    if is_staff(auth) {
+        return Ok(true);
+    }
+    let Some(subject) = role_subject_key(&auth.0.sub) else {
+        return Ok(false);
+    };
+    Ok(data
+        .repo
+        .tester_board_ids(&subject)
+        .await?
+        .contains(&board.id))
+}
+
+/// `NotFound` unless `viewer` may see `board`.
+pub async fn ensure_access(
+    data: &AppState,
+    board: &Board,
+    viewer: Option<&Auth>,
+) -> Result<(), ApiError> {
+    if can_access(data, board, viewer).await? {
+        Ok(())
+    } else {
+        metrics::increment_counter!("beta_board_hidden");
+        Err(ApiError::NotFound)
+    }
+}
+
+/// `boards` without the beta boards `viewer` may not see.
+pub async fn retain_accessible(
+    data: &AppState,
+    mut boards: Vec<Board>,
+    viewer: Option<&Auth>,
+) -> Result<Vec<Board>, ApiError> {
+    if !boards.iter().any(|b| b.beta) || viewer.is_some_and(is_staff) {
+        return Ok(boards);
+    }
+    let testing: HashSet<Id> = match viewer.and_then(|a| role_subject_key(&a.0.sub)) {
+        Some(subject) => data
+            .repo
+            .tester_board_ids(&subject)
+            .await?
+            .into_iter()
+            .collect(),
+        None => HashSet::new(),
+    };
+    boards.retain(|b| !b.beta || testing.contains(&b.id));
+    Ok(boards)
+}
@@ -0,0 +1,310 @@
+//! Sorting requests into humans, known crawlers and unknown bots.
+//!
+//! [`classify`] looks at the `User-Agent` and at headers every browser sends:
+//! named search and link-preview crawlers are [`BotClass::Crawler`], while an
+//! empty agent, an HTTP library or a browser agent without `Accept-Language`
+//! is an [`BotClass::UnknownBot`]. Crawlers get the server-rendered pages (see
+//! [`crate::ssr`]); [`BotLimits`] counts every class and holds each bot class
+//! to its own per-IP request limit, answering `429` past it.
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{RequestHead, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header;
+use actix_web::{Error, ResponseError};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::ApiError;
+use crate::rate_limit::{RateLimitAlgorithm, RateLimiter};
+use crate::routes::extract_client_ip;
+
+/// Paths never limited: probes and scrapers are bots by design, and
+/// crawlers must always be able to read the crawl rules.
+const EXEMPT_PATHS: &[&str] = &["/healthz", "/readyz", "/metrics", "/robots.txt"];
+
+/// Substrings of well-known search, feed and link-preview crawlers.
+const KNOWN_CRAWLERS: &[&str] = &[
+    "googlebot",
+    "bingbot",
+    "duckduckbot",
+    "yandexbot",
+    "baiduspider",
+    "applebot",
+    "slurp",
+    "facebookexternalhit",
+    "twitterbot",
+    "discordbot",
+    "slackbot",
+    "linkedinbot",
+    "telegrambot",
+    "embedly",
+];
+
+/// Browsers without JavaScript; human, but served the rendered pages.
+const TEXT_BROWSERS: &[&str] = &["lynx", "w3m", "elinks", "links ("];
+
+/// Substrings of automation that does not say who runs it.
+const BOT_MARKERS: &[&str] = &[
+    "bot",
+    "crawler",
+    "spider",
+    "scrapy",
+    "curl/",
+    "wget/",
+    "python-requests",
+    "python-urllib",
+    "aiohttp",
+    "go-http-client",
+    "java/",
+    "okhttp",
+    "libwww-perl",
+    "node-fetch",
+    "axios/",
+    "headlesschrome",
+    "phantomjs",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BotClass {
+    Human,
+    Crawler,
+    UnknownBot,
+}
+
+impl BotClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Human => "human",
+            Self::Crawler => "crawler",
+            Self::UnknownBot => "unknown_bot",
+        }
+    }
+}
+
+fn user_agent(head: &RequestHead) -> Option<String> {
+    head.headers()
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|ua| !ua.is_empty())
+        .map(str::to_ascii_lowercase)
+}
+
+pub fn classify(head: &RequestHead) -> BotClass {
+    let Some(ua) = user_agent(head) else {
+        return BotClass::UnknownBot;
+    };
+    if KNOWN_CRAWLERS.iter().any(|needle| ua.contains(needle)) {
+        return BotClass::Crawler;
+    }
+    if TEXT_BROWSERS.iter().any(|needle| ua.contains(needle)) {
+        return BotClass::Human;
+    }
+    // Browsers send a language with every request, fetches included.
+    if BOT_MARKERS.iter().any(|needle| ua.contains(needle))
+        || !head.headers().contains_key(header::ACCEPT_LANGUAGE)
+    {
+        return BotClass::UnknownBot;
+    }
+    BotClass::Human
+}
+
+/// Whether the SPA's URLs should answer with server-rendered pages.
+pub fn wants_rendered_pages(head: &RequestHead) -> bool {
+    classify(head) == BotClass::Crawler
+        || user_agent(head).is_some_and(|ua| TEXT_BROWSERS.iter().any(|needle| ua.contains(needle)))
+}
+
+#[derive(Clone, Debug)]
+pub struct BotLimitConfig {
+    /// Requests per window per IP for unknown bots; zero disables the limit.
+    pub unknown_limit: usize,
+    /// Requests per window per IP for known crawlers; zero disables the limit.
+    pub crawler_limit: usize,
+    pub window: Duration,
+}
+
+impl BotLimitConfig {
+    pub fn from_env() -> Self {
+        fn usize_env(name: &str, default: usize) -> usize {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        Self {
+            unknown_limit: usize_env("BOT_UNKNOWN_LIMIT", 60),
+            crawler_limit: usize_env("BOT_CRAWLER_LIMIT", 0),
+            window: Duration::from_secs(usize_env("BOT_WINDOW_SECS", 60).max(1) as u64),
+        }
+    }
+
+    fn limit(&self, class: BotClass) -> Option<usize> {
+        let limit = match class {
+            BotClass::Human => 0,
+            BotClass::Crawler => self.crawler_limit,
+            BotClass::UnknownBot => self.unknown_limit,
+        };
+        (limit > 0).then_some(limit)
+    }
+}
+
+/// Middleware counting request classes in `requests_classified` and
+/// enforcing [`BotLimitConfig`]. Build it once and clone it into each
+/// worker's app so the counts are shared.
+#[derive(Clone)]
+pub struct BotLimits {
+    cfg: Arc<BotLimitConfig>,
+    limiter: Arc<dyn RateLimiter>,
+}
+
+impl BotLimits {
+    pub fn new(cfg: BotLimitConfig, algorithm: RateLimitAlgorithm) -> Self {
+        Self {
+            cfg: Arc::new(cfg),
+            limiter: algorithm.limiter(),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for BotLimits
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = BotLimitsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(BotLimitsMiddleware {
+            service: Rc::new(service),
+            limits: self.clone(),
+        }))
+    }
+}
+
+pub struct BotLimitsMiddleware<S> {
+    service: Rc<S>,
+    limits: BotLimits,
+}
+
+impl<S, B> Service<ServiceRequest> for BotLimitsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let class = classify(req.head());
+        metrics::increment_counter!("requests_classified", "class" => class.as_str());
+        let cfg = &self.limits.cfg;
+        if let Some(limit) = cfg
+            .limit(class)
+            .filter(|_| !EXEMPT_PATHS.contains(&req.path()))
+        {
+            let key = format!(
+                "bot:{}:{}",
+                class.as_str(),
+                extract_client_ip(req.request())
+            );
+            if !self.limits.limiter.check(&key, limit, cfg.window) {
+                metrics::increment_counter!("bot_requests_limited", "class" => class.as_str());
+                let response = ApiError::RateLimited {
+                    retry_after: cfg.window.as_secs(),
+                }
+                .error_response();
+                return Box::pin(ready(Ok(req.into_response(response).map_into_right_body())));
+            }
+        }
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test as web_test, web, App, HttpResponse};
+
+    #[test]
+    fn classifies_by_agent_and_browser_headers() {
+        let class = |agent: Option<&str>, language: bool| {
+            let mut req = web_test::TestRequest::get();
+            if let Some(agent) = agent {
+                req = req.insert_header((header::USER_AGENT, agent));
+            }
+            if language {
+                req = req.insert_header((header::ACCEPT_LANGUAGE, "en"));
+            }
+            classify(req.to_srv_request().head())
+        };
+        let firefox = "Mozilla/5.0 (X11; Linux x86_64; rv:130.0) Gecko/20100101 Firefox/130.0";
+        assert_eq!(class(Some(firefox), true), BotClass::Human);
+        assert_eq!(class(Some(firefox), false), BotClass::UnknownBot);
+        assert_eq!(
+            class(Some("Mozilla/5.0 (compatible; Googlebot/2.1)"), false),
+            BotClass::Crawler
+        );
+        assert_eq!(class(Some("curl/8.5.0"), true), BotClass::UnknownBot);
+        assert_eq!(class(Some("SomeNewBot/1.0"), true), BotClass::UnknownBot);
+        assert_eq!(class(None, true), BotClass::UnknownBot);
+        assert_eq!(class(Some("Lynx/2.9.0"), false), BotClass::Human);
+    }
+
+    #[actix_web::test]
+    async fn unknown_bots_get_their_own_limit() {
+        let limits = BotLimits::new(
+            BotLimitConfig {
+                unknown_limit: 2,
+                crawler_limit: 0,
+                window: Duration::from_secs(30),
+            },
+            RateLimitAlgorithm::SlidingWindow,
+        );
+        let app = web_test::init_service(
+            App::new()
+                .wrap(limits)
+                .route("/", web::get().to(HttpResponse::Ok))
+                .route("/healthz", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        let request = |path: &str, agent: &str| {
+            web_test::TestRequest::get()
+                .uri(path)
+                .peer_addr("10.0.0.1:1000".parse().unwrap())
+                .insert_header((header::USER_AGENT, agent))
+                .insert_header((header::ACCEPT_LANGUAGE, "en"))
+                .to_request()
+        };
+        for _ in 0..2 {
+            let resp = web_test::call_service(&app, request("/", "curl/8.5.0")).await;
+            assert_eq!(resp.status(), 200);
+        }
+        let resp = web_test::call_service(&app, request("/", "curl/8.5.0")).await;
+        assert_eq!(resp.status(), 429);
+        assert_eq!(resp.headers().get("Retry-After").unwrap(), "30");
+        let resp = web_test::call_service(&app, request("/healthz", "curl/8.5.0")).await;
+        assert_eq!(resp.status(), 200);
+        for agent in ["Googlebot/2.1", "Mozilla/5.0 Firefox/130.0"] {
+            let resp = web_test::call_service(&app, request("/", agent)).await;
+            assert_eq!(resp.status(), 200);
+        }
+    }
+}
@@ -0,0 +1,413 @@
+//! Outbound bridges announcing new threads on other platforms.
+//!
+//! Admins bridge a board to a Discord webhook, a Telegram chat or a Nostr
+//! relay under `/api/v1/admin/boards/{id}/bridges`. This runner leases the
+//! enabled bridges, announces threads posted since each bridge's cursor and
+//! advances it. Every bridge posts at most `BRIDGE_MAX_PER_MINUTE` times a
+//! minute; the rest wait for later passes. A failed post keeps the cursor
+//! in place and the bridge is retried after `BRIDGE_RETRY_SECS`.
+//!
+//! Telegram bridges post through the bot named by `BRIDGE_TELEGRAM_BOT_TOKEN`
+//! and Nostr bridges publish kind 1 notes signed with
+//! `BRIDGE_NOSTR_SECRET_KEY`.
+
+use futures_util::{SinkExt, StreamExt};
+use secp256k1::{Keypair, Secp256k1};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+use crate::models::{BoardBridge, BridgeKind, Id, Thread};
+use crate::nostr::NostrEvent;
+use crate::repo::{Repo, RepoError};
+
+/// Longest bridge target accepted, in characters.
+pub const MAX_TARGET_CHARS: usize = 512;
+
+/// How long a relay may take to acknowledge a published note.
+const RELAY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug)]
+pub struct BridgeConfig {
+    pub enabled: bool,
+    pub poll_interval: Duration,
+    /// Bridges leased per poll at most.
+    pub batch_size: i64,
+    /// Posts per bridge per minute at most.
+    pub max_per_minute: i64,
+    /// Age a thread must reach before it is announced, so threads still
+    /// being committed are not skipped.
+    pub settle: Duration,
+    pub lease: Duration,
+    /// Wait before retrying a bridge whose post failed.
+    pub retry: Duration,
+    /// Public origin used in links, e.g. `https://rib.example`.
+    pub base_url: String,
+    pub telegram_bot_token: Option<String>,
+    pub telegram_api_url: String,
+    /// Hex secret key Nostr notes are signed with.
+    pub nostr_secret_key: Option<String>,
+}
+
+impl BridgeConfig {
+    pub fn from_env() -> Self {
+        fn u64_env(name: &str, default: u64) -> u64 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        fn opt_env(name: &str) -> Option<String> {
+            std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+        }
+        Self {
+            enabled: std::env::var("BRIDGES_ENABLED")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(true),
+            poll_interval: Duration::from_secs(u64_env("BRIDGE_POLL_SECS", 15).max(1)),
+            batch_size: u64_env("BRIDGE_BATCH_SIZE", 20).max(1) as i64,
+            max_per_minute: u64_env("BRIDGE_MAX_PER_MINUTE", 10).max(1) as i64,
+            settle: Duration::from_secs(u64_env("BRIDGE_SETTLE_SECS", 5)),
+            lease: Duration::from_secs(u64_env("BRIDGE_LEASE_SECS", 120).max(1)),
+            retry: Duration::from_secs(u64_env("BRIDGE_RETRY_SECS", 60)),
+            base_url: crate::sitemap::site_url(),
+            telegram_bot_token: opt_env("BRIDGE_TELEGRAM_BOT_TOKEN"),
+            telegram_api_url: opt_env("BRIDGE_TELEGRAM_API_URL")
+                .unwrap_or_else(|| "https://api.telegram.org".to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            nostr_secret_key: opt_env("BRIDGE_NOSTR_SECRET_KEY"),
+        }
+    }
+
+    /// The Nostr signing key, if one is configured.
+    pub fn nostr_keypair(&self) -> anyhow::Result<Option<Keypair>> {
+        self.nostr_secret_key
+            .as_deref()
+            .map(|key| {
+                let bytes = hex::decode(key.trim())
+                    .map_err(|_| anyhow::anyhow!("BRIDGE_NOSTR_SECRET_KEY must be hex"))?;
+                Ok(Keypair::from_seckey_slice(&Secp256k1::new(), &bytes)?)
+            })
+            .transpose()
+    }
+
+    /// Check a target before a bridge is created: URLs must use the scheme
+    /// the platform expects and the platform's credentials must be set.
+    pub fn validate_target(&self, kind: BridgeKind, target: &str) -> Result<(), String> {
+        if target.is_empty() || target.chars().count() > MAX_TARGET_CHARS {
+            return Err(format!("target must be 1 to {MAX_TARGET_CHARS} characters"));
+        }
+        let scheme = |allowed: &[&str]| {
+            reqwest::Url::parse(target)
+                .ok()
+                .filter(|url| allowed.contains(&url.scheme()) && url.host().is_some())
+                .map(|_| ())
+        };
+        match kind {
+            BridgeKind::Discord => scheme(&["https", "http"])
+                .ok_or_else(|| "discord target must be a webhook URL".to_string()),
+            BridgeKind::Telegram => {
+                if self.telegram_bot_token.is_none() {
+                    return Err("BRIDGE_TELEGRAM_BOT_TOKEN is not set".into());
+                }
+                let valid = target.strip_prefix('@').map_or_else(
+                    || {
+                        target
+                            .strip_prefix('-')
+                            .unwrap_or(target)
+                            .parse::<u64>()
+                            .is_ok()
+                    },
+                    |name| {
+                        !name.is_empty()
+                            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                    },
+                );
+                valid
+                    .then_some(())
+                    .ok_or_else(|| "telegram target must be a chat id or @channel".to_string())
+            }
+            BridgeKind::Nostr => {
+                match self.nostr_keypair() {
+                    Ok(Some(_)) => {}
+                    Ok(None) => return Err("BRIDGE_NOSTR_SECRET_KEY is not set".into()),
+                    Err(e) => return Err(e.to_string()),
+                }
+                scheme(&["wss", "ws"])
+                    .ok_or_else(|| "nostr target must be a wss:// relay URL".to_string())
+            }
+        }
+    }
+}
+
+/// Plain-text announcement of a new thread.
+pub fn announcement(base_url: &str, board_slug: &str, thread: &Thread) -> String {
+    let mut text = format!("New thread on /{board_slug}/: {}", thread.subject);
+    let excerpt = crate::digest::excerpt(&thread.body);
+    if !excerpt.is_empty() {
+        text.push_str("\n\n");
+        text.push_str(&excerpt);
+    }
+    text.push_str(&format!("\n\n{base_url}/thread/{}", thread.id));
+    text
+}
+
+/// Polls the enabled bridges and announces new threads.
+#[derive(Clone)]
+pub struct BridgeRunner {
+    repo: Arc<dyn Repo>,
+    cfg: BridgeConfig,
+    client: reqwest::Client,
+    nostr_keypair: Option<Keypair>,
+}
+
+impl BridgeRunner {
+    pub fn new(repo: Arc<dyn Repo>, cfg: BridgeConfig) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(3))
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        let nostr_keypair = cfg.nostr_keypair()?;
+        Ok(Self {
+            repo,
+            cfg,
+            client,
+            nostr_keypair,
+        })
+    }
+
+    /// Run every leased bridge once; returns the threads announced.
+    pub async fn run_once(&self) -> usize {
+        let bridges = match self
+            .repo
+            .claim_board_bridges(self.cfg.batch_size, self.cfg.lease.as_secs() as i64)
+            .await
+        {
+            Ok(bridges) => bridges,
+            Err(e) => {
+                log::error!("bridge claim failed: {e}");
+                return 0;
+            }
+        };
+        let mut announced = 0;
+        for bridge in bridges {
+            announced += self.run_bridge(&bridge).await;
+        }
+        announced
+    }
+
+    async fn run_bridge(&self, bridge: &BoardBridge) -> usize {
+        let budget = self.cfg.max_per_minute - i64::from(bridge.window_sent);
+        let mut cursor = bridge.last_thread_id;
+        let mut sent = 0;
+        let error = if budget > 0 {
+            self.announce(bridge, budget, &mut cursor, &mut sent)
+                .await
+                .err()
+        } else {
+            None
+        };
+        if let Err(e) = self
+            .repo
+            .release_board_bridge(
+                bridge.id,
+                cursor,
+                sent as i32,
+                error,
+                self.cfg.retry.as_secs() as i64,
+            )
+            .await
+        {
+            log::error!("bridge {} release failed: {e}", bridge.id);
+        }
+        sent
+    }
+
+    /// Announce up to `budget` pending threads in order, moving `cursor`
+    /// past each one posted; stops at the first failure.
+    async fn announce(
+        &self,
+        bridge: &BoardBridge,
+        budget: i64,
+        cursor: &mut Id,
+        sent: &mut usize,
+    ) -> Result<(), String> {
+        let lookup_failed = |e: RepoError| {
+            log::error!("bridge {} lookup failed: {e}", bridge.id);
+            "database unavailable".to_string()
+        };
+        let threads = self
+            .repo
+            .pending_bridge_threads(bridge, budget, self.cfg.settle.as_secs() as i64)
+            .await
+            .map_err(lookup_failed)?;
+        if threads.is_empty() {
+            return Ok(());
+        }
+        let board = self
+            .repo
+            .get_board(bridge.board_id)
+            .await
+            .map_err(lookup_failed)?;
+        for thread in threads {
+            let text = announcement(&self.cfg.base_url, &board.slug, &thread);
+            if let Err(e) = self.send(bridge, &thread, &text).await {
+                metrics::increment_counter!("bridge_posts_failed", "kind" => bridge.kind.as_str());
+                log::warn!(
+                    "bridge {} ({}) failed on thread {}: {e}",
+                    bridge.id,
+                    bridge.kind.as_str(),
+                    thread.id
+                );
+                return Err(e.to_string());
+            }
+            metrics::increment_counter!("bridge_posts", "kind" => bridge.kind.as_str());
+            *cursor = thread.id;
+            *sent += 1;
+        }
+        Ok(())
+    }
+
+    async fn send(&self, bridge: &BoardBridge, thread: &Thread, text: &str) -> anyhow::Result<()> {
+        match bridge.kind {
+            BridgeKind::Discord => {
+                let body = serde_json::json!({
+                    "content": text,
+                    "allowed_mentions": { "parse": [] },
+                });
+                self.post_json(&bridge.target, &body).await
+            }
+            BridgeKind::Telegram => {
+                let token = self
+                    .cfg
+                    .telegram_bot_token
+                    .as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("BRIDGE_TELEGRAM_BOT_TOKEN is not set"))?;
+                let url = format!("{}/bot{token}/sendMessage", self.cfg.telegram_api_url);
+                let body = serde_json::json!({ "chat_id": bridge.target, "text": text });
+                self.post_json(&url, &body).await
+            }
+            BridgeKind::Nostr => {
+                let keypair = self
+                    .nostr_keypair
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("BRIDGE_NOSTR_SECRET_KEY is not set"))?;
+                let event = NostrEvent::sign(
+                    keypair,
+                    chrono::Utc::now().timestamp(),
+                    1,
+                    vec![vec![
+                        "r".into(),
+                        format!("{}/thread/{}", self.cfg.base_url, thread.id),
+                    ]],
+                    text.to_string(),
+                );
+                publish_to_relay(&bridge.target, &event).await
+            }
+        }
+    }
+
+    async fn post_json(&self, url: &str, body: &serde_json::Value) -> anyhow::Result<()> {
+        let response = self.client.post(url).json(body).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            // Do not echo the URL: webhook and bot URLs carry credentials.
+            anyhow::bail!("responded with {status}");
+        }
+        Ok(())
+    }
+
+    /// Spawn the polling loop on the current runtime.
+    pub fn spawn(self) {
+        crate::system::job_started("bridges", self.cfg.poll_interval);
+        actix_web::rt::spawn(async move {
+            loop {
+                self.run_once().await;
+                crate::system::job_ran("bridges");
+                tokio::time::sleep(self.cfg.poll_interval).await;
+            }
+        });
+    }
+}
+
+/// Publish `event` to a relay (NIP-01) and wait for its `OK`.
+async fn publish_to_relay(relay: &str, event: &NostrEvent) -> anyhow::Result<()> {
+    let publish = async {
+        let (mut socket, _) = tokio_tungstenite::connect_async(relay).await?;
+        let message = serde_json::json!(["EVENT", event]).to_string();
+        socket.send(WsMessage::text(message)).await?;
+        while let Some(message) = socket.next().await {
+            let WsMessage::Text(text) = message? else {
+                continue;
+            };
+            let Ok(reply) = serde_json::from_str::<Vec<serde_json::Value>>(text.as_str()) else {
+                continue;
+            };
+            if reply.first().and_then(|v| v.as_str()) == Some("OK")
+                && reply.get(1).and_then(|v| v.as_str()) == Some(event.id.as_str())
+            {
+                let _ = socket.close(None).await;
+                if reply.get(2).and_then(|v| v.as_bool()) == Some(true) {
+                    return Ok(());
+                }
+                let reason = reply.get(3).and_then(|v| v.as_str()).unwrap_or_default();
+                anyhow::bail!("relay rejected the note: {reason}");
+            }
+        }
+        anyhow::bail!("relay closed the connection before acknowledging")
+    };
+    tokio::time::timeout(RELAY_TIMEOUT, publish)
+        .await
+        .map_err(|_| anyhow::anyhow!("relay did not acknowledge in time"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> BridgeConfig {
+        BridgeConfig {
+            enabled: true,
+            poll_interval: Duration::from_secs(1),
+            batch_size: 1,
+            max_per_minute: 1,
+            settle: Duration::ZERO,
+            lease: Duration::from_secs(1),
+            retry: Duration::ZERO,
+            base_url: "https://rib.example".into(),
+            telegram_bot_token: None,
+            telegram_api_url: "https://api.telegram.org".into(),
+            nostr_secret_key: None,
+        }
+    }
+
+    #[test]
+    fn targets_need_the_platform_scheme_and_credentials() {
+        let mut cfg = config();
+        let discord = "https://discord.com/api/webhooks/1/abc";
+        assert!(cfg.validate_target(BridgeKind::Discord, discord).is_ok());
+        assert!(cfg
+            .validate_target(BridgeKind::Discord, "discord.com")
+            .is_err());
+        assert!(cfg
+            .validate_target(BridgeKind::Telegram, "@rib_news")
+            .is_err());
+        cfg.telegram_bot_token = Some("123:abc".into());
+        for chat in ["@rib_news", "-1001234", "42"] {
+            assert!(
+                cfg.validate_target(BridgeKind::Telegram, chat).is_ok(),
+                "{chat}"
+            );
+        }
+        assert!(cfg.validate_target(BridgeKind::Telegram, "@").is_err());
+        assert!(cfg
+            .validate_target(BridgeKind::Nostr, "wss://relay.example")
+            .is_err());
+        cfg.nostr_secret_key = Some(hex::encode([7u8; 32]));
+        assert!(cfg
+            .validate_target(BridgeKind::Nostr, "wss://relay.example")
+            .is_ok());
+        assert!(cfg.validate_target(BridgeKind::Nostr, discord).is_err());
+    }
+}
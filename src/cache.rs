@@ -0,0 +1,107 @@
+use std::sync::{Arc, Mutex};
+
+use crate::models::Board;
+
+#[derive(Default)]
+struct BoardCacheState {
+    active: bool,
+    generation: u64,
+    boards: Option<Vec<Board>>,
+}
+
+/// Per-replica cache of the public board list.
+///
+/// Only serves entries while a change listener is connected (see
+/// `notify::ChangeListener`); otherwise every read goes to the database.
+#[derive(Clone, Default)]
+pub struct BoardCache {
+    state: Arc<Mutex<BoardCacheState>>,
+}
+
+impl BoardCache {
+    /// Whether entries are kept, i.e. a change listener is connected.
+    pub fn is_active(&self) -> bool {
+        self.state.lock().unwrap().active
+    }
+
+    pub fn get(&self) -> Option<Vec<Board>> {
+        let state = self.state.lock().unwrap();
+        if state.active {
+            state.boards.clone()
+        } else {
+            None
+        }
+    }
+
+    /// Generation to pass to [`BoardCache::store`] after loading from the database.
+    pub fn generation(&self) -> u64 {
+        self.state.lock().unwrap().generation
+    }
+
+    /// Store a freshly loaded list unless an invalidation happened meanwhile.
+    pub fn store(&self, generation: u64, boards: Vec<Board>) {
+        let mut state = self.state.lock().unwrap();
+        if state.active && state.generation == generation {
+            state.boards = Some(boards);
+        }
+    }
+
+    pub fn invalidate(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.generation = state.generation.wrapping_add(1);
+        state.boards = None;
+    }
+
+    /// Enable or disable caching; entries are dropped on every transition.
+    pub fn set_active(&self, active: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.active = active;
+        state.generation = state.generation.wrapping_add(1);
+        state.boards = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board() -> Board {
+        Board {
+            id: 1,
+            slug: "b".into(),
+            title: "Random".into(),
+            created_at: chrono::Utc::now(),
+            deleted_at: None,
+            anonymous_posting: false,
+            op_moderation: false,
+            max_threads: 0,
+            prune_overflow: false,
+            reply_cooldown_secs: 0,
+            reactions: Vec::new(),
+            tag_vocabulary: Vec::new(),
+            allowed_mime: Vec::new(),
+            tombstones: false,
+            beta: false,
+        }
+    }
+
+    #[test]
+    fn inactive_cache_never_serves() {
+        let cache = BoardCache::default();
+        cache.store(cache.generation(), vec![board()]);
+        assert!(cache.get().is_none());
+    }
+
+    #[test]
+    fn invalidation_discards_in_flight_loads() {
+        let cache = BoardCache::default();
+        cache.set_active(true);
+        let generation = cache.generation();
+        cache.invalidate();
+        cache.store(generation, vec![board()]);
+        assert!(cache.get().is_none());
+
+        cache.store(cache.generation(), vec![board()]);
+        assert_eq!(cache.get().map(|b| b.len()), Some(1));
+    }
+}
@@ -0,0 +1,382 @@
+//! Short-lived, single-use values shared by the sign-in and anti-abuse flows.
+//!
+//! A [`ChallengeStore`] keeps string values under a namespace and key until
+//! their TTL runs out: Bitcoin sign-in challenges (`btc`), redeemed proof of
+//! work challenges (`pow`), used Discord OAuth states (`oauth_state`) and used
+//! magic-link nonces (`email_login`). Expired entries are never returned and
+//! are dropped without a sweeper: Redis expires keys itself, Postgres deletes
+//! expired rows as it writes and the in-memory store prunes as it is used.
+//!
+//! `CHALLENGE_STORE` picks the backend: `memory` (the default; one replica
+//! only, since other replicas cannot see its entries), `postgres` (the
+//! `challenges` table) or `redis` (`CHALLENGE_REDIS_URL`, falling back to
+//! `LIVE_REDIS_URL`).
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[async_trait]
+pub trait ChallengeStore: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Store `value` for `ttl`, replacing any previous value under the key.
+    async fn put(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<()>;
+
+    /// Remove the value under the key and return it if it had not expired.
+    async fn take(&self, namespace: &str, key: &str) -> anyhow::Result<Option<String>>;
+
+    /// Store `value` for `ttl` unless an unexpired value is already there;
+    /// `false` means the key was taken, e.g. a one-time token being replayed.
+    async fn insert_new(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<bool>;
+}
+
+#[derive(Clone, Debug)]
+pub struct ChallengeStoreConfig {
+    pub backend: Option<String>,
+    pub redis_url: Option<String>,
+}
+
+impl ChallengeStoreConfig {
+    pub fn from_env() -> Self {
+        fn opt_env(name: &str) -> Option<String> {
+            std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+        }
+        Self {
+            backend: opt_env("CHALLENGE_STORE").map(|v| v.to_lowercase()),
+            redis_url: opt_env("CHALLENGE_REDIS_URL").or_else(|| opt_env("LIVE_REDIS_URL")),
+        }
+    }
+
+    pub fn build(&self, pool: &PgPool) -> anyhow::Result<Arc<dyn ChallengeStore>> {
+        Ok(match self.backend.as_deref() {
+            None | Some("memory") => MemoryChallengeStore::shared(),
+            Some("postgres") => Arc::new(PgChallengeStore::new(pool.clone())),
+            Some("redis") => {
+                let url = self.redis_url.as_deref().ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "CHALLENGE_REDIS_URL or LIVE_REDIS_URL must be set when CHALLENGE_STORE=redis"
+                    )
+                })?;
+                Arc::new(RedisChallengeStore::new(url)?)
+            }
+            Some(other) => anyhow::bail!("unsupported CHALLENGE_STORE '{other}'"),
+        })
+    }
+}
+
+/// Writes between sweeps of expired entries.
+const SWEEP_EVERY: usize = 256;
+
+/// Process-local store; entries vanish on restart.
+#[derive(Default)]
+pub struct MemoryChallengeStore {
+    entries: DashMap<(String, String), (String, Instant)>,
+    writes: AtomicUsize,
+}
+
+static SHARED_MEMORY_STORE: Lazy<Arc<MemoryChallengeStore>> = Lazy::new(Arc::default);
+
+impl MemoryChallengeStore {
+    /// The process-wide instance, so every state built without a configured
+    /// backend sees the same entries.
+    pub fn shared() -> Arc<dyn ChallengeStore> {
+        SHARED_MEMORY_STORE.clone()
+    }
+
+    fn sweep(&self) {
+        if self
+            .writes
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(SWEEP_EVERY)
+        {
+            let now = Instant::now();
+            self.entries.retain(|_, (_, expires)| *expires > now);
+        }
+    }
+}
+
+fn entry_key(namespace: &str, key: &str) -> (String, String) {
+    (namespace.to_string(), key.to_string())
+}
+
+#[async_trait]
+impl ChallengeStore for MemoryChallengeStore {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn put(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<()> {
+        self.sweep();
+        self.entries.insert(
+            entry_key(namespace, key),
+            (value.to_string(), Instant::now() + ttl),
+        );
+        Ok(())
+    }
+
+    async fn take(&self, namespace: &str, key: &str) -> anyhow::Result<Option<String>> {
+        Ok(self
+            .entries
+            .remove(&entry_key(namespace, key))
+            .filter(|(_, (_, expires))| *expires > Instant::now())
+            .map(|(_, (value, _))| value))
+    }
+
+    async fn insert_new(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<bool> {
+        self.sweep();
+        let now = Instant::now();
+        let mut inserted = false;
+        self.entries
+            .entry(entry_key(namespace, key))
+            .and_modify(|(old, expires)| {
+                if *expires <= now {
+                    *old = value.to_string();
+                    *expires = now + ttl;
+                    inserted = true;
+                }
+            })
+            .or_insert_with(|| {
+                inserted = true;
+                (value.to_string(), now + ttl)
+            });
+        Ok(inserted)
+    }
+}
+
+/// Rows in `challenges`, shared by every replica on the database.
+pub struct PgChallengeStore {
+    pool: PgPool,
+    writes: AtomicUsize,
+}
+
+impl PgChallengeStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            writes: AtomicUsize::new(0),
+        }
+    }
+
+    async fn sweep(&self) -> anyhow::Result<()> {
+        if self
+            .writes
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(SWEEP_EVERY)
+        {
+            sqlx::query!("DELETE FROM challenges WHERE expires_at <= now()")
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChallengeStore for PgChallengeStore {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn put(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<()> {
+        self.sweep().await?;
+        sqlx::query!(
+            "INSERT INTO challenges (namespace, key, value, expires_at)
+             VALUES ($1, $2, $3, now() + make_interval(secs => $4))
+             ON CONFLICT (namespace, key)
+             DO UPDATE SET value = EXCLUDED.value, expires_at = EXCLUDED.expires_at",
+            namespace,
+            key,
+            value,
+            ttl.as_secs_f64()
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn take(&self, namespace: &str, key: &str) -> anyhow::Result<Option<String>> {
+        let row = sqlx::query!(
+            r#"DELETE FROM challenges WHERE namespace = $1 AND key = $2
+               RETURNING value, expires_at > now() as "live!""#,
+            namespace,
+            key
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.and_then(|row| row.live.then_some(row.value)))
+    }
+
+    async fn insert_new(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<bool> {
+        self.sweep().await?;
+        let inserted = sqlx::query!(
+            "INSERT INTO challenges (namespace, key, value, expires_at)
+             VALUES ($1, $2, $3, now() + make_interval(secs => $4))
+             ON CONFLICT (namespace, key)
+             DO UPDATE SET value = EXCLUDED.value, expires_at = EXCLUDED.expires_at
+             WHERE challenges.expires_at <= now()",
+            namespace,
+            key,
+            value,
+            ttl.as_secs_f64()
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        Ok(inserted == 1)
+    }
+}
+
+/// Keys `rib:challenge:{namespace}:{key}` with Redis-native expiry.
+pub struct RedisChallengeStore {
+    client: redis::Client,
+    connection: tokio::sync::OnceCell<redis::aio::MultiplexedConnection>,
+}
+
+impl RedisChallengeStore {
+    pub fn new(url: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: redis::Client::open(url)?,
+            connection: tokio::sync::OnceCell::new(),
+        })
+    }
+
+    async fn connection(&self) -> anyhow::Result<redis::aio::MultiplexedConnection> {
+        Ok(self
+            .connection
+            .get_or_try_init(|| self.client.get_multiplexed_async_connection())
+            .await?
+            .clone())
+    }
+}
+
+fn redis_key(namespace: &str, key: &str) -> String {
+    format!("rib:challenge:{namespace}:{key}")
+}
+
+/// Redis rejects a zero expiry; round sub-millisecond TTLs up.
+fn ttl_millis(ttl: Duration) -> u64 {
+    (ttl.as_millis() as u64).max(1)
+}
+
+#[async_trait]
+impl ChallengeStore for RedisChallengeStore {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn put(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<()> {
+        redis::cmd("SET")
+            .arg(redis_key(namespace, key))
+            .arg(value)
+            .arg("PX")
+            .arg(ttl_millis(ttl))
+            .query_async::<()>(&mut self.connection().await?)
+            .await?;
+        Ok(())
+    }
+
+    async fn take(&self, namespace: &str, key: &str) -> anyhow::Result<Option<String>> {
+        Ok(redis::cmd("GETDEL")
+            .arg(redis_key(namespace, key))
+            .query_async::<Option<String>>(&mut self.connection().await?)
+            .await?)
+    }
+
+    async fn insert_new(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<bool> {
+        let reply = redis::cmd("SET")
+            .arg(redis_key(namespace, key))
+            .arg(value)
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl_millis(ttl))
+            .query_async::<Option<String>>(&mut self.connection().await?)
+            .await?;
+        Ok(reply.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn memory_entries_are_single_use_and_expire() {
+        let store = MemoryChallengeStore::default();
+        let ttl = Duration::from_secs(60);
+        store.put("btc", "addr", "first", ttl).await.unwrap();
+        store.put("btc", "addr", "second", ttl).await.unwrap();
+        assert_eq!(
+            store.take("btc", "addr").await.unwrap().as_deref(),
+            Some("second")
+        );
+        assert_eq!(store.take("btc", "addr").await.unwrap(), None);
+
+        assert!(store.insert_new("pow", "c", "1", ttl).await.unwrap());
+        assert!(!store.insert_new("pow", "c", "1", ttl).await.unwrap());
+        assert!(store
+            .insert_new("oauth_state", "c", "1", ttl)
+            .await
+            .unwrap());
+
+        store.put("btc", "gone", "v", Duration::ZERO).await.unwrap();
+        assert_eq!(store.take("btc", "gone").await.unwrap(), None);
+        assert!(store
+            .insert_new("pow", "old", "1", Duration::ZERO)
+            .await
+            .unwrap());
+        assert!(store.insert_new("pow", "old", "1", ttl).await.unwrap());
+    }
+}
@@ -0,0 +1,458 @@
+//! `rib check-config`: validate the configuration without serving.
+//!
+//! Every check runs, even after a failure, and the report lists each one as
+//! `ok`, `warn` or `FAIL` with what it found. The command exits non-zero when
+//! any check fails, so a CI/CD step can stop a bad rollout before the first
+//! request. Checks read secrets but never print them.
+//!
+//! Besides the static checks (secrets, OAuth, CORS, and every setting the
+//! server refuses to start with), the database is connected to and its schema
+//! compared with the migrations, a read replica is connected to when one is
+//! configured, and the image store bucket is looked up without being created.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::access_policy::{AccessPolicy, PolicyConfig};
+use crate::challenges::ChallengeStoreConfig;
+use crate::db::{MigrationStatus, PoolConfig, MIGRATOR};
+use crate::live::LiveConfig;
+use crate::mailer::MailerConfig;
+use crate::outbox::OutboxConfig;
+use crate::previews::PreviewConfig;
+use crate::repo::pg::PgRepo;
+use crate::repo::SchemaRepo;
+use crate::search::SearchConfig;
+use crate::storage::{ImageStore, S3ImageStore};
+
+/// Shortest `JWT_SECRET` and `TRIPCODE_SECRET` accepted.
+const MIN_SECRET_LEN: usize = 32;
+
+/// How long the network checks wait before giving up.
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    fn push(&mut self, name: &'static str, status: Status, detail: impl Into<String>) {
+        self.checks.push(Check {
+            name,
+            status,
+            detail: detail.into(),
+        });
+    }
+
+    pub fn failed(&self) -> bool {
+        self.checks.iter().any(|c| c.status == Status::Fail)
+    }
+
+    fn count(&self, status: Status) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        for check in &self.checks {
+            writeln!(
+                f,
+                "{:<4}  {:<width$}  {}",
+                check.status.label(),
+                check.name,
+                check.detail
+            )?;
+        }
+        write!(
+            f,
+            "{} ok, {} warnings, {} failed",
+            self.count(Status::Ok),
+            self.count(Status::Warn),
+            self.count(Status::Fail)
+        )
+    }
+}
+
+/// Run every check, including the ones that need the network.
+pub async fn check_all() -> Report {
+    let mut report = check_static();
+    check_database(&mut report).await;
+    check_image_store(&mut report).await;
+    report
+}
+
+/// Checks that only read the environment.
+pub fn check_static() -> Report {
+    let mut report = Report::default();
+    check_secret(&mut report, "jwt_secret", "JWT_SECRET", true);
+    check_secret(
+        &mut report,
+        "tripcode_secret",
+        "TRIPCODE_SECRET",
+        !cfg!(debug_assertions),
+    );
+    check_oauth(&mut report);
+    check_cors(&mut report);
+    check_services(&mut report);
+    report
+}
+
+fn check_secret(report: &mut Report, name: &'static str, var: &str, required: bool) {
+    let Some(secret) = std::env::var(var).ok().filter(|v| !v.is_empty()) else {
+        if required {
+            report.push(name, Status::Fail, format!("{var} is not set"));
+        } else {
+            report.push(name, Status::Warn, format!("{var} is not set"));
+        }
+        return;
+    };
+    if secret.starts_with("CHANGE_ME") {
+        report.push(
+            name,
+            Status::Fail,
+            format!("{var} is still the .env.example placeholder"),
+        );
+    } else if secret.len() < MIN_SECRET_LEN {
+        report.push(
+            name,
+            Status::Fail,
+            format!(
+                "{var} is {} characters; at least {MIN_SECRET_LEN} required",
+                secret.len()
+            ),
+        );
+    } else if distinct_chars(&secret) < 10 {
+        report.push(
+            name,
+            Status::Warn,
+            format!("{var} repeats few characters; generate it randomly"),
+        );
+    } else {
+        report.push(name, Status::Ok, format!("{var} set"));
+    }
+}
+
+fn distinct_chars(value: &str) -> usize {
+    let mut chars: Vec<char> = value.chars().collect();
+    chars.sort_unstable();
+    chars.dedup();
+    chars.len()
+}
+
+fn check_oauth(report: &mut Report) {
+    let set = |var: &str| std::env::var(var).is_ok_and(|v| !v.trim().is_empty());
+    let (id, secret) = (set("DISCORD_CLIENT_ID"), set("DISCORD_CLIENT_SECRET"));
+    match (id, secret) {
+        (false, false) => report.push(
+            "discord_oauth",
+            Status::Warn,
+            "not configured; Discord login is disabled",
+        ),
+        (true, false) | (false, true) => report.push(
+            "discord_oauth",
+            Status::Fail,
+            "DISCORD_CLIENT_ID and DISCORD_CLIENT_SECRET must be set together",
+        ),
+        (true, true) => match std::env::var("DISCORD_REDIRECT_URI") {
+            Err(_) => report.push(
+                "discord_oauth",
+                Status::Warn,
+                "DISCORD_REDIRECT_URI is not set; the localhost default will be used",
+            ),
+            Ok(uri) => match reqwest::Url::parse(&uri) {
+                Ok(url) if url.path().ends_with("/auth/discord/callback") => {
+                    report.push("discord_oauth", Status::Ok, format!("redirects to {uri}"))
+                }
+                Ok(_) => report.push(
+                    "discord_oauth",
+                    Status::Warn,
+                    format!(
+                        "DISCORD_REDIRECT_URI {uri} is not the /api/v1/auth/discord/callback route"
+                    ),
+                ),
+                Err(e) => report.push(
+                    "discord_oauth",
+                    Status::Fail,
+                    format!("DISCORD_REDIRECT_URI is not a URL: {e}"),
+                ),
+            },
+        },
+    }
+}
+
+fn check_cors(report: &mut Report) {
+    let frontend = std::env::var("FRONTEND_URL").ok();
+    let extra = std::env::var("CORS_ALLOWED_ORIGINS").unwrap_or_default();
+    let origins: Vec<String> = frontend
+        .into_iter()
+        .chain(extra.split(',').map(str::to_string))
+        .map(|origin| origin.trim().to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect();
+    if origins.is_empty() {
+        report.push(
+            "cors",
+            Status::Warn,
+            "FRONTEND_URL is not set; only local development origins are allowed",
+        );
+        return;
+    }
+    let mut problems = Vec::new();
+    let mut warnings = Vec::new();
+    for origin in &origins {
+        match origin_problem(origin) {
+            Some(problem) => problems.push(format!("{origin}: {problem}")),
+            None if origin.starts_with("http://") && !is_local_origin(origin) => {
+                warnings.push(format!("{origin} is not HTTPS"))
+            }
+            None => {}
+        }
+    }
+    if !problems.is_empty() {
+        report.push("cors", Status::Fail, problems.join("; "));
+    } else if !warnings.is_empty() {
+        report.push("cors", Status::Warn, warnings.join("; "));
+    } else {
+        report.push("cors", Status::Ok, format!("allows {}", origins.join(", ")));
+    }
+}
+
+/// Why `origin` cannot be matched against a browser's `Origin` header.
+fn origin_problem(origin: &str) -> Option<&'static str> {
+    if origin == "*" {
+        return Some("wildcards cannot be used with credentials");
+    }
+    let Ok(url) = reqwest::Url::parse(origin) else {
+        return Some("not a URL");
+    };
+    if !matches!(url.scheme(), "http" | "https") {
+        return Some("scheme must be http or https");
+    }
+    if url.host_str().is_none() {
+        return Some("no host");
+    }
+    if url.path() != "/" || url.query().is_some() {
+        return Some("origins have no path or query");
+    }
+    None
+}
+
+fn is_local_origin(origin: &str) -> bool {
+    reqwest::Url::parse(origin).is_ok_and(|url| {
+        matches!(
+            url.host_str(),
+            Some("localhost") | Some("127.0.0.1") | Some("[::1]")
+        )
+    })
+}
+
+/// Settings the server parses at startup and refuses to start without.
+fn check_services(report: &mut Report) {
+    match SearchConfig::from_env().build() {
+        Ok(Some(backend)) => report.push("search", Status::Ok, backend.name()),
+        Ok(None) => report.push("search", Status::Ok, "Postgres full-text search"),
+        Err(e) => report.push("search", Status::Fail, e.to_string()),
+    }
+    match MailerConfig::from_env().build() {
+        Ok(Some(mailer)) => report.push("mailer", Status::Ok, mailer.name()),
+        Ok(None) => report.push("mailer", Status::Ok, "email login disabled"),
+        Err(e) => report.push("mailer", Status::Fail, e.to_string()),
+    }
+    match PreviewConfig::from_env().build() {
+        Ok(Some(renderer)) => report.push("previews", Status::Ok, renderer.name()),
+        Ok(None) => report.push("previews", Status::Ok, "document thumbnails disabled"),
+        Err(e) => report.push("previews", Status::Fail, e.to_string()),
+    }
+    let outbox = OutboxConfig::from_env();
+    if outbox.enabled {
+        match outbox.sinks() {
+            Ok(sinks) => report.push(
+                "outbox",
+                Status::Ok,
+                format!("{} configured sink(s)", sinks.len()),
+            ),
+            Err(e) => report.push("outbox", Status::Fail, e.to_string()),
+        }
+    } else {
+        report.push("outbox", Status::Ok, "relay disabled");
+    }
+    match crate::bridges::BridgeConfig::from_env().nostr_keypair() {
+        Ok(Some(_)) => report.push("bridges", Status::Ok, "Nostr signing key loaded"),
+        Ok(None) => report.push("bridges", Status::Ok, "no Nostr signing key"),
+        Err(e) => report.push("bridges", Status::Fail, e.to_string()),
+    }
+    match crate::activitypub::FederationConfig::from_env().build() {
+        Ok(Some(_)) => report.push("activitypub", Status::Ok, "signing key loaded"),
+        Ok(None) => report.push("activitypub", Status::Ok, "disabled"),
+        Err(e) => report.push("activitypub", Status::Fail, e.to_string()),
+    }
+    match crate::ethereum::EthConfig::from_env().validate() {
+        Ok(()) => report.push("ethereum", Status::Ok, "configured"),
+        Err(e) => report.push("ethereum", Status::Fail, e),
+    }
+    match crate::routes::btc_network() {
+        Ok(network) => report.push("bitcoin_network", Status::Ok, network.to_string()),
+        Err(e) => report.push("bitcoin_network", Status::Fail, e),
+    }
+    match AccessPolicy::new(PolicyConfig::from_env()) {
+        Ok(_) => report.push("access_policy", Status::Ok, "loaded"),
+        Err(e) => report.push("access_policy", Status::Fail, e),
+    }
+}
+
+async fn check_database(report: &mut Report) {
+    let Ok(url) = std::env::var("DATABASE_URL") else {
+        report.push("database", Status::Fail, "DATABASE_URL is not set");
+        return;
+    };
+    let pool_cfg = PoolConfig {
+        acquire_timeout: NETWORK_TIMEOUT,
+        ..PoolConfig::from_env()
+    };
+    let pool = match pool_cfg.connect(&url).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            report.push("database", Status::Fail, format!("cannot connect: {e}"));
+            return;
+        }
+    };
+    report.push("database", Status::Ok, "connected");
+    let repo = PgRepo::new(pool.clone());
+    match repo.applied_migrations().await {
+        Ok(applied) => {
+            let status = MigrationStatus::compare(&MIGRATOR, &applied);
+            if status.up_to_date {
+                report.push(
+                    "migrations",
+                    Status::Ok,
+                    format!("schema at version {:?}", status.schema_version),
+                );
+            } else {
+                let pending = status.pending.len();
+                let detail = format!(
+                    "{pending} pending (schema {:?}, latest {:?})",
+                    status.schema_version, status.latest_version
+                );
+                if crate::db::auto_migrate_enabled() {
+                    report.push(
+                        "migrations",
+                        Status::Ok,
+                        format!("{detail}; applied at startup"),
+                    );
+                } else {
+                    report.push(
+                        "migrations",
+                        Status::Fail,
+                        format!("{detail} and DB_AUTO_MIGRATE is off"),
+                    );
+                }
+            }
+        }
+        Err(e) => report.push("migrations", Status::Fail, e.to_string()),
+    }
+    match LiveConfig::from_env().build(&pool) {
+        Ok(Some(bus)) => report.push("live", Status::Ok, bus.name()),
+        Ok(None) => report.push("live", Status::Ok, "single replica"),
+        Err(e) => report.push("live", Status::Fail, e.to_string()),
+    }
+    match ChallengeStoreConfig::from_env().build(&pool) {
+        Ok(store) => report.push("challenges", Status::Ok, store.name()),
+        Err(e) => report.push("challenges", Status::Fail, e.to_string()),
+    }
+    pool.close().await;
+
+    if let Some(read_url) = std::env::var("DATABASE_READ_URL")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+    {
+        match pool_cfg.connect(&read_url).await {
+            Ok(read_pool) => {
+                report.push("read_replica", Status::Ok, "connected");
+                read_pool.close().await;
+            }
+            Err(e) => report.push("read_replica", Status::Fail, format!("cannot connect: {e}")),
+        }
+    }
+}
+
+async fn check_image_store(report: &mut Report) {
+    let store = match S3ImageStore::connect().await {
+        Ok(store) => store,
+        Err(e) => {
+            report.push("image_store", Status::Fail, e.to_string());
+            return;
+        }
+    };
+    match tokio::time::timeout(NETWORK_TIMEOUT, store.check()).await {
+        Ok(Ok(())) => report.push("image_store", Status::Ok, store.describe()),
+        // SDK errors carry the whole provider chain; the first line says enough.
+        Ok(Err(e)) => report.push(
+            "image_store",
+            Status::Fail,
+            e.to_string().lines().next().unwrap_or_default(),
+        ),
+        Err(_) => report.push(
+            "image_store",
+            Status::Fail,
+            format!(
+                "{} did not answer within {NETWORK_TIMEOUT:?}",
+                store.describe()
+            ),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn origins_must_be_bare_http_origins() {
+        assert_eq!(origin_problem("https://rib.example"), None);
+        assert_eq!(origin_problem("http://localhost:5173"), None);
+        assert!(origin_problem("*").is_some());
+        assert!(origin_problem("rib.example").is_some());
+        assert!(origin_problem("ftp://rib.example").is_some());
+        assert!(origin_problem("https://rib.example/app").is_some());
+    }
+
+    #[test]
+    fn report_fails_when_any_check_fails() {
+        let mut report = Report::default();
+        report.push("a", Status::Ok, "fine");
+        report.push("b", Status::Warn, "hmm");
+        assert!(!report.failed());
+        report.push("c", Status::Fail, "broken");
+        assert!(report.failed());
+        let text = report.to_string();
+        assert!(text.contains("FAIL  c  broken"));
+        assert!(text.ends_with("1 ok, 1 warnings, 1 failed"));
+    }
+}
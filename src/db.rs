@@ -0,0 +1,292 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::migrate::Migrator;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::PgPool;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Connection pool sizing and timeouts derived from env.
+#[derive(Clone, Debug)]
+pub struct PoolConfig {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout: Duration,
+    pub idle_timeout: Option<Duration>,
+    pub statement_timeout: Option<Duration>,
+    pub metrics_interval: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(5),
+            idle_timeout: Some(Duration::from_secs(600)),
+            statement_timeout: None,
+            metrics_interval: Duration::from_secs(15),
+        }
+    }
+}
+
+impl PoolConfig {
+    pub fn from_env() -> Self {
+        fn u64_env(name: &str) -> Option<u64> {
+            std::env::var(name).ok().and_then(|v| v.parse().ok())
+        }
+        let defaults = Self::default();
+        let max_connections = u64_env("DB_MAX_CONNECTIONS")
+            .map(|v| v.clamp(1, u32::MAX as u64) as u32)
+            .unwrap_or(defaults.max_connections);
+        Self {
+            max_connections,
+            min_connections: u64_env("DB_MIN_CONNECTIONS")
+                .map(|v| (v as u32).min(max_connections))
+                .unwrap_or(defaults.min_connections),
+            acquire_timeout: u64_env("DB_ACQUIRE_TIMEOUT_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.acquire_timeout),
+            // 0 disables idle reaping.
+            idle_timeout: match u64_env("DB_IDLE_TIMEOUT_SECS") {
+                Some(0) => None,
+                Some(secs) => Some(Duration::from_secs(secs)),
+                None => defaults.idle_timeout,
+            },
+            statement_timeout: u64_env("DB_STATEMENT_TIMEOUT_MS")
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis),
+            metrics_interval: u64_env("DB_POOL_METRICS_INTERVAL_SECS")
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.metrics_interval),
+        }
+    }
+
+    fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(self.acquire_timeout)
+            .idle_timeout(self.idle_timeout)
+    }
+
+    fn connect_options(&self, url: &str) -> Result<PgConnectOptions, sqlx::Error> {
+        let mut options = PgConnectOptions::from_str(url)?;
+        if let Some(timeout) = self.statement_timeout {
+            // Applied server-side to every statement on every pooled connection.
+            options = options.options([("statement_timeout", timeout.as_millis().to_string())]);
+        }
+        Ok(options)
+    }
+
+    pub async fn connect(&self, url: &str) -> Result<PgPool, sqlx::Error> {
+        self.pool_options()
+            .connect_with(self.connect_options(url)?)
+            .await
+    }
+
+    /// Build a pool that connects on first use, so an unavailable server
+    /// (e.g. a read replica) does not block startup.
+    pub fn connect_lazy(&self, url: &str) -> Result<PgPool, sqlx::Error> {
+        Ok(self
+            .pool_options()
+            .connect_lazy_with(self.connect_options(url)?))
+    }
+}
+
+/// Record pool occupancy gauges and a probe acquire latency for one pool.
+pub async fn sample_pool_metrics(pool: &PgPool, name: &'static str) {
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+    metrics::gauge!("db_pool_connections", size as f64, "pool" => name);
+    metrics::gauge!("db_pool_idle", idle as f64, "pool" => name);
+    metrics::gauge!("db_pool_in_use", size.saturating_sub(idle) as f64, "pool" => name);
+    metrics::gauge!(
+        "db_pool_max_connections",
+        pool.options().get_max_connections() as f64,
+        "pool" => name
+    );
+    // The probe waits in the same queue as requests, so its latency tracks
+    // what handlers see when the pool is saturated.
+    let started = Instant::now();
+    match pool.acquire().await {
+        Ok(conn) => {
+            metrics::histogram!(
+                "db_pool_acquire_seconds",
+                started.elapsed().as_secs_f64(),
+                "pool" => name
+            );
+            drop(conn);
+        }
+        Err(e) => {
+            metrics::increment_counter!("db_pool_acquire_failed", "pool" => name);
+            log::warn!("{name} pool acquire probe failed: {e}");
+        }
+    }
+}
+
+/// Periodically sample pool metrics until the task is aborted.
+pub fn spawn_pool_metrics(pool: PgPool, name: &'static str, interval: Duration) -> JoinHandle<()> {
+    actix_web::rt::spawn(async move {
+        loop {
+            sample_pool_metrics(&pool, name).await;
+            tokio::time::sleep(interval).await;
+        }
+    })
+}
+
+/// Migrations embedded in this binary from `./migrations`.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Whether startup should apply pending migrations (`DB_AUTO_MIGRATE`, default on).
+/// When off, the server refuses to boot unless the schema is already current.
+pub fn auto_migrate_enabled() -> bool {
+    std::env::var("DB_AUTO_MIGRATE")
+        .map(|v| !(v == "0" || v.eq_ignore_ascii_case("false")))
+        .unwrap_or(true)
+}
+
+/// One row of sqlx's `_sqlx_migrations` bookkeeping table.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub description: String,
+    pub installed_on: DateTime<Utc>,
+    pub success: bool,
+    pub checksum: Vec<u8>,
+    pub execution_time_ns: i64,
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct MigrationEntry {
+    pub version: i64,
+    pub description: String,
+    /// Unset for pending migrations.
+    pub installed_on: Option<DateTime<Utc>>,
+    pub execution_ms: Option<i64>,
+    /// False when the migration failed part-way; sqlx refuses to continue past it.
+    pub success: Option<bool>,
+    /// False when the file in this build differs from what was applied.
+    pub checksum_matches: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct MigrationStatus {
+    /// Highest successfully applied version.
+    pub schema_version: Option<i64>,
+    /// Highest version embedded in this build.
+    pub latest_version: Option<i64>,
+    /// No pending, failed, or modified migrations.
+    pub up_to_date: bool,
+    pub auto_migrate: bool,
+    pub applied: Vec<MigrationEntry>,
+    pub pending: Vec<MigrationEntry>,
+    /// Applied versions this build does not know about, e.g. after a rollback.
+    pub unknown: Vec<MigrationEntry>,
+}
+
+impl MigrationStatus {
+    /// Compare the embedded migrations against what the database recorded.
+    pub fn compare(migrator: &Migrator, applied: &[AppliedMigration]) -> Self {
+        let known: Vec<_> = migrator
+            .iter()
+            .filter(|m| !m.migration_type.is_down_migration())
+            .collect();
+        let mut status = MigrationStatus {
+            schema_version: applied
+                .iter()
+                .filter(|a| a.success)
+                .map(|a| a.version)
+                .max(),
+            latest_version: known.iter().map(|m| m.version).max(),
+            up_to_date: true,
+            auto_migrate: auto_migrate_enabled(),
+            applied: Vec::new(),
+            pending: Vec::new(),
+            unknown: Vec::new(),
+        };
+        for m in &known {
+            match applied.iter().find(|a| a.version == m.version) {
+                Some(a) => {
+                    let checksum_matches = *a.checksum == *m.checksum;
+                    status.up_to_date &= a.success && checksum_matches;
+                    status.applied.push(MigrationEntry {
+                        version: a.version,
+                        description: a.description.clone(),
+                        installed_on: Some(a.installed_on),
+                        execution_ms: Some(a.execution_time_ns / 1_000_000),
+                        success: Some(a.success),
+                        checksum_matches: Some(checksum_matches),
+                    });
+                }
+                None => {
+                    status.up_to_date = false;
+                    status.pending.push(MigrationEntry {
+                        version: m.version,
+                        description: m.description.to_string(),
+                        installed_on: None,
+                        execution_ms: None,
+                        success: None,
+                        checksum_matches: None,
+                    });
+                }
+            }
+        }
+        for a in applied {
+            if !known.iter().any(|m| m.version == a.version) {
+                status.unknown.push(MigrationEntry {
+                    version: a.version,
+                    description: a.description.clone(),
+                    installed_on: Some(a.installed_on),
+                    execution_ms: Some(a.execution_time_ns / 1_000_000),
+                    success: Some(a.success),
+                    checksum_matches: None,
+                });
+            }
+        }
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied(version: i64, checksum: &[u8]) -> AppliedMigration {
+        AppliedMigration {
+            version,
+            description: "test".into(),
+            installed_on: Utc::now(),
+            success: true,
+            checksum: checksum.to_vec(),
+            execution_time_ns: 2_000_000,
+        }
+    }
+
+    #[test]
+    fn compares_applied_against_embedded_migrations() {
+        let empty = MigrationStatus::compare(&MIGRATOR, &[]);
+        assert!(!empty.up_to_date);
+        assert_eq!(empty.schema_version, None);
+        assert_eq!(empty.pending.len(), MIGRATOR.iter().count());
+
+        let all: Vec<_> = MIGRATOR
+            .iter()
+            .map(|m| applied(m.version, &m.checksum))
+            .collect();
+        let current = MigrationStatus::compare(&MIGRATOR, &all);
+        assert!(current.up_to_date);
+        assert_eq!(current.schema_version, current.latest_version);
+        assert_eq!(current.applied[0].execution_ms, Some(2));
+
+        let mut modified = all.clone();
+        modified[0].checksum = vec![0];
+        modified.push(applied(i64::MAX, b"future"));
+        let drifted = MigrationStatus::compare(&MIGRATOR, &modified);
+        assert!(!drifted.up_to_date);
+        assert_eq!(drifted.applied[0].checksum_matches, Some(false));
+        assert_eq!(drifted.unknown.len(), 1);
+    }
+}
@@ -0,0 +1,344 @@
+//! Email digests of new replies in watched threads and new saved search
+//! matches.
+//!
+//! Subscribers choose `immediate` (one mail per polling pass) or `daily`
+//! delivery and confirm their address first. Every digest carries a signed
+//! one-click unsubscribe link.
+
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::{create_notification_token, NotificationAction};
+use crate::mailer::{Email, Mailer};
+use crate::models::{DigestEntry, DigestFrequency, Id, SearchDigestEntry};
+use crate::repo::Repo;
+
+/// Unsubscribe links keep working for this long after the digest was sent.
+pub const UNSUBSCRIBE_LINK_TTL: Duration = Duration::from_secs(365 * 24 * 3600);
+/// Confirmation links for a new digest address.
+pub const CONFIRM_LINK_TTL: Duration = Duration::from_secs(7 * 24 * 3600);
+
+const DAILY_SECS: i64 = 24 * 3600;
+/// Replies, and separately saved search matches, listed per digest; the rest
+/// follow in the next one.
+const ENTRIES_PER_DIGEST: i64 = 100;
+
+#[derive(Clone, Debug)]
+pub struct DigestConfig {
+    pub enabled: bool,
+    pub poll_interval: Duration,
+    pub batch_size: i64,
+    /// Public origin used in links, e.g. `https://rib.example`.
+    pub base_url: String,
+}
+
+impl DigestConfig {
+    pub fn from_env() -> Self {
+        fn u64_env(name: &str, default: u64) -> u64 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        Self {
+            enabled: std::env::var("DIGESTS_ENABLED")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(true),
+            poll_interval: Duration::from_secs(u64_env("DIGEST_POLL_SECS", 60).max(1)),
+            batch_size: u64_env("DIGEST_BATCH_SIZE", 50) as i64,
+            base_url: public_base_url(),
+        }
+    }
+}
+
+/// Origin that mailed links point at.
+pub fn public_base_url() -> String {
+    std::env::var("FRONTEND_URL")
+        .unwrap_or_else(|_| "http://localhost:8080".into())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+pub fn unsubscribe_url(base_url: &str, subject: &str, email: &str) -> anyhow::Result<String> {
+    let token = create_notification_token(
+        NotificationAction::Unsubscribe,
+        subject,
+        email,
+        UNSUBSCRIBE_LINK_TTL,
+    )?;
+    Ok(format!(
+        "{base_url}/api/v1/notifications/unsubscribe?token={}",
+        urlencoding::encode(&token)
+    ))
+}
+
+pub fn confirm_url(base_url: &str, subject: &str, email: &str) -> anyhow::Result<String> {
+    let token = create_notification_token(
+        NotificationAction::ConfirmEmail,
+        subject,
+        email,
+        CONFIRM_LINK_TTL,
+    )?;
+    Ok(format!(
+        "{base_url}/api/v1/notifications/confirm?token={}",
+        urlencoding::encode(&token)
+    ))
+}
+
+pub(crate) fn excerpt(text: &str) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match flat.char_indices().nth(200) {
+        Some((cut, _)) => format!("{}...", &flat[..cut]),
+        None => flat,
+    }
+}
+
+/// Plain-text digest body: replies grouped by thread, then saved search
+/// matches grouped by query, each in the order given.
+pub fn render_digest(
+    base_url: &str,
+    entries: &[DigestEntry],
+    matches: &[SearchDigestEntry],
+    unsubscribe: &str,
+) -> String {
+    let mut out = String::new();
+    let mut current: Option<Id> = None;
+    for entry in entries {
+        if current != Some(entry.thread_id) {
+            if current.is_some() {
+                out.push('\n');
+            }
+            let _ = writeln!(
+                out,
+                "{} - {base_url}/thread/{}",
+                entry.thread_subject, entry.thread_id
+            );
+            current = Some(entry.thread_id);
+        }
+        let _ = writeln!(
+            out,
+            "  #{} {}: {}",
+            entry.reply_id,
+            entry.created_at.format("%Y-%m-%d %H:%M UTC"),
+            excerpt(&entry.content)
+        );
+    }
+    let mut current: Option<&str> = None;
+    for entry in matches {
+        if current != Some(entry.query.as_str()) {
+            if current.is_some() || !entries.is_empty() {
+                out.push('\n');
+            }
+            let _ = writeln!(out, "New matches for \"{}\":", entry.query);
+            current = Some(&entry.query);
+        }
+        let _ = writeln!(
+            out,
+            "  {} - {base_url}/thread/{}",
+            entry.thread_subject, entry.thread_id
+        );
+        let _ = writeln!(
+            out,
+            "    {}{}: {}",
+            entry
+                .reply_id
+                .map(|id| format!("#{id} "))
+                .unwrap_or_default(),
+            entry.created_at.format("%Y-%m-%d %H:%M UTC"),
+            excerpt(&entry.content)
+        );
+    }
+    let reason = match (entries.is_empty(), matches.is_empty()) {
+        (_, true) => "watch these threads",
+        (true, false) => "saved these searches",
+        (false, false) => "watch these threads and saved these searches",
+    };
+    let _ = write!(
+        out,
+        "\nYou get this because you {reason}.\nStop these emails: {unsubscribe}\n"
+    );
+    out
+}
+
+/// Highest reply id per thread, for advancing subscription watermarks.
+fn watermarks(entries: &[DigestEntry]) -> Vec<(Id, Id)> {
+    let mut marks: Vec<(Id, Id)> = Vec::new();
+    for entry in entries {
+        match marks.last_mut() {
+            Some((thread_id, reply_id)) if *thread_id == entry.thread_id => {
+                *reply_id = (*reply_id).max(entry.reply_id);
+            }
+            _ => marks.push((entry.thread_id, entry.reply_id)),
+        }
+    }
+    marks
+}
+
+/// Polls for due digests and mails them.
+#[derive(Clone)]
+pub struct DigestWorker {
+    repo: Arc<dyn Repo>,
+    mailer: Arc<dyn Mailer>,
+    cfg: DigestConfig,
+}
+
+impl DigestWorker {
+    pub fn new(repo: Arc<dyn Repo>, mailer: Arc<dyn Mailer>, cfg: DigestConfig) -> Self {
+        Self { repo, mailer, cfg }
+    }
+
+    /// Send every due digest once; returns the number of emails sent.
+    pub async fn run_once(&self) -> usize {
+        let mut sent = 0;
+        for (frequency, min_interval) in [
+            (DigestFrequency::Immediate, 0),
+            (DigestFrequency::Daily, DAILY_SECS),
+        ] {
+            let recipients = match self
+                .repo
+                .due_digest_recipients(frequency, min_interval, self.cfg.batch_size)
+                .await
+            {
+                Ok(recipients) => recipients,
+                Err(e) => {
+                    log::error!("digest recipient lookup failed: {e}");
+                    continue;
+                }
+            };
+            for recipient in recipients {
+                match self.send_digest(&recipient.subject, &recipient.email).await {
+                    Ok(false) => {}
+                    Ok(true) => {
+                        sent += 1;
+                        metrics::increment_counter!("digests_sent", "frequency" => frequency.as_str());
+                    }
+                    Err(e) => {
+                        metrics::increment_counter!("digests_failed");
+                        log::warn!("digest for {} failed: {e}", recipient.subject);
+                    }
+                }
+            }
+        }
+        sent
+    }
+
+    /// Mail one subscriber's pending replies and saved search matches;
+    /// `false` if there were none.
+    async fn send_digest(&self, subject: &str, email: &str) -> anyhow::Result<bool> {
+        let entries = self
+            .repo
+            .digest_entries(subject, ENTRIES_PER_DIGEST)
+            .await?;
+        let matches = self
+            .repo
+            .search_digest_entries(subject, ENTRIES_PER_DIGEST)
+            .await?;
+        let marks = watermarks(&entries);
+        if marks.is_empty() && matches.is_empty() {
+            return Ok(false);
+        }
+        let unsubscribe = unsubscribe_url(&self.cfg.base_url, subject, email)?;
+        let message = Email {
+            to: email.to_string(),
+            subject: match (marks.len(), matches.first()) {
+                (0, Some(first)) if matches.iter().all(|m| m.query == first.query) => {
+                    format!("New matches for \"{}\"", first.query)
+                }
+                (0, Some(_)) => "New matches for your saved searches".to_string(),
+                (1, _) => format!("New replies in \"{}\"", entries[0].thread_subject),
+                (n, _) => format!("New replies in {n} watched threads"),
+            },
+            body: render_digest(&self.cfg.base_url, &entries, &matches, &unsubscribe),
+            unsubscribe_url: Some(unsubscribe),
+        };
+        self.mailer.send(&message).await?;
+        let match_ids: Vec<Id> = matches.iter().map(|m| m.match_id).collect();
+        self.repo
+            .mark_digest_sent(subject, &marks, &match_ids)
+            .await?;
+        Ok(true)
+    }
+
+    /// Spawn the polling loop on the current runtime.
+    pub fn spawn(self) {
+        crate::system::job_started("digests", self.cfg.poll_interval);
+        actix_web::rt::spawn(async move {
+            loop {
+                self.run_once().await;
+                crate::system::job_ran("digests");
+                tokio::time::sleep(self.cfg.poll_interval).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn entry(thread_id: Id, reply_id: Id, content: &str) -> DigestEntry {
+        DigestEntry {
+            thread_id,
+            thread_subject: format!("thread {thread_id}"),
+            reply_id,
+            content: content.into(),
+            created_at: chrono::Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn digests_group_replies_by_thread() {
+        let entries = [
+            entry(1, 10, "first"),
+            entry(1, 12, "second\nline"),
+            entry(4, 11, "other"),
+        ];
+        assert_eq!(watermarks(&entries), vec![(1, 12), (4, 11)]);
+        let body = render_digest("https://rib.example", &entries, &[], "https://unsub");
+        assert!(body.starts_with(
+            "thread 1 - https://rib.example/thread/1\n  #10 2026-01-02 03:04 UTC: first\n"
+        ));
+        assert!(body.contains("  #12 2026-01-02 03:04 UTC: second line\n"));
+        assert!(body.contains("\nthread 4 - https://rib.example/thread/4\n"));
+        assert!(body.ends_with("Stop these emails: https://unsub\n"));
+    }
+
+    #[test]
+    fn digests_list_saved_search_matches_by_query() {
+        let created_at = chrono::Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 0).unwrap();
+        let matches = [
+            SearchDigestEntry {
+                match_id: 1,
+                query: "rust".into(),
+                thread_id: 7,
+                thread_subject: "Rust general".into(),
+                reply_id: None,
+                content: "new thread".into(),
+                created_at,
+            },
+            SearchDigestEntry {
+                match_id: 2,
+                query: "rust".into(),
+                thread_id: 3,
+                thread_subject: "Other".into(),
+                reply_id: Some(30),
+                content: "rust reply".into(),
+                created_at,
+            },
+        ];
+        let body = render_digest("https://rib.example", &[], &matches, "https://unsub");
+        assert!(body.starts_with(
+            "New matches for \"rust\":\n  Rust general - https://rib.example/thread/7\n    2026-01-02 03:04 UTC: new thread\n"
+        ));
+        assert!(body.contains("    #30 2026-01-02 03:04 UTC: rust reply\n"));
+        assert!(body.contains("because you saved these searches."));
+    }
+
+    #[test]
+    fn long_replies_are_cut() {
+        let long = "x".repeat(300);
+        assert_eq!(excerpt(&long).chars().count(), 203);
+    }
+}
@@ -0,0 +1,190 @@
+//! Duplicate post detection.
+//!
+//! Each accepted thread body and reply is remembered as a hash per poster
+//! subject and per client IP for a short window, so double-submits and
+//! copypasta floods are rejected before they reach the database. The buffer
+//! is bounded and per replica; it is a flood brake, not a uniqueness rule.
+
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::ApiError;
+
+#[derive(Debug, Clone)]
+pub struct DuplicateConfig {
+    /// How long a post blocks an identical one; zero disables the check.
+    pub window: Duration,
+    /// Hashes kept at most; the oldest are dropped first.
+    pub capacity: usize,
+}
+
+impl DuplicateConfig {
+    /// No duplicate checks; the default for states built without
+    /// [`crate::routes::AppState::with_duplicates`].
+    pub fn disabled() -> Self {
+        Self {
+            window: Duration::ZERO,
+            capacity: 1,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        fn u64_env(name: &str) -> Option<u64> {
+            std::env::var(name).ok().and_then(|v| v.parse().ok())
+        }
+        Self {
+            window: Duration::from_secs(u64_env("DUPLICATE_POST_WINDOW_SECS").unwrap_or(120)),
+            capacity: u64_env("DUPLICATE_POST_CAPACITY").unwrap_or(10_000).max(1) as usize,
+        }
+    }
+}
+
+type Fingerprint = [u8; 32];
+
+#[derive(Default)]
+struct Recent {
+    order: VecDeque<(Instant, Fingerprint)>,
+    seen: HashMap<Fingerprint, Instant>,
+}
+
+impl Recent {
+    fn evict_oldest(&mut self) {
+        if let Some((at, print)) = self.order.pop_front() {
+            // A newer post with the same fingerprint keeps its entry.
+            if self.seen.get(&print) == Some(&at) {
+                self.seen.remove(&print);
+            }
+        }
+    }
+}
+
+pub struct DuplicateGuard {
+    cfg: DuplicateConfig,
+    recent: Mutex<Recent>,
+}
+
+/// Fingerprints claimed for a post; hand them back with
+/// [`DuplicateGuard::release`] if the post is not stored after all.
+#[must_use]
+pub struct Claim(Vec<Fingerprint>);
+
+fn fingerprint(kind: &str, poster: &str, content: &str) -> Fingerprint {
+    let mut hasher = Sha256::new();
+    for part in [kind, poster, content] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.finalize().into()
+}
+
+impl DuplicateGuard {
+    pub fn new(cfg: DuplicateConfig) -> Self {
+        Self {
+            cfg,
+            recent: Mutex::new(Recent::default()),
+        }
+    }
+
+    /// Reject `content` if any of `posters` (subject, client IP) posted the
+    /// same `kind` of content within the window; otherwise remember it.
+    pub fn claim(&self, kind: &str, posters: &[&str], content: &str) -> Result<Claim, ApiError> {
+        if self.cfg.window.is_zero() {
+            return Ok(Claim(Vec::new()));
+        }
+        let now = Instant::now();
+        let prints: Vec<Fingerprint> = posters
+            .iter()
+            .map(|poster| fingerprint(kind, poster, content))
+            .collect();
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        while recent
+            .order
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) >= self.cfg.window)
+        {
+            recent.evict_oldest();
+        }
+        if let Some(at) = prints.iter().find_map(|print| recent.seen.get(print)) {
+            let wait = self.cfg.window.saturating_sub(now.duration_since(*at));
+            metrics::increment_counter!("duplicate_post_rejected", "kind" => kind.to_string());
+            return Err(ApiError::Duplicate(format!(
+                "identical {kind} posted moments ago; change it or wait {}s",
+                wait.as_secs().max(1)
+            )));
+        }
+        for print in &prints {
+            recent.order.push_back((now, *print));
+            recent.seen.insert(*print, now);
+        }
+        while recent.order.len() > self.cfg.capacity {
+            recent.evict_oldest();
+        }
+        Ok(Claim(prints))
+    }
+
+    /// Forget a claim whose post failed, so the poster can retry at once.
+    pub fn release(&self, claim: Claim) {
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        for print in claim.0 {
+            recent.seen.remove(&print);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard(window: Duration, capacity: usize) -> DuplicateGuard {
+        DuplicateGuard::new(DuplicateConfig { window, capacity })
+    }
+
+    #[test]
+    fn same_content_from_same_poster_is_rejected_within_window() {
+        let guard = guard(Duration::from_secs(60), 100);
+        let _ = guard
+            .claim("reply", &["discord:a", "10.0.0.1"], "hi")
+            .unwrap();
+        assert!(guard
+            .claim("reply", &["discord:a", "10.0.0.2"], "hi")
+            .is_err());
+        assert!(guard
+            .claim("reply", &["discord:b", "10.0.0.1"], "hi")
+            .is_err());
+        assert!(guard
+            .claim("thread", &["discord:a", "10.0.0.1"], "hi")
+            .is_ok());
+        assert!(guard
+            .claim("reply", &["discord:b", "10.0.0.2"], "hi")
+            .is_ok());
+        assert!(guard
+            .claim("reply", &["discord:a", "10.0.0.1"], "hi!")
+            .is_ok());
+    }
+
+    #[test]
+    fn expired_evicted_and_released_claims_allow_reposting() {
+        let guard = guard(Duration::from_millis(20), 100);
+        let _ = guard.claim("reply", &["a"], "x").unwrap();
+        std::thread::sleep(Duration::from_millis(30));
+        let claim = guard.claim("reply", &["a"], "x").unwrap();
+        guard.release(claim);
+        assert!(guard.claim("reply", &["a"], "x").is_ok());
+
+        let guard = self::guard(Duration::from_secs(60), 2);
+        for content in ["1", "2", "3"] {
+            let _ = guard.claim("reply", &["a"], content).unwrap();
+        }
+        assert!(guard.claim("reply", &["a"], "1").is_ok());
+        assert!(guard.claim("reply", &["a"], "3").is_err());
+    }
+
+    #[test]
+    fn disabled_guard_accepts_repeats() {
+        let guard = guard(Duration::ZERO, 10);
+        let _ = guard.claim("reply", &["a"], "x").unwrap();
+        assert!(guard.claim("reply", &["a"], "x").is_ok());
+    }
+}
@@ -1,11 +1,25 @@
-use actix_web::{HttpResponse, ResponseError};
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
 use serde::Serialize;
 
+use crate::features::Feature;
+use crate::models::BanNotice;
 use crate::repo::RepoError;
+use crate::reporting::{ErrorEvent, ErrorKind};
 
 #[derive(Debug, Serialize)]
 pub struct ApiErrorBody {
     pub error: String,
+    /// [`ApiError::code`] when the body renders an [`ApiError`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+}
+
+/// [`ApiErrorBody`] of a banned caller, with what they may be told.
+#[derive(Debug, Serialize)]
+pub struct BannedBody<'a> {
+    pub error: String,
+    pub code: &'static str,
+    pub ban: &'a BanNotice,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -14,16 +28,43 @@ pub enum ApiError {
     NotFound,
     #[error("conflict")]
     Conflict,
+    /// The change would leave no admin; a `409` with its own code.
+    #[error("the last admin cannot be removed or demoted")]
+    LastAdmin,
+    /// Admins removing their own admin role must pass `confirm: true`.
+    #[error("confirm removing your own admin role")]
+    ConfirmationRequired,
+    /// Duplicate of a recent post, with a message that is safe to show the caller.
+    #[error("{0}")]
+    Duplicate(String),
     #[error("internal error")]
     Internal,
+    #[error("unauthorized")]
+    Unauthorized,
     #[error("forbidden")]
     Forbidden,
+    /// A `403` whose body carries the ban's public reason and expiry.
+    #[error("banned")]
+    Banned(BanNotice),
     #[error("insufficient funds")]
     InsufficientFunds,
     #[error("bad request")]
     BadRequest,
+    /// Bad request with a message that is safe to show the caller.
+    #[error("{0}")]
+    Invalid(String),
     #[error("rate limited")]
     RateLimited { retry_after: u64 },
+    #[error("service unavailable")]
+    Unavailable,
+    /// Switched off in `DISABLED_FEATURES`; a `503` with its own code.
+    #[error("{} is disabled for maintenance", .0.as_str())]
+    FeatureDisabled(Feature),
+    #[error("unprocessable entity")]
+    Unprocessable,
+    /// Content held back from the caller (`451`), with a message safe to show.
+    #[error("{0}")]
+    Withheld(String),
 }
 
 impl From<RepoError> for ApiError {
@@ -31,27 +72,101 @@ impl From<RepoError> for ApiError {
         match e {
             RepoError::NotFound => ApiError::NotFound,
             RepoError::Conflict => ApiError::Conflict,
+            RepoError::LastAdmin => ApiError::LastAdmin,
+            RepoError::Unavailable(cause) => {
+                log::warn!("database unavailable: {cause}");
+                ApiError::Unavailable
+            }
+            RepoError::Aborted(cause) => {
+                log::warn!("transaction aborted: {cause}");
+                ApiError::Unavailable
+            }
+            RepoError::Constraint(constraint) => {
+                log::info!("write rejected by constraint {constraint}");
+                ApiError::Unprocessable
+            }
+            RepoError::Other(e) => {
+                log::error!("database error: {e}");
+                ApiError::Internal
+            }
+        }
+    }
+}
+
+impl ApiError {
+    /// Stable machine-readable identifier for API clients.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::NotFound => "not_found",
+            ApiError::Conflict => "conflict",
+            ApiError::LastAdmin => "last_admin",
+            ApiError::ConfirmationRequired => "confirmation_required",
+            ApiError::Duplicate(_) => "duplicate",
+            ApiError::Internal => "internal",
+            ApiError::Unauthorized => "unauthorized",
+            ApiError::Forbidden => "forbidden",
+            ApiError::Banned(_) => "banned",
+            ApiError::InsufficientFunds => "insufficient_funds",
+            ApiError::BadRequest | ApiError::Invalid(_) => "bad_request",
+            ApiError::RateLimited { .. } => "rate_limited",
+            ApiError::Unavailable => "unavailable",
+            ApiError::FeatureDisabled(_) => "feature_disabled",
+            ApiError::Unprocessable => "unprocessable",
+            ApiError::Withheld(_) => "withheld",
         }
     }
 }
 
 impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::Conflict
+            | ApiError::Duplicate(_)
+            | ApiError::LastAdmin
+            | ApiError::ConfirmationRequired => StatusCode::CONFLICT,
+            ApiError::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden | ApiError::Banned(_) | ApiError::InsufficientFunds => {
+                StatusCode::FORBIDDEN
+            }
+            ApiError::BadRequest | ApiError::Invalid(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unprocessable => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Unavailable | ApiError::FeatureDisabled(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Withheld(_) => StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS,
+        }
+    }
+
     fn error_response(&self) -> HttpResponse {
-        let mut builder = match self {
-            ApiError::NotFound => HttpResponse::NotFound(),
-            ApiError::Conflict => HttpResponse::Conflict(),
-            ApiError::Internal => HttpResponse::InternalServerError(),
-            ApiError::Forbidden => HttpResponse::Forbidden(),
-            ApiError::InsufficientFunds => HttpResponse::Forbidden(),
-            ApiError::BadRequest => HttpResponse::BadRequest(),
+        let mut builder = HttpResponse::build(self.status_code());
+        match self {
+            ApiError::Internal => crate::reporting::report(ErrorEvent::in_current_request(
+                ErrorKind::Internal,
+                "internal error",
+            )),
+            ApiError::Unavailable => {
+                builder.insert_header(("Retry-After", "1"));
+            }
             ApiError::RateLimited { retry_after } => {
-                let mut b = HttpResponse::TooManyRequests();
-                b.insert_header(("Retry-After", retry_after.to_string()));
-                b
+                builder.insert_header(("Retry-After", retry_after.to_string()));
+            }
+            // Review may lift it; caches must not keep the refusal.
+            ApiError::Withheld(_) => {
+                builder.insert_header(("Cache-Control", "no-store"));
             }
-        };
+            ApiError::Banned(ban) => {
+                return builder.json(BannedBody {
+                    error: self.to_string(),
+                    code: self.code(),
+                    ban,
+                });
+            }
+            _ => {}
+        }
         builder.json(ApiErrorBody {
             error: self.to_string(),
+            code: Some(self.code()),
         })
     }
 }
@@ -0,0 +1,527 @@
+//! Sign-In with Ethereum (EIP-4361).
+//!
+//! `POST /api/v1/auth/ethereum/challenge` hands out a single-use nonce and a
+//! ready-to-sign SIWE message for an address. The wallet signs the message
+//! with `personal_sign` (EIP-191) and `POST /api/v1/auth/ethereum/verify`
+//! checks it: the domain, URI, chain and validity window must match this
+//! server, the nonce must be one it issued for that address, and the
+//! signature must recover to the address. The session subject is
+//! `eth:<lowercase address>`.
+//!
+//! With `ETH_GATE_CONTRACT` set, the address must also hold at least
+//! `ETH_GATE_MIN_BALANCE` (raw units, default 1) of that ERC-20 or ERC-721
+//! token; `balanceOf` is read through the JSON-RPC endpoint in `ETH_RPC_URL`.
+
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, Secp256k1};
+use sha3::{Digest, Keccak256};
+use std::fmt;
+use std::time::Duration;
+
+use crate::auth::{session_cookie, Role};
+use crate::error::ApiError;
+use crate::routes::{ensure_subject_not_banned, AppState};
+
+/// How long an issued nonce (and the message built around it) is valid.
+const CHALLENGE_TTL: Duration = Duration::from_secs(300);
+
+/// `balanceOf(address)`, shared by ERC-20 and ERC-721.
+const BALANCE_OF_SELECTOR: &str = "70a08231";
+
+#[derive(Clone, Debug)]
+pub struct EthConfig {
+    /// RFC 3986 authority the message must be bound to.
+    pub domain: String,
+    /// URI the message must name.
+    pub uri: String,
+    pub chain_id: u64,
+    pub rpc_url: Option<String>,
+    /// Token whose balance gates sign-in, when set.
+    pub gate_contract: Option<String>,
+    pub gate_min_balance: u128,
+}
+
+impl EthConfig {
+    pub fn from_env() -> Self {
+        fn opt_env(name: &str) -> Option<String> {
+            std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+        }
+        let uri = opt_env("ETH_SIWE_URI")
+            .or_else(|| opt_env("FRONTEND_URL"))
+            .unwrap_or_else(|| "http://localhost:5173".to_string())
+            .trim_end_matches('/')
+            .to_string();
+        let domain = opt_env("ETH_SIWE_DOMAIN").unwrap_or_else(|| {
+            uri.split_once("://")
+                .map_or(uri.as_str(), |(_, rest)| rest)
+                .split('/')
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        });
+        Self {
+            domain,
+            uri,
+            chain_id: opt_env("ETH_CHAIN_ID")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1),
+            rpc_url: opt_env("ETH_RPC_URL"),
+            gate_contract: opt_env("ETH_GATE_CONTRACT"),
+            gate_min_balance: opt_env("ETH_GATE_MIN_BALANCE")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1),
+        }
+    }
+
+    /// Problems that would make every verification fail.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(contract) = &self.gate_contract {
+            if parse_address(contract).is_none() {
+                return Err(format!("ETH_GATE_CONTRACT '{contract}' is not an address"));
+            }
+            if self.rpc_url.is_none() {
+                return Err("ETH_GATE_CONTRACT requires ETH_RPC_URL".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The 20 address bytes, lowercase hex without `0x`, if `value` is a valid
+/// address. Mixed-case input must carry a correct EIP-55 checksum.
+pub fn parse_address(value: &str) -> Option<String> {
+    let hex_part = value.strip_prefix("0x")?;
+    if hex_part.len() != 40 || !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let lower = hex_part.to_ascii_lowercase();
+    let mixed = hex_part != lower && hex_part != hex_part.to_ascii_uppercase();
+    if mixed && checksum_address(&lower) != value {
+        return None;
+    }
+    Some(lower)
+}
+
+/// EIP-55 form of a lowercase hex address (without `0x`).
+pub fn checksum_address(lower: &str) -> String {
+    let hash = Keccak256::digest(lower.as_bytes());
+    let mut out = String::with_capacity(42);
+    out.push_str("0x");
+    for (i, c) in lower.chars().enumerate() {
+        let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+        if c.is_ascii_alphabetic() && nibble >= 8 {
+            out.push(c.to_ascii_uppercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Address (lowercase hex, no `0x`) that produced an EIP-191 `personal_sign`
+/// signature (`0x` + 65 bytes `r || s || v`) over `message`.
+pub fn recover_signer(message: &str, signature: &str) -> anyhow::Result<String> {
+    let raw = hex::decode(signature.strip_prefix("0x").unwrap_or(signature))?;
+    if raw.len() != 65 {
+        anyhow::bail!("unexpected signature length (want 65)");
+    }
+    let v = match raw[64] {
+        27 | 28 => raw[64] - 27,
+        0 | 1 => raw[64],
+        _ => anyhow::bail!("invalid recovery byte"),
+    };
+    let signature =
+        RecoverableSignature::from_compact(&raw[..64], RecoveryId::from_i32(v as i32)?)?;
+    let mut hasher = Keccak256::new();
+    hasher.update(format!("\x19Ethereum Signed Message:\n{}", message.len()).as_bytes());
+    hasher.update(message.as_bytes());
+    let digest = Message::from_digest_slice(&hasher.finalize())?;
+    let pubkey = Secp256k1::new().recover_ecdsa(&digest, &signature)?;
+    let hash = Keccak256::digest(&pubkey.serialize_uncompressed()[1..]);
+    Ok(hex::encode(&hash[12..]))
+}
+
+/// The fields of an EIP-4361 message this server issues and checks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SiweMessage {
+    pub domain: String,
+    pub address: String,
+    pub statement: Option<String>,
+    pub uri: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub nonce: String,
+    pub issued_at: DateTime<Utc>,
+    pub expiration_time: Option<DateTime<Utc>>,
+    pub not_before: Option<DateTime<Utc>>,
+}
+
+impl fmt::Display for SiweMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} wants you to sign in with your Ethereum account:",
+            self.domain
+        )?;
+        writeln!(f, "{}", self.address)?;
+        writeln!(f)?;
+        if let Some(statement) = &self.statement {
+            writeln!(f, "{statement}")?;
+            writeln!(f)?;
+        }
+        writeln!(f, "URI: {}", self.uri)?;
+        writeln!(f, "Version: {}", self.version)?;
+        writeln!(f, "Chain ID: {}", self.chain_id)?;
+        writeln!(f, "Nonce: {}", self.nonce)?;
+        write!(f, "Issued At: {}", rfc3339(&self.issued_at))?;
+        if let Some(expiration) = &self.expiration_time {
+            write!(f, "\nExpiration Time: {}", rfc3339(expiration))?;
+        }
+        if let Some(not_before) = &self.not_before {
+            write!(f, "\nNot Before: {}", rfc3339(not_before))?;
+        }
+        Ok(())
+    }
+}
+
+fn rfc3339(at: &DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+impl SiweMessage {
+    pub fn parse(message: &str) -> Result<Self, String> {
+        let mut lines = message.lines();
+        let domain = lines
+            .next()
+            .and_then(|line| line.strip_suffix(" wants you to sign in with your Ethereum account:"))
+            .ok_or("missing preamble")?
+            .to_string();
+        let address = lines.next().ok_or("missing address")?.to_string();
+        if lines.next() != Some("") {
+            return Err("expected a blank line after the address".into());
+        }
+        let mut rest: Vec<&str> = lines.collect();
+        let statement = match rest.first() {
+            Some(line) if !line.starts_with("URI: ") => {
+                if rest.get(1) != Some(&"") {
+                    return Err("expected a blank line after the statement".into());
+                }
+                let statement = line.to_string();
+                rest.drain(..2);
+                Some(statement)
+            }
+            _ => None,
+        };
+        let field = |name: &str| {
+            rest.iter()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(": "))
+        };
+        let time = |name: &str| -> Result<Option<DateTime<Utc>>, String> {
+            field(name)
+                .map(|v| {
+                    DateTime::parse_from_rfc3339(v)
+                        .map(|t| t.with_timezone(&Utc))
+                        .map_err(|e| format!("{name}: {e}"))
+                })
+                .transpose()
+        };
+        Ok(Self {
+            domain,
+            address,
+            statement,
+            uri: field("URI").ok_or("missing URI")?.to_string(),
+            version: field("Version").ok_or("missing Version")?.to_string(),
+            chain_id: field("Chain ID")
+                .and_then(|v| v.parse().ok())
+                .ok_or("missing or invalid Chain ID")?,
+            nonce: field("Nonce").ok_or("missing Nonce")?.to_string(),
+            issued_at: time("Issued At")?.ok_or("missing Issued At")?,
+            expiration_time: time("Expiration Time")?,
+            not_before: time("Not Before")?,
+        })
+    }
+}
+
+async fn token_balance(cfg: &EthConfig, contract: &str, address: &str) -> anyhow::Result<u128> {
+    let rpc_url = cfg
+        .rpc_url
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("ETH_RPC_URL is not set"))?;
+    let client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(3))
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let response: serde_json::Value = client
+        .post(rpc_url)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_call",
+            "params": [
+                {"to": contract, "data": format!("0x{BALANCE_OF_SELECTOR}{address:0>64}")},
+                "latest"
+            ]
+        }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if let Some(error) = response.get("error") {
+        anyhow::bail!("eth_call failed: {error}");
+    }
+    let result = response
+        .get("result")
+        .and_then(serde_json::Value::as_str)
+        .ok_or_else(|| anyhow::anyhow!("eth_call returned no result"))?;
+    let digits = result
+        .strip_prefix("0x")
+        .unwrap_or(result)
+        .trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    if digits.len() > 32 {
+        // More than fits in 128 bits is more than any threshold.
+        return Ok(u128::MAX);
+    }
+    Ok(u128::from_str_radix(digits, 16)?)
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct EthereumChallengeRequest {
+    pub address: String,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct EthereumChallengeResponse {
+    pub nonce: String,
+    /// EIP-4361 message to sign with `personal_sign`
+    pub message: String,
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct EthereumVerifyRequest {
+    /// The signed EIP-4361 message
+    pub message: String,
+    /// `0x`-prefixed 65-byte signature
+    pub signature: String,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct EthereumVerifyResponse {
+    pub token: String,
+}
+
+fn store_error(e: anyhow::Error) -> ApiError {
+    log::error!("ethereum sign-in challenge store failed: {e}");
+    ApiError::Internal
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/ethereum/challenge",
+    request_body = EthereumChallengeRequest,
+    responses(
+        (status = 200, description = "Nonce and message to sign", body = EthereumChallengeResponse),
+        (status = 400, description = "Not an Ethereum address")
+    )
+)]
+pub async fn ethereum_challenge(
+    payload: web::Json<EthereumChallengeRequest>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let address = parse_address(payload.address.trim()).ok_or(ApiError::BadRequest)?;
+    let cfg = EthConfig::from_env();
+    let mut nonce_bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let nonce = hex::encode(nonce_bytes);
+    let issued_at = Utc::now();
+    let message = SiweMessage {
+        domain: cfg.domain.clone(),
+        address: checksum_address(&address),
+        statement: Some("Sign in to rib.".to_string()),
+        uri: cfg.uri.clone(),
+        version: "1".to_string(),
+        chain_id: cfg.chain_id,
+        nonce: nonce.clone(),
+        issued_at,
+        expiration_time: Some(
+            issued_at
+                + chrono::Duration::from_std(CHALLENGE_TTL).map_err(|_| ApiError::Internal)?,
+        ),
+        not_before: None,
+    };
+    data.challenges
+        .put("siwe", &nonce, &address, CHALLENGE_TTL)
+        .await
+        .map_err(store_error)?;
+    Ok(HttpResponse::Ok().json(EthereumChallengeResponse {
+        nonce,
+        message: message.to_string(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/ethereum/verify",
+    request_body = EthereumVerifyRequest,
+    responses(
+        (status = 200, description = "JWT token", body = EthereumVerifyResponse),
+        (status = 400, description = "Malformed message, unknown nonce or bad signature"),
+        (status = 403, description = "Banned, or token balance below the gate"),
+        (status = 410, description = "Message expired")
+    )
+)]
+pub async fn ethereum_verify(
+    payload: web::Json<EthereumVerifyRequest>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let cfg = EthConfig::from_env();
+    let message = SiweMessage::parse(&payload.message).map_err(|e| {
+        log::info!("rejected SIWE message: {e}");
+        ApiError::BadRequest
+    })?;
+    let address = parse_address(&message.address).ok_or(ApiError::BadRequest)?;
+    if message.domain != cfg.domain
+        || message.uri != cfg.uri
+        || message.version != "1"
+        || message.chain_id != cfg.chain_id
+    {
+        return Err(ApiError::BadRequest);
+    }
+    let now = Utc::now();
+    if message.not_before.is_some_and(|t| now < t) {
+        return Err(ApiError::BadRequest);
+    }
+    if message.expiration_time.is_some_and(|t| now >= t) {
+        return Ok(HttpResponse::Gone().finish());
+    }
+    // Nonces are single-use and bound to the address they were issued for.
+    let issued_to = data
+        .challenges
+        .take("siwe", &message.nonce)
+        .await
+        .map_err(store_error)?
+        .ok_or(ApiError::BadRequest)?;
+    if issued_to != address {
+        return Err(ApiError::BadRequest);
+    }
+    match recover_signer(&payload.message, &payload.signature) {
+        Ok(signer) if signer == address => {}
+        Ok(_) => return Err(ApiError::BadRequest),
+        Err(e) => {
+            log::info!("ethereum signature verify failed: {e}");
+            return Err(ApiError::BadRequest);
+        }
+    }
+    if let Some(contract) = &cfg.gate_contract {
+        match token_balance(&cfg, contract, &address).await {
+            Ok(balance) if balance >= cfg.gate_min_balance => {}
+            Ok(_) => return Err(ApiError::InsufficientFunds),
+            Err(e) => {
+                log::warn!("ethereum token balance lookup failed: {e}");
+                return Err(ApiError::Internal);
+            }
+        }
+    }
+    let subject = format!("eth:0x{address}");
+    ensure_subject_not_banned(data.get_ref(), &subject).await?;
+    let role = data
+        .repo
+        .get_subject_role(&subject)
+        .await
+        .unwrap_or(Role::User);
+    let jwt =
+        crate::auth::create_jwt(&subject, &subject, vec![role]).map_err(|_| ApiError::Internal)?;
+    Ok(HttpResponse::Ok()
+        .cookie(session_cookie(&jwt))
+        .json(EthereumVerifyResponse { token: jwt }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn addresses_follow_eip55() {
+        let lower = "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed";
+        assert_eq!(
+            checksum_address(lower),
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        );
+        assert_eq!(
+            parse_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").as_deref(),
+            Some(lower)
+        );
+        assert_eq!(parse_address(&format!("0x{lower}")).as_deref(), Some(lower));
+        // A wrong checksum is a typo, not an address.
+        assert!(parse_address("0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").is_none());
+        assert!(parse_address("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").is_none());
+    }
+
+    #[test]
+    fn recovers_the_personal_sign_signer() {
+        // Private key and address from the web3.js `eth.accounts` examples.
+        let secret = secp256k1::SecretKey::from_slice(
+            &hex::decode("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+                .unwrap(),
+        )
+        .unwrap();
+        let message = "Some data";
+        let mut hasher = Keccak256::new();
+        hasher.update(format!("\x19Ethereum Signed Message:\n{}", message.len()).as_bytes());
+        hasher.update(message.as_bytes());
+        let digest = Message::from_digest_slice(&hasher.finalize()).unwrap();
+        let (recid, compact) = Secp256k1::new()
+            .sign_ecdsa_recoverable(&digest, &secret)
+            .serialize_compact();
+        let mut raw = compact.to_vec();
+        raw.push(27 + recid.to_i32() as u8);
+        let signature = format!("0x{}", hex::encode(raw));
+        assert_eq!(
+            recover_signer(message, &signature).unwrap(),
+            "2c7536e3605d9c16a7a3d7b1898e529396a65c23"
+        );
+        assert_ne!(
+            recover_signer("Other data", &signature).unwrap(),
+            "2c7536e3605d9c16a7a3d7b1898e529396a65c23"
+        );
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let issued_at = DateTime::parse_from_rfc3339("2026-10-17T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let message = SiweMessage {
+            domain: "rib.example".into(),
+            address: "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".into(),
+            statement: Some("Sign in to rib.".into()),
+            uri: "https://rib.example".into(),
+            version: "1".into(),
+            chain_id: 1,
+            nonce: "0123456789abcdef".into(),
+            issued_at,
+            expiration_time: Some(issued_at + chrono::Duration::minutes(5)),
+            not_before: None,
+        };
+        let text = message.to_string();
+        assert!(text.starts_with(
+            "rib.example wants you to sign in with your Ethereum account:\n0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed\n\nSign in to rib.\n\nURI: https://rib.example\n"
+        ));
+        assert_eq!(SiweMessage::parse(&text).unwrap(), message);
+        let without_statement = SiweMessage {
+            statement: None,
+            ..message
+        };
+        assert_eq!(
+            SiweMessage::parse(&without_statement.to_string()).unwrap(),
+            without_statement
+        );
+        assert!(SiweMessage::parse("hello").is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Excerpts of text and code uploads.
+//!
+//! Text files of up to [`MAX_FILE_BYTES`] that are valid UTF-8 get their
+//! opening lines stored with the language their name or content suggests,
+//! so clients can render a code preview from `/images/{hash}/preview`
+//! without downloading the file. The excerpt is data for the client to
+//! escape and highlight; it is never served as markup.
+
+use crate::models::TextExcerpt;
+
+/// Largest text file that gets an excerpt.
+pub const MAX_FILE_BYTES: usize = 1024 * 1024;
+/// Longest excerpt, in bytes; cut at a character boundary.
+pub const MAX_EXCERPT_BYTES: usize = 8 * 1024;
+/// Most lines in an excerpt.
+pub const MAX_EXCERPT_LINES: usize = 200;
+
+/// Whether uploads of `mime` get an excerpt.
+pub fn excerpts(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || matches!(
+            mime,
+            "application/json" | "application/xml" | "application/yaml"
+        )
+}
+
+/// The excerpt of a text upload, if it gets one.
+pub fn excerpt(bytes: &[u8], mime: &str, file_name: Option<&str>) -> Option<TextExcerpt> {
+    if !excerpts(mime) || bytes.len() > MAX_FILE_BYTES || bytes.contains(&0) {
+        return None;
+    }
+    let text = std::str::from_utf8(bytes).ok()?;
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut end = text
+        .match_indices('\n')
+        .nth(MAX_EXCERPT_LINES - 1)
+        .map_or(text.len(), |(at, _)| at + 1)
+        .min(MAX_EXCERPT_BYTES);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    Some(TextExcerpt {
+        language: detect_language(file_name, mime, text).map(str::to_string),
+        excerpt: text[..end].to_string(),
+        line_count: text.lines().count() as i64,
+        truncated: end < text.len(),
+    })
+}
+
+/// A highlighter-style language name (`rust`, `python`, `json`, ...) from the
+/// file name, the sniffed type, or the first line, in that order.
+pub fn detect_language(file_name: Option<&str>, mime: &str, text: &str) -> Option<&'static str> {
+    let name = file_name
+        .and_then(|n| n.rsplit(['/', '\\']).next())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match name.as_str() {
+        "dockerfile" | "containerfile" => return Some("dockerfile"),
+        "makefile" | "gnumakefile" => return Some("makefile"),
+        _ => {}
+    }
+    if let Some(language) = name
+        .rsplit_once('.')
+        .and_then(|(_, extension)| by_extension(extension))
+    {
+        return Some(language);
+    }
+    match mime {
+        "text/csv" => return Some("csv"),
+        "text/html" => return Some("html"),
+        "text/css" => return Some("css"),
+        "text/javascript" => return Some("javascript"),
+        "application/json" => return Some("json"),
+        "application/xml" | "text/xml" => return Some("xml"),
+        "application/yaml" => return Some("yaml"),
+        _ => {}
+    }
+    by_content(text)
+}
+
+fn by_extension(extension: &str) -> Option<&'static str> {
+    Some(match extension {
+        "rs" => "rust",
+        "py" | "pyw" => "python",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "jsx",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "tsx",
+        "go" => "go",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx" => "cpp",
+        "cs" => "csharp",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "scala" => "scala",
+        "swift" => "swift",
+        "rb" => "ruby",
+        "php" => "php",
+        "pl" | "pm" => "perl",
+        "lua" => "lua",
+        "r" => "r",
+        "dart" => "dart",
+        "hs" => "haskell",
+        "ex" | "exs" => "elixir",
+        "erl" | "hrl" => "erlang",
+        "clj" | "cljs" => "clojure",
+        "sh" | "bash" | "zsh" => "bash",
+        "ps1" | "psm1" => "powershell",
+        "sql" => "sql",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "scss" => "scss",
+        "vue" => "vue",
+        "svelte" => "svelte",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "toml" => "toml",
+        "ini" | "cfg" => "ini",
+        "xml" | "svg" => "xml",
+        "md" | "markdown" => "markdown",
+        "csv" => "csv",
+        "diff" | "patch" => "diff",
+        _ => return None,
+    })
+}
+
+fn by_content(text: &str) -> Option<&'static str> {
+    let first = text.lines().next().unwrap_or_default().trim();
+    if let Some(interpreter) = first.strip_prefix("#!") {
+        let interpreter = interpreter.rsplit('/').next().unwrap_or_default();
+        // `#!/usr/bin/env python3` names the interpreter after `env`.
+        let interpreter = interpreter
+            .split_whitespace()
+            .find(|word| *word != "env")
+            .unwrap_or_default();
+        return Some(
+            match interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+                "python" => "python",
+                "node" | "deno" => "javascript",
+                "sh" | "bash" | "zsh" | "dash" => "bash",
+                "ruby" => "ruby",
+                "perl" => "perl",
+                "php" => "php",
+                _ => return None,
+            },
+        );
+    }
+    let lowered = first.to_ascii_lowercase();
+    if lowered.starts_with("<?php") {
+        Some("php")
+    } else if lowered.starts_with("<?xml") {
+        Some("xml")
+    } else if lowered.starts_with("<!doctype html") || lowered.starts_with("<html") {
+        Some("html")
+    } else if lowered.starts_with("diff --git") {
+        Some("diff")
+    } else if (first.starts_with('{') || first.starts_with('['))
+        && serde_json::from_str::<serde::de::IgnoredAny>(text).is_ok()
+    {
+        Some("json")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn languages_come_from_the_name_type_or_first_line() {
+        let detect = |name: Option<&str>, text: &str| detect_language(name, "text/plain", text);
+        assert_eq!(detect(Some("src/main.rs"), ""), Some("rust"));
+        assert_eq!(detect(Some("Dockerfile"), ""), Some("dockerfile"));
+        assert_eq!(
+            detect(None, "#!/usr/bin/env python3\nprint(1)"),
+            Some("python")
+        );
+        assert_eq!(detect(None, "#!/bin/bash\necho hi"), Some("bash"));
+        assert_eq!(detect(Some("notes"), "{\"a\": [1, 2]}"), Some("json"));
+        assert_eq!(detect(Some("notes.txt"), "{ not json"), None);
+        assert_eq!(detect_language(None, "text/csv", "a,b"), Some("csv"));
+    }
+
+    #[test]
+    fn excerpts_keep_the_opening_lines() {
+        let text = "line\n".repeat(500);
+        let found = excerpt(text.as_bytes(), "text/plain", Some("log.txt")).unwrap();
+        assert_eq!(found.excerpt.lines().count(), MAX_EXCERPT_LINES);
+        assert_eq!(found.line_count, 500);
+        assert!(found.truncated);
+        assert_eq!(found.language, None);
+
+        let wide = "é".repeat(MAX_EXCERPT_BYTES);
+        let found = excerpt(wide.as_bytes(), "text/plain", None).unwrap();
+        assert!(found.excerpt.len() <= MAX_EXCERPT_BYTES);
+        assert!(found.truncated);
+
+        let short = excerpt(b"\xef\xbb\xbffn main() {}\n", "text/plain", Some("a.rs")).unwrap();
+        assert_eq!(short.excerpt, "fn main() {}\n");
+        assert!(!short.truncated);
+        assert!(excerpt(b"\xff\xfe", "text/plain", None).is_none());
+        assert!(excerpt(b"PK", "application/zip", None).is_none());
+    }
+}
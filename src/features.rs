@@ -0,0 +1,106 @@
+//! Runtime switches for individual capabilities.
+//!
+//! `DISABLED_FEATURES` names capabilities to turn off, comma-separated, for
+//! example `uploads,search`. It is reloaded with the rest of the live
+//! configuration ([`crate::reload`]), so one capability can be taken down
+//! for maintenance and brought back without a restart. Requests to a
+//! disabled capability get `503` with the code `feature_disabled`.
+
+use serde::Serialize;
+
+use crate::error::ApiError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// `POST /api/v1/images` and `/api/v1/images/remote`.
+    Uploads,
+    /// Creating boards, over every API.
+    NewBoards,
+    /// Bitcoin sign-in challenges and verification.
+    BitcoinAuth,
+    /// Search, over every API.
+    Search,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 4] = [
+        Feature::Uploads,
+        Feature::NewBoards,
+        Feature::BitcoinAuth,
+        Feature::Search,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Feature::Uploads => "uploads",
+            Feature::NewBoards => "new_boards",
+            Feature::BitcoinAuth => "bitcoin_auth",
+            Feature::Search => "search",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.as_str() == value)
+    }
+}
+
+/// Capabilities currently switched off.
+#[derive(Clone, Debug, Default)]
+pub struct FeatureFlags {
+    disabled: Vec<Feature>,
+}
+
+impl FeatureFlags {
+    pub fn from_env() -> Self {
+        let value = std::env::var("DISABLED_FEATURES").unwrap_or_default();
+        Self::parse(&value)
+    }
+
+    /// Unknown names are logged and skipped, so a typo never disables more.
+    pub fn parse(value: &str) -> Self {
+        let mut disabled = Vec::new();
+        for name in value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            match Feature::parse(name) {
+                Some(feature) if !disabled.contains(&feature) => disabled.push(feature),
+                Some(_) => {}
+                None => log::warn!("unknown feature {name:?} in DISABLED_FEATURES; ignored"),
+            }
+        }
+        Self { disabled }
+    }
+
+    pub fn disabled(&self) -> &[Feature] {
+        &self.disabled
+    }
+
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        !self.disabled.contains(&feature)
+    }
+
+    pub fn ensure_enabled(&self, feature: Feature) -> Result<(), ApiError> {
+        if self.is_enabled(feature) {
+            Ok(())
+        } else {
+            metrics::increment_counter!("feature_disabled_requests", "feature" => feature.as_str());
+            Err(ApiError::FeatureDisabled(feature))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_known_names_and_skips_the_rest() {
+        let flags = FeatureFlags::parse(" uploads, nope,search,uploads,");
+        assert_eq!(flags.disabled(), [Feature::Uploads, Feature::Search]);
+        assert!(flags.is_enabled(Feature::NewBoards));
+        assert!(matches!(
+            flags.ensure_enabled(Feature::Search),
+            Err(ApiError::FeatureDisabled(Feature::Search))
+        ));
+        assert!(FeatureFlags::parse("").disabled().is_empty());
+    }
+}
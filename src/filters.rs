@@ -0,0 +1,218 @@
+//! Personal mute lists: hidden threads, muted authors and tripcodes, and
+//! keyword filters.
+//!
+//! Clients usually apply the list themselves; listings also honour
+//! `?apply_filters=1` for signed-in callers. Muted authors are stored by
+//! subject but only ever shown back as the post reference they were muted
+//! from, so the list does not reveal who wrote what.
+
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+use crate::models::{FilterKind, Id, Reply, Thread, UserFilter};
+
+/// Entries a subject may keep.
+pub const MAX_FILTERS: usize = 500;
+const MAX_KEYWORD_CHARS: usize = 64;
+const TRIPCODE_HEX_LEN: usize = 12;
+
+/// Post whose author a `subject` filter mutes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostRef {
+    Thread(Id),
+    Reply(Id),
+}
+
+impl PostRef {
+    pub fn parse(value: &str) -> Option<Self> {
+        let (kind, id) = value.split_once(':')?;
+        let id: Id = id.parse().ok().filter(|id| *id > 0)?;
+        match kind {
+            "thread" => Some(PostRef::Thread(id)),
+            "reply" => Some(PostRef::Reply(id)),
+            _ => None,
+        }
+    }
+}
+
+/// Canonical stored form of a filter value; the error says what was expected.
+pub fn normalize(kind: FilterKind, value: &str) -> Result<String, String> {
+    let value = value.trim();
+    match kind {
+        FilterKind::Thread => value
+            .parse::<Id>()
+            .ok()
+            .filter(|id| *id > 0)
+            .map(|id| id.to_string())
+            .ok_or_else(|| "thread filters take a thread id".to_string()),
+        FilterKind::Subject => match PostRef::parse(value) {
+            Some(PostRef::Thread(id)) => Ok(format!("thread:{id}")),
+            Some(PostRef::Reply(id)) => Ok(format!("reply:{id}")),
+            None => Err("subject filters take a post reference like thread:12 or reply:34".into()),
+        },
+        FilterKind::Tripcode => {
+            let hex = value
+                .strip_prefix('!')
+                .unwrap_or(value)
+                .to_ascii_lowercase();
+            if hex.len() == TRIPCODE_HEX_LEN && hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                Ok(format!("!{hex}"))
+            } else {
+                Err("tripcode filters take a tripcode like !1a2b3c4d5e6f".into())
+            }
+        }
+        FilterKind::Keyword => {
+            let keyword = value
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase();
+            if keyword.is_empty() || keyword.chars().count() > MAX_KEYWORD_CHARS {
+                Err(format!(
+                    "keywords must be 1 to {MAX_KEYWORD_CHARS} characters"
+                ))
+            } else {
+                Ok(keyword)
+            }
+        }
+    }
+}
+
+/// Private author subject recorded in a post's `created_by`.
+pub fn author_subject(created_by: &Value) -> Option<&str> {
+    created_by.get("subject")?.as_str()
+}
+
+/// A subject's filters, ready to match against listings.
+#[derive(Debug, Default)]
+pub struct FilterSet {
+    threads: HashSet<Id>,
+    subjects: HashSet<String>,
+    tripcodes: HashSet<String>,
+    keywords: Vec<String>,
+}
+
+impl FilterSet {
+    pub fn new(filters: &[UserFilter]) -> Self {
+        let mut set = Self::default();
+        for filter in filters {
+            match filter.kind {
+                FilterKind::Thread => set.threads.extend(filter.value.parse::<Id>().ok()),
+                FilterKind::Subject => set.subjects.extend(filter.target_subject.clone()),
+                FilterKind::Tripcode => {
+                    set.tripcodes.insert(filter.value.clone());
+                }
+                FilterKind::Keyword => set.keywords.push(filter.value.clone()),
+            }
+        }
+        set
+    }
+
+    fn hides_author(&self, created_by: &Value, tripcode: Option<&str>) -> bool {
+        author_subject(created_by).is_some_and(|subject| self.subjects.contains(subject))
+            || tripcode.is_some_and(|tripcode| self.tripcodes.contains(tripcode))
+    }
+
+    fn hides_text(&self, texts: &[&str]) -> bool {
+        if self.keywords.is_empty() {
+            return false;
+        }
+        let texts: Vec<String> = texts.iter().map(|text| text.to_lowercase()).collect();
+        self.keywords
+            .iter()
+            .any(|keyword| texts.iter().any(|text| text.contains(keyword.as_str())))
+    }
+
+    pub fn hides_thread(&self, thread: &Thread) -> bool {
+        self.threads.contains(&thread.id)
+            || self.hides_author(&thread.created_by, thread.tripcode.as_deref())
+            || self.hides_text(&[&thread.subject, &thread.body])
+    }
+
+    /// Replies are matched by author and text; hiding a thread does not hide
+    /// its replies once the thread is opened.
+    pub fn hides_reply(&self, reply: &Reply) -> bool {
+        self.hides_author(&reply.created_by, reply.tripcode.as_deref())
+            || self.hides_text(&[&reply.content])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn filter(kind: FilterKind, value: &str, target_subject: Option<&str>) -> UserFilter {
+        UserFilter {
+            id: 1,
+            kind,
+            value: value.into(),
+            created_at: Utc::now(),
+            target_subject: target_subject.map(Into::into),
+        }
+    }
+
+    fn thread(id: Id, subject: &str, author: &str, tripcode: Option<&str>) -> Thread {
+        Thread {
+            id,
+            board_id: 1,
+            subject: subject.into(),
+            body: "body".into(),
+            created_at: Utc::now(),
+            bump_time: Utc::now(),
+            image_hash: None,
+            mime: None,
+            image_size: None,
+            author_name: None,
+            tripcode: tripcode.map(Into::into),
+            deleted_at: None,
+            closed_at: None,
+            archived_at: None,
+            pinned_reply_id: None,
+            tags: Vec::new(),
+            reply_count: 0,
+            image_count: 0,
+            slug: String::new(),
+            created_by: json!({"v": 1, "subject": author}),
+            author: None,
+        }
+    }
+
+    #[test]
+    fn values_are_normalized() {
+        assert_eq!(normalize(FilterKind::Thread, " 42 ").unwrap(), "42");
+        assert!(normalize(FilterKind::Thread, "0").is_err());
+        assert_eq!(
+            normalize(FilterKind::Subject, "reply:7").unwrap(),
+            "reply:7"
+        );
+        assert!(normalize(FilterKind::Subject, "discord:123").is_err());
+        assert_eq!(
+            normalize(FilterKind::Tripcode, "1A2B3C4D5E6F").unwrap(),
+            "!1a2b3c4d5e6f"
+        );
+        assert!(normalize(FilterKind::Tripcode, "!short").is_err());
+        assert_eq!(
+            normalize(FilterKind::Keyword, "  Spoiler   Alert ").unwrap(),
+            "spoiler alert"
+        );
+        assert!(normalize(FilterKind::Keyword, "   ").is_err());
+    }
+
+    #[test]
+    fn filter_set_matches_threads_authors_and_keywords() {
+        let set = FilterSet::new(&[
+            filter(FilterKind::Thread, "3", None),
+            filter(FilterKind::Subject, "thread:9", Some("discord:muted")),
+            filter(FilterKind::Tripcode, "!1a2b3c4d5e6f", None),
+            filter(FilterKind::Keyword, "spoiler", None),
+        ]);
+        assert!(set.hides_thread(&thread(3, "fine", "discord:a", None)));
+        assert!(set.hides_thread(&thread(4, "fine", "discord:muted", None)));
+        assert!(set.hides_thread(&thread(5, "fine", "discord:a", Some("!1a2b3c4d5e6f"))));
+        assert!(set.hides_thread(&thread(6, "Big SPOILER inside", "discord:a", None)));
+        assert!(!set.hides_thread(&thread(7, "fine", "discord:a", Some("!ffffffffffff"))));
+    }
+}
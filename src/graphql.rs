@@ -0,0 +1,426 @@
+use actix_web::{web, HttpResponse};
+use async_graphql::connection::{self, Connection, Edge};
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, ErrorExtensions, Object, OutputType, Schema,
+    SimpleObject,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use crate::error::ApiError;
+use crate::models::{Board, Id, Reply, SearchHit, Thread, ThreadOrder};
+use crate::repo::Repo;
+use crate::reporting::{self, ErrorEvent, ErrorKind};
+use crate::routes::AppState;
+use crate::service;
+
+const DEFAULT_PAGE: usize = 50;
+const MAX_PAGE: usize = 100;
+
+pub type RibSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Process-wide schema; per-request state (app data, loaders) travels with the request.
+pub fn schema() -> &'static RibSchema {
+    static SCHEMA: OnceLock<RibSchema> = OnceLock::new();
+    SCHEMA.get_or_init(|| {
+        Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+            .limit_depth(12)
+            .limit_complexity(2000)
+            .finish()
+    })
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("/graphql", web::post().to(graphql));
+}
+
+async fn graphql(
+    data: web::Data<AppState>,
+    req: web::Json<async_graphql::Request>,
+) -> HttpResponse {
+    let repo = data.repo.clone();
+    let request = req
+        .into_inner()
+        .data(data)
+        .data(DataLoader::new(BoardLoader(repo.clone()), tokio::spawn))
+        .data(DataLoader::new(ThreadLoader(repo.clone()), tokio::spawn))
+        .data(DataLoader::new(RepliesLoader(repo), tokio::spawn));
+    HttpResponse::Ok().json(schema().execute(request).await)
+}
+
+fn field_error(e: ApiError) -> async_graphql::Error {
+    if matches!(e, ApiError::Internal) {
+        reporting::report(ErrorEvent::in_current_request(
+            ErrorKind::Internal,
+            "internal error in graphql resolver",
+        ));
+    }
+    async_graphql::Error::new(e.to_string()).extend_with(|_, ext| ext.set("code", e.code()))
+}
+
+/// Missing (or hidden) resources resolve to `null` rather than an error.
+fn optional<T>(result: Result<T, ApiError>) -> async_graphql::Result<Option<T>> {
+    match result {
+        Ok(v) => Ok(Some(v)),
+        Err(ApiError::NotFound) => Ok(None),
+        Err(e) => Err(field_error(e)),
+    }
+}
+
+fn state<'a>(ctx: &Context<'a>) -> &'a web::Data<AppState> {
+    ctx.data_unchecked::<web::Data<AppState>>()
+}
+
+/// Boards by id for `Thread.board`.
+pub struct BoardLoader(Arc<dyn Repo>);
+
+impl Loader<Id> for BoardLoader {
+    type Value = Board;
+    type Error = Arc<ApiError>;
+
+    async fn load(&self, keys: &[Id]) -> Result<HashMap<Id, Board>, Self::Error> {
+        let boards = self
+            .0
+            .get_boards(keys)
+            .await
+            .map_err(|e| Arc::new(e.into()))?;
+        Ok(boards.into_iter().map(|b| (b.id, b)).collect())
+    }
+}
+
+/// Threads by id for `Reply.thread` and search hits.
+pub struct ThreadLoader(Arc<dyn Repo>);
+
+impl Loader<Id> for ThreadLoader {
+    type Value = Thread;
+    type Error = Arc<ApiError>;
+
+    async fn load(&self, keys: &[Id]) -> Result<HashMap<Id, Thread>, Self::Error> {
+        let threads = self
+            .0
+            .get_threads(keys)
+            .await
+            .map_err(|e| Arc::new(e.into()))?;
+        Ok(threads.into_iter().map(|t| (t.id, t)).collect())
+    }
+}
+
+/// Visible replies keyed by thread id, so a page of threads loads its replies in one query.
+pub struct RepliesLoader(Arc<dyn Repo>);
+
+impl Loader<Id> for RepliesLoader {
+    type Value = Vec<Reply>;
+    type Error = Arc<ApiError>;
+
+    async fn load(&self, keys: &[Id]) -> Result<HashMap<Id, Vec<Reply>>, Self::Error> {
+        let replies = self
+            .0
+            .list_replies_for_threads(keys, false)
+            .await
+            .map_err(|e| Arc::new(e.into()))?;
+        let mut by_thread: HashMap<Id, Vec<Reply>> =
+            keys.iter().map(|id| (*id, Vec::new())).collect();
+        for reply in replies {
+            by_thread.entry(reply.thread_id).or_default().push(reply);
+        }
+        Ok(by_thread)
+    }
+}
+
+async fn load_one<L, V>(ctx: &Context<'_>, id: Id) -> async_graphql::Result<Option<V>>
+where
+    L: Loader<Id, Value = V, Error = Arc<ApiError>>,
+    V: Send + Sync + Clone + 'static,
+{
+    ctx.data_unchecked::<DataLoader<L>>()
+        .load_one(id)
+        .await
+        .map_err(|e| field_error(Arc::try_unwrap(e).unwrap_or(ApiError::Internal)))
+}
+
+#[derive(SimpleObject)]
+pub struct ListInfo {
+    /// Number of items across all pages.
+    total_count: usize,
+}
+
+pub type Page<T> = Connection<usize, T, ListInfo>;
+
+/// Relay-style pagination over an already loaded, ordered list. Cursors are
+/// positions, so they stay valid as long as the list order does.
+async fn paginate<T: OutputType>(
+    items: Vec<T>,
+    after: Option<String>,
+    before: Option<String>,
+    first: Option<i32>,
+    last: Option<i32>,
+) -> async_graphql::Result<Page<T>> {
+    connection::query(
+        after,
+        before,
+        first,
+        last,
+        |after: Option<usize>, before: Option<usize>, first, last| async move {
+            let total = items.len();
+            let mut start = after.map(|a| a + 1).unwrap_or(0).min(total);
+            let mut end = before.unwrap_or(total).clamp(start, total);
+            match (first, last) {
+                (Some(first), _) => end = end.min(start + first.min(MAX_PAGE)),
+                (None, Some(last)) => start = start.max(end.saturating_sub(last.min(MAX_PAGE))),
+                (None, None) => end = end.min(start + DEFAULT_PAGE),
+            }
+            let mut page = Connection::with_additional_fields(
+                start > 0,
+                end < total,
+                ListInfo { total_count: total },
+            );
+            page.edges.extend(
+                items
+                    .into_iter()
+                    .enumerate()
+                    .skip(start)
+                    .take(end - start)
+                    .map(|(i, item)| Edge::new(i, item)),
+            );
+            Ok::<_, async_graphql::Error>(page)
+        },
+    )
+    .await
+}
+
+pub struct BoardNode(Board);
+
+#[Object(name = "Board")]
+impl BoardNode {
+    async fn id(&self) -> Id {
+        self.0.id
+    }
+    async fn slug(&self) -> &str {
+        &self.0.slug
+    }
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+    /// Posting without signing in is allowed.
+    async fn anonymous_posting(&self) -> bool {
+        self.0.anonymous_posting
+    }
+    /// Threads, most recently bumped first.
+    async fn threads(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<Page<ThreadNode>> {
+        let threads =
+            service::list_threads(state(ctx), self.0.id, false, ThreadOrder::default(), None)
+                .await
+                .map_err(field_error)?;
+        paginate(
+            threads.into_iter().map(ThreadNode).collect(),
+            after,
+            before,
+            first,
+            last,
+        )
+        .await
+    }
+}
+
+pub struct ThreadNode(Thread);
+
+#[Object(name = "Thread")]
+impl ThreadNode {
+    async fn id(&self) -> Id {
+        self.0.id
+    }
+    async fn board_id(&self) -> Id {
+        self.0.board_id
+    }
+    async fn subject(&self) -> &str {
+        &self.0.subject
+    }
+    async fn body(&self) -> &str {
+        &self.0.body
+    }
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+    async fn bump_time(&self) -> DateTime<Utc> {
+        self.0.bump_time
+    }
+    async fn image_hash(&self) -> Option<&str> {
+        self.0.image_hash.as_deref()
+    }
+    async fn mime(&self) -> Option<&str> {
+        self.0.mime.as_deref()
+    }
+    async fn author_name(&self) -> Option<&str> {
+        self.0.author_name.as_deref()
+    }
+    async fn tripcode(&self) -> Option<&str> {
+        self.0.tripcode.as_deref()
+    }
+    async fn reply_count(&self) -> i32 {
+        self.0.reply_count
+    }
+    async fn image_count(&self) -> i32 {
+        self.0.image_count
+    }
+    async fn board(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<BoardNode>> {
+        let board = load_one::<BoardLoader, _>(ctx, self.0.board_id).await?;
+        Ok(board.filter(|b| b.deleted_at.is_none()).map(BoardNode))
+    }
+    /// Replies, oldest first.
+    async fn replies(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<Page<ReplyNode>> {
+        let replies = load_one::<RepliesLoader, _>(ctx, self.0.id)
+            .await?
+            .unwrap_or_default();
+        paginate(
+            replies.into_iter().map(ReplyNode).collect(),
+            after,
+            before,
+            first,
+            last,
+        )
+        .await
+    }
+}
+
+pub struct ReplyNode(Reply);
+
+#[Object(name = "Reply")]
+impl ReplyNode {
+    async fn id(&self) -> Id {
+        self.0.id
+    }
+    async fn thread_id(&self) -> Id {
+        self.0.thread_id
+    }
+    async fn content(&self) -> &str {
+        &self.0.content
+    }
+    async fn image_hash(&self) -> Option<&str> {
+        self.0.image_hash.as_deref()
+    }
+    async fn mime(&self) -> Option<&str> {
+        self.0.mime.as_deref()
+    }
+    async fn author_name(&self) -> Option<&str> {
+        self.0.author_name.as_deref()
+    }
+    async fn tripcode(&self) -> Option<&str> {
+        self.0.tripcode.as_deref()
+    }
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+    async fn thread(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<ThreadNode>> {
+        let thread = load_one::<ThreadLoader, _>(ctx, self.0.thread_id).await?;
+        Ok(thread.filter(|t| t.deleted_at.is_none()).map(ThreadNode))
+    }
+}
+
+pub struct SearchHitNode(SearchHit);
+
+#[Object(name = "SearchHit")]
+impl SearchHitNode {
+    /// `thread` or `reply`.
+    async fn kind(&self) -> &str {
+        &self.0.kind
+    }
+    async fn id(&self) -> Id {
+        self.0.id
+    }
+    async fn thread_id(&self) -> Id {
+        self.0.thread_id
+    }
+    async fn board_id(&self) -> Id {
+        self.0.board_id
+    }
+    async fn excerpt(&self) -> &str {
+        &self.0.excerpt
+    }
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+    async fn thread(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<ThreadNode>> {
+        let thread = load_one::<ThreadLoader, _>(ctx, self.0.thread_id).await?;
+        Ok(thread.filter(|t| t.deleted_at.is_none()).map(ThreadNode))
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct SearchResultsNode {
+    /// Engine that answered: `postgres`, `meilisearch`, or `elasticsearch`.
+    backend: String,
+    hits: Vec<SearchHitNode>,
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn boards(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<Page<BoardNode>> {
+        let boards = service::list_boards(state(ctx), false, None)
+            .await
+            .map_err(field_error)?;
+        paginate(
+            boards.into_iter().map(BoardNode).collect(),
+            after,
+            before,
+            first,
+            last,
+        )
+        .await
+    }
+
+    async fn board(&self, ctx: &Context<'_>, id: Id) -> async_graphql::Result<Option<BoardNode>> {
+        Ok(optional(service::get_board(state(ctx), id, false, None).await)?.map(BoardNode))
+    }
+
+    async fn thread(&self, ctx: &Context<'_>, id: Id) -> async_graphql::Result<Option<ThreadNode>> {
+        Ok(optional(service::get_thread(state(ctx), id, false, None).await)?.map(ThreadNode))
+    }
+
+    async fn reply(&self, ctx: &Context<'_>, id: Id) -> async_graphql::Result<Option<ReplyNode>> {
+        Ok(optional(service::get_reply(state(ctx), id, false, None).await)?.map(ReplyNode))
+    }
+
+    async fn search(
+        &self,
+        ctx: &Context<'_>,
+        q: String,
+        board_id: Option<Id>,
+        limit: Option<i64>,
+    ) -> async_graphql::Result<SearchResultsNode> {
+        let results = service::search(state(ctx), &q, board_id, limit, None)
+            .await
+            .map_err(field_error)?;
+        Ok(SearchResultsNode {
+            backend: results.backend,
+            hits: results.hits.into_iter().map(SearchHitNode).collect(),
+        })
+    }
+}
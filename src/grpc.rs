@@ -0,0 +1,314 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use tonic::{Request, Response, Status};
+
+use crate::auth::{decode_jwt, Auth};
+use crate::error::ApiError;
+use crate::models;
+use crate::pow::POW_HEADER;
+use crate::routes::AppState;
+use crate::service;
+
+pub mod pb {
+    tonic::include_proto!("rib.v1");
+}
+
+use pb::rib_server::{Rib, RibServer};
+
+/// gRPC listener settings; the service shares the HTTP server's repo and auth.
+#[derive(Clone, Debug)]
+pub struct GrpcConfig {
+    /// `None` when `GRPC_PORT=0`.
+    pub addr: Option<SocketAddr>,
+}
+
+impl GrpcConfig {
+    pub fn from_env() -> Self {
+        let port = std::env::var("GRPC_PORT")
+            .ok()
+            .and_then(|v| v.parse::<u16>().ok())
+            .unwrap_or(50051);
+        Self {
+            addr: (port != 0).then(|| SocketAddr::from(([0, 0, 0, 0], port))),
+        }
+    }
+}
+
+/// Serve the gRPC API until the task is aborted.
+pub async fn serve(state: Arc<AppState>, addr: SocketAddr) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(RibServer::new(RibService::new(state)))
+        .serve(addr)
+        .await
+}
+
+pub struct RibService {
+    state: Arc<AppState>,
+}
+
+impl RibService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+}
+
+fn status(e: ApiError) -> Status {
+    let message = e.to_string();
+    match e {
+        ApiError::NotFound => Status::not_found(message),
+        ApiError::Conflict | ApiError::Duplicate(_) => Status::already_exists(message),
+        ApiError::LastAdmin | ApiError::ConfirmationRequired => {
+            Status::failed_precondition(message)
+        }
+        ApiError::Unauthorized => Status::unauthenticated(message),
+        ApiError::Forbidden | ApiError::Banned(_) | ApiError::Withheld(_) => {
+            Status::permission_denied(message)
+        }
+        ApiError::BadRequest | ApiError::Invalid(_) => Status::invalid_argument(message),
+        ApiError::RateLimited { .. } => Status::resource_exhausted(message),
+        ApiError::Unavailable | ApiError::FeatureDisabled(_) => Status::unavailable(message),
+        ApiError::InsufficientFunds | ApiError::Unprocessable => {
+            Status::failed_precondition(message)
+        }
+        ApiError::Internal => Status::internal(message),
+    }
+}
+
+/// Same bearer tokens as the HTTP API, carried in `authorization` metadata.
+/// `None` without metadata, for boards that allow anonymous posting.
+fn authenticate<T>(request: &Request<T>) -> Result<Option<Auth>, Status> {
+    let Some(header) = request.metadata().get("authorization") else {
+        return Ok(None);
+    };
+    let token = header
+        .to_str()
+        .ok()
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(|| Status::unauthenticated("invalid token"))?;
+    decode_jwt(token)
+        .map(|claims| Some(Auth(claims)))
+        .map_err(|_| Status::unauthenticated("invalid token"))
+}
+
+fn pow_token<T>(request: &Request<T>) -> Option<String> {
+    request
+        .metadata()
+        .get(POW_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned)
+}
+
+fn client_ip<T>(request: &Request<T>) -> String {
+    request
+        .remote_addr()
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+impl From<models::Board> for pb::Board {
+    fn from(b: models::Board) -> Self {
+        Self {
+            id: b.id,
+            slug: b.slug,
+            title: b.title,
+            created_at: b.created_at.to_rfc3339(),
+            anonymous_posting: b.anonymous_posting,
+        }
+    }
+}
+
+impl From<models::Thread> for pb::Thread {
+    fn from(t: models::Thread) -> Self {
+        Self {
+            id: t.id,
+            board_id: t.board_id,
+            subject: t.subject,
+            body: t.body,
+            created_at: t.created_at.to_rfc3339(),
+            bump_time: t.bump_time.to_rfc3339(),
+            image_hash: t.image_hash,
+            mime: t.mime,
+            author_name: t.author_name,
+            tripcode: t.tripcode,
+        }
+    }
+}
+
+impl From<models::Reply> for pb::Reply {
+    fn from(r: models::Reply) -> Self {
+        Self {
+            id: r.id,
+            thread_id: r.thread_id,
+            content: r.content,
+            image_hash: r.image_hash,
+            mime: r.mime,
+            author_name: r.author_name,
+            tripcode: r.tripcode,
+            created_at: r.created_at.to_rfc3339(),
+        }
+    }
+}
+
+impl From<models::SearchHit> for pb::SearchHit {
+    fn from(h: models::SearchHit) -> Self {
+        Self {
+            kind: h.kind,
+            id: h.id,
+            thread_id: h.thread_id,
+            board_id: h.board_id,
+            excerpt: h.excerpt,
+            created_at: h.created_at.to_rfc3339(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Rib for RibService {
+    async fn list_boards(
+        &self,
+        request: Request<pb::ListBoardsRequest>,
+    ) -> Result<Response<pb::ListBoardsResponse>, Status> {
+        let auth = authenticate(&request)?;
+        let boards = service::list_boards(&self.state, false, auth.as_ref())
+            .await
+            .map_err(status)?;
+        Ok(Response::new(pb::ListBoardsResponse {
+            boards: boards.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn get_board(
+        &self,
+        request: Request<pb::GetBoardRequest>,
+    ) -> Result<Response<pb::Board>, Status> {
+        let auth = authenticate(&request)?;
+        let board = service::get_board(&self.state, request.into_inner().id, false, auth.as_ref())
+            .await
+            .map_err(status)?;
+        Ok(Response::new(board.into()))
+    }
+
+    async fn list_threads(
+        &self,
+        request: Request<pb::ListThreadsRequest>,
+    ) -> Result<Response<pb::ListThreadsResponse>, Status> {
+        let auth = authenticate(&request)?;
+        let threads = service::list_threads(
+            &self.state,
+            request.into_inner().board_id,
+            false,
+            models::ThreadOrder::default(),
+            auth.as_ref(),
+        )
+        .await
+        .map_err(status)?;
+        Ok(Response::new(pb::ListThreadsResponse {
+            threads: threads.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn get_thread(
+        &self,
+        request: Request<pb::GetThreadRequest>,
+    ) -> Result<Response<pb::Thread>, Status> {
+        let auth = authenticate(&request)?;
+        let thread =
+            service::get_thread(&self.state, request.into_inner().id, false, auth.as_ref())
+                .await
+                .map_err(status)?;
+        Ok(Response::new(thread.into()))
+    }
+
+    async fn list_replies(
+        &self,
+        request: Request<pb::ListRepliesRequest>,
+    ) -> Result<Response<pb::ListRepliesResponse>, Status> {
+        let auth = authenticate(&request)?;
+        let replies = service::list_replies(
+            &self.state,
+            request.into_inner().thread_id,
+            false,
+            models::SortOrder::Asc,
+            auth.as_ref(),
+        )
+        .await
+        .map_err(status)?;
+        Ok(Response::new(pb::ListRepliesResponse {
+            replies: replies.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn search(
+        &self,
+        request: Request<pb::SearchRequest>,
+    ) -> Result<Response<pb::SearchResponse>, Status> {
+        let auth = authenticate(&request)?;
+        let req = request.into_inner();
+        let results = service::search(&self.state, &req.q, req.board_id, req.limit, auth.as_ref())
+            .await
+            .map_err(status)?;
+        Ok(Response::new(pb::SearchResponse {
+            backend: results.backend,
+            hits: results.hits.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn create_thread(
+        &self,
+        request: Request<pb::CreateThreadRequest>,
+    ) -> Result<Response<pb::Thread>, Status> {
+        let auth = authenticate(&request)?;
+        let ip = client_ip(&request);
+        let pow = pow_token(&request);
+        let req = request.into_inner();
+        let new = models::NewThread {
+            board_id: req.board_id,
+            subject: req.subject,
+            body: req.body,
+            image_hash: req.image_hash,
+            mime: req.mime,
+            author_name: req.author_name,
+            tripcode_password: req.tripcode_password,
+            delete_password: req.delete_password,
+            tags: Vec::new(),
+        };
+        let poster = service::Poster {
+            auth: auth.as_ref(),
+            client_ip: &ip,
+            pow: pow.as_deref(),
+        };
+        let thread = service::create_thread(&self.state, poster, new)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(thread.into()))
+    }
+
+    async fn create_reply(
+        &self,
+        request: Request<pb::CreateReplyRequest>,
+    ) -> Result<Response<pb::Reply>, Status> {
+        let auth = authenticate(&request)?;
+        let ip = client_ip(&request);
+        let pow = pow_token(&request);
+        let req = request.into_inner();
+        let new = models::NewReply {
+            thread_id: req.thread_id,
+            content: req.content,
+            image_hash: req.image_hash,
+            mime: req.mime,
+            author_name: req.author_name,
+            tripcode_password: req.tripcode_password,
+            delete_password: req.delete_password,
+        };
+        let poster = service::Poster {
+            auth: auth.as_ref(),
+            client_ip: &ip,
+            pow: pow.as_deref(),
+        };
+        let reply = service::create_reply(&self.state, poster, new)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(reply.into()))
+    }
+}
@@ -0,0 +1,145 @@
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::Error;
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use std::rc::Rc;
+use std::time::Instant;
+
+/// Histogram buckets (seconds) for `http_request_duration_seconds`.
+pub const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+const EXCLUDED_PATHS: &[&str] = &["/metrics"];
+
+/// Route template the request matched (e.g. `/api/v1/threads/{id}`), keeping
+/// label cardinality bounded regardless of ids in the path.
+pub(crate) fn route_label(req: &ServiceRequest) -> String {
+    req.match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string())
+}
+
+fn status_class(status: u16) -> &'static str {
+    match status {
+        100..=199 => "1xx",
+        200..=299 => "2xx",
+        300..=399 => "3xx",
+        400..=499 => "4xx",
+        _ => "5xx",
+    }
+}
+
+/// Middleware recording `http_requests_total` and `http_request_duration_seconds`
+/// by route template, method, and status class.
+#[derive(Clone, Default)]
+pub struct HttpMetrics;
+
+impl<S, B> Transform<S, ServiceRequest> for HttpMetrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = HttpMetricsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(HttpMetricsMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct HttpMetricsMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for HttpMetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if EXCLUDED_PATHS.contains(&req.path()) {
+            return Box::pin(self.service.call(req));
+        }
+        let route = route_label(&req);
+        let method = req.method().to_string();
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let started = Instant::now();
+            let res = fut.await;
+            let status = match &res {
+                Ok(res) => res.status().as_u16(),
+                Err(e) => e.as_response_error().status_code().as_u16(),
+            };
+            let labels = [
+                ("route", route),
+                ("method", method),
+                ("status", status_class(status).to_string()),
+            ];
+            metrics::increment_counter!("http_requests_total", &labels);
+            metrics::histogram!(
+                "http_request_duration_seconds",
+                started.elapsed().as_secs_f64(),
+                &labels
+            );
+            res
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App, HttpResponse};
+    use metrics_exporter_prometheus::PrometheusBuilder;
+
+    #[actix_web::test]
+    async fn records_route_templates_and_skips_metrics_endpoint() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        metrics::set_boxed_recorder(Box::new(recorder)).expect("first recorder in this binary");
+
+        let app = test::init_service(
+            App::new()
+                .wrap(HttpMetrics)
+                .route("/things/{id}", web::get().to(HttpResponse::Ok))
+                .route("/metrics", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        for uri in ["/things/1", "/things/2", "/missing", "/metrics"] {
+            test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+        }
+
+        let rendered = handle.render();
+        let line = |needle: &str| {
+            rendered
+                .lines()
+                .find(|l| l.starts_with("http_requests_total") && l.contains(needle))
+                .map(str::to_string)
+        };
+        let things = line("route=\"/things/{id}\"").expect("things counter");
+        assert!(
+            things.contains("status=\"2xx\"") && things.ends_with(" 2"),
+            "{things}"
+        );
+        let missing = line("route=\"unmatched\"").expect("unmatched counter");
+        assert!(missing.contains("status=\"4xx\""), "{missing}");
+        assert!(line("/metrics").is_none(), "{rendered}");
+    }
+}
@@ -0,0 +1,152 @@
+//! Admin impersonation for support debugging.
+//!
+//! `POST /api/v1/admin/impersonate` issues an admin a short-lived token that
+//! acts as another subject, with that subject's role. The token keeps the
+//! admin in `sub` and the subject in `act_as`; [`crate::auth::decode_jwt`]
+//! swaps them, so handlers see the subject and `impersonated_by` names the
+//! admin. Issuing the token and every state-changing request made with it
+//! are recorded in `impersonation_log` by [`ImpersonationAudit`], which also
+//! marks each response with `X-Impersonating: <subject key>`. Admins cannot
+//! be impersonated and impersonation tokens cannot be refreshed.
+
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::Method;
+use actix_web::{web, Error};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use std::rc::Rc;
+
+use crate::auth::{decode_jwt, request_token};
+use crate::http_metrics::route_label;
+use crate::models::NewImpersonationEntry;
+use crate::routes::AppState;
+
+pub const IMPERSONATING_HEADER: &str = "x-impersonating";
+/// Token lifetime when the request names none.
+pub const DEFAULT_MINUTES: i64 = 15;
+pub const MAX_REASON_CHARS: usize = 500;
+/// Entries returned per log page.
+pub const MAX_LOG_ENTRIES: i64 = 500;
+
+/// Longest token an admin may ask for (`IMPERSONATION_MAX_MINUTES`, default 60).
+pub fn max_minutes() -> i64 {
+    std::env::var("IMPERSONATION_MAX_MINUTES")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(60)
+        .clamp(1, 24 * 60)
+}
+
+/// JWT subject of a signed-in subject key: `discord:<id>` signs in as
+/// `<id>:<name>`, the other providers as the key itself. `None` for keys
+/// that never sign in, such as anonymous posters.
+pub fn jwt_subject(subject_key: &str) -> Option<String> {
+    let (provider, id) = subject_key.split_once(':')?;
+    match provider {
+        "discord" => Some(format!("{id}:{id}")),
+        "btc" | "eth" | "nostr" | "email" => Some(subject_key.to_string()),
+        _ => None,
+    }
+}
+
+/// Middleware marking and auditing requests made with impersonation tokens.
+#[derive(Clone, Default)]
+pub struct ImpersonationAudit;
+
+impl<S, B> Transform<S, ServiceRequest> for ImpersonationAudit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ImpersonationAuditMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ImpersonationAuditMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct ImpersonationAuditMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for ImpersonationAuditMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let claims = request_token(&req).and_then(|token| decode_jwt(&token).ok());
+        let Some((sub, actor)) = claims.and_then(|c| c.impersonated_by.map(|actor| (c.sub, actor)))
+        else {
+            return Box::pin(self.service.call(req));
+        };
+        let subject = crate::routes::role_subject_key(&sub).unwrap_or(sub);
+        let audited = !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+        let method = req.method().to_string();
+        let route = route_label(&req);
+        let state = req.app_data::<web::Data<AppState>>().cloned();
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await;
+            if audited {
+                let status = match &res {
+                    Ok(res) => res.status(),
+                    Err(e) => e.as_response_error().status_code(),
+                };
+                let entry = NewImpersonationEntry::Request {
+                    actor,
+                    subject: subject.clone(),
+                    method,
+                    route,
+                    status: status.as_u16(),
+                };
+                match &state {
+                    Some(state) => {
+                        if let Err(e) = state.repo.record_impersonation(entry).await {
+                            log::error!("recording impersonated request failed: {e}");
+                        }
+                    }
+                    None => log::error!("impersonated request not recorded: no app state"),
+                }
+            }
+            let mut res = res?;
+            if let Ok(value) = HeaderValue::from_str(&subject) {
+                res.headers_mut()
+                    .insert(HeaderName::from_static(IMPERSONATING_HEADER), value);
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subject_keys_map_to_login_subjects() {
+        assert_eq!(jwt_subject("discord:42").as_deref(), Some("42:42"));
+        assert_eq!(jwt_subject("btc:bc1q").as_deref(), Some("btc:bc1q"));
+        assert_eq!(jwt_subject("anon:abc"), None);
+        assert_eq!(jwt_subject("nope"), None);
+    }
+}
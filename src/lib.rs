@@ -1,12 +1,78 @@
+pub mod access_policy;
+pub mod activitypub;
+pub mod api_v2;
+pub mod appeals;
+pub mod archive;
+pub mod archives;
 pub mod auth;
+pub mod beta;
+pub mod bots;
+pub mod bridges;
+pub mod cache;
+pub mod challenges;
+pub mod config_check;
+pub mod db;
+pub mod digest;
+pub mod duplicates;
 pub mod error;
+pub mod ethereum;
+pub mod excerpts;
+pub mod features;
+pub mod filters;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod http_metrics;
+pub mod impersonation;
+pub mod live;
+pub mod mailer;
+pub mod matrix;
+pub mod media_archive;
+pub mod metadata;
 pub mod models;
+pub mod negotiate;
+pub mod nostr;
+pub mod notify;
 pub mod openapi;
+pub mod outbox;
+pub mod panic_guard;
+pub mod pow;
+pub mod preferences;
+pub mod previews;
+pub mod profiles;
 pub mod rate_limit;
+pub mod readiness;
+pub mod reload;
+pub mod remote_media;
 pub mod repo;
+pub mod reporting;
+pub mod reports;
+pub mod retry;
+pub mod role_expiry;
+pub mod roles;
 pub mod routes;
+pub mod saved_searches;
+pub mod scheduled;
+pub mod search;
 pub mod security;
+pub mod seed;
+pub mod service;
+pub mod service_accounts;
+pub mod shedding;
+pub mod sitemap;
+pub mod slow_log;
+pub mod slugs;
+pub mod ssr;
 pub mod storage; // expose storage for routes // in-memory rate limiting
+pub mod system;
+pub mod tags;
+pub mod throttle;
+pub mod thumbnails;
+pub mod transfer;
+pub mod trust;
+pub mod uploads;
+pub mod version;
 
 // Re-export commonly used items for tests / external users
 pub use routes::btc_test_insert_challenge;
@@ -0,0 +1,251 @@
+use async_trait::async_trait;
+use futures_util::StreamExt as _;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::models::OutboxEvent;
+use crate::outbox::{events, EventSink};
+
+/// Process-local fan-out of post events to connected live-update clients.
+#[derive(Clone)]
+pub struct LiveHub {
+    tx: broadcast::Sender<OutboxEvent>,
+}
+
+impl Default for LiveHub {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl LiveHub {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity.max(1));
+        Self { tx }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<OutboxEvent> {
+        self.tx.subscribe()
+    }
+
+    /// Deliver to subscribers on this replica; returns how many received it.
+    pub fn publish_local(&self, event: OutboxEvent) -> usize {
+        // No subscribers is not an error: nobody is watching on this replica.
+        self.tx.send(event).unwrap_or(0)
+    }
+}
+
+/// Cross-replica transport feeding every replica's [`LiveHub`].
+#[async_trait]
+pub trait LiveBus: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn publish(&self, event: &OutboxEvent) -> anyhow::Result<()>;
+    /// Forward bus messages into the local hub until the task is aborted.
+    fn spawn_listener(self: Arc<Self>, hub: LiveHub) -> JoinHandle<()>;
+}
+
+/// Redis pub/sub bridge: publishes on one channel every replica subscribes to.
+pub struct RedisLiveBus {
+    client: redis::Client,
+    channel: String,
+    publisher: tokio::sync::OnceCell<redis::aio::MultiplexedConnection>,
+}
+
+impl RedisLiveBus {
+    pub fn new(url: &str, channel: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: redis::Client::open(url)?,
+            channel: channel.to_string(),
+            publisher: tokio::sync::OnceCell::new(),
+        })
+    }
+
+    async fn listen(&self, hub: &LiveHub) -> anyhow::Result<()> {
+        let mut pubsub = self.client.get_async_pubsub().await?;
+        pubsub.subscribe(&self.channel).await?;
+        log::info!("live updates subscribed to redis channel {}", self.channel);
+        let mut messages = pubsub.on_message();
+        while let Some(message) = messages.next().await {
+            let payload: String = message.get_payload()?;
+            match serde_json::from_str::<OutboxEvent>(&payload) {
+                Ok(event) => {
+                    hub.publish_local(event);
+                }
+                Err(e) => log::warn!("ignoring malformed live event: {e}"),
+            }
+        }
+        anyhow::bail!("redis subscription closed")
+    }
+}
+
+#[async_trait]
+impl LiveBus for RedisLiveBus {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn publish(&self, event: &OutboxEvent) -> anyhow::Result<()> {
+        let mut conn = self
+            .publisher
+            .get_or_try_init(|| self.client.get_multiplexed_async_connection())
+            .await?
+            .clone();
+        let payload = serde_json::to_string(event)?;
+        redis::cmd("PUBLISH")
+            .arg(&self.channel)
+            .arg(payload)
+            .query_async::<()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    fn spawn_listener(self: Arc<Self>, hub: LiveHub) -> JoinHandle<()> {
+        actix_web::rt::spawn(async move {
+            loop {
+                if let Err(e) = self.listen(&hub).await {
+                    log::warn!("redis live listener error: {e}; reconnecting");
+                }
+                tokio::time::sleep(Duration::from_secs(2)).await;
+            }
+        })
+    }
+}
+
+/// Live-update settings derived from env.
+#[derive(Clone, Debug, Default)]
+pub struct LiveConfig {
+    pub bus: Option<String>,
+    pub redis_url: Option<String>,
+    pub redis_channel: String,
+}
+
+impl LiveConfig {
+    pub fn from_env() -> Self {
+        Self {
+            bus: std::env::var("LIVE_BUS")
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .map(|v| v.to_lowercase()),
+            redis_url: std::env::var("LIVE_REDIS_URL")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
+            redis_channel: std::env::var("LIVE_REDIS_CHANNEL")
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .unwrap_or_else(|| "rib:live".into()),
+        }
+    }
+
+    /// Returns `None` for single-replica deployments (local broadcast only).
+    /// `LIVE_BUS` defaults to `redis` when `LIVE_REDIS_URL` is set.
+    pub fn build(&self, pool: &sqlx::PgPool) -> anyhow::Result<Option<Arc<dyn LiveBus>>> {
+        let bus = self
+            .bus
+            .as_deref()
+            .or(self.redis_url.as_ref().map(|_| "redis"));
+        Ok(match bus {
+            None | Some("local") => None,
+            Some("redis") => {
+                let url = self.redis_url.as_deref().ok_or_else(|| {
+                    anyhow::anyhow!("LIVE_REDIS_URL must be set when LIVE_BUS=redis")
+                })?;
+                Some(Arc::new(RedisLiveBus::new(url, &self.redis_channel)?))
+            }
+            Some("postgres") => Some(Arc::new(crate::notify::PgLiveBus::new(pool.clone()))),
+            Some(other) => anyhow::bail!("unsupported LIVE_BUS '{other}'"),
+        })
+    }
+}
+
+/// Outbox sink publishing post events to live-update clients.
+///
+/// The relay claims each event on exactly one replica, so with a bus configured
+/// the event goes through the bus and every replica's listener re-broadcasts it.
+pub struct LiveSink {
+    hub: LiveHub,
+    bus: Option<Arc<dyn LiveBus>>,
+}
+
+impl LiveSink {
+    pub fn new(hub: LiveHub, bus: Option<Arc<dyn LiveBus>>) -> Self {
+        Self { hub, bus }
+    }
+}
+
+#[async_trait]
+impl EventSink for LiveSink {
+    fn name(&self) -> &'static str {
+        "live"
+    }
+
+    async fn deliver(&self, event: &OutboxEvent) -> anyhow::Result<()> {
+        if event.event_type == events::REPORT_CREATED {
+            return Ok(());
+        }
+        match &self.bus {
+            Some(bus) => bus.publish(event).await,
+            None => {
+                self.hub.publish_local(event.clone());
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Format an event as a server-sent events frame.
+pub fn sse_frame(event: &OutboxEvent) -> String {
+    format!(
+        "id: {}\nevent: {}\ndata: {}\n\n",
+        event.id,
+        event.event_type,
+        serde_json::to_string(event).unwrap_or_default()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outbox::events;
+
+    fn event(id: i64) -> OutboxEvent {
+        OutboxEvent {
+            id,
+            event_type: events::REPLY_CREATED.into(),
+            payload: serde_json::json!({"reply_id": 3, "thread_id": 2}),
+            created_at: chrono::Utc::now(),
+            attempts: 0,
+        }
+    }
+
+    #[actix_web::test]
+    async fn sink_without_bus_broadcasts_locally() {
+        let hub = LiveHub::default();
+        let mut rx = hub.subscribe();
+        LiveSink::new(hub.clone(), None)
+            .deliver(&event(7))
+            .await
+            .unwrap();
+        assert_eq!(rx.recv().await.unwrap().id, 7);
+    }
+
+    #[actix_web::test]
+    async fn sink_keeps_reports_from_live_clients() {
+        let hub = LiveHub::default();
+        let mut rx = hub.subscribe();
+        let sink = LiveSink::new(hub.clone(), None);
+        let mut report = event(8);
+        report.event_type = events::REPORT_CREATED.into();
+        sink.deliver(&report).await.unwrap();
+        sink.deliver(&event(9)).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap().id, 9);
+    }
+
+    #[test]
+    fn sse_frame_carries_type_and_id() {
+        let frame = sse_frame(&event(9));
+        assert!(frame.starts_with("id: 9\nevent: reply.created\ndata: {"));
+        assert!(frame.ends_with("\n\n"));
+    }
+}
@@ -65,16 +65,49 @@ async fn serve_frontend(req: HttpRequest) -> HttpResponse {
     }
 }
 
-use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
-use once_cell::sync::Lazy;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+use rib::access_policy::{AccessPolicy, PolicyConfig};
+use rib::activitypub::{FederationConfig, FederationRunner};
 use rib::auth::{Auth, Role};
+use rib::bots::{BotLimitConfig, BotLimits};
+use rib::bridges::{BridgeConfig, BridgeRunner};
+use rib::cache::BoardCache;
+use rib::challenges::ChallengeStoreConfig;
+use rib::db::{spawn_pool_metrics, PoolConfig};
+use rib::digest::{DigestConfig, DigestWorker};
+use rib::duplicates::{DuplicateConfig, DuplicateGuard};
+use rib::error::ApiError;
+use rib::http_metrics::{HttpMetrics, DURATION_BUCKETS};
+use rib::impersonation::ImpersonationAudit;
+use rib::live::{LiveConfig, LiveHub, LiveSink};
+use rib::mailer::MailerConfig;
+use rib::matrix::{MatrixConfig, MatrixMirrorRunner};
+use rib::media_archive::{MediaArchiveConfig, MediaArchiver};
+use rib::models::ServiceScope;
+use rib::notify::ChangeListener;
 use rib::openapi::ApiDoc;
-use rib::rate_limit::{InMemoryRateLimiter, RateLimitConfig, RateLimiterFacade};
+use rib::outbox::{OutboxConfig, OutboxRelay};
+use rib::panic_guard::CatchPanic;
+use rib::previews::PreviewConfig;
+use rib::rate_limit::{RateLimitAlgorithm, RateLimiterFacade};
+use rib::readiness::Readiness;
+use rib::reload::ConfigReloader;
 use rib::require_role; // macro
+use rib::retry::{ResilientRepo, RetryPolicy};
+use rib::role_expiry::{RoleExpiryConfig, RoleExpiryRunner};
 use rib::routes::{config, AppState};
+use rib::saved_searches::{SavedSearchConfig, SavedSearchMatcher};
+use rib::scheduled::{ScheduleConfig, ScheduledThreadRunner};
+use rib::search::{SearchConfig, SearchIndexSink};
 use rib::security::SecurityHeaders;
+use rib::shedding::{LoadShedder, ShedConfig};
+use rib::slow_log::{SlowLogConfig, SlowRequestLog, SLOW_BUCKETS};
 use rib::storage::build_image_store;
-use tracing::{info, Level};
+use rib::system::SystemInfo;
+use rib::throttle::Throttle;
+use rib::thumbnails::ThumbnailConfig;
+use rib::trust::TrustConfig;
+use tracing::{info, warn, Level};
 use tracing_actix_web::TracingLogger;
 use tracing_subscriber::EnvFilter;
 use utoipa::OpenApi; // bring trait into scope for ApiDoc::openapi()
@@ -94,6 +127,13 @@ async fn main() -> std::io::Result<()> {
         let _ = dotenvy::dotenv();
     }
 
+    // `rib check-config` reports on the configuration and exits; non-zero on any failure.
+    if std::env::args().nth(1).as_deref() == Some("check-config") {
+        let report = rib::config_check::check_all().await;
+        println!("{report}");
+        std::process::exit(if report.failed() { 1 } else { 0 });
+    }
+
     // Validate required environment variables
     validate_env_vars();
 
@@ -104,6 +144,35 @@ async fn main() -> std::io::Result<()> {
 
     info!("Bootstrapping RIB server");
 
+    // Flushes queued error reports when dropped at shutdown.
+    let _error_reporting = rib::reporting::init_from_env();
+    info!("Error reporting enabled: {}", _error_reporting.is_some());
+
+    // `/readyz` answers 503 until every warmup step below is ready.
+    let readiness = Readiness::new(&["metrics", "migrations", "image_store", "caches"]);
+    // Installed before anything records metrics, so startup work is counted too.
+    let prometheus = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Suffix("_slow_request_seconds".into()),
+            SLOW_BUCKETS,
+        )
+        .and_then(|b| {
+            b.set_buckets_for_metric(
+                Matcher::Suffix("_slow_operation_seconds".into()),
+                SLOW_BUCKETS,
+            )
+        })
+        .and_then(|b| {
+            b.set_buckets_for_metric(
+                Matcher::Full("http_request_duration_seconds".into()),
+                DURATION_BUCKETS,
+            )
+        })
+        .expect("valid histogram buckets")
+        .install_recorder()
+        .expect("install prometheus recorder");
+    readiness.mark_ready("metrics");
+
     // Log loaded configuration (non-sensitive)
     info!(
         "Discord OAuth configured: {}",
@@ -115,19 +184,18 @@ async fn main() -> std::io::Result<()> {
     );
 
     // Build Postgres repository (default and only backend now) and run migrations
+    let pool_cfg = PoolConfig::from_env();
     let repo = {
-        use sqlx::postgres::PgPoolOptions;
         use tokio::time::{sleep, Duration};
         let db_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        info!(
+            "Postgres pool: max {} / min {} connections",
+            pool_cfg.max_connections, pool_cfg.min_connections
+        );
         let mut attempts = 0u8;
         let pool = loop {
             attempts += 1;
-            match PgPoolOptions::new()
-                .max_connections(5)
-                .acquire_timeout(Duration::from_secs(5))
-                .connect(&db_url)
-                .await
-            {
+            match pool_cfg.connect(&db_url).await {
                 Ok(pool) => break pool,
                 Err(e) => {
                     if attempts >= 8 {
@@ -141,33 +209,282 @@ async fn main() -> std::io::Result<()> {
                 }
             }
         };
-        if let Err(e) = sqlx::migrate!().run(&pool).await {
-            panic!("Database migration failed: {e}");
+        let repo = rib::repo::pg::PgRepo::new(pool);
+        if rib::db::auto_migrate_enabled() {
+            if let Err(e) = rib::db::MIGRATOR.run(repo.pool()).await {
+                panic!("Database migration failed: {e}");
+            }
+            info!("Postgres migrations applied");
+        } else {
+            // Migrations are applied out of band; refuse to serve against a stale schema.
+            use rib::repo::SchemaRepo;
+            let applied = repo
+                .applied_migrations()
+                .await
+                .unwrap_or_else(|e| panic!("Reading migration status failed: {e}"));
+            let status = rib::db::MigrationStatus::compare(&rib::db::MIGRATOR, &applied);
+            if !status.up_to_date {
+                let pending: Vec<String> = status
+                    .pending
+                    .iter()
+                    .map(|m| format!("{} {}", m.version, m.description))
+                    .collect();
+                panic!(
+                    "DB_AUTO_MIGRATE is off and the schema is not current (version {:?}, latest {:?}, pending [{}]); apply migrations with `sqlx migrate run` first",
+                    status.schema_version,
+                    status.latest_version,
+                    pending.join(", ")
+                );
+            }
+            info!("Postgres schema at version {:?}", status.schema_version);
         }
-        info!("Postgres migrations applied");
         info!("Using Postgres repository backend");
-        rib::repo::pg::PgRepo::new(pool)
+        match std::env::var("DATABASE_READ_URL")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+        {
+            Some(read_url) => {
+                let read_pool = pool_cfg
+                    .connect_lazy(&read_url)
+                    .expect("DATABASE_READ_URL must be a valid Postgres URL");
+                info!("Routing listing and search reads to read replica");
+                repo.with_read_pool(read_pool)
+            }
+            None => repo,
+        }
     };
 
+    readiness.mark_ready("migrations");
+
     let openapi = ApiDoc::openapi();
     let image_store = build_image_store().await; // FS or S3 depending on feature/env
+    readiness.mark_ready("image_store");
     info!("OpenAPI spec generated");
 
+    // `rib seed` populates demo content and exits; SEED_DEMO_DATA=1 does the same before serving.
+    let seed_only = std::env::args().nth(1).as_deref() == Some("seed");
+    let seed_on_boot = std::env::var("SEED_DEMO_DATA")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    if seed_only || seed_on_boot {
+        match rib::seed::seed_demo_data(&repo, image_store.as_ref()).await {
+            Ok(summary) if summary.seeded => info!(
+                "Seeded demo data: {} boards, {} threads, {} replies, {} images, {} roles",
+                summary.boards, summary.threads, summary.replies, summary.images, summary.roles
+            ),
+            Ok(_) => info!("Demo data already present; skipping seed"),
+            Err(e) if seed_only => {
+                return Err(std::io::Error::other(format!("seeding failed: {e}")));
+            }
+            Err(e) => warn!("Demo data seeding failed: {e}"),
+        }
+        if seed_only {
+            return Ok(());
+        }
+    }
+
     // Pre-build shared components to move into closure cheaply
+    let slow_log_cfg = SlowLogConfig::from_env();
+    // Rate limits, throttle caps, CORS origins and upload policy reload on SIGHUP.
+    let reloader = ConfigReloader::from_env();
+    let throttle = Throttle::new(reloader.throttle.clone());
+    let shedder = LoadShedder::new(ShedConfig::from_env());
+    let bot_limits = BotLimits::new(BotLimitConfig::from_env(), RateLimitAlgorithm::from_env());
+    let access_policy = AccessPolicy::new(PolicyConfig::from_env())
+        .unwrap_or_else(|e| panic!("Loading access policy failed: {e}"));
     let rl_enabled = std::env::var("RL_ENABLED")
         .map(|v| v == "true" || v == "1")
         .unwrap_or(false);
     let rate_limiter_global = if rl_enabled {
-        Some(RateLimiterFacade::new(
-            InMemoryRateLimiter::new(true),
-            RateLimitConfig::from_env(),
+        Some(RateLimiterFacade::with_algorithm(
+            RateLimitAlgorithm::from_env(),
+            reloader.rate_limits.clone(),
         ))
     } else {
         None
     };
-    let repo_arc = std::sync::Arc::new(repo);
+    let pool = repo.pool().clone();
+    // Background tasks hold pooled connections; abort them before the runtime exits.
+    let mut listeners = vec![spawn_pool_metrics(
+        pool.clone(),
+        "primary",
+        pool_cfg.metrics_interval,
+    )];
+    listeners.extend(shedder.spawn_probe(pool.clone()));
+    listeners.extend(access_policy.spawn_reloader());
+    listeners.extend(reloader.spawn_signal_handler());
+    let mut system = SystemInfo::default().with_pool("primary", pool.clone());
+    if let Some(read_pool) = repo.read_pool() {
+        listeners.push(spawn_pool_metrics(
+            read_pool.clone(),
+            "replica",
+            pool_cfg.metrics_interval,
+        ));
+        system = system.with_pool("replica", read_pool.clone());
+    }
+    let system = std::sync::Arc::new(system);
+    let repo_arc = std::sync::Arc::new(ResilientRepo::new(repo, RetryPolicy::from_env()));
+    let board_cache = BoardCache::default();
+    let duplicates = std::sync::Arc::new(DuplicateGuard::new(DuplicateConfig::from_env()));
+    let trust = TrustConfig::from_env();
+    let uploads = reloader.uploads.clone();
+    let outbox_wakeup = std::sync::Arc::new(tokio::sync::Notify::new());
+    let pg_notify_enabled = std::env::var("PG_NOTIFY_ENABLED")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(true);
+    if pg_notify_enabled {
+        info!("Listening for Postgres change notifications");
+        listeners.push(
+            ChangeListener::new(pool.clone())
+                .with_board_cache(board_cache.clone())
+                .with_outbox_wakeup(outbox_wakeup.clone())
+                .spawn(),
+        );
+    }
+    let search_backend = SearchConfig::from_env()
+        .build()
+        .expect("search backend configuration");
+    let mailer = MailerConfig::from_env()
+        .build()
+        .expect("mailer configuration");
+    if let Some(mailer) = &mailer {
+        info!("Email login enabled via {}", mailer.name());
+        let digest_cfg = DigestConfig::from_env();
+        if digest_cfg.enabled {
+            info!("Reply digests polling every {:?}", digest_cfg.poll_interval);
+            DigestWorker::new(repo_arc.clone(), mailer.clone(), digest_cfg).spawn();
+        }
+    }
+    let previews = PreviewConfig::from_env()
+        .build()
+        .expect("preview renderer configuration");
+    if let Some(previews) = &previews {
+        info!("Document thumbnails rendered via {}", previews.name());
+    }
+    let thumbnails = ThumbnailConfig::from_env();
+    if thumbnails.enabled {
+        info!("Image thumbnails up to {}px", thumbnails.max_dim);
+    }
+    let schedule_cfg = ScheduleConfig::from_env();
+    if schedule_cfg.enabled {
+        info!(
+            "Scheduled threads polling every {:?}",
+            schedule_cfg.poll_interval
+        );
+        ScheduledThreadRunner::new(repo_arc.clone(), schedule_cfg).spawn();
+    }
+    let bridge_cfg = BridgeConfig::from_env();
+    if bridge_cfg.enabled {
+        info!("Board bridges polling every {:?}", bridge_cfg.poll_interval);
+        BridgeRunner::new(repo_arc.clone(), bridge_cfg)
+            .expect("bridge configuration")
+            .spawn();
+    }
+    let matrix_cfg = MatrixConfig::from_env();
+    if matrix_cfg.enabled && matrix_cfg.is_configured() {
+        info!(
+            "Matrix mirrors polling every {:?}",
+            matrix_cfg.poll_interval
+        );
+        MatrixMirrorRunner::new(repo_arc.clone(), matrix_cfg)
+            .expect("matrix mirror configuration")
+            .spawn();
+    }
+    let media_archive_cfg = MediaArchiveConfig::from_env();
+    if media_archive_cfg.enabled {
+        info!(
+            "Archived thread media moved every {:?}",
+            media_archive_cfg.poll_interval
+        );
+        MediaArchiver::new(repo_arc.clone(), image_store.clone(), media_archive_cfg).spawn();
+    }
+    let role_expiry_cfg = RoleExpiryConfig::from_env();
+    if role_expiry_cfg.enabled {
+        info!(
+            "Temporary roles checked every {:?}",
+            role_expiry_cfg.poll_interval
+        );
+        RoleExpiryRunner::new(repo_arc.clone(), mailer.clone(), role_expiry_cfg).spawn();
+    }
+    let federation_cfg = FederationConfig::from_env();
+    let federation = federation_cfg.build().expect("ActivityPub configuration");
+    if let Some(federation) = &federation {
+        info!(
+            "Boards federated over ActivityPub at {}",
+            federation.actor_id("{slug}")
+        );
+        FederationRunner::new(repo_arc.clone(), federation.clone(), federation_cfg).spawn();
+    }
+    let saved_search_cfg = SavedSearchConfig::from_env();
+    if saved_search_cfg.enabled {
+        info!(
+            "Saved searches matched every {:?}",
+            saved_search_cfg.poll_interval
+        );
+        SavedSearchMatcher::new(repo_arc.clone(), saved_search_cfg).spawn();
+    }
+    let live_hub = LiveHub::default();
+    let live_bus = LiveConfig::from_env()
+        .build(&pool)
+        .expect("live update configuration");
+    if let Some(bus) = &live_bus {
+        info!("Live updates fanned out across replicas via {}", bus.name());
+        listeners.push(bus.clone().spawn_listener(live_hub.clone()));
+    }
+    let challenges = ChallengeStoreConfig::from_env()
+        .build(&pool)
+        .expect("challenge store configuration");
+    info!("Sign-in challenges kept in the {} store", challenges.name());
+    let outbox_cfg = OutboxConfig::from_env();
+    if outbox_cfg.enabled {
+        let mut sinks = outbox_cfg.sinks().expect("outbox sinks");
+        sinks.push(std::sync::Arc::new(LiveSink::new(
+            live_hub.clone(),
+            live_bus.clone(),
+        )));
+        if let Some(backend) = &search_backend {
+            info!("Mirroring posts into {} search backend", backend.name());
+            sinks.push(std::sync::Arc::new(SearchIndexSink::new(
+                repo_arc.clone(),
+                backend.clone(),
+            )));
+        }
+        info!("Outbox relay started with {} sink(s)", sinks.len());
+        OutboxRelay::new(repo_arc.clone(), sinks, outbox_cfg)
+            .with_wakeup(outbox_wakeup)
+            .spawn();
+    }
+    #[cfg(feature = "grpc")]
+    if let Some(addr) = rib::grpc::GrpcConfig::from_env().addr {
+        let state = std::sync::Arc::new(
+            AppState::new(
+                repo_arc.clone(),
+                image_store.clone(),
+                rate_limiter_global.clone(),
+            )
+            .with_search(search_backend.clone())
+            .with_live(live_hub.clone())
+            .with_board_cache(board_cache.clone())
+            .with_duplicates(duplicates.clone())
+            .with_trust(trust.clone())
+            .with_challenges(challenges.clone()),
+        );
+        info!("gRPC listening on {addr}");
+        listeners.push(actix_web::rt::spawn(async move {
+            if let Err(e) = rib::grpc::serve(state, addr).await {
+                tracing::error!("gRPC server failed: {e}");
+            }
+        }));
+    }
+    listeners.push(readiness.spawn_cache_primer(
+        "caches",
+        repo_arc.clone(),
+        board_cache.clone(),
+        pg_notify_enabled,
+    ));
     let image_store_arc = image_store.clone();
     let openapi_spec = openapi.clone();
+    let cors_origins = reloader.cors_origins.clone();
     let server = HttpServer::new(move || {
         // base application
         let cors = {
@@ -183,23 +500,29 @@ async fn main() -> std::io::Result<()> {
                 .allowed_methods(["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"]) // adjust as needed
                 .supports_credentials()
                 .max_age(3600);
-            // If FRONTEND_URL env var is provided and not already covered, add it.
-            if let Ok(front) = std::env::var("FRONTEND_URL") {
-                c = c.allowed_origin(&front);
-            }
+            // FRONTEND_URL and CORS_ALLOWED_ORIGINS, as of the last reload.
+            let origins = cors_origins.clone();
+            c = c.allowed_origin_fn(move |origin, _| {
+                origin
+                    .to_str()
+                    .is_ok_and(|origin| origins.current().allows(origin))
+            });
             // Accept Authorization & Content-Type explicitly when not using allow_any_header
             // c = c.allowed_headers(vec![header::CONTENT_TYPE, header::AUTHORIZATION]);
             c
         };
 
         // metrics exporter handle clone per worker
-        static PROM_HANDLE: Lazy<PrometheusHandle> = Lazy::new(|| {
-            PrometheusBuilder::new()
-                .install_recorder()
-                .expect("install prometheus recorder")
-        });
-        let prometheus = PROM_HANDLE.clone();
+        let prometheus = prometheus.clone();
         let mut app = App::new()
+            .wrap(CatchPanic)
+            .wrap(ImpersonationAudit)
+            .wrap(SlowRequestLog::new(slow_log_cfg.clone()))
+            .wrap(shedder.clone())
+            .wrap(access_policy.clone())
+            .wrap(bot_limits.clone())
+            .wrap(throttle.clone())
+            .wrap(HttpMetrics)
             .wrap(TracingLogger::default())
             .wrap(Compress::default())
             .wrap(SecurityHeaders::from_env())
@@ -209,17 +532,26 @@ async fn main() -> std::io::Result<()> {
             .route("/mod/secret", web::get().to(moderator_only))
             .route(
                 "/metrics",
-                web::get().to(move || {
+                web::get().to(move |req: HttpRequest, data: web::Data<AppState>| {
                     let handle = prometheus.clone();
                     async move {
+                        if rib::service_accounts::metrics_require_account() {
+                            rib::service_accounts::require(&req, &data, ServiceScope::Metrics)
+                                .await?;
+                        }
                         let body = handle.render();
-                        HttpResponse::Ok()
-                            .content_type("text/plain; version=0.0.4")
-                            .body(body)
+                        Ok::<_, ApiError>(
+                            HttpResponse::Ok()
+                                .content_type("text/plain; version=0.0.4")
+                                .body(body),
+                        )
                     }
                 }),
             );
 
+        // Crawlers get server-rendered pages at the SPA's board and thread URLs.
+        app = app.configure(rib::ssr::crawler_fallback);
+
         // Catch-all route for SPA assets *after* API & docs so they override only unknown paths.
         app = app.service(
             actix_web::web::resource("/{tail:.*}").route(actix_web::web::get().to(serve_frontend)),
@@ -227,11 +559,28 @@ async fn main() -> std::io::Result<()> {
 
         // inject in-memory repository when the feature is enabled
         // Provide repo (either in-memory or Postgres)
-        app = app.app_data(actix_web::web::Data::new(AppState {
-            repo: repo_arc.clone(),
-            image_store: image_store_arc.clone(),
-            rate_limiter: rate_limiter_global.clone(),
-        }));
+        app = app.app_data(actix_web::web::Data::new(
+            AppState::new(
+                repo_arc.clone(),
+                image_store_arc.clone(),
+                rate_limiter_global.clone(),
+            )
+            .with_search(search_backend.clone())
+            .with_live(live_hub.clone())
+            .with_board_cache(board_cache.clone())
+            .with_duplicates(duplicates.clone())
+            .with_trust(trust.clone())
+            .with_uploads(uploads.clone())
+            .with_features(reloader.features.clone())
+            .with_mailer(mailer.clone())
+            .with_previews(previews.clone())
+            .with_thumbnails(thumbnails.clone())
+            .with_challenges(challenges.clone())
+            .with_system(system.clone())
+            .with_reloader(Some(reloader.clone()))
+            .with_federation(federation.clone())
+            .with_readiness(readiness.clone()),
+        ));
 
         app
     })
@@ -239,7 +588,12 @@ async fn main() -> std::io::Result<()> {
 
     info!("Listening on http://0.0.0.0:8080 (all interfaces)");
 
-    server.run().await // <-- run the server
+    let result = server.run().await; // <-- run the server
+    for listener in listeners {
+        listener.abort();
+        let _ = listener.await;
+    }
+    result
 }
 
 /// Validate that required environment variables are set
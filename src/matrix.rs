@@ -0,0 +1,378 @@
+//! Matrix room mirroring.
+//!
+//! Admins mirror a whole board or a single thread into a Matrix room under
+//! `/api/v1/admin/matrix-mirrors`. This runner leases the enabled mirrors,
+//! posts threads (board mirrors only) and replies made since each mirror's
+//! cursors and redacts the room events of posts deleted since. It posts as
+//! the bot behind `MATRIX_ACCESS_TOKEN`, or, with `MATRIX_APPSERVICE_USER_ID`,
+//! as that user of an application service whose `as_token` is the access
+//! token. Either way the user must already be in the room.
+//!
+//! Transaction ids are derived from the post, so a post sent again after a
+//! lost lease maps to the same room event instead of a duplicate.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::models::{Id, MatrixEvent, MatrixMirror, MatrixPost};
+use crate::repo::{Repo, RepoError};
+
+/// Longest room id accepted, in bytes (the Matrix limit).
+pub const MAX_ROOM_ID_BYTES: usize = 255;
+
+#[derive(Clone, Debug)]
+pub struct MatrixConfig {
+    pub enabled: bool,
+    /// Client-server API origin, e.g. `https://matrix.example.org`.
+    pub homeserver_url: Option<String>,
+    pub access_token: Option<String>,
+    /// Application service user to post as; the token is then the `as_token`.
+    pub appservice_user_id: Option<String>,
+    pub poll_interval: Duration,
+    /// Mirrors leased per poll at most.
+    pub batch_size: i64,
+    /// Posts and redactions relayed per mirror and poll at most.
+    pub posts_per_pass: i64,
+    /// Age a post must reach before it is mirrored, so posts still being
+    /// committed are not skipped.
+    pub settle: Duration,
+    pub lease: Duration,
+    /// Wait before retrying a mirror whose pass failed.
+    pub retry: Duration,
+    /// Public origin used in links, e.g. `https://rib.example`.
+    pub base_url: String,
+}
+
+impl MatrixConfig {
+    pub fn from_env() -> Self {
+        fn u64_env(name: &str, default: u64) -> u64 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        fn opt_env(name: &str) -> Option<String> {
+            std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+        }
+        Self {
+            enabled: std::env::var("MATRIX_MIRRORS_ENABLED")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(true),
+            homeserver_url: opt_env("MATRIX_HOMESERVER_URL")
+                .map(|url| url.trim_end_matches('/').to_string()),
+            access_token: opt_env("MATRIX_ACCESS_TOKEN"),
+            appservice_user_id: opt_env("MATRIX_APPSERVICE_USER_ID"),
+            poll_interval: Duration::from_secs(u64_env("MATRIX_POLL_SECS", 15).max(1)),
+            batch_size: u64_env("MATRIX_BATCH_SIZE", 20).max(1) as i64,
+            posts_per_pass: u64_env("MATRIX_POSTS_PER_PASS", 20).max(1) as i64,
+            settle: Duration::from_secs(u64_env("MATRIX_SETTLE_SECS", 5)),
+            lease: Duration::from_secs(u64_env("MATRIX_LEASE_SECS", 120).max(1)),
+            retry: Duration::from_secs(u64_env("MATRIX_RETRY_SECS", 60)),
+            base_url: crate::sitemap::site_url(),
+        }
+    }
+
+    /// Whether a homeserver and credentials are set.
+    pub fn is_configured(&self) -> bool {
+        self.homeserver_url.is_some() && self.access_token.is_some()
+    }
+
+    /// Check a room before a mirror is created: it must be a room id (not
+    /// an alias) and Matrix must be configured.
+    pub fn validate_room(&self, room_id: &str) -> Result<(), String> {
+        if !self.is_configured() {
+            return Err("MATRIX_HOMESERVER_URL and MATRIX_ACCESS_TOKEN are not set".into());
+        }
+        let valid = room_id.len() <= MAX_ROOM_ID_BYTES
+            && room_id
+                .strip_prefix('!')
+                .and_then(|rest| rest.split_once(':'))
+                .is_some_and(|(local, server)| {
+                    !local.is_empty()
+                        && !server.is_empty()
+                        && !room_id.contains(char::is_whitespace)
+                });
+        valid
+            .then_some(())
+            .ok_or_else(|| "room_id must be a room id like !abc:matrix.org".to_string())
+    }
+}
+
+/// Plain-text body of a mirrored post. Board mirrors name the board for
+/// new threads and the thread for replies.
+pub fn message(base_url: &str, board_slug: Option<&str>, post: &MatrixPost) -> String {
+    let author = post.author_name.as_deref().unwrap_or("Anonymous");
+    let link = format!("{base_url}/thread/{}", post.thread_id);
+    match (post.reply_id, board_slug) {
+        (None, slug) => {
+            let mut text = match slug {
+                Some(slug) => format!("New thread on /{slug}/: {}", post.subject),
+                None => post.subject.clone(),
+            };
+            let excerpt = crate::digest::excerpt(&post.body);
+            if !excerpt.is_empty() {
+                text.push_str("\n\n");
+                text.push_str(&excerpt);
+            }
+            format!("{text}\n\n{link}")
+        }
+        (Some(_), Some(_)) => format!(
+            "{author} in \u{201c}{}\u{201d}:\n{}\n\n{link}",
+            post.subject, post.body
+        ),
+        (Some(_), None) => format!("{author}: {}", post.body),
+    }
+}
+
+/// Polls the enabled mirrors, posting new posts and redacting deleted ones.
+#[derive(Clone)]
+pub struct MatrixMirrorRunner {
+    repo: Arc<dyn Repo>,
+    cfg: MatrixConfig,
+    client: reqwest::Client,
+}
+
+impl MatrixMirrorRunner {
+    pub fn new(repo: Arc<dyn Repo>, cfg: MatrixConfig) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(3))
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        Ok(Self { repo, cfg, client })
+    }
+
+    /// Run every leased mirror once; returns the posts and redactions relayed.
+    pub async fn run_once(&self) -> usize {
+        let mirrors = match self
+            .repo
+            .claim_matrix_mirrors(self.cfg.batch_size, self.cfg.lease.as_secs() as i64)
+            .await
+        {
+            Ok(mirrors) => mirrors,
+            Err(e) => {
+                log::error!("matrix mirror claim failed: {e}");
+                return 0;
+            }
+        };
+        let mut relayed = 0;
+        for mirror in mirrors {
+            relayed += self.run_mirror(&mirror).await;
+        }
+        relayed
+    }
+
+    async fn run_mirror(&self, mirror: &MatrixMirror) -> usize {
+        let mut cursors = (mirror.last_thread_id, mirror.last_reply_id);
+        let mut relayed = 0;
+        let error = self.relay(mirror, &mut cursors, &mut relayed).await.err();
+        if let Err(e) = self
+            .repo
+            .release_matrix_mirror(
+                mirror.id,
+                cursors.0,
+                cursors.1,
+                relayed > 0,
+                error,
+                self.cfg.retry.as_secs() as i64,
+            )
+            .await
+        {
+            log::error!("matrix mirror {} release failed: {e}", mirror.id);
+        }
+        relayed
+    }
+
+    /// Redact deleted posts, then post new ones in order, moving `cursors`
+    /// (thread, reply) past each one; stops at the first failure.
+    async fn relay(
+        &self,
+        mirror: &MatrixMirror,
+        cursors: &mut (Id, Id),
+        relayed: &mut usize,
+    ) -> Result<(), String> {
+        let lookup_failed = |e: RepoError| {
+            log::error!("matrix mirror {} lookup failed: {e}", mirror.id);
+            "database unavailable".to_string()
+        };
+        let failed = |what: &str, e: anyhow::Error| {
+            metrics::increment_counter!("matrix_mirror_failures");
+            log::warn!("matrix mirror {} failed to {what}: {e}", mirror.id);
+            e.to_string()
+        };
+        let redactions = self
+            .repo
+            .pending_matrix_redactions(mirror.id, self.cfg.posts_per_pass)
+            .await
+            .map_err(lookup_failed)?;
+        for event in redactions {
+            self.redact(mirror, &event)
+                .await
+                .map_err(|e| failed("redact", e))?;
+            self.repo
+                .mark_matrix_event_redacted(mirror.id, &event.event_id)
+                .await
+                .map_err(lookup_failed)?;
+            metrics::increment_counter!("matrix_mirror_redactions");
+            *relayed += 1;
+        }
+
+        let posts = self
+            .repo
+            .pending_matrix_posts(
+                mirror,
+                self.cfg.posts_per_pass,
+                self.cfg.settle.as_secs() as i64,
+            )
+            .await
+            .map_err(lookup_failed)?;
+        let slug = match mirror.board_id {
+            Some(board_id) if !posts.is_empty() => Some(
+                self.repo
+                    .get_board(board_id)
+                    .await
+                    .map_err(lookup_failed)?
+                    .slug,
+            ),
+            _ => None,
+        };
+        for post in posts {
+            let text = message(&self.cfg.base_url, slug.as_deref(), &post);
+            let event_id = self
+                .send(mirror, &post, &text)
+                .await
+                .map_err(|e| failed("post", e))?;
+            self.repo
+                .record_matrix_event(mirror.id, &post, &event_id)
+                .await
+                .map_err(lookup_failed)?;
+            metrics::increment_counter!("matrix_mirror_posts");
+            match post.reply_id {
+                Some(reply_id) => cursors.1 = reply_id,
+                None => cursors.0 = post.thread_id,
+            }
+            *relayed += 1;
+        }
+        Ok(())
+    }
+
+    async fn send(
+        &self,
+        mirror: &MatrixMirror,
+        post: &MatrixPost,
+        text: &str,
+    ) -> anyhow::Result<String> {
+        let txn = match post.reply_id {
+            Some(reply_id) => format!("rib{}r{reply_id}", mirror.id),
+            None => format!("rib{}t{}", mirror.id, post.thread_id),
+        };
+        let path = format!(
+            "rooms/{}/send/m.room.message/{txn}",
+            urlencoding::encode(&mirror.room_id)
+        );
+        let body = serde_json::json!({ "msgtype": "m.text", "body": text });
+        self.put(&path, &body).await
+    }
+
+    async fn redact(&self, mirror: &MatrixMirror, event: &MatrixEvent) -> anyhow::Result<()> {
+        let txn = match event.reply_id {
+            Some(reply_id) => format!("rib{}xr{reply_id}", mirror.id),
+            None => format!("rib{}xt{}", mirror.id, event.thread_id),
+        };
+        let path = format!(
+            "rooms/{}/redact/{}/{txn}",
+            urlencoding::encode(&mirror.room_id),
+            urlencoding::encode(&event.event_id)
+        );
+        let body = serde_json::json!({ "reason": "Deleted on the board" });
+        self.put(&path, &body).await.map(|_| ())
+    }
+
+    /// PUT to the client-server API; returns the `event_id` answered.
+    async fn put(&self, path: &str, body: &serde_json::Value) -> anyhow::Result<String> {
+        let (Some(homeserver), Some(token)) = (&self.cfg.homeserver_url, &self.cfg.access_token)
+        else {
+            anyhow::bail!("MATRIX_HOMESERVER_URL and MATRIX_ACCESS_TOKEN are not set");
+        };
+        let mut request = self
+            .client
+            .put(format!("{homeserver}/_matrix/client/v3/{path}"))
+            .bearer_auth(token)
+            .json(body);
+        if let Some(user_id) = &self.cfg.appservice_user_id {
+            request = request.query(&[("user_id", user_id)]);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let errcode = response
+                .json::<serde_json::Value>()
+                .await
+                .ok()
+                .and_then(|v| v["errcode"].as_str().map(str::to_string));
+            match errcode {
+                Some(errcode) => anyhow::bail!("responded with {status} ({errcode})"),
+                None => anyhow::bail!("responded with {status}"),
+            }
+        }
+        let reply: serde_json::Value = response.json().await?;
+        reply["event_id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("response has no event_id"))
+    }
+
+    /// Spawn the polling loop on the current runtime.
+    pub fn spawn(self) {
+        crate::system::job_started("matrix_mirrors", self.cfg.poll_interval);
+        actix_web::rt::spawn(async move {
+            loop {
+                self.run_once().await;
+                crate::system::job_ran("matrix_mirrors");
+                tokio::time::sleep(self.cfg.poll_interval).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rooms_must_be_room_ids() {
+        let mut cfg = MatrixConfig::from_env();
+        cfg.homeserver_url = None;
+        assert!(cfg.validate_room("!abc:matrix.org").is_err());
+        cfg.homeserver_url = Some("https://matrix.example".into());
+        cfg.access_token = Some("token".into());
+        assert!(cfg.validate_room("!abc:matrix.org").is_ok());
+        for room in ["#rib:matrix.org", "!abc", "!:matrix.org", "!a b:matrix.org"] {
+            assert!(cfg.validate_room(room).is_err(), "{room}");
+        }
+    }
+
+    #[test]
+    fn messages_name_the_board_or_the_author() {
+        let mut post = MatrixPost {
+            thread_id: 7,
+            reply_id: None,
+            subject: "Hello".into(),
+            body: "First".into(),
+            author_name: None,
+            created_at: chrono::Utc::now(),
+        };
+        let base = "https://rib.example";
+        assert_eq!(
+            message(base, Some("tech"), &post),
+            "New thread on /tech/: Hello\n\nFirst\n\nhttps://rib.example/thread/7"
+        );
+        post.reply_id = Some(9);
+        post.author_name = Some("ann".into());
+        post.body = "Agreed".into();
+        assert_eq!(message(base, None, &post), "ann: Agreed");
+        assert_eq!(
+            message(base, Some("tech"), &post),
+            "ann in \u{201c}Hello\u{201d}:\nAgreed\n\nhttps://rib.example/thread/7"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! Moving media of archived threads to colder storage.
+//!
+//! Threads are archived inline when they fall off a board's page limit.
+//! This runner then finds blobs whose every visible attachment sits in an
+//! archived thread and moves them to the archive tier of the image store
+//! (`S3_ARCHIVE_STORAGE_CLASS`, tagged for the lifecycle rule). Blobs
+//! attached again to a live post are moved back to the upload tier.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::repo::Repo;
+use crate::storage::{ImageStore, ImageStoreError, StorageTier};
+
+#[derive(Clone, Debug)]
+pub struct MediaArchiveConfig {
+    pub enabled: bool,
+    pub poll_interval: Duration,
+    /// Blobs moved per direction and poll at most.
+    pub batch_size: i64,
+}
+
+impl MediaArchiveConfig {
+    pub fn from_env() -> Self {
+        fn u64_env(name: &str, default: u64) -> u64 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        // Without an archive class there is nothing to move media to.
+        let archive_class =
+            std::env::var("S3_ARCHIVE_STORAGE_CLASS").is_ok_and(|v| !v.trim().is_empty());
+        Self {
+            enabled: archive_class
+                && std::env::var("MEDIA_ARCHIVE_ENABLED")
+                    .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                    .unwrap_or(true),
+            poll_interval: Duration::from_secs(u64_env("MEDIA_ARCHIVE_POLL_SECS", 300).max(1)),
+            batch_size: u64_env("MEDIA_ARCHIVE_BATCH_SIZE", 100).max(1) as i64,
+        }
+    }
+}
+
+/// Polls for blobs to move between the upload and archive tiers.
+#[derive(Clone)]
+pub struct MediaArchiver {
+    repo: Arc<dyn Repo>,
+    store: Arc<dyn ImageStore>,
+    cfg: MediaArchiveConfig,
+}
+
+impl MediaArchiver {
+    pub fn new(repo: Arc<dyn Repo>, store: Arc<dyn ImageStore>, cfg: MediaArchiveConfig) -> Self {
+        Self { repo, store, cfg }
+    }
+
+    /// Restore re-attached blobs, then archive newly archived ones; returns
+    /// how many blobs moved.
+    pub async fn run_once(&self) -> usize {
+        let restored = self.move_blobs(StorageTier::Hot).await;
+        let archived = self.move_blobs(StorageTier::Archive).await;
+        metrics::counter!("media_blobs_restored", restored as u64);
+        metrics::counter!("media_blobs_archived", archived as u64);
+        restored + archived
+    }
+
+    async fn move_blobs(&self, tier: StorageTier) -> usize {
+        let archive = tier == StorageTier::Archive;
+        let due = if archive {
+            self.repo.blobs_to_archive(self.cfg.batch_size).await
+        } else {
+            self.repo.blobs_to_restore(self.cfg.batch_size).await
+        };
+        let hashes = match due {
+            Ok(hashes) => hashes,
+            Err(e) => {
+                log::error!("media archive lookup failed: {e}");
+                return 0;
+            }
+        };
+        let mut moved = 0;
+        for hash in hashes {
+            match self.store.set_tier(&hash, tier).await {
+                // A blob that is gone has nothing left to move.
+                Ok(()) | Err(ImageStoreError::NotFound) => {}
+                Err(e) => {
+                    metrics::increment_counter!("media_archive_failed");
+                    log::warn!("moving blob {hash} to {tier:?} failed: {e}");
+                    continue;
+                }
+            }
+            match self.repo.set_blob_archived(&hash, archive).await {
+                Ok(()) => moved += 1,
+                Err(e) => log::error!("recording blob {hash} as {tier:?} failed: {e}"),
+            }
+        }
+        moved
+    }
+
+    /// Spawn the polling loop on the current runtime.
+    pub fn spawn(self) {
+        crate::system::job_started("media_archive", self.cfg.poll_interval);
+        actix_web::rt::spawn(async move {
+            loop {
+                self.run_once().await;
+                crate::system::job_ran("media_archive");
+                tokio::time::sleep(self.cfg.poll_interval).await;
+            }
+        });
+    }
+}
@@ -0,0 +1,260 @@
+//! Removal of embedded metadata from image uploads.
+//!
+//! Phones write the camera, the time and often the GPS position into every
+//! photo. Before an upload is hashed and stored, the blocks carrying such
+//! metadata are cut out of JPEG, PNG and WebP files. The pixels are copied
+//! as they are, never re-encoded, so images keep their quality and colour
+//! profile. EXIF orientation goes with the rest of EXIF.
+
+use anyhow::{bail, ensure, Context};
+
+/// Image types metadata is stripped from.
+const STRIPPED: &[&str] = &["image/jpeg", "image/png", "image/webp"];
+
+/// Whether uploads of `mime` have their metadata stripped.
+pub fn strips(mime: &str) -> bool {
+    STRIPPED.contains(&mime)
+}
+
+/// `bytes` without their metadata, or `None` when there was none to remove.
+/// Fails on files too malformed to walk.
+pub fn strip(bytes: &[u8], mime: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    match mime {
+        "image/jpeg" => strip_jpeg(bytes),
+        "image/png" => strip_png(bytes),
+        "image/webp" => strip_webp(bytes),
+        _ => Ok(None),
+    }
+}
+
+/// JPEG: drops APP1 (EXIF and XMP), APP13 (IPTC) and comment segments.
+fn strip_jpeg(bytes: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+    ensure!(bytes.starts_with(&[0xFF, 0xD8]), "not a JPEG");
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(&bytes[..2]);
+    let mut removed = false;
+    let mut i = 2;
+    while i < bytes.len() {
+        ensure!(bytes[i] == 0xFF, "expected a marker at byte {i}");
+        let marker = *bytes.get(i + 1).context("truncated marker")?;
+        match marker {
+            // Padding before a marker.
+            0xFF => {
+                i += 1;
+                continue;
+            }
+            // Start of scan or end of image: the rest is image data.
+            0xDA | 0xD9 => {
+                out.extend_from_slice(&bytes[i..]);
+                break;
+            }
+            // Markers without a length.
+            0x01 | 0xD0..=0xD7 => {
+                out.extend_from_slice(&bytes[i..i + 2]);
+                i += 2;
+                continue;
+            }
+            _ => {}
+        }
+        let length = bytes
+            .get(i + 2..i + 4)
+            .map(|len| u16::from_be_bytes([len[0], len[1]]) as usize)
+            .context("truncated segment length")?;
+        ensure!(length >= 2, "invalid segment length");
+        let end = i + 2 + length;
+        ensure!(end <= bytes.len(), "truncated segment");
+        if matches!(marker, 0xE1 | 0xED | 0xFE) {
+            removed = true;
+        } else {
+            out.extend_from_slice(&bytes[i..end]);
+        }
+        i = end;
+    }
+    Ok(removed.then_some(out))
+}
+
+/// PNG chunks carrying EXIF, text (including XMP) or a modification time.
+const PNG_METADATA: &[&[u8; 4]] = &[b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// PNG: drops metadata chunks and anything after `IEND`.
+fn strip_png(bytes: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    ensure!(bytes.starts_with(SIGNATURE), "not a PNG");
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(SIGNATURE);
+    let mut removed = false;
+    let mut i = SIGNATURE.len();
+    loop {
+        let header = bytes.get(i..i + 8).context("truncated chunk")?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let kind = &header[4..8];
+        let end = i
+            .checked_add(12)
+            .and_then(|n| n.checked_add(length))
+            .filter(|end| *end <= bytes.len())
+            .context("truncated chunk")?;
+        if PNG_METADATA.iter().any(|m| m.as_slice() == kind) {
+            removed = true;
+        } else {
+            out.extend_from_slice(&bytes[i..end]);
+        }
+        i = end;
+        if kind == b"IEND" {
+            break;
+        }
+    }
+    Ok((removed || i < bytes.len()).then_some(out))
+}
+
+/// VP8X flags announcing EXIF and XMP chunks.
+const VP8X_EXIF: u8 = 0x08;
+const VP8X_XMP: u8 = 0x04;
+
+/// WebP: drops `EXIF` and `XMP ` chunks and clears their VP8X flags.
+fn strip_webp(bytes: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+    ensure!(
+        bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP",
+        "not a WebP"
+    );
+    let riff_end = (u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize)
+        .saturating_add(8)
+        .min(bytes.len());
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(&bytes[..12]);
+    let mut removed = false;
+    let mut i = 12;
+    while i < riff_end {
+        let header = bytes.get(i..i + 8).context("truncated chunk")?;
+        let kind = &header[..4];
+        let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        // Chunks are padded to an even length.
+        let end = (i + 8)
+            .checked_add(length + (length & 1))
+            .context("chunk too large")?;
+        if end > bytes.len() {
+            bail!("truncated chunk");
+        }
+        match kind {
+            b"EXIF" | b"XMP " => removed = true,
+            b"VP8X" => {
+                ensure!(length >= 1, "empty VP8X chunk");
+                let start = out.len();
+                out.extend_from_slice(&bytes[i..end]);
+                out[start + 8] &= !(VP8X_EXIF | VP8X_XMP);
+            }
+            _ => out.extend_from_slice(&bytes[i..end]),
+        }
+        i = end;
+    }
+    if !removed {
+        return Ok(None);
+    }
+    let riff_size = u32::try_from(out.len() - 8).context("file too large")?;
+    out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Ok(Some(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, ImageFormat, RgbImage};
+    use std::io::Cursor;
+
+    /// Big-endian EXIF whose IFD0 points at a GPS IFD holding a latitude ref.
+    fn exif_with_gps() -> Vec<u8> {
+        let mut tiff = b"MM\x00\x2a\x00\x00\x00\x08".to_vec();
+        // IFD0: one entry, GPSInfo (0x8825) LONG 1 -> offset 26.
+        tiff.extend_from_slice(&[0x00, 0x01, 0x88, 0x25, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01]);
+        tiff.extend_from_slice(&[0x00, 0x00, 0x00, 0x1A, 0x00, 0x00, 0x00, 0x00]);
+        // GPS IFD: GPSLatitudeRef (0x0001) ASCII 2 "N".
+        tiff.extend_from_slice(&[0x00, 0x01, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02]);
+        tiff.extend_from_slice(&[b'N', 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        [b"Exif\x00\x00".as_slice(), &tiff].concat()
+    }
+
+    const GPS_POINTER: &[u8] = &[0x88, 0x25, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01];
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+
+    fn encode(format: ImageFormat) -> Vec<u8> {
+        let mut out = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 8, image::Rgb([10, 120, 30])))
+            .write_to(&mut out, format)
+            .unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn gps_tags_are_cut_from_jpegs() {
+        let plain = encode(ImageFormat::Jpeg);
+        let exif = exif_with_gps();
+        let mut tagged = plain[..2].to_vec();
+        tagged.extend_from_slice(&[0xFF, 0xE1]);
+        tagged.extend_from_slice(&((exif.len() + 2) as u16).to_be_bytes());
+        tagged.extend_from_slice(&exif);
+        tagged.extend_from_slice(&plain[2..]);
+        assert!(contains(&tagged, GPS_POINTER));
+
+        let stripped = strip(&tagged, "image/jpeg").unwrap().unwrap();
+        assert!(!contains(&stripped, GPS_POINTER));
+        assert!(!contains(&stripped, b"Exif\x00\x00"));
+        assert_eq!(stripped, plain);
+        assert!(strip(&plain, "image/jpeg").unwrap().is_none());
+    }
+
+    #[test]
+    fn metadata_chunks_are_cut_from_pngs() {
+        let plain = encode(ImageFormat::Png);
+        let iend = plain.len() - 12;
+        let mut tagged = plain[..iend].to_vec();
+        for (kind, data) in [
+            (b"eXIf", exif_with_gps()),
+            (b"tEXt", b"GPS\x0052N".to_vec()),
+        ] {
+            tagged.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            tagged.extend_from_slice(kind);
+            tagged.extend_from_slice(&data);
+            tagged.extend_from_slice(&[0; 4]);
+        }
+        tagged.extend_from_slice(&plain[iend..]);
+
+        let stripped = strip(&tagged, "image/png").unwrap().unwrap();
+        assert!(!contains(&stripped, GPS_POINTER));
+        assert_eq!(stripped, plain);
+        assert!(strip(&plain, "image/png").unwrap().is_none());
+    }
+
+    #[test]
+    fn exif_chunks_and_flags_are_cut_from_webps() {
+        let simple = encode(ImageFormat::WebP);
+        let bitstream = &simple[12..];
+        let exif = exif_with_gps();
+        let mut vp8x = vec![VP8X_EXIF, 0, 0, 0];
+        vp8x.extend_from_slice(&[7, 0, 0, 7, 0, 0]);
+        let mut tagged = b"RIFF\x00\x00\x00\x00WEBP".to_vec();
+        tagged.extend_from_slice(b"VP8X\x0a\x00\x00\x00");
+        tagged.extend_from_slice(&vp8x);
+        tagged.extend_from_slice(bitstream);
+        tagged.extend_from_slice(b"EXIF");
+        tagged.extend_from_slice(&(exif.len() as u32).to_le_bytes());
+        tagged.extend_from_slice(&exif);
+        let size = (tagged.len() - 8) as u32;
+        tagged[4..8].copy_from_slice(&size.to_le_bytes());
+
+        let stripped = strip(&tagged, "image/webp").unwrap().unwrap();
+        assert!(!contains(&stripped, GPS_POINTER));
+        assert_eq!(stripped[20] & VP8X_EXIF, 0);
+        let size = u32::from_le_bytes(stripped[4..8].try_into().unwrap()) as usize;
+        assert_eq!(size + 8, stripped.len());
+        assert!(image::load_from_memory(&stripped).is_ok());
+    }
+
+    #[test]
+    fn malformed_files_are_refused() {
+        assert!(strip(b"\xFF\xD8\xFF\xE1\xFF\xFF", "image/jpeg").is_err());
+        assert!(strip(b"\x89PNG\r\n\x1a\n\x00\x00", "image/png").is_err());
+        assert!(strip(b"GIF89a", "image/gif").unwrap().is_none());
+    }
+}
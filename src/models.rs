@@ -13,6 +13,36 @@ pub struct Board {
     pub title: String,
     pub created_at: DateTime<Utc>,
     pub deleted_at: Option<DateTime<Utc>>, // soft delete marker
+    /// Posting without signing in is allowed
+    #[serde(default)]
+    pub anonymous_posting: bool,
+    /// Thread creators may delete replies in and close their own threads
+    #[serde(default)]
+    pub op_moderation: bool,
+    /// Active threads kept on the board; 0 means no limit
+    #[serde(default)]
+    pub max_threads: i32,
+    /// Soft-delete threads pushed past `max_threads` instead of archiving them
+    #[serde(default)]
+    pub prune_overflow: bool,
+    /// Seconds a poster waits between replies in the same thread; 0 means none
+    #[serde(default)]
+    pub reply_cooldown_secs: i32,
+    /// Emoji accepted as reactions on replies; empty turns reactions off
+    #[serde(default)]
+    pub reactions: Vec<String>,
+    /// Tags threads may carry; empty accepts any well-formed tag
+    #[serde(default)]
+    pub tag_vocabulary: Vec<String>,
+    /// Attachment types accepted here (MIME types, `type/*` or categories); empty uses the deployment list
+    #[serde(default)]
+    pub allowed_mime: Vec<String>,
+    /// Removed replies stay in thread listings as stubs showing the removal reason
+    #[serde(default)]
+    pub tombstones: bool,
+    /// Soft-launched: listed and open for posting only to staff and the board's testers
+    #[serde(default)]
+    pub beta: bool,
 }
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
 pub struct NewBoard {
@@ -29,13 +59,100 @@ pub struct Thread {
     pub bump_time: DateTime<Utc>,
     pub image_hash: Option<String>,
     pub mime: Option<String>,
+    /// Attachment size in bytes, when it was recorded at upload
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_size: Option<i64>,
     pub author_name: Option<String>,
     pub tripcode: Option<String>,
     pub deleted_at: Option<DateTime<Utc>>, // soft delete marker
+    /// Set while the thread takes no new replies
+    #[serde(default)]
+    pub closed_at: Option<DateTime<Utc>>,
+    /// Set once the thread falls off the board's page limit; archived threads take no new replies
+    #[serde(default)]
+    pub archived_at: Option<DateTime<Utc>>,
+    /// Reply shown above the others
+    #[serde(default)]
+    pub pinned_reply_id: Option<Id>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Replies not deleted or held for review
+    #[serde(default)]
+    pub reply_count: i32,
+    /// Counted replies that carry an image
+    #[serde(default)]
+    pub image_count: i32,
+    /// URL slug derived from the subject, as in `/api/v1/boards/{board}/threads/{id}-{slug}`
+    #[serde(default)]
+    pub slug: String,
     #[serde(skip_serializing, default)]
     #[schema(skip)]
     #[allow(dead_code)]
     pub created_by: Value, // internal author attribution JSON (hidden from API clients)
+    /// Public profile of a signed-in author who set a display name or avatar.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<AuthorProfile>)]
+    pub author: Option<sqlx::types::Json<AuthorProfile>>,
+}
+
+/// Key a thread listing is ordered by (`?sort=`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreadSort {
+    #[default]
+    BumpTime,
+    CreatedAt,
+    ReplyCount,
+}
+
+impl ThreadSort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ThreadSort::BumpTime => "bump_time",
+            ThreadSort::CreatedAt => "created_at",
+            ThreadSort::ReplyCount => "reply_count",
+        }
+    }
+}
+
+/// Key a reply listing is ordered by; replies sort by creation time only.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplySort {
+    #[default]
+    CreatedAt,
+}
+
+/// Direction of a listing (`?order=`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn is_asc(self) -> bool {
+        self == SortOrder::Asc
+    }
+}
+
+/// How a thread listing is ordered. Equal keys fall back to the thread id in
+/// the same direction, so pages never shuffle between requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThreadOrder {
+    pub sort: ThreadSort,
+    pub order: SortOrder,
+}
+
+impl Default for ThreadOrder {
+    /// Most recently bumped first.
+    fn default() -> Self {
+        Self {
+            sort: ThreadSort::BumpTime,
+            order: SortOrder::Desc,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
@@ -49,6 +166,12 @@ pub struct NewThread {
     pub author_name: Option<String>,
     #[serde(default)]
     pub tripcode_password: Option<String>,
+    /// Lets the poster soft-delete the post later without an account; stored hashed.
+    #[serde(default)]
+    pub delete_password: Option<String>,
+    /// Up to five tags, from the board's vocabulary when it has one.
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
 pub struct Reply {
@@ -57,6 +180,9 @@ pub struct Reply {
     pub content: String,
     pub image_hash: Option<String>,
     pub mime: Option<String>,
+    /// Attachment size in bytes, when it was recorded at upload
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_size: Option<i64>,
     pub author_name: Option<String>,
     pub tripcode: Option<String>,
     pub created_at: DateTime<Utc>,
@@ -65,6 +191,17 @@ pub struct Reply {
     #[schema(skip)]
     #[allow(dead_code)]
     pub created_by: Value, // internal author attribution JSON (hidden)
+    /// Public profile of a signed-in author who set a display name or avatar.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<AuthorProfile>)]
+    pub author: Option<sqlx::types::Json<AuthorProfile>>,
+    /// Reaction counts, most used first.
+    #[serde(default)]
+    #[schema(value_type = Vec<ReactionCount>)]
+    pub reactions: sqlx::types::Json<Vec<ReactionCount>>,
+    /// Why staff removed the reply; shown on tombstones and to staff.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removal_reason: Option<String>,
 }
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
 pub struct NewReply {
@@ -76,6 +213,45 @@ pub struct NewReply {
     pub author_name: Option<String>,
     #[serde(default)]
     pub tripcode_password: Option<String>,
+    /// Lets the poster soft-delete the post later without an account; stored hashed.
+    #[serde(default)]
+    pub delete_password: Option<String>,
+}
+
+/// How many active threads of a board carry a tag.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: i64,
+}
+
+/// How many subjects reacted to a reply with one emoji.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ReactionCount {
+    pub emoji: String,
+    pub count: i64,
+}
+
+/// A reaction to a reply; it must be one of the board's `reactions`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewReaction {
+    pub emoji: String,
+}
+
+/// Display name and avatar a signed-in user chose, shown on their posts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AuthorProfile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// Served from `/images/{hash}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateProfile {
+    /// `null` clears the display name.
+    pub display_name: Option<String>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -92,18 +268,240 @@ pub struct Image {
     pub hash: String,
     pub mime: String,
 }
+/// An accepted upload, recorded for quotas and review.
+#[derive(Debug, Clone)]
+pub struct NewUpload {
+    pub hash: String,
+    pub uploaded_by: String,
+    pub mime: String,
+    pub size_bytes: i64,
+    /// Name the client sent, if any
+    pub file_name: Option<String>,
+    /// Why the name and content disagree, under the `flag` policy
+    pub flag_reason: Option<String>,
+    /// Whether posts by other subjects may attach the blob
+    pub shareable: bool,
+}
+/// Bytes a subject uploaded since some instant, for quotas.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UploadUsage {
+    pub bytes: i64,
+    /// Earliest upload counted; the quota frees up as it ages out
+    pub oldest_at: Option<DateTime<Utc>>,
+}
+/// A visible post attaching a blob.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct ImageReference {
+    pub board_id: Id,
+    pub thread_id: Id,
+    /// Set when a reply, not the opening post, attaches it
+    pub reply_id: Option<Id>,
+}
+/// One subject's upload of a blob, as staff see it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct UploadRecord {
+    pub hash: String,
+    pub uploaded_by: String,
+    pub mime: String,
+    pub size_bytes: i64,
+    pub file_name: Option<String>,
+    pub flag_reason: Option<String>,
+    pub shareable: bool,
+    pub created_at: DateTime<Utc>,
+}
+/// A post attaching a blob, deleted ones included.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct AttachedPost {
+    pub board_id: Id,
+    pub thread_id: Id,
+    /// Set when a reply, not the opening post, attaches it
+    pub reply_id: Option<Id>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+/// What staff need to handle a takedown of one blob.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImageDetails {
+    pub hash: String,
+    /// Uploads of the blob, oldest first; the first names its uploader
+    pub uploads: Vec<UploadRecord>,
+    /// Posts attaching it, newest first
+    pub attachments: Vec<AttachedPost>,
+    pub quarantine: Option<QuarantinedImage>,
+    pub legal_hold: Option<LegalHold>,
+    /// Files inside the blob when it is an inspected archive
+    #[serde(default)]
+    pub archive: Option<ArchiveListing>,
+}
+/// Files inside an uploaded archive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ArchiveListing {
+    /// `zip`, `tar`, `tar.gz`, or `gzip` for a single compressed file
+    pub format: String,
+    /// The first 1000 entries, in archive order
+    pub entries: Vec<ArchiveEntry>,
+    pub entry_count: i64,
+    /// Bytes the archive unpacks to
+    pub unpacked_bytes: i64,
+    /// Whether `entries` stops short of `entry_count`
+    pub truncated: bool,
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ArchiveEntry {
+    pub path: String,
+    pub size: i64,
+    pub dir: bool,
+}
+/// The opening lines of a text or code upload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TextExcerpt {
+    /// Highlighter-style name such as `rust` or `json`; unset for plain text
+    pub language: Option<String>,
+    /// Up to 200 lines or 8 KiB of the file, as UTF-8
+    pub excerpt: String,
+    /// Lines in the whole file
+    pub line_count: i64,
+    /// Whether the file goes on past the excerpt
+    pub truncated: bool,
+}
+/// A blob served only to staff until they approve or destroy it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QuarantinedImage {
+    pub hash: String,
+    pub reason: String,
+    /// What flagged it: `upload`, a scanner name, or the moderator's subject
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+    /// Set once the blob was destroyed; its bytes are refused from then on
+    pub destroyed_at: Option<DateTime<Utc>>,
+    /// Sniffed type and size from the first recorded upload
+    pub mime: Option<String>,
+    pub size_bytes: Option<i64>,
+    /// Subjects that uploaded the blob
+    pub uploaded_by: Vec<String>,
+    /// Posts attaching it
+    pub attachments: i64,
+}
+/// Staff request to hold a blob back for review.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewQuarantine {
+    pub hash: String,
+    pub reason: String,
+}
+/// A hold keeping a thread, reply or blob from hard deletion; exactly one
+/// target is set. Released holds are kept as history.
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct LegalHold {
+    pub id: Id,
+    pub thread_id: Option<Id>,
+    pub reply_id: Option<Id>,
+    pub image_hash: Option<String>,
+    pub reason: String,
+    /// Admin subject that placed the hold
+    pub placed_by: String,
+    pub created_at: DateTime<Utc>,
+    pub released_at: Option<DateTime<Utc>>,
+    pub released_by: Option<String>,
+    pub release_reason: Option<String>,
+}
+/// Admin request to place a hold; set exactly one target.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewLegalHold {
+    pub thread_id: Option<Id>,
+    pub reply_id: Option<Id>,
+    pub image_hash: Option<String>,
+    /// Case or notice reference and why the target must be preserved
+    pub reason: String,
+}
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReleaseLegalHold {
+    pub reason: String,
+}
+/// Post a report flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportKind {
+    Thread,
+    Reply,
+}
+
+impl ReportKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReportKind::Thread => "thread",
+            ReportKind::Reply => "reply",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "thread" => Some(ReportKind::Thread),
+            "reply" => Some(ReportKind::Reply),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportStatus {
+    Open,
+    /// A moderator acted on the post.
+    Resolved,
+    /// A moderator found nothing to act on.
+    Dismissed,
+}
+
+impl ReportStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReportStatus::Open => "open",
+            ReportStatus::Resolved => "resolved",
+            ReportStatus::Dismissed => "dismissed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "open" => Some(ReportStatus::Open),
+            "resolved" => Some(ReportStatus::Resolved),
+            "dismissed" => Some(ReportStatus::Dismissed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewReport {
+    pub kind: ReportKind,
+    /// Thread or reply id
+    pub target_id: Id,
+    pub reason: String,
+}
+
+/// A user's flag on a thread or reply, queued for moderators.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Report {
     pub id: Id,
+    pub kind: ReportKind,
     pub target_id: Id,
+    /// The reported thread, or the reported reply's thread
+    pub thread_id: Id,
+    pub reporter: String,
     pub reason: String,
+    pub status: ReportStatus,
     pub created_at: DateTime<Utc>,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    /// Moderator's note on the outcome
+    pub note: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
 pub struct SubjectBan {
     pub subject: String,
     pub reason: String,
+    /// Shown to the banned subject; `reason` stays with staff
+    pub public_reason: Option<String>,
     pub banned_by: String,
     pub created_at: DateTime<Utc>,
     pub expires_at: Option<DateTime<Utc>>,
@@ -113,10 +511,858 @@ pub struct SubjectBan {
 pub struct NewSubjectBan {
     pub subject: String,
     pub reason: String,
+    #[serde(default)]
+    pub public_reason: Option<String>,
     pub expires_at: Option<DateTime<Utc>>,
 }
+
+/// A ban on a client address or CIDR range.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct IpBan {
+    pub id: Id,
+    /// Banned range in CIDR notation; a single address is a /32 or /128
+    pub network: String,
+    pub reason: String,
+    /// Shown to banned clients; `reason` stays with staff
+    pub public_reason: Option<String>,
+    pub banned_by: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewIpBan {
+    /// An address, e.g. `203.0.113.7`, or a range, e.g. `203.0.113.0/24`
+    pub network: String,
+    pub reason: String,
+    #[serde(default)]
+    pub public_reason: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BanScope {
+    Subject,
+    Ip,
+}
+
+/// What a banned client is told, in the `ban` field of its `403`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BanNotice {
+    /// Whether the account or the address is banned
+    pub scope: BanScope,
+    pub reason: Option<String>,
+    /// `None` for a permanent ban
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A subject's posting history, the input to its trust score.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SubjectTrust {
+    pub subject: String,
+    /// First post, or first ban for subjects that never posted
+    pub first_seen_at: DateTime<Utc>,
+    pub posts: i64,
+    /// Posts staff deleted or rejected from review and did not restore
+    pub removals: i64,
+    pub bans: i64,
+}
+
+/// A post from a low-trust subject waiting for staff review.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HeldPost {
+    pub thread_id: Id,
+    /// Unset when the thread itself is held
+    pub reply_id: Option<Id>,
+    pub board_id: Id,
+    pub subject: Option<String>,
+    /// Thread subject and body, or reply content
+    pub content: String,
+    pub held_at: DateTime<Utc>,
+}
+
+/// Moderation action an appeal contests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AppealKind {
+    /// The caller's active subject ban.
+    Ban,
+    /// Deletion of one of the caller's threads.
+    Thread,
+    /// Deletion of one of the caller's replies.
+    Reply,
+}
+
+impl AppealKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AppealKind::Ban => "ban",
+            AppealKind::Thread => "thread",
+            AppealKind::Reply => "reply",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "ban" => Some(AppealKind::Ban),
+            "thread" => Some(AppealKind::Thread),
+            "reply" => Some(AppealKind::Reply),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AppealStatus {
+    Pending,
+    /// The ban was lifted or the post restored.
+    Accepted,
+    Denied,
+}
+
+impl AppealStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AppealStatus::Pending => "pending",
+            AppealStatus::Accepted => "accepted",
+            AppealStatus::Denied => "denied",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(AppealStatus::Pending),
+            "accepted" => Some(AppealStatus::Accepted),
+            "denied" => Some(AppealStatus::Denied),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewAppeal {
+    pub kind: AppealKind,
+    /// Thread or reply id; omitted for bans
+    #[serde(default)]
+    pub target_id: Option<Id>,
+    pub message: String,
+}
+
+/// A user's request to reverse one moderation action.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Appeal {
+    pub id: Id,
+    pub subject: String,
+    pub kind: AppealKind,
+    pub target_id: Option<Id>,
+    pub message: String,
+    pub status: AppealStatus,
+    /// When the ban was issued or the post deleted; identifies the action
+    pub action_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    /// Staff note shown to the user with the decision
+    pub note: Option<String>,
+}
+
+/// A change to a subject's role assignment, kept as its role history.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RoleChange {
+    pub id: Id,
+    pub subject: String,
+    /// Admin who made the change; absent when the expiry job demoted a lapsed role
+    pub changed_by: Option<String>,
+    /// `user`, `moderator` or `admin`; absent when the subject had no assignment
+    pub from_role: Option<String>,
+    /// Absent when the assignment was removed
+    pub to_role: Option<String>,
+    /// When the new role lapses back to `user`
+    pub expires_at: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A role change to record in the role history.
+#[derive(Debug, Clone)]
+pub struct NewRoleChange {
+    pub subject: String,
+    pub changed_by: Option<String>,
+    pub from_role: Option<String>,
+    pub to_role: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+}
+
+/// An impersonation token an admin issued, or a request made with one.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ImpersonationEntry {
+    pub id: Id,
+    /// The admin's token subject
+    pub actor: String,
+    /// Subject key acted as
+    pub subject: String,
+    /// `token` when issued, `request` for each state-changing request
+    pub kind: String,
+    pub method: Option<String>,
+    /// Route pattern, e.g. `/api/v1/threads/{id}/replies`
+    pub route: Option<String>,
+    /// Response status
+    pub status: Option<i32>,
+    /// Why the token was issued
+    pub reason: Option<String>,
+    /// When the token lapses
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An impersonation log entry to record.
+#[derive(Debug, Clone)]
+pub enum NewImpersonationEntry {
+    Token {
+        actor: String,
+        subject: String,
+        reason: String,
+        expires_at: DateTime<Utc>,
+    },
+    Request {
+        actor: String,
+        subject: String,
+        method: String,
+        route: String,
+        status: u16,
+    },
+}
+
+/// What a service account may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceScope {
+    /// Read-only `GET /api/v1/admin/export`
+    Export,
+    /// Scrape `/metrics`
+    Metrics,
+    /// Post threads with `POST /api/v1/service/announcements`
+    Announce,
+}
+
+impl ServiceScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ServiceScope::Export => "export",
+            ServiceScope::Metrics => "metrics",
+            ServiceScope::Announce => "announce",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "export" => Some(ServiceScope::Export),
+            "metrics" => Some(ServiceScope::Metrics),
+            "announce" => Some(ServiceScope::Announce),
+            _ => None,
+        }
+    }
+}
+
+/// An automation credential, acting as subject `service:<name>`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ServiceAccount {
+    pub id: Id,
+    pub name: String,
+    pub scopes: Vec<ServiceScope>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Revoked accounts no longer authenticate
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewServiceAccount {
+    /// Lowercase letters, digits and dashes, e.g. `backup-job`
+    pub name: String,
+    pub scopes: Vec<ServiceScope>,
+}
+
+/// A created service account with its token, which is shown only once.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreatedServiceAccount {
+    pub account: ServiceAccount,
+    /// Send as `Authorization: Bearer <token>`; it does not expire
+    pub token: String,
+}
+
+/// A thread posted by a service account with the `announce` scope.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewAnnouncement {
+    pub board_id: Id,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct AppealDecision {
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ReportDecision {
+    #[serde(default)]
+    pub note: Option<String>,
+}
+/// How often replies in watched threads are emailed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DigestFrequency {
+    #[default]
+    Off,
+    /// Shortly after each reply, batched per polling pass.
+    Immediate,
+    Daily,
+}
+
+impl DigestFrequency {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DigestFrequency::Off => "off",
+            DigestFrequency::Immediate => "immediate",
+            DigestFrequency::Daily => "daily",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(DigestFrequency::Off),
+            "immediate" => Some(DigestFrequency::Immediate),
+            "daily" => Some(DigestFrequency::Daily),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct NotificationSettings {
+    pub email: Option<String>,
+    /// Digests are only sent once the address was confirmed from a mailed link.
+    pub email_verified: bool,
+    pub digest: DigestFrequency,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateNotificationSettings {
+    pub email: Option<String>,
+    pub digest: DigestFrequency,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct ThreadSubscription {
+    pub thread_id: Id,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a personal filter hides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterKind {
+    /// A thread id.
+    Thread,
+    /// The author of a post, given as `thread:<id>` or `reply:<id>`.
+    Subject,
+    /// A public tripcode such as `!1a2b3c4d5e6f`.
+    Tripcode,
+    /// A case-insensitive word or phrase.
+    Keyword,
+}
+
+impl FilterKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FilterKind::Thread => "thread",
+            FilterKind::Subject => "subject",
+            FilterKind::Tripcode => "tripcode",
+            FilterKind::Keyword => "keyword",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "thread" => Some(FilterKind::Thread),
+            "subject" => Some(FilterKind::Subject),
+            "tripcode" => Some(FilterKind::Tripcode),
+            "keyword" => Some(FilterKind::Keyword),
+            _ => None,
+        }
+    }
+}
+
+/// An entry of a subject's personal mute list.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserFilter {
+    pub id: Id,
+    pub kind: FilterKind,
+    pub value: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing, default)]
+    #[schema(skip)]
+    pub target_subject: Option<String>, // muted author's subject (hidden from API clients)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewUserFilter {
+    pub kind: FilterKind,
+    pub value: String,
+}
+
+/// A query whose new matches a subject is notified about.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SavedSearch {
+    pub id: Id,
+    /// Web search syntax, as for `/api/v1/search`
+    pub query: String,
+    /// Only posts on this board match; any board when unset
+    pub board_id: Option<Id>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewSavedSearch {
+    pub query: String,
+    #[serde(default)]
+    pub board_id: Option<Id>,
+}
+
+/// A post a saved search matched after it was saved.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SavedSearchMatch {
+    pub thread_id: Id,
+    /// Unset when the thread itself matched
+    pub reply_id: Option<Id>,
+    pub matched_at: DateTime<Utc>,
+    /// When a digest listed the match
+    pub notified_at: Option<DateTime<Utc>>,
+}
+
+/// A subscriber whose digest is due.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DigestRecipient {
+    pub subject: String,
+    pub email: String,
+}
+
+/// A saved search match not yet notified to its subject.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SearchDigestEntry {
+    pub match_id: Id,
+    pub query: String,
+    pub thread_id: Id,
+    pub thread_subject: String,
+    pub reply_id: Option<Id>,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A reply not yet notified to a subscriber.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DigestEntry {
+    pub thread_id: Id,
+    pub thread_subject: String,
+    pub reply_id: Id,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
 pub struct UpdateBoard {
+    /// New slug; links under the old one redirect here
     pub slug: Option<String>,
     pub title: Option<String>,
+    /// Allow posting without signing in (proof of work and stricter rate limits apply)
+    pub anonymous_posting: Option<bool>,
+    /// Let thread creators delete replies in and close their own threads
+    pub op_moderation: Option<bool>,
+    /// Active threads to keep (0 removes the limit); lowering it applies immediately
+    pub max_threads: Option<i32>,
+    /// Soft-delete overflowing threads instead of archiving them
+    pub prune_overflow: Option<bool>,
+    /// Seconds between one poster's replies in a thread (0 disables); staff are exempt
+    pub reply_cooldown_secs: Option<i32>,
+    /// Emoji accepted as reactions, replacing the current set (empty turns reactions off)
+    pub reactions: Option<Vec<String>>,
+    /// Tags new threads may carry, replacing the current vocabulary (empty accepts any tag)
+    pub tag_vocabulary: Option<Vec<String>>,
+    /// Attachment types accepted, replacing the current list: MIME types, `type/*` wildcards or
+    /// the categories images, video, audio, media, documents, text, archives (empty uses the deployment list)
+    pub allowed_mime: Option<Vec<String>>,
+    /// Show removed replies as stubs with the reason staff gave, instead of hiding them
+    pub tombstones: Option<bool>,
+    /// Restrict the board to staff and its testers (true), or launch it publicly (false)
+    pub beta: Option<bool>,
+}
+
+/// Who took a moderation action in a thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationActor {
+    /// The thread's creator: pinning on any board, otherwise with `op_moderation`.
+    Op,
+    Moderator,
+    Admin,
+}
+
+impl ModerationActor {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModerationActor::Op => "op",
+            ModerationActor::Moderator => "moderator",
+            ModerationActor::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "op" => Some(ModerationActor::Op),
+            "moderator" => Some(ModerationActor::Moderator),
+            "admin" => Some(ModerationActor::Admin),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationAction {
+    DeleteReply,
+    CloseThread,
+    ReopenThread,
+    PinReply,
+    UnpinReply,
+}
+
+impl ModerationAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModerationAction::DeleteReply => "delete_reply",
+            ModerationAction::CloseThread => "close_thread",
+            ModerationAction::ReopenThread => "reopen_thread",
+            ModerationAction::PinReply => "pin_reply",
+            ModerationAction::UnpinReply => "unpin_reply",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "delete_reply" => Some(ModerationAction::DeleteReply),
+            "close_thread" => Some(ModerationAction::CloseThread),
+            "reopen_thread" => Some(ModerationAction::ReopenThread),
+            "pin_reply" => Some(ModerationAction::PinReply),
+            "unpin_reply" => Some(ModerationAction::UnpinReply),
+            _ => None,
+        }
+    }
+}
+
+/// A thread moderation action to record in the audit log.
+#[derive(Debug, Clone)]
+pub struct NewModerationEntry {
+    /// Subject key of the thread creator, or the staff token subject.
+    pub actor: String,
+    pub actor_role: ModerationActor,
+    pub action: ModerationAction,
+    pub thread_id: Id,
+    pub reply_id: Option<Id>,
+}
+
+/// Body of every staff and thread-creator delete endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeletionReason {
+    /// Why the post is removed, e.g. "Rule 3: no spam"; public on boards with `tombstones`
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeletionKind {
+    Board,
+    Thread,
+    Reply,
+}
+
+impl DeletionKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeletionKind::Board => "board",
+            DeletionKind::Thread => "thread",
+            DeletionKind::Reply => "reply",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "board" => Some(DeletionKind::Board),
+            "thread" => Some(DeletionKind::Thread),
+            "reply" => Some(DeletionKind::Reply),
+            _ => None,
+        }
+    }
+}
+
+/// A removal to record in the deletion log.
+#[derive(Debug, Clone)]
+pub struct NewDeletionEntry {
+    pub kind: DeletionKind,
+    pub target_id: Id,
+    pub hard: bool,
+    /// Subject key of the thread creator, or the staff token subject.
+    pub actor: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeletionEntry {
+    pub id: Id,
+    pub kind: DeletionKind,
+    pub target_id: Id,
+    /// Deleted outright rather than soft-deleted
+    pub hard: bool,
+    pub actor: String,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PinReply {
+    pub reply_id: Id,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ModerationEntry {
+    pub id: Id,
+    pub actor: String,
+    pub actor_role: ModerationActor,
+    pub action: ModerationAction,
+    pub thread_id: Id,
+    pub reply_id: Option<Id>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A thread staff scheduled to be posted by the background runner.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScheduledThread {
+    pub id: Id,
+    pub board_id: Id,
+    pub subject: String,
+    pub body: String,
+    pub author_name: Option<String>,
+    /// Next time the thread is posted
+    pub publish_at: DateTime<Utc>,
+    /// Post again this many days after each run; one-off when absent
+    pub repeat_days: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    /// Thread posted by the latest run
+    pub last_thread_id: Option<Id>,
+    /// Set once a one-off schedule has run
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing, default)]
+    #[schema(skip)]
+    pub created_by: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewScheduledThread {
+    pub board_id: Id,
+    pub subject: String,
+    pub body: String,
+    #[serde(default)]
+    pub author_name: Option<String>,
+    /// Must be in the future
+    pub publish_at: DateTime<Utc>,
+    /// Repeat every N days (1 to 365), e.g. 7 for a weekly general
+    #[serde(default)]
+    pub repeat_days: Option<i32>,
+}
+
+/// Where a board bridge announces new threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BridgeKind {
+    /// A Discord webhook URL.
+    Discord,
+    /// A Telegram chat id or `@channel`, posted to by the configured bot.
+    Telegram,
+    /// A Nostr relay `wss://` URL, published to with the configured key.
+    Nostr,
+}
+
+impl BridgeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BridgeKind::Discord => "discord",
+            BridgeKind::Telegram => "telegram",
+            BridgeKind::Nostr => "nostr",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "discord" => Some(BridgeKind::Discord),
+            "telegram" => Some(BridgeKind::Telegram),
+            "nostr" => Some(BridgeKind::Nostr),
+            _ => None,
+        }
+    }
+}
+
+/// A subject allowed onto a beta board before launch.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct BoardTester {
+    pub board_id: Id,
+    pub subject: String,
+    pub added_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewBoardTester {
+    /// Provider subject key, e.g. `discord:1234`
+    pub subject: String,
+}
+
+/// An outbound bridge announcing a board's new threads elsewhere.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BoardBridge {
+    pub id: Id,
+    pub board_id: Id,
+    pub kind: BridgeKind,
+    pub target: String,
+    pub enabled: bool,
+    /// Newest thread announced, or the newest when the bridge was enabled
+    pub last_thread_id: Id,
+    pub last_sent_at: Option<DateTime<Utc>>,
+    /// Why the latest delivery failed; cleared by the next success
+    pub last_error: Option<String>,
+    /// Posts sent in the current one-minute rate limit window
+    #[serde(skip_serializing, default)]
+    #[schema(skip)]
+    pub window_sent: i32,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewBoardBridge {
+    pub kind: BridgeKind,
+    pub target: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateBoardBridge {
+    /// Re-enabling skips threads posted while the bridge was off
+    pub enabled: bool,
+}
+
+/// A Matrix room mirroring the posts of a board or of a single thread.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct MatrixMirror {
+    pub id: Id,
+    /// Set when the whole board is mirrored
+    pub board_id: Option<Id>,
+    /// Set when a single thread is mirrored
+    pub thread_id: Option<Id>,
+    pub room_id: String,
+    pub enabled: bool,
+    /// Newest thread posted to the room, or the newest when the mirror was enabled
+    pub last_thread_id: Id,
+    /// Newest reply posted to the room, or the newest when the mirror was enabled
+    pub last_reply_id: Id,
+    pub last_sent_at: Option<DateTime<Utc>>,
+    /// Why the latest pass failed; cleared by the next success
+    pub last_error: Option<String>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Mirror either `board_id` or `thread_id` into a room, e.g. `!abc:matrix.org`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewMatrixMirror {
+    #[serde(default)]
+    pub board_id: Option<Id>,
+    #[serde(default)]
+    pub thread_id: Option<Id>,
+    pub room_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateMatrixMirror {
+    /// Re-enabling skips posts made while the mirror was off
+    pub enabled: bool,
+}
+
+/// A thread or reply waiting to be posted to a Matrix room.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct MatrixPost {
+    pub thread_id: Id,
+    /// `None` for the opening post of a thread
+    pub reply_id: Option<Id>,
+    pub subject: String,
+    pub body: String,
+    pub author_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The room event a mirrored post became.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct MatrixEvent {
+    pub event_id: String,
+    pub thread_id: Id,
+    pub reply_id: Option<Id>,
+}
+
+/// A remote ActivityPub actor following a board.
+#[derive(Debug, Clone)]
+pub struct NewApFollower {
+    pub actor: String,
+    pub inbox: String,
+    pub shared_inbox: Option<String>,
+}
+
+/// A signed ActivityPub delivery waiting for a remote inbox.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ApDelivery {
+    pub id: Id,
+    pub board_id: Id,
+    pub inbox: String,
+    pub activity: Value,
+    pub attempts: i32,
+}
+
+/// Domain event recorded in the transactional outbox.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct OutboxEvent {
+    pub id: Id,
+    pub event_type: String,
+    pub payload: Value,
+    pub created_at: DateTime<Utc>,
+    pub attempts: i32,
+}
+
+/// A thread or reply matched by `/api/v1/search`.
+/// A thread with its first replies, for catalog hover previews.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ThreadPreview {
+    pub thread: Thread,
+    pub replies: Vec<Reply>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct SearchHit {
+    pub kind: String, // "thread" | "reply"
+    pub id: Id,
+    pub thread_id: Id,
+    pub board_id: Id,
+    pub excerpt: String,
+    pub created_at: DateTime<Utc>,
 }
@@ -0,0 +1,453 @@
+//! Response-format negotiation for listing endpoints.
+//!
+//! Handlers that implement [`TextDump`] for their items can answer
+//! `Accept: text/plain` with a readable dump and
+//! `Accept: text/tab-separated-values` with one row per item, while JSON
+//! stays the default. `Accept: application/x-ndjson` gets one JSON object per
+//! line, which large listings stream straight from the database. JSON and
+//! NDJSON items can be trimmed with `?fields=id,subject` ([`Fields`]).
+
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::web::Bytes;
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::{Stream, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::error::ApiError;
+use crate::models::{Reply, Thread};
+use crate::repo::RepoResult;
+
+pub const TEXT_PLAIN: &str = "text/plain";
+pub const TSV: &str = "text/tab-separated-values";
+pub const NDJSON: &str = "application/x-ndjson";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Text,
+    Tsv,
+    Ndjson,
+}
+
+impl Format {
+    /// Pick the highest-weighted format the client accepts. Anything we
+    /// cannot serve, a missing header and wildcards all mean JSON.
+    pub fn from_accept(accept: Option<&str>) -> Self {
+        let Some(accept) = accept else {
+            return Format::Json;
+        };
+        // (format, weight, named explicitly rather than by a wildcard)
+        let mut best = (Format::Json, 0.0_f32, false);
+        for range in accept.split(',') {
+            let mut parts = range.split(';');
+            let media = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+            let q = parts
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|v| v.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let (format, explicit) = match media.as_str() {
+                "application/json" => (Format::Json, true),
+                "application/*" | "*/*" => (Format::Json, false),
+                TEXT_PLAIN => (Format::Text, true),
+                TSV => (Format::Tsv, true),
+                NDJSON => (Format::Ndjson, true),
+                _ => continue,
+            };
+            // On equal weight an explicit type beats a wildcard, else the earlier entry wins.
+            if q > best.1 || (q == best.1 && explicit && !best.2) {
+                best = (format, q, explicit);
+            }
+        }
+        best.0
+    }
+
+    pub fn from_request(req: &HttpRequest) -> Self {
+        Self::from_accept(
+            req.headers()
+                .get(header::ACCEPT)
+                .and_then(|v| v.to_str().ok()),
+        )
+    }
+}
+
+/// Plain-text and TSV renderings of a listing item.
+pub trait TextDump {
+    /// TSV column names, in row order.
+    const COLUMNS: &'static [&'static str];
+
+    /// Append a human-readable block, ending with a blank line.
+    fn write_text(&self, out: &mut String);
+
+    /// Field values in [`Self::COLUMNS`] order; escaping is done by the caller.
+    fn tsv_row(&self) -> Vec<String>;
+}
+
+/// Escape a TSV field the way `COPY ... TEXT` does, so tabs and newlines in
+/// post bodies cannot break rows.
+fn tsv_field(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn render_text<T: TextDump>(items: &[T]) -> String {
+    let mut out = String::new();
+    for item in items {
+        item.write_text(&mut out);
+    }
+    out
+}
+
+pub fn render_tsv<T: TextDump>(items: &[T]) -> String {
+    let mut out = T::COLUMNS.join("\t");
+    out.push('\n');
+    for item in items {
+        let row: Vec<String> = item.tsv_row().iter().map(|f| tsv_field(f)).collect();
+        out.push_str(&row.join("\t"));
+        out.push('\n');
+    }
+    out
+}
+
+/// Listing items that `?fields=` can trim.
+pub trait Selectable {
+    /// Serialized field names.
+    const FIELDS: &'static [&'static str];
+}
+
+/// Fields a client asked for with `?fields=id,subject`; empty keeps them all.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fields(Vec<String>);
+
+impl Fields {
+    /// Parse a comma-separated list; names `T` does not serialize are rejected.
+    pub fn parse<T: Selectable>(value: &str) -> Result<Self, ApiError> {
+        let mut fields: Vec<String> = Vec::new();
+        for name in value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            if !T::FIELDS.contains(&name) {
+                return Err(ApiError::Invalid(format!(
+                    "unknown field {name:?}; fields are {}",
+                    T::FIELDS.join(", ")
+                )));
+            }
+            if !fields.iter().any(|f| f == name) {
+                fields.push(name.to_string());
+            }
+        }
+        Ok(Self(fields))
+    }
+
+    pub fn from_request<T: Selectable>(req: &HttpRequest) -> Result<Self, ApiError> {
+        let query = web::Query::<HashMap<String, String>>::from_query(req.query_string())
+            .map_err(|_| ApiError::BadRequest)?;
+        match query.get("fields") {
+            Some(value) => Self::parse::<T>(value),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn is_all(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// `item` as JSON with only the selected fields.
+    pub fn select<T: Serialize>(&self, item: &T) -> Result<Value, ApiError> {
+        let mut value = serde_json::to_value(item).map_err(|_| ApiError::Internal)?;
+        if let (false, Value::Object(map)) = (self.is_all(), &mut value) {
+            map.retain(|key, _| self.0.iter().any(|f| f == key));
+        }
+        Ok(value)
+    }
+}
+
+fn ndjson_line<T: Serialize>(item: &T, fields: &Fields) -> Result<Vec<u8>, ApiError> {
+    let mut line = if fields.is_all() {
+        serde_json::to_vec(item)
+    } else {
+        serde_json::to_vec(&fields.select(item)?)
+    }
+    .map_err(|_| ApiError::Internal)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+pub fn render_ndjson<T: Serialize>(items: &[T], fields: &Fields) -> Result<Vec<u8>, ApiError> {
+    let mut out = Vec::new();
+    for item in items {
+        out.extend(ndjson_line(item, fields)?);
+    }
+    Ok(out)
+}
+
+/// `200 OK` streaming `rows` as NDJSON. A row that fails after the head was
+/// sent aborts the response, so clients see a truncated body rather than a
+/// well-formed partial listing.
+pub fn stream_ndjson<T, S>(fields: Fields, rows: S) -> HttpResponse
+where
+    T: Serialize,
+    S: Stream<Item = RepoResult<T>> + 'static,
+{
+    let body = rows.map(move |row| {
+        let row = row.map_err(|e| {
+            log::error!("streamed listing failed: {e}");
+            ApiError::from(e)
+        })?;
+        ndjson_line(&row, &fields).map(Bytes::from)
+    });
+    let mut resp = HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(body);
+    resp.headers_mut()
+        .append(header::VARY, HeaderValue::from_static("Accept"));
+    resp
+}
+
+/// `200 OK` with `items` in the negotiated format; `400` for a bad `?fields=`.
+pub fn respond<T: Serialize + TextDump + Selectable>(
+    req: &HttpRequest,
+    items: &[T],
+) -> Result<HttpResponse, ApiError> {
+    let fields = Fields::from_request::<T>(req)?;
+    let mut resp = match Format::from_request(req) {
+        Format::Json if fields.is_all() => HttpResponse::Ok().json(items),
+        Format::Json => HttpResponse::Ok().json(
+            items
+                .iter()
+                .map(|item| fields.select(item))
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        Format::Ndjson => HttpResponse::Ok()
+            .content_type("application/x-ndjson")
+            .body(render_ndjson(items, &fields)?),
+        Format::Text => HttpResponse::Ok()
+            .content_type("text/plain; charset=utf-8")
+            .body(render_text(items)),
+        Format::Tsv => HttpResponse::Ok()
+            .content_type("text/tab-separated-values; charset=utf-8")
+            .body(render_tsv(items)),
+    };
+    resp.headers_mut()
+        .append(header::VARY, HeaderValue::from_static("Accept"));
+    Ok(resp)
+}
+
+fn author(name: &Option<String>, tripcode: &Option<String>) -> String {
+    let mut s = name.as_deref().unwrap_or("Anonymous").to_string();
+    if let Some(trip) = tripcode {
+        let _ = write!(s, " {trip}");
+    }
+    s
+}
+
+fn opt(v: &Option<String>) -> String {
+    v.clone().unwrap_or_default()
+}
+
+fn indented(out: &mut String, text: &str) {
+    for line in text.lines() {
+        let _ = writeln!(out, "    {line}");
+    }
+}
+
+impl Selectable for Thread {
+    const FIELDS: &'static [&'static str] = &[
+        "id",
+        "board_id",
+        "subject",
+        "body",
+        "created_at",
+        "bump_time",
+        "image_hash",
+        "mime",
+        "author_name",
+        "tripcode",
+        "deleted_at",
+        "closed_at",
+        "archived_at",
+        "pinned_reply_id",
+        "tags",
+        "reply_count",
+        "image_count",
+        "slug",
+        "author",
+    ];
+}
+
+impl Selectable for Reply {
+    const FIELDS: &'static [&'static str] = &[
+        "id",
+        "thread_id",
+        "content",
+        "image_hash",
+        "mime",
+        "author_name",
+        "tripcode",
+        "created_at",
+        "deleted_at",
+        "author",
+        "reactions",
+        "removal_reason",
+    ];
+}
+
+impl TextDump for Thread {
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "board_id",
+        "created_at",
+        "bump_time",
+        "author_name",
+        "tripcode",
+        "image_hash",
+        "subject",
+        "body",
+    ];
+
+    fn write_text(&self, out: &mut String) {
+        let _ = writeln!(out, "#{} {}", self.id, self.subject);
+        let _ = writeln!(
+            out,
+            "{} | {} | bumped {}",
+            author(&self.author_name, &self.tripcode),
+            self.created_at.format("%Y-%m-%d %H:%M UTC"),
+            self.bump_time.format("%Y-%m-%d %H:%M UTC"),
+        );
+        if let Some(hash) = &self.image_hash {
+            let _ = writeln!(out, "[image /images/{hash}]");
+        }
+        indented(out, &self.body);
+        out.push('\n');
+    }
+
+    fn tsv_row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.board_id.to_string(),
+            self.created_at.to_rfc3339(),
+            self.bump_time.to_rfc3339(),
+            opt(&self.author_name),
+            opt(&self.tripcode),
+            opt(&self.image_hash),
+            self.subject.clone(),
+            self.body.clone(),
+        ]
+    }
+}
+
+impl TextDump for Reply {
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "thread_id",
+        "created_at",
+        "author_name",
+        "tripcode",
+        "image_hash",
+        "content",
+    ];
+
+    fn write_text(&self, out: &mut String) {
+        let _ = writeln!(
+            out,
+            "#{} {} | {}",
+            self.id,
+            author(&self.author_name, &self.tripcode),
+            self.created_at.format("%Y-%m-%d %H:%M UTC"),
+        );
+        if let Some(hash) = &self.image_hash {
+            let _ = writeln!(out, "[image /images/{hash}]");
+        }
+        if let Some(reason) = &self.removal_reason {
+            let _ = writeln!(out, "[removed: {reason}]");
+        }
+        indented(out, &self.content);
+        out.push('\n');
+    }
+
+    fn tsv_row(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.thread_id.to_string(),
+            self.created_at.to_rfc3339(),
+            opt(&self.author_name),
+            opt(&self.tripcode),
+            opt(&self.image_hash),
+            self.content.clone(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_header_picks_highest_weight() {
+        assert_eq!(Format::from_accept(None), Format::Json);
+        assert_eq!(Format::from_accept(Some("*/*")), Format::Json);
+        assert_eq!(Format::from_accept(Some("text/plain")), Format::Text);
+        assert_eq!(
+            Format::from_accept(Some("text/plain;q=0.5, text/tab-separated-values")),
+            Format::Tsv
+        );
+        assert_eq!(
+            Format::from_accept(Some("text/plain, */*;q=0.1")),
+            Format::Text
+        );
+        assert_eq!(Format::from_accept(Some("*/*, text/plain")), Format::Text);
+        assert_eq!(Format::from_accept(Some("text/html")), Format::Json);
+        assert_eq!(
+            Format::from_accept(Some("application/x-ndjson, application/json;q=0.9")),
+            Format::Ndjson
+        );
+    }
+
+    #[test]
+    fn fields_select_known_names_only() {
+        let reply = Reply {
+            id: 7,
+            thread_id: 3,
+            content: "hello".into(),
+            image_hash: None,
+            mime: None,
+            image_size: None,
+            author_name: None,
+            tripcode: None,
+            created_at: chrono::Utc::now(),
+            deleted_at: None,
+            created_by: Value::Null,
+            author: None,
+            reactions: sqlx::types::Json(Vec::new()),
+            removal_reason: None,
+        };
+        let everything = Fields::default().select(&reply).unwrap();
+        for key in everything.as_object().unwrap().keys() {
+            assert!(
+                Reply::FIELDS.contains(&key.as_str()),
+                "{key} not selectable"
+            );
+        }
+        let fields = Fields::parse::<Reply>(" id, content,,id ").unwrap();
+        assert_eq!(
+            fields.select(&reply).unwrap(),
+            serde_json::json!({"id": 7, "content": "hello"})
+        );
+        assert!(Fields::parse::<Reply>("id,created_by").is_err());
+        assert!(Fields::parse::<Thread>("subject,reply_count").is_ok());
+    }
+
+    #[test]
+    fn tsv_fields_escape_separators() {
+        assert_eq!(tsv_field("a\tb\nc\\d"), "a\\tb\\nc\\\\d");
+    }
+}
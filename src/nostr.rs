@@ -0,0 +1,272 @@
+//! Nostr key sign-in (NIP-98 style).
+//!
+//! `POST /api/v1/auth/nostr/challenge` hands out a single-use challenge. The
+//! client signs a kind 27235 HTTP-auth event with its Nostr key, tagged with
+//! the verify URL (`u`), `method` `POST` and the `challenge`, and posts it to
+//! `POST /api/v1/auth/nostr/verify`. The event id must hash its content
+//! (NIP-01), the BIP-340 signature must match the event's key, it must be
+//! recent, and the challenge must be one this server issued and has not seen
+//! redeemed. The session subject is `nostr:<npub>`.
+
+use actix_web::{web, HttpResponse};
+use bitcoin::bech32::{self, ToBase32, Variant};
+use rand::RngCore;
+use secp256k1::{schnorr, Keypair, Message, Secp256k1, XOnlyPublicKey};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+use crate::auth::{session_cookie, Role};
+use crate::error::ApiError;
+use crate::routes::{ensure_subject_not_banned, AppState};
+
+/// NIP-98 HTTP auth event kind.
+pub const HTTP_AUTH_KIND: u32 = 27235;
+
+/// How long an issued challenge can be redeemed.
+const CHALLENGE_TTL: Duration = Duration::from_secs(300);
+
+/// Allowed distance between the event's `created_at` and now (NIP-98).
+const MAX_CLOCK_SKEW_SECS: i64 = 60;
+
+/// URL the event's `u` tag must name: `NOSTR_AUTH_URL`, or the verify route
+/// under `FRONTEND_URL`.
+pub fn auth_url() -> String {
+    std::env::var("NOSTR_AUTH_URL")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| {
+            let frontend = std::env::var("FRONTEND_URL")
+                .unwrap_or_else(|_| "http://localhost:5173".to_string());
+            format!(
+                "{}/api/v1/auth/nostr/verify",
+                frontend.trim_end_matches('/')
+            )
+        })
+}
+
+/// A signed Nostr event (NIP-01).
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct NostrEvent {
+    /// Hex sha256 of the serialized event
+    pub id: String,
+    /// Hex x-only public key
+    pub pubkey: String,
+    pub created_at: i64,
+    pub kind: u32,
+    pub tags: Vec<Vec<String>>,
+    pub content: String,
+    /// Hex BIP-340 signature of `id`
+    pub sig: String,
+}
+
+impl NostrEvent {
+    /// The NIP-01 id: sha256 of `[0, pubkey, created_at, kind, tags, content]`.
+    pub fn compute_id(&self) -> [u8; 32] {
+        let serialized = serde_json::json!([
+            0,
+            self.pubkey,
+            self.created_at,
+            self.kind,
+            self.tags,
+            self.content
+        ])
+        .to_string();
+        Sha256::digest(serialized.as_bytes()).into()
+    }
+
+    /// Build and sign an event with `keypair`.
+    pub fn sign(
+        keypair: &Keypair,
+        created_at: i64,
+        kind: u32,
+        tags: Vec<Vec<String>>,
+        content: String,
+    ) -> Self {
+        let (pubkey, _) = keypair.x_only_public_key();
+        let mut event = Self {
+            id: String::new(),
+            pubkey: hex::encode(pubkey.serialize()),
+            created_at,
+            kind,
+            tags,
+            content,
+            sig: String::new(),
+        };
+        let id = event.compute_id();
+        let sig = Secp256k1::signing_only().sign_schnorr_with_aux_rand(
+            &Message::from_digest_slice(&id).expect("sha256 digests are 32 bytes"),
+            keypair,
+            &rand::random(),
+        );
+        event.id = hex::encode(id);
+        event.sig = hex::encode(sig.as_ref());
+        event
+    }
+
+    /// First value of the tag named `name`.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|tag| tag.first().map(String::as_str) == Some(name))
+            .and_then(|tag| tag.get(1))
+            .map(String::as_str)
+    }
+
+    /// Check the id and signature; returns the signing key.
+    pub fn verify_signature(&self) -> anyhow::Result<XOnlyPublicKey> {
+        let id = self.compute_id();
+        if hex::decode(&self.id)? != id {
+            anyhow::bail!("event id does not match its content");
+        }
+        let pubkey = XOnlyPublicKey::from_slice(&hex::decode(&self.pubkey)?)?;
+        let sig = schnorr::Signature::from_slice(&hex::decode(&self.sig)?)?;
+        Secp256k1::verification_only().verify_schnorr(
+            &sig,
+            &Message::from_digest_slice(&id)?,
+            &pubkey,
+        )?;
+        Ok(pubkey)
+    }
+}
+
+/// NIP-19 `npub` encoding of a public key.
+pub fn npub(pubkey: &XOnlyPublicKey) -> String {
+    bech32::encode("npub", pubkey.serialize().to_base32(), Variant::Bech32)
+        .expect("npub is a valid human-readable part")
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct NostrChallengeResponse {
+    /// Value for the event's `challenge` tag
+    pub challenge: String,
+    /// Value for the event's `u` tag
+    pub url: String,
+    pub kind: u32,
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct NostrVerifyRequest {
+    pub event: NostrEvent,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct NostrVerifyResponse {
+    pub token: String,
+}
+
+fn store_error(e: anyhow::Error) -> ApiError {
+    log::error!("nostr sign-in challenge store failed: {e}");
+    ApiError::Internal
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/nostr/challenge",
+    responses(
+        (status = 200, description = "Challenge to sign into an HTTP auth event", body = NostrChallengeResponse)
+    )
+)]
+pub async fn nostr_challenge(data: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let challenge = hex::encode(bytes);
+    data.challenges
+        .put("nostr", &challenge, "", CHALLENGE_TTL)
+        .await
+        .map_err(store_error)?;
+    Ok(HttpResponse::Ok().json(NostrChallengeResponse {
+        challenge,
+        url: auth_url(),
+        kind: HTTP_AUTH_KIND,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/nostr/verify",
+    request_body = NostrVerifyRequest,
+    responses(
+        (status = 200, description = "JWT token", body = NostrVerifyResponse),
+        (status = 400, description = "Malformed, stale or badly signed event, or unknown challenge"),
+        (status = 403, description = "Banned")
+    )
+)]
+pub async fn nostr_verify(
+    payload: web::Json<NostrVerifyRequest>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let event = &payload.event;
+    let now = chrono::Utc::now().timestamp();
+    if event.kind != HTTP_AUTH_KIND
+        || (event.created_at - now).abs() > MAX_CLOCK_SKEW_SECS
+        || event.tag("u") != Some(auth_url().as_str())
+        || !event
+            .tag("method")
+            .is_some_and(|m| m.eq_ignore_ascii_case("POST"))
+    {
+        return Err(ApiError::BadRequest);
+    }
+    let challenge = event.tag("challenge").ok_or(ApiError::BadRequest)?;
+    let pubkey = event.verify_signature().map_err(|e| {
+        log::info!("nostr event verify failed: {e}");
+        ApiError::BadRequest
+    })?;
+    // Only after the signature checks out, so forged events cannot burn
+    // someone else's challenge.
+    data.challenges
+        .take("nostr", challenge)
+        .await
+        .map_err(store_error)?
+        .ok_or(ApiError::BadRequest)?;
+    let subject = format!("nostr:{}", npub(&pubkey));
+    ensure_subject_not_banned(data.get_ref(), &subject).await?;
+    let role = data
+        .repo
+        .get_subject_role(&subject)
+        .await
+        .unwrap_or(Role::User);
+    let jwt =
+        crate::auth::create_jwt(&subject, &subject, vec![role]).map_err(|_| ApiError::Internal)?;
+    Ok(HttpResponse::Ok()
+        .cookie(session_cookie(&jwt))
+        .json(NostrVerifyResponse { token: jwt }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_npub_per_nip19() {
+        // Example from NIP-19.
+        let pubkey = XOnlyPublicKey::from_slice(
+            &hex::decode("7e7e9c42a91bfef19fa929e5fda1b72e0ebc1a4c1141673e2794234d86addf4e")
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            npub(&pubkey),
+            "npub10elfcs4fr0l0r8af98jlmgdh9c8tcxjvz9qkw038js35mp4dma8qzvjptg"
+        );
+    }
+
+    #[test]
+    fn verifies_signed_events() {
+        let secp = Secp256k1::new();
+        let keypair = secp256k1::Keypair::from_seckey_slice(&secp, &[7u8; 32]).unwrap();
+        let (pubkey, _) = keypair.x_only_public_key();
+        let event = NostrEvent::sign(
+            &keypair,
+            1_700_000_000,
+            HTTP_AUTH_KIND,
+            vec![vec!["challenge".into(), "abc".into()]],
+            "line\n\"quoted\"".into(),
+        );
+        assert_eq!(event.verify_signature().unwrap(), pubkey);
+        assert_eq!(event.tag("challenge"), Some("abc"));
+
+        let mut edited = event.clone();
+        edited.content.push('!');
+        assert!(edited.verify_signature().is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use async_trait::async_trait;
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use crate::cache::BoardCache;
+use crate::live::{LiveBus, LiveHub};
+use crate::models::OutboxEvent;
+
+/// Channel notified by triggers with the name of the changed table.
+pub const CACHE_CHANNEL: &str = "rib_cache";
+/// Channel notified whenever outbox rows are committed.
+pub const OUTBOX_CHANNEL: &str = "rib_outbox";
+/// Channel carrying serialized live-update events between replicas.
+pub const LIVE_CHANNEL: &str = "rib_live";
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// Live-update bus over Postgres NOTIFY, for deployments without Redis.
+/// Payloads are small JSON events, well under the 8000 byte NOTIFY limit.
+pub struct PgLiveBus {
+    pool: PgPool,
+}
+
+impl PgLiveBus {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    async fn listen(&self, hub: &LiveHub) -> anyhow::Result<()> {
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        listener.listen(LIVE_CHANNEL).await?;
+        log::info!("live updates listening on postgres channel {LIVE_CHANNEL}");
+        loop {
+            let notification = listener.recv().await?;
+            match serde_json::from_str::<OutboxEvent>(notification.payload()) {
+                Ok(event) => {
+                    hub.publish_local(event);
+                }
+                Err(e) => log::warn!("ignoring malformed live event: {e}"),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl LiveBus for PgLiveBus {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn publish(&self, event: &OutboxEvent) -> anyhow::Result<()> {
+        sqlx::query!(
+            "SELECT pg_notify($1, $2)",
+            LIVE_CHANNEL,
+            serde_json::to_string(event)?
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    fn spawn_listener(self: Arc<Self>, hub: LiveHub) -> JoinHandle<()> {
+        actix_web::rt::spawn(async move {
+            loop {
+                if let Err(e) = self.listen(&hub).await {
+                    log::warn!("postgres live listener error: {e}; reconnecting");
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        })
+    }
+}
+
+/// Reacts to trigger notifications: drops stale caches and wakes the outbox relay.
+pub struct ChangeListener {
+    pool: PgPool,
+    boards: Option<BoardCache>,
+    outbox_wakeup: Option<Arc<Notify>>,
+}
+
+impl ChangeListener {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            boards: None,
+            outbox_wakeup: None,
+        }
+    }
+
+    pub fn with_board_cache(mut self, cache: BoardCache) -> Self {
+        self.boards = Some(cache);
+        self
+    }
+
+    pub fn with_outbox_wakeup(mut self, wakeup: Arc<Notify>) -> Self {
+        self.outbox_wakeup = Some(wakeup);
+        self
+    }
+
+    /// Caches are only trusted while notifications can reach us.
+    fn set_caches_active(&self, active: bool) {
+        if let Some(cache) = &self.boards {
+            cache.set_active(active);
+        }
+    }
+
+    fn dispatch(&self, channel: &str, payload: &str) {
+        match (channel, payload) {
+            (CACHE_CHANNEL, "boards") => {
+                if let Some(cache) = &self.boards {
+                    cache.invalidate();
+                }
+            }
+            (OUTBOX_CHANNEL, _) => {
+                if let Some(wakeup) = &self.outbox_wakeup {
+                    wakeup.notify_one();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    async fn listen(&self) -> anyhow::Result<()> {
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        listener.listen_all([CACHE_CHANNEL, OUTBOX_CHANNEL]).await?;
+        self.set_caches_active(true);
+        loop {
+            match listener.try_recv().await? {
+                Some(notification) => {
+                    self.dispatch(notification.channel(), notification.payload());
+                }
+                // Notifications may have been missed; resubscribe from scratch.
+                None => anyhow::bail!("listener connection lost"),
+            }
+        }
+    }
+
+    /// Abort the returned task before the runtime shuts down so the listener
+    /// connection is released while a runtime is still available.
+    pub fn spawn(self) -> JoinHandle<()> {
+        actix_web::rt::spawn(async move {
+            loop {
+                if let Err(e) = self.listen().await {
+                    log::warn!("change listener error: {e}; reconnecting");
+                }
+                self.set_caches_active(false);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        })
+    }
+}
@@ -1,6 +1,20 @@
 use crate::models::{
-    Board, Image, NewBoard, NewReply, NewSubjectBan, NewThread, Reply, Report, SubjectBan, Thread,
+    Appeal, AppealDecision, AppealKind, AppealStatus, ArchiveEntry, ArchiveListing, AttachedPost,
+    AuthorProfile, BanNotice, BanScope, Board, BoardBridge, BoardTester, BridgeKind,
+    CreatedServiceAccount, DeletionEntry, DeletionKind, DeletionReason, DigestFrequency,
+    FilterKind, HeldPost, Image, ImageDetails, ImageReference, ImpersonationEntry, IpBan,
+    LegalHold, MatrixMirror, ModerationAction, ModerationActor, ModerationEntry, NewAnnouncement,
+    NewAppeal, NewBoard, NewBoardBridge, NewBoardTester, NewIpBan, NewLegalHold, NewMatrixMirror,
+    NewQuarantine, NewReaction, NewReply, NewReport, NewSavedSearch, NewScheduledThread,
+    NewServiceAccount, NewSubjectBan, NewThread, NewUserFilter, NotificationSettings, PinReply,
+    QuarantinedImage, ReactionCount, ReleaseLegalHold, Reply, ReplySort, Report, ReportDecision,
+    ReportKind, ReportStatus, RoleChange, SavedSearch, SavedSearchMatch, ScheduledThread,
+    SearchHit, ServiceAccount, ServiceScope, SortOrder, SubjectBan, SubjectTrust, TagCount,
+    TextExcerpt, Thread, ThreadPreview, ThreadSort, ThreadSubscription, UpdateBoardBridge,
+    UpdateMatrixMirror, UpdateNotificationSettings, UpdateProfile, UploadRecord, UserFilter,
 };
+use actix_web::HttpResponse;
+use once_cell::sync::Lazy;
 use utoipa::{Modify, OpenApi};
 
 struct SecurityAddon;
@@ -18,47 +32,230 @@ impl Modify for SecurityAddon {
     }
 }
 
+/// Stamps the build into `info`: `version` becomes `{version}+{sha}`.
+struct BuildInfo;
+
+impl Modify for BuildInfo {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi.info.version = crate::version::spec_version();
+        let built = crate::version::build_time()
+            .map(|at| format!(", built {}", at.to_rfc3339()))
+            .unwrap_or_default();
+        openapi.info.description = Some(format!(
+            "Commit {}{built}. `GET /api/v1/version` reports the same, with compiled-in features.",
+            crate::version::GIT_SHA
+        ));
+    }
+}
+
 #[derive(OpenApi)]
 #[openapi(
     paths(
         crate::routes::list_boards,
         crate::routes::create_board,
         crate::routes::list_threads,
+        crate::routes::list_archived_threads,
+        crate::routes::list_board_tags,
         crate::routes::create_thread,
         crate::routes::get_thread,
+        crate::routes::get_thread_by_slug,
         crate::routes::list_replies,
         crate::routes::create_reply,
+        crate::routes::search,
+        crate::routes::batch_threads,
+        crate::routes::pow_challenge,
+        crate::routes::pow_clearance,
+        crate::version::version,
+        crate::readiness::readyz,
+        crate::routes::delete_thread_with_password,
+        crate::routes::delete_reply_with_password,
+        crate::routes::close_thread,
+        crate::routes::reopen_thread,
+        crate::routes::moderate_delete_reply,
+        crate::routes::pin_reply,
+        crate::routes::unpin_reply,
+        crate::routes::react_to_reply,
+        crate::routes::remove_reaction,
+        crate::routes::live_events,
         crate::routes::update_board,
         crate::routes::auth_me,
         crate::routes::bitcoin_challenge,
         crate::routes::bitcoin_verify,
+        crate::ethereum::ethereum_challenge,
+        crate::ethereum::ethereum_verify,
+        crate::nostr::nostr_challenge,
+        crate::nostr::nostr_verify,
+        crate::routes::email_login_start,
+        crate::routes::subscribe_thread,
+        crate::routes::unsubscribe_thread,
+        crate::routes::list_my_subscriptions,
+        crate::routes::get_my_notifications,
+        crate::routes::put_my_notifications,
+        crate::routes::get_my_preferences,
+        crate::routes::put_my_preferences,
+        crate::routes::list_my_filters,
+        crate::routes::create_my_filter,
+        crate::routes::delete_my_filter,
+        crate::routes::list_my_saved_searches,
+        crate::routes::create_my_saved_search,
+        crate::routes::delete_my_saved_search,
+        crate::routes::list_my_saved_search_matches,
+        crate::routes::file_appeal,
+        crate::routes::file_report,
+        crate::routes::list_my_appeals,
+        crate::routes::get_my_profile,
+        crate::routes::put_my_profile,
+        crate::routes::put_my_avatar,
+        crate::routes::delete_my_avatar,
+        crate::routes::reset_subject_profile,
+        crate::routes::list_moderation_log,
+        crate::routes::list_deletion_log,
+        crate::routes::create_scheduled_thread,
+        crate::routes::list_scheduled_threads,
+        crate::routes::cancel_scheduled_thread,
+        crate::routes::list_board_bridges,
+        crate::routes::list_board_bridges_of_board,
+        crate::routes::list_board_testers,
+        crate::routes::add_board_tester,
+        crate::routes::remove_board_tester,
+        crate::routes::create_board_bridge,
+        crate::routes::update_board_bridge,
+        crate::routes::delete_board_bridge,
+        crate::routes::list_matrix_mirrors,
+        crate::routes::create_matrix_mirror,
+        crate::routes::update_matrix_mirror,
+        crate::routes::delete_matrix_mirror,
         crate::routes::upload_image,
+        crate::routes::upload_remote_image,
         crate::routes::set_subject_role,
         crate::routes::list_roles,
         crate::routes::delete_role,
+        crate::routes::role_history,
+        crate::routes::impersonate,
+        crate::routes::list_impersonations,
+        crate::routes::create_service_account,
+        crate::routes::list_service_accounts,
+        crate::routes::revoke_service_account,
+        crate::routes::post_announcement,
         crate::routes::get_thread_author,
         crate::routes::get_reply_author,
         crate::routes::create_subject_ban,
         crate::routes::list_subject_bans,
+        crate::routes::create_ip_ban,
+        crate::routes::list_ip_bans,
+        crate::routes::delete_ip_ban,
         crate::routes::delete_subject_ban,
+        crate::routes::list_held_posts,
+        crate::routes::approve_held_thread,
+        crate::routes::reject_held_thread,
+        crate::routes::approve_held_reply,
+        crate::routes::reject_held_reply,
+        crate::routes::list_quarantine,
+        crate::routes::quarantine_image,
+        crate::routes::approve_quarantined_image,
+        crate::routes::destroy_quarantined_image,
+        crate::routes::list_uploaded_images,
+        crate::routes::get_image_details,
+        crate::routes::get_archive_listing,
+        crate::routes::get_text_preview,
+        crate::routes::take_down_image,
+        crate::routes::list_legal_holds,
+        crate::routes::place_legal_hold,
+        crate::routes::release_legal_hold,
+        crate::routes::get_subject_trust,
+        crate::routes::list_appeals,
+        crate::routes::accept_appeal,
+        crate::routes::deny_appeal,
+        crate::routes::list_reports,
+        crate::routes::resolve_report,
+        crate::routes::dismiss_report,
+        crate::routes::admin_system,
+        crate::routes::admin_reload_config,
+        crate::routes::admin_migration_status,
+        crate::routes::admin_export,
+        crate::routes::admin_import,
+        crate::routes::admin_import_archive,
+        crate::api_v2::list_boards,
+        crate::api_v2::get_board,
+        crate::api_v2::create_board,
+        crate::api_v2::list_threads,
+        crate::api_v2::create_thread,
+        crate::api_v2::get_thread,
+        crate::api_v2::list_replies,
+        crate::api_v2::create_reply,
+        crate::api_v2::get_reply,
+        crate::api_v2::search,
     ),
     components(schemas(
-        Board, NewBoard, Thread, NewThread, Reply, NewReply,
-        Image, Report, SubjectBan, NewSubjectBan, crate::routes::FileUploadResponse,
+        Board, NewBoard, Thread, NewThread, Reply, NewReply, ThreadSort, ReplySort, SortOrder,
+        Image, ImageReference, Report, SubjectBan, NewSubjectBan, IpBan, NewIpBan, BanScope, BanNotice, crate::routes::FileUploadResponse, crate::routes::RemoteUpload,
+        Appeal, NewAppeal, AppealDecision, AppealKind, AppealStatus,
+        NewReport, ReportDecision, ReportKind, ReportStatus,
+        SubjectTrust, HeldPost, QuarantinedImage, NewQuarantine, UploadRecord, AttachedPost, ImageDetails, ArchiveListing, ArchiveEntry, TextExcerpt, LegalHold, NewLegalHold, ReleaseLegalHold, crate::trust::TrustReport,
         crate::routes::BitcoinChallengeRequest, crate::routes::BitcoinChallengeResponse,
         crate::routes::BitcoinVerifyRequest, crate::routes::BitcoinVerifyResponse,
+        crate::ethereum::EthereumChallengeRequest, crate::ethereum::EthereumChallengeResponse,
+        crate::ethereum::EthereumVerifyRequest, crate::ethereum::EthereumVerifyResponse,
+        crate::nostr::NostrChallengeResponse, crate::nostr::NostrVerifyRequest,
+        crate::nostr::NostrVerifyResponse, crate::nostr::NostrEvent,
+        crate::routes::EmailLoginStartRequest,
+        ThreadSubscription, NotificationSettings, UpdateNotificationSettings, DigestFrequency,
+        UserFilter, NewUserFilter, FilterKind, SavedSearch, NewSavedSearch, SavedSearchMatch, AuthorProfile, UpdateProfile,
+        ModerationEntry, ModerationActor, ModerationAction, DeletionReason, DeletionKind, DeletionEntry, PinReply, ReactionCount, NewReaction, TagCount,
+        ScheduledThread, NewScheduledThread,
+        BoardBridge, NewBoardBridge, UpdateBoardBridge, BridgeKind, BoardTester, NewBoardTester,
+        MatrixMirror, NewMatrixMirror, UpdateMatrixMirror,
         crate::routes::SetSubjectRoleRequest, crate::routes::RoleAssignment,
-        crate::routes::AuthorAttribution
+        crate::routes::RemoveRoleRequest, RoleChange,
+        crate::routes::ImpersonateRequest, crate::routes::ImpersonationToken, ImpersonationEntry,
+        ServiceScope, ServiceAccount, NewServiceAccount, CreatedServiceAccount, NewAnnouncement,
+        crate::routes::AuthorAttribution, SearchHit, crate::routes::SearchResults,
+        ThreadPreview, crate::routes::BatchRequest,
+        crate::routes::DeletePassword,
+        crate::pow::PowChallenge,
+        crate::pow::PowClearance,
+        crate::version::VersionInfo,
+        crate::readiness::ReadinessReport, crate::readiness::StepProgress, crate::readiness::StepStatus,
+        crate::transfer::ImportReport, crate::transfer::ImportCounts,
+        crate::transfer::ConflictStrategy, crate::transfer::ExportFormat,
+        crate::archive::ArchiveImportRequest, crate::archive::ArchiveMapping,
+        crate::archive::ArchiveImportReport, crate::archive::MediaSummary,
+        crate::db::MigrationStatus, crate::db::MigrationEntry,
+        crate::system::SystemReport, crate::system::PoolStats, crate::system::JobStatus,
+        crate::reload::ReloadReport,
+        crate::api_v2::BoardList, crate::api_v2::ThreadList, crate::api_v2::ReplyList,
+        crate::api_v2::SearchHitList, crate::api_v2::BoardData, crate::api_v2::ThreadData,
+        crate::api_v2::ReplyData, crate::api_v2::Pagination, crate::api_v2::Meta,
+        crate::api_v2::ErrorEnvelope, crate::api_v2::ErrorDetail
      )),
     tags(
         (name = "boards", description = "Board operations"),
         (name = "threads", description = "Thread operations"),
         (name = "replies", description = "Reply operations"),
+        (name = "v2", description = "Enveloped API: `{ data, pagination, meta }` and `{ error, meta }`"),
     ),
-    modifiers(&SecurityAddon)
+    modifiers(&SecurityAddon, &BuildInfo)
 )]
 pub struct ApiDoc;
 
+static OPENAPI_YAML: Lazy<Option<String>> = Lazy::new(|| match ApiDoc::openapi().to_yaml() {
+    Ok(yaml) => Some(yaml),
+    Err(e) => {
+        log::error!("OpenAPI document did not serialize to YAML: {e}");
+        None
+    }
+});
+
+/// The document served at `/docs/openapi.json`, as YAML.
+pub async fn openapi_yaml() -> HttpResponse {
+    match OPENAPI_YAML.as_deref() {
+        Some(yaml) => HttpResponse::Ok()
+            .content_type("application/yaml")
+            .body(yaml),
+        None => HttpResponse::InternalServerError().finish(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::ApiDoc;
@@ -75,4 +272,59 @@ mod tests {
             .get("bearer_auth")
             .is_some());
     }
+
+    #[actix_web::test]
+    async fn spec_carries_the_build_and_is_served_as_yaml() {
+        let document = serde_json::to_value(ApiDoc::openapi()).expect("serialize OpenAPI");
+        assert_eq!(
+            document["info"]["version"],
+            format!("{}+{}", crate::version::VERSION, crate::version::GIT_SHA)
+        );
+        assert!(document["paths"].get("/api/v1/version").is_some());
+
+        let resp = super::openapi_yaml().await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/yaml"
+        );
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let yaml = String::from_utf8(body.to_vec()).unwrap();
+        assert!(yaml.starts_with("openapi: 3"));
+        assert!(yaml.contains("/api/v1/version:"));
+    }
+
+    #[test]
+    fn operations_requiring_a_session_document_it() {
+        let document = serde_json::to_value(ApiDoc::openapi()).expect("serialize OpenAPI");
+        let mut secured = 0;
+        for (path, item) in document["paths"].as_object().unwrap() {
+            for (method, operation) in item.as_object().unwrap() {
+                let Some(requirements) = operation["security"].as_array() else {
+                    continue;
+                };
+                assert!(
+                    requirements.iter().any(|r| r.get("bearer_auth").is_some()),
+                    "{method} {path} names no bearer scheme"
+                );
+                // Operations that also allow anonymous callers list `{}`.
+                if requirements.iter().all(|r| r.get("bearer_auth").is_some()) {
+                    secured += 1;
+                    assert!(
+                        operation["responses"].get("401").is_some(),
+                        "{method} {path} requires a session but documents no 401"
+                    );
+                }
+            }
+        }
+        assert!(secured > 0);
+        let admin = &document["paths"]["/api/v1/admin/roles"]["get"];
+        assert!(admin["responses"].get("403").is_some());
+        let boards = &document["paths"]["/api/v1/boards"]["get"]["security"];
+        assert!(boards
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|r| r.as_object().unwrap().is_empty()));
+    }
 }
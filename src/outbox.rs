@@ -0,0 +1,249 @@
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use crate::models::OutboxEvent;
+use crate::repo::Repo;
+
+/// Event type names written to the outbox by repository mutations.
+pub mod events {
+    pub const THREAD_CREATED: &str = "thread.created";
+    pub const THREAD_DELETED: &str = "thread.deleted";
+    pub const THREAD_ARCHIVED: &str = "thread.archived";
+    pub const REPLY_CREATED: &str = "reply.created";
+    pub const REPLY_DELETED: &str = "reply.deleted";
+    /// For moderation tooling; live-update clients never see it.
+    pub const REPORT_CREATED: &str = "report.created";
+}
+
+/// Destination for outbox events (webhooks, live-update bus, search indexer).
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn deliver(&self, event: &OutboxEvent) -> anyhow::Result<()>;
+}
+
+/// POSTs each event as JSON to a fixed URL, optionally signed with HMAC-SHA256.
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+    secret: Option<String>,
+}
+
+impl WebhookSink {
+    pub fn new(url: String, secret: Option<String>) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(3))
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        Ok(Self {
+            client,
+            url,
+            secret,
+        })
+    }
+}
+
+fn webhook_signature(secret: &str, body: &[u8]) -> anyhow::Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+    mac.update(body);
+    Ok(format!(
+        "sha256={}",
+        hex::encode(mac.finalize().into_bytes())
+    ))
+}
+
+#[async_trait]
+impl EventSink for WebhookSink {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn deliver(&self, event: &OutboxEvent) -> anyhow::Result<()> {
+        let body = serde_json::to_vec(event)?;
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Rib-Event", event.event_type.as_str())
+            .header("X-Rib-Event-Id", event.id.to_string());
+        if let Some(secret) = &self.secret {
+            request = request.header("X-Rib-Signature", webhook_signature(secret, &body)?);
+        }
+        let response = request.body(body).send().await?;
+        if !response.status().is_success() {
+            anyhow::bail!("webhook responded with {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+/// Relay settings derived from env.
+#[derive(Clone, Debug)]
+pub struct OutboxConfig {
+    pub enabled: bool,
+    pub poll_interval: Duration,
+    pub batch_size: i64,
+    pub lease: Duration,
+    pub retention: Duration,
+    pub webhook_url: Option<String>,
+    pub webhook_secret: Option<String>,
+}
+
+impl OutboxConfig {
+    pub fn from_env() -> Self {
+        fn u64_env(name: &str, default: u64) -> u64 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        fn opt_env(name: &str) -> Option<String> {
+            std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+        }
+        Self {
+            enabled: std::env::var("OUTBOX_RELAY_ENABLED")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(true),
+            poll_interval: Duration::from_millis(u64_env("OUTBOX_POLL_INTERVAL_MS", 1000)),
+            batch_size: u64_env("OUTBOX_BATCH_SIZE", 50) as i64,
+            lease: Duration::from_secs(u64_env("OUTBOX_LEASE_SECS", 60)),
+            retention: Duration::from_secs(u64_env("OUTBOX_RETENTION_SECS", 7 * 24 * 3600)),
+            webhook_url: opt_env("OUTBOX_WEBHOOK_URL"),
+            webhook_secret: opt_env("OUTBOX_WEBHOOK_SECRET"),
+        }
+    }
+
+    /// Build the sinks this configuration enables.
+    pub fn sinks(&self) -> anyhow::Result<Vec<Arc<dyn EventSink>>> {
+        let mut sinks: Vec<Arc<dyn EventSink>> = Vec::new();
+        if let Some(url) = &self.webhook_url {
+            sinks.push(Arc::new(WebhookSink::new(
+                url.clone(),
+                self.webhook_secret.clone(),
+            )?));
+        }
+        Ok(sinks)
+    }
+}
+
+/// Exponential retry delay capped at one hour.
+fn retry_delay_secs(attempts: i32) -> i64 {
+    2_i64.saturating_pow(attempts.clamp(0, 12) as u32).min(3600)
+}
+
+/// Polls the outbox and fans events out to every configured sink.
+#[derive(Clone)]
+pub struct OutboxRelay {
+    repo: Arc<dyn Repo>,
+    sinks: Vec<Arc<dyn EventSink>>,
+    cfg: OutboxConfig,
+    wakeup: Option<Arc<Notify>>,
+}
+
+impl OutboxRelay {
+    pub fn new(repo: Arc<dyn Repo>, sinks: Vec<Arc<dyn EventSink>>, cfg: OutboxConfig) -> Self {
+        Self {
+            repo,
+            sinks,
+            cfg,
+            wakeup: None,
+        }
+    }
+
+    /// Poll as soon as `wakeup` fires instead of waiting out the interval.
+    /// The interval still applies as a fallback for retries and missed wakeups.
+    pub fn with_wakeup(mut self, wakeup: Arc<Notify>) -> Self {
+        self.wakeup = Some(wakeup);
+        self
+    }
+
+    /// Deliver one batch; returns the number of events marked delivered.
+    /// An event is delivered only once every sink accepted it.
+    pub async fn run_once(&self) -> usize {
+        let batch = match self
+            .repo
+            .claim_outbox_events(self.cfg.batch_size, self.cfg.lease.as_secs() as i64)
+            .await
+        {
+            Ok(batch) => batch,
+            Err(e) => {
+                log::error!("outbox claim failed: {e}");
+                return 0;
+            }
+        };
+        let mut delivered = 0;
+        for event in batch {
+            let mut failure = None;
+            for sink in &self.sinks {
+                if let Err(e) = sink.deliver(&event).await {
+                    metrics::increment_counter!("outbox_delivery_failed", "sink" => sink.name());
+                    failure = Some(format!("{}: {e}", sink.name()));
+                    break;
+                }
+            }
+            let outcome = match failure {
+                None => self
+                    .repo
+                    .mark_outbox_delivered(event.id)
+                    .await
+                    .map(|_| true),
+                Some(error) => {
+                    log::warn!(
+                        "outbox event {} ({}) delivery failed: {error}",
+                        event.id,
+                        event.event_type
+                    );
+                    self.repo
+                        .mark_outbox_failed(event.id, &error, retry_delay_secs(event.attempts))
+                        .await
+                        .map(|_| false)
+                }
+            };
+            match outcome {
+                Ok(true) => {
+                    delivered += 1;
+                    metrics::increment_counter!("outbox_delivered", "event" => event.event_type.clone());
+                }
+                Ok(false) => {}
+                Err(e) => log::error!("outbox event {} status update failed: {e}", event.id),
+            }
+        }
+        delivered
+    }
+
+    /// Spawn the polling loop on the current runtime.
+    pub fn spawn(self) {
+        crate::system::job_started("outbox_relay", self.cfg.poll_interval);
+        actix_web::rt::spawn(async move {
+            let mut ticks: u64 = 0;
+            loop {
+                let delivered = self.run_once().await;
+                crate::system::job_ran("outbox_relay");
+                ticks = ticks.wrapping_add(1);
+                if ticks.is_multiple_of(600) {
+                    if let Err(e) = self
+                        .repo
+                        .prune_delivered_outbox(self.cfg.retention.as_secs() as i64)
+                        .await
+                    {
+                        log::warn!("outbox prune failed: {e}");
+                    }
+                }
+                // Drain backlogs without waiting; idle relays poll at the configured interval.
+                if delivered < self.cfg.batch_size as usize {
+                    match &self.wakeup {
+                        Some(wakeup) => {
+                            let _ = tokio::time::timeout(self.cfg.poll_interval, wakeup.notified())
+                                .await;
+                        }
+                        None => tokio::time::sleep(self.cfg.poll_interval).await,
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,125 @@
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::InternalError;
+use actix_web::{Error, HttpMessage, HttpResponse};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use futures_util::FutureExt;
+use std::panic::AssertUnwindSafe;
+use std::rc::Rc;
+use tracing_actix_web::RequestId;
+
+use crate::error::{ApiError, ApiErrorBody};
+use crate::http_metrics::route_label;
+
+/// Middleware turning a panicking handler into the standard JSON 500 instead
+/// of dropping the connection. Register it innermost so outer middleware
+/// (metrics, logging) sees an ordinary response.
+#[derive(Clone, Default)]
+pub struct CatchPanic;
+
+impl<S, B> Transform<S, ServiceRequest> for CatchPanic
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = CatchPanicMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CatchPanicMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct CatchPanicMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for CatchPanicMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let route = route_label(&req);
+        let request_id = req
+            .extensions()
+            .get::<RequestId>()
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        // Panics inside `call` itself (before a future exists) are caught too.
+        let svc = self.service.clone();
+        let fut = AssertUnwindSafe(async move { svc.call(req).await }).catch_unwind();
+        Box::pin(async move {
+            match fut.await {
+                Ok(res) => res,
+                Err(_) => {
+                    metrics::increment_counter!("panics_total", "route" => route.clone());
+                    log::error!("handler panicked: request_id={request_id} route={route}");
+                    // Built directly rather than via `ApiError::error_response` so the
+                    // panic is not reported a second time as an internal error. The
+                    // request was consumed by the handler, so the response travels as
+                    // an error that the dispatcher renders.
+                    let response = HttpResponse::InternalServerError().json(ApiErrorBody {
+                        error: ApiError::Internal.to_string(),
+                        code: Some(ApiError::Internal.code()),
+                    });
+                    Err(InternalError::from_response("handler panicked", response).into())
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App};
+
+    #[actix_web::test]
+    async fn panics_become_json_500s() {
+        let app = test::init_service(
+            App::new()
+                .wrap(CatchPanic)
+                .route(
+                    "/boom",
+                    web::get().to(|| async {
+                        if true {
+                            panic!("handler bug");
+                        }
+                        HttpResponse::Ok().finish()
+                    }),
+                )
+                .route("/ok", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        let err = test::try_call_service(&app, test::TestRequest::get().uri("/boom").to_request())
+            .await
+            .expect_err("panic surfaces as an error response");
+        let resp = err.error_response();
+        assert_eq!(resp.status(), 500);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"], "internal error");
+
+        // The worker keeps serving after a caught panic.
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/ok").to_request()).await;
+        assert_eq!(resp.status(), 200);
+    }
+}
@@ -0,0 +1,284 @@
+//! Hashcash-style proof of work guarding anonymous posting.
+//!
+//! The server hands out signed, expiring challenges; the client finds a
+//! `nonce` such that `sha256("{challenge}:{nonce}")` starts with at least
+//! `difficulty` zero bits and sends `{challenge}:{nonce}` back with its post.
+//! Challenges are stateless until redeemed; redeemed ones are remembered in
+//! the challenge store until they expire so a solution cannot be replayed (on
+//! any replica, with a shared store).
+//! A redeemed solution can also buy a clearance cookie, signed and bound to
+//! the client IP, that the access policy accepts in place of a fresh solution.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::challenges::{ChallengeStore, MemoryChallengeStore};
+use crate::error::ApiError;
+
+/// Header (and gRPC metadata key) carrying a solved challenge.
+pub const POW_HEADER: &str = "x-proof-of-work";
+
+/// Cookie carrying a clearance bought with a solved challenge.
+pub const CLEARANCE_COOKIE: &str = "rib_clearance";
+
+#[derive(Debug, Clone)]
+pub struct PowConfig {
+    /// Leading zero bits required of the solution hash.
+    pub difficulty: u32,
+    /// How long an issued challenge stays redeemable.
+    pub ttl: Duration,
+    /// How long a clearance cookie is honoured.
+    pub clearance_ttl: Duration,
+}
+
+impl PowConfig {
+    pub fn from_env() -> Self {
+        fn u64_env(name: &str) -> Option<u64> {
+            std::env::var(name).ok().and_then(|v| v.parse().ok())
+        }
+        Self {
+            difficulty: u64_env("POW_DIFFICULTY").unwrap_or(20).min(32) as u32,
+            ttl: Duration::from_secs(u64_env("POW_TTL_SECS").unwrap_or(300).max(1)),
+            clearance_ttl: Duration::from_secs(
+                u64_env("POW_CLEARANCE_TTL_SECS").unwrap_or(3600).max(1),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct PowChallenge {
+    /// Opaque signed challenge string
+    pub challenge: String,
+    /// Leading zero bits required of `sha256("{challenge}:{nonce}")`
+    pub difficulty: u32,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct PowClearance {
+    /// Value of the clearance cookie, also set by the response
+    pub value: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+pub struct ProofOfWork {
+    cfg: PowConfig,
+    /// Remembers redeemed challenges until they expire.
+    redeemed: Arc<dyn ChallengeStore>,
+}
+
+fn secret() -> Result<String, ApiError> {
+    std::env::var("POW_SECRET")
+        .or_else(|_| std::env::var("JWT_SECRET"))
+        .map_err(|_| ApiError::Internal)
+}
+
+fn sign(domain: &[u8], payload: &str) -> Result<String, ApiError> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret()?.as_bytes()).map_err(|_| ApiError::Internal)?;
+    mac.update(domain);
+    mac.update(payload.as_bytes());
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+const POW_DOMAIN: &[u8] = b"rib-pow-v1\0";
+const CLEARANCE_DOMAIN: &[u8] = b"rib-clearance-v1\0";
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            return bits + byte.leading_zeros();
+        }
+    }
+    bits
+}
+
+fn invalid() -> ApiError {
+    ApiError::Invalid("invalid or expired proof of work".into())
+}
+
+/// Whether `nonce` solves `challenge` at `difficulty`.
+pub fn is_solution(challenge: &str, nonce: &str, difficulty: u32) -> bool {
+    let hash = Sha256::digest(format!("{challenge}:{nonce}").as_bytes());
+    leading_zero_bits(&hash) >= difficulty
+}
+
+impl ProofOfWork {
+    pub fn new(cfg: PowConfig) -> Self {
+        Self::with_store(cfg, MemoryChallengeStore::shared())
+    }
+
+    pub fn with_store(cfg: PowConfig, redeemed: Arc<dyn ChallengeStore>) -> Self {
+        Self { cfg, redeemed }
+    }
+
+    pub fn config(&self) -> &PowConfig {
+        &self.cfg
+    }
+
+    /// A fresh challenge: `{expires}.{difficulty}.{random}.{signature}`.
+    pub fn issue(&self) -> Result<PowChallenge, ApiError> {
+        let expires_at = Utc::now()
+            + chrono::Duration::from_std(self.cfg.ttl).map_err(|_| ApiError::Internal)?;
+        let mut random = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut random);
+        let payload = format!(
+            "{}.{}.{}",
+            expires_at.timestamp(),
+            self.cfg.difficulty,
+            hex::encode(random)
+        );
+        let signature = sign(POW_DOMAIN, &payload)?;
+        Ok(PowChallenge {
+            challenge: format!("{payload}.{signature}"),
+            difficulty: self.cfg.difficulty,
+            expires_at,
+        })
+    }
+
+    /// Check and consume a `{challenge}:{nonce}` token.
+    pub async fn redeem(&self, token: &str) -> Result<(), ApiError> {
+        let (challenge, expires) = self.verify(token)?;
+        let ttl = Duration::from_secs((expires - Utc::now().timestamp()).max(1) as u64);
+        let fresh = self
+            .redeemed
+            .insert_new("pow", challenge, "", ttl)
+            .await
+            .map_err(|e| {
+                log::error!("recording redeemed proof of work failed: {e}");
+                ApiError::Internal
+            })?;
+        if !fresh {
+            metrics::increment_counter!("pow_replayed");
+            return Err(invalid());
+        }
+        Ok(())
+    }
+
+    /// Check a `{challenge}:{nonce}` token without consuming it; returns the
+    /// challenge and its expiry (unix seconds).
+    pub fn verify<'a>(&self, token: &'a str) -> Result<(&'a str, i64), ApiError> {
+        let (challenge, nonce) = token.rsplit_once(':').ok_or_else(invalid)?;
+        let (payload, signature) = challenge.rsplit_once('.').ok_or_else(invalid)?;
+        let mut parts = payload.splitn(3, '.');
+        let expires: i64 = parts
+            .next()
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(invalid)?;
+        let difficulty: u32 = parts
+            .next()
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(invalid)?;
+        let now = Utc::now().timestamp();
+        if sign(POW_DOMAIN, payload)? != signature || expires <= now || nonce.len() > 64 {
+            return Err(invalid());
+        }
+        if !is_solution(challenge, nonce, difficulty) {
+            return Err(invalid());
+        }
+        Ok((challenge, expires))
+    }
+
+    /// Redeem a solved `{challenge}:{nonce}` token for a clearance
+    /// `{expires}.{signature}` bound to `client`.
+    pub async fn clearance(&self, token: &str, client: &str) -> Result<PowClearance, ApiError> {
+        self.redeem(token).await?;
+        let expires_at = Utc::now()
+            + chrono::Duration::from_std(self.cfg.clearance_ttl).map_err(|_| ApiError::Internal)?;
+        let expires = expires_at.timestamp();
+        let signature = sign(CLEARANCE_DOMAIN, &format!("{expires}.{client}"))?;
+        Ok(PowClearance {
+            value: format!("{expires}.{signature}"),
+            expires_at,
+        })
+    }
+
+    /// Whether `value` is an unexpired clearance issued to `client`.
+    pub fn is_cleared(&self, value: &str, client: &str) -> bool {
+        let Some((expires, signature)) = value.split_once('.') else {
+            return false;
+        };
+        expires
+            .parse::<i64>()
+            .is_ok_and(|expires| expires > Utc::now().timestamp())
+            && sign(CLEARANCE_DOMAIN, &format!("{expires}.{client}"))
+                .is_ok_and(|expected| expected == signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve(challenge: &str, difficulty: u32) -> String {
+        (0u64..)
+            .map(|n| n.to_string())
+            .find(|nonce| is_solution(challenge, nonce, difficulty))
+            .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn solutions_redeem_once() {
+        std::env::set_var("POW_SECRET", "pow-test-secret");
+        let pow = ProofOfWork::with_store(
+            PowConfig {
+                difficulty: 8,
+                ttl: Duration::from_secs(60),
+                clearance_ttl: Duration::from_secs(60),
+            },
+            Arc::new(MemoryChallengeStore::default()),
+        );
+        let issued = pow.issue().unwrap();
+        let nonce = solve(&issued.challenge, issued.difficulty);
+        let token = format!("{}:{nonce}", issued.challenge);
+        assert!(pow.redeem(&token).await.is_ok());
+        assert!(pow.redeem(&token).await.is_err());
+
+        let tampered = issued.challenge.replacen(".8.", ".0.", 1);
+        assert!(pow.redeem(&format!("{tampered}:0")).await.is_err());
+        assert!(pow.redeem("garbage").await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn clearances_are_bound_to_their_client() {
+        std::env::set_var("POW_SECRET", "pow-test-secret");
+        let pow = ProofOfWork::with_store(
+            PowConfig {
+                difficulty: 8,
+                ttl: Duration::from_secs(60),
+                clearance_ttl: Duration::from_secs(60),
+            },
+            Arc::new(MemoryChallengeStore::default()),
+        );
+        let issued = pow.issue().unwrap();
+        let token = format!(
+            "{}:{}",
+            issued.challenge,
+            solve(&issued.challenge, issued.difficulty)
+        );
+        let clearance = pow.clearance(&token, "10.0.0.1").await.unwrap();
+        assert!(pow.is_cleared(&clearance.value, "10.0.0.1"));
+        assert!(!pow.is_cleared(&clearance.value, "10.0.0.2"));
+        assert!(!pow.is_cleared("garbage", "10.0.0.1"));
+        // The solution was spent on the clearance.
+        assert!(pow.clearance(&token, "10.0.0.1").await.is_err());
+        let (_, signature) = clearance.value.split_once('.').unwrap();
+        let later = Utc::now().timestamp() + 7200;
+        assert!(!pow.is_cleared(&format!("{later}.{signature}"), "10.0.0.1"));
+    }
+
+    #[test]
+    fn counts_leading_zero_bits() {
+        assert_eq!(leading_zero_bits(&[0, 0x0f, 0xff]), 12);
+        assert_eq!(leading_zero_bits(&[0x80]), 0);
+        assert_eq!(leading_zero_bits(&[0, 0]), 16);
+    }
+}
@@ -0,0 +1,97 @@
+//! Per-subject preference blobs synced by the frontend.
+//!
+//! The blob is free-form JSON so the frontend can add settings without a
+//! server release, but it must be an object within the size and nesting
+//! limits below, and the keys the server knows about must have the right shape.
+
+use serde_json::Value;
+
+/// Serialized size limit of a stored blob.
+pub const MAX_PREFERENCES_BYTES: usize = 16 * 1024;
+const MAX_DEPTH: usize = 8;
+const MAX_HIDDEN_THREADS: usize = 1000;
+const MAX_FILTERS: usize = 200;
+const MAX_THEME_CHARS: usize = 32;
+
+fn depth(value: &Value) -> usize {
+    match value {
+        Value::Array(items) => 1 + items.iter().map(depth).max().unwrap_or(0),
+        Value::Object(map) => 1 + map.values().map(depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Check a blob before storing it; the error names the offending key.
+pub fn validate(prefs: &Value) -> Result<(), String> {
+    let Value::Object(map) = prefs else {
+        return Err("preferences must be a JSON object".into());
+    };
+    if serde_json::to_vec(prefs).map_or(usize::MAX, |bytes| bytes.len()) > MAX_PREFERENCES_BYTES {
+        return Err(format!("preferences exceed {MAX_PREFERENCES_BYTES} bytes"));
+    }
+    if depth(prefs) > MAX_DEPTH {
+        return Err(format!("preferences nest deeper than {MAX_DEPTH} levels"));
+    }
+    if let Some(theme) = map.get("theme") {
+        match theme.as_str() {
+            Some(theme) if !theme.is_empty() && theme.chars().count() <= MAX_THEME_CHARS => {}
+            _ => {
+                return Err(format!(
+                    "theme must be a non-empty string of at most {MAX_THEME_CHARS} characters"
+                ))
+            }
+        }
+    }
+    if let Some(hidden) = map.get("hidden_threads") {
+        let valid = hidden.as_array().is_some_and(|ids| {
+            ids.len() <= MAX_HIDDEN_THREADS
+                && ids.iter().all(|id| id.as_i64().is_some_and(|id| id > 0))
+        });
+        if !valid {
+            return Err(format!(
+                "hidden_threads must be an array of at most {MAX_HIDDEN_THREADS} thread ids"
+            ));
+        }
+    }
+    if let Some(filters) = map.get("filters") {
+        if filters
+            .as_array()
+            .is_none_or(|filters| filters.len() > MAX_FILTERS)
+        {
+            return Err(format!(
+                "filters must be an array of at most {MAX_FILTERS} entries"
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn accepts_known_and_unknown_keys() {
+        assert!(validate(&json!({})).is_ok());
+        assert!(validate(&json!({
+            "theme": "dark",
+            "hidden_threads": [1, 2, 3],
+            "filters": ["spoiler", {"tripcode": "!abc"}],
+            "experimental": {"compact": true}
+        }))
+        .is_ok());
+    }
+
+    #[test]
+    fn rejects_wrong_shapes_and_oversized_blobs() {
+        assert!(validate(&json!([])).is_err());
+        assert!(validate(&json!({"theme": 3})).is_err());
+        assert!(validate(&json!({"theme": ""})).is_err());
+        assert!(validate(&json!({"hidden_threads": [1, "2"]})).is_err());
+        assert!(validate(&json!({"hidden_threads": [0]})).is_err());
+        assert!(validate(&json!({"filters": "spoiler"})).is_err());
+        assert!(validate(&json!({"notes": "x".repeat(MAX_PREFERENCES_BYTES)})).is_err());
+        assert!(validate(&json!({"a": [[[[[[[[["deep"]]]]]]]]]})).is_err());
+    }
+}
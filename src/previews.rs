@@ -0,0 +1,124 @@
+//! First-page previews of document uploads.
+//!
+//! Rendering PDFs and office files needs pdfium or LibreOffice, which this
+//! binary does not link. A sidecar at `PREVIEW_RENDERER_URL` does it instead:
+//! it receives the document bytes with their `Content-Type` and answers with
+//! a PNG, JPEG or WebP image of the first page. The image is stored as a blob
+//! of its own and served as the document's thumbnail.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Image types a renderer may answer with.
+const THUMBNAIL_MIME: &[&str] = &["image/png", "image/jpeg", "image/webp"];
+
+/// Renders a preview image of a document; swapped for a double in tests.
+#[async_trait]
+pub trait PreviewRenderer: Send + Sync {
+    fn name(&self) -> &'static str;
+    /// An image of the document's first page.
+    async fn render(&self, bytes: &[u8], mime: &str) -> anyhow::Result<Vec<u8>>;
+}
+
+pub struct PreviewConfig {
+    /// e.g. `http://previews:3000/render`
+    pub renderer_url: Option<String>,
+    pub timeout: Duration,
+    /// Largest preview image accepted from the renderer.
+    pub max_bytes: usize,
+}
+
+impl PreviewConfig {
+    pub fn from_env() -> Self {
+        fn u64_env(name: &str, default: u64) -> u64 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        Self {
+            renderer_url: std::env::var("PREVIEW_RENDERER_URL")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
+            timeout: Duration::from_secs(u64_env("PREVIEW_TIMEOUT_SECS", 20).max(1)),
+            max_bytes: u64_env("PREVIEW_MAX_BYTES", 2 * 1024 * 1024).max(1) as usize,
+        }
+    }
+
+    /// Returns `None` when no renderer is configured (no document thumbnails).
+    pub fn build(&self) -> anyhow::Result<Option<Arc<dyn PreviewRenderer>>> {
+        let Some(url) = self.renderer_url.as_deref() else {
+            return Ok(None);
+        };
+        Ok(Some(Arc::new(HttpPreviewRenderer::new(
+            url,
+            self.timeout,
+            self.max_bytes,
+        )?)))
+    }
+}
+
+/// Whether uploads of `mime` get a rendered thumbnail.
+pub fn renders(mime: &str) -> bool {
+    crate::uploads::DOCUMENTS.contains(&mime)
+}
+
+/// The sniffed type of a rendered preview, if it is an image we serve.
+pub fn thumbnail_mime(bytes: &[u8]) -> Option<&'static str> {
+    let mime = infer::get(bytes)?.mime_type();
+    THUMBNAIL_MIME.iter().copied().find(|m| *m == mime)
+}
+
+pub struct HttpPreviewRenderer {
+    client: reqwest::Client,
+    url: reqwest::Url,
+    max_bytes: usize,
+}
+
+impl HttpPreviewRenderer {
+    pub fn new(url: &str, timeout: Duration, max_bytes: usize) -> anyhow::Result<Self> {
+        let url = reqwest::Url::parse(url)
+            .map_err(|e| anyhow::anyhow!("invalid PREVIEW_RENDERER_URL: {e}"))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            anyhow::bail!("PREVIEW_RENDERER_URL must be an http or https URL");
+        }
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .connect_timeout(Duration::from_secs(3))
+                .timeout(timeout)
+                .build()?,
+            url,
+            max_bytes,
+        })
+    }
+}
+
+#[async_trait]
+impl PreviewRenderer for HttpPreviewRenderer {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn render(&self, bytes: &[u8], mime: &str) -> anyhow::Result<Vec<u8>> {
+        let mut response = self
+            .client
+            .post(self.url.clone())
+            .header(reqwest::header::CONTENT_TYPE, mime)
+            .header(reqwest::header::ACCEPT, THUMBNAIL_MIME.join(", "))
+            .body(bytes.to_vec())
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            anyhow::bail!("preview renderer responded with {}", response.status());
+        }
+        let mut image = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if image.len() + chunk.len() > self.max_bytes {
+                anyhow::bail!("preview is larger than {} bytes", self.max_bytes);
+            }
+            image.extend_from_slice(&chunk);
+        }
+        Ok(image)
+    }
+}
@@ -0,0 +1,169 @@
+//! Display names and avatars for signed-in users.
+//!
+//! Both are chosen by the user, independent of the provider identity, and
+//! show up as the `author` object on their posts. Names are unique regardless
+//! of case; moderators can reset a profile that breaks the rules.
+//!
+//! Listings resolve the profiles of a whole page of posts with one lookup
+//! through [`author_subjects`] and [`fill_authors`] instead of one per row.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::models::{AuthorProfile, Reply, Thread};
+
+/// Largest accepted avatar upload.
+pub const AVATAR_SIZE_LIMIT: usize = 256 * 1024;
+/// Avatar formats browsers render inline; SVG is excluded because it can carry script.
+pub const AVATAR_MIME: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+const MIN_NAME_CHARS: usize = 3;
+const MAX_NAME_CHARS: usize = 32;
+/// Names that would pass for staff or system messages.
+const RESERVED_NAMES: &[&str] = &[
+    "admin",
+    "administrator",
+    "anonymous",
+    "mod",
+    "moderator",
+    "rib",
+    "staff",
+    "system",
+];
+
+/// Trim and check a requested display name.
+///
+/// Letters, digits, `_`, `-`, `.` and single inner spaces are allowed, which
+/// keeps look-alike and invisible characters out of names.
+pub fn normalize_display_name(name: &str) -> Result<String, String> {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let length = name.chars().count();
+    if !(MIN_NAME_CHARS..=MAX_NAME_CHARS).contains(&length) {
+        return Err(format!(
+            "display names must be {MIN_NAME_CHARS} to {MAX_NAME_CHARS} characters"
+        ));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ' '))
+    {
+        return Err("display names may only use letters, digits, spaces, _ - and .".into());
+    }
+    let lowered = name.to_ascii_lowercase();
+    if RESERVED_NAMES.iter().any(|reserved| {
+        lowered
+            .split([' ', '_', '-', '.'])
+            .any(|word| word == *reserved)
+    }) {
+        return Err("that display name is reserved".into());
+    }
+    Ok(name)
+}
+
+/// A post that shows its author's profile.
+pub trait Authored {
+    fn created_by(&self) -> &Value;
+    fn set_author(&mut self, author: Option<AuthorProfile>);
+}
+
+impl Authored for Thread {
+    fn created_by(&self) -> &Value {
+        &self.created_by
+    }
+    fn set_author(&mut self, author: Option<AuthorProfile>) {
+        self.author = author.map(sqlx::types::Json);
+    }
+}
+
+impl Authored for Reply {
+    fn created_by(&self) -> &Value {
+        &self.created_by
+    }
+    fn set_author(&mut self, author: Option<AuthorProfile>) {
+        self.author = author.map(sqlx::types::Json);
+    }
+}
+
+/// Distinct signed-in authors of `posts`, in first-seen order.
+pub fn author_subjects<T: Authored>(posts: &[T]) -> Vec<String> {
+    let mut subjects: Vec<String> = Vec::new();
+    for post in posts {
+        if let Some(subject) = crate::filters::author_subject(post.created_by()) {
+            if !subjects.iter().any(|seen| seen == subject) {
+                subjects.push(subject.to_string());
+            }
+        }
+    }
+    subjects
+}
+
+/// Set each post's `author` from `profiles`, keyed by subject; posts whose
+/// author has no profile get none.
+pub fn fill_authors<T: Authored>(posts: &mut [T], profiles: &HashMap<String, AuthorProfile>) {
+    for post in posts {
+        let author = crate::filters::author_subject(post.created_by())
+            .and_then(|subject| profiles.get(subject))
+            .cloned();
+        post.set_author(author);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_trimmed_and_checked() {
+        assert_eq!(
+            normalize_display_name("  Night  Owl ").unwrap(),
+            "Night Owl"
+        );
+        assert_eq!(normalize_display_name("n.o_w-l").unwrap(), "n.o_w-l");
+        assert!(normalize_display_name("ab").is_err());
+        assert!(normalize_display_name(&"x".repeat(33)).is_err());
+        assert!(normalize_display_name("n\u{200b}ight").is_err());
+        assert!(normalize_display_name("Ñandú").is_err());
+        assert!(normalize_display_name("Admin").is_err());
+        assert!(normalize_display_name("the_moderator").is_err());
+        assert!(normalize_display_name("modest").is_ok());
+    }
+
+    #[test]
+    fn authors_are_resolved_once_per_page() {
+        let reply = |id, created_by: Value| Reply {
+            id,
+            thread_id: 1,
+            content: String::new(),
+            image_hash: None,
+            mime: None,
+            image_size: None,
+            author_name: None,
+            tripcode: None,
+            created_at: chrono::Utc::now(),
+            deleted_at: None,
+            created_by,
+            author: None,
+            reactions: sqlx::types::Json(Vec::new()),
+            removal_reason: None,
+        };
+        let mut page = vec![
+            reply(1, serde_json::json!({ "subject": "discord:1" })),
+            reply(2, serde_json::json!({ "ip_hash": "x" })),
+            reply(3, serde_json::json!({ "subject": "discord:2" })),
+            reply(4, serde_json::json!({ "subject": "discord:1" })),
+        ];
+        assert_eq!(author_subjects(&page), ["discord:1", "discord:2"]);
+        let profile = AuthorProfile {
+            display_name: Some("Night Owl".into()),
+            avatar_hash: None,
+        };
+        let profiles = HashMap::from([("discord:1".to_string(), profile.clone())]);
+        fill_authors(&mut page, &profiles);
+        let authors: Vec<_> = page
+            .iter()
+            .map(|r| r.author.as_ref().map(|a| &a.0))
+            .collect();
+        assert_eq!(authors, [Some(&profile), None, None, Some(&profile)]);
+    }
+}
@@ -4,24 +4,66 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use crate::reload::Reloadable;
+
+/// Counts hits per key; implementations differ in how a window is measured.
+pub trait RateLimiter: Send + Sync {
+    /// Returns true if allowed, false if limited.
+    fn check(&self, key: &str, limit: usize, window: Duration) -> bool;
+}
+
+/// Per-key state shared by the limiters, pruned every 256 checks.
+struct Keyed<S> {
+    store: DashMap<String, S>,
+    checks: AtomicUsize,
+}
+
+impl<S> Keyed<S> {
+    fn new() -> Self {
+        Self {
+            store: DashMap::new(),
+            checks: AtomicUsize::new(0),
+        }
+    }
+
+    /// Run `hit` on the key's state; `live` tells which states are still
+    /// worth keeping.
+    fn check(
+        &self,
+        key: &str,
+        live: impl Fn(&S) -> bool,
+        init: impl FnOnce() -> S,
+        hit: impl FnOnce(&mut S) -> bool,
+    ) -> bool {
+        if self
+            .checks
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(256)
+        {
+            self.store.retain(|_, entry| live(entry));
+        }
+        let mut entry = self.store.entry(key.to_string()).or_insert_with(init);
+        hit(&mut entry)
+    }
+}
+
 struct RateWindow {
     hits: VecDeque<Instant>,
     window: Duration,
 }
 
-/// Sliding window in-memory rate limiter (pod local).
+/// Sliding window in-memory rate limiter (pod local). Exact, but keeps every
+/// hit inside the window.
 #[derive(Clone)]
 pub struct InMemoryRateLimiter {
-    store: Arc<DashMap<String, RateWindow>>,
-    checks: Arc<AtomicUsize>,
+    keys: Arc<Keyed<RateWindow>>,
     pub enabled: bool,
 }
 
 impl InMemoryRateLimiter {
     pub fn new(enabled: bool) -> Self {
         Self {
-            store: Arc::new(DashMap::new()),
-            checks: Arc::new(AtomicUsize::new(0)),
+            keys: Arc::new(Keyed::new()),
             enabled,
         }
     }
@@ -32,38 +74,203 @@ impl InMemoryRateLimiter {
             return true;
         }
         let now = Instant::now();
-        if self
-            .checks
-            .fetch_add(1, Ordering::Relaxed)
-            .is_multiple_of(256)
-        {
-            self.store.retain(|_, entry| {
+        self.keys.check(
+            key,
+            |entry| {
                 entry
                     .hits
                     .back()
                     .is_some_and(|last| now.duration_since(*last) < entry.window)
-            });
-        }
-        let mut entry = self
-            .store
-            .entry(key.to_string())
-            .or_insert_with(|| RateWindow {
+            },
+            || RateWindow {
                 hits: VecDeque::new(),
                 window,
-            });
-        entry.window = window;
-        while let Some(front) = entry.hits.front() {
-            if now.duration_since(*front) >= window {
-                entry.hits.pop_front();
-            } else {
-                break;
-            }
+            },
+            |entry| {
+                entry.window = window;
+                while let Some(front) = entry.hits.front() {
+                    if now.duration_since(*front) >= window {
+                        entry.hits.pop_front();
+                    } else {
+                        break;
+                    }
+                }
+                if entry.hits.len() < limit {
+                    entry.hits.push_back(now);
+                    true
+                } else {
+                    false
+                }
+            },
+        )
+    }
+}
+
+impl RateLimiter for InMemoryRateLimiter {
+    fn check(&self, key: &str, limit: usize, window: Duration) -> bool {
+        InMemoryRateLimiter::check(self, key, limit, window)
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// When the bucket is full again and the key can be forgotten.
+    full_at: Instant,
+}
+
+/// Token bucket limiter (pod local). Each key refills at `limit` tokens per
+/// window and holds up to `limit × burst` of them, so idle keys may post a
+/// burst; the state per key is constant.
+#[derive(Clone)]
+pub struct TokenBucketRateLimiter {
+    keys: Arc<Keyed<Bucket>>,
+    burst: f64,
+}
+
+impl TokenBucketRateLimiter {
+    /// `burst` scales the bucket size relative to the limit; values below one
+    /// are raised to one.
+    pub fn new(burst: f64) -> Self {
+        Self {
+            keys: Arc::new(Keyed::new()),
+            burst: burst.max(1.0),
         }
-        if entry.hits.len() < limit {
-            entry.hits.push_back(now);
-            true
-        } else {
-            false
+    }
+}
+
+impl RateLimiter for TokenBucketRateLimiter {
+    fn check(&self, key: &str, limit: usize, window: Duration) -> bool {
+        if limit == 0 {
+            return false;
+        }
+        if window.is_zero() {
+            return true;
+        }
+        let now = Instant::now();
+        let capacity = (limit as f64 * self.burst).floor().max(1.0);
+        let per_sec = limit as f64 / window.as_secs_f64();
+        self.keys.check(
+            key,
+            |bucket| bucket.full_at > now,
+            || Bucket {
+                tokens: capacity,
+                updated: now,
+                full_at: now,
+            },
+            |bucket| {
+                let refilled = now.duration_since(bucket.updated).as_secs_f64() * per_sec;
+                bucket.tokens = (bucket.tokens + refilled).min(capacity);
+                bucket.updated = now;
+                let allowed = bucket.tokens >= 1.0;
+                if allowed {
+                    bucket.tokens -= 1.0;
+                }
+                bucket.full_at =
+                    now + Duration::from_secs_f64((capacity - bucket.tokens) / per_sec);
+                allowed
+            },
+        )
+    }
+}
+
+struct FixedWindow {
+    started: Instant,
+    hits: usize,
+    window: Duration,
+}
+
+/// Fixed window limiter (pod local). Counts hits in consecutive windows
+/// starting at a key's first hit; cheap, but allows up to twice the limit
+/// across a window boundary.
+#[derive(Clone)]
+pub struct FixedWindowRateLimiter {
+    keys: Arc<Keyed<FixedWindow>>,
+}
+
+impl FixedWindowRateLimiter {
+    pub fn new() -> Self {
+        Self {
+            keys: Arc::new(Keyed::new()),
+        }
+    }
+}
+
+impl Default for FixedWindowRateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateLimiter for FixedWindowRateLimiter {
+    fn check(&self, key: &str, limit: usize, window: Duration) -> bool {
+        let now = Instant::now();
+        self.keys.check(
+            key,
+            |entry| now.duration_since(entry.started) < entry.window,
+            || FixedWindow {
+                started: now,
+                hits: 0,
+                window,
+            },
+            |entry| {
+                entry.window = window;
+                if now.duration_since(entry.started) >= window {
+                    entry.started = now;
+                    entry.hits = 0;
+                }
+                if entry.hits < limit {
+                    entry.hits += 1;
+                    true
+                } else {
+                    false
+                }
+            },
+        )
+    }
+}
+
+/// Limiter implementation chosen with `RL_ALGORITHM`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RateLimitAlgorithm {
+    SlidingWindow,
+    /// Bucket size as a multiple of the limit (`RL_BURST_FACTOR`).
+    TokenBucket {
+        burst: f64,
+    },
+    FixedWindow,
+}
+
+impl RateLimitAlgorithm {
+    pub fn parse(value: &str, burst: f64) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "sliding-window" => Some(Self::SlidingWindow),
+            "token-bucket" => Some(Self::TokenBucket { burst }),
+            "fixed-window" => Some(Self::FixedWindow),
+            _ => None,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let burst = std::env::var("RL_BURST_FACTOR")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|v: &f64| v.is_finite())
+            .unwrap_or(1.0);
+        match std::env::var("RL_ALGORITHM") {
+            Ok(value) => Self::parse(&value, burst).unwrap_or_else(|| {
+                log::warn!("unknown RL_ALGORITHM {value:?}; using sliding-window");
+                Self::SlidingWindow
+            }),
+            Err(_) => Self::SlidingWindow,
+        }
+    }
+
+    pub fn limiter(self) -> Arc<dyn RateLimiter> {
+        match self {
+            Self::SlidingWindow => Arc::new(InMemoryRateLimiter::new(true)),
+            Self::TokenBucket { burst } => Arc::new(TokenBucketRateLimiter::new(burst)),
+            Self::FixedWindow => Arc::new(FixedWindowRateLimiter::new()),
         }
     }
 }
@@ -77,6 +284,14 @@ pub struct RateLimitConfig {
     pub reply_window: Duration,
     pub image_limit: usize,
     pub image_window: Duration,
+    /// Stricter limits for posts without an account, keyed by client IP.
+    pub anon_thread_limit: usize,
+    pub anon_thread_window: Duration,
+    pub anon_reply_limit: usize,
+    pub anon_reply_window: Duration,
+    /// Magic-link emails per recipient.
+    pub email_login_limit: usize,
+    pub email_login_window: Duration,
 }
 
 impl RateLimitConfig {
@@ -102,42 +317,96 @@ impl RateLimitConfig {
             reply_window: dur_env("RL_REPLY_WINDOW", 60),
             image_limit: usize_env("RL_IMAGE_LIMIT", 5),
             image_window: dur_env("RL_IMAGE_WINDOW", 3600),
+            anon_thread_limit: usize_env("RL_ANON_THREAD_LIMIT", 1),
+            anon_thread_window: dur_env("RL_ANON_THREAD_WINDOW", 900),
+            anon_reply_limit: usize_env("RL_ANON_REPLY_LIMIT", 3),
+            anon_reply_window: dur_env("RL_ANON_REPLY_WINDOW", 120),
+            email_login_limit: usize_env("RL_EMAIL_LOGIN_LIMIT", 3),
+            email_login_window: dur_env("RL_EMAIL_LOGIN_WINDOW", 900),
         }
     }
 }
 
+/// `limit` scaled by a poster's trust factor; never below one.
+fn scaled(limit: usize, factor: f64) -> usize {
+    ((limit as f64 * factor).round() as usize).max(1)
+}
+
 /// High level guard used by handlers.
 #[derive(Clone)]
 pub struct RateLimiterFacade {
-    pub limiter: InMemoryRateLimiter,
-    pub cfg: RateLimitConfig,
+    pub limiter: Arc<dyn RateLimiter>,
+    pub cfg: Reloadable<RateLimitConfig>,
 }
 
 impl RateLimiterFacade {
-    pub fn new(limiter: InMemoryRateLimiter, cfg: RateLimitConfig) -> Self {
-        Self { limiter, cfg }
+    pub fn new(
+        limiter: impl RateLimiter + 'static,
+        cfg: impl Into<Reloadable<RateLimitConfig>>,
+    ) -> Self {
+        Self {
+            limiter: Arc::new(limiter),
+            cfg: cfg.into(),
+        }
     }
-    pub fn allow_thread(&self, ip: &str) -> bool {
+    pub fn with_algorithm(
+        algorithm: RateLimitAlgorithm,
+        cfg: impl Into<Reloadable<RateLimitConfig>>,
+    ) -> Self {
+        Self {
+            limiter: algorithm.limiter(),
+            cfg: cfg.into(),
+        }
+    }
+    /// Limits in force now; they change when configuration is reloaded.
+    pub fn config(&self) -> Arc<RateLimitConfig> {
+        self.cfg.current()
+    }
+    pub fn allow_thread(&self, ip: &str, factor: f64) -> bool {
+        let cfg = self.config();
         self.limiter.check(
             &format!("thread:{ip}"),
-            self.cfg.thread_limit,
-            self.cfg.thread_window,
+            scaled(cfg.thread_limit, factor),
+            cfg.thread_window,
         )
     }
-    pub fn allow_reply(&self, ip: &str) -> bool {
+    pub fn allow_reply(&self, ip: &str, factor: f64) -> bool {
+        let cfg = self.config();
         self.limiter.check(
             &format!("reply:{ip}"),
-            self.cfg.reply_limit,
-            self.cfg.reply_window,
+            scaled(cfg.reply_limit, factor),
+            cfg.reply_window,
         )
     }
-    pub fn allow_image(&self, ip: &str) -> bool {
+    pub fn allow_anon_thread(&self, ip: &str, factor: f64) -> bool {
+        let cfg = self.config();
         self.limiter.check(
-            &format!("image:{ip}"),
-            self.cfg.image_limit,
-            self.cfg.image_window,
+            &format!("anon-thread:{ip}"),
+            scaled(cfg.anon_thread_limit, factor),
+            cfg.anon_thread_window,
         )
     }
+    pub fn allow_anon_reply(&self, ip: &str, factor: f64) -> bool {
+        let cfg = self.config();
+        self.limiter.check(
+            &format!("anon-reply:{ip}"),
+            scaled(cfg.anon_reply_limit, factor),
+            cfg.anon_reply_window,
+        )
+    }
+    pub fn allow_email_login(&self, subject: &str) -> bool {
+        let cfg = self.config();
+        self.limiter.check(
+            &format!("email-login:{subject}"),
+            cfg.email_login_limit,
+            cfg.email_login_window,
+        )
+    }
+    pub fn allow_image(&self, ip: &str) -> bool {
+        let cfg = self.config();
+        self.limiter
+            .check(&format!("image:{ip}"), cfg.image_limit, cfg.image_window)
+    }
 }
 
 #[cfg(test)]
@@ -160,6 +429,63 @@ mod tests {
         for index in 0..300 {
             assert!(rl.check(&format!("key-{index}"), 1, expired));
         }
-        assert!(rl.store.len() < 256);
+        assert!(rl.keys.store.len() < 256);
+    }
+
+    #[test]
+    fn token_bucket_allows_bursts_and_refills() {
+        let rl = TokenBucketRateLimiter::new(2.0);
+        let window = Duration::from_millis(400);
+        for _ in 0..4 {
+            assert!(rl.check("k", 2, window));
+        }
+        assert!(!rl.check("k", 2, window));
+        // One token comes back every 200ms.
+        std::thread::sleep(Duration::from_millis(250));
+        assert!(rl.check("k", 2, window));
+        assert!(!rl.check("k", 2, window));
+        assert!(rl.check("other", 2, window));
+        assert!(!rl.check("k", 0, window));
+    }
+
+    #[test]
+    fn fixed_window_resets_after_the_window() {
+        let rl = FixedWindowRateLimiter::new();
+        let window = Duration::from_millis(50);
+        for _ in 0..3 {
+            assert!(rl.check("k", 3, window));
+        }
+        assert!(!rl.check("k", 3, window));
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(rl.check("k", 3, window));
+        for index in 0..300 {
+            assert!(rl.check(&format!("key-{index}"), 1, Duration::ZERO));
+        }
+        assert!(rl.keys.store.len() < 256);
+    }
+
+    #[test]
+    fn algorithms_parse_from_their_names() {
+        assert_eq!(
+            RateLimitAlgorithm::parse("sliding-window", 1.0),
+            Some(RateLimitAlgorithm::SlidingWindow)
+        );
+        assert_eq!(
+            RateLimitAlgorithm::parse(" Token_Bucket ", 3.0),
+            Some(RateLimitAlgorithm::TokenBucket { burst: 3.0 })
+        );
+        assert_eq!(
+            RateLimitAlgorithm::parse("fixed-window", 1.0),
+            Some(RateLimitAlgorithm::FixedWindow)
+        );
+        assert_eq!(RateLimitAlgorithm::parse("leaky", 1.0), None);
+    }
+
+    #[test]
+    fn post_limits_scale_with_trust() {
+        assert_eq!(scaled(10, 1.0), 10);
+        assert_eq!(scaled(10, 0.25), 3);
+        assert_eq!(scaled(1, 0.1), 1);
+        assert_eq!(scaled(3, 2.0), 6);
     }
 }
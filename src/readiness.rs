@@ -0,0 +1,207 @@
+//! Startup warmup tracked for `GET /readyz`.
+//!
+//! Each warmup step is registered up front and marked ready once it has
+//! finished: the Prometheus recorder is installed, migrations are applied or
+//! verified, the image store bucket exists, and the board list is loaded into
+//! the cache. `/readyz` answers `503` with the progress of every step until all
+//! of them are ready, then `200`, so a rolling deploy only sends traffic to a
+//! pod that has warmed up. `/healthz` stays a bare liveness check.
+//!
+//! Steps that run before the listener binds are already ready by the time
+//! `/readyz` can be asked; the cache is primed in the background while the
+//! server starts.
+
+use actix_web::{web, HttpResponse};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::cache::BoardCache;
+use crate::repo::Repo;
+
+/// How long cache priming waits for the change listener before loading anyway.
+const LISTENER_WAIT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    Pending,
+    Ready,
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct StepProgress {
+    pub name: String,
+    pub status: StepStatus,
+    /// Milliseconds from startup until the step finished
+    pub ready_after_ms: Option<u64>,
+    /// Why the last attempt failed, while the step is retried
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub steps: Vec<StepProgress>,
+}
+
+/// Shared warmup progress; clones see the same steps.
+#[derive(Clone)]
+pub struct Readiness {
+    started: Instant,
+    steps: Arc<Mutex<Vec<StepProgress>>>,
+}
+
+impl Default for Readiness {
+    /// No steps, so ready from the start.
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl Readiness {
+    pub fn new(steps: &[&str]) -> Self {
+        Self {
+            started: Instant::now(),
+            steps: Arc::new(Mutex::new(
+                steps
+                    .iter()
+                    .map(|name| StepProgress {
+                        name: name.to_string(),
+                        status: StepStatus::Pending,
+                        ready_after_ms: None,
+                        last_error: None,
+                    })
+                    .collect(),
+            )),
+        }
+    }
+
+    pub fn mark_ready(&self, step: &str) {
+        let elapsed = self.started.elapsed().as_millis() as u64;
+        if let Some(progress) = self
+            .steps
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|s| s.name == step)
+        {
+            progress.status = StepStatus::Ready;
+            progress.ready_after_ms = Some(elapsed);
+            progress.last_error = None;
+            log::info!("warmup step {step} ready after {elapsed}ms");
+        }
+    }
+
+    /// Note a failed attempt; the step stays pending.
+    pub fn record_error(&self, step: &str, error: impl ToString) {
+        if let Some(progress) = self
+            .steps
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|s| s.name == step)
+        {
+            progress.last_error = Some(error.to_string());
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.steps
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|s| s.status == StepStatus::Ready)
+    }
+
+    pub fn report(&self) -> ReadinessReport {
+        let steps = self.steps.lock().unwrap().clone();
+        ReadinessReport {
+            ready: steps.iter().all(|s| s.status == StepStatus::Ready),
+            steps,
+        }
+    }
+
+    /// Load the public board list into `cache` and mark `step` ready, retrying
+    /// until the database answers. With notifications enabled the cache only
+    /// keeps entries once the change listener is connected, so wait for it a
+    /// little first.
+    pub fn spawn_cache_primer(
+        &self,
+        step: &'static str,
+        repo: Arc<dyn Repo>,
+        cache: BoardCache,
+        wait_for_listener: bool,
+    ) -> tokio::task::JoinHandle<()> {
+        let readiness = self.clone();
+        actix_web::rt::spawn(async move {
+            if wait_for_listener {
+                let deadline = Instant::now() + LISTENER_WAIT;
+                while !cache.is_active() && Instant::now() < deadline {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            }
+            let mut backoff = Duration::from_millis(250);
+            loop {
+                let generation = cache.generation();
+                match repo.list_boards_from_primary().await {
+                    Ok(boards) => {
+                        cache.store(generation, boards);
+                        readiness.mark_ready(step);
+                        return;
+                    }
+                    Err(e) => {
+                        log::warn!("priming board cache failed: {e}; retrying in {backoff:?}");
+                        readiness.record_error(step, e);
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(Duration::from_secs(10));
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    responses(
+        (status = 200, description = "Warmup finished; ready for traffic", body = ReadinessReport),
+        (status = 503, description = "Still warming up; steps show progress", body = ReadinessReport)
+    )
+)]
+pub async fn readyz(data: web::Data<crate::routes::AppState>) -> HttpResponse {
+    let report = data.readiness.report();
+    let mut response = if report.ready {
+        HttpResponse::Ok()
+    } else {
+        HttpResponse::ServiceUnavailable()
+    };
+    response
+        .insert_header((actix_web::http::header::CACHE_CONTROL, "no-store"))
+        .json(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ready_once_every_step_is() {
+        let readiness = Readiness::new(&["migrations", "caches"]);
+        assert!(!readiness.is_ready());
+        readiness.mark_ready("migrations");
+        readiness.record_error("caches", "database unavailable");
+        let report = readiness.report();
+        assert!(!report.ready);
+        assert_eq!(report.steps[0].status, StepStatus::Ready);
+        assert_eq!(
+            report.steps[1].last_error.as_deref(),
+            Some("database unavailable")
+        );
+        readiness.mark_ready("caches");
+        assert!(readiness.is_ready());
+        assert!(readiness.report().steps[1].last_error.is_none());
+        assert!(Readiness::default().is_ready());
+    }
+}
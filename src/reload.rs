@@ -0,0 +1,210 @@
+//! Live reload of non-structural configuration.
+//!
+//! Rate limits, throttle caps, CORS origins, the upload policy (accepted
+//! types, quota, per-role limits) and disabled features can change without
+//! a restart: on `SIGHUP`
+//! or `POST /api/v1/admin/config/reload`, [`ConfigReloader`] reads the env
+//! file named by `CONFIG_ENV_FILE`, if any, over the process environment and
+//! rebuilds each setting from it. Listeners, pools, storage and on/off
+//! switches such as `RL_ENABLED` keep their startup values; requests already
+//! running finish under the settings they started with.
+
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tokio::task::JoinHandle;
+
+use crate::features::FeatureFlags;
+use crate::rate_limit::RateLimitConfig;
+use crate::throttle::ThrottleConfig;
+use crate::uploads::UploadConfig;
+
+/// A setting that may be swapped while the server runs. Clones share the
+/// value, so a handle kept by the reloader updates every holder.
+pub struct Reloadable<T>(Arc<RwLock<Arc<T>>>);
+
+impl<T> Reloadable<T> {
+    pub fn new(value: T) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(value))))
+    }
+
+    pub fn current(&self) -> Arc<T> {
+        self.0.read().unwrap().clone()
+    }
+
+    pub fn replace(&self, value: T) {
+        *self.0.write().unwrap() = Arc::new(value);
+    }
+}
+
+impl<T> Clone for Reloadable<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> From<T> for Reloadable<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Origins allowed cross-origin requests beyond the local development ones:
+/// `FRONTEND_URL` plus the comma-separated `CORS_ALLOWED_ORIGINS`.
+#[derive(Clone, Debug, Default)]
+pub struct CorsOrigins(Vec<String>);
+
+impl CorsOrigins {
+    pub fn from_env() -> Self {
+        let frontend = std::env::var("FRONTEND_URL").ok();
+        let extra = std::env::var("CORS_ALLOWED_ORIGINS").unwrap_or_default();
+        Self(
+            frontend
+                .into_iter()
+                .chain(extra.split(',').map(str::to_string))
+                .map(|origin| origin.trim().trim_end_matches('/').to_string())
+                .filter(|origin| !origin.is_empty())
+                .collect(),
+        )
+    }
+
+    pub fn allows(&self, origin: &str) -> bool {
+        self.0.iter().any(|allowed| allowed == origin)
+    }
+}
+
+/// What a reload did.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ReloadReport {
+    /// Env file read before rebuilding, when `CONFIG_ENV_FILE` is set
+    pub env_file: Option<String>,
+    /// Settings rebuilt: `rate_limits`, `throttle`, `cors_origins`, `uploads`, `features`
+    pub reloaded: Vec<String>,
+}
+
+/// Handles to every reloadable setting. Build it once at startup and pass
+/// the handles to the components that read them.
+#[derive(Clone)]
+pub struct ConfigReloader {
+    env_file: Option<PathBuf>,
+    pub rate_limits: Reloadable<RateLimitConfig>,
+    pub throttle: Reloadable<ThrottleConfig>,
+    pub cors_origins: Reloadable<CorsOrigins>,
+    pub uploads: Reloadable<UploadConfig>,
+    pub features: Reloadable<FeatureFlags>,
+}
+
+impl ConfigReloader {
+    pub fn from_env() -> Self {
+        Self {
+            env_file: std::env::var("CONFIG_ENV_FILE")
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .map(PathBuf::from),
+            rate_limits: RateLimitConfig::from_env().into(),
+            throttle: ThrottleConfig::from_env().into(),
+            cors_origins: CorsOrigins::from_env().into(),
+            uploads: UploadConfig::from_env().into(),
+            features: FeatureFlags::from_env().into(),
+        }
+    }
+
+    /// Re-read the env file and rebuild every setting. An unreadable file is
+    /// an error and leaves all settings as they were.
+    pub fn reload(&self) -> Result<ReloadReport, String> {
+        if let Some(path) = &self.env_file {
+            dotenvy::from_path_override(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+        self.rate_limits.replace(RateLimitConfig::from_env());
+        self.throttle.replace(ThrottleConfig::from_env());
+        self.cors_origins.replace(CorsOrigins::from_env());
+        self.uploads.replace(UploadConfig::from_env());
+        self.features.replace(FeatureFlags::from_env());
+        Ok(ReloadReport {
+            env_file: self.env_file.as_ref().map(|p| p.display().to_string()),
+            reloaded: [
+                "rate_limits",
+                "throttle",
+                "cors_origins",
+                "uploads",
+                "features",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+        })
+    }
+
+    /// [`Self::reload`] with the outcome counted and logged.
+    pub fn reload_logged(&self, trigger: &'static str) -> Result<ReloadReport, String> {
+        let result = self.reload();
+        match &result {
+            Ok(_) => {
+                metrics::increment_counter!("config_reloads", "trigger" => trigger, "result" => "ok");
+                log::info!("configuration reloaded ({trigger})");
+            }
+            Err(e) => {
+                metrics::increment_counter!("config_reloads", "trigger" => trigger, "result" => "error");
+                log::error!(
+                    "configuration not reloaded ({trigger}), keeping the previous one: {e}"
+                );
+            }
+        }
+        result
+    }
+
+    /// Reload on every `SIGHUP` until the task is aborted; nothing off Unix.
+    pub fn spawn_signal_handler(&self) -> Option<JoinHandle<()>> {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            let mut hangups = match signal(SignalKind::hangup()) {
+                Ok(hangups) => hangups,
+                Err(e) => {
+                    log::warn!("SIGHUP reload unavailable: {e}");
+                    return None;
+                }
+            };
+            let reloader = self.clone();
+            Some(actix_web::rt::spawn(async move {
+                while hangups.recv().await.is_some() {
+                    let _ = reloader.reload_logged("signal");
+                }
+            }))
+        }
+        #[cfg(not(unix))]
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reload_reads_the_env_file_into_every_handle() {
+        let path = std::env::temp_dir().join(format!("rib-reload-{}.env", std::process::id()));
+        std::fs::write(
+            &path,
+            "RL_REPLY_LIMIT=42\nCORS_ALLOWED_ORIGINS=https://a.example/\n",
+        )
+        .unwrap();
+        std::env::set_var("CONFIG_ENV_FILE", &path);
+        let reloader = ConfigReloader::from_env();
+        let rate_limits = reloader.rate_limits.clone();
+        let cors = reloader.cors_origins.clone();
+        assert!(!cors.current().allows("https://a.example"));
+
+        let report = reloader.reload().unwrap();
+        assert_eq!(report.reloaded.len(), 5);
+        assert_eq!(rate_limits.current().reply_limit, 42);
+        assert!(cors.current().allows("https://a.example"));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(reloader.reload().is_err());
+        assert_eq!(rate_limits.current().reply_limit, 42);
+
+        std::env::remove_var("CONFIG_ENV_FILE");
+        std::env::remove_var("RL_REPLY_LIMIT");
+        std::env::remove_var("CORS_ALLOWED_ORIGINS");
+    }
+}
@@ -1,5 +1,8 @@
 use crate::auth::Role as AuthRole;
+use crate::db::AppliedMigration;
 use crate::models::*;
+use crate::sitemap::{SitemapBoard, SitemapThread};
+use crate::transfer::{ConflictStrategy, Dump, ImportOptions, ImportReport};
 use serde_json::Value;
 
 #[derive(thiserror::Error, Debug)]
@@ -8,26 +11,106 @@ pub enum RepoError {
     NotFound,
     #[error("conflict")]
     Conflict,
+    /// The change would leave no active admin assignment.
+    #[error("last admin")]
+    LastAdmin,
+    /// Transient: pool exhausted, connection lost, or the server shutting down.
+    #[error("database unavailable: {0}")]
+    Unavailable(String),
+    /// Rolled back by a serialization failure or deadlock. Nothing was
+    /// written, so the whole transaction may run again.
+    #[error("transaction aborted: {0}")]
+    Aborted(String),
+    /// A foreign key, check, or not-null constraint rejected the write.
+    #[error("constraint violated: {0}")]
+    Constraint(String),
+    #[error("database error: {0}")]
+    Other(sqlx::Error),
+}
+
+impl From<sqlx::Error> for RepoError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::RowNotFound => RepoError::NotFound,
+            sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::WorkerCrashed => RepoError::Unavailable(e.to_string()),
+            sqlx::Error::Database(ref db) => {
+                // SQLSTATE classes: 23 integrity, 40 transaction rollback, 57 operator intervention.
+                match db.code().as_deref() {
+                    // 23001 is raised by the legal hold trigger, not by foreign keys.
+                    Some("23505") | Some("23P01") | Some("23001") => RepoError::Conflict,
+                    Some(code) if code.starts_with("23") => {
+                        RepoError::Constraint(db.constraint().unwrap_or(code).to_string())
+                    }
+                    Some("40001") | Some("40P01") => RepoError::Aborted(db.message().to_string()),
+                    Some("57P01") | Some("57014") => {
+                        RepoError::Unavailable(db.message().to_string())
+                    }
+                    _ => RepoError::Other(e),
+                }
+            }
+            other => RepoError::Other(other),
+        }
+    }
 }
 
 pub type RepoResult<T> = Result<T, RepoError>;
+/// Rows delivered one at a time; dropping the stream cancels the query.
+pub type RowStream<T> = futures_util::stream::BoxStream<'static, RepoResult<T>>;
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
 
 #[async_trait]
 pub trait BoardRepo: Send + Sync {
     async fn list_boards(&self, include_deleted: bool) -> RepoResult<Vec<Board>>;
+    /// Boards that are not soft-deleted, read from the primary even when a
+    /// replica is configured, for caches reloaded right after a change.
+    async fn list_boards_from_primary(&self) -> RepoResult<Vec<Board>>;
     async fn create_board(&self, new: NewBoard) -> RepoResult<Board>;
     async fn update_board(&self, id: Id, upd: UpdateBoard) -> RepoResult<Board>;
     async fn soft_delete_board(&self, id: Id) -> RepoResult<()>;
     async fn restore_board(&self, id: Id) -> RepoResult<()>;
     async fn hard_delete_board(&self, id: Id) -> RepoResult<()>;
     async fn get_board(&self, id: Id) -> RepoResult<Board>;
+    /// Boards with the given ids, including soft-deleted ones; unknown ids are skipped.
+    async fn get_boards(&self, ids: &[Id]) -> RepoResult<Vec<Board>>;
+    /// Adds `subject` to a beta board's testers; adding a tester twice keeps the first entry.
+    async fn add_board_tester(
+        &self,
+        board_id: Id,
+        subject: &str,
+        added_by: &str,
+    ) -> RepoResult<BoardTester>;
+    /// Testers of a board, oldest first.
+    async fn list_board_testers(&self, board_id: Id) -> RepoResult<Vec<BoardTester>>;
+    async fn remove_board_tester(&self, board_id: Id, subject: &str) -> RepoResult<()>;
+    /// Ids of the boards `subject` tests.
+    async fn tester_board_ids(&self, subject: &str) -> RepoResult<Vec<Id>>;
+    /// The board that last gave up `slug` in a rename, if any.
+    async fn board_for_old_slug(&self, slug: &str) -> RepoResult<Option<Id>>;
 }
 
 #[async_trait]
 pub trait ThreadRepo: Send + Sync {
-    async fn list_threads(&self, board_id: Id, include_deleted: bool) -> RepoResult<Vec<Thread>>;
+    /// Active threads of a board in `order`.
+    async fn list_threads(
+        &self,
+        board_id: Id,
+        include_deleted: bool,
+        order: ThreadOrder,
+    ) -> RepoResult<Vec<Thread>>;
+    /// Same rows as [`ThreadRepo::list_threads`] without holding them all in memory.
+    fn stream_threads(
+        &self,
+        board_id: Id,
+        include_deleted: bool,
+        order: ThreadOrder,
+    ) -> RowStream<Thread>;
     async fn create_thread(
         &self,
         new: NewThread,
@@ -35,32 +118,107 @@ pub trait ThreadRepo: Send + Sync {
         public_identity: PublicIdentity,
     ) -> RepoResult<Thread>;
     async fn get_thread(&self, id: Id) -> RepoResult<Thread>;
+    /// Threads that fell off the board's page limit, most recently archived first.
+    async fn list_archived_threads(&self, board_id: Id, limit: i64) -> RepoResult<Vec<Thread>>;
+    /// Active threads of a board carrying `tag`, in `order`.
+    async fn list_tagged_threads(
+        &self,
+        board_id: Id,
+        tag: &str,
+        include_deleted: bool,
+        order: ThreadOrder,
+    ) -> RepoResult<Vec<Thread>>;
+    /// Tags on a board's active threads with how many carry each, most used first.
+    async fn tag_counts(&self, board_id: Id) -> RepoResult<Vec<TagCount>>;
+    /// Threads with the given ids, including soft-deleted ones; unknown ids are skipped.
+    async fn get_threads(&self, ids: &[Id]) -> RepoResult<Vec<Thread>>;
     async fn soft_delete_thread(&self, id: Id) -> RepoResult<()>;
     async fn restore_thread(&self, id: Id) -> RepoResult<()>;
     async fn hard_delete_thread(&self, id: Id) -> RepoResult<()>;
+    /// Stored deletion password hash; `None` when the poster did not set one.
+    async fn thread_delete_password(&self, id: Id) -> RepoResult<Option<String>>;
+    /// Whether the thread carries or once carried `slug`.
+    async fn thread_had_slug(&self, id: Id, slug: &str) -> RepoResult<bool>;
 }
 
 #[async_trait]
 pub trait ReplyRepo: Send + Sync {
-    async fn list_replies(&self, thread_id: Id, include_deleted: bool) -> RepoResult<Vec<Reply>>;
+    /// Replies of a thread by creation time in `order`.
+    async fn list_replies(
+        &self,
+        thread_id: Id,
+        include_deleted: bool,
+        order: SortOrder,
+    ) -> RepoResult<Vec<Reply>>;
+    /// Same rows as [`ReplyRepo::list_replies`] without holding them all in memory.
+    fn stream_replies(
+        &self,
+        thread_id: Id,
+        include_deleted: bool,
+        order: SortOrder,
+    ) -> RowStream<Reply>;
+    /// Replies of several threads in one query, ordered by thread then creation time.
+    async fn list_replies_for_threads(
+        &self,
+        thread_ids: &[Id],
+        include_deleted: bool,
+    ) -> RepoResult<Vec<Reply>>;
+    /// The oldest `per_thread` visible replies of each thread, in one query.
+    async fn list_first_replies(
+        &self,
+        thread_ids: &[Id],
+        per_thread: i64,
+    ) -> RepoResult<Vec<Reply>>;
     async fn create_reply(
         &self,
         new: NewReply,
         created_by: Value,
         public_identity: PublicIdentity,
     ) -> RepoResult<Reply>;
+    /// When `subject` last replied in the thread, deleted replies included.
+    async fn last_reply_at(
+        &self,
+        thread_id: Id,
+        subject: &str,
+    ) -> RepoResult<Option<DateTime<Utc>>>;
     async fn soft_delete_reply(&self, id: Id) -> RepoResult<()>;
     async fn restore_reply(&self, id: Id) -> RepoResult<()>;
     async fn hard_delete_reply(&self, id: Id) -> RepoResult<()>;
     async fn get_reply(&self, id: Id) -> RepoResult<Reply>;
+    /// Stored deletion password hash; `None` when the poster did not set one.
+    async fn reply_delete_password(&self, id: Id) -> RepoResult<Option<String>>;
 }
 
 #[async_trait]
 pub trait RoleRepo: Send + Sync {
     async fn get_subject_role(&self, subject: &str) -> Option<AuthRole>;
+    /// Assign a lasting role, clearing any expiry. `LastAdmin` if it would
+    /// demote the only admin.
     async fn set_subject_role(&self, subject: &str, role: AuthRole) -> RepoResult<()>;
-    async fn list_roles(&self) -> RepoResult<Vec<(String, AuthRole)>>;
+    /// Assign a role that lapses back to `user` at `expires_at`. `LastAdmin`
+    /// if it would put an expiry on the only admin.
+    async fn set_subject_role_until(
+        &self,
+        subject: &str,
+        role: AuthRole,
+        expires_at: DateTime<Utc>,
+    ) -> RepoResult<()>;
+    /// Assignments by subject, with the time temporary ones lapse.
+    async fn list_roles(&self) -> RepoResult<Vec<(String, AuthRole, Option<DateTime<Utc>>)>>;
+    /// `LastAdmin` if `subject` is the only admin.
     async fn delete_role(&self, subject: &str) -> RepoResult<()>;
+    /// Demote lapsed assignments to `user`, recording each in the role history.
+    async fn demote_expired_roles(&self) -> RepoResult<Vec<RoleChange>>;
+    async fn record_role_change(&self, change: NewRoleChange) -> RepoResult<RoleChange>;
+    /// A subject's role changes, newest first.
+    async fn role_history(&self, subject: &str, limit: i64) -> RepoResult<Vec<RoleChange>>;
+    async fn record_impersonation(&self, entry: NewImpersonationEntry) -> RepoResult<()>;
+    /// Newest first, optionally for one subject acted as.
+    async fn list_impersonations(
+        &self,
+        subject: Option<&str>,
+        limit: i64,
+    ) -> RepoResult<Vec<ImpersonationEntry>>;
 }
 
 #[async_trait]
@@ -68,11 +226,55 @@ pub trait ImageRepo: Send + Sync {
     async fn list_board_image_hashes(&self, board_id: Id) -> RepoResult<Vec<String>>;
     async fn list_thread_image_hashes(&self, thread_id: Id) -> RepoResult<Vec<String>>;
     async fn is_image_referenced(&self, hash: &str) -> RepoResult<bool>;
+    /// Visible posts attaching a blob, newest first.
+    async fn image_references(&self, hash: &str, limit: i64) -> RepoResult<Vec<ImageReference>>;
+    /// Note that `uploaded_by` uploaded a blob; repeats by the same subject are
+    /// ignored, except that they can mark it shareable.
+    async fn record_upload(&self, upload: &NewUpload) -> RepoResult<()>;
+    async fn upload_usage(&self, subject: &str, since: DateTime<Utc>) -> RepoResult<UploadUsage>;
+    /// Uploads of a blob, oldest first.
+    async fn image_uploads(&self, hash: &str) -> RepoResult<Vec<UploadRecord>>;
+    /// Posts attaching a blob, deleted ones included, newest first.
+    async fn image_attachments(&self, hash: &str) -> RepoResult<Vec<AttachedPost>>;
+    /// A subject's uploads, newest first.
+    async fn list_uploads_by(&self, uploader: &str, limit: i64) -> RepoResult<Vec<UploadRecord>>;
+    /// Soft-delete the visible posts attaching a blob and detach it from all
+    /// posts; returns how many posts were hidden. The caller deletes the bytes.
+    /// `Conflict` while the blob is under legal hold.
+    async fn take_down_image(&self, hash: &str) -> RepoResult<u64>;
+    /// Hold a blob back for review; a blob already quarantined keeps its first reason.
+    async fn quarantine_image(&self, hash: &str, reason: &str, source: &str) -> RepoResult<()>;
+    async fn get_quarantined_image(&self, hash: &str) -> RepoResult<Option<QuarantinedImage>>;
+    /// Blobs awaiting review, oldest first.
+    async fn list_quarantined_images(&self, limit: i64) -> RepoResult<Vec<QuarantinedImage>>;
+    /// Serve a quarantined blob normally again.
+    async fn release_quarantined_image(&self, hash: &str) -> RepoResult<()>;
+    /// Detach a quarantined blob from every post and mark it destroyed; the
+    /// caller deletes the bytes. `Conflict` while the blob is under legal hold.
+    async fn destroy_quarantined_image(&self, hash: &str) -> RepoResult<()>;
+    /// Blobs not yet archived whose every visible attachment is in an
+    /// archived thread.
+    async fn blobs_to_archive(&self, limit: i64) -> RepoResult<Vec<String>>;
+    /// Archived blobs attached again to a visible post in a live thread.
+    async fn blobs_to_restore(&self, limit: i64) -> RepoResult<Vec<String>>;
+    /// Record that a blob was moved to, or back from, the archive tier.
+    async fn set_blob_archived(&self, hash: &str, archived: bool) -> RepoResult<()>;
+    /// Record the rendered preview of a blob, replacing an earlier one.
+    async fn set_image_thumbnail(&self, hash: &str, thumbnail_hash: &str) -> RepoResult<()>;
+    /// The blob holding a blob's rendered preview.
+    async fn image_thumbnail(&self, hash: &str) -> RepoResult<Option<String>>;
+    /// Store the file listing of an inspected archive.
+    async fn set_archive_listing(&self, hash: &str, listing: &ArchiveListing) -> RepoResult<()>;
+    async fn archive_listing(&self, hash: &str) -> RepoResult<Option<ArchiveListing>>;
+    /// Store the excerpt of a text upload; the first one stored is kept.
+    async fn set_text_excerpt(&self, hash: &str, excerpt: &TextExcerpt) -> RepoResult<()>;
+    async fn text_excerpt(&self, hash: &str) -> RepoResult<Option<TextExcerpt>>;
 }
 
 #[async_trait]
 pub trait BanRepo: Send + Sync {
-    async fn is_subject_banned(&self, subject: &str) -> RepoResult<bool>;
+    /// The subject's active ban, if any.
+    async fn active_subject_ban(&self, subject: &str) -> RepoResult<Option<BanNotice>>;
     async fn create_subject_ban(
         &self,
         new: NewSubjectBan,
@@ -80,577 +282,5278 @@ pub trait BanRepo: Send + Sync {
     ) -> RepoResult<SubjectBan>;
     async fn list_subject_bans(&self) -> RepoResult<Vec<SubjectBan>>;
     async fn delete_subject_ban(&self, subject: &str) -> RepoResult<()>;
+    /// The longest-lasting active ban covering `ip`, if any.
+    async fn active_ip_ban(&self, ip: &str) -> RepoResult<Option<BanNotice>>;
+    /// `network` must already be valid CIDR or address notation.
+    async fn create_ip_ban(&self, new: NewIpBan, banned_by: &str) -> RepoResult<IpBan>;
+    /// Active IP bans, newest first.
+    async fn list_ip_bans(&self) -> RepoResult<Vec<IpBan>>;
+    async fn delete_ip_ban(&self, id: Id) -> RepoResult<()>;
+}
+
+#[async_trait]
+pub trait OutboxRepo: Send + Sync {
+    /// Lease up to `limit` due events for delivery; leased rows are hidden from
+    /// other relays for `lease_secs` unless marked delivered or failed first.
+    async fn claim_outbox_events(
+        &self,
+        limit: i64,
+        lease_secs: i64,
+    ) -> RepoResult<Vec<OutboxEvent>>;
+    async fn mark_outbox_delivered(&self, id: Id) -> RepoResult<()>;
+    async fn mark_outbox_failed(&self, id: Id, error: &str, retry_in_secs: i64) -> RepoResult<()>;
+    async fn prune_delivered_outbox(&self, older_than_secs: i64) -> RepoResult<u64>;
+}
+
+#[async_trait]
+pub trait SearchRepo: Send + Sync {
+    /// Full-text search over visible threads and replies (Postgres FTS),
+    /// leaving out posts on `excluded_boards`.
+    async fn search_posts(
+        &self,
+        query: &str,
+        board_id: Option<Id>,
+        excluded_boards: &[Id],
+        limit: i64,
+    ) -> RepoResult<Vec<SearchHit>>;
+}
+
+#[async_trait]
+pub trait TransferRepo: Send + Sync {
+    /// Image rows ordered by id, for keyset-paginated export.
+    async fn list_images_after(&self, after_id: Id, limit: i64) -> RepoResult<Vec<Image>>;
+    /// Load a validated dump in one transaction. Source ids are remapped to
+    /// fresh ones; the transaction is rolled back on a dry run or when
+    /// conflicts are found under [`ConflictStrategy::Fail`].
+    async fn import_dump(&self, dump: &Dump, options: ImportOptions) -> RepoResult<ImportReport>;
+}
+
+#[async_trait]
+pub trait SchemaRepo: Send + Sync {
+    /// Rows of sqlx's migration table, ordered by version; empty on a fresh database.
+    async fn applied_migrations(&self) -> RepoResult<Vec<AppliedMigration>>;
+}
+
+#[async_trait]
+pub trait SitemapRepo: Send + Sync {
+    /// Visible boards by id, with the latest bump time of their visible threads.
+    async fn sitemap_boards(&self) -> RepoResult<Vec<SitemapBoard>>;
+    /// Visible threads bumped since `since`.
+    async fn count_sitemap_threads(&self, since: DateTime<Utc>) -> RepoResult<i64>;
+    /// One page of visible threads bumped since `since`, ordered by id so
+    /// pages stay stable as threads are bumped.
+    async fn list_sitemap_threads(
+        &self,
+        since: DateTime<Utc>,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<SitemapThread>>;
+}
+
+#[async_trait]
+pub trait NotificationRepo: Send + Sync {
+    /// Watch a thread; only replies posted after this call are notified.
+    async fn subscribe_thread(&self, subject: &str, thread_id: Id) -> RepoResult<()>;
+    async fn unsubscribe_thread(&self, subject: &str, thread_id: Id) -> RepoResult<()>;
+    async fn list_thread_subscriptions(&self, subject: &str)
+        -> RepoResult<Vec<ThreadSubscription>>;
+    async fn get_notification_settings(
+        &self,
+        subject: &str,
+    ) -> RepoResult<Option<NotificationSettings>>;
+    /// Upsert settings; changing the address clears its verification.
+    async fn put_notification_settings(
+        &self,
+        subject: &str,
+        update: UpdateNotificationSettings,
+    ) -> RepoResult<NotificationSettings>;
+    /// Mark `email` verified if it is still the subject's address.
+    async fn verify_notification_email(&self, subject: &str, email: &str) -> RepoResult<bool>;
+    async fn disable_digests(&self, subject: &str) -> RepoResult<()>;
+    /// Verified subscribers on `digest` with unseen replies whose last digest
+    /// went out at least `min_interval_secs` ago.
+    async fn due_digest_recipients(
+        &self,
+        digest: DigestFrequency,
+        min_interval_secs: i64,
+        limit: i64,
+    ) -> RepoResult<Vec<DigestRecipient>>;
+    /// Unseen visible replies by others in the subject's watched threads.
+    async fn digest_entries(&self, subject: &str, limit: i64) -> RepoResult<Vec<DigestEntry>>;
+    /// Unnotified saved search matches of the subject that are still visible.
+    async fn search_digest_entries(
+        &self,
+        subject: &str,
+        limit: i64,
+    ) -> RepoResult<Vec<SearchDigestEntry>>;
+    /// Advance each `(thread_id, reply_id)` watermark, mark the listed saved
+    /// search matches notified and stamp the digest time.
+    async fn mark_digest_sent(
+        &self,
+        subject: &str,
+        watermarks: &[(Id, Id)],
+        match_ids: &[Id],
+    ) -> RepoResult<()>;
+}
+
+#[async_trait]
+pub trait PreferenceRepo: Send + Sync {
+    /// The subject's stored preference blob, if any.
+    async fn get_preferences(&self, subject: &str) -> RepoResult<Option<Value>>;
+    /// Replace the subject's blob; callers validate it first.
+    async fn put_preferences(&self, subject: &str, preferences: Value) -> RepoResult<()>;
+}
+
+#[async_trait]
+pub trait FilterRepo: Send + Sync {
+    /// The subject's mute list, oldest first.
+    async fn list_filters(&self, subject: &str) -> RepoResult<Vec<UserFilter>>;
+    /// Add a normalized entry; `Conflict` if the same one already exists.
+    async fn create_filter(
+        &self,
+        subject: &str,
+        new: NewUserFilter,
+        target_subject: Option<String>,
+    ) -> RepoResult<UserFilter>;
+    async fn delete_filter(&self, subject: &str, id: Id) -> RepoResult<()>;
+}
+
+#[async_trait]
+pub trait ProfileRepo: Send + Sync {
+    async fn get_profile(&self, subject: &str) -> RepoResult<Option<AuthorProfile>>;
+    /// `Conflict` if another subject holds the name in any letter case.
+    async fn set_display_name(
+        &self,
+        subject: &str,
+        display_name: Option<&str>,
+    ) -> RepoResult<AuthorProfile>;
+    /// Point the avatar at a stored `(hash, mime)`, or clear it.
+    async fn set_avatar(
+        &self,
+        subject: &str,
+        avatar: Option<(&str, &str)>,
+    ) -> RepoResult<AuthorProfile>;
+    /// Clear both display name and avatar; `NotFound` if neither was set.
+    async fn reset_profile(&self, subject: &str) -> RepoResult<()>;
+}
+
+#[async_trait]
+pub trait ModerationRepo: Send + Sync {
+    /// Newest first, optionally for one thread.
+    async fn list_moderation_log(
+        &self,
+        thread_id: Option<Id>,
+        limit: i64,
+    ) -> RepoResult<Vec<ModerationEntry>>;
+    /// Log a removal and its reason; entries outlive what they removed.
+    async fn record_deletion(&self, entry: NewDeletionEntry) -> RepoResult<()>;
+    /// Newest first, optionally for one kind of post.
+    async fn list_deletion_log(
+        &self,
+        kind: Option<DeletionKind>,
+        limit: i64,
+    ) -> RepoResult<Vec<DeletionEntry>>;
+}
+
+#[async_trait]
+pub trait ScheduleRepo: Send + Sync {
+    async fn create_scheduled_thread(
+        &self,
+        new: NewScheduledThread,
+        created_by: Value,
+    ) -> RepoResult<ScheduledThread>;
+    /// Schedules that have not finished, soonest first.
+    async fn list_scheduled_threads(&self) -> RepoResult<Vec<ScheduledThread>>;
+    /// Remove a pending schedule; threads it already posted stay.
+    async fn cancel_scheduled_thread(&self, id: Id) -> RepoResult<()>;
+    /// Post up to `limit` due schedules, each in its own transaction, and
+    /// advance repeating ones. Returns the threads created.
+    async fn publish_due_threads(&self, limit: i64) -> RepoResult<Vec<Thread>>;
+}
+
+#[async_trait]
+pub trait ReactionRepo: Send + Sync {
+    /// Set `subject`'s reaction on a reply, replacing any earlier one, and
+    /// return the reply's counts. A subject holds one reaction per reply.
+    async fn set_reaction(
+        &self,
+        reply_id: Id,
+        subject: &str,
+        emoji: &str,
+    ) -> RepoResult<Vec<ReactionCount>>;
+    /// Remove `subject`'s reaction, if any, and return the reply's counts.
+    async fn clear_reaction(&self, reply_id: Id, subject: &str) -> RepoResult<Vec<ReactionCount>>;
+}
+
+#[async_trait]
+pub trait SavedSearchRepo: Send + Sync {
+    /// The subject's saved searches, oldest first.
+    async fn list_saved_searches(&self, subject: &str) -> RepoResult<Vec<SavedSearch>>;
+    /// Save a query; only posts made from now on will match it.
+    async fn create_saved_search(
+        &self,
+        subject: &str,
+        new: NewSavedSearch,
+    ) -> RepoResult<SavedSearch>;
+    async fn delete_saved_search(&self, subject: &str, id: Id) -> RepoResult<()>;
+    /// Newest matches of one of the subject's searches; `NotFound` if it is not theirs.
+    async fn list_saved_search_matches(
+        &self,
+        subject: &str,
+        id: Id,
+        limit: i64,
+    ) -> RepoResult<Vec<SavedSearchMatch>>;
+    /// Evaluate up to `limit` searches against posts made since their last
+    /// pass, each in its own transaction. Posts younger than `settle_secs`
+    /// wait for the next pass. Returns the matches recorded.
+    async fn match_saved_searches(&self, limit: i64, settle_secs: i64) -> RepoResult<u64>;
+}
+
+#[async_trait]
+pub trait AppealRepo: Send + Sync {
+    /// File an appeal against the subject's active ban or the deletion of
+    /// one of their posts. `NotFound` when there is no such action, and
+    /// `Conflict` when it was already appealed.
+    async fn create_appeal(&self, subject: &str, new: NewAppeal) -> RepoResult<Appeal>;
+    /// Appeals in `status`, oldest first.
+    async fn list_appeals(&self, status: AppealStatus, limit: i64) -> RepoResult<Vec<Appeal>>;
+    /// The subject's own appeals, newest first.
+    async fn list_subject_appeals(&self, subject: &str) -> RepoResult<Vec<Appeal>>;
+    /// Accept or deny a pending appeal; accepting lifts the ban or restores
+    /// the post in the same transaction. `Conflict` if already decided.
+    async fn decide_appeal(
+        &self,
+        id: Id,
+        decided_by: &str,
+        status: AppealStatus,
+        note: Option<String>,
+    ) -> RepoResult<Appeal>;
+}
+
+#[async_trait]
+pub trait ReportRepo: Send + Sync {
+    /// Flag a visible thread or reply. `NotFound` when the post does not
+    /// exist or is deleted or held, and `Conflict` when the reporter already
+    /// has an open report on it.
+    async fn create_report(&self, reporter: &str, new: NewReport) -> RepoResult<Report>;
+    /// Reports in `status`, oldest first.
+    async fn list_reports(&self, status: ReportStatus, limit: i64) -> RepoResult<Vec<Report>>;
+    /// Resolve or dismiss an open report, closing the other open reports on
+    /// the same post with it. `Conflict` if already decided.
+    async fn decide_report(
+        &self,
+        id: Id,
+        decided_by: &str,
+        status: ReportStatus,
+        note: Option<String>,
+    ) -> RepoResult<Report>;
+}
+
+#[async_trait]
+pub trait TrustRepo: Send + Sync {
+    /// `None` for subjects that never posted or were banned.
+    async fn subject_trust(&self, subject: &str) -> RepoResult<Option<SubjectTrust>>;
+    /// Held threads and replies, oldest first.
+    async fn list_held_posts(&self, limit: i64) -> RepoResult<Vec<HeldPost>>;
+    /// Publish a held thread, or reject it so it stays deleted.
+    /// `NotFound` unless the thread is held.
+    async fn release_held_thread(&self, id: Id, approve: bool) -> RepoResult<()>;
+    async fn release_held_reply(&self, id: Id, approve: bool) -> RepoResult<()>;
+}
+
+#[async_trait]
+pub trait HoldRepo: Send + Sync {
+    /// Place a legal hold. `NotFound` when the thread or reply does not
+    /// exist, `Conflict` when the target is already held.
+    async fn place_legal_hold(&self, new: NewLegalHold, placed_by: &str) -> RepoResult<LegalHold>;
+    /// `NotFound` unless the hold is active.
+    async fn release_legal_hold(
+        &self,
+        id: Id,
+        released_by: &str,
+        reason: &str,
+    ) -> RepoResult<LegalHold>;
+    /// Holds, newest first; released ones too when `include_released`.
+    async fn list_legal_holds(
+        &self,
+        include_released: bool,
+        limit: i64,
+    ) -> RepoResult<Vec<LegalHold>>;
+    /// The active hold on a blob, if any.
+    async fn image_legal_hold(&self, hash: &str) -> RepoResult<Option<LegalHold>>;
+}
+
+#[async_trait]
+pub trait BridgeRepo: Send + Sync {
+    /// Bridge `board_id`; only threads posted from now on are announced.
+    /// `Conflict` when the board already bridges to this target.
+    async fn create_board_bridge(
+        &self,
+        board_id: Id,
+        new: NewBoardBridge,
+        created_by: &str,
+    ) -> RepoResult<BoardBridge>;
+    /// Bridges of one board, or of every board, oldest first.
+    async fn list_board_bridges(&self, board_id: Option<Id>) -> RepoResult<Vec<BoardBridge>>;
+    /// Turn a bridge on or off; turning it on moves the cursor past threads
+    /// posted in the meantime.
+    async fn set_board_bridge_enabled(&self, id: Id, enabled: bool) -> RepoResult<BoardBridge>;
+    async fn delete_board_bridge(&self, id: Id) -> RepoResult<()>;
+    /// Lease up to `limit` enabled bridges of live boards for `lease_secs`;
+    /// `window_sent` counts only the current minute.
+    async fn claim_board_bridges(
+        &self,
+        limit: i64,
+        lease_secs: i64,
+    ) -> RepoResult<Vec<BoardBridge>>;
+    /// Visible threads of the bridge's board after its cursor and older than
+    /// `settle_secs`, oldest first.
+    async fn pending_bridge_threads(
+        &self,
+        bridge: &BoardBridge,
+        limit: i64,
+        settle_secs: i64,
+    ) -> RepoResult<Vec<Thread>>;
+    /// End a lease: advance the cursor to `last_thread_id`, count `sent`
+    /// posts in the rate limit window and record `error`. A bridge that
+    /// failed stays leased for `retry_secs` so it is retried later.
+    async fn release_board_bridge(
+        &self,
+        id: Id,
+        last_thread_id: Id,
+        sent: i32,
+        error: Option<String>,
+        retry_secs: i64,
+    ) -> RepoResult<()>;
+}
+
+#[async_trait]
+pub trait MatrixRepo: Send + Sync {
+    /// Create an enabled mirror; only posts made from now on are mirrored.
+    async fn create_matrix_mirror(
+        &self,
+        new: NewMatrixMirror,
+        created_by: &str,
+    ) -> RepoResult<MatrixMirror>;
+    async fn list_matrix_mirrors(&self) -> RepoResult<Vec<MatrixMirror>>;
+    /// Re-enabling moves the cursors past posts made while it was off.
+    async fn set_matrix_mirror_enabled(&self, id: Id, enabled: bool) -> RepoResult<MatrixMirror>;
+    async fn delete_matrix_mirror(&self, id: Id) -> RepoResult<()>;
+    /// Lease up to `limit` enabled mirrors whose target still exists for
+    /// `lease_secs`; a lease ends with `release_matrix_mirror`.
+    async fn claim_matrix_mirrors(
+        &self,
+        limit: i64,
+        lease_secs: i64,
+    ) -> RepoResult<Vec<MatrixMirror>>;
+    /// Visible threads (board mirrors only) and replies after the mirror's
+    /// cursors that are older than `settle_secs`, oldest first.
+    async fn pending_matrix_posts(
+        &self,
+        mirror: &MatrixMirror,
+        limit: i64,
+        settle_secs: i64,
+    ) -> RepoResult<Vec<MatrixPost>>;
+    /// Remember the room event a post became; repeats are ignored.
+    async fn record_matrix_event(
+        &self,
+        mirror_id: Id,
+        post: &MatrixPost,
+        event_id: &str,
+    ) -> RepoResult<()>;
+    /// Events of the mirror whose post, or the post's thread, was deleted
+    /// and which were not redacted yet.
+    async fn pending_matrix_redactions(
+        &self,
+        mirror_id: Id,
+        limit: i64,
+    ) -> RepoResult<Vec<MatrixEvent>>;
+    async fn mark_matrix_event_redacted(&self, mirror_id: Id, event_id: &str) -> RepoResult<()>;
+    /// End a lease: advance the cursors, note whether anything was sent and
+    /// record `error`. A mirror that failed stays leased for `retry_secs`.
+    async fn release_matrix_mirror(
+        &self,
+        id: Id,
+        last_thread_id: Id,
+        last_reply_id: Id,
+        sent: bool,
+        error: Option<String>,
+        retry_secs: i64,
+    ) -> RepoResult<()>;
+}
+
+#[async_trait]
+pub trait ServiceAccountRepo: Send + Sync {
+    /// `Conflict` if the name is taken, revoked accounts included.
+    async fn create_service_account(
+        &self,
+        new: &NewServiceAccount,
+        token_hash: &str,
+        created_by: &str,
+    ) -> RepoResult<ServiceAccount>;
+    /// All accounts, revoked ones included, by name.
+    async fn list_service_accounts(&self) -> RepoResult<Vec<ServiceAccount>>;
+    /// `NotFound` if there is no such active account.
+    async fn revoke_service_account(&self, id: Id) -> RepoResult<()>;
+    /// The active account holding a token, marking it used.
+    async fn use_service_account(&self, token_hash: &str) -> RepoResult<Option<ServiceAccount>>;
+}
+
+#[async_trait]
+pub trait FederationRepo: Send + Sync {
+    /// Record a remote follower, replacing its inboxes if it followed
+    /// before. A board's first follower starts its fan-out after the
+    /// board's newest thread.
+    async fn add_ap_follower(&self, board_id: Id, follower: NewApFollower) -> RepoResult<()>;
+    /// Forget a follower; unknown followers are ignored.
+    async fn remove_ap_follower(&self, board_id: Id, actor: &str) -> RepoResult<()>;
+    async fn count_ap_followers(&self, board_id: Id) -> RepoResult<i64>;
+    /// Queue one activity for one inbox.
+    async fn enqueue_ap_delivery(
+        &self,
+        board_id: Id,
+        inbox: &str,
+        activity: Value,
+    ) -> RepoResult<()>;
+    /// Visible threads of followed boards that were not fanned out yet and
+    /// are older than `settle_secs`, oldest first.
+    async fn pending_ap_threads(&self, limit: i64, settle_secs: i64) -> RepoResult<Vec<Thread>>;
+    /// Queue `activity` for every distinct follower inbox of the thread's
+    /// board and move the board's cursor past the thread, in one
+    /// transaction. `false` when another runner already fanned it out.
+    async fn fan_out_ap_thread(&self, thread: &Thread, activity: Value) -> RepoResult<bool>;
+    /// Lease up to `limit` due deliveries for `lease_secs`.
+    async fn claim_ap_deliveries(&self, limit: i64, lease_secs: i64)
+        -> RepoResult<Vec<ApDelivery>>;
+    /// Drop a delivered item, or retry it after `retry_secs`. Items that
+    /// failed `max_attempts` times are dropped too.
+    async fn finish_ap_delivery(
+        &self,
+        id: Id,
+        error: Option<String>,
+        retry_secs: i64,
+        max_attempts: i32,
+    ) -> RepoResult<()>;
+}
+
+/// Post an image row belongs to.
+#[derive(Debug, Clone, Copy)]
+pub enum ImageOwner {
+    Thread(Id),
+    Reply(Id),
+}
+
+/// Repository operations running inside one database transaction.
+///
+/// Dropping the handle without calling [`RepoTx::commit`] rolls everything back.
+#[async_trait]
+pub trait RepoTx: Send {
+    async fn get_board(&mut self, id: Id) -> RepoResult<Board>;
+    async fn get_thread(&mut self, id: Id) -> RepoResult<Thread>;
+    async fn get_reply(&mut self, id: Id) -> RepoResult<Reply>;
+    async fn create_thread(
+        &mut self,
+        new: NewThread,
+        created_by: Value,
+        public_identity: PublicIdentity,
+    ) -> RepoResult<Thread>;
+    async fn create_reply(
+        &mut self,
+        new: NewReply,
+        created_by: Value,
+        public_identity: PublicIdentity,
+    ) -> RepoResult<Reply>;
+    async fn attach_image(&mut self, owner: ImageOwner, hash: &str, mime: &str) -> RepoResult<()>;
+    /// Whether `subject` uploaded the blob or an uploader shared it. The
+    /// upload rows stay locked until the transaction ends.
+    async fn upload_attachable(&mut self, hash: &str, subject: Option<&str>) -> RepoResult<bool>;
+    async fn set_thread_delete_password(&mut self, id: Id, hash: &str) -> RepoResult<()>;
+    async fn set_reply_delete_password(&mut self, id: Id, hash: &str) -> RepoResult<()>;
+    async fn soft_delete_thread(&mut self, id: Id) -> RepoResult<()>;
+    async fn soft_delete_reply(&mut self, id: Id) -> RepoResult<()>;
+    /// Close a thread to new replies, or reopen it.
+    async fn set_thread_closed(&mut self, id: Id, closed: bool) -> RepoResult<()>;
+    /// Pin one of the thread's visible replies, or clear the pin with `None`.
+    async fn set_pinned_reply(&mut self, thread_id: Id, reply_id: Option<Id>) -> RepoResult<()>;
+    /// Hide a new post for staff review; it is deleted until approved.
+    async fn hold_thread(&mut self, id: Id) -> RepoResult<()>;
+    async fn hold_reply(&mut self, id: Id) -> RepoResult<()>;
+    /// Deletions later in this transaction are not held against their
+    /// authors' trust, as when authors delete their own posts.
+    async fn exempt_from_trust(&mut self) -> RepoResult<()>;
+    /// Write an outbox event that is published only if the transaction commits.
+    async fn record_event(&mut self, event_type: &str, payload: Value) -> RepoResult<()>;
+    /// Audit a thread moderation action alongside the change itself.
+    async fn record_moderation(&mut self, entry: NewModerationEntry) -> RepoResult<()>;
+    /// Log a removal's reason alongside the removal itself.
+    async fn record_deletion(&mut self, entry: NewDeletionEntry) -> RepoResult<()>;
+    async fn commit(self: Box<Self>) -> RepoResult<()>;
+}
+
+#[async_trait]
+pub trait UnitOfWork: Send + Sync {
+    async fn begin(&self) -> RepoResult<Box<dyn RepoTx>>;
+    /// How [`transaction`] re-runs work the database rolled back; without
+    /// one the error is returned.
+    fn retry_policy(&self) -> Option<&crate::retry::RetryPolicy> {
+        None
+    }
+}
+
+/// Run `work` in a transaction: commit on `Ok`, roll back on `Err`. Work
+/// rolled back by a serialization failure or deadlock runs again in a fresh
+/// transaction under the repo's [`UnitOfWork::retry_policy`].
+///
+/// ```ignore
+/// let thread = transaction(&*data.repo, |tx| Box::pin(async move {
+///     let thread = tx.create_thread(new, created_by, identity).await?;
+///     tx.attach_image(ImageOwner::Thread(thread.id), &hash, &mime).await?;
+///     Ok(thread)
+/// })).await?;
+/// ```
+pub async fn transaction<R, T, F>(repo: &R, work: F) -> RepoResult<T>
+where
+    R: UnitOfWork + ?Sized,
+    T: Send,
+    F: for<'t> Fn(&'t mut dyn RepoTx) -> BoxFuture<'t, RepoResult<T>> + Send + Sync,
+{
+    let work = &work;
+    let attempt = move || async move {
+        let mut tx = repo.begin().await?;
+        let value = work(tx.as_mut()).await?;
+        tx.commit().await?;
+        Ok(value)
+    };
+    match repo.retry_policy() {
+        Some(policy) => policy.rerun_aborted("transaction", attempt).await,
+        None => attempt().await,
+    }
 }
 
-pub trait Repo: BoardRepo + ThreadRepo + ReplyRepo + RoleRepo + ImageRepo + BanRepo {}
+pub trait Repo:
+    BoardRepo
+    + ThreadRepo
+    + ReplyRepo
+    + RoleRepo
+    + ImageRepo
+    + BanRepo
+    + OutboxRepo
+    + SearchRepo
+    + TransferRepo
+    + SchemaRepo
+    + SitemapRepo
+    + NotificationRepo
+    + PreferenceRepo
+    + FilterRepo
+    + ProfileRepo
+    + ModerationRepo
+    + ScheduleRepo
+    + ReactionRepo
+    + SavedSearchRepo
+    + AppealRepo
+    + ReportRepo
+    + TrustRepo
+    + HoldRepo
+    + BridgeRepo
+    + MatrixRepo
+    + FederationRepo
+    + ServiceAccountRepo
+    + UnitOfWork
+{
+}
 
-impl<T> Repo for T where T: BoardRepo + ThreadRepo + ReplyRepo + RoleRepo + ImageRepo + BanRepo {}
+impl<T> Repo for T where
+    T: BoardRepo
+        + ThreadRepo
+        + ReplyRepo
+        + RoleRepo
+        + ImageRepo
+        + BanRepo
+        + OutboxRepo
+        + SearchRepo
+        + TransferRepo
+        + SchemaRepo
+        + SitemapRepo
+        + NotificationRepo
+        + PreferenceRepo
+        + FilterRepo
+        + ProfileRepo
+        + ModerationRepo
+        + ScheduleRepo
+        + ReactionRepo
+        + SavedSearchRepo
+        + AppealRepo
+        + ReportRepo
+        + TrustRepo
+        + HoldRepo
+        + BridgeRepo
+        + MatrixRepo
+        + FederationRepo
+        + ServiceAccountRepo
+        + UnitOfWork
+{
+}
 
 // Postgres implementation (now the only backend)
 pub mod pg {
     use super::*;
-    use sqlx::{Pool, Postgres, Row}; // Row is new
+    use crate::outbox::events;
+    use crate::profiles::{author_subjects, fill_authors, Authored};
+    use sqlx::{PgConnection, PgExecutor, Pool, Postgres};
+    use std::collections::HashMap;
+    use std::future::Future;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
 
-    #[derive(Clone)]
-    pub struct PgRepo {
-        pool: Pool<Postgres>,
+    /// Rows buffered between a streamed query and its consumer.
+    const ROW_STREAM_BUFFER: usize = 64;
+
+    /// How long reads bypass a replica after it failed at the connection level.
+    const REPLICA_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+    // Outbox rows share the caller's transaction so an event exists iff its mutation committed.
+    async fn record_event(
+        conn: &mut PgConnection,
+        event_type: &str,
+        payload: Value,
+    ) -> RepoResult<()> {
+        sqlx::query!(
+            "INSERT INTO outbox (event_type, payload) VALUES ($1, $2)",
+            event_type,
+            payload
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
     }
 
-    impl PgRepo {
-        pub fn new(pool: Pool<Postgres>) -> Self {
-            Self { pool }
-        }
+    async fn record_deletion(conn: &mut PgConnection, entry: NewDeletionEntry) -> RepoResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO deletion_log (kind, target_id, hard, actor, reason)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+            entry.kind.as_str(),
+            entry.target_id,
+            entry.hard,
+            entry.actor,
+            entry.reason
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
     }
 
-    #[async_trait]
-    impl BoardRepo for PgRepo {
-        async fn list_boards(&self, include_deleted: bool) -> RepoResult<Vec<Board>> {
-            let sql = if include_deleted {
-                "SELECT id, slug, title, created_at, deleted_at FROM boards ORDER BY id"
-            } else {
-                "SELECT id, slug, title, created_at, deleted_at FROM boards WHERE deleted_at IS NULL ORDER BY id"
-            };
-            let recs = sqlx::query_as::<_, Board>(sql)
-                .fetch_all(&self.pool)
-                .await
-                .map_err(|_| RepoError::NotFound)?;
-            Ok(recs)
-        }
-        async fn create_board(&self, new: NewBoard) -> RepoResult<Board> {
-            let rec = sqlx::query_as::<_, Board>("INSERT INTO boards (slug, title) VALUES ($1,$2) RETURNING id, slug, title, created_at, deleted_at")
-                .bind(&new.slug).bind(&new.title)
-                .fetch_one(&self.pool).await.map_err(|_| RepoError::Conflict)?;
-            Ok(rec)
+    /// `LastAdmin` unless an active admin other than `subject` remains. Locks
+    /// the admin assignments so concurrent demotions cannot both pass.
+    async fn ensure_other_admin(conn: &mut PgConnection, subject: &str) -> RepoResult<()> {
+        let admins = sqlx::query_scalar!(
+            r#"
+            SELECT subject FROM user_roles
+            WHERE role = 'admin' AND (expires_at IS NULL OR expires_at > now())
+            FOR UPDATE
+            "#
+        )
+        .fetch_all(conn)
+        .await?;
+        if admins.iter().any(|s| s == subject) && admins.len() == 1 {
+            return Err(RepoError::LastAdmin);
         }
-        async fn update_board(&self, id: Id, upd: UpdateBoard) -> RepoResult<Board> {
-            // Build dynamic SQL (simple field subset)
-            let mut slug = None;
-            let mut title = None;
-            if let Some(s) = upd.slug {
-                slug = Some(s);
-            }
-            if let Some(t) = upd.title {
-                title = Some(t);
+        Ok(())
+    }
+
+    /// Run `produce` on its own task, handing rows over through a small
+    /// channel so the caller owns a stream that never buffers many rows.
+    fn row_stream<T, F, Fut>(produce: F) -> RowStream<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(tokio::sync::mpsc::Sender<RepoResult<T>>) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (tx, rx) = tokio::sync::mpsc::channel(ROW_STREAM_BUFFER);
+        tokio::spawn(produce(tx));
+        Box::pin(futures_util::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|row| (row, rx))
+        }))
+    }
+
+    /// Forward every row of `rows`, stopping when the receiver is gone.
+    /// Authors are filled in for each batch of ready rows with one lookup.
+    async fn forward_rows<T: Authored>(
+        pool: Pool<Postgres>,
+        rows: futures_util::stream::BoxStream<'_, Result<T, sqlx::Error>>,
+        tx: tokio::sync::mpsc::Sender<RepoResult<T>>,
+    ) {
+        use futures_util::StreamExt;
+        let mut batches = rows.ready_chunks(ROW_STREAM_BUFFER);
+        while let Some(batch) = batches.next().await {
+            let mut posts = Vec::with_capacity(batch.len());
+            let mut failure = None;
+            for row in batch {
+                match row {
+                    Ok(post) => posts.push(post),
+                    Err(e) => {
+                        failure = Some(RepoError::from(e));
+                        break;
+                    }
+                }
             }
-            let rec = sqlx::query_as::<_, Board>(
-                "UPDATE boards SET slug = COALESCE($2, slug), title = COALESCE($3, title) WHERE id=$1 RETURNING id, slug, title, created_at, deleted_at"
-            )
-            .bind(id)
-            .bind(slug.as_ref())
-            .bind(title.as_ref())
-            .fetch_one(&self.pool).await.map_err(|_| RepoError::NotFound)?;
-            Ok(rec)
-        }
-        async fn get_board(&self, id: Id) -> RepoResult<Board> {
-            let rec = sqlx::query_as::<_, Board>(
-                "SELECT id, slug, title, created_at, deleted_at FROM boards WHERE id=$1",
-            )
-            .bind(id)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|_| RepoError::NotFound)?;
-            Ok(rec)
-        }
-        async fn soft_delete_board(&self, id: Id) -> RepoResult<()> {
-            let res = sqlx::query(
-                "UPDATE boards SET deleted_at = COALESCE(deleted_at, now()) WHERE id=$1",
-            )
-            .bind(id)
-            .execute(&self.pool)
-            .await
-            .map_err(|_| RepoError::NotFound)?;
-            if res.rows_affected() == 0 {
-                return Err(RepoError::NotFound);
+            let subjects = author_subjects(&posts);
+            if !subjects.is_empty() {
+                match load_profiles(&pool, &subjects).await {
+                    Ok(profiles) => fill_authors(&mut posts, &profiles),
+                    Err(e) => {
+                        let _ = tx.send(Err(e.into())).await;
+                        return;
+                    }
+                }
             }
-            Ok(())
-        }
-        async fn restore_board(&self, id: Id) -> RepoResult<()> {
-            let res = sqlx::query("UPDATE boards SET deleted_at = NULL WHERE id=$1")
-                .bind(id)
-                .execute(&self.pool)
-                .await
-                .map_err(|_| RepoError::NotFound)?;
-            if res.rows_affected() == 0 {
-                return Err(RepoError::NotFound);
+            for post in posts {
+                if tx.send(Ok(post)).await.is_err() {
+                    return;
+                }
             }
-            Ok(())
-        }
-        async fn hard_delete_board(&self, id: Id) -> RepoResult<()> {
-            let res = sqlx::query("DELETE FROM boards WHERE id=$1")
-                .bind(id)
-                .execute(&self.pool)
-                .await
-                .map_err(|_| RepoError::NotFound)?;
-            if res.rows_affected() == 0 {
-                return Err(RepoError::NotFound);
+            if let Some(e) = failure {
+                let _ = tx.send(Err(e)).await;
+                return;
             }
-            Ok(())
         }
     }
 
-    #[async_trait]
-    impl ThreadRepo for PgRepo {
-        async fn list_threads(
-            &self,
-            board_id: Id,
-            include_deleted: bool,
-        ) -> RepoResult<Vec<Thread>> {
-            let base = r#"
-          SELECT t.id, t.board_id, t.subject, t.body, t.created_at, t.bump_time, t.created_by,
-              img.hash as image_hash, img.mime as mime, t.author_name, t.tripcode, t.deleted_at
-                FROM threads t
-                LEFT JOIN LATERAL (
-                   SELECT i.hash, i.mime FROM images i
-                   WHERE i.thread_id = t.id
-                   ORDER BY i.id ASC LIMIT 1
-                ) img ON TRUE
-                WHERE t.board_id = $1
-            "#;
-            let sql = if include_deleted {
-                format!("{base} ORDER BY t.bump_time DESC")
-            } else {
-                format!("{base} AND t.deleted_at IS NULL ORDER BY t.bump_time DESC")
-            };
-            let recs = sqlx::query_as::<_, Thread>(&sql)
-                .bind(board_id)
-                .fetch_all(&self.pool)
-                .await
-                .map_err(|_| RepoError::NotFound)?;
-            Ok(recs)
-        }
-        async fn create_thread(
-            &self,
-            new: NewThread,
-            created_by: Value,
-            public_identity: PublicIdentity,
-        ) -> RepoResult<Thread> {
-            let mut tx = self.pool.begin().await.map_err(|_| RepoError::Conflict)?;
-
-            // insert thread and capture its id
-            let rec = sqlx::query(
-                "INSERT INTO threads (board_id, subject, body, created_by, author_name, tripcode) VALUES ($1,$2,$3,$4,$5,$6) RETURNING id"
-            )
-                .bind(new.board_id)
-                .bind(&new.subject)
-                .bind(&new.body)
-                .bind(&created_by)
-                .bind(&public_identity.author_name)
-                .bind(&public_identity.tripcode)
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|_| RepoError::NotFound)?;
-            let thread_id: Id = rec.get::<Id, _>("id");
-
-            if let (Some(hash), Some(mime)) = (new.image_hash.as_ref(), new.mime.as_ref()) {
-                sqlx::query(
-                    "INSERT INTO images (thread_id, reply_id, hash, mime) VALUES ($1, NULL, $2, $3)"
-                )
-                    .bind(thread_id)
-                    .bind(hash)
-                    .bind(mime)
-                    .execute(&mut *tx)
-                    .await
-                    .map_err(|_| RepoError::Conflict)?;
-            }
-
-            tx.commit().await.map_err(|_| RepoError::Conflict)?;
+    /// Profiles of `subjects` that set a display name or avatar.
+    async fn load_profiles<'c>(
+        conn: impl PgExecutor<'c>,
+        subjects: &[String],
+    ) -> Result<HashMap<String, AuthorProfile>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"SELECT subject, display_name, avatar_hash FROM user_profiles
+               WHERE subject = ANY($1) AND (display_name IS NOT NULL OR avatar_hash IS NOT NULL)"#,
+            subjects
+        )
+        .fetch_all(conn)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let profile = AuthorProfile {
+                    display_name: row.display_name,
+                    avatar_hash: row.avatar_hash,
+                };
+                (row.subject, profile)
+            })
+            .collect())
+    }
 
-            // fetch and return full thread record
-            let thread = sqlx::query_as::<_, Thread>(
-                r#"
+    async fn fetch_thread<'c>(conn: impl PgExecutor<'c>, id: Id) -> RepoResult<Thread> {
+        Ok(sqlx::query_as!(
+            Thread,
+            r#"
           SELECT t.id, t.board_id, t.subject, t.body, t.created_at, t.bump_time, t.created_by,
-              img.hash as image_hash, img.mime as mime, t.author_name, t.tripcode, t.deleted_at
+              author_profile(t.created_by) as "author: sqlx::types::Json<AuthorProfile>",
+              img.hash as "image_hash?", img.mime as "mime?", img.size_bytes as "image_size?", t.author_name, t.tripcode, t.deleted_at, t.closed_at, t.archived_at, t.pinned_reply_id, t.tags,
+              t.reply_count, t.image_count, t.slug
                 FROM threads t
                 LEFT JOIN LATERAL (
-                    SELECT i.hash, i.mime
-                    FROM images i
-                    WHERE i.thread_id = t.id
-                    ORDER BY i.id ASC
-                    LIMIT 1
+                   SELECT i.hash, i.mime, i.size_bytes FROM images i WHERE i.thread_id = t.id ORDER BY i.id ASC LIMIT 1
                 ) img ON TRUE
                 WHERE t.id = $1
             "#,
-            )
-            .bind(thread_id)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|_| RepoError::NotFound)?;
+            id
+        )
+        .fetch_one(conn)
+        .await?)
+    }
 
-            Ok(thread)
-        }
-        async fn get_thread(&self, id: Id) -> RepoResult<Thread> {
-            let thread = sqlx::query_as::<_, Thread>(r#"
-          SELECT t.id, t.board_id, t.subject, t.body, t.created_at, t.bump_time, t.created_by,
-              img.hash as image_hash, img.mime as mime, t.author_name, t.tripcode, t.deleted_at
-                FROM threads t
+    async fn fetch_reply<'c>(conn: impl PgExecutor<'c>, id: Id) -> RepoResult<Reply> {
+        Ok(sqlx::query_as!(
+            Reply,
+            r#"
+          SELECT r.id, r.thread_id, r.content,
+              img.hash as "image_hash?", img.mime as "mime?", img.size_bytes as "image_size?",
+              r.author_name, r.tripcode, r.created_at, r.deleted_at, r.created_by,
+              author_profile(r.created_by) as "author: sqlx::types::Json<AuthorProfile>",
+              reaction_counts(r.id) as "reactions!: sqlx::types::Json<Vec<ReactionCount>>",
+              removal_reason(r.id, r.deleted_at) as "removal_reason?"
+                FROM replies r
                 LEFT JOIN LATERAL (
-                   SELECT i.hash, i.mime FROM images i WHERE i.thread_id = t.id ORDER BY i.id ASC LIMIT 1
+                    SELECT i.hash, i.mime, i.size_bytes FROM images i WHERE i.reply_id = r.id ORDER BY i.id ASC LIMIT 1
                 ) img ON TRUE
-                WHERE t.id = $1
-            "#).bind(id).fetch_one(&self.pool).await.map_err(|_| RepoError::NotFound)?;
-            Ok(thread)
-        }
-        async fn soft_delete_thread(&self, id: Id) -> RepoResult<()> {
-            let res = sqlx::query(
-                "UPDATE threads SET deleted_at = COALESCE(deleted_at, now()) WHERE id=$1",
-            )
-            .bind(id)
-            .execute(&self.pool)
-            .await
-            .map_err(|_| RepoError::NotFound)?;
+                WHERE r.id = $1
+            "#,
+            id
+        )
+        .fetch_one(conn)
+        .await?)
+    }
+
+    async fn insert_image(
+        conn: &mut PgConnection,
+        owner: ImageOwner,
+        hash: &str,
+        mime: &str,
+    ) -> RepoResult<()> {
+        let (thread_id, reply_id) = match owner {
+            ImageOwner::Thread(id) => (Some(id), None),
+            ImageOwner::Reply(id) => (None, Some(id)),
+        };
+        sqlx::query!(
+            r#"
+            INSERT INTO images (thread_id, reply_id, hash, mime, size_bytes)
+            VALUES ($1, $2, $3, $4,
+                    (SELECT size_bytes FROM uploads WHERE hash = $3 ORDER BY created_at LIMIT 1))
+            "#,
+            thread_id,
+            reply_id,
+            hash,
+            mime
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_thread(
+        conn: &mut PgConnection,
+        new: &NewThread,
+        created_by: &Value,
+        public_identity: &PublicIdentity,
+    ) -> RepoResult<Id> {
+        let thread_id: Id = sqlx::query_scalar!("INSERT INTO threads (board_id, subject, body, created_by, author_name, tripcode, tags) VALUES ($1,$2,$3,$4,$5,$6,$7) RETURNING id", new.board_id, new.subject, new.body, created_by, public_identity.author_name, public_identity.tripcode, &new.tags)
+            .fetch_one(&mut *conn)
+            .await?;
+        if let (Some(hash), Some(mime)) = (new.image_hash.as_ref(), new.mime.as_ref()) {
+            insert_image(conn, ImageOwner::Thread(thread_id), hash, mime).await?;
+        }
+        record_event(
+            conn,
+            events::THREAD_CREATED,
+            serde_json::json!({ "thread_id": thread_id, "board_id": new.board_id }),
+        )
+        .await?;
+        enforce_thread_limit(conn, new.board_id).await?;
+        Ok(thread_id)
+    }
+
+    /// Archive, or prune when the board says so, the least recently bumped
+    /// threads beyond the board's `max_threads`.
+    async fn enforce_thread_limit(conn: &mut PgConnection, board_id: Id) -> RepoResult<()> {
+        // NO KEY UPDATE serializes concurrent thread creation on the board
+        // without conflicting with the key-share locks the inserts hold.
+        let board = sqlx::query!(
+            "SELECT max_threads, prune_overflow, reply_cooldown_secs FROM boards WHERE id=$1 FOR NO KEY UPDATE",
+            board_id
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        if board.max_threads == 0 {
+            return Ok(());
+        }
+        let overflow: Vec<Id> = sqlx::query_scalar!(
+            r#"SELECT id FROM threads
+               WHERE board_id = $1 AND deleted_at IS NULL AND archived_at IS NULL
+               ORDER BY bump_time DESC, id DESC
+               OFFSET $2"#,
+            board_id,
+            i64::from(board.max_threads)
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        for id in overflow {
+            if board.prune_overflow {
+                // Pruning is housekeeping, not moderation of the author.
+                set_trust_exempt(conn, true).await?;
+                soft_delete_thread_in(conn, id).await?;
+                set_trust_exempt(conn, false).await?;
+            } else {
+                sqlx::query!("UPDATE threads SET archived_at = now() WHERE id=$1", id)
+                    .execute(&mut *conn)
+                    .await?;
+                record_event(
+                    conn,
+                    events::THREAD_ARCHIVED,
+                    serde_json::json!({ "thread_id": id, "board_id": board_id }),
+                )
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn insert_reply(
+        conn: &mut PgConnection,
+        new: &NewReply,
+        created_by: &Value,
+        public_identity: &PublicIdentity,
+    ) -> RepoResult<Id> {
+        let reply_id: Id = sqlx::query_scalar!("INSERT INTO replies (thread_id, content, created_by, author_name, tripcode) VALUES ($1,$2,$3,$4,$5) RETURNING id", new.thread_id, new.content, created_by, public_identity.author_name, public_identity.tripcode)
+            .fetch_one(&mut *conn)
+            .await?;
+        if let (Some(hash), Some(mime)) = (new.image_hash.as_ref(), new.mime.as_ref()) {
+            insert_image(conn, ImageOwner::Reply(reply_id), hash, mime).await?;
+        }
+        // bump parent thread
+        sqlx::query!(
+            "UPDATE threads SET bump_time = now() WHERE id=$1",
+            new.thread_id
+        )
+        .execute(&mut *conn)
+        .await?;
+        record_event(
+            conn,
+            events::REPLY_CREATED,
+            serde_json::json!({ "reply_id": reply_id, "thread_id": new.thread_id }),
+        )
+        .await?;
+        Ok(reply_id)
+    }
+
+    async fn soft_delete_thread_in(conn: &mut PgConnection, id: Id) -> RepoResult<()> {
+        let res = sqlx::query!(
+            "UPDATE threads SET deleted_at = COALESCE(deleted_at, now()) WHERE id=$1",
+            id
+        )
+        .execute(&mut *conn)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+        record_event(
+            conn,
+            events::THREAD_DELETED,
+            serde_json::json!({ "thread_id": id, "hard": false }),
+        )
+        .await
+    }
+
+    async fn soft_delete_reply_in(conn: &mut PgConnection, id: Id) -> RepoResult<()> {
+        let res = sqlx::query!(
+            "UPDATE replies SET deleted_at = COALESCE(deleted_at, now()) WHERE id=$1",
+            id
+        )
+        .execute(&mut *conn)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(RepoError::NotFound);
+        }
+        sqlx::query!(
+            "UPDATE threads SET pinned_reply_id = NULL WHERE pinned_reply_id = $1",
+            id
+        )
+        .execute(&mut *conn)
+        .await?;
+        record_event(
+            conn,
+            events::REPLY_DELETED,
+            serde_json::json!({ "reply_id": id, "hard": false }),
+        )
+        .await
+    }
+
+    /// `Conflict` while a legal hold keeps the blob.
+    async fn ensure_image_not_held(conn: &mut PgConnection, hash: &str) -> RepoResult<()> {
+        let held = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM legal_holds WHERE image_hash = $1 AND released_at IS NULL) as "exists!""#,
+            hash
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        if held {
+            return Err(RepoError::Conflict);
+        }
+        Ok(())
+    }
+
+    /// See `trust_count_removal` in the subject trust migration.
+    async fn set_trust_exempt(conn: &mut PgConnection, exempt: bool) -> RepoResult<()> {
+        sqlx::query_scalar!(
+            "SELECT set_config('rib.trust_exempt', $1, true)",
+            if exempt { "on" } else { "off" }
+        )
+        .fetch_one(conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Open transaction handed out by [`UnitOfWork::begin`]; rolls back on drop.
+    pub struct PgTx {
+        tx: sqlx::Transaction<'static, Postgres>,
+    }
+
+    #[async_trait]
+    impl RepoTx for PgTx {
+        async fn get_board(&mut self, id: Id) -> RepoResult<Board> {
+            Ok(sqlx::query_as!(
+                Board,
+                "SELECT id, slug, title, created_at, deleted_at, anonymous_posting, op_moderation, max_threads, prune_overflow, reply_cooldown_secs, reactions, tag_vocabulary, allowed_mime, tombstones, beta FROM boards WHERE id=$1",
+                id
+            )
+            .fetch_one(&mut *self.tx)
+            .await?)
+        }
+        async fn get_thread(&mut self, id: Id) -> RepoResult<Thread> {
+            fetch_thread(&mut *self.tx, id).await
+        }
+        async fn get_reply(&mut self, id: Id) -> RepoResult<Reply> {
+            fetch_reply(&mut *self.tx, id).await
+        }
+        async fn create_thread(
+            &mut self,
+            new: NewThread,
+            created_by: Value,
+            public_identity: PublicIdentity,
+        ) -> RepoResult<Thread> {
+            let id = insert_thread(&mut self.tx, &new, &created_by, &public_identity).await?;
+            fetch_thread(&mut *self.tx, id).await
+        }
+        async fn create_reply(
+            &mut self,
+            new: NewReply,
+            created_by: Value,
+            public_identity: PublicIdentity,
+        ) -> RepoResult<Reply> {
+            let id = insert_reply(&mut self.tx, &new, &created_by, &public_identity).await?;
+            fetch_reply(&mut *self.tx, id).await
+        }
+        async fn attach_image(
+            &mut self,
+            owner: ImageOwner,
+            hash: &str,
+            mime: &str,
+        ) -> RepoResult<()> {
+            insert_image(&mut self.tx, owner, hash, mime).await
+        }
+        async fn upload_attachable(
+            &mut self,
+            hash: &str,
+            subject: Option<&str>,
+        ) -> RepoResult<bool> {
+            Ok(sqlx::query_scalar!(
+                r#"SELECT 1 as "one!" FROM uploads
+                   WHERE hash = $1 AND (shareable OR uploaded_by = $2)
+                   LIMIT 1 FOR SHARE"#,
+                hash,
+                subject
+            )
+            .fetch_optional(&mut *self.tx)
+            .await?
+            .is_some())
+        }
+        async fn set_thread_delete_password(&mut self, id: Id, hash: &str) -> RepoResult<()> {
+            sqlx::query!(
+                "UPDATE threads SET delete_password_hash = $2 WHERE id=$1",
+                id,
+                hash
+            )
+            .execute(&mut *self.tx)
+            .await?;
+            Ok(())
+        }
+        async fn set_reply_delete_password(&mut self, id: Id, hash: &str) -> RepoResult<()> {
+            sqlx::query!(
+                "UPDATE replies SET delete_password_hash = $2 WHERE id=$1",
+                id,
+                hash
+            )
+            .execute(&mut *self.tx)
+            .await?;
+            Ok(())
+        }
+        async fn soft_delete_thread(&mut self, id: Id) -> RepoResult<()> {
+            soft_delete_thread_in(&mut self.tx, id).await
+        }
+        async fn soft_delete_reply(&mut self, id: Id) -> RepoResult<()> {
+            soft_delete_reply_in(&mut self.tx, id).await
+        }
+        async fn hold_thread(&mut self, id: Id) -> RepoResult<()> {
+            sqlx::query!("UPDATE threads SET held_at = now() WHERE id=$1", id)
+                .execute(&mut *self.tx)
+                .await?;
+            soft_delete_thread_in(&mut self.tx, id).await
+        }
+        async fn hold_reply(&mut self, id: Id) -> RepoResult<()> {
+            sqlx::query!("UPDATE replies SET held_at = now() WHERE id=$1", id)
+                .execute(&mut *self.tx)
+                .await?;
+            soft_delete_reply_in(&mut self.tx, id).await
+        }
+        async fn exempt_from_trust(&mut self) -> RepoResult<()> {
+            set_trust_exempt(&mut self.tx, true).await
+        }
+        async fn set_thread_closed(&mut self, id: Id, closed: bool) -> RepoResult<()> {
+            let res = sqlx::query!(
+                r#"
+                UPDATE threads
+                SET closed_at = CASE WHEN $2 THEN COALESCE(closed_at, now()) END
+                WHERE id=$1 AND deleted_at IS NULL
+                "#,
+                id,
+                closed
+            )
+            .execute(&mut *self.tx)
+            .await?;
             if res.rows_affected() == 0 {
                 return Err(RepoError::NotFound);
             }
             Ok(())
         }
-        async fn restore_thread(&self, id: Id) -> RepoResult<()> {
-            let res = sqlx::query("UPDATE threads SET deleted_at = NULL WHERE id=$1")
-                .bind(id)
-                .execute(&self.pool)
+        async fn set_pinned_reply(
+            &mut self,
+            thread_id: Id,
+            reply_id: Option<Id>,
+        ) -> RepoResult<()> {
+            let res = sqlx::query!(
+                r#"
+                UPDATE threads SET pinned_reply_id = $2
+                WHERE id=$1 AND deleted_at IS NULL
+                  AND ($2::BIGINT IS NULL OR EXISTS (
+                      SELECT 1 FROM replies r
+                      WHERE r.id = $2 AND r.thread_id = $1 AND r.deleted_at IS NULL
+                  ))
+                "#,
+                thread_id,
+                reply_id
+            )
+            .execute(&mut *self.tx)
+            .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+        async fn record_event(&mut self, event_type: &str, payload: Value) -> RepoResult<()> {
+            record_event(&mut self.tx, event_type, payload).await
+        }
+        async fn record_moderation(&mut self, entry: NewModerationEntry) -> RepoResult<()> {
+            sqlx::query!(
+                r#"
+                INSERT INTO moderation_log (actor, actor_role, action, thread_id, reply_id)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+                entry.actor,
+                entry.actor_role.as_str(),
+                entry.action.as_str(),
+                entry.thread_id,
+                entry.reply_id
+            )
+            .execute(&mut *self.tx)
+            .await?;
+            Ok(())
+        }
+        async fn record_deletion(&mut self, entry: NewDeletionEntry) -> RepoResult<()> {
+            record_deletion(&mut self.tx, entry).await
+        }
+        async fn commit(self: Box<Self>) -> RepoResult<()> {
+            Ok(self.tx.commit().await?)
+        }
+    }
+
+    #[derive(Clone)]
+    struct ReadReplica {
+        pool: Pool<Postgres>,
+        unavailable_until: Arc<Mutex<Option<Instant>>>,
+    }
+
+    impl ReadReplica {
+        fn is_available(&self) -> bool {
+            self.unavailable_until
+                .lock()
+                .unwrap()
+                .is_none_or(|until| Instant::now() >= until)
+        }
+
+        fn mark_unavailable(&self) {
+            *self.unavailable_until.lock().unwrap() = Some(Instant::now() + REPLICA_RETRY_AFTER);
+        }
+    }
+
+    // Errors meaning the replica itself is unreachable, as opposed to a bad query.
+    fn is_connection_error(e: &sqlx::Error) -> bool {
+        matches!(
+            e,
+            sqlx::Error::Io(_)
+                | sqlx::Error::Tls(_)
+                | sqlx::Error::Protocol(_)
+                | sqlx::Error::PoolTimedOut
+                | sqlx::Error::PoolClosed
+                | sqlx::Error::WorkerCrashed
+        )
+    }
+
+    #[derive(Clone)]
+    pub struct PgRepo {
+        pool: Pool<Postgres>,
+        replica: Option<ReadReplica>,
+    }
+
+    impl PgRepo {
+        pub fn new(pool: Pool<Postgres>) -> Self {
+            Self {
+                pool,
+                replica: None,
+            }
+        }
+
+        /// Route listing and search reads to a replica pool. Point lookups
+        /// (`get_*`), roles, and bans stay on the primary so callers always
+        /// read their own writes and authorization never sees replication lag.
+        pub fn with_read_pool(mut self, pool: Pool<Postgres>) -> Self {
+            self.replica = Some(ReadReplica {
+                pool,
+                unavailable_until: Arc::new(Mutex::new(None)),
+            });
+            self
+        }
+
+        pub fn pool(&self) -> &Pool<Postgres> {
+            &self.pool
+        }
+
+        pub fn read_pool(&self) -> Option<&Pool<Postgres>> {
+            self.replica.as_ref().map(|r| &r.pool)
+        }
+
+        /// Pool for a streamed read: the replica while it is healthy. Streams
+        /// do not fall back mid-way, so a failing replica ends them early.
+        fn stream_pool(&self) -> Pool<Postgres> {
+            self.replica
+                .as_ref()
+                .filter(|r| r.is_available())
+                .map(|r| r.pool.clone())
+                .unwrap_or_else(|| self.pool.clone())
+        }
+
+        /// Fill in the authors of a page of posts with one profile lookup.
+        async fn with_authors<T: Authored>(&self, mut posts: Vec<T>) -> RepoResult<Vec<T>> {
+            let subjects = author_subjects(&posts);
+            if subjects.is_empty() {
+                return Ok(posts);
+            }
+            let subjects = &subjects;
+            let profiles = self
+                .read(|pool| async move { load_profiles(&pool, subjects).await })
+                .await?;
+            fill_authors(&mut posts, &profiles);
+            Ok(posts)
+        }
+
+        /// Run a read on the replica when one is configured and healthy,
+        /// falling back to the primary if the replica cannot be reached.
+        async fn read<T, F, Fut>(&self, query: F) -> Result<T, sqlx::Error>
+        where
+            F: Fn(Pool<Postgres>) -> Fut,
+            Fut: Future<Output = Result<T, sqlx::Error>>,
+        {
+            if let Some(replica) = self.replica.as_ref().filter(|r| r.is_available()) {
+                match query(replica.pool.clone()).await {
+                    Err(e) if is_connection_error(&e) => {
+                        replica.mark_unavailable();
+                        metrics::increment_counter!("db_replica_fallback");
+                        log::warn!("read replica unavailable, using primary: {e}");
+                    }
+                    result => return result,
+                }
+            }
+            query(self.pool.clone()).await
+        }
+
+        async fn upsert_subject_role(
+            &self,
+            subject: &str,
+            role: AuthRole,
+            expires_at: Option<DateTime<Utc>>,
+        ) -> RepoResult<()> {
+            let role_str = match role {
+                AuthRole::Admin => "admin",
+                AuthRole::Moderator => "moderator",
+                AuthRole::User => "user",
+            };
+            let mut tx = self.pool.begin().await?;
+            if role != AuthRole::Admin || expires_at.is_some() {
+                ensure_other_admin(&mut tx, subject).await?;
+            }
+            let _ = sqlx::query!("INSERT INTO user_roles (subject, role, expires_at, updated_at) VALUES ($1,$2,$3, now()) ON CONFLICT (subject) DO UPDATE SET role=EXCLUDED.role, expires_at=EXCLUDED.expires_at, updated_at=now()", subject, role_str, expires_at)
+                .execute(&mut *tx)
                 .await
-                .map_err(|_| RepoError::NotFound)?;
+                ?;
+            tx.commit().await?;
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl BoardRepo for PgRepo {
+        async fn list_boards(&self, include_deleted: bool) -> RepoResult<Vec<Board>> {
+            let recs = self
+                .read(|pool| async move {
+                    sqlx::query_as!(
+                        Board,
+                        "SELECT id, slug, title, created_at, deleted_at, anonymous_posting, op_moderation, max_threads, prune_overflow, reply_cooldown_secs, reactions, tag_vocabulary, allowed_mime, tombstones, beta FROM boards WHERE $1 OR deleted_at IS NULL ORDER BY id",
+                        include_deleted
+                    )
+                    .fetch_all(&pool)
+                    .await
+                })
+                .await?;
+            Ok(recs)
+        }
+        async fn list_boards_from_primary(&self) -> RepoResult<Vec<Board>> {
+            Ok(sqlx::query_as!(
+                Board,
+                "SELECT id, slug, title, created_at, deleted_at, anonymous_posting, op_moderation, max_threads, prune_overflow, reply_cooldown_secs, reactions, tag_vocabulary, allowed_mime, tombstones, beta FROM boards WHERE deleted_at IS NULL ORDER BY id"
+            )
+            .fetch_all(&self.pool)
+            .await?)
+        }
+        async fn create_board(&self, new: NewBoard) -> RepoResult<Board> {
+            let rec = sqlx::query_as!(
+                Board,
+                "INSERT INTO boards (slug, title) VALUES ($1,$2) RETURNING id, slug, title, created_at, deleted_at, anonymous_posting, op_moderation, max_threads, prune_overflow, reply_cooldown_secs, reactions, tag_vocabulary, allowed_mime, tombstones, beta",
+                new.slug,
+                new.title
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            Ok(rec)
+        }
+        async fn update_board(&self, id: Id, upd: UpdateBoard) -> RepoResult<Board> {
+            // Build dynamic SQL (simple field subset)
+            let mut slug = None;
+            let mut title = None;
+            if let Some(s) = upd.slug {
+                slug = Some(s);
+            }
+            if let Some(t) = upd.title {
+                title = Some(t);
+            }
+            let limit_changed = upd.max_threads.is_some() || upd.prune_overflow.is_some();
+            let mut tx = self.pool.begin().await?;
+            let rec = sqlx::query_as!(
+                Board,
+                "UPDATE boards SET slug = COALESCE($2, slug), title = COALESCE($3, title), anonymous_posting = COALESCE($4, anonymous_posting), op_moderation = COALESCE($5, op_moderation), max_threads = COALESCE($6, max_threads), prune_overflow = COALESCE($7, prune_overflow), reply_cooldown_secs = COALESCE($8, reply_cooldown_secs), reactions = COALESCE($9, reactions), tag_vocabulary = COALESCE($10, tag_vocabulary), allowed_mime = COALESCE($11, allowed_mime), tombstones = COALESCE($12, tombstones), beta = COALESCE($13, beta) WHERE id=$1 RETURNING id, slug, title, created_at, deleted_at, anonymous_posting, op_moderation, max_threads, prune_overflow, reply_cooldown_secs, reactions, tag_vocabulary, allowed_mime, tombstones, beta",
+                id,
+                slug,
+                title,
+                upd.anonymous_posting,
+                upd.op_moderation,
+                upd.max_threads,
+                upd.prune_overflow,
+                upd.reply_cooldown_secs,
+                upd.reactions.as_deref(),
+                upd.tag_vocabulary.as_deref(),
+                upd.allowed_mime.as_deref(),
+                upd.tombstones,
+                upd.beta
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            if limit_changed {
+                enforce_thread_limit(&mut tx, id).await?;
+            }
+            tx.commit().await?;
+            Ok(rec)
+        }
+        async fn get_board(&self, id: Id) -> RepoResult<Board> {
+            let rec = sqlx::query_as!(
+                Board,
+                "SELECT id, slug, title, created_at, deleted_at, anonymous_posting, op_moderation, max_threads, prune_overflow, reply_cooldown_secs, reactions, tag_vocabulary, allowed_mime, tombstones, beta FROM boards WHERE id=$1",
+                id
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            Ok(rec)
+        }
+        async fn get_boards(&self, ids: &[Id]) -> RepoResult<Vec<Board>> {
+            let recs = self
+                .read(|pool| async move {
+                    sqlx::query_as!(
+                        Board,
+                        "SELECT id, slug, title, created_at, deleted_at, anonymous_posting, op_moderation, max_threads, prune_overflow, reply_cooldown_secs, reactions, tag_vocabulary, allowed_mime, tombstones, beta FROM boards WHERE id = ANY($1) ORDER BY id",
+                        ids
+                    )
+                    .fetch_all(&pool)
+                    .await
+                })
+                .await?;
+            Ok(recs)
+        }
+        async fn soft_delete_board(&self, id: Id) -> RepoResult<()> {
+            let res = sqlx::query!(
+                "UPDATE boards SET deleted_at = COALESCE(deleted_at, now()) WHERE id=$1",
+                id
+            )
+            .execute(&self.pool)
+            .await?;
             if res.rows_affected() == 0 {
                 return Err(RepoError::NotFound);
             }
             Ok(())
         }
-        async fn hard_delete_thread(&self, id: Id) -> RepoResult<()> {
-            let res = sqlx::query("DELETE FROM threads WHERE id=$1")
-                .bind(id)
+        async fn restore_board(&self, id: Id) -> RepoResult<()> {
+            let res = sqlx::query!("UPDATE boards SET deleted_at = NULL WHERE id=$1", id)
                 .execute(&self.pool)
-                .await
-                .map_err(|_| RepoError::NotFound)?;
+                .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+        async fn hard_delete_board(&self, id: Id) -> RepoResult<()> {
+            let res = sqlx::query!("DELETE FROM boards WHERE id=$1", id)
+                .execute(&self.pool)
+                .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+        async fn add_board_tester(
+            &self,
+            board_id: Id,
+            subject: &str,
+            added_by: &str,
+        ) -> RepoResult<BoardTester> {
+            sqlx::query_as!(
+                BoardTester,
+                r#"
+                INSERT INTO board_testers (board_id, subject, added_by)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (board_id, subject) DO UPDATE SET board_id = EXCLUDED.board_id
+                RETURNING board_id, subject, added_by, created_at
+                "#,
+                board_id,
+                subject,
+                added_by
+            )
+            .fetch_one(&self.pool)
+            .await
+            .map_err(RepoError::from)
+        }
+        async fn list_board_testers(&self, board_id: Id) -> RepoResult<Vec<BoardTester>> {
+            sqlx::query_as!(
+                BoardTester,
+                "SELECT board_id, subject, added_by, created_at FROM board_testers WHERE board_id=$1 ORDER BY created_at, subject",
+                board_id
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(RepoError::from)
+        }
+        async fn remove_board_tester(&self, board_id: Id, subject: &str) -> RepoResult<()> {
+            let res = sqlx::query!(
+                "DELETE FROM board_testers WHERE board_id=$1 AND subject=$2",
+                board_id,
+                subject
+            )
+            .execute(&self.pool)
+            .await?;
             if res.rows_affected() == 0 {
                 return Err(RepoError::NotFound);
             }
             Ok(())
         }
+        async fn tester_board_ids(&self, subject: &str) -> RepoResult<Vec<Id>> {
+            Ok(sqlx::query_scalar!(
+                "SELECT board_id FROM board_testers WHERE subject=$1",
+                subject
+            )
+            .fetch_all(&self.pool)
+            .await?)
+        }
+        async fn board_for_old_slug(&self, slug: &str) -> RepoResult<Option<Id>> {
+            Ok(
+                sqlx::query_scalar!("SELECT board_id FROM board_slugs WHERE slug=$1", slug)
+                    .fetch_optional(&self.pool)
+                    .await?,
+            )
+        }
     }
 
     #[async_trait]
-    impl ReplyRepo for PgRepo {
-        async fn list_replies(
+    impl ThreadRepo for PgRepo {
+        async fn list_threads(
             &self,
-            thread_id: Id,
+            board_id: Id,
             include_deleted: bool,
-        ) -> RepoResult<Vec<Reply>> {
-            let base = r#"
-                SELECT r.id, r.thread_id, r.content, img.hash as image_hash, img.mime as mime,
-                    r.author_name, r.tripcode, r.created_at, r.deleted_at, r.created_by
-                FROM replies r
+            order: ThreadOrder,
+        ) -> RepoResult<Vec<Thread>> {
+            let recs = self
+                .read(|pool| async move {
+                    sqlx::query_as!(
+                        Thread,
+                        r#"
+          SELECT t.id, t.board_id, t.subject, t.body, t.created_at, t.bump_time, t.created_by,
+              NULL::jsonb as "author?: sqlx::types::Json<AuthorProfile>",
+              img.hash as "image_hash?", img.mime as "mime?", img.size_bytes as "image_size?", t.author_name, t.tripcode, t.deleted_at, t.closed_at, t.archived_at, t.pinned_reply_id, t.tags,
+              t.reply_count, t.image_count, t.slug
+                FROM threads t
+                LEFT JOIN LATERAL (
+                   SELECT i.hash, i.mime, i.size_bytes FROM images i
+                   WHERE i.thread_id = t.id
+                   ORDER BY i.id ASC LIMIT 1
+                ) img ON TRUE
+                WHERE t.board_id = $1 AND t.archived_at IS NULL AND ($2 OR t.deleted_at IS NULL)
+                ORDER BY
+                    CASE WHEN $3 = 'bump_time' AND $4 THEN t.bump_time END ASC,
+                    CASE WHEN $3 = 'bump_time' AND NOT $4 THEN t.bump_time END DESC,
+                    CASE WHEN $3 = 'created_at' AND $4 THEN t.created_at END ASC,
+                    CASE WHEN $3 = 'created_at' AND NOT $4 THEN t.created_at END DESC,
+                    CASE WHEN $3 = 'reply_count' AND $4 THEN t.reply_count END ASC,
+                    CASE WHEN $3 = 'reply_count' AND NOT $4 THEN t.reply_count END DESC,
+                    CASE WHEN $4 THEN t.id END ASC,
+                    t.id DESC
+            "#,
+                        board_id,
+                        include_deleted,
+                        order.sort.as_str(),
+                        order.order.is_asc()
+                    )
+                    .fetch_all(&pool)
+                    .await
+                })
+                .await?;
+            self.with_authors(recs).await
+        }
+        fn stream_threads(
+            &self,
+            board_id: Id,
+            include_deleted: bool,
+            order: ThreadOrder,
+        ) -> RowStream<Thread> {
+            let pool = self.stream_pool();
+            row_stream(move |tx| async move {
+                let rows = sqlx::query_as!(
+                    Thread,
+                    r#"
+          SELECT t.id, t.board_id, t.subject, t.body, t.created_at, t.bump_time, t.created_by,
+              NULL::jsonb as "author?: sqlx::types::Json<AuthorProfile>",
+              img.hash as "image_hash?", img.mime as "mime?", img.size_bytes as "image_size?", t.author_name, t.tripcode, t.deleted_at, t.closed_at, t.archived_at, t.pinned_reply_id, t.tags,
+              t.reply_count, t.image_count, t.slug
+                FROM threads t
                 LEFT JOIN LATERAL (
-                   SELECT i.hash, i.mime FROM images i WHERE i.reply_id = r.id ORDER BY i.id ASC LIMIT 1
+                   SELECT i.hash, i.mime, i.size_bytes FROM images i
+                   WHERE i.thread_id = t.id
+                   ORDER BY i.id ASC LIMIT 1
                 ) img ON TRUE
-                WHERE r.thread_id = $1
-            "#;
-            let sql = if include_deleted {
-                format!("{base} ORDER BY r.created_at ASC")
-            } else {
-                format!("{base} AND r.deleted_at IS NULL ORDER BY r.created_at ASC")
-            };
-            let recs = sqlx::query_as::<_, Reply>(&sql)
-                .bind(thread_id)
-                .fetch_all(&self.pool)
-                .await
-                .map_err(|_| RepoError::NotFound)?;
-            Ok(recs)
+                WHERE t.board_id = $1 AND t.archived_at IS NULL AND ($2 OR t.deleted_at IS NULL)
+                ORDER BY
+                    CASE WHEN $3 = 'bump_time' AND $4 THEN t.bump_time END ASC,
+                    CASE WHEN $3 = 'bump_time' AND NOT $4 THEN t.bump_time END DESC,
+                    CASE WHEN $3 = 'created_at' AND $4 THEN t.created_at END ASC,
+                    CASE WHEN $3 = 'created_at' AND NOT $4 THEN t.created_at END DESC,
+                    CASE WHEN $3 = 'reply_count' AND $4 THEN t.reply_count END ASC,
+                    CASE WHEN $3 = 'reply_count' AND NOT $4 THEN t.reply_count END DESC,
+                    CASE WHEN $4 THEN t.id END ASC,
+                    t.id DESC
+            "#,
+                    board_id,
+                    include_deleted,
+                    order.sort.as_str(),
+                    order.order.is_asc()
+                )
+                .fetch(&pool);
+                forward_rows(pool.clone(), rows, tx).await;
+            })
         }
-        async fn create_reply(
+        async fn create_thread(
             &self,
-            new: NewReply,
+            new: NewThread,
             created_by: Value,
             public_identity: PublicIdentity,
-        ) -> RepoResult<Reply> {
-            let mut tx = self.pool.begin().await.map_err(|_| RepoError::Conflict)?;
-
-            let rec = sqlx::query(
-                "INSERT INTO replies (thread_id, content, created_by, author_name, tripcode) VALUES ($1,$2,$3,$4,$5) RETURNING id"
+        ) -> RepoResult<Thread> {
+            let mut tx = self.pool.begin().await?;
+            let thread_id = insert_thread(&mut tx, &new, &created_by, &public_identity).await?;
+            tx.commit().await?;
+            fetch_thread(&self.pool, thread_id).await
+        }
+        async fn get_thread(&self, id: Id) -> RepoResult<Thread> {
+            fetch_thread(&self.pool, id).await
+        }
+        async fn list_archived_threads(&self, board_id: Id, limit: i64) -> RepoResult<Vec<Thread>> {
+            let recs = self
+                .read(|pool| async move {
+                    sqlx::query_as!(
+                        Thread,
+                        r#"
+          SELECT t.id, t.board_id, t.subject, t.body, t.created_at, t.bump_time, t.created_by,
+              NULL::jsonb as "author?: sqlx::types::Json<AuthorProfile>",
+              img.hash as "image_hash?", img.mime as "mime?", img.size_bytes as "image_size?", t.author_name, t.tripcode, t.deleted_at, t.closed_at, t.archived_at, t.pinned_reply_id, t.tags,
+              t.reply_count, t.image_count, t.slug
+                FROM threads t
+                LEFT JOIN LATERAL (
+                   SELECT i.hash, i.mime, i.size_bytes FROM images i WHERE i.thread_id = t.id ORDER BY i.id ASC LIMIT 1
+                ) img ON TRUE
+                WHERE t.board_id = $1 AND t.archived_at IS NOT NULL AND t.deleted_at IS NULL
+                ORDER BY t.archived_at DESC, t.id DESC
+                LIMIT $2
+            "#,
+                        board_id,
+                        limit
+                    )
+                    .fetch_all(&pool)
+                    .await
+                })
+                .await?;
+            self.with_authors(recs).await
+        }
+        async fn list_tagged_threads(
+            &self,
+            board_id: Id,
+            tag: &str,
+            include_deleted: bool,
+            order: ThreadOrder,
+        ) -> RepoResult<Vec<Thread>> {
+            let recs = self
+                .read(|pool| async move {
+                    sqlx::query_as!(
+                        Thread,
+                        r#"
+          SELECT t.id, t.board_id, t.subject, t.body, t.created_at, t.bump_time, t.created_by,
+              NULL::jsonb as "author?: sqlx::types::Json<AuthorProfile>",
+              img.hash as "image_hash?", img.mime as "mime?", img.size_bytes as "image_size?", t.author_name, t.tripcode, t.deleted_at, t.closed_at, t.archived_at, t.pinned_reply_id, t.tags,
+              t.reply_count, t.image_count, t.slug
+                FROM threads t
+                LEFT JOIN LATERAL (
+                   SELECT i.hash, i.mime, i.size_bytes FROM images i WHERE i.thread_id = t.id ORDER BY i.id ASC LIMIT 1
+                ) img ON TRUE
+                WHERE t.board_id = $1 AND t.tags @> ARRAY[$2] AND t.archived_at IS NULL
+                    AND ($3 OR t.deleted_at IS NULL)
+                ORDER BY
+                    CASE WHEN $4 = 'bump_time' AND $5 THEN t.bump_time END ASC,
+                    CASE WHEN $4 = 'bump_time' AND NOT $5 THEN t.bump_time END DESC,
+                    CASE WHEN $4 = 'created_at' AND $5 THEN t.created_at END ASC,
+                    CASE WHEN $4 = 'created_at' AND NOT $5 THEN t.created_at END DESC,
+                    CASE WHEN $4 = 'reply_count' AND $5 THEN t.reply_count END ASC,
+                    CASE WHEN $4 = 'reply_count' AND NOT $5 THEN t.reply_count END DESC,
+                    CASE WHEN $5 THEN t.id END ASC,
+                    t.id DESC
+            "#,
+                        board_id,
+                        tag,
+                        include_deleted,
+                        order.sort.as_str(),
+                        order.order.is_asc()
+                    )
+                    .fetch_all(&pool)
+                    .await
+                })
+                .await?;
+            self.with_authors(recs).await
+        }
+        async fn tag_counts(&self, board_id: Id) -> RepoResult<Vec<TagCount>> {
+            let recs = self
+                .read(|pool| async move {
+                    sqlx::query_as!(
+                        TagCount,
+                        r#"SELECT tag as "tag!", count(*) as "count!"
+                           FROM threads t, unnest(t.tags) AS tag
+                           WHERE t.board_id = $1 AND t.deleted_at IS NULL AND t.archived_at IS NULL
+                           GROUP BY tag
+                           ORDER BY count(*) DESC, tag"#,
+                        board_id
+                    )
+                    .fetch_all(&pool)
+                    .await
+                })
+                .await?;
+            Ok(recs)
+        }
+        async fn get_threads(&self, ids: &[Id]) -> RepoResult<Vec<Thread>> {
+            let recs = self
+                .read(|pool| async move {
+                    sqlx::query_as!(
+                        Thread,
+                        r#"
+          SELECT t.id, t.board_id, t.subject, t.body, t.created_at, t.bump_time, t.created_by,
+              NULL::jsonb as "author?: sqlx::types::Json<AuthorProfile>",
+              img.hash as "image_hash?", img.mime as "mime?", img.size_bytes as "image_size?", t.author_name, t.tripcode, t.deleted_at, t.closed_at, t.archived_at, t.pinned_reply_id, t.tags,
+              t.reply_count, t.image_count, t.slug
+                FROM threads t
+                LEFT JOIN LATERAL (
+                   SELECT i.hash, i.mime, i.size_bytes FROM images i WHERE i.thread_id = t.id ORDER BY i.id ASC LIMIT 1
+                ) img ON TRUE
+                WHERE t.id = ANY($1)
+                ORDER BY t.id
+            "#,
+                        ids
+                    )
+                    .fetch_all(&pool)
+                    .await
+                })
+                .await?;
+            self.with_authors(recs).await
+        }
+        async fn soft_delete_thread(&self, id: Id) -> RepoResult<()> {
+            let mut tx = self.pool.begin().await?;
+            soft_delete_thread_in(&mut tx, id).await?;
+            tx.commit().await?;
+            Ok(())
+        }
+        async fn restore_thread(&self, id: Id) -> RepoResult<()> {
+            let res = sqlx::query!("UPDATE threads SET deleted_at = NULL WHERE id=$1", id)
+                .execute(&self.pool)
+                .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+        async fn hard_delete_thread(&self, id: Id) -> RepoResult<()> {
+            let mut tx = self.pool.begin().await?;
+            let res = sqlx::query!("DELETE FROM threads WHERE id=$1", id)
+                .execute(&mut *tx)
+                .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            record_event(
+                &mut tx,
+                events::THREAD_DELETED,
+                serde_json::json!({ "thread_id": id, "hard": true }),
+            )
+            .await?;
+            tx.commit().await?;
+            Ok(())
+        }
+        async fn thread_delete_password(&self, id: Id) -> RepoResult<Option<String>> {
+            Ok(
+                sqlx::query_scalar!("SELECT delete_password_hash FROM threads WHERE id=$1", id)
+                    .fetch_one(&self.pool)
+                    .await?,
+            )
+        }
+        async fn thread_had_slug(&self, id: Id, slug: &str) -> RepoResult<bool> {
+            Ok(sqlx::query_scalar!(
+                r#"SELECT EXISTS(SELECT 1 FROM thread_slugs WHERE thread_id=$1 AND slug=$2) as "exists!""#,
+                id,
+                slug
+            )
+            .fetch_one(&self.pool)
+            .await?)
+        }
+    }
+
+    #[async_trait]
+    impl ReplyRepo for PgRepo {
+        async fn list_replies(
+            &self,
+            thread_id: Id,
+            include_deleted: bool,
+            order: SortOrder,
+        ) -> RepoResult<Vec<Reply>> {
+            let recs = self
+                .read(|pool| async move {
+                    sqlx::query_as!(
+                        Reply,
+                        r#"
+                SELECT r.id, r.thread_id, r.content, img.hash as "image_hash?", img.mime as "mime?", img.size_bytes as "image_size?",
+                    r.author_name, r.tripcode, r.created_at, r.deleted_at, r.created_by,
+              NULL::jsonb as "author?: sqlx::types::Json<AuthorProfile>",
+              reaction_counts(r.id) as "reactions!: sqlx::types::Json<Vec<ReactionCount>>",
+              removal_reason(r.id, r.deleted_at) as "removal_reason?"
+                FROM replies r
+                LEFT JOIN LATERAL (
+                   SELECT i.hash, i.mime, i.size_bytes FROM images i WHERE i.reply_id = r.id ORDER BY i.id ASC LIMIT 1
+                ) img ON TRUE
+                WHERE r.thread_id = $1 AND ($2 OR r.deleted_at IS NULL)
+                ORDER BY
+                    CASE WHEN $3 THEN r.created_at END ASC,
+                    CASE WHEN $3 THEN r.id END ASC,
+                    r.created_at DESC,
+                    r.id DESC
+            "#,
+                        thread_id,
+                        include_deleted,
+                        order.is_asc()
+                    )
+                    .fetch_all(&pool)
+                    .await
+                })
+                .await?;
+            self.with_authors(recs).await
+        }
+        fn stream_replies(
+            &self,
+            thread_id: Id,
+            include_deleted: bool,
+            order: SortOrder,
+        ) -> RowStream<Reply> {
+            let pool = self.stream_pool();
+            row_stream(move |tx| async move {
+                let rows = sqlx::query_as!(
+                    Reply,
+                    r#"
+                SELECT r.id, r.thread_id, r.content, img.hash as "image_hash?", img.mime as "mime?", img.size_bytes as "image_size?",
+                    r.author_name, r.tripcode, r.created_at, r.deleted_at, r.created_by,
+              NULL::jsonb as "author?: sqlx::types::Json<AuthorProfile>",
+              reaction_counts(r.id) as "reactions!: sqlx::types::Json<Vec<ReactionCount>>",
+              removal_reason(r.id, r.deleted_at) as "removal_reason?"
+                FROM replies r
+                LEFT JOIN LATERAL (
+                   SELECT i.hash, i.mime, i.size_bytes FROM images i WHERE i.reply_id = r.id ORDER BY i.id ASC LIMIT 1
+                ) img ON TRUE
+                WHERE r.thread_id = $1 AND ($2 OR r.deleted_at IS NULL)
+                ORDER BY
+                    CASE WHEN $3 THEN r.created_at END ASC,
+                    CASE WHEN $3 THEN r.id END ASC,
+                    r.created_at DESC,
+                    r.id DESC
+            "#,
+                    thread_id,
+                    include_deleted,
+                    order.is_asc()
+                )
+                .fetch(&pool);
+                forward_rows(pool.clone(), rows, tx).await;
+            })
+        }
+        async fn list_replies_for_threads(
+            &self,
+            thread_ids: &[Id],
+            include_deleted: bool,
+        ) -> RepoResult<Vec<Reply>> {
+            let recs = self
+                .read(|pool| async move {
+                    sqlx::query_as!(
+                        Reply,
+                        r#"
+                SELECT r.id, r.thread_id, r.content, img.hash as "image_hash?", img.mime as "mime?", img.size_bytes as "image_size?",
+                    r.author_name, r.tripcode, r.created_at, r.deleted_at, r.created_by,
+              NULL::jsonb as "author?: sqlx::types::Json<AuthorProfile>",
+              reaction_counts(r.id) as "reactions!: sqlx::types::Json<Vec<ReactionCount>>",
+              removal_reason(r.id, r.deleted_at) as "removal_reason?"
+                FROM replies r
+                LEFT JOIN LATERAL (
+                   SELECT i.hash, i.mime, i.size_bytes FROM images i WHERE i.reply_id = r.id ORDER BY i.id ASC LIMIT 1
+                ) img ON TRUE
+                WHERE r.thread_id = ANY($1) AND ($2 OR r.deleted_at IS NULL)
+                ORDER BY r.thread_id, r.created_at ASC
+            "#,
+                        thread_ids,
+                        include_deleted
+                    )
+                    .fetch_all(&pool)
+                    .await
+                })
+                .await?;
+            self.with_authors(recs).await
+        }
+        async fn list_first_replies(
+            &self,
+            thread_ids: &[Id],
+            per_thread: i64,
+        ) -> RepoResult<Vec<Reply>> {
+            let recs = self
+                .read(|pool| async move {
+                    sqlx::query_as!(
+                        Reply,
+                        r#"
+                SELECT r.id as "id!", r.thread_id as "thread_id!", r.content as "content!",
+                    img.hash as "image_hash?", img.mime as "mime?", img.size_bytes as "image_size?", r.author_name, r.tripcode,
+                    r.created_at as "created_at!", r.deleted_at, r.created_by as "created_by!",
+                    NULL::jsonb as "author?: sqlx::types::Json<AuthorProfile>",
+              reaction_counts(r.id) as "reactions!: sqlx::types::Json<Vec<ReactionCount>>",
+              removal_reason(r.id, r.deleted_at) as "removal_reason?"
+                FROM (
+                    SELECT *, ROW_NUMBER() OVER (PARTITION BY thread_id ORDER BY created_at, id) AS n
+                    FROM replies
+                    WHERE thread_id = ANY($1) AND deleted_at IS NULL
+                ) r
+                LEFT JOIN LATERAL (
+                   SELECT i.hash, i.mime, i.size_bytes FROM images i WHERE i.reply_id = r.id ORDER BY i.id ASC LIMIT 1
+                ) img ON TRUE
+                WHERE r.n <= $2
+                ORDER BY r.thread_id, r.n
+            "#,
+                        thread_ids,
+                        per_thread
+                    )
+                    .fetch_all(&pool)
+                    .await
+                })
+                .await?;
+            self.with_authors(recs).await
+        }
+        async fn create_reply(
+            &self,
+            new: NewReply,
+            created_by: Value,
+            public_identity: PublicIdentity,
+        ) -> RepoResult<Reply> {
+            let mut tx = self.pool.begin().await?;
+            let reply_id = insert_reply(&mut tx, &new, &created_by, &public_identity).await?;
+            tx.commit().await?;
+            fetch_reply(&self.pool, reply_id).await
+        }
+        async fn last_reply_at(
+            &self,
+            thread_id: Id,
+            subject: &str,
+        ) -> RepoResult<Option<DateTime<Utc>>> {
+            // Primary, not a replica: a lagging read would let a flood through.
+            Ok(sqlx::query_scalar!(
+                "SELECT max(created_at) FROM replies WHERE thread_id=$1 AND created_by->>'subject' = $2",
+                thread_id,
+                subject
+            )
+            .fetch_one(&self.pool)
+            .await?)
+        }
+        async fn soft_delete_reply(&self, id: Id) -> RepoResult<()> {
+            let mut tx = self.pool.begin().await?;
+            soft_delete_reply_in(&mut tx, id).await?;
+            tx.commit().await?;
+            Ok(())
+        }
+        async fn restore_reply(&self, id: Id) -> RepoResult<()> {
+            let res = sqlx::query!("UPDATE replies SET deleted_at = NULL WHERE id=$1", id)
+                .execute(&self.pool)
+                .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+        async fn hard_delete_reply(&self, id: Id) -> RepoResult<()> {
+            // Need to also detach reply's image (image row cascades ON DELETE, but we want to allow external store cleanup)
+            let mut tx = self.pool.begin().await?;
+            let res = sqlx::query!("DELETE FROM replies WHERE id=$1", id)
+                .execute(&mut *tx)
+                .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            record_event(
+                &mut tx,
+                events::REPLY_DELETED,
+                serde_json::json!({ "reply_id": id, "hard": true }),
+            )
+            .await?;
+            tx.commit().await?;
+            Ok(())
+        }
+        async fn get_reply(&self, id: Id) -> RepoResult<Reply> {
+            fetch_reply(&self.pool, id).await
+        }
+        async fn reply_delete_password(&self, id: Id) -> RepoResult<Option<String>> {
+            Ok(
+                sqlx::query_scalar!("SELECT delete_password_hash FROM replies WHERE id=$1", id)
+                    .fetch_one(&self.pool)
+                    .await?,
+            )
+        }
+    }
+
+    #[async_trait]
+    impl RoleRepo for PgRepo {
+        async fn get_subject_role(&self, subject: &str) -> Option<AuthRole> {
+            // A lapsed role counts as `user` until the expiry runner demotes it.
+            if let Ok(rec) = sqlx::query!(
+                r#"SELECT CASE WHEN expires_at <= now() THEN 'user' ELSE role END as "role!" FROM user_roles WHERE subject=$1"#,
+                subject
+            )
+            .fetch_one(&self.pool)
+            .await
+            {
+                return match rec.role.as_str() {
+                    "admin" => Some(AuthRole::Admin),
+                    "moderator" => Some(AuthRole::Moderator),
+                    "user" => Some(AuthRole::User),
+                    _ => None,
+                };
+            }
+            None
+        }
+        async fn set_subject_role(&self, subject: &str, role: AuthRole) -> RepoResult<()> {
+            self.upsert_subject_role(subject, role, None).await
+        }
+        async fn set_subject_role_until(
+            &self,
+            subject: &str,
+            role: AuthRole,
+            expires_at: DateTime<Utc>,
+        ) -> RepoResult<()> {
+            self.upsert_subject_role(subject, role, Some(expires_at))
+                .await
+        }
+        async fn list_roles(&self) -> RepoResult<Vec<(String, AuthRole, Option<DateTime<Utc>>)>> {
+            let rows =
+                sqlx::query!("SELECT subject, role, expires_at FROM user_roles ORDER BY subject")
+                    .fetch_all(&self.pool)
+                    .await?;
+            let mut out = Vec::with_capacity(rows.len());
+            for r in rows {
+                if let Some(role) = match r.role.as_str() {
+                    "admin" => Some(AuthRole::Admin),
+                    "moderator" => Some(AuthRole::Moderator),
+                    "user" => Some(AuthRole::User),
+                    _ => None,
+                } {
+                    out.push((r.subject, role, r.expires_at));
+                }
+            }
+            Ok(out)
+        }
+        async fn delete_role(&self, subject: &str) -> RepoResult<()> {
+            let mut tx = self.pool.begin().await?;
+            ensure_other_admin(&mut tx, subject).await?;
+            let res = sqlx::query!("DELETE FROM user_roles WHERE subject=$1", subject)
+                .execute(&mut *tx)
+                .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            tx.commit().await?;
+            Ok(())
+        }
+        async fn demote_expired_roles(&self) -> RepoResult<Vec<RoleChange>> {
+            Ok(sqlx::query_as!(
+                RoleChange,
+                r#"
+                WITH demoted AS (
+                    UPDATE user_roles u SET role = 'user', expires_at = NULL, updated_at = now()
+                    FROM user_roles old
+                    WHERE old.subject = u.subject AND u.expires_at <= now()
+                    RETURNING u.subject, old.role
+                )
+                INSERT INTO role_changes (subject, from_role, to_role, reason)
+                SELECT subject, role, 'user', 'expired' FROM demoted
+                RETURNING id, subject, changed_by, from_role, to_role, expires_at, reason, created_at
+                "#
+            )
+            .fetch_all(&self.pool)
+            .await?)
+        }
+        async fn record_role_change(&self, change: NewRoleChange) -> RepoResult<RoleChange> {
+            Ok(sqlx::query_as!(
+                RoleChange,
+                r#"
+                INSERT INTO role_changes (subject, changed_by, from_role, to_role, expires_at, reason)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING id, subject, changed_by, from_role, to_role, expires_at, reason, created_at
+                "#,
+                change.subject,
+                change.changed_by,
+                change.from_role,
+                change.to_role,
+                change.expires_at,
+                change.reason
+            )
+            .fetch_one(&self.pool)
+            .await?)
+        }
+        async fn record_impersonation(&self, entry: NewImpersonationEntry) -> RepoResult<()> {
+            let (actor, subject, kind, method, route, status, reason, expires_at) = match entry {
+                NewImpersonationEntry::Token {
+                    actor,
+                    subject,
+                    reason,
+                    expires_at,
+                } => (
+                    actor,
+                    subject,
+                    "token",
+                    None,
+                    None,
+                    None,
+                    Some(reason),
+                    Some(expires_at),
+                ),
+                NewImpersonationEntry::Request {
+                    actor,
+                    subject,
+                    method,
+                    route,
+                    status,
+                } => (
+                    actor,
+                    subject,
+                    "request",
+                    Some(method),
+                    Some(route),
+                    Some(status as i32),
+                    None,
+                    None,
+                ),
+            };
+            sqlx::query!(
+                r#"
+                INSERT INTO impersonation_log
+                    (actor, subject, kind, method, route, status, reason, expires_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                "#,
+                actor,
+                subject,
+                kind,
+                method,
+                route,
+                status,
+                reason,
+                expires_at
+            )
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        }
+        async fn list_impersonations(
+            &self,
+            subject: Option<&str>,
+            limit: i64,
+        ) -> RepoResult<Vec<ImpersonationEntry>> {
+            Ok(sqlx::query_as!(
+                ImpersonationEntry,
+                r#"
+                SELECT id, actor, subject, kind, method, route, status, reason, expires_at, created_at
+                FROM impersonation_log
+                WHERE $1::text IS NULL OR subject = $1
+                ORDER BY id DESC
+                LIMIT $2
+                "#,
+                subject,
+                limit
+            )
+            .fetch_all(&self.pool)
+            .await?)
+        }
+        async fn role_history(&self, subject: &str, limit: i64) -> RepoResult<Vec<RoleChange>> {
+            Ok(sqlx::query_as!(
+                RoleChange,
+                r#"
+                SELECT id, subject, changed_by, from_role, to_role, expires_at, reason, created_at
+                FROM role_changes
+                WHERE subject = $1
+                ORDER BY id DESC
+                LIMIT $2
+                "#,
+                subject,
+                limit
+            )
+            .fetch_all(&self.pool)
+            .await?)
+        }
+    } // end impl RoleRepo
+
+    #[async_trait]
+    impl ImageRepo for PgRepo {
+        async fn list_board_image_hashes(&self, board_id: Id) -> RepoResult<Vec<String>> {
+            sqlx::query_scalar!(
+                r#"
+                SELECT DISTINCT i.hash
+                FROM images i
+                LEFT JOIN threads direct_thread ON direct_thread.id = i.thread_id
+                LEFT JOIN replies r ON r.id = i.reply_id
+                LEFT JOIN threads reply_thread ON reply_thread.id = r.thread_id
+                WHERE direct_thread.board_id = $1 OR reply_thread.board_id = $1
+                "#,
+                board_id
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn list_thread_image_hashes(&self, thread_id: Id) -> RepoResult<Vec<String>> {
+            sqlx::query_scalar!(
+                r#"
+                SELECT DISTINCT i.hash
+                FROM images i
+                LEFT JOIN replies r ON r.id = i.reply_id
+                WHERE i.thread_id = $1 OR r.thread_id = $1
+                "#,
+                thread_id
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn is_image_referenced(&self, hash: &str) -> RepoResult<bool> {
+            sqlx::query_scalar!(
+                r#"SELECT EXISTS(SELECT 1 FROM images WHERE hash=$1) as "exists!""#,
+                hash
+            )
+            .fetch_one(&self.pool)
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn image_references(
+            &self,
+            hash: &str,
+            limit: i64,
+        ) -> RepoResult<Vec<ImageReference>> {
+            self.read(|pool| async move {
+                sqlx::query_as!(
+                    ImageReference,
+                    r#"
+                    SELECT t.board_id, t.id as thread_id, r.id as "reply_id?"
+                    FROM images i
+                    LEFT JOIN replies r ON r.id = i.reply_id
+                    JOIN threads t ON t.id = COALESCE(i.thread_id, r.thread_id)
+                    JOIN boards b ON b.id = t.board_id
+                    WHERE i.hash = $1
+                      AND t.deleted_at IS NULL AND r.deleted_at IS NULL AND b.deleted_at IS NULL
+                    ORDER BY i.id DESC
+                    LIMIT $2
+                    "#,
+                    hash,
+                    limit
+                )
+                .fetch_all(&pool)
+                .await
+            })
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn record_upload(&self, upload: &NewUpload) -> RepoResult<()> {
+            sqlx::query!(
+                r#"
+                INSERT INTO uploads (hash, uploaded_by, mime, size_bytes, file_name, flag_reason, shareable)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (hash, uploaded_by) DO UPDATE SET shareable = uploads.shareable OR EXCLUDED.shareable
+                "#,
+                upload.hash,
+                upload.uploaded_by,
+                upload.mime,
+                upload.size_bytes,
+                upload.file_name,
+                upload.flag_reason,
+                upload.shareable
+            )
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        }
+
+        async fn upload_usage(
+            &self,
+            subject: &str,
+            since: DateTime<Utc>,
+        ) -> RepoResult<UploadUsage> {
+            Ok(sqlx::query_as!(
+                UploadUsage,
+                r#"
+                SELECT COALESCE(SUM(size_bytes), 0)::BIGINT as "bytes!", MIN(created_at) as oldest_at
+                FROM uploads WHERE uploaded_by=$1 AND created_at > $2
+                "#,
+                subject,
+                since
+            )
+            .fetch_one(&self.pool)
+            .await?)
+        }
+
+        async fn image_uploads(&self, hash: &str) -> RepoResult<Vec<UploadRecord>> {
+            Ok(sqlx::query_as!(
+                UploadRecord,
+                r#"
+                SELECT hash, uploaded_by, mime, size_bytes, file_name, flag_reason, shareable, created_at
+                FROM uploads WHERE hash = $1
+                ORDER BY created_at, uploaded_by
+                "#,
+                hash
+            )
+            .fetch_all(&self.pool)
+            .await?)
+        }
+
+        async fn image_attachments(&self, hash: &str) -> RepoResult<Vec<AttachedPost>> {
+            Ok(sqlx::query_as!(
+                AttachedPost,
+                r#"
+                SELECT t.board_id, t.id as thread_id, r.id as "reply_id?",
+                       COALESCE(r.deleted_at, t.deleted_at) as deleted_at
+                FROM images i
+                LEFT JOIN replies r ON r.id = i.reply_id
+                JOIN threads t ON t.id = COALESCE(i.thread_id, r.thread_id)
+                WHERE i.hash = $1
+                ORDER BY i.id DESC
+                "#,
+                hash
+            )
+            .fetch_all(&self.pool)
+            .await?)
+        }
+
+        async fn list_uploads_by(
+            &self,
+            uploader: &str,
+            limit: i64,
+        ) -> RepoResult<Vec<UploadRecord>> {
+            self.read(|pool| async move {
+                sqlx::query_as!(
+                    UploadRecord,
+                    r#"
+                    SELECT hash, uploaded_by, mime, size_bytes, file_name, flag_reason, shareable, created_at
+                    FROM uploads WHERE uploaded_by = $1
+                    ORDER BY created_at DESC, hash
+                    LIMIT $2
+                    "#,
+                    uploader,
+                    limit
+                )
+                .fetch_all(&pool)
+                .await
+            })
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn take_down_image(&self, hash: &str) -> RepoResult<u64> {
+            let mut tx = self.pool.begin().await?;
+            ensure_image_not_held(&mut tx, hash).await?;
+            let threads = sqlx::query_scalar!(
+                r#"SELECT DISTINCT t.id FROM images i JOIN threads t ON t.id = i.thread_id
+                   WHERE i.hash = $1 AND t.deleted_at IS NULL"#,
+                hash
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+            let replies = sqlx::query_scalar!(
+                r#"SELECT DISTINCT r.id FROM images i JOIN replies r ON r.id = i.reply_id
+                   WHERE i.hash = $1 AND r.deleted_at IS NULL"#,
+                hash
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+            for id in &threads {
+                soft_delete_thread_in(&mut tx, *id).await?;
+            }
+            for id in &replies {
+                soft_delete_reply_in(&mut tx, *id).await?;
+            }
+            sqlx::query!("DELETE FROM images WHERE hash = $1", hash)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok((threads.len() + replies.len()) as u64)
+        }
+
+        async fn quarantine_image(&self, hash: &str, reason: &str, source: &str) -> RepoResult<()> {
+            sqlx::query!(
+                "INSERT INTO image_quarantine (hash, reason, source) VALUES ($1, $2, $3) ON CONFLICT (hash) DO NOTHING",
+                hash,
+                reason,
+                source
+            )
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        }
+
+        async fn get_quarantined_image(&self, hash: &str) -> RepoResult<Option<QuarantinedImage>> {
+            // Read from the primary: a blob quarantined a moment ago must not be served.
+            Ok(sqlx::query_as!(
+                QuarantinedImage,
+                r#"
+                SELECT q.hash, q.reason, q.source, q.created_at, q.destroyed_at,
+                       u.mime as "mime?", u.size_bytes as "size_bytes?",
+                       COALESCE(u.uploaded_by, '{}') as "uploaded_by!",
+                       (SELECT count(*) FROM images i WHERE i.hash = q.hash) as "attachments!"
+                FROM image_quarantine q
+                LEFT JOIN LATERAL (
+                    SELECT min(mime) as mime, max(size_bytes) as size_bytes,
+                           array_agg(uploaded_by ORDER BY created_at) as uploaded_by
+                    FROM uploads WHERE hash = q.hash
+                ) u ON true
+                WHERE q.hash = $1
+                "#,
+                hash
+            )
+            .fetch_optional(&self.pool)
+            .await?)
+        }
+
+        async fn list_quarantined_images(&self, limit: i64) -> RepoResult<Vec<QuarantinedImage>> {
+            self.read(|pool| async move {
+                sqlx::query_as!(
+                    QuarantinedImage,
+                    r#"
+                    SELECT q.hash, q.reason, q.source, q.created_at, q.destroyed_at,
+                           u.mime as "mime?", u.size_bytes as "size_bytes?",
+                           COALESCE(u.uploaded_by, '{}') as "uploaded_by!",
+                           (SELECT count(*) FROM images i WHERE i.hash = q.hash) as "attachments!"
+                    FROM image_quarantine q
+                    LEFT JOIN LATERAL (
+                        SELECT min(mime) as mime, max(size_bytes) as size_bytes,
+                               array_agg(uploaded_by ORDER BY created_at) as uploaded_by
+                        FROM uploads WHERE hash = q.hash
+                    ) u ON true
+                    WHERE q.destroyed_at IS NULL
+                    ORDER BY q.created_at, q.hash
+                    LIMIT $1
+                    "#,
+                    limit
+                )
+                .fetch_all(&pool)
+                .await
+            })
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn release_quarantined_image(&self, hash: &str) -> RepoResult<()> {
+            let res = sqlx::query!(
+                "DELETE FROM image_quarantine WHERE hash = $1 AND destroyed_at IS NULL",
+                hash
+            )
+            .execute(&self.pool)
+            .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+
+        async fn destroy_quarantined_image(&self, hash: &str) -> RepoResult<()> {
+            let mut tx = self.pool.begin().await?;
+            ensure_image_not_held(&mut tx, hash).await?;
+            let res = sqlx::query!(
+                "UPDATE image_quarantine SET destroyed_at = now() WHERE hash = $1 AND destroyed_at IS NULL",
+                hash
+            )
+            .execute(&mut *tx)
+            .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            sqlx::query!("DELETE FROM images WHERE hash = $1", hash)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(())
+        }
+
+        async fn blobs_to_archive(&self, limit: i64) -> RepoResult<Vec<String>> {
+            let hashes = sqlx::query_scalar!(
+                r#"SELECT DISTINCT i.hash AS "hash!"
+                   FROM images i
+                   LEFT JOIN replies r ON r.id = i.reply_id
+                   JOIN threads t ON t.id = COALESCE(i.thread_id, r.thread_id)
+                   WHERE t.archived_at IS NOT NULL AND t.deleted_at IS NULL
+                     AND NOT EXISTS (SELECT 1 FROM archived_blobs a WHERE a.hash = i.hash)
+                     AND NOT EXISTS (
+                         SELECT 1 FROM images li
+                         LEFT JOIN replies lr ON lr.id = li.reply_id
+                         JOIN threads lt ON lt.id = COALESCE(li.thread_id, lr.thread_id)
+                         WHERE li.hash = i.hash AND lt.archived_at IS NULL
+                           AND lt.deleted_at IS NULL AND lr.deleted_at IS NULL)
+                   LIMIT $1"#,
+                limit
+            )
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(hashes)
+        }
+
+        async fn blobs_to_restore(&self, limit: i64) -> RepoResult<Vec<String>> {
+            let hashes = sqlx::query_scalar!(
+                r#"SELECT a.hash FROM archived_blobs a
+                   WHERE EXISTS (
+                       SELECT 1 FROM images li
+                       LEFT JOIN replies lr ON lr.id = li.reply_id
+                       JOIN threads lt ON lt.id = COALESCE(li.thread_id, lr.thread_id)
+                       WHERE li.hash = a.hash AND lt.archived_at IS NULL
+                         AND lt.deleted_at IS NULL AND lr.deleted_at IS NULL)
+                   ORDER BY a.archived_at
+                   LIMIT $1"#,
+                limit
+            )
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(hashes)
+        }
+
+        async fn set_blob_archived(&self, hash: &str, archived: bool) -> RepoResult<()> {
+            if archived {
+                sqlx::query!(
+                    "INSERT INTO archived_blobs (hash) VALUES ($1) ON CONFLICT DO NOTHING",
+                    hash
+                )
+                .execute(&self.pool)
+                .await?;
+            } else {
+                sqlx::query!("DELETE FROM archived_blobs WHERE hash = $1", hash)
+                    .execute(&self.pool)
+                    .await?;
+            }
+            Ok(())
+        }
+
+        async fn set_image_thumbnail(&self, hash: &str, thumbnail_hash: &str) -> RepoResult<()> {
+            sqlx::query!(
+                r#"
+                INSERT INTO blob_thumbnails (hash, thumbnail_hash) VALUES ($1, $2)
+                ON CONFLICT (hash) DO UPDATE
+                SET thumbnail_hash = EXCLUDED.thumbnail_hash, created_at = now()
+                "#,
+                hash,
+                thumbnail_hash
+            )
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        }
+
+        async fn image_thumbnail(&self, hash: &str) -> RepoResult<Option<String>> {
+            Ok(sqlx::query_scalar!(
+                "SELECT thumbnail_hash FROM blob_thumbnails WHERE hash = $1",
+                hash
+            )
+            .fetch_optional(&self.pool)
+            .await?)
+        }
+
+        async fn set_archive_listing(
+            &self,
+            hash: &str,
+            listing: &ArchiveListing,
+        ) -> RepoResult<()> {
+            sqlx::query!(
+                r#"
+                INSERT INTO archive_listings (hash, listing) VALUES ($1, $2)
+                ON CONFLICT (hash) DO UPDATE SET listing = EXCLUDED.listing
+                "#,
+                hash,
+                sqlx::types::Json(listing) as _
+            )
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        }
+
+        async fn archive_listing(&self, hash: &str) -> RepoResult<Option<ArchiveListing>> {
+            Ok(sqlx::query_scalar!(
+                r#"SELECT listing as "listing: sqlx::types::Json<ArchiveListing>" FROM archive_listings WHERE hash = $1"#,
+                hash
+            )
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|listing| listing.0))
+        }
+
+        async fn set_text_excerpt(&self, hash: &str, excerpt: &TextExcerpt) -> RepoResult<()> {
+            sqlx::query!(
+                r#"
+                INSERT INTO text_excerpts (hash, language, excerpt, line_count, truncated)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (hash) DO NOTHING
+                "#,
+                hash,
+                excerpt.language,
+                excerpt.excerpt,
+                excerpt.line_count,
+                excerpt.truncated
+            )
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        }
+
+        async fn text_excerpt(&self, hash: &str) -> RepoResult<Option<TextExcerpt>> {
+            Ok(sqlx::query_as!(
+                TextExcerpt,
+                "SELECT language, excerpt, line_count, truncated FROM text_excerpts WHERE hash = $1",
+                hash
+            )
+            .fetch_optional(&self.pool)
+            .await?)
+        }
+    }
+
+    #[async_trait]
+    impl BanRepo for PgRepo {
+        async fn active_subject_ban(&self, subject: &str) -> RepoResult<Option<BanNotice>> {
+            let row = sqlx::query!(
+                "SELECT public_reason, expires_at FROM subject_bans WHERE subject=$1 AND (expires_at IS NULL OR expires_at > now())",
+                subject
+            )
+            .fetch_optional(&self.pool)
+            .await?;
+            Ok(row.map(|r| BanNotice {
+                scope: BanScope::Subject,
+                reason: r.public_reason,
+                expires_at: r.expires_at,
+            }))
+        }
+
+        async fn create_subject_ban(
+            &self,
+            new: NewSubjectBan,
+            banned_by: &str,
+        ) -> RepoResult<SubjectBan> {
+            sqlx::query_as!(
+                SubjectBan,
+                r#"
+                INSERT INTO subject_bans (subject, reason, public_reason, banned_by, expires_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (subject) DO UPDATE SET
+                    reason = EXCLUDED.reason,
+                    public_reason = EXCLUDED.public_reason,
+                    banned_by = EXCLUDED.banned_by,
+                    created_at = now(),
+                    expires_at = EXCLUDED.expires_at
+                RETURNING subject, reason, public_reason, banned_by, created_at, expires_at
+                "#,
+                new.subject,
+                new.reason,
+                new.public_reason,
+                banned_by,
+                new.expires_at
+            )
+            .fetch_one(&self.pool)
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn list_subject_bans(&self) -> RepoResult<Vec<SubjectBan>> {
+            sqlx::query_as!(
+                SubjectBan,
+                r#"
+                SELECT subject, reason, public_reason, banned_by, created_at, expires_at
+                FROM subject_bans
+                WHERE expires_at IS NULL OR expires_at > now()
+                ORDER BY created_at DESC
+                "#,
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn delete_subject_ban(&self, subject: &str) -> RepoResult<()> {
+            let result = sqlx::query!("DELETE FROM subject_bans WHERE subject=$1", subject)
+                .execute(&self.pool)
+                .await?;
+            if result.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+
+        async fn active_ip_ban(&self, ip: &str) -> RepoResult<Option<BanNotice>> {
+            // Unparseable addresses match nothing.
+            if ip.parse::<std::net::IpAddr>().is_err() {
+                return Ok(None);
+            }
+            let row = sqlx::query!(
+                r#"
+                SELECT public_reason, expires_at
+                FROM ip_bans
+                WHERE network >>= $1::text::inet
+                  AND (expires_at IS NULL OR expires_at > now())
+                ORDER BY expires_at DESC NULLS FIRST
+                LIMIT 1
+                "#,
+                ip
+            )
+            .fetch_optional(&self.pool)
+            .await?;
+            Ok(row.map(|r| BanNotice {
+                scope: BanScope::Ip,
+                reason: r.public_reason,
+                expires_at: r.expires_at,
+            }))
+        }
+
+        async fn create_ip_ban(&self, new: NewIpBan, banned_by: &str) -> RepoResult<IpBan> {
+            sqlx::query_as!(
+                IpBan,
+                r#"
+                INSERT INTO ip_bans (network, reason, public_reason, banned_by, expires_at)
+                VALUES (network($1::text::inet), $2, $3, $4, $5)
+                RETURNING id, network::text as "network!", reason, public_reason, banned_by,
+                          created_at, expires_at
+                "#,
+                new.network,
+                new.reason,
+                new.public_reason,
+                banned_by,
+                new.expires_at
+            )
+            .fetch_one(&self.pool)
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn list_ip_bans(&self) -> RepoResult<Vec<IpBan>> {
+            sqlx::query_as!(
+                IpBan,
+                r#"
+                SELECT id, network::text as "network!", reason, public_reason, banned_by,
+                       created_at, expires_at
+                FROM ip_bans
+                WHERE expires_at IS NULL OR expires_at > now()
+                ORDER BY id DESC
+                "#,
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn delete_ip_ban(&self, id: Id) -> RepoResult<()> {
+            let result = sqlx::query!("DELETE FROM ip_bans WHERE id=$1", id)
+                .execute(&self.pool)
+                .await?;
+            if result.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl OutboxRepo for PgRepo {
+        async fn claim_outbox_events(
+            &self,
+            limit: i64,
+            lease_secs: i64,
+        ) -> RepoResult<Vec<OutboxEvent>> {
+            sqlx::query_as!(
+                OutboxEvent,
+                r#"
+                UPDATE outbox SET
+                    attempts = attempts + 1,
+                    next_attempt_at = now() + make_interval(secs => $2)
+                WHERE id IN (
+                    SELECT id FROM outbox
+                    WHERE delivered_at IS NULL AND next_attempt_at <= now()
+                    ORDER BY id
+                    LIMIT $1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id, event_type, payload, created_at, attempts
+                "#,
+                limit,
+                lease_secs as f64
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map(|mut events| {
+                events.sort_by_key(|event| event.id);
+                events
+            })
+            .map_err(RepoError::from)
+        }
+
+        async fn mark_outbox_delivered(&self, id: Id) -> RepoResult<()> {
+            let res = sqlx::query!(
+                "UPDATE outbox SET delivered_at = now(), last_error = NULL WHERE id=$1",
+                id
+            )
+            .execute(&self.pool)
+            .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+
+        async fn mark_outbox_failed(
+            &self,
+            id: Id,
+            error: &str,
+            retry_in_secs: i64,
+        ) -> RepoResult<()> {
+            let res = sqlx::query!("UPDATE outbox SET last_error = $2, next_attempt_at = now() + make_interval(secs => $3) WHERE id=$1 AND delivered_at IS NULL", id, error, retry_in_secs as f64)
+            .execute(&self.pool)
+            .await
+            ?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+
+        async fn prune_delivered_outbox(&self, older_than_secs: i64) -> RepoResult<u64> {
+            sqlx::query!(
+                "DELETE FROM outbox WHERE delivered_at < now() - make_interval(secs => $1)",
+                older_than_secs as f64
+            )
+            .execute(&self.pool)
+            .await
+            .map(|res| res.rows_affected())
+            .map_err(RepoError::from)
+        }
+    }
+
+    #[async_trait]
+    impl SchemaRepo for PgRepo {
+        async fn applied_migrations(&self) -> RepoResult<Vec<AppliedMigration>> {
+            let exists = sqlx::query_scalar!(
+                r#"SELECT to_regclass('_sqlx_migrations') IS NOT NULL AS "exists!""#
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            if !exists {
+                return Ok(Vec::new());
+            }
+            // Owned by sqlx rather than our migrations, so it is not checked at build time.
+            Ok(sqlx::query_as::<_, AppliedMigration>(
+                "SELECT version, description, installed_on, success, checksum,
+                        execution_time AS execution_time_ns
+                 FROM _sqlx_migrations ORDER BY version",
+            )
+            .fetch_all(&self.pool)
+            .await?)
+        }
+    }
+
+    #[async_trait]
+    impl SitemapRepo for PgRepo {
+        async fn sitemap_boards(&self) -> RepoResult<Vec<SitemapBoard>> {
+            Ok(self
+                .read(|pool| async move {
+                    sqlx::query_as!(
+                        SitemapBoard,
+                        r#"
+                SELECT b.slug, MAX(t.bump_time) AS lastmod
+                FROM boards b
+                LEFT JOIN threads t ON t.board_id = b.id AND t.deleted_at IS NULL
+                WHERE b.deleted_at IS NULL
+                GROUP BY b.id
+                ORDER BY b.id
+            "#
+                    )
+                    .fetch_all(&pool)
+                    .await
+                })
+                .await?)
+        }
+        async fn count_sitemap_threads(&self, since: DateTime<Utc>) -> RepoResult<i64> {
+            Ok(self
+                .read(|pool| async move {
+                    sqlx::query_scalar!(
+                        r#"
+                SELECT COUNT(*) AS "count!"
+                FROM threads t JOIN boards b ON b.id = t.board_id
+                WHERE t.deleted_at IS NULL AND b.deleted_at IS NULL AND t.bump_time >= $1
+            "#,
+                        since
+                    )
+                    .fetch_one(&pool)
+                    .await
+                })
+                .await?)
+        }
+        async fn list_sitemap_threads(
+            &self,
+            since: DateTime<Utc>,
+            limit: i64,
+            offset: i64,
+        ) -> RepoResult<Vec<SitemapThread>> {
+            Ok(self
+                .read(|pool| async move {
+                    sqlx::query_as!(
+                        SitemapThread,
+                        r#"
+                SELECT t.id, t.bump_time
+                FROM threads t JOIN boards b ON b.id = t.board_id
+                WHERE t.deleted_at IS NULL AND b.deleted_at IS NULL AND t.bump_time >= $1
+                ORDER BY t.id
+                LIMIT $2 OFFSET $3
+            "#,
+                        since,
+                        limit,
+                        offset
+                    )
+                    .fetch_all(&pool)
+                    .await
+                })
+                .await?)
+        }
+    }
+
+    #[async_trait]
+    impl NotificationRepo for PgRepo {
+        async fn subscribe_thread(&self, subject: &str, thread_id: Id) -> RepoResult<()> {
+            sqlx::query!(
+                r#"
+                INSERT INTO thread_subscriptions (subject, thread_id, last_notified_reply_id)
+                SELECT $1, t.id, COALESCE((SELECT MAX(r.id) FROM replies r WHERE r.thread_id = t.id), 0)
+                FROM threads t WHERE t.id = $2
+                ON CONFLICT (subject, thread_id) DO NOTHING
+                "#,
+                subject,
+                thread_id
+            )
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        }
+
+        async fn unsubscribe_thread(&self, subject: &str, thread_id: Id) -> RepoResult<()> {
+            let result = sqlx::query!(
+                "DELETE FROM thread_subscriptions WHERE subject=$1 AND thread_id=$2",
+                subject,
+                thread_id
+            )
+            .execute(&self.pool)
+            .await?;
+            if result.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+
+        async fn list_thread_subscriptions(
+            &self,
+            subject: &str,
+        ) -> RepoResult<Vec<ThreadSubscription>> {
+            sqlx::query_as!(
+                ThreadSubscription,
+                r#"
+                SELECT s.thread_id, s.created_at
+                FROM thread_subscriptions s JOIN threads t ON t.id = s.thread_id
+                WHERE s.subject = $1 AND t.deleted_at IS NULL
+                ORDER BY s.created_at DESC
+                "#,
+                subject
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn get_notification_settings(
+            &self,
+            subject: &str,
+        ) -> RepoResult<Option<NotificationSettings>> {
+            let row = sqlx::query!(
+                "SELECT email, email_verified, digest FROM notification_settings WHERE subject=$1",
+                subject
+            )
+            .fetch_optional(&self.pool)
+            .await?;
+            Ok(row.map(|row| NotificationSettings {
+                email: row.email,
+                email_verified: row.email_verified,
+                digest: DigestFrequency::parse(&row.digest).unwrap_or_default(),
+            }))
+        }
+
+        async fn put_notification_settings(
+            &self,
+            subject: &str,
+            update: UpdateNotificationSettings,
+        ) -> RepoResult<NotificationSettings> {
+            let row = sqlx::query!(
+                r#"
+                INSERT INTO notification_settings (subject, email, digest)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (subject) DO UPDATE SET
+                    email = EXCLUDED.email,
+                    email_verified = notification_settings.email_verified
+                        AND notification_settings.email IS NOT DISTINCT FROM EXCLUDED.email,
+                    digest = EXCLUDED.digest,
+                    updated_at = now()
+                RETURNING email, email_verified, digest
+                "#,
+                subject,
+                update.email,
+                update.digest.as_str()
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            Ok(NotificationSettings {
+                email: row.email,
+                email_verified: row.email_verified,
+                digest: DigestFrequency::parse(&row.digest).unwrap_or_default(),
+            })
+        }
+
+        async fn verify_notification_email(&self, subject: &str, email: &str) -> RepoResult<bool> {
+            let result = sqlx::query!(
+                "UPDATE notification_settings SET email_verified = TRUE, updated_at = now() WHERE subject=$1 AND email=$2",
+                subject,
+                email
+            )
+            .execute(&self.pool)
+            .await?;
+            Ok(result.rows_affected() > 0)
+        }
+
+        async fn disable_digests(&self, subject: &str) -> RepoResult<()> {
+            sqlx::query!(
+                "UPDATE notification_settings SET digest = 'off', updated_at = now() WHERE subject=$1",
+                subject
+            )
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        }
+
+        async fn due_digest_recipients(
+            &self,
+            digest: DigestFrequency,
+            min_interval_secs: i64,
+            limit: i64,
+        ) -> RepoResult<Vec<DigestRecipient>> {
+            sqlx::query_as!(
+                DigestRecipient,
+                r#"
+                SELECT n.subject, n.email AS "email!"
+                FROM notification_settings n
+                WHERE n.digest = $1 AND n.email_verified AND n.email IS NOT NULL
+                  AND (n.last_digest_at IS NULL
+                       OR n.last_digest_at <= now() - make_interval(secs => $2))
+                  AND (EXISTS (
+                      SELECT 1
+                      FROM thread_subscriptions s
+                      JOIN threads t ON t.id = s.thread_id AND t.deleted_at IS NULL
+                      JOIN replies r ON r.thread_id = s.thread_id
+                          AND r.id > s.last_notified_reply_id AND r.deleted_at IS NULL
+                      WHERE s.subject = n.subject
+                        AND r.created_by->>'subject' IS DISTINCT FROM n.subject
+                  ) OR EXISTS (
+                      SELECT 1
+                      FROM saved_searches s
+                      JOIN saved_search_matches m ON m.search_id = s.id AND m.notified_at IS NULL
+                      WHERE s.subject = n.subject
+                  ))
+                ORDER BY n.last_digest_at NULLS FIRST
+                LIMIT $3
+                "#,
+                digest.as_str(),
+                min_interval_secs as f64,
+                limit
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn digest_entries(&self, subject: &str, limit: i64) -> RepoResult<Vec<DigestEntry>> {
+            sqlx::query_as!(
+                DigestEntry,
+                r#"
+                SELECT t.id AS thread_id, t.subject AS thread_subject, r.id AS reply_id,
+                       r.content, r.created_at
+                FROM thread_subscriptions s
+                JOIN threads t ON t.id = s.thread_id AND t.deleted_at IS NULL
+                JOIN replies r ON r.thread_id = s.thread_id
+                    AND r.id > s.last_notified_reply_id AND r.deleted_at IS NULL
+                WHERE s.subject = $1
+                  AND r.created_by->>'subject' IS DISTINCT FROM $1
+                ORDER BY t.id, r.id
+                LIMIT $2
+                "#,
+                subject,
+                limit
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn search_digest_entries(
+            &self,
+            subject: &str,
+            limit: i64,
+        ) -> RepoResult<Vec<SearchDigestEntry>> {
+            sqlx::query_as!(
+                SearchDigestEntry,
+                r#"
+                SELECT m.id AS match_id, s.query, t.id AS thread_id, t.subject AS thread_subject,
+                       r.id AS "reply_id?", COALESCE(r.content, t.body) AS "content!",
+                       COALESCE(r.created_at, t.created_at) AS "created_at!"
+                FROM saved_search_matches m
+                JOIN saved_searches s ON s.id = m.search_id
+                JOIN threads t ON t.id = m.thread_id AND t.deleted_at IS NULL
+                LEFT JOIN replies r ON r.id = m.reply_id
+                WHERE s.subject = $1 AND m.notified_at IS NULL
+                  AND (m.reply_id IS NULL OR r.deleted_at IS NULL)
+                ORDER BY s.id, m.id
+                LIMIT $2
+                "#,
+                subject,
+                limit
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn mark_digest_sent(
+            &self,
+            subject: &str,
+            watermarks: &[(Id, Id)],
+            match_ids: &[Id],
+        ) -> RepoResult<()> {
+            let (thread_ids, reply_ids): (Vec<Id>, Vec<Id>) = watermarks.iter().copied().unzip();
+            let mut tx = self.pool.begin().await?;
+            sqlx::query!(
+                r#"
+                UPDATE thread_subscriptions s
+                SET last_notified_reply_id = GREATEST(s.last_notified_reply_id, w.reply_id)
+                FROM UNNEST($2::BIGINT[], $3::BIGINT[]) AS w(thread_id, reply_id)
+                WHERE s.subject = $1 AND s.thread_id = w.thread_id
+                "#,
+                subject,
+                &thread_ids,
+                &reply_ids
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query!(
+                r#"
+                UPDATE saved_search_matches m SET notified_at = now()
+                FROM saved_searches s
+                WHERE s.id = m.search_id AND s.subject = $1 AND m.id = ANY($2)
+                "#,
+                subject,
+                match_ids
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query!(
+                "UPDATE notification_settings SET last_digest_at = now() WHERE subject=$1",
+                subject
+            )
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl PreferenceRepo for PgRepo {
+        async fn get_preferences(&self, subject: &str) -> RepoResult<Option<Value>> {
+            sqlx::query_scalar!(
+                "SELECT preferences FROM user_preferences WHERE subject=$1",
+                subject
+            )
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn put_preferences(&self, subject: &str, preferences: Value) -> RepoResult<()> {
+            sqlx::query!(
+                r#"
+                INSERT INTO user_preferences (subject, preferences) VALUES ($1, $2)
+                ON CONFLICT (subject) DO UPDATE SET
+                    preferences = EXCLUDED.preferences,
+                    updated_at = now()
+                "#,
+                subject,
+                preferences
+            )
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl FilterRepo for PgRepo {
+        async fn list_filters(&self, subject: &str) -> RepoResult<Vec<UserFilter>> {
+            let rows = sqlx::query!(
+                r#"
+                SELECT id, kind, value, target_subject, created_at
+                FROM user_filters WHERE subject=$1 ORDER BY id
+                "#,
+                subject
+            )
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows
+                .into_iter()
+                .filter_map(|row| {
+                    Some(UserFilter {
+                        id: row.id,
+                        kind: FilterKind::parse(&row.kind)?,
+                        value: row.value,
+                        created_at: row.created_at,
+                        target_subject: row.target_subject,
+                    })
+                })
+                .collect())
+        }
+
+        async fn create_filter(
+            &self,
+            subject: &str,
+            new: NewUserFilter,
+            target_subject: Option<String>,
+        ) -> RepoResult<UserFilter> {
+            let row = sqlx::query!(
+                r#"
+                INSERT INTO user_filters (subject, kind, value, target_subject)
+                VALUES ($1, $2, $3, $4)
+                RETURNING id, created_at
+                "#,
+                subject,
+                new.kind.as_str(),
+                new.value,
+                target_subject
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            Ok(UserFilter {
+                id: row.id,
+                kind: new.kind,
+                value: new.value,
+                created_at: row.created_at,
+                target_subject,
+            })
+        }
+
+        async fn delete_filter(&self, subject: &str, id: Id) -> RepoResult<()> {
+            let result = sqlx::query!(
+                "DELETE FROM user_filters WHERE subject=$1 AND id=$2",
+                subject,
+                id
+            )
+            .execute(&self.pool)
+            .await?;
+            if result.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl ProfileRepo for PgRepo {
+        async fn get_profile(&self, subject: &str) -> RepoResult<Option<AuthorProfile>> {
+            Ok(sqlx::query_as!(
+                AuthorProfile,
+                "SELECT display_name, avatar_hash FROM user_profiles WHERE subject=$1",
+                subject
+            )
+            .fetch_optional(&self.pool)
+            .await?)
+        }
+
+        async fn set_display_name(
+            &self,
+            subject: &str,
+            display_name: Option<&str>,
+        ) -> RepoResult<AuthorProfile> {
+            Ok(sqlx::query_as!(
+                AuthorProfile,
+                r#"
+                INSERT INTO user_profiles (subject, display_name) VALUES ($1, $2)
+                ON CONFLICT (subject) DO UPDATE SET
+                    display_name = EXCLUDED.display_name,
+                    updated_at = now()
+                RETURNING display_name, avatar_hash
+                "#,
+                subject,
+                display_name
+            )
+            .fetch_one(&self.pool)
+            .await?)
+        }
+
+        async fn set_avatar(
+            &self,
+            subject: &str,
+            avatar: Option<(&str, &str)>,
+        ) -> RepoResult<AuthorProfile> {
+            let (hash, mime) = avatar.unzip();
+            Ok(sqlx::query_as!(
+                AuthorProfile,
+                r#"
+                INSERT INTO user_profiles (subject, avatar_hash, avatar_mime) VALUES ($1, $2, $3)
+                ON CONFLICT (subject) DO UPDATE SET
+                    avatar_hash = EXCLUDED.avatar_hash,
+                    avatar_mime = EXCLUDED.avatar_mime,
+                    updated_at = now()
+                RETURNING display_name, avatar_hash
+                "#,
+                subject,
+                hash,
+                mime
+            )
+            .fetch_one(&self.pool)
+            .await?)
+        }
+
+        async fn reset_profile(&self, subject: &str) -> RepoResult<()> {
+            let result = sqlx::query!(
+                r#"
+                UPDATE user_profiles
+                SET display_name = NULL, avatar_hash = NULL, avatar_mime = NULL, updated_at = now()
+                WHERE subject=$1 AND (display_name IS NOT NULL OR avatar_hash IS NOT NULL)
+                "#,
+                subject
+            )
+            .execute(&self.pool)
+            .await?;
+            if result.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl ModerationRepo for PgRepo {
+        async fn list_moderation_log(
+            &self,
+            thread_id: Option<Id>,
+            limit: i64,
+        ) -> RepoResult<Vec<ModerationEntry>> {
+            let rows = sqlx::query!(
+                r#"
+                SELECT id, actor, actor_role, action, thread_id, reply_id, created_at
+                FROM moderation_log
+                WHERE $1::BIGINT IS NULL OR thread_id = $1
+                ORDER BY id DESC
+                LIMIT $2
+                "#,
+                thread_id,
+                limit
+            )
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows
+                .into_iter()
+                .filter_map(|row| {
+                    Some(ModerationEntry {
+                        id: row.id,
+                        actor: row.actor,
+                        actor_role: ModerationActor::parse(&row.actor_role)?,
+                        action: ModerationAction::parse(&row.action)?,
+                        thread_id: row.thread_id,
+                        reply_id: row.reply_id,
+                        created_at: row.created_at,
+                    })
+                })
+                .collect())
+        }
+        async fn record_deletion(&self, entry: NewDeletionEntry) -> RepoResult<()> {
+            let mut conn = self.pool.acquire().await?;
+            record_deletion(&mut conn, entry).await
+        }
+        async fn list_deletion_log(
+            &self,
+            kind: Option<DeletionKind>,
+            limit: i64,
+        ) -> RepoResult<Vec<DeletionEntry>> {
+            let rows = sqlx::query!(
+                r#"
+                SELECT id, kind, target_id, hard, actor, reason, created_at
+                FROM deletion_log
+                WHERE $1::TEXT IS NULL OR kind = $1
+                ORDER BY id DESC
+                LIMIT $2
+                "#,
+                kind.map(DeletionKind::as_str),
+                limit
+            )
+            .fetch_all(&self.pool)
+            .await?;
+            Ok(rows
+                .into_iter()
+                .filter_map(|row| {
+                    Some(DeletionEntry {
+                        id: row.id,
+                        kind: DeletionKind::parse(&row.kind)?,
+                        target_id: row.target_id,
+                        hard: row.hard,
+                        actor: row.actor,
+                        reason: row.reason,
+                        created_at: row.created_at,
+                    })
+                })
+                .collect())
+        }
+    }
+
+    #[async_trait]
+    impl ScheduleRepo for PgRepo {
+        async fn create_scheduled_thread(
+            &self,
+            new: NewScheduledThread,
+            created_by: Value,
+        ) -> RepoResult<ScheduledThread> {
+            Ok(sqlx::query_as!(
+                ScheduledThread,
+                r#"INSERT INTO scheduled_threads (board_id, subject, body, author_name, publish_at, repeat_days, created_by)
+                   VALUES ($1,$2,$3,$4,$5,$6,$7)
+                   RETURNING id, board_id, subject, body, author_name, publish_at, repeat_days, created_at,
+                       last_thread_id, completed_at, created_by"#,
+                new.board_id,
+                new.subject,
+                new.body,
+                new.author_name,
+                new.publish_at,
+                new.repeat_days,
+                created_by
+            )
+            .fetch_one(&self.pool)
+            .await?)
+        }
+
+        async fn list_scheduled_threads(&self) -> RepoResult<Vec<ScheduledThread>> {
+            Ok(sqlx::query_as!(
+                ScheduledThread,
+                r#"SELECT id, board_id, subject, body, author_name, publish_at, repeat_days, created_at,
+                       last_thread_id, completed_at, created_by
+                   FROM scheduled_threads
+                   WHERE completed_at IS NULL
+                   ORDER BY publish_at, id"#
+            )
+            .fetch_all(&self.pool)
+            .await?)
+        }
+
+        async fn cancel_scheduled_thread(&self, id: Id) -> RepoResult<()> {
+            let res = sqlx::query!(
+                "DELETE FROM scheduled_threads WHERE id=$1 AND completed_at IS NULL",
+                id
+            )
+            .execute(&self.pool)
+            .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+
+        async fn publish_due_threads(&self, limit: i64) -> RepoResult<Vec<Thread>> {
+            let mut published = Vec::new();
+            for _ in 0..limit {
+                let mut tx = self.pool.begin().await?;
+                // SKIP LOCKED lets several replicas run the publisher safely.
+                let Some(due) = sqlx::query!(
+                    r#"SELECT s.id, s.board_id, s.subject, s.body, s.author_name, s.created_by
+                       FROM scheduled_threads s JOIN boards b ON b.id = s.board_id
+                       WHERE s.completed_at IS NULL AND s.publish_at <= now() AND b.deleted_at IS NULL
+                       ORDER BY s.publish_at, s.id
+                       LIMIT 1
+                       FOR UPDATE OF s SKIP LOCKED"#
+                )
+                .fetch_optional(&mut *tx)
+                .await?
+                else {
+                    break;
+                };
+                let new = NewThread {
+                    board_id: due.board_id,
+                    subject: due.subject,
+                    body: due.body,
+                    image_hash: None,
+                    mime: None,
+                    author_name: None,
+                    tripcode_password: None,
+                    delete_password: None,
+                    tags: Vec::new(),
+                };
+                let identity = PublicIdentity {
+                    author_name: due.author_name,
+                    tripcode: None,
+                };
+                let thread_id = insert_thread(&mut tx, &new, &due.created_by, &identity).await?;
+                // Repeating schedules skip runs missed while no publisher was up.
+                sqlx::query!(
+                    r#"UPDATE scheduled_threads SET
+                           last_thread_id = $2,
+                           completed_at = CASE WHEN repeat_days IS NULL THEN now() END,
+                           publish_at = CASE WHEN repeat_days IS NULL THEN publish_at
+                               ELSE publish_at + make_interval(days => repeat_days * (1 + floor(
+                                   extract(epoch FROM now() - publish_at) / (repeat_days * 86400))::INT))
+                           END
+                       WHERE id = $1"#,
+                    due.id,
+                    thread_id
+                )
+                .execute(&mut *tx)
+                .await?;
+                tx.commit().await?;
+                published.push(fetch_thread(&self.pool, thread_id).await?);
+            }
+            Ok(published)
+        }
+    }
+
+    async fn reaction_counts(
+        pool: &Pool<Postgres>,
+        reply_id: Id,
+    ) -> RepoResult<Vec<ReactionCount>> {
+        let counts = sqlx::query_scalar!(
+            r#"SELECT reaction_counts($1) as "counts!: sqlx::types::Json<Vec<ReactionCount>>""#,
+            reply_id
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(counts.0)
+    }
+
+    #[async_trait]
+    impl ReactionRepo for PgRepo {
+        async fn set_reaction(
+            &self,
+            reply_id: Id,
+            subject: &str,
+            emoji: &str,
+        ) -> RepoResult<Vec<ReactionCount>> {
+            sqlx::query!(
+                r#"INSERT INTO reply_reactions (reply_id, subject, emoji) VALUES ($1,$2,$3)
+                   ON CONFLICT (reply_id, subject)
+                   DO UPDATE SET emoji = EXCLUDED.emoji, created_at = now()"#,
+                reply_id,
+                subject,
+                emoji
+            )
+            .execute(&self.pool)
+            .await?;
+            reaction_counts(&self.pool, reply_id).await
+        }
+
+        async fn clear_reaction(
+            &self,
+            reply_id: Id,
+            subject: &str,
+        ) -> RepoResult<Vec<ReactionCount>> {
+            sqlx::query!(
+                "DELETE FROM reply_reactions WHERE reply_id=$1 AND subject=$2",
+                reply_id,
+                subject
+            )
+            .execute(&self.pool)
+            .await?;
+            reaction_counts(&self.pool, reply_id).await
+        }
+    }
+
+    #[async_trait]
+    impl SavedSearchRepo for PgRepo {
+        async fn list_saved_searches(&self, subject: &str) -> RepoResult<Vec<SavedSearch>> {
+            Ok(sqlx::query_as!(
+                SavedSearch,
+                "SELECT id, query, board_id, created_at FROM saved_searches WHERE subject=$1 ORDER BY id",
+                subject
+            )
+            .fetch_all(&self.pool)
+            .await?)
+        }
+
+        async fn create_saved_search(
+            &self,
+            subject: &str,
+            new: NewSavedSearch,
+        ) -> RepoResult<SavedSearch> {
+            Ok(sqlx::query_as!(
+                SavedSearch,
+                r#"INSERT INTO saved_searches (subject, query, board_id, last_thread_id, last_reply_id)
+                   VALUES ($1, $2, $3,
+                       (SELECT COALESCE(max(id), 0) FROM threads),
+                       (SELECT COALESCE(max(id), 0) FROM replies))
+                   RETURNING id, query, board_id, created_at"#,
+                subject,
+                new.query,
+                new.board_id
+            )
+            .fetch_one(&self.pool)
+            .await?)
+        }
+
+        async fn delete_saved_search(&self, subject: &str, id: Id) -> RepoResult<()> {
+            let res = sqlx::query!(
+                "DELETE FROM saved_searches WHERE id=$1 AND subject=$2",
+                id,
+                subject
+            )
+            .execute(&self.pool)
+            .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+
+        async fn list_saved_search_matches(
+            &self,
+            subject: &str,
+            id: Id,
+            limit: i64,
+        ) -> RepoResult<Vec<SavedSearchMatch>> {
+            sqlx::query_scalar!(
+                "SELECT id FROM saved_searches WHERE id=$1 AND subject=$2",
+                id,
+                subject
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            Ok(sqlx::query_as!(
+                SavedSearchMatch,
+                r#"SELECT thread_id, reply_id, matched_at, notified_at
+                   FROM saved_search_matches
+                   WHERE search_id = $1
+                   ORDER BY id DESC
+                   LIMIT $2"#,
+                id,
+                limit
+            )
+            .fetch_all(&self.pool)
+            .await?)
+        }
+
+        async fn match_saved_searches(&self, limit: i64, settle_secs: i64) -> RepoResult<u64> {
+            // Posts from transactions that began moments ago may still commit
+            // below the high-water mark, so young posts wait a pass.
+            let marks = sqlx::query!(
+                r#"SELECT
+                       (SELECT COALESCE(max(id), 0) FROM threads
+                        WHERE created_at <= now() - make_interval(secs => $1)) AS "threads!",
+                       (SELECT COALESCE(max(id), 0) FROM replies
+                        WHERE created_at <= now() - make_interval(secs => $1)) AS "replies!""#,
+                settle_secs as f64
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            let mut matched = 0;
+            for _ in 0..limit {
+                let mut tx = self.pool.begin().await?;
+                // SKIP LOCKED lets several replicas run the matcher safely.
+                let Some(search) = sqlx::query!(
+                    r#"SELECT id, subject, query, board_id, last_thread_id, last_reply_id
+                       FROM saved_searches
+                       WHERE last_thread_id < $1 OR last_reply_id < $2
+                       ORDER BY id
+                       LIMIT 1
+                       FOR UPDATE SKIP LOCKED"#,
+                    marks.threads,
+                    marks.replies
+                )
+                .fetch_optional(&mut *tx)
+                .await?
+                else {
+                    break;
+                };
+                let threads = sqlx::query!(
+                    r#"INSERT INTO saved_search_matches (search_id, thread_id)
+                       SELECT $1, t.id FROM threads t
+                       WHERE t.id > $2 AND t.id <= $3 AND t.deleted_at IS NULL
+                         AND ($4::BIGINT IS NULL OR t.board_id = $4)
+                         AND t.created_by->>'subject' IS DISTINCT FROM $5
+                         AND to_tsvector('simple', t.subject || ' ' || t.body)
+                             @@ websearch_to_tsquery('simple', $6)
+                       ON CONFLICT DO NOTHING"#,
+                    search.id,
+                    search.last_thread_id,
+                    marks.threads.max(search.last_thread_id),
+                    search.board_id,
+                    search.subject,
+                    search.query
+                )
+                .execute(&mut *tx)
+                .await?;
+                let replies = sqlx::query!(
+                    r#"INSERT INTO saved_search_matches (search_id, thread_id, reply_id)
+                       SELECT $1, r.thread_id, r.id FROM replies r
+                       JOIN threads t ON t.id = r.thread_id AND t.deleted_at IS NULL
+                       WHERE r.id > $2 AND r.id <= $3 AND r.deleted_at IS NULL
+                         AND ($4::BIGINT IS NULL OR t.board_id = $4)
+                         AND r.created_by->>'subject' IS DISTINCT FROM $5
+                         AND to_tsvector('simple', r.content) @@ websearch_to_tsquery('simple', $6)
+                       ON CONFLICT DO NOTHING"#,
+                    search.id,
+                    search.last_reply_id,
+                    marks.replies.max(search.last_reply_id),
+                    search.board_id,
+                    search.subject,
+                    search.query
+                )
+                .execute(&mut *tx)
+                .await?;
+                sqlx::query!(
+                    r#"UPDATE saved_searches SET
+                           last_thread_id = GREATEST(last_thread_id, $2),
+                           last_reply_id = GREATEST(last_reply_id, $3)
+                       WHERE id = $1"#,
+                    search.id,
+                    marks.threads,
+                    marks.replies
+                )
+                .execute(&mut *tx)
+                .await?;
+                tx.commit().await?;
+                matched += threads.rows_affected() + replies.rows_affected();
+            }
+            Ok(matched)
+        }
+    }
+
+    /// `appeals` row; kind and status are stored as text.
+    struct AppealRecord {
+        id: Id,
+        subject: String,
+        kind: String,
+        target_id: Option<Id>,
+        message: String,
+        status: String,
+        action_at: DateTime<Utc>,
+        created_at: DateTime<Utc>,
+        decided_by: Option<String>,
+        decided_at: Option<DateTime<Utc>>,
+        note: Option<String>,
+    }
+
+    impl AppealRecord {
+        fn into_appeal(self) -> RepoResult<Appeal> {
+            let (Some(kind), Some(status)) = (
+                AppealKind::parse(&self.kind),
+                AppealStatus::parse(&self.status),
+            ) else {
+                return Err(RepoError::Constraint("appeals_kind_status".into()));
+            };
+            Ok(Appeal {
+                id: self.id,
+                subject: self.subject,
+                kind,
+                target_id: self.target_id,
+                message: self.message,
+                status,
+                action_at: self.action_at,
+                created_at: self.created_at,
+                decided_by: self.decided_by,
+                decided_at: self.decided_at,
+                note: self.note,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl AppealRepo for PgRepo {
+        async fn create_appeal(&self, subject: &str, new: NewAppeal) -> RepoResult<Appeal> {
+            let action_at = match (new.kind, new.target_id) {
+                (AppealKind::Ban, None) => sqlx::query_scalar!(
+                    "SELECT created_at FROM subject_bans WHERE subject=$1 AND (expires_at IS NULL OR expires_at > now())",
+                    subject
+                )
+                .fetch_optional(&self.pool)
+                .await?,
+                (AppealKind::Thread, Some(id)) => sqlx::query_scalar!(
+                    r#"SELECT deleted_at as "deleted_at!" FROM threads WHERE id=$1 AND created_by->>'subject' = $2 AND deleted_at IS NOT NULL AND held_at IS NULL"#,
+                    id,
+                    subject
+                )
+                .fetch_optional(&self.pool)
+                .await?,
+                (AppealKind::Reply, Some(id)) => sqlx::query_scalar!(
+                    r#"SELECT deleted_at as "deleted_at!" FROM replies WHERE id=$1 AND created_by->>'subject' = $2 AND deleted_at IS NOT NULL AND held_at IS NULL"#,
+                    id,
+                    subject
+                )
+                .fetch_optional(&self.pool)
+                .await?,
+                _ => None,
+            };
+            let action_at = action_at.ok_or(RepoError::NotFound)?;
+            sqlx::query_as!(
+                AppealRecord,
+                r#"
+                INSERT INTO appeals (subject, kind, target_id, message, action_at)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING id, subject, kind, target_id, message, status, action_at,
+                          created_at, decided_by, decided_at, note
+                "#,
+                subject,
+                new.kind.as_str(),
+                new.target_id,
+                new.message,
+                action_at
+            )
+            .fetch_one(&self.pool)
+            .await?
+            .into_appeal()
+        }
+
+        async fn list_appeals(&self, status: AppealStatus, limit: i64) -> RepoResult<Vec<Appeal>> {
+            sqlx::query_as!(
+                AppealRecord,
+                r#"
+                SELECT id, subject, kind, target_id, message, status, action_at,
+                       created_at, decided_by, decided_at, note
+                FROM appeals
+                WHERE status = $1
+                ORDER BY id
+                LIMIT $2
+                "#,
+                status.as_str(),
+                limit
+            )
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(AppealRecord::into_appeal)
+            .collect()
+        }
+
+        async fn list_subject_appeals(&self, subject: &str) -> RepoResult<Vec<Appeal>> {
+            sqlx::query_as!(
+                AppealRecord,
+                r#"
+                SELECT id, subject, kind, target_id, message, status, action_at,
+                       created_at, decided_by, decided_at, note
+                FROM appeals
+                WHERE subject = $1
+                ORDER BY id DESC
+                "#,
+                subject
+            )
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(AppealRecord::into_appeal)
+            .collect()
+        }
+
+        async fn decide_appeal(
+            &self,
+            id: Id,
+            decided_by: &str,
+            status: AppealStatus,
+            note: Option<String>,
+        ) -> RepoResult<Appeal> {
+            let mut tx = self.pool.begin().await?;
+            let appeal = sqlx::query_as!(
+                AppealRecord,
+                r#"
+                SELECT id, subject, kind, target_id, message, status, action_at,
+                       created_at, decided_by, decided_at, note
+                FROM appeals
+                WHERE id = $1
+                FOR UPDATE
+                "#,
+                id
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(RepoError::NotFound)?
+            .into_appeal()?;
+            if appeal.status != AppealStatus::Pending {
+                return Err(RepoError::Conflict);
+            }
+            // Only the appealed action is reversed: a newer ban or a second
+            // deletion of the post has its own action time and stays.
+            if status == AppealStatus::Accepted {
+                match (appeal.kind, appeal.target_id) {
+                    (AppealKind::Ban, _) => {
+                        let lifted = sqlx::query!(
+                            "DELETE FROM subject_bans WHERE subject=$1 AND created_at=$2",
+                            appeal.subject,
+                            appeal.action_at
+                        )
+                        .execute(&mut *tx)
+                        .await?;
+                        // An overturned ban does not count against trust.
+                        if lifted.rows_affected() > 0 {
+                            sqlx::query!(
+                                "UPDATE subject_trust SET bans = GREATEST(bans - 1, 0) WHERE subject=$1",
+                                appeal.subject
+                            )
+                            .execute(&mut *tx)
+                            .await?;
+                        }
+                    }
+                    (AppealKind::Thread, Some(thread_id)) => {
+                        sqlx::query!(
+                            "UPDATE threads SET deleted_at = NULL WHERE id=$1 AND deleted_at=$2",
+                            thread_id,
+                            appeal.action_at
+                        )
+                        .execute(&mut *tx)
+                        .await?;
+                    }
+                    (AppealKind::Reply, Some(reply_id)) => {
+                        sqlx::query!(
+                            "UPDATE replies SET deleted_at = NULL WHERE id=$1 AND deleted_at=$2",
+                            reply_id,
+                            appeal.action_at
+                        )
+                        .execute(&mut *tx)
+                        .await?;
+                    }
+                    _ => {}
+                }
+            }
+            let appeal = sqlx::query_as!(
+                AppealRecord,
+                r#"
+                UPDATE appeals
+                SET status = $2, decided_by = $3, decided_at = now(), note = $4
+                WHERE id = $1
+                RETURNING id, subject, kind, target_id, message, status, action_at,
+                          created_at, decided_by, decided_at, note
+                "#,
+                id,
+                status.as_str(),
+                decided_by,
+                note
+            )
+            .fetch_one(&mut *tx)
+            .await?
+            .into_appeal()?;
+            tx.commit().await?;
+            Ok(appeal)
+        }
+    }
+
+    /// `reports` row; kind and status are stored as text.
+    struct ReportRecord {
+        id: Id,
+        kind: String,
+        target_id: Id,
+        thread_id: Id,
+        reporter: String,
+        reason: String,
+        status: String,
+        created_at: DateTime<Utc>,
+        decided_by: Option<String>,
+        decided_at: Option<DateTime<Utc>>,
+        note: Option<String>,
+    }
+
+    impl ReportRecord {
+        fn into_report(self) -> RepoResult<Report> {
+            let (Some(kind), Some(status)) = (
+                ReportKind::parse(&self.kind),
+                ReportStatus::parse(&self.status),
+            ) else {
+                return Err(RepoError::Constraint("reports_kind_status".into()));
+            };
+            Ok(Report {
+                id: self.id,
+                kind,
+                target_id: self.target_id,
+                thread_id: self.thread_id,
+                reporter: self.reporter,
+                reason: self.reason,
+                status,
+                created_at: self.created_at,
+                decided_by: self.decided_by,
+                decided_at: self.decided_at,
+                note: self.note,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl ReportRepo for PgRepo {
+        async fn create_report(&self, reporter: &str, new: NewReport) -> RepoResult<Report> {
+            let mut tx = self.pool.begin().await?;
+            let thread_id = match new.kind {
+                ReportKind::Thread => sqlx::query_scalar!(
+                    "SELECT id FROM threads WHERE id=$1 AND deleted_at IS NULL AND held_at IS NULL",
+                    new.target_id
+                )
+                .fetch_optional(&mut *tx)
+                .await?,
+                ReportKind::Reply => sqlx::query_scalar!(
+                    "SELECT thread_id FROM replies WHERE id=$1 AND deleted_at IS NULL AND held_at IS NULL",
+                    new.target_id
+                )
+                .fetch_optional(&mut *tx)
+                .await?,
+            };
+            let thread_id = thread_id.ok_or(RepoError::NotFound)?;
+            let report = sqlx::query_as!(
+                ReportRecord,
+                r#"
+                INSERT INTO reports (kind, target_id, thread_id, reporter, reason)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING id, kind, target_id, thread_id, reporter, reason, status,
+                          created_at, decided_by, decided_at, note
+                "#,
+                new.kind.as_str(),
+                new.target_id,
+                thread_id,
+                reporter,
+                new.reason
+            )
+            .fetch_one(&mut *tx)
+            .await?
+            .into_report()?;
+            record_event(
+                &mut tx,
+                events::REPORT_CREATED,
+                serde_json::json!({
+                    "report_id": report.id,
+                    "kind": new.kind.as_str(),
+                    "target_id": report.target_id,
+                    "thread_id": thread_id,
+                }),
+            )
+            .await?;
+            tx.commit().await?;
+            Ok(report)
+        }
+
+        async fn list_reports(&self, status: ReportStatus, limit: i64) -> RepoResult<Vec<Report>> {
+            sqlx::query_as!(
+                ReportRecord,
+                r#"
+                SELECT id, kind, target_id, thread_id, reporter, reason, status,
+                       created_at, decided_by, decided_at, note
+                FROM reports
+                WHERE status = $1
+                ORDER BY id
+                LIMIT $2
+                "#,
+                status.as_str(),
+                limit
+            )
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(ReportRecord::into_report)
+            .collect()
+        }
+
+        async fn decide_report(
+            &self,
+            id: Id,
+            decided_by: &str,
+            status: ReportStatus,
+            note: Option<String>,
+        ) -> RepoResult<Report> {
+            let mut tx = self.pool.begin().await?;
+            let report = sqlx::query_as!(
+                ReportRecord,
+                r#"
+                SELECT id, kind, target_id, thread_id, reporter, reason, status,
+                       created_at, decided_by, decided_at, note
+                FROM reports
+                WHERE id = $1
+                FOR UPDATE
+                "#,
+                id
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(RepoError::NotFound)?
+            .into_report()?;
+            if report.status != ReportStatus::Open {
+                return Err(RepoError::Conflict);
+            }
+            sqlx::query!(
+                r#"
+                UPDATE reports
+                SET status = $3, decided_by = $4, decided_at = now(), note = $5
+                WHERE kind = $1 AND target_id = $2 AND status = 'open'
+                "#,
+                report.kind.as_str(),
+                report.target_id,
+                status.as_str(),
+                decided_by,
+                note
+            )
+            .execute(&mut *tx)
+            .await?;
+            let report = sqlx::query_as!(
+                ReportRecord,
+                r#"
+                SELECT id, kind, target_id, thread_id, reporter, reason, status,
+                       created_at, decided_by, decided_at, note
+                FROM reports
+                WHERE id = $1
+                "#,
+                id
+            )
+            .fetch_one(&mut *tx)
+            .await?
+            .into_report()?;
+            tx.commit().await?;
+            Ok(report)
+        }
+    }
+
+    #[async_trait]
+    impl TrustRepo for PgRepo {
+        async fn subject_trust(&self, subject: &str) -> RepoResult<Option<SubjectTrust>> {
+            Ok(sqlx::query_as!(
+                SubjectTrust,
+                "SELECT subject, first_seen_at, posts, removals, bans FROM subject_trust WHERE subject=$1",
+                subject
+            )
+            .fetch_optional(&self.pool)
+            .await?)
+        }
+
+        async fn list_held_posts(&self, limit: i64) -> RepoResult<Vec<HeldPost>> {
+            Ok(sqlx::query_as!(
+                HeldPost,
+                r#"
+                SELECT thread_id as "thread_id!", reply_id, board_id as "board_id!",
+                       subject, content as "content!", held_at as "held_at!"
+                FROM (
+                    SELECT t.id AS thread_id, NULL::BIGINT AS reply_id, t.board_id,
+                           t.created_by->>'subject' AS subject,
+                           t.subject || E'\n\n' || t.body AS content, t.held_at
+                    FROM threads t
+                    WHERE t.held_at IS NOT NULL
+                    UNION ALL
+                    SELECT r.thread_id, r.id, t.board_id, r.created_by->>'subject',
+                           r.content, r.held_at
+                    FROM replies r
+                    JOIN threads t ON t.id = r.thread_id
+                    WHERE r.held_at IS NOT NULL
+                ) held
+                ORDER BY held_at, thread_id, reply_id NULLS FIRST
+                LIMIT $1
+                "#,
+                limit
+            )
+            .fetch_all(&self.pool)
+            .await?)
+        }
+
+        async fn release_held_thread(&self, id: Id, approve: bool) -> RepoResult<()> {
+            let res = sqlx::query!(
+                r#"
+                UPDATE threads
+                SET held_at = NULL, deleted_at = CASE WHEN $2 THEN NULL ELSE deleted_at END
+                WHERE id=$1 AND held_at IS NOT NULL
+                "#,
+                id,
+                approve
+            )
+            .execute(&self.pool)
+            .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+
+        async fn release_held_reply(&self, id: Id, approve: bool) -> RepoResult<()> {
+            let res = sqlx::query!(
+                r#"
+                UPDATE replies
+                SET held_at = NULL, deleted_at = CASE WHEN $2 THEN NULL ELSE deleted_at END
+                WHERE id=$1 AND held_at IS NOT NULL
+                "#,
+                id,
+                approve
+            )
+            .execute(&self.pool)
+            .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl HoldRepo for PgRepo {
+        async fn place_legal_hold(
+            &self,
+            new: NewLegalHold,
+            placed_by: &str,
+        ) -> RepoResult<LegalHold> {
+            let mut tx = self.pool.begin().await?;
+            // Lock the post so a concurrent hard delete either wins or sees the hold.
+            if let Some(id) = new.thread_id {
+                sqlx::query_scalar!("SELECT id FROM threads WHERE id=$1 FOR SHARE", id)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .ok_or(RepoError::NotFound)?;
+            }
+            if let Some(id) = new.reply_id {
+                sqlx::query_scalar!("SELECT id FROM replies WHERE id=$1 FOR SHARE", id)
+                    .fetch_optional(&mut *tx)
+                    .await?
+                    .ok_or(RepoError::NotFound)?;
+            }
+            let hold = sqlx::query_as!(
+                LegalHold,
+                r#"
+                INSERT INTO legal_holds (thread_id, reply_id, image_hash, reason, placed_by)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING id, thread_id, reply_id, image_hash, reason, placed_by, created_at,
+                          released_at, released_by, release_reason
+                "#,
+                new.thread_id,
+                new.reply_id,
+                new.image_hash,
+                new.reason,
+                placed_by
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            Ok(hold)
+        }
+
+        async fn release_legal_hold(
+            &self,
+            id: Id,
+            released_by: &str,
+            reason: &str,
+        ) -> RepoResult<LegalHold> {
+            sqlx::query_as!(
+                LegalHold,
+                r#"
+                UPDATE legal_holds SET released_at = now(), released_by = $2, release_reason = $3
+                WHERE id = $1 AND released_at IS NULL
+                RETURNING id, thread_id, reply_id, image_hash, reason, placed_by, created_at,
+                          released_at, released_by, release_reason
+                "#,
+                id,
+                released_by,
+                reason
+            )
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(RepoError::NotFound)
+        }
+
+        async fn list_legal_holds(
+            &self,
+            include_released: bool,
+            limit: i64,
+        ) -> RepoResult<Vec<LegalHold>> {
+            self.read(|pool| async move {
+                sqlx::query_as!(
+                    LegalHold,
+                    r#"
+                    SELECT id, thread_id, reply_id, image_hash, reason, placed_by, created_at,
+                           released_at, released_by, release_reason
+                    FROM legal_holds
+                    WHERE $1 OR released_at IS NULL
+                    ORDER BY id DESC
+                    LIMIT $2
+                    "#,
+                    include_released,
+                    limit
+                )
+                .fetch_all(&pool)
+                .await
+            })
+            .await
+            .map_err(RepoError::from)
+        }
+
+        async fn image_legal_hold(&self, hash: &str) -> RepoResult<Option<LegalHold>> {
+            // Read from the primary: a blob held a moment ago must not be served.
+            Ok(sqlx::query_as!(
+                LegalHold,
+                r#"
+                SELECT id, thread_id, reply_id, image_hash, reason, placed_by, created_at,
+                       released_at, released_by, release_reason
+                FROM legal_holds
+                WHERE image_hash = $1 AND released_at IS NULL
+                "#,
+                hash
+            )
+            .fetch_optional(&self.pool)
+            .await?)
+        }
+    }
+
+    /// `board_bridges` row; the kind is stored as text.
+    struct BoardBridgeRecord {
+        id: Id,
+        board_id: Id,
+        kind: String,
+        target: String,
+        enabled: bool,
+        last_thread_id: Id,
+        last_sent_at: Option<DateTime<Utc>>,
+        last_error: Option<String>,
+        window_sent: i32,
+        created_by: String,
+        created_at: DateTime<Utc>,
+    }
+
+    impl BoardBridgeRecord {
+        fn into_bridge(self) -> RepoResult<BoardBridge> {
+            let kind = BridgeKind::parse(&self.kind)
+                .ok_or_else(|| RepoError::Constraint("board_bridges_kind_check".into()))?;
+            Ok(BoardBridge {
+                id: self.id,
+                board_id: self.board_id,
+                kind,
+                target: self.target,
+                enabled: self.enabled,
+                last_thread_id: self.last_thread_id,
+                last_sent_at: self.last_sent_at,
+                last_error: self.last_error,
+                window_sent: self.window_sent,
+                created_by: self.created_by,
+                created_at: self.created_at,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl BridgeRepo for PgRepo {
+        async fn create_board_bridge(
+            &self,
+            board_id: Id,
+            new: NewBoardBridge,
+            created_by: &str,
+        ) -> RepoResult<BoardBridge> {
+            sqlx::query_as!(
+                BoardBridgeRecord,
+                r#"
+                INSERT INTO board_bridges (board_id, kind, target, created_by, last_thread_id)
+                VALUES ($1, $2, $3, $4, (SELECT COALESCE(max(id), 0) FROM threads))
+                RETURNING id, board_id, kind, target, enabled, last_thread_id, last_sent_at,
+                          last_error, window_sent, created_by, created_at
+                "#,
+                board_id,
+                new.kind.as_str(),
+                new.target,
+                created_by
+            )
+            .fetch_one(&self.pool)
+            .await?
+            .into_bridge()
+        }
+
+        async fn list_board_bridges(&self, board_id: Option<Id>) -> RepoResult<Vec<BoardBridge>> {
+            sqlx::query_as!(
+                BoardBridgeRecord,
+                r#"
+                SELECT id, board_id, kind, target, enabled, last_thread_id, last_sent_at,
+                       last_error, window_sent, created_by, created_at
+                FROM board_bridges
+                WHERE $1::BIGINT IS NULL OR board_id = $1
+                ORDER BY id
+                "#,
+                board_id
+            )
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(BoardBridgeRecord::into_bridge)
+            .collect()
+        }
+
+        async fn set_board_bridge_enabled(&self, id: Id, enabled: bool) -> RepoResult<BoardBridge> {
+            sqlx::query_as!(
+                BoardBridgeRecord,
+                r#"
+                UPDATE board_bridges SET
+                    enabled = $2,
+                    last_thread_id = CASE WHEN $2 AND NOT enabled
+                        THEN GREATEST(last_thread_id, (SELECT COALESCE(max(id), 0) FROM threads))
+                        ELSE last_thread_id END
+                WHERE id = $1
+                RETURNING id, board_id, kind, target, enabled, last_thread_id, last_sent_at,
+                          last_error, window_sent, created_by, created_at
+                "#,
+                id,
+                enabled
+            )
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(RepoError::NotFound)?
+            .into_bridge()
+        }
+
+        async fn delete_board_bridge(&self, id: Id) -> RepoResult<()> {
+            let res = sqlx::query!("DELETE FROM board_bridges WHERE id=$1", id)
+                .execute(&self.pool)
+                .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
+            }
+            Ok(())
+        }
+
+        async fn claim_board_bridges(
+            &self,
+            limit: i64,
+            lease_secs: i64,
+        ) -> RepoResult<Vec<BoardBridge>> {
+            // SKIP LOCKED and the lease let several replicas run the bridges.
+            sqlx::query_as!(
+                BoardBridgeRecord,
+                r#"
+                UPDATE board_bridges bb SET leased_until = now() + make_interval(secs => $2)
+                WHERE bb.id IN (
+                    SELECT br.id FROM board_bridges br JOIN boards b ON b.id = br.board_id
+                    WHERE br.enabled AND b.deleted_at IS NULL
+                      AND (br.leased_until IS NULL OR br.leased_until <= now())
+                    ORDER BY br.leased_until NULLS FIRST, br.id
+                    LIMIT $1
+                    FOR UPDATE OF br SKIP LOCKED
+                )
+                RETURNING bb.id, bb.board_id, bb.kind, bb.target, bb.enabled, bb.last_thread_id,
+                          bb.last_sent_at, bb.last_error,
+                          CASE WHEN bb.window_started_at > now() - interval '1 minute'
+                              THEN bb.window_sent ELSE 0 END as "window_sent!",
+                          bb.created_by, bb.created_at
+                "#,
+                limit,
+                lease_secs as f64
+            )
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(BoardBridgeRecord::into_bridge)
+            .collect()
+        }
+
+        async fn pending_bridge_threads(
+            &self,
+            bridge: &BoardBridge,
+            limit: i64,
+            settle_secs: i64,
+        ) -> RepoResult<Vec<Thread>> {
+            Ok(sqlx::query_as!(
+                Thread,
+                r#"
+                SELECT t.id, t.board_id, t.subject, t.body, t.created_at, t.bump_time, t.created_by,
+                    author_profile(t.created_by) as "author: sqlx::types::Json<AuthorProfile>",
+                    img.hash as "image_hash?", img.mime as "mime?", img.size_bytes as "image_size?", t.author_name, t.tripcode, t.deleted_at, t.closed_at, t.archived_at, t.pinned_reply_id, t.tags,
+                    t.reply_count, t.image_count, t.slug
+                FROM threads t
+                LEFT JOIN LATERAL (
+                    SELECT i.hash, i.mime, i.size_bytes FROM images i WHERE i.thread_id = t.id ORDER BY i.id ASC LIMIT 1
+                ) img ON TRUE
+                JOIN boards b ON b.id = t.board_id AND NOT b.beta
+                WHERE t.board_id = $1 AND t.id > $2
+                  AND t.deleted_at IS NULL AND t.held_at IS NULL
+                  AND t.created_at <= now() - make_interval(secs => $4)
+                ORDER BY t.id
+                LIMIT $3
+                "#,
+                bridge.board_id,
+                bridge.last_thread_id,
+                limit,
+                settle_secs as f64
+            )
+            .fetch_all(&self.pool)
+            .await?)
+        }
+
+        async fn release_board_bridge(
+            &self,
+            id: Id,
+            last_thread_id: Id,
+            sent: i32,
+            error: Option<String>,
+            retry_secs: i64,
+        ) -> RepoResult<()> {
+            sqlx::query!(
+                r#"
+                UPDATE board_bridges SET
+                    last_thread_id = GREATEST(last_thread_id, $2),
+                    last_sent_at = CASE WHEN $3 > 0 THEN now() ELSE last_sent_at END,
+                    window_sent = CASE WHEN window_started_at > now() - interval '1 minute'
+                        THEN window_sent + $3 ELSE $3 END,
+                    window_started_at = CASE WHEN window_started_at > now() - interval '1 minute'
+                        THEN window_started_at ELSE now() END,
+                    last_error = CASE WHEN $4::TEXT IS NOT NULL THEN $4
+                        WHEN $3 > 0 THEN NULL ELSE last_error END,
+                    leased_until = CASE WHEN $4::TEXT IS NOT NULL
+                        THEN now() + make_interval(secs => $5) END
+                WHERE id = $1
+                "#,
+                id,
+                last_thread_id,
+                sent,
+                error,
+                retry_secs as f64
+            )
+            .execute(&self.pool)
+            .await?;
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl MatrixRepo for PgRepo {
+        async fn create_matrix_mirror(
+            &self,
+            new: NewMatrixMirror,
+            created_by: &str,
+        ) -> RepoResult<MatrixMirror> {
+            Ok(sqlx::query_as!(
+                MatrixMirror,
+                r#"
+                INSERT INTO matrix_mirrors (board_id, thread_id, room_id, created_by,
+                                            last_thread_id, last_reply_id)
+                VALUES ($1, $2, $3, $4,
+                        (SELECT COALESCE(max(id), 0) FROM threads),
+                        (SELECT COALESCE(max(id), 0) FROM replies))
+                RETURNING id, board_id, thread_id, room_id, enabled, last_thread_id, last_reply_id,
+                          last_sent_at, last_error, created_by, created_at
+                "#,
+                new.board_id,
+                new.thread_id,
+                new.room_id,
+                created_by
+            )
+            .fetch_one(&self.pool)
+            .await?)
+        }
+
+        async fn list_matrix_mirrors(&self) -> RepoResult<Vec<MatrixMirror>> {
+            Ok(sqlx::query_as!(
+                MatrixMirror,
+                r#"
+                SELECT id, board_id, thread_id, room_id, enabled, last_thread_id, last_reply_id,
+                       last_sent_at, last_error, created_by, created_at
+                FROM matrix_mirrors
+                ORDER BY id
+                "#
+            )
+            .fetch_all(&self.pool)
+            .await?)
+        }
+
+        async fn set_matrix_mirror_enabled(
+            &self,
+            id: Id,
+            enabled: bool,
+        ) -> RepoResult<MatrixMirror> {
+            sqlx::query_as!(
+                MatrixMirror,
+                r#"
+                UPDATE matrix_mirrors SET
+                    enabled = $2,
+                    last_thread_id = CASE WHEN $2 AND NOT enabled
+                        THEN GREATEST(last_thread_id, (SELECT COALESCE(max(id), 0) FROM threads))
+                        ELSE last_thread_id END,
+                    last_reply_id = CASE WHEN $2 AND NOT enabled
+                        THEN GREATEST(last_reply_id, (SELECT COALESCE(max(id), 0) FROM replies))
+                        ELSE last_reply_id END
+                WHERE id = $1
+                RETURNING id, board_id, thread_id, room_id, enabled, last_thread_id, last_reply_id,
+                          last_sent_at, last_error, created_by, created_at
+                "#,
+                id,
+                enabled
             )
-                .bind(new.thread_id)
-                .bind(&new.content)
-                .bind(&created_by)
-                .bind(&public_identity.author_name)
-                .bind(&public_identity.tripcode)
-                .fetch_one(&mut *tx)
-                .await
-                .map_err(|_| RepoError::NotFound)?;
-            let reply_id: Id = rec.get::<Id, _>("id");
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or(RepoError::NotFound)
+        }
 
-            if let (Some(hash), Some(mime)) = (new.image_hash.as_ref(), new.mime.as_ref()) {
-                sqlx::query(
-                    "INSERT INTO images (thread_id, reply_id, hash, mime) VALUES (NULL, $1, $2, $3)"
-                )
-                    .bind(reply_id)
-                    .bind(hash)
-                    .bind(mime)
-                    .execute(&mut *tx)
-                    .await
-                    .map_err(|_| RepoError::Conflict)?;
+        async fn delete_matrix_mirror(&self, id: Id) -> RepoResult<()> {
+            let res = sqlx::query!("DELETE FROM matrix_mirrors WHERE id=$1", id)
+                .execute(&self.pool)
+                .await?;
+            if res.rows_affected() == 0 {
+                return Err(RepoError::NotFound);
             }
+            Ok(())
+        }
 
-            // bump parent thread
-            let _ = sqlx::query("UPDATE threads SET bump_time = now() WHERE id=$1")
-                .bind(new.thread_id)
-                .execute(&mut *tx)
-                .await;
-
-            tx.commit().await.map_err(|_| RepoError::Conflict)?;
-
-            // fetch and return full reply record
-            let reply = sqlx::query_as::<_, Reply>(
+        async fn claim_matrix_mirrors(
+            &self,
+            limit: i64,
+            lease_secs: i64,
+        ) -> RepoResult<Vec<MatrixMirror>> {
+            // SKIP LOCKED and the lease let several replicas run the mirrors.
+            Ok(sqlx::query_as!(
+                MatrixMirror,
                 r#"
-          SELECT r.id, r.thread_id, r.content,
-              img.hash as image_hash, img.mime as mime,
-              r.author_name, r.tripcode, r.created_at, r.deleted_at, r.created_by
-                FROM replies r
-                LEFT JOIN LATERAL (
-                    SELECT i.hash, i.mime
-                    FROM images i
-                    WHERE i.reply_id = r.id
-                    ORDER BY i.id ASC
-                    LIMIT 1
-                ) img ON TRUE
-                WHERE r.id = $1
-            "#,
+                UPDATE matrix_mirrors mm SET leased_until = now() + make_interval(secs => $2)
+                WHERE mm.id IN (
+                    SELECT m.id FROM matrix_mirrors m
+                    LEFT JOIN threads t ON t.id = m.thread_id
+                    JOIN boards b ON b.id = COALESCE(m.board_id, t.board_id)
+                    WHERE m.enabled AND b.deleted_at IS NULL
+                      AND (m.leased_until IS NULL OR m.leased_until <= now())
+                    ORDER BY m.leased_until NULLS FIRST, m.id
+                    LIMIT $1
+                    FOR UPDATE OF m SKIP LOCKED
+                )
+                RETURNING mm.id, mm.board_id, mm.thread_id, mm.room_id, mm.enabled,
+                          mm.last_thread_id, mm.last_reply_id, mm.last_sent_at, mm.last_error,
+                          mm.created_by, mm.created_at
+                "#,
+                limit,
+                lease_secs as f64
             )
-            .bind(reply_id)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|_| RepoError::NotFound)?;
+            .fetch_all(&self.pool)
+            .await?)
+        }
 
-            Ok(reply)
+        async fn pending_matrix_posts(
+            &self,
+            mirror: &MatrixMirror,
+            limit: i64,
+            settle_secs: i64,
+        ) -> RepoResult<Vec<MatrixPost>> {
+            Ok(sqlx::query_as!(
+                MatrixPost,
+                r#"
+                SELECT t.id as "thread_id!", NULL::BIGINT as reply_id, t.subject as "subject!",
+                       t.body as "body!", t.author_name, t.created_at as "created_at!"
+                FROM threads t JOIN boards b ON b.id = t.board_id AND NOT b.beta
+                WHERE t.board_id = $1 AND t.id > $3
+                  AND t.deleted_at IS NULL AND t.held_at IS NULL
+                  AND t.created_at <= now() - make_interval(secs => $6)
+                UNION ALL
+                SELECT r.thread_id, r.id, t.subject, r.content, r.author_name, r.created_at
+                FROM replies r JOIN threads t ON t.id = r.thread_id
+                JOIN boards b ON b.id = t.board_id AND NOT b.beta
+                WHERE (t.board_id = $1 OR t.id = $2) AND r.id > $4
+                  AND r.deleted_at IS NULL AND r.held_at IS NULL
+                  AND t.deleted_at IS NULL AND t.held_at IS NULL
+                  AND r.created_at <= now() - make_interval(secs => $6)
+                ORDER BY 6, 2 NULLS FIRST
+                LIMIT $5
+                "#,
+                mirror.board_id,
+                mirror.thread_id,
+                mirror.last_thread_id,
+                mirror.last_reply_id,
+                limit,
+                settle_secs as f64
+            )
+            .fetch_all(&self.pool)
+            .await?)
         }
-        async fn soft_delete_reply(&self, id: Id) -> RepoResult<()> {
-            let res = sqlx::query(
-                "UPDATE replies SET deleted_at = COALESCE(deleted_at, now()) WHERE id=$1",
+
+        async fn record_matrix_event(
+            &self,
+            mirror_id: Id,
+            post: &MatrixPost,
+            event_id: &str,
+        ) -> RepoResult<()> {
+            sqlx::query!(
+                r#"
+                INSERT INTO matrix_events (mirror_id, event_id, thread_id, reply_id)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT DO NOTHING
+                "#,
+                mirror_id,
+                event_id,
+                post.thread_id,
+                post.reply_id
             )
-            .bind(id)
             .execute(&self.pool)
-            .await
-            .map_err(|_| RepoError::NotFound)?;
-            if res.rows_affected() == 0 {
-                return Err(RepoError::NotFound);
-            }
+            .await?;
             Ok(())
         }
-        async fn restore_reply(&self, id: Id) -> RepoResult<()> {
-            let res = sqlx::query("UPDATE replies SET deleted_at = NULL WHERE id=$1")
-                .bind(id)
-                .execute(&self.pool)
-                .await
-                .map_err(|_| RepoError::NotFound)?;
-            if res.rows_affected() == 0 {
-                return Err(RepoError::NotFound);
-            }
-            Ok(())
+
+        async fn pending_matrix_redactions(
+            &self,
+            mirror_id: Id,
+            limit: i64,
+        ) -> RepoResult<Vec<MatrixEvent>> {
+            Ok(sqlx::query_as!(
+                MatrixEvent,
+                r#"
+                SELECT e.event_id, e.thread_id, e.reply_id
+                FROM matrix_events e
+                JOIN threads t ON t.id = e.thread_id
+                LEFT JOIN replies r ON r.id = e.reply_id
+                WHERE e.mirror_id = $1 AND e.redacted_at IS NULL
+                  AND (t.deleted_at IS NOT NULL OR r.deleted_at IS NOT NULL)
+                ORDER BY e.thread_id, e.reply_id NULLS FIRST
+                LIMIT $2
+                "#,
+                mirror_id,
+                limit
+            )
+            .fetch_all(&self.pool)
+            .await?)
         }
-        async fn hard_delete_reply(&self, id: Id) -> RepoResult<()> {
-            // Need to also detach reply's image (image row cascades ON DELETE, but we want to allow external store cleanup)
-            let res = sqlx::query("DELETE FROM replies WHERE id=$1")
-                .bind(id)
-                .execute(&self.pool)
-                .await
-                .map_err(|_| RepoError::NotFound)?;
-            if res.rows_affected() == 0 {
-                return Err(RepoError::NotFound);
-            }
+
+        async fn mark_matrix_event_redacted(
+            &self,
+            mirror_id: Id,
+            event_id: &str,
+        ) -> RepoResult<()> {
+            sqlx::query!(
+                "UPDATE matrix_events SET redacted_at = now() WHERE mirror_id=$1 AND event_id=$2",
+                mirror_id,
+                event_id
+            )
+            .execute(&self.pool)
+            .await?;
             Ok(())
         }
-        async fn get_reply(&self, id: Id) -> RepoResult<Reply> {
-            let rec = sqlx::query_as::<_, Reply>(
+
+        async fn release_matrix_mirror(
+            &self,
+            id: Id,
+            last_thread_id: Id,
+            last_reply_id: Id,
+            sent: bool,
+            error: Option<String>,
+            retry_secs: i64,
+        ) -> RepoResult<()> {
+            sqlx::query!(
                 r#"
-          SELECT r.id, r.thread_id, r.content,
-              img.hash as image_hash, img.mime as mime,
-              r.author_name, r.tripcode, r.created_at, r.deleted_at, r.created_by
-                FROM replies r
-                LEFT JOIN LATERAL (
-                    SELECT i.hash, i.mime
-                    FROM images i
-                    WHERE i.reply_id = r.id
-                    ORDER BY i.id ASC
-                    LIMIT 1
-                ) img ON TRUE
-                WHERE r.id=$1
-            "#,
+                UPDATE matrix_mirrors SET
+                    last_thread_id = GREATEST(last_thread_id, $2),
+                    last_reply_id = GREATEST(last_reply_id, $3),
+                    last_sent_at = CASE WHEN $4 THEN now() ELSE last_sent_at END,
+                    last_error = CASE WHEN $5::TEXT IS NOT NULL THEN $5
+                        WHEN $4 THEN NULL ELSE last_error END,
+                    leased_until = CASE WHEN $5::TEXT IS NOT NULL
+                        THEN now() + make_interval(secs => $6) END
+                WHERE id = $1
+                "#,
+                id,
+                last_thread_id,
+                last_reply_id,
+                sent,
+                error,
+                retry_secs as f64
             )
-            .bind(id)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|_| RepoError::NotFound)?;
-            Ok(rec)
+            .execute(&self.pool)
+            .await?;
+            Ok(())
         }
     }
 
     #[async_trait]
-    impl RoleRepo for PgRepo {
-        async fn get_subject_role(&self, subject: &str) -> Option<AuthRole> {
-            if let Ok(rec) = sqlx::query("SELECT role FROM user_roles WHERE subject=$1")
-                .bind(subject)
-                .fetch_one(&self.pool)
-                .await
-            {
-                let role: String = rec.get("role");
-                return match role.as_str() {
-                    "admin" => Some(AuthRole::Admin),
-                    "moderator" => Some(AuthRole::Moderator),
-                    "user" => Some(AuthRole::User),
-                    _ => None,
-                };
-            }
-            None
+    impl FederationRepo for PgRepo {
+        async fn add_ap_follower(&self, board_id: Id, follower: NewApFollower) -> RepoResult<()> {
+            let mut tx = self.pool.begin().await?;
+            sqlx::query!(
+                r#"
+                INSERT INTO ap_followers (board_id, actor, inbox, shared_inbox)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (board_id, actor)
+                DO UPDATE SET inbox = EXCLUDED.inbox, shared_inbox = EXCLUDED.shared_inbox
+                "#,
+                board_id,
+                follower.actor,
+                follower.inbox,
+                follower.shared_inbox
+            )
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query!(
+                r#"
+                INSERT INTO ap_board_cursors (board_id, last_thread_id)
+                VALUES ($1, (SELECT COALESCE(max(id), 0) FROM threads))
+                ON CONFLICT (board_id) DO NOTHING
+                "#,
+                board_id
+            )
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            Ok(())
         }
-        async fn set_subject_role(&self, subject: &str, role: AuthRole) -> RepoResult<()> {
-            let role_str = match role {
-                AuthRole::Admin => "admin",
-                AuthRole::Moderator => "moderator",
-                AuthRole::User => "user",
-            };
-            let _ = sqlx::query("INSERT INTO user_roles (subject, role, updated_at) VALUES ($1,$2, now()) ON CONFLICT (subject) DO UPDATE SET role=EXCLUDED.role, updated_at=now()")
-                .bind(subject)
-                .bind(role_str)
-                .execute(&self.pool)
-                .await
-                .map_err(|_| RepoError::Conflict)?;
+
+        async fn remove_ap_follower(&self, board_id: Id, actor: &str) -> RepoResult<()> {
+            sqlx::query!(
+                "DELETE FROM ap_followers WHERE board_id=$1 AND actor=$2",
+                board_id,
+                actor
+            )
+            .execute(&self.pool)
+            .await?;
             Ok(())
         }
-        async fn list_roles(&self) -> RepoResult<Vec<(String, AuthRole)>> {
-            let rows = sqlx::query("SELECT subject, role FROM user_roles ORDER BY subject")
-                .fetch_all(&self.pool)
-                .await
-                .map_err(|_| RepoError::NotFound)?;
-            let mut out = Vec::with_capacity(rows.len());
-            for r in rows {
-                let subject: String = r.get("subject");
-                let role_str: String = r.get("role");
-                if let Some(role) = match role_str.as_str() {
-                    "admin" => Some(AuthRole::Admin),
-                    "moderator" => Some(AuthRole::Moderator),
-                    "user" => Some(AuthRole::User),
-                    _ => None,
-                } {
-                    out.push((subject, role));
-                }
-            }
-            Ok(out)
+
+        async fn count_ap_followers(&self, board_id: Id) -> RepoResult<i64> {
+            Ok(sqlx::query_scalar!(
+                r#"SELECT count(*) as "count!" FROM ap_followers WHERE board_id=$1"#,
+                board_id
+            )
+            .fetch_one(&self.pool)
+            .await?)
         }
-        async fn delete_role(&self, subject: &str) -> RepoResult<()> {
-            let res = sqlx::query("DELETE FROM user_roles WHERE subject=$1")
-                .bind(subject)
-                .execute(&self.pool)
-                .await
-                .map_err(|_| RepoError::NotFound)?;
-            if res.rows_affected() == 0 {
-                return Err(RepoError::NotFound);
-            }
+
+        async fn enqueue_ap_delivery(
+            &self,
+            board_id: Id,
+            inbox: &str,
+            activity: Value,
+        ) -> RepoResult<()> {
+            sqlx::query!(
+                "INSERT INTO ap_deliveries (board_id, inbox, activity) VALUES ($1, $2, $3)",
+                board_id,
+                inbox,
+                activity
+            )
+            .execute(&self.pool)
+            .await?;
             Ok(())
         }
-    } // end impl RoleRepo
 
-    #[async_trait]
-    impl ImageRepo for PgRepo {
-        async fn list_board_image_hashes(&self, board_id: Id) -> RepoResult<Vec<String>> {
-            sqlx::query_scalar(
+        async fn pending_ap_threads(
+            &self,
+            limit: i64,
+            settle_secs: i64,
+        ) -> RepoResult<Vec<Thread>> {
+            Ok(sqlx::query_as!(
+                Thread,
                 r#"
-                SELECT DISTINCT i.hash
-                FROM images i
-                LEFT JOIN threads direct_thread ON direct_thread.id = i.thread_id
-                LEFT JOIN replies r ON r.id = i.reply_id
-                LEFT JOIN threads reply_thread ON reply_thread.id = r.thread_id
-                WHERE direct_thread.board_id = $1 OR reply_thread.board_id = $1
+                SELECT t.id, t.board_id, t.subject, t.body, t.created_at, t.bump_time, t.created_by,
+                    author_profile(t.created_by) as "author: sqlx::types::Json<AuthorProfile>",
+                    img.hash as "image_hash?", img.mime as "mime?", img.size_bytes as "image_size?", t.author_name, t.tripcode, t.deleted_at, t.closed_at, t.archived_at, t.pinned_reply_id, t.tags,
+                    t.reply_count, t.image_count, t.slug
+                FROM ap_board_cursors c
+                JOIN boards b ON b.id = c.board_id AND b.deleted_at IS NULL AND NOT b.beta
+                JOIN threads t ON t.board_id = c.board_id AND t.id > c.last_thread_id
+                LEFT JOIN LATERAL (
+                    SELECT i.hash, i.mime, i.size_bytes FROM images i WHERE i.thread_id = t.id ORDER BY i.id ASC LIMIT 1
+                ) img ON TRUE
+                WHERE t.deleted_at IS NULL AND t.held_at IS NULL
+                  AND t.created_at <= now() - make_interval(secs => $2)
+                  AND EXISTS (SELECT 1 FROM ap_followers f WHERE f.board_id = c.board_id)
+                ORDER BY t.id
+                LIMIT $1
                 "#,
+                limit,
+                settle_secs as f64
             )
-            .bind(board_id)
             .fetch_all(&self.pool)
-            .await
-            .map_err(|_| RepoError::NotFound)
+            .await?)
         }
 
-        async fn list_thread_image_hashes(&self, thread_id: Id) -> RepoResult<Vec<String>> {
-            sqlx::query_scalar(
+        async fn fan_out_ap_thread(&self, thread: &Thread, activity: Value) -> RepoResult<bool> {
+            let mut tx = self.pool.begin().await?;
+            let advanced = sqlx::query!(
+                "UPDATE ap_board_cursors SET last_thread_id = $2 WHERE board_id = $1 AND last_thread_id < $2",
+                thread.board_id,
+                thread.id
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+                == 1;
+            if advanced {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO ap_deliveries (board_id, inbox, activity)
+                    SELECT DISTINCT $1::BIGINT, COALESCE(shared_inbox, inbox), $2::JSONB
+                    FROM ap_followers WHERE board_id = $1
+                    "#,
+                    thread.board_id,
+                    activity
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+            tx.commit().await?;
+            Ok(advanced)
+        }
+
+        async fn claim_ap_deliveries(
+            &self,
+            limit: i64,
+            lease_secs: i64,
+        ) -> RepoResult<Vec<ApDelivery>> {
+            // SKIP LOCKED and the lease let several replicas deliver.
+            Ok(sqlx::query_as!(
+                ApDelivery,
                 r#"
-                SELECT DISTINCT i.hash
-                FROM images i
-                LEFT JOIN replies r ON r.id = i.reply_id
-                WHERE i.thread_id = $1 OR r.thread_id = $1
+                UPDATE ap_deliveries SET next_attempt_at = now() + make_interval(secs => $2)
+                WHERE id IN (
+                    SELECT id FROM ap_deliveries
+                    WHERE next_attempt_at <= now()
+                    ORDER BY id
+                    LIMIT $1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id, board_id, inbox, activity, attempts
                 "#,
+                limit,
+                lease_secs as f64
             )
-            .bind(thread_id)
             .fetch_all(&self.pool)
-            .await
-            .map_err(|_| RepoError::NotFound)
+            .await?)
         }
 
-        async fn is_image_referenced(&self, hash: &str) -> RepoResult<bool> {
-            sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM images WHERE hash=$1)")
-                .bind(hash)
-                .fetch_one(&self.pool)
-                .await
-                .map_err(|_| RepoError::NotFound)
+        async fn finish_ap_delivery(
+            &self,
+            id: Id,
+            error: Option<String>,
+            retry_secs: i64,
+            max_attempts: i32,
+        ) -> RepoResult<()> {
+            match error {
+                None => {
+                    sqlx::query!("DELETE FROM ap_deliveries WHERE id=$1", id)
+                        .execute(&self.pool)
+                        .await?;
+                }
+                Some(error) => {
+                    let mut tx = self.pool.begin().await?;
+                    let attempts = sqlx::query_scalar!(
+                        r#"
+                        UPDATE ap_deliveries SET
+                            attempts = attempts + 1,
+                            last_error = $2,
+                            next_attempt_at = now() + make_interval(secs => $3)
+                        WHERE id = $1
+                        RETURNING attempts
+                        "#,
+                        id,
+                        error,
+                        retry_secs as f64
+                    )
+                    .fetch_optional(&mut *tx)
+                    .await?;
+                    if attempts.is_some_and(|attempts| attempts >= max_attempts) {
+                        sqlx::query!("DELETE FROM ap_deliveries WHERE id=$1", id)
+                            .execute(&mut *tx)
+                            .await?;
+                    }
+                    tx.commit().await?;
+                }
+            }
+            Ok(())
         }
     }
 
     #[async_trait]
-    impl BanRepo for PgRepo {
-        async fn is_subject_banned(&self, subject: &str) -> RepoResult<bool> {
-            sqlx::query_scalar(
-                "SELECT EXISTS(SELECT 1 FROM subject_bans WHERE subject=$1 AND (expires_at IS NULL OR expires_at > now()))",
+    impl TransferRepo for PgRepo {
+        async fn list_images_after(&self, after_id: Id, limit: i64) -> RepoResult<Vec<Image>> {
+            Ok(sqlx::query_as!(
+                Image,
+                "SELECT id, thread_id, reply_id, hash, mime FROM images WHERE id > $1 ORDER BY id LIMIT $2",
+                after_id,
+                limit
             )
-            .bind(subject)
-            .fetch_one(&self.pool)
-            .await
-            .map_err(|_| RepoError::NotFound)
+            .fetch_all(&self.pool)
+            .await?)
         }
 
-        async fn create_subject_ban(
+        async fn import_dump(
             &self,
-            new: NewSubjectBan,
-            banned_by: &str,
-        ) -> RepoResult<SubjectBan> {
-            sqlx::query_as::<_, SubjectBan>(
+            dump: &Dump,
+            options: ImportOptions,
+        ) -> RepoResult<ImportReport> {
+            let strategy = options.on_conflict;
+            let mut report = ImportReport {
+                dry_run: options.dry_run,
+                on_conflict: strategy,
+                ..Default::default()
+            };
+            let mut tx = self.pool.begin().await?;
+
+            for role in &dump.roles {
+                let existing = sqlx::query_scalar!(
+                    "SELECT role FROM user_roles WHERE subject=$1",
+                    role.subject
+                )
+                .fetch_optional(&mut *tx)
+                .await?;
+                match (existing, strategy) {
+                    (Some(current), _) if current == role.role => report.roles.skipped += 1,
+                    (Some(_), ConflictStrategy::Skip) => report.roles.skipped += 1,
+                    (Some(current), ConflictStrategy::Fail) => {
+                        report.conflicts.push(format!(
+                            "role for {} is {current}, dump has {}",
+                            role.subject, role.role
+                        ));
+                        report.roles.skipped += 1;
+                    }
+                    (existing, _) => {
+                        sqlx::query!(
+                            "INSERT INTO user_roles (subject, role, updated_at) VALUES ($1,$2, now()) ON CONFLICT (subject) DO UPDATE SET role=EXCLUDED.role, expires_at=NULL, updated_at=now()",
+                            role.subject,
+                            role.role
+                        )
+                        .execute(&mut *tx)
+                        .await?;
+                        if existing.is_some() {
+                            report.roles.merged += 1;
+                        } else {
+                            report.roles.created += 1;
+                        }
+                    }
+                }
+            }
+
+            // Source id -> id in this database. Rows whose parent was skipped are skipped too.
+            let mut boards = HashMap::new();
+            for board in &dump.boards {
+                let existing =
+                    sqlx::query_scalar!("SELECT id FROM boards WHERE slug=$1", board.slug)
+                        .fetch_optional(&mut *tx)
+                        .await?;
+                match (existing, strategy) {
+                    (Some(id), ConflictStrategy::Merge) => {
+                        boards.insert(board.id, id);
+                        report.boards.merged += 1;
+                    }
+                    (Some(_), ConflictStrategy::Fail) => {
+                        report
+                            .conflicts
+                            .push(format!("board slug '{}' already exists", board.slug));
+                        report.boards.skipped += 1;
+                    }
+                    (Some(_), ConflictStrategy::Skip) => report.boards.skipped += 1,
+                    (None, _) => {
+                        let id = sqlx::query_scalar!(
+                            "INSERT INTO boards (slug, title, created_at, deleted_at) VALUES ($1,$2,$3,$4) RETURNING id",
+                            board.slug,
+                            board.title,
+                            board.created_at,
+                            board.deleted_at
+                        )
+                        .fetch_one(&mut *tx)
+                        .await?;
+                        boards.insert(board.id, id);
+                        report.boards.created += 1;
+                    }
+                }
+            }
+
+            let mut threads = HashMap::new();
+            for thread in &dump.threads {
+                let Some(board_id) = boards.get(&thread.board_id) else {
+                    report.threads.skipped += 1;
+                    continue;
+                };
+                let id = sqlx::query_scalar!(
+                    r#"INSERT INTO threads (board_id, subject, body, created_by, author_name, tripcode, created_at, bump_time, deleted_at)
+                    VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9) RETURNING id"#,
+                    board_id,
+                    thread.subject,
+                    thread.body,
+                    thread.created_by,
+                    thread.author_name,
+                    thread.tripcode,
+                    thread.created_at,
+                    thread.bump_time,
+                    thread.deleted_at
+                )
+                .fetch_one(&mut *tx)
+                .await?;
+                threads.insert(thread.id, id);
+                report.threads.created += 1;
+            }
+
+            let mut replies = HashMap::new();
+            for reply in &dump.replies {
+                let Some(thread_id) = threads.get(&reply.thread_id) else {
+                    report.replies.skipped += 1;
+                    continue;
+                };
+                let id = sqlx::query_scalar!(
+                    r#"INSERT INTO replies (thread_id, content, created_by, author_name, tripcode, created_at, deleted_at)
+                    VALUES ($1,$2,$3,$4,$5,$6,$7) RETURNING id"#,
+                    thread_id,
+                    reply.content,
+                    reply.created_by,
+                    reply.author_name,
+                    reply.tripcode,
+                    reply.created_at,
+                    reply.deleted_at
+                )
+                .fetch_one(&mut *tx)
+                .await?;
+                replies.insert(reply.id, id);
+                report.replies.created += 1;
+            }
+
+            for image in &dump.images {
+                let owner = match (image.thread_id, image.reply_id) {
+                    (Some(id), _) => threads.get(&id).copied().map(ImageOwner::Thread),
+                    (_, Some(id)) => replies.get(&id).copied().map(ImageOwner::Reply),
+                    _ => None,
+                };
+                // Hashes may be shared between posts; each imported post is a new owner.
+                let Some(owner) = owner else {
+                    report.images.skipped += 1;
+                    continue;
+                };
+                insert_image(&mut tx, owner, &image.hash, &image.mime).await?;
+                report.images.created += 1;
+            }
+
+            // Imported history is not replayed through the outbox: webhooks and
+            // live clients only see new activity.
+            report.committed = !options.dry_run && report.conflicts.is_empty();
+            if report.committed {
+                tx.commit().await?;
+            } else {
+                tx.rollback().await?;
+            }
+            Ok(report)
+        }
+    }
+
+    fn service_account(
+        id: Id,
+        name: String,
+        scopes: Vec<String>,
+        created_by: String,
+        created_at: DateTime<Utc>,
+        last_used_at: Option<DateTime<Utc>>,
+        revoked_at: Option<DateTime<Utc>>,
+    ) -> ServiceAccount {
+        ServiceAccount {
+            id,
+            name,
+            scopes: scopes
+                .iter()
+                .filter_map(|s| ServiceScope::parse(s))
+                .collect(),
+            created_by,
+            created_at,
+            last_used_at,
+            revoked_at,
+        }
+    }
+
+    #[async_trait]
+    impl ServiceAccountRepo for PgRepo {
+        async fn create_service_account(
+            &self,
+            new: &NewServiceAccount,
+            token_hash: &str,
+            created_by: &str,
+        ) -> RepoResult<ServiceAccount> {
+            let scopes: Vec<String> = new.scopes.iter().map(|s| s.as_str().into()).collect();
+            let r = sqlx::query!(
                 r#"
-                INSERT INTO subject_bans (subject, reason, banned_by, expires_at)
+                INSERT INTO service_accounts (name, scopes, token_hash, created_by)
                 VALUES ($1, $2, $3, $4)
-                ON CONFLICT (subject) DO UPDATE SET
-                    reason = EXCLUDED.reason,
-                    banned_by = EXCLUDED.banned_by,
-                    created_at = now(),
-                    expires_at = EXCLUDED.expires_at
-                RETURNING subject, reason, banned_by, created_at, expires_at
+                RETURNING id, name, scopes, created_by, created_at, last_used_at, revoked_at
                 "#,
+                new.name,
+                &scopes,
+                token_hash,
+                created_by
             )
-            .bind(&new.subject)
-            .bind(&new.reason)
-            .bind(banned_by)
-            .bind(new.expires_at)
             .fetch_one(&self.pool)
-            .await
-            .map_err(|_| RepoError::Conflict)
+            .await?;
+            Ok(service_account(
+                r.id,
+                r.name,
+                r.scopes,
+                r.created_by,
+                r.created_at,
+                r.last_used_at,
+                r.revoked_at,
+            ))
         }
-
-        async fn list_subject_bans(&self) -> RepoResult<Vec<SubjectBan>> {
-            sqlx::query_as::<_, SubjectBan>(
+        async fn list_service_accounts(&self) -> RepoResult<Vec<ServiceAccount>> {
+            let rows = sqlx::query!(
                 r#"
-                SELECT subject, reason, banned_by, created_at, expires_at
-                FROM subject_bans
-                WHERE expires_at IS NULL OR expires_at > now()
-                ORDER BY created_at DESC
-                "#,
+                SELECT id, name, scopes, created_by, created_at, last_used_at, revoked_at
+                FROM service_accounts
+                ORDER BY name
+                "#
             )
             .fetch_all(&self.pool)
-            .await
-            .map_err(|_| RepoError::NotFound)
+            .await?;
+            Ok(rows
+                .into_iter()
+                .map(|r| {
+                    service_account(
+                        r.id,
+                        r.name,
+                        r.scopes,
+                        r.created_by,
+                        r.created_at,
+                        r.last_used_at,
+                        r.revoked_at,
+                    )
+                })
+                .collect())
         }
-
-        async fn delete_subject_ban(&self, subject: &str) -> RepoResult<()> {
-            let result = sqlx::query("DELETE FROM subject_bans WHERE subject=$1")
-                .bind(subject)
-                .execute(&self.pool)
-                .await
-                .map_err(|_| RepoError::NotFound)?;
-            if result.rows_affected() == 0 {
+        async fn revoke_service_account(&self, id: Id) -> RepoResult<()> {
+            let res = sqlx::query!(
+                "UPDATE service_accounts SET revoked_at = now() WHERE id = $1 AND revoked_at IS NULL",
+                id
+            )
+            .execute(&self.pool)
+            .await?;
+            if res.rows_affected() == 0 {
                 return Err(RepoError::NotFound);
             }
             Ok(())
         }
+        async fn use_service_account(
+            &self,
+            token_hash: &str,
+        ) -> RepoResult<Option<ServiceAccount>> {
+            let row = sqlx::query!(
+                r#"
+                UPDATE service_accounts SET last_used_at = now()
+                WHERE token_hash = $1 AND revoked_at IS NULL
+                RETURNING id, name, scopes, created_by, created_at, last_used_at, revoked_at
+                "#,
+                token_hash
+            )
+            .fetch_optional(&self.pool)
+            .await?;
+            Ok(row.map(|r| {
+                service_account(
+                    r.id,
+                    r.name,
+                    r.scopes,
+                    r.created_by,
+                    r.created_at,
+                    r.last_used_at,
+                    r.revoked_at,
+                )
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl UnitOfWork for PgRepo {
+        async fn begin(&self) -> RepoResult<Box<dyn RepoTx>> {
+            Ok(Box::new(PgTx {
+                tx: self.pool.begin().await?,
+            }))
+        }
+    }
+
+    #[async_trait]
+    impl SearchRepo for PgRepo {
+        async fn search_posts(
+            &self,
+            query: &str,
+            board_id: Option<Id>,
+            excluded_boards: &[Id],
+            limit: i64,
+        ) -> RepoResult<Vec<SearchHit>> {
+            self.read(|pool| async move {
+                sqlx::query_as!(
+                    SearchHit,
+                    r#"
+                WITH q AS (SELECT websearch_to_tsquery('simple', $1) AS query)
+                SELECT kind as "kind!", id as "id!", thread_id as "thread_id!",
+                    board_id as "board_id!", excerpt as "excerpt!", created_at as "created_at!"
+                FROM (
+                    SELECT 'thread' AS kind, t.id, t.id AS thread_id, t.board_id,
+                        left(t.subject || ' ' || t.body, 200) AS excerpt, t.created_at,
+                        ts_rank(to_tsvector('simple', t.subject || ' ' || t.body), q.query) AS rank
+                    FROM threads t
+                    JOIN boards b ON b.id = t.board_id
+                    CROSS JOIN q
+                    WHERE to_tsvector('simple', t.subject || ' ' || t.body) @@ q.query
+                      AND t.deleted_at IS NULL AND b.deleted_at IS NULL
+                      AND ($2::BIGINT IS NULL OR t.board_id = $2)
+                      AND t.board_id <> ALL($4)
+                    UNION ALL
+                    SELECT 'reply' AS kind, r.id, r.thread_id, t.board_id,
+                        left(r.content, 200) AS excerpt, r.created_at,
+                        ts_rank(to_tsvector('simple', r.content), q.query) AS rank
+                    FROM replies r
+                    JOIN threads t ON t.id = r.thread_id
+                    JOIN boards b ON b.id = t.board_id
+                    CROSS JOIN q
+                    WHERE to_tsvector('simple', r.content) @@ q.query
+                      AND r.deleted_at IS NULL AND t.deleted_at IS NULL AND b.deleted_at IS NULL
+                      AND ($2::BIGINT IS NULL OR t.board_id = $2)
+                      AND t.board_id <> ALL($4)
+                ) hits
+                ORDER BY rank DESC, created_at DESC, id DESC
+                LIMIT $3
+                "#,
+                    query,
+                    board_id,
+                    limit,
+                    excluded_boards
+                )
+                .fetch_all(&pool)
+                .await
+            })
+            .await
+            .map_err(RepoError::from)
+        }
     }
 } // end pg module
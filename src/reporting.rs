@@ -0,0 +1,183 @@
+use std::sync::OnceLock;
+
+use crate::slow_log::current_request;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Panic,
+    Internal,
+}
+
+/// One reportable failure with the request it happened in, when known.
+#[derive(Debug, Clone)]
+pub struct ErrorEvent {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub method: Option<String>,
+    pub route: Option<String>,
+    pub subject: Option<String>,
+}
+
+impl ErrorEvent {
+    /// Attach the method, route, and subject of the request served on this task.
+    pub fn in_current_request(kind: ErrorKind, message: impl Into<String>) -> Self {
+        let ctx = current_request();
+        Self {
+            kind,
+            message: message.into(),
+            method: ctx.as_ref().map(|c| c.method.clone()),
+            route: ctx.as_ref().map(|c| c.route.clone()),
+            subject: ctx.as_ref().map(|c| c.subject()),
+        }
+    }
+}
+
+/// Destination for panics and internal errors, e.g. Sentry.
+pub trait ErrorReporter: Send + Sync {
+    fn capture(&self, event: &ErrorEvent);
+}
+
+static REPORTER: OnceLock<Box<dyn ErrorReporter>> = OnceLock::new();
+
+/// Install the process-wide reporter; later calls are ignored.
+pub fn install(reporter: Box<dyn ErrorReporter>) {
+    if REPORTER.set(reporter).is_err() {
+        log::warn!("error reporter already installed");
+    }
+}
+
+pub fn report(event: ErrorEvent) {
+    if let Some(reporter) = REPORTER.get() {
+        reporter.capture(&event);
+    }
+}
+
+/// Report panics before they unwind, keeping the default hook's output.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+        let message = match info.location() {
+            Some(loc) => format!("panicked at {}:{}: {payload}", loc.file(), loc.line()),
+            None => format!("panicked: {payload}"),
+        };
+        report(ErrorEvent::in_current_request(ErrorKind::Panic, message));
+        previous(info);
+    }));
+}
+
+/// Reporter backed by the Sentry SDK; events carry release, route, method, and user.
+pub struct SentryReporter;
+
+impl ErrorReporter for SentryReporter {
+    fn capture(&self, event: &ErrorEvent) {
+        let level = match event.kind {
+            ErrorKind::Panic => sentry::Level::Fatal,
+            ErrorKind::Internal => sentry::Level::Error,
+        };
+        sentry::with_scope(
+            |scope| {
+                scope.set_tag(
+                    "kind",
+                    match event.kind {
+                        ErrorKind::Panic => "panic",
+                        ErrorKind::Internal => "internal",
+                    },
+                );
+                if let Some(route) = &event.route {
+                    scope.set_tag("route", route);
+                }
+                if let Some(method) = &event.method {
+                    scope.set_tag("method", method);
+                }
+                if let Some(subject) = &event.subject {
+                    scope.set_user(Some(sentry::User {
+                        id: Some(subject.clone()),
+                        ..Default::default()
+                    }));
+                }
+            },
+            || sentry::capture_message(&event.message, level),
+        );
+    }
+}
+
+/// Initialise Sentry when `SENTRY_DSN` is set. Keep the guard alive for the
+/// process lifetime; dropping it flushes queued events.
+pub fn init_from_env() -> Option<sentry::ClientInitGuard> {
+    let dsn = std::env::var("SENTRY_DSN")
+        .ok()
+        .filter(|v| !v.trim().is_empty())?;
+    let release = std::env::var("SENTRY_RELEASE")
+        .unwrap_or_else(|_| concat!("rib@", env!("CARGO_PKG_VERSION")).to_string());
+    let guard = sentry::init((
+        dsn,
+        sentry::ClientOptions {
+            release: Some(release.into()),
+            environment: std::env::var("SENTRY_ENVIRONMENT").ok().map(Into::into),
+            attach_stacktrace: true,
+            ..Default::default()
+        },
+    ));
+    if !guard.is_enabled() {
+        log::warn!("SENTRY_DSN is set but invalid; error reporting disabled");
+        return None;
+    }
+    install(Box::new(SentryReporter));
+    install_panic_hook();
+    Some(guard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ApiError;
+    use crate::slow_log::{SlowLogConfig, SlowRequestLog};
+    use actix_web::{test, web, App};
+    use std::sync::{Arc, Mutex};
+
+    struct Captured(Arc<Mutex<Vec<ErrorEvent>>>);
+
+    impl ErrorReporter for Captured {
+        fn capture(&self, event: &ErrorEvent) {
+            self.0.lock().unwrap().push(event.clone());
+        }
+    }
+
+    #[actix_web::test]
+    async fn internal_errors_are_reported_with_request_context() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        install(Box::new(Captured(events.clone())));
+        let app = test::init_service(
+            App::new()
+                .wrap(SlowRequestLog::new(SlowLogConfig::default()))
+                .route(
+                    "/fail/{id}",
+                    web::post().to(|| async { Err::<String, _>(ApiError::Internal) }),
+                )
+                .route(
+                    "/missing",
+                    web::get().to(|| async { Err::<String, _>(ApiError::NotFound) }),
+                ),
+        )
+        .await;
+        let resp =
+            test::call_service(&app, test::TestRequest::post().uri("/fail/7").to_request()).await;
+        assert_eq!(resp.status(), 500);
+        let resp =
+            test::call_service(&app, test::TestRequest::get().uri("/missing").to_request()).await;
+        assert_eq!(resp.status(), 404);
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, ErrorKind::Internal);
+        assert_eq!(events[0].route.as_deref(), Some("/fail/{id}"));
+        assert_eq!(events[0].method.as_deref(), Some("POST"));
+        assert_eq!(events[0].subject.as_deref(), Some("anonymous"));
+    }
+}
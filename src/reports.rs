@@ -0,0 +1,12 @@
+//! User reports on threads and replies.
+//!
+//! A signed-in user flags a visible post with `POST /api/v1/reports`, once
+//! while their report on it is open. Moderators work the queue under
+//! `/api/v1/admin/reports`; resolving or dismissing a report closes every
+//! open report on the same post, so a post flagged by many users is
+//! decided once.
+
+pub const MAX_REASON_CHARS: usize = 1000;
+pub const MAX_NOTE_CHARS: usize = 1000;
+/// Reports returned per queue page.
+pub const MAX_REPORT_QUEUE: i64 = 100;
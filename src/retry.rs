@@ -0,0 +1,1701 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde_json::Value;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::auth::Role as AuthRole;
+use crate::db::AppliedMigration;
+use crate::models::*;
+use crate::repo::{
+    AppealRepo, BanRepo, BoardRepo, BridgeRepo, FederationRepo, FilterRepo, HoldRepo, ImageRepo,
+    MatrixRepo, ModerationRepo, NotificationRepo, OutboxRepo, PreferenceRepo, ProfileRepo,
+    ReactionRepo, ReplyRepo, Repo, RepoError, RepoResult, RepoTx, ReportRepo, RoleRepo, RowStream,
+    SavedSearchRepo, ScheduleRepo, SchemaRepo, SearchRepo, ServiceAccountRepo, SitemapRepo,
+    ThreadRepo, TransferRepo, TrustRepo, UnitOfWork,
+};
+use crate::sitemap::{SitemapBoard, SitemapThread};
+use crate::slow_log::{self, SlowLogConfig};
+use crate::transfer::{Dump, ImportOptions, ImportReport};
+
+/// Per-operation timeout and retry budget for repository calls.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub timeout: Duration,
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    /// Attempts at least this slow are logged; see [`crate::slow_log`].
+    pub slow_threshold: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            max_retries: 2,
+            base_delay: Duration::from_millis(50),
+            slow_threshold: SlowLogConfig::default().query_threshold,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_env() -> Self {
+        fn u64_env(name: &str) -> Option<u64> {
+            std::env::var(name).ok().and_then(|v| v.parse().ok())
+        }
+        let defaults = Self::default();
+        Self {
+            timeout: u64_env("REPO_TIMEOUT_MS")
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.timeout),
+            max_retries: u64_env("REPO_MAX_RETRIES")
+                .map(|v| v.min(10) as u32)
+                .unwrap_or(defaults.max_retries),
+            base_delay: u64_env("REPO_RETRY_BASE_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.base_delay),
+            slow_threshold: SlowLogConfig::from_env().query_threshold,
+        }
+    }
+
+    /// Full jitter: uniform in [0, base * 2^attempt].
+    fn backoff(&self, attempt: u32) -> Duration {
+        let cap = self.base_delay.saturating_mul(1 << attempt.min(10));
+        let millis = cap.as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=millis))
+    }
+
+    async fn with_timeout<T>(
+        &self,
+        op: &'static str,
+        fut: impl Future<Output = RepoResult<T>>,
+    ) -> RepoResult<T> {
+        let started = Instant::now();
+        match tokio::time::timeout(self.timeout, fut).await {
+            Ok(result) => {
+                slow_log::observe_query(op, started.elapsed(), self.slow_threshold);
+                result
+            }
+            Err(_) => {
+                metrics::increment_counter!("repo_timeouts", "op" => op);
+                Err(RepoError::Unavailable(format!(
+                    "{op} timed out after {:?}",
+                    self.timeout
+                )))
+            }
+        }
+    }
+
+    /// Run an idempotent operation, retrying transient failures.
+    pub async fn retry<T, F, Fut>(&self, op: &'static str, call: F) -> RepoResult<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = RepoResult<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match self.with_timeout(op, call()).await {
+                Err(RepoError::Unavailable(cause) | RepoError::Aborted(cause))
+                    if attempt < self.max_retries =>
+                {
+                    metrics::increment_counter!("repo_retries", "op" => op);
+                    log::debug!("retrying {op} after transient error: {cause}");
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Run a non-idempotent operation. Any other transient error may have
+    /// followed a successful commit, so it is only run again when the
+    /// database rolled it back; see [`Self::rerun_aborted`].
+    pub async fn once<T, F, Fut>(&self, op: &'static str, call: F) -> RepoResult<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = RepoResult<T>>,
+    {
+        self.rerun_aborted(op, || self.with_timeout(op, call()))
+            .await
+    }
+
+    /// Run `call` again, within the retry budget, while a serialization
+    /// failure or deadlock rolls it back. An aborted attempt wrote nothing,
+    /// so this is safe for whole write transactions.
+    pub async fn rerun_aborted<T, F, Fut>(&self, op: &'static str, call: F) -> RepoResult<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = RepoResult<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match call().await {
+                Err(RepoError::Aborted(cause)) if attempt < self.max_retries => {
+                    metrics::increment_counter!("repo_retries", "op" => op);
+                    log::debug!("re-running {op} after it was rolled back: {cause}");
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// `Repo` decorator applying a [`RetryPolicy`] to every operation.
+pub struct ResilientRepo<R> {
+    inner: R,
+    policy: RetryPolicy,
+}
+
+impl<R: Repo> ResilientRepo<R> {
+    pub fn new(inner: R, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<R: Repo> BoardRepo for ResilientRepo<R> {
+    async fn list_boards(&self, include_deleted: bool) -> RepoResult<Vec<Board>> {
+        self.policy
+            .retry("list_boards", || self.inner.list_boards(include_deleted))
+            .await
+    }
+    async fn list_boards_from_primary(&self) -> RepoResult<Vec<Board>> {
+        self.policy
+            .retry("list_boards_from_primary", || {
+                self.inner.list_boards_from_primary()
+            })
+            .await
+    }
+    async fn create_board(&self, new: NewBoard) -> RepoResult<Board> {
+        self.policy
+            .once("create_board", || self.inner.create_board(new.clone()))
+            .await
+    }
+    async fn update_board(&self, id: Id, upd: UpdateBoard) -> RepoResult<Board> {
+        self.policy
+            .retry("update_board", || self.inner.update_board(id, upd.clone()))
+            .await
+    }
+    async fn soft_delete_board(&self, id: Id) -> RepoResult<()> {
+        self.policy
+            .retry("soft_delete_board", || self.inner.soft_delete_board(id))
+            .await
+    }
+    async fn restore_board(&self, id: Id) -> RepoResult<()> {
+        self.policy
+            .retry("restore_board", || self.inner.restore_board(id))
+            .await
+    }
+    async fn hard_delete_board(&self, id: Id) -> RepoResult<()> {
+        // A retry after an unseen commit reports NotFound; never re-run deletes.
+        self.policy
+            .once("hard_delete_board", || self.inner.hard_delete_board(id))
+            .await
+    }
+    async fn get_board(&self, id: Id) -> RepoResult<Board> {
+        self.policy
+            .retry("get_board", || self.inner.get_board(id))
+            .await
+    }
+    async fn get_boards(&self, ids: &[Id]) -> RepoResult<Vec<Board>> {
+        self.policy
+            .retry("get_boards", || self.inner.get_boards(ids))
+            .await
+    }
+    async fn add_board_tester(
+        &self,
+        board_id: Id,
+        subject: &str,
+        added_by: &str,
+    ) -> RepoResult<BoardTester> {
+        self.policy
+            .retry("add_board_tester", || {
+                self.inner.add_board_tester(board_id, subject, added_by)
+            })
+            .await
+    }
+    async fn list_board_testers(&self, board_id: Id) -> RepoResult<Vec<BoardTester>> {
+        self.policy
+            .retry("list_board_testers", || {
+                self.inner.list_board_testers(board_id)
+            })
+            .await
+    }
+    async fn remove_board_tester(&self, board_id: Id, subject: &str) -> RepoResult<()> {
+        self.policy
+            .once("remove_board_tester", || {
+                self.inner.remove_board_tester(board_id, subject)
+            })
+            .await
+    }
+    async fn tester_board_ids(&self, subject: &str) -> RepoResult<Vec<Id>> {
+        self.policy
+            .retry("tester_board_ids", || self.inner.tester_board_ids(subject))
+            .await
+    }
+    async fn board_for_old_slug(&self, slug: &str) -> RepoResult<Option<Id>> {
+        self.policy
+            .retry("board_for_old_slug", || self.inner.board_for_old_slug(slug))
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> ThreadRepo for ResilientRepo<R> {
+    async fn list_threads(
+        &self,
+        board_id: Id,
+        include_deleted: bool,
+        order: ThreadOrder,
+    ) -> RepoResult<Vec<Thread>> {
+        self.policy
+            .retry("list_threads", || {
+                self.inner.list_threads(board_id, include_deleted, order)
+            })
+            .await
+    }
+    fn stream_threads(
+        &self,
+        board_id: Id,
+        include_deleted: bool,
+        order: ThreadOrder,
+    ) -> RowStream<Thread> {
+        self.inner.stream_threads(board_id, include_deleted, order)
+    }
+    async fn create_thread(
+        &self,
+        new: NewThread,
+        created_by: Value,
+        public_identity: PublicIdentity,
+    ) -> RepoResult<Thread> {
+        self.policy
+            .once("create_thread", || {
+                self.inner
+                    .create_thread(new.clone(), created_by.clone(), public_identity.clone())
+            })
+            .await
+    }
+    async fn get_thread(&self, id: Id) -> RepoResult<Thread> {
+        self.policy
+            .retry("get_thread", || self.inner.get_thread(id))
+            .await
+    }
+    async fn list_archived_threads(&self, board_id: Id, limit: i64) -> RepoResult<Vec<Thread>> {
+        self.policy
+            .retry("list_archived_threads", || {
+                self.inner.list_archived_threads(board_id, limit)
+            })
+            .await
+    }
+    async fn list_tagged_threads(
+        &self,
+        board_id: Id,
+        tag: &str,
+        include_deleted: bool,
+        order: ThreadOrder,
+    ) -> RepoResult<Vec<Thread>> {
+        self.policy
+            .retry("list_tagged_threads", || {
+                self.inner
+                    .list_tagged_threads(board_id, tag, include_deleted, order)
+            })
+            .await
+    }
+    async fn tag_counts(&self, board_id: Id) -> RepoResult<Vec<TagCount>> {
+        self.policy
+            .retry("tag_counts", || self.inner.tag_counts(board_id))
+            .await
+    }
+    async fn get_threads(&self, ids: &[Id]) -> RepoResult<Vec<Thread>> {
+        self.policy
+            .retry("get_threads", || self.inner.get_threads(ids))
+            .await
+    }
+    async fn soft_delete_thread(&self, id: Id) -> RepoResult<()> {
+        // Soft deletes record outbox events; re-running one would emit a duplicate.
+        self.policy
+            .once("soft_delete_thread", || self.inner.soft_delete_thread(id))
+            .await
+    }
+    async fn restore_thread(&self, id: Id) -> RepoResult<()> {
+        self.policy
+            .retry("restore_thread", || self.inner.restore_thread(id))
+            .await
+    }
+    async fn hard_delete_thread(&self, id: Id) -> RepoResult<()> {
+        self.policy
+            .once("hard_delete_thread", || self.inner.hard_delete_thread(id))
+            .await
+    }
+    async fn thread_delete_password(&self, id: Id) -> RepoResult<Option<String>> {
+        self.policy
+            .retry("thread_delete_password", || {
+                self.inner.thread_delete_password(id)
+            })
+            .await
+    }
+    async fn thread_had_slug(&self, id: Id, slug: &str) -> RepoResult<bool> {
+        self.policy
+            .retry("thread_had_slug", || self.inner.thread_had_slug(id, slug))
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> ReplyRepo for ResilientRepo<R> {
+    async fn list_replies(
+        &self,
+        thread_id: Id,
+        include_deleted: bool,
+        order: SortOrder,
+    ) -> RepoResult<Vec<Reply>> {
+        self.policy
+            .retry("list_replies", || {
+                self.inner.list_replies(thread_id, include_deleted, order)
+            })
+            .await
+    }
+    fn stream_replies(
+        &self,
+        thread_id: Id,
+        include_deleted: bool,
+        order: SortOrder,
+    ) -> RowStream<Reply> {
+        self.inner.stream_replies(thread_id, include_deleted, order)
+    }
+    async fn list_replies_for_threads(
+        &self,
+        thread_ids: &[Id],
+        include_deleted: bool,
+    ) -> RepoResult<Vec<Reply>> {
+        self.policy
+            .retry("list_replies_for_threads", || {
+                self.inner
+                    .list_replies_for_threads(thread_ids, include_deleted)
+            })
+            .await
+    }
+    async fn list_first_replies(
+        &self,
+        thread_ids: &[Id],
+        per_thread: i64,
+    ) -> RepoResult<Vec<Reply>> {
+        self.policy
+            .retry("list_first_replies", || {
+                self.inner.list_first_replies(thread_ids, per_thread)
+            })
+            .await
+    }
+    async fn create_reply(
+        &self,
+        new: NewReply,
+        created_by: Value,
+        public_identity: PublicIdentity,
+    ) -> RepoResult<Reply> {
+        self.policy
+            .once("create_reply", || {
+                self.inner
+                    .create_reply(new.clone(), created_by.clone(), public_identity.clone())
+            })
+            .await
+    }
+    async fn last_reply_at(
+        &self,
+        thread_id: Id,
+        subject: &str,
+    ) -> RepoResult<Option<DateTime<Utc>>> {
+        self.policy
+            .retry("last_reply_at", || {
+                self.inner.last_reply_at(thread_id, subject)
+            })
+            .await
+    }
+    async fn soft_delete_reply(&self, id: Id) -> RepoResult<()> {
+        self.policy
+            .once("soft_delete_reply", || self.inner.soft_delete_reply(id))
+            .await
+    }
+    async fn restore_reply(&self, id: Id) -> RepoResult<()> {
+        self.policy
+            .retry("restore_reply", || self.inner.restore_reply(id))
+            .await
+    }
+    async fn hard_delete_reply(&self, id: Id) -> RepoResult<()> {
+        self.policy
+            .once("hard_delete_reply", || self.inner.hard_delete_reply(id))
+            .await
+    }
+    async fn get_reply(&self, id: Id) -> RepoResult<Reply> {
+        self.policy
+            .retry("get_reply", || self.inner.get_reply(id))
+            .await
+    }
+    async fn reply_delete_password(&self, id: Id) -> RepoResult<Option<String>> {
+        self.policy
+            .retry("reply_delete_password", || {
+                self.inner.reply_delete_password(id)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> RoleRepo for ResilientRepo<R> {
+    async fn get_subject_role(&self, subject: &str) -> Option<AuthRole> {
+        // Errors already collapse to `None` (no elevated role) in the inner repo.
+        self.inner.get_subject_role(subject).await
+    }
+    async fn set_subject_role(&self, subject: &str, role: AuthRole) -> RepoResult<()> {
+        self.policy
+            .retry("set_subject_role", || {
+                self.inner.set_subject_role(subject, role.clone())
+            })
+            .await
+    }
+    async fn set_subject_role_until(
+        &self,
+        subject: &str,
+        role: AuthRole,
+        expires_at: DateTime<Utc>,
+    ) -> RepoResult<()> {
+        self.policy
+            .retry("set_subject_role_until", || {
+                self.inner
+                    .set_subject_role_until(subject, role.clone(), expires_at)
+            })
+            .await
+    }
+    async fn list_roles(&self) -> RepoResult<Vec<(String, AuthRole, Option<DateTime<Utc>>)>> {
+        self.policy
+            .retry("list_roles", || self.inner.list_roles())
+            .await
+    }
+    async fn delete_role(&self, subject: &str) -> RepoResult<()> {
+        self.policy
+            .once("delete_role", || self.inner.delete_role(subject))
+            .await
+    }
+    async fn demote_expired_roles(&self) -> RepoResult<Vec<RoleChange>> {
+        self.policy
+            .once("demote_expired_roles", || self.inner.demote_expired_roles())
+            .await
+    }
+    async fn record_role_change(&self, change: NewRoleChange) -> RepoResult<RoleChange> {
+        self.policy
+            .once("record_role_change", || {
+                self.inner.record_role_change(change.clone())
+            })
+            .await
+    }
+    async fn record_impersonation(&self, entry: NewImpersonationEntry) -> RepoResult<()> {
+        self.policy
+            .once("record_impersonation", || {
+                self.inner.record_impersonation(entry.clone())
+            })
+            .await
+    }
+    async fn list_impersonations(
+        &self,
+        subject: Option<&str>,
+        limit: i64,
+    ) -> RepoResult<Vec<ImpersonationEntry>> {
+        self.policy
+            .retry("list_impersonations", || {
+                self.inner.list_impersonations(subject, limit)
+            })
+            .await
+    }
+    async fn role_history(&self, subject: &str, limit: i64) -> RepoResult<Vec<RoleChange>> {
+        self.policy
+            .retry("role_history", || self.inner.role_history(subject, limit))
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> ImageRepo for ResilientRepo<R> {
+    async fn list_board_image_hashes(&self, board_id: Id) -> RepoResult<Vec<String>> {
+        self.policy
+            .retry("list_board_image_hashes", || {
+                self.inner.list_board_image_hashes(board_id)
+            })
+            .await
+    }
+    async fn list_thread_image_hashes(&self, thread_id: Id) -> RepoResult<Vec<String>> {
+        self.policy
+            .retry("list_thread_image_hashes", || {
+                self.inner.list_thread_image_hashes(thread_id)
+            })
+            .await
+    }
+    async fn is_image_referenced(&self, hash: &str) -> RepoResult<bool> {
+        self.policy
+            .retry("is_image_referenced", || {
+                self.inner.is_image_referenced(hash)
+            })
+            .await
+    }
+    async fn image_references(&self, hash: &str, limit: i64) -> RepoResult<Vec<ImageReference>> {
+        self.policy
+            .retry("image_references", || {
+                self.inner.image_references(hash, limit)
+            })
+            .await
+    }
+    async fn record_upload(&self, upload: &NewUpload) -> RepoResult<()> {
+        self.policy
+            .once("record_upload", || self.inner.record_upload(upload))
+            .await
+    }
+    async fn upload_usage(&self, subject: &str, since: DateTime<Utc>) -> RepoResult<UploadUsage> {
+        self.policy
+            .retry("upload_usage", || self.inner.upload_usage(subject, since))
+            .await
+    }
+    async fn image_uploads(&self, hash: &str) -> RepoResult<Vec<UploadRecord>> {
+        self.policy
+            .retry("image_uploads", || self.inner.image_uploads(hash))
+            .await
+    }
+    async fn image_attachments(&self, hash: &str) -> RepoResult<Vec<AttachedPost>> {
+        self.policy
+            .retry("image_attachments", || self.inner.image_attachments(hash))
+            .await
+    }
+    async fn list_uploads_by(&self, uploader: &str, limit: i64) -> RepoResult<Vec<UploadRecord>> {
+        self.policy
+            .retry("list_uploads_by", || {
+                self.inner.list_uploads_by(uploader, limit)
+            })
+            .await
+    }
+    async fn take_down_image(&self, hash: &str) -> RepoResult<u64> {
+        self.policy
+            .once("take_down_image", || self.inner.take_down_image(hash))
+            .await
+    }
+    async fn quarantine_image(&self, hash: &str, reason: &str, source: &str) -> RepoResult<()> {
+        self.policy
+            .retry("quarantine_image", || {
+                self.inner.quarantine_image(hash, reason, source)
+            })
+            .await
+    }
+    async fn get_quarantined_image(&self, hash: &str) -> RepoResult<Option<QuarantinedImage>> {
+        self.policy
+            .retry("get_quarantined_image", || {
+                self.inner.get_quarantined_image(hash)
+            })
+            .await
+    }
+    async fn list_quarantined_images(&self, limit: i64) -> RepoResult<Vec<QuarantinedImage>> {
+        self.policy
+            .retry("list_quarantined_images", || {
+                self.inner.list_quarantined_images(limit)
+            })
+            .await
+    }
+    async fn release_quarantined_image(&self, hash: &str) -> RepoResult<()> {
+        self.policy
+            .once("release_quarantined_image", || {
+                self.inner.release_quarantined_image(hash)
+            })
+            .await
+    }
+    async fn destroy_quarantined_image(&self, hash: &str) -> RepoResult<()> {
+        self.policy
+            .once("destroy_quarantined_image", || {
+                self.inner.destroy_quarantined_image(hash)
+            })
+            .await
+    }
+
+    async fn blobs_to_archive(&self, limit: i64) -> RepoResult<Vec<String>> {
+        self.policy
+            .retry("blobs_to_archive", || self.inner.blobs_to_archive(limit))
+            .await
+    }
+
+    async fn blobs_to_restore(&self, limit: i64) -> RepoResult<Vec<String>> {
+        self.policy
+            .retry("blobs_to_restore", || self.inner.blobs_to_restore(limit))
+            .await
+    }
+
+    async fn set_blob_archived(&self, hash: &str, archived: bool) -> RepoResult<()> {
+        self.policy
+            .retry("set_blob_archived", || {
+                self.inner.set_blob_archived(hash, archived)
+            })
+            .await
+    }
+
+    async fn set_image_thumbnail(&self, hash: &str, thumbnail_hash: &str) -> RepoResult<()> {
+        self.policy
+            .retry("set_image_thumbnail", || {
+                self.inner.set_image_thumbnail(hash, thumbnail_hash)
+            })
+            .await
+    }
+
+    async fn image_thumbnail(&self, hash: &str) -> RepoResult<Option<String>> {
+        self.policy
+            .retry("image_thumbnail", || self.inner.image_thumbnail(hash))
+            .await
+    }
+
+    async fn set_archive_listing(&self, hash: &str, listing: &ArchiveListing) -> RepoResult<()> {
+        self.policy
+            .retry("set_archive_listing", || {
+                self.inner.set_archive_listing(hash, listing)
+            })
+            .await
+    }
+
+    async fn archive_listing(&self, hash: &str) -> RepoResult<Option<ArchiveListing>> {
+        self.policy
+            .retry("archive_listing", || self.inner.archive_listing(hash))
+            .await
+    }
+
+    async fn set_text_excerpt(&self, hash: &str, excerpt: &TextExcerpt) -> RepoResult<()> {
+        self.policy
+            .retry("set_text_excerpt", || {
+                self.inner.set_text_excerpt(hash, excerpt)
+            })
+            .await
+    }
+
+    async fn text_excerpt(&self, hash: &str) -> RepoResult<Option<TextExcerpt>> {
+        self.policy
+            .retry("text_excerpt", || self.inner.text_excerpt(hash))
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> BanRepo for ResilientRepo<R> {
+    async fn active_subject_ban(&self, subject: &str) -> RepoResult<Option<BanNotice>> {
+        self.policy
+            .retry("active_subject_ban", || {
+                self.inner.active_subject_ban(subject)
+            })
+            .await
+    }
+    async fn create_subject_ban(
+        &self,
+        new: NewSubjectBan,
+        banned_by: &str,
+    ) -> RepoResult<SubjectBan> {
+        self.policy
+            .once("create_subject_ban", || {
+                self.inner.create_subject_ban(new.clone(), banned_by)
+            })
+            .await
+    }
+    async fn list_subject_bans(&self) -> RepoResult<Vec<SubjectBan>> {
+        self.policy
+            .retry("list_subject_bans", || self.inner.list_subject_bans())
+            .await
+    }
+    async fn delete_subject_ban(&self, subject: &str) -> RepoResult<()> {
+        self.policy
+            .once("delete_subject_ban", || {
+                self.inner.delete_subject_ban(subject)
+            })
+            .await
+    }
+    async fn active_ip_ban(&self, ip: &str) -> RepoResult<Option<BanNotice>> {
+        self.policy
+            .retry("active_ip_ban", || self.inner.active_ip_ban(ip))
+            .await
+    }
+    async fn create_ip_ban(&self, new: NewIpBan, banned_by: &str) -> RepoResult<IpBan> {
+        self.policy
+            .once("create_ip_ban", || {
+                self.inner.create_ip_ban(new.clone(), banned_by)
+            })
+            .await
+    }
+    async fn list_ip_bans(&self) -> RepoResult<Vec<IpBan>> {
+        self.policy
+            .retry("list_ip_bans", || self.inner.list_ip_bans())
+            .await
+    }
+    async fn delete_ip_ban(&self, id: Id) -> RepoResult<()> {
+        self.policy
+            .once("delete_ip_ban", || self.inner.delete_ip_ban(id))
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> OutboxRepo for ResilientRepo<R> {
+    async fn claim_outbox_events(
+        &self,
+        limit: i64,
+        lease_secs: i64,
+    ) -> RepoResult<Vec<OutboxEvent>> {
+        // The relay polls again shortly; leased rows are never retried blindly.
+        self.policy
+            .once("claim_outbox_events", || {
+                self.inner.claim_outbox_events(limit, lease_secs)
+            })
+            .await
+    }
+    async fn mark_outbox_delivered(&self, id: Id) -> RepoResult<()> {
+        self.policy
+            .retry("mark_outbox_delivered", || {
+                self.inner.mark_outbox_delivered(id)
+            })
+            .await
+    }
+    async fn mark_outbox_failed(&self, id: Id, error: &str, retry_in_secs: i64) -> RepoResult<()> {
+        self.policy
+            .once("mark_outbox_failed", || {
+                self.inner.mark_outbox_failed(id, error, retry_in_secs)
+            })
+            .await
+    }
+    async fn prune_delivered_outbox(&self, older_than_secs: i64) -> RepoResult<u64> {
+        self.policy
+            .retry("prune_delivered_outbox", || {
+                self.inner.prune_delivered_outbox(older_than_secs)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> SearchRepo for ResilientRepo<R> {
+    async fn search_posts(
+        &self,
+        query: &str,
+        board_id: Option<Id>,
+        excluded_boards: &[Id],
+        limit: i64,
+    ) -> RepoResult<Vec<SearchHit>> {
+        self.policy
+            .retry("search_posts", || {
+                self.inner
+                    .search_posts(query, board_id, excluded_boards, limit)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> TransferRepo for ResilientRepo<R> {
+    async fn list_images_after(&self, after_id: Id, limit: i64) -> RepoResult<Vec<Image>> {
+        self.policy
+            .retry("list_images_after", || {
+                self.inner.list_images_after(after_id, limit)
+            })
+            .await
+    }
+    async fn import_dump(&self, dump: &Dump, options: ImportOptions) -> RepoResult<ImportReport> {
+        // Bulk imports legitimately outlast the per-operation timeout.
+        self.inner.import_dump(dump, options).await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> SchemaRepo for ResilientRepo<R> {
+    async fn applied_migrations(&self) -> RepoResult<Vec<AppliedMigration>> {
+        self.policy
+            .retry("applied_migrations", || self.inner.applied_migrations())
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> SitemapRepo for ResilientRepo<R> {
+    async fn sitemap_boards(&self) -> RepoResult<Vec<SitemapBoard>> {
+        self.policy
+            .retry("sitemap_boards", || self.inner.sitemap_boards())
+            .await
+    }
+    async fn count_sitemap_threads(&self, since: DateTime<Utc>) -> RepoResult<i64> {
+        self.policy
+            .retry("count_sitemap_threads", || {
+                self.inner.count_sitemap_threads(since)
+            })
+            .await
+    }
+    async fn list_sitemap_threads(
+        &self,
+        since: DateTime<Utc>,
+        limit: i64,
+        offset: i64,
+    ) -> RepoResult<Vec<SitemapThread>> {
+        self.policy
+            .retry("list_sitemap_threads", || {
+                self.inner.list_sitemap_threads(since, limit, offset)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> NotificationRepo for ResilientRepo<R> {
+    async fn subscribe_thread(&self, subject: &str, thread_id: Id) -> RepoResult<()> {
+        self.policy
+            .retry("subscribe_thread", || {
+                self.inner.subscribe_thread(subject, thread_id)
+            })
+            .await
+    }
+    async fn unsubscribe_thread(&self, subject: &str, thread_id: Id) -> RepoResult<()> {
+        self.policy
+            .once("unsubscribe_thread", || {
+                self.inner.unsubscribe_thread(subject, thread_id)
+            })
+            .await
+    }
+    async fn list_thread_subscriptions(
+        &self,
+        subject: &str,
+    ) -> RepoResult<Vec<ThreadSubscription>> {
+        self.policy
+            .retry("list_thread_subscriptions", || {
+                self.inner.list_thread_subscriptions(subject)
+            })
+            .await
+    }
+    async fn get_notification_settings(
+        &self,
+        subject: &str,
+    ) -> RepoResult<Option<NotificationSettings>> {
+        self.policy
+            .retry("get_notification_settings", || {
+                self.inner.get_notification_settings(subject)
+            })
+            .await
+    }
+    async fn put_notification_settings(
+        &self,
+        subject: &str,
+        update: UpdateNotificationSettings,
+    ) -> RepoResult<NotificationSettings> {
+        self.policy
+            .retry("put_notification_settings", || {
+                self.inner
+                    .put_notification_settings(subject, update.clone())
+            })
+            .await
+    }
+    async fn verify_notification_email(&self, subject: &str, email: &str) -> RepoResult<bool> {
+        self.policy
+            .retry("verify_notification_email", || {
+                self.inner.verify_notification_email(subject, email)
+            })
+            .await
+    }
+    async fn disable_digests(&self, subject: &str) -> RepoResult<()> {
+        self.policy
+            .retry("disable_digests", || self.inner.disable_digests(subject))
+            .await
+    }
+    async fn due_digest_recipients(
+        &self,
+        digest: DigestFrequency,
+        min_interval_secs: i64,
+        limit: i64,
+    ) -> RepoResult<Vec<DigestRecipient>> {
+        self.policy
+            .retry("due_digest_recipients", || {
+                self.inner
+                    .due_digest_recipients(digest, min_interval_secs, limit)
+            })
+            .await
+    }
+    async fn digest_entries(&self, subject: &str, limit: i64) -> RepoResult<Vec<DigestEntry>> {
+        self.policy
+            .retry("digest_entries", || {
+                self.inner.digest_entries(subject, limit)
+            })
+            .await
+    }
+    async fn search_digest_entries(
+        &self,
+        subject: &str,
+        limit: i64,
+    ) -> RepoResult<Vec<SearchDigestEntry>> {
+        self.policy
+            .retry("search_digest_entries", || {
+                self.inner.search_digest_entries(subject, limit)
+            })
+            .await
+    }
+    async fn mark_digest_sent(
+        &self,
+        subject: &str,
+        watermarks: &[(Id, Id)],
+        match_ids: &[Id],
+    ) -> RepoResult<()> {
+        self.policy
+            .retry("mark_digest_sent", || {
+                self.inner.mark_digest_sent(subject, watermarks, match_ids)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> PreferenceRepo for ResilientRepo<R> {
+    async fn get_preferences(&self, subject: &str) -> RepoResult<Option<Value>> {
+        self.policy
+            .retry("get_preferences", || self.inner.get_preferences(subject))
+            .await
+    }
+    async fn put_preferences(&self, subject: &str, preferences: Value) -> RepoResult<()> {
+        self.policy
+            .retry("put_preferences", || {
+                self.inner.put_preferences(subject, preferences.clone())
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> FilterRepo for ResilientRepo<R> {
+    async fn list_filters(&self, subject: &str) -> RepoResult<Vec<UserFilter>> {
+        self.policy
+            .retry("list_filters", || self.inner.list_filters(subject))
+            .await
+    }
+    async fn create_filter(
+        &self,
+        subject: &str,
+        new: NewUserFilter,
+        target_subject: Option<String>,
+    ) -> RepoResult<UserFilter> {
+        self.policy
+            .once("create_filter", || {
+                self.inner
+                    .create_filter(subject, new.clone(), target_subject.clone())
+            })
+            .await
+    }
+    async fn delete_filter(&self, subject: &str, id: Id) -> RepoResult<()> {
+        self.policy
+            .once("delete_filter", || self.inner.delete_filter(subject, id))
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> ProfileRepo for ResilientRepo<R> {
+    async fn get_profile(&self, subject: &str) -> RepoResult<Option<AuthorProfile>> {
+        self.policy
+            .retry("get_profile", || self.inner.get_profile(subject))
+            .await
+    }
+    async fn set_display_name(
+        &self,
+        subject: &str,
+        display_name: Option<&str>,
+    ) -> RepoResult<AuthorProfile> {
+        self.policy
+            .retry("set_display_name", || {
+                self.inner.set_display_name(subject, display_name)
+            })
+            .await
+    }
+    async fn set_avatar(
+        &self,
+        subject: &str,
+        avatar: Option<(&str, &str)>,
+    ) -> RepoResult<AuthorProfile> {
+        self.policy
+            .retry("set_avatar", || self.inner.set_avatar(subject, avatar))
+            .await
+    }
+    async fn reset_profile(&self, subject: &str) -> RepoResult<()> {
+        self.policy
+            .once("reset_profile", || self.inner.reset_profile(subject))
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> ModerationRepo for ResilientRepo<R> {
+    async fn list_moderation_log(
+        &self,
+        thread_id: Option<Id>,
+        limit: i64,
+    ) -> RepoResult<Vec<ModerationEntry>> {
+        self.policy
+            .retry("list_moderation_log", || {
+                self.inner.list_moderation_log(thread_id, limit)
+            })
+            .await
+    }
+    async fn record_deletion(&self, entry: NewDeletionEntry) -> RepoResult<()> {
+        self.policy
+            .once("record_deletion", || {
+                self.inner.record_deletion(entry.clone())
+            })
+            .await
+    }
+    async fn list_deletion_log(
+        &self,
+        kind: Option<DeletionKind>,
+        limit: i64,
+    ) -> RepoResult<Vec<DeletionEntry>> {
+        self.policy
+            .retry("list_deletion_log", || {
+                self.inner.list_deletion_log(kind, limit)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> SavedSearchRepo for ResilientRepo<R> {
+    async fn list_saved_searches(&self, subject: &str) -> RepoResult<Vec<SavedSearch>> {
+        self.policy
+            .retry("list_saved_searches", || {
+                self.inner.list_saved_searches(subject)
+            })
+            .await
+    }
+    async fn create_saved_search(
+        &self,
+        subject: &str,
+        new: NewSavedSearch,
+    ) -> RepoResult<SavedSearch> {
+        self.policy
+            .once("create_saved_search", || {
+                self.inner.create_saved_search(subject, new.clone())
+            })
+            .await
+    }
+    async fn delete_saved_search(&self, subject: &str, id: Id) -> RepoResult<()> {
+        self.policy
+            .once("delete_saved_search", || {
+                self.inner.delete_saved_search(subject, id)
+            })
+            .await
+    }
+    async fn list_saved_search_matches(
+        &self,
+        subject: &str,
+        id: Id,
+        limit: i64,
+    ) -> RepoResult<Vec<SavedSearchMatch>> {
+        self.policy
+            .retry("list_saved_search_matches", || {
+                self.inner.list_saved_search_matches(subject, id, limit)
+            })
+            .await
+    }
+    async fn match_saved_searches(&self, limit: i64, settle_secs: i64) -> RepoResult<u64> {
+        // Each search commits on its own; the next pass picks up the rest.
+        self.policy
+            .once("match_saved_searches", || {
+                self.inner.match_saved_searches(limit, settle_secs)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> TrustRepo for ResilientRepo<R> {
+    async fn subject_trust(&self, subject: &str) -> RepoResult<Option<SubjectTrust>> {
+        self.policy
+            .retry("subject_trust", || self.inner.subject_trust(subject))
+            .await
+    }
+    async fn list_held_posts(&self, limit: i64) -> RepoResult<Vec<HeldPost>> {
+        self.policy
+            .retry("list_held_posts", || self.inner.list_held_posts(limit))
+            .await
+    }
+    async fn release_held_thread(&self, id: Id, approve: bool) -> RepoResult<()> {
+        self.policy
+            .once("release_held_thread", || {
+                self.inner.release_held_thread(id, approve)
+            })
+            .await
+    }
+    async fn release_held_reply(&self, id: Id, approve: bool) -> RepoResult<()> {
+        self.policy
+            .once("release_held_reply", || {
+                self.inner.release_held_reply(id, approve)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> HoldRepo for ResilientRepo<R> {
+    async fn place_legal_hold(&self, new: NewLegalHold, placed_by: &str) -> RepoResult<LegalHold> {
+        self.policy
+            .once("place_legal_hold", || {
+                self.inner.place_legal_hold(new.clone(), placed_by)
+            })
+            .await
+    }
+    async fn release_legal_hold(
+        &self,
+        id: Id,
+        released_by: &str,
+        reason: &str,
+    ) -> RepoResult<LegalHold> {
+        self.policy
+            .once("release_legal_hold", || {
+                self.inner.release_legal_hold(id, released_by, reason)
+            })
+            .await
+    }
+    async fn list_legal_holds(
+        &self,
+        include_released: bool,
+        limit: i64,
+    ) -> RepoResult<Vec<LegalHold>> {
+        self.policy
+            .retry("list_legal_holds", || {
+                self.inner.list_legal_holds(include_released, limit)
+            })
+            .await
+    }
+    async fn image_legal_hold(&self, hash: &str) -> RepoResult<Option<LegalHold>> {
+        self.policy
+            .retry("image_legal_hold", || self.inner.image_legal_hold(hash))
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> MatrixRepo for ResilientRepo<R> {
+    async fn create_matrix_mirror(
+        &self,
+        new: NewMatrixMirror,
+        created_by: &str,
+    ) -> RepoResult<MatrixMirror> {
+        self.policy
+            .once("create_matrix_mirror", || {
+                self.inner.create_matrix_mirror(new.clone(), created_by)
+            })
+            .await
+    }
+    async fn list_matrix_mirrors(&self) -> RepoResult<Vec<MatrixMirror>> {
+        self.policy
+            .retry("list_matrix_mirrors", || self.inner.list_matrix_mirrors())
+            .await
+    }
+    async fn set_matrix_mirror_enabled(&self, id: Id, enabled: bool) -> RepoResult<MatrixMirror> {
+        self.policy
+            .retry("set_matrix_mirror_enabled", || {
+                self.inner.set_matrix_mirror_enabled(id, enabled)
+            })
+            .await
+    }
+    async fn delete_matrix_mirror(&self, id: Id) -> RepoResult<()> {
+        self.policy
+            .once("delete_matrix_mirror", || {
+                self.inner.delete_matrix_mirror(id)
+            })
+            .await
+    }
+    async fn claim_matrix_mirrors(
+        &self,
+        limit: i64,
+        lease_secs: i64,
+    ) -> RepoResult<Vec<MatrixMirror>> {
+        // A lost claim only delays the mirrors until the lease runs out.
+        self.policy
+            .once("claim_matrix_mirrors", || {
+                self.inner.claim_matrix_mirrors(limit, lease_secs)
+            })
+            .await
+    }
+    async fn pending_matrix_posts(
+        &self,
+        mirror: &MatrixMirror,
+        limit: i64,
+        settle_secs: i64,
+    ) -> RepoResult<Vec<MatrixPost>> {
+        self.policy
+            .retry("pending_matrix_posts", || {
+                self.inner.pending_matrix_posts(mirror, limit, settle_secs)
+            })
+            .await
+    }
+    async fn record_matrix_event(
+        &self,
+        mirror_id: Id,
+        post: &MatrixPost,
+        event_id: &str,
+    ) -> RepoResult<()> {
+        self.policy
+            .retry("record_matrix_event", || {
+                self.inner.record_matrix_event(mirror_id, post, event_id)
+            })
+            .await
+    }
+    async fn pending_matrix_redactions(
+        &self,
+        mirror_id: Id,
+        limit: i64,
+    ) -> RepoResult<Vec<MatrixEvent>> {
+        self.policy
+            .retry("pending_matrix_redactions", || {
+                self.inner.pending_matrix_redactions(mirror_id, limit)
+            })
+            .await
+    }
+    async fn mark_matrix_event_redacted(&self, mirror_id: Id, event_id: &str) -> RepoResult<()> {
+        self.policy
+            .retry("mark_matrix_event_redacted", || {
+                self.inner.mark_matrix_event_redacted(mirror_id, event_id)
+            })
+            .await
+    }
+    async fn release_matrix_mirror(
+        &self,
+        id: Id,
+        last_thread_id: Id,
+        last_reply_id: Id,
+        sent: bool,
+        error: Option<String>,
+        retry_secs: i64,
+    ) -> RepoResult<()> {
+        self.policy
+            .retry("release_matrix_mirror", || {
+                self.inner.release_matrix_mirror(
+                    id,
+                    last_thread_id,
+                    last_reply_id,
+                    sent,
+                    error.clone(),
+                    retry_secs,
+                )
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> FederationRepo for ResilientRepo<R> {
+    async fn add_ap_follower(&self, board_id: Id, follower: NewApFollower) -> RepoResult<()> {
+        self.policy
+            .once("add_ap_follower", || {
+                self.inner.add_ap_follower(board_id, follower.clone())
+            })
+            .await
+    }
+    async fn remove_ap_follower(&self, board_id: Id, actor: &str) -> RepoResult<()> {
+        self.policy
+            .retry("remove_ap_follower", || {
+                self.inner.remove_ap_follower(board_id, actor)
+            })
+            .await
+    }
+    async fn count_ap_followers(&self, board_id: Id) -> RepoResult<i64> {
+        self.policy
+            .retry("count_ap_followers", || {
+                self.inner.count_ap_followers(board_id)
+            })
+            .await
+    }
+    async fn enqueue_ap_delivery(
+        &self,
+        board_id: Id,
+        inbox: &str,
+        activity: Value,
+    ) -> RepoResult<()> {
+        self.policy
+            .once("enqueue_ap_delivery", || {
+                self.inner
+                    .enqueue_ap_delivery(board_id, inbox, activity.clone())
+            })
+            .await
+    }
+    async fn pending_ap_threads(&self, limit: i64, settle_secs: i64) -> RepoResult<Vec<Thread>> {
+        self.policy
+            .retry("pending_ap_threads", || {
+                self.inner.pending_ap_threads(limit, settle_secs)
+            })
+            .await
+    }
+    async fn fan_out_ap_thread(&self, thread: &Thread, activity: Value) -> RepoResult<bool> {
+        self.policy
+            .once("fan_out_ap_thread", || {
+                self.inner.fan_out_ap_thread(thread, activity.clone())
+            })
+            .await
+    }
+    async fn claim_ap_deliveries(
+        &self,
+        limit: i64,
+        lease_secs: i64,
+    ) -> RepoResult<Vec<ApDelivery>> {
+        // A lost claim only delays the deliveries until the lease runs out.
+        self.policy
+            .once("claim_ap_deliveries", || {
+                self.inner.claim_ap_deliveries(limit, lease_secs)
+            })
+            .await
+    }
+    async fn finish_ap_delivery(
+        &self,
+        id: Id,
+        error: Option<String>,
+        retry_secs: i64,
+        max_attempts: i32,
+    ) -> RepoResult<()> {
+        self.policy
+            .once("finish_ap_delivery", || {
+                self.inner
+                    .finish_ap_delivery(id, error.clone(), retry_secs, max_attempts)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> BridgeRepo for ResilientRepo<R> {
+    async fn create_board_bridge(
+        &self,
+        board_id: Id,
+        new: NewBoardBridge,
+        created_by: &str,
+    ) -> RepoResult<BoardBridge> {
+        self.policy
+            .once("create_board_bridge", || {
+                self.inner
+                    .create_board_bridge(board_id, new.clone(), created_by)
+            })
+            .await
+    }
+    async fn list_board_bridges(&self, board_id: Option<Id>) -> RepoResult<Vec<BoardBridge>> {
+        self.policy
+            .retry("list_board_bridges", || {
+                self.inner.list_board_bridges(board_id)
+            })
+            .await
+    }
+    async fn set_board_bridge_enabled(&self, id: Id, enabled: bool) -> RepoResult<BoardBridge> {
+        self.policy
+            .once("set_board_bridge_enabled", || {
+                self.inner.set_board_bridge_enabled(id, enabled)
+            })
+            .await
+    }
+    async fn delete_board_bridge(&self, id: Id) -> RepoResult<()> {
+        self.policy
+            .once("delete_board_bridge", || self.inner.delete_board_bridge(id))
+            .await
+    }
+    async fn claim_board_bridges(
+        &self,
+        limit: i64,
+        lease_secs: i64,
+    ) -> RepoResult<Vec<BoardBridge>> {
+        // A lost claim only delays the bridges until the lease runs out.
+        self.policy
+            .once("claim_board_bridges", || {
+                self.inner.claim_board_bridges(limit, lease_secs)
+            })
+            .await
+    }
+    async fn pending_bridge_threads(
+        &self,
+        bridge: &BoardBridge,
+        limit: i64,
+        settle_secs: i64,
+    ) -> RepoResult<Vec<Thread>> {
+        self.policy
+            .retry("pending_bridge_threads", || {
+                self.inner
+                    .pending_bridge_threads(bridge, limit, settle_secs)
+            })
+            .await
+    }
+    async fn release_board_bridge(
+        &self,
+        id: Id,
+        last_thread_id: Id,
+        sent: i32,
+        error: Option<String>,
+        retry_secs: i64,
+    ) -> RepoResult<()> {
+        self.policy
+            .once("release_board_bridge", || {
+                self.inner
+                    .release_board_bridge(id, last_thread_id, sent, error.clone(), retry_secs)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> AppealRepo for ResilientRepo<R> {
+    async fn create_appeal(&self, subject: &str, new: NewAppeal) -> RepoResult<Appeal> {
+        self.policy
+            .once("create_appeal", || {
+                self.inner.create_appeal(subject, new.clone())
+            })
+            .await
+    }
+    async fn list_appeals(&self, status: AppealStatus, limit: i64) -> RepoResult<Vec<Appeal>> {
+        self.policy
+            .retry("list_appeals", || self.inner.list_appeals(status, limit))
+            .await
+    }
+    async fn list_subject_appeals(&self, subject: &str) -> RepoResult<Vec<Appeal>> {
+        self.policy
+            .retry("list_subject_appeals", || {
+                self.inner.list_subject_appeals(subject)
+            })
+            .await
+    }
+    async fn decide_appeal(
+        &self,
+        id: Id,
+        decided_by: &str,
+        status: AppealStatus,
+        note: Option<String>,
+    ) -> RepoResult<Appeal> {
+        self.policy
+            .once("decide_appeal", || {
+                self.inner
+                    .decide_appeal(id, decided_by, status, note.clone())
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> ReportRepo for ResilientRepo<R> {
+    async fn create_report(&self, reporter: &str, new: NewReport) -> RepoResult<Report> {
+        self.policy
+            .once("create_report", || {
+                self.inner.create_report(reporter, new.clone())
+            })
+            .await
+    }
+    async fn list_reports(&self, status: ReportStatus, limit: i64) -> RepoResult<Vec<Report>> {
+        self.policy
+            .retry("list_reports", || self.inner.list_reports(status, limit))
+            .await
+    }
+    async fn decide_report(
+        &self,
+        id: Id,
+        decided_by: &str,
+        status: ReportStatus,
+        note: Option<String>,
+    ) -> RepoResult<Report> {
+        self.policy
+            .once("decide_report", || {
+                self.inner
+                    .decide_report(id, decided_by, status, note.clone())
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> ReactionRepo for ResilientRepo<R> {
+    async fn set_reaction(
+        &self,
+        reply_id: Id,
+        subject: &str,
+        emoji: &str,
+    ) -> RepoResult<Vec<ReactionCount>> {
+        // An upsert of the same row; safe to repeat.
+        self.policy
+            .retry("set_reaction", || {
+                self.inner.set_reaction(reply_id, subject, emoji)
+            })
+            .await
+    }
+    async fn clear_reaction(&self, reply_id: Id, subject: &str) -> RepoResult<Vec<ReactionCount>> {
+        self.policy
+            .retry("clear_reaction", || {
+                self.inner.clear_reaction(reply_id, subject)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> ScheduleRepo for ResilientRepo<R> {
+    async fn create_scheduled_thread(
+        &self,
+        new: NewScheduledThread,
+        created_by: Value,
+    ) -> RepoResult<ScheduledThread> {
+        self.policy
+            .once("create_scheduled_thread", || {
+                self.inner
+                    .create_scheduled_thread(new.clone(), created_by.clone())
+            })
+            .await
+    }
+    async fn list_scheduled_threads(&self) -> RepoResult<Vec<ScheduledThread>> {
+        self.policy
+            .retry("list_scheduled_threads", || {
+                self.inner.list_scheduled_threads()
+            })
+            .await
+    }
+    async fn cancel_scheduled_thread(&self, id: Id) -> RepoResult<()> {
+        self.policy
+            .once("cancel_scheduled_thread", || {
+                self.inner.cancel_scheduled_thread(id)
+            })
+            .await
+    }
+    async fn publish_due_threads(&self, limit: i64) -> RepoResult<Vec<Thread>> {
+        // Each schedule commits on its own; the next poll picks up the rest.
+        self.policy
+            .once("publish_due_threads", || {
+                self.inner.publish_due_threads(limit)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> ServiceAccountRepo for ResilientRepo<R> {
+    async fn create_service_account(
+        &self,
+        new: &NewServiceAccount,
+        token_hash: &str,
+        created_by: &str,
+    ) -> RepoResult<ServiceAccount> {
+        self.policy
+            .once("create_service_account", || {
+                self.inner
+                    .create_service_account(new, token_hash, created_by)
+            })
+            .await
+    }
+    async fn list_service_accounts(&self) -> RepoResult<Vec<ServiceAccount>> {
+        self.policy
+            .retry("list_service_accounts", || {
+                self.inner.list_service_accounts()
+            })
+            .await
+    }
+    async fn revoke_service_account(&self, id: Id) -> RepoResult<()> {
+        self.policy
+            .once("revoke_service_account", || {
+                self.inner.revoke_service_account(id)
+            })
+            .await
+    }
+    async fn use_service_account(&self, token_hash: &str) -> RepoResult<Option<ServiceAccount>> {
+        self.policy
+            .retry("use_service_account", || {
+                self.inner.use_service_account(token_hash)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl<R: Repo> UnitOfWork for ResilientRepo<R> {
+    async fn begin(&self) -> RepoResult<Box<dyn RepoTx>> {
+        // Statements inside the transaction are bounded by the statement timeout;
+        // `transaction` re-runs a rolled-back unit of work under `retry_policy`.
+        self.policy.retry("begin", || self.inner.begin()).await
+    }
+    fn retry_policy(&self) -> Option<&RetryPolicy> {
+        Some(&self.policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            timeout: Duration::from_millis(50),
+            max_retries: 2,
+            base_delay: Duration::from_millis(1),
+            slow_threshold: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn transient_errors_are_retried_until_budget_is_spent() {
+        let calls = AtomicU32::new(0);
+        let result: RepoResult<()> = fast_policy()
+            .retry("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(RepoError::Unavailable("reset".into()))
+            })
+            .await;
+        assert!(matches!(result, Err(RepoError::Unavailable(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[actix_web::test]
+    async fn permanent_errors_and_successes_are_not_retried() {
+        let calls = AtomicU32::new(0);
+        let result: RepoResult<()> = fast_policy()
+            .retry("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(RepoError::NotFound)
+            })
+            .await;
+        assert!(matches!(result, Err(RepoError::NotFound)));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[actix_web::test]
+    async fn slow_operations_time_out_as_unavailable() {
+        let result = fast_policy()
+            .once("test", || async {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Ok(())
+            })
+            .await;
+        assert!(matches!(result, Err(RepoError::Unavailable(_))));
+    }
+
+    #[actix_web::test]
+    async fn writes_rolled_back_by_the_database_run_once_more() {
+        let calls = AtomicU32::new(0);
+        let result = fast_policy()
+            .once("test", || async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(RepoError::Aborted("could not serialize access".into())),
+                    _ => Ok(()),
+                }
+            })
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_web::test]
+    async fn writes_are_not_rerun_after_other_transient_errors() {
+        let calls = AtomicU32::new(0);
+        let result: RepoResult<()> = fast_policy()
+            .once("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(RepoError::Unavailable("connection reset".into()))
+            })
+            .await;
+        assert!(matches!(result, Err(RepoError::Unavailable(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,89 @@
+//! Demoting temporary role assignments.
+//!
+//! `POST /api/v1/admin/roles` may give a moderator or admin role an
+//! `expires_at`. Lookups already treat a lapsed role as `user`; this runner
+//! makes it so in `user_roles`, records each demotion in the role history and
+//! mails the subject when a mailer is configured. Tokens issued before the
+//! expiry keep their roles until they expire themselves.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::mailer::Mailer;
+use crate::repo::Repo;
+
+#[derive(Clone, Debug)]
+pub struct RoleExpiryConfig {
+    pub enabled: bool,
+    pub poll_interval: Duration,
+}
+
+impl RoleExpiryConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: std::env::var("ROLE_EXPIRY_ENABLED")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(true),
+            poll_interval: Duration::from_secs(
+                std::env::var("ROLE_EXPIRY_POLL_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(60u64)
+                    .max(1),
+            ),
+        }
+    }
+}
+
+/// Polls for lapsed role assignments and demotes them to `user`.
+#[derive(Clone)]
+pub struct RoleExpiryRunner {
+    repo: Arc<dyn Repo>,
+    mailer: Option<Arc<dyn Mailer>>,
+    cfg: RoleExpiryConfig,
+}
+
+impl RoleExpiryRunner {
+    pub fn new(
+        repo: Arc<dyn Repo>,
+        mailer: Option<Arc<dyn Mailer>>,
+        cfg: RoleExpiryConfig,
+    ) -> Self {
+        Self { repo, mailer, cfg }
+    }
+
+    /// Demote every lapsed assignment; returns how many were demoted.
+    pub async fn run_once(&self) -> usize {
+        let demoted = match self.repo.demote_expired_roles().await {
+            Ok(demoted) => demoted,
+            Err(e) => {
+                log::error!("role expiry failed: {e}");
+                return 0;
+            }
+        };
+        for change in &demoted {
+            log::info!(
+                "role {} of {} expired; demoted to user",
+                change.from_role.as_deref().unwrap_or("user"),
+                change.subject
+            );
+            if let Some(mailer) = &self.mailer {
+                crate::roles::notify(&*self.repo, &**mailer, change).await;
+            }
+        }
+        metrics::counter!("roles_expired", demoted.len() as u64);
+        demoted.len()
+    }
+
+    /// Spawn the polling loop on the current runtime.
+    pub fn spawn(self) {
+        crate::system::job_started("role_expiry", self.cfg.poll_interval);
+        actix_web::rt::spawn(async move {
+            loop {
+                self.run_once().await;
+                crate::system::job_ran("role_expiry");
+                tokio::time::sleep(self.cfg.poll_interval).await;
+            }
+        });
+    }
+}
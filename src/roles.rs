@@ -0,0 +1,126 @@
+//! Role assignment history and change notices.
+//!
+//! Every change made through `/api/v1/admin/roles`, and every demotion by
+//! the role expiry job, is kept in `role_changes` and listed at
+//! `GET /api/v1/admin/roles/{subject}/history`. The affected subject is
+//! mailed when they have a confirmed notification address. Seeding and
+//! role imports write `user_roles` directly and are not recorded.
+
+use crate::mailer::{Email, Mailer};
+use crate::models::{NotificationSettings, RoleChange};
+use crate::repo::Repo;
+
+pub const MAX_ROLE_REASON_CHARS: usize = 500;
+/// Changes returned per history page.
+pub const MAX_ROLE_HISTORY: i64 = 500;
+
+fn role_name(role: Option<&str>) -> &str {
+    role.unwrap_or("user")
+}
+
+/// Notice of a role change for the affected subject.
+pub fn change_email(change: &RoleChange, to: &str) -> Email {
+    let to_role = role_name(change.to_role.as_deref());
+    let mut body = if change.changed_by.is_none() {
+        format!(
+            "Your temporary {} role has expired; you are now a {to_role}.\n",
+            role_name(change.from_role.as_deref())
+        )
+    } else if change.to_role.is_none() {
+        format!(
+            "An administrator removed your {} role; you are now a user.\n",
+            role_name(change.from_role.as_deref())
+        )
+    } else {
+        format!("An administrator made you a {to_role}.\n")
+    };
+    if let Some(expires_at) = change.expires_at {
+        body.push_str(&format!(
+            "\nThe role lapses back to user at {}.\n",
+            expires_at.format("%Y-%m-%d %H:%M UTC")
+        ));
+    }
+    if let Some(reason) = change
+        .reason
+        .as_deref()
+        .filter(|_| change.changed_by.is_some())
+    {
+        body.push_str(&format!("\nReason:\n{reason}\n"));
+    }
+    Email {
+        to: to.into(),
+        subject: "Your role has changed".into(),
+        body,
+        unsubscribe_url: None,
+    }
+}
+
+/// Mail `change` to its subject's confirmed address, if any. Failures are
+/// logged; the change itself stands.
+pub async fn notify(repo: &dyn Repo, mailer: &dyn Mailer, change: &RoleChange) {
+    let settings = match repo.get_notification_settings(&change.subject).await {
+        Ok(settings) => settings,
+        Err(e) => {
+            log::warn!(
+                "loading notification settings for role change {} failed: {e}",
+                change.id
+            );
+            return;
+        }
+    };
+    if let Some(NotificationSettings {
+        email: Some(email),
+        email_verified: true,
+        ..
+    }) = settings
+    {
+        if let Err(e) = mailer.send(&change_email(change, &email)).await {
+            log::warn!("sending role change via {} failed: {e}", mailer.name());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn change(
+        changed_by: Option<&str>,
+        from_role: Option<&str>,
+        to_role: Option<&str>,
+        reason: Option<&str>,
+    ) -> RoleChange {
+        RoleChange {
+            id: 1,
+            subject: "discord:1".into(),
+            changed_by: changed_by.map(Into::into),
+            from_role: from_role.map(Into::into),
+            to_role: to_role.map(Into::into),
+            expires_at: None,
+            reason: reason.map(Into::into),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn notices_describe_the_change() {
+        let mut granted = change(Some("admin:admin"), None, Some("moderator"), Some("help"));
+        granted.expires_at = Some(Utc.with_ymd_and_hms(2030, 1, 2, 3, 4, 0).unwrap());
+        let mail = change_email(&granted, "a@b.c");
+        assert_eq!(mail.to, "a@b.c");
+        assert!(mail.body.contains("made you a moderator"));
+        assert!(mail.body.contains("2030-01-02 03:04 UTC"));
+        assert!(mail.body.contains("Reason:\nhelp"));
+
+        let removed = change(Some("admin:admin"), Some("admin"), None, None);
+        let body = change_email(&removed, "a@b.c").body;
+        assert!(body.contains("removed your admin role"));
+        assert!(!body.contains("Reason"));
+
+        let expired = change(None, Some("moderator"), Some("user"), Some("expired"));
+        let body = change_email(&expired, "a@b.c").body;
+        assert!(body.contains("temporary moderator role has expired"));
+        assert!(!body.contains("Reason"));
+    }
+}
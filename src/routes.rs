@@ -7,14 +7,37 @@ use sha2::{Digest, Sha256};
 use std::str::FromStr;
 use std::sync::Arc;
 
+use crate::archive::{ArchiveImportReport, ArchiveImportRequest};
 use crate::auth::{
     clear_oauth_transaction_cookie, clear_session_cookie, consume_oauth_transaction,
     create_oauth_transaction, session_cookie, Auth, Role, OAUTH_TRANSACTION_COOKIE_NAME,
 };
+use crate::cache::BoardCache;
+use crate::challenges::ChallengeStore;
+use crate::db::MigrationStatus;
+use crate::duplicates::{DuplicateConfig, DuplicateGuard};
 use crate::error::ApiError;
+use crate::features::{Feature, FeatureFlags};
+use crate::live::{sse_frame, LiveHub};
+use crate::mailer::Mailer;
 use crate::models::*;
-use crate::repo::Repo;
+use crate::negotiate;
+use crate::pow::{PowConfig, ProofOfWork, POW_HEADER};
+use crate::previews::PreviewRenderer;
+use crate::readiness::Readiness;
+use crate::reload::{ConfigReloader, Reloadable};
+use crate::remote_media::FetchError;
+use crate::repo::{transaction, Repo};
+use crate::search::SearchBackend;
+use crate::service::{self, Poster};
 use crate::storage::{is_valid_content_hash, ImageStore, ImageStoreError};
+use crate::thumbnails::ThumbnailConfig;
+use crate::transfer::{Dump, ExportQuery, ImportOptions};
+use crate::trust::TrustConfig;
+use crate::uploads::{
+    content_mismatch, declared_length, request_exceeds, MismatchPolicy, RolePolicy, UploadConfig,
+    MAX_FILE_NAME_CHARS,
+};
 use actix_web::HttpRequest;
 
 fn trusted_forwarded_ip(value: &str, trusted_hops: usize) -> Option<String> {
@@ -26,13 +49,17 @@ fn trusted_forwarded_ip(value: &str, trusted_hops: usize) -> Option<String> {
     Some(addresses[index].to_string())
 }
 
+/// Whether headers set by the edge proxy (`X-Forwarded-For`, GeoIP) are trusted.
+pub(crate) fn trust_proxy_headers() -> bool {
+    std::env::var("TRUST_PROXY_HEADERS")
+        .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
 // Forwarded headers are security-sensitive and ignored unless the deployment
 // explicitly declares how many downstream proxy entries it trusts.
-fn extract_client_ip(req: &HttpRequest) -> String {
-    let trust_proxy_headers = std::env::var("TRUST_PROXY_HEADERS")
-        .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
-        .unwrap_or(false);
-    if trust_proxy_headers {
+pub(crate) fn extract_client_ip(req: &HttpRequest) -> String {
+    if trust_proxy_headers() {
         let trusted_hops = std::env::var("TRUSTED_PROXY_HOPS")
             .ok()
             .and_then(|value| value.parse().ok())
@@ -52,6 +79,75 @@ fn extract_client_ip(req: &HttpRequest) -> String {
     "unknown".to_string()
 }
 
+/// `?include_deleted=1`, honoured for admins only.
+pub(crate) fn include_deleted(req: &HttpRequest, auth: Option<&Auth>) -> bool {
+    req.query_string().contains("include_deleted=1")
+        && auth.is_some_and(|a| a.0.roles.iter().any(|r| matches!(r, Role::Admin)))
+}
+
+/// The caller's mute list when `?apply_filters=1` is set and they are signed in.
+pub(crate) async fn requested_filters(
+    req: &HttpRequest,
+    auth: Option<&Auth>,
+    data: &AppState,
+) -> Result<Option<crate::filters::FilterSet>, ApiError> {
+    if !req.query_string().contains("apply_filters=1") {
+        return Ok(None);
+    }
+    let Some(subject) = auth.and_then(|a| role_subject_key(&a.0.sub)) else {
+        return Ok(None);
+    };
+    let filters = data.repo.list_filters(&subject).await?;
+    Ok(Some(crate::filters::FilterSet::new(&filters)))
+}
+
+#[derive(serde::Deserialize)]
+struct ThreadSortQuery {
+    sort: Option<ThreadSort>,
+    order: Option<SortOrder>,
+}
+
+#[derive(serde::Deserialize)]
+struct ReplySortQuery {
+    sort: Option<ReplySort>,
+    order: Option<SortOrder>,
+}
+
+/// `?sort=bump_time|created_at|reply_count&order=asc|desc` on thread
+/// listings; most recently bumped first by default.
+pub(crate) fn thread_order(req: &HttpRequest) -> Result<ThreadOrder, ApiError> {
+    let query = web::Query::<ThreadSortQuery>::from_query(req.query_string()).map_err(|_| {
+        ApiError::Invalid(
+            "sort must be bump_time, created_at or reply_count and order asc or desc".into(),
+        )
+    })?;
+    Ok(ThreadOrder {
+        sort: query.sort.unwrap_or_default(),
+        order: query.order.unwrap_or(SortOrder::Desc),
+    })
+}
+
+/// `?sort=created_at&order=asc|desc` on reply listings; oldest first by default.
+pub(crate) fn reply_order(req: &HttpRequest) -> Result<SortOrder, ApiError> {
+    let query = web::Query::<ReplySortQuery>::from_query(req.query_string())
+        .map_err(|_| ApiError::Invalid("sort must be created_at and order asc or desc".into()))?;
+    match query.sort.unwrap_or_default() {
+        ReplySort::CreatedAt => Ok(query.order.unwrap_or(SortOrder::Asc)),
+    }
+}
+
+/// `OPTIONS` route naming the methods a content resource serves. CORS
+/// preflights are answered by the CORS middleware before reaching it.
+pub(crate) fn allow(methods: &'static str) -> actix_web::Route {
+    web::route()
+        .method(actix_web::http::Method::OPTIONS)
+        .to(move || async move {
+            HttpResponse::NoContent()
+                .insert_header(("Allow", methods))
+                .finish()
+        })
+}
+
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/v1")
@@ -60,12 +156,66 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                     .route(web::get().to(list_boards))
                     .route(web::post().to(create_board)),
             )
-            .service(web::resource("/boards/{id}/threads").route(web::get().to(list_threads)))
+            .service(
+                web::resource("/boards/{id}/threads")
+                    .route(web::get().to(list_threads))
+                    .route(web::head().to(list_threads))
+                    .route(allow("GET, HEAD, OPTIONS")),
+            )
+            .service(
+                web::resource("/boards/{id}/archive")
+                    .route(web::get().to(list_archived_threads))
+                    .route(web::head().to(list_archived_threads))
+                    .route(allow("GET, HEAD, OPTIONS")),
+            )
+            .service(web::resource("/boards/{id}/tags").route(web::get().to(list_board_tags)))
+            .service(
+                web::resource("/boards/{board}/threads/{thread}")
+                    .route(web::get().to(get_thread_by_slug))
+                    .route(web::head().to(get_thread_by_slug)),
+            )
             .service(web::resource("/threads").route(web::post().to(create_thread)))
-            .service(web::resource("/threads/{id}").route(web::get().to(get_thread)))
-            .service(web::resource("/threads/{id}/replies").route(web::get().to(list_replies)))
+            .service(
+                web::resource("/threads/{id}")
+                    .route(web::get().to(get_thread))
+                    .route(web::head().to(get_thread))
+                    .route(web::delete().to(delete_thread_with_password))
+                    .route(allow("GET, HEAD, DELETE, OPTIONS")),
+            )
+            .service(
+                web::resource("/threads/{id}/replies")
+                    .route(web::get().to(list_replies))
+                    .route(web::head().to(list_replies))
+                    .route(allow("GET, HEAD, OPTIONS")),
+            )
+            .service(
+                web::resource("/threads/{id}/replies/{reply_id}")
+                    .route(web::delete().to(moderate_delete_reply)),
+            )
+            .service(web::resource("/threads/{id}/close").route(web::post().to(close_thread)))
+            .service(web::resource("/threads/{id}/reopen").route(web::post().to(reopen_thread)))
+            .service(
+                web::resource("/threads/{id}/pinned-reply")
+                    .route(web::put().to(pin_reply))
+                    .route(web::delete().to(unpin_reply)),
+            )
             .service(web::resource("/replies").route(web::post().to(create_reply)))
+            .service(
+                web::resource("/replies/{id}").route(web::delete().to(delete_reply_with_password)),
+            )
+            .service(
+                web::resource("/replies/{id}/reactions")
+                    .route(web::post().to(react_to_reply))
+                    .route(web::delete().to(remove_reaction)),
+            )
+            .service(web::resource("/search").route(web::get().to(search)))
+            .service(web::resource("/pow").route(web::get().to(pow_challenge)))
+            .service(web::resource("/pow/clearance").route(web::post().to(pow_clearance)))
+            .service(web::resource("/version").route(web::get().to(crate::version::version)))
+            .service(web::resource("/batch").route(web::post().to(batch_threads)))
+            .service(web::resource("/live").route(web::get().to(live_events)))
             .service(web::resource("/images").route(web::post().to(upload_image)))
+            .service(web::resource("/images/remote").route(web::post().to(upload_remote_image)))
             .service(web::resource("/boards/{id}").route(web::patch().to(update_board)))
             .service(web::resource("/auth/discord/callback").route(web::get().to(discord_callback)))
             .service(web::resource("/auth/discord/login").route(web::get().to(discord_login)))
@@ -73,6 +223,88 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                 web::resource("/auth/bitcoin/challenge").route(web::post().to(bitcoin_challenge)),
             )
             .service(web::resource("/auth/bitcoin/verify").route(web::post().to(bitcoin_verify)))
+            .service(
+                web::resource("/auth/ethereum/challenge")
+                    .route(web::post().to(crate::ethereum::ethereum_challenge)),
+            )
+            .service(
+                web::resource("/auth/ethereum/verify")
+                    .route(web::post().to(crate::ethereum::ethereum_verify)),
+            )
+            .service(
+                web::resource("/auth/nostr/challenge")
+                    .route(web::post().to(crate::nostr::nostr_challenge)),
+            )
+            .service(
+                web::resource("/auth/nostr/verify")
+                    .route(web::post().to(crate::nostr::nostr_verify)),
+            )
+            .service(
+                web::resource("/threads/{id}/subscription")
+                    .route(web::put().to(subscribe_thread))
+                    .route(web::delete().to(unsubscribe_thread)),
+            )
+            .service(
+                web::resource("/users/me/subscriptions")
+                    .route(web::get().to(list_my_subscriptions)),
+            )
+            .service(
+                web::resource("/users/me/preferences")
+                    .route(web::get().to(get_my_preferences))
+                    .route(web::put().to(put_my_preferences)),
+            )
+            .service(
+                web::resource("/users/me/filters")
+                    .route(web::get().to(list_my_filters))
+                    .route(web::post().to(create_my_filter)),
+            )
+            .service(
+                web::resource("/users/me/filters/{id}").route(web::delete().to(delete_my_filter)),
+            )
+            .service(
+                web::resource("/users/me/saved-searches")
+                    .route(web::get().to(list_my_saved_searches))
+                    .route(web::post().to(create_my_saved_search)),
+            )
+            .service(
+                web::resource("/users/me/saved-searches/{id}")
+                    .route(web::delete().to(delete_my_saved_search)),
+            )
+            .service(
+                web::resource("/users/me/saved-searches/{id}/matches")
+                    .route(web::get().to(list_my_saved_search_matches)),
+            )
+            .service(web::resource("/users/me/appeals").route(web::get().to(list_my_appeals)))
+            .service(web::resource("/appeals").route(web::post().to(file_appeal)))
+            .service(web::resource("/reports").route(web::post().to(file_report)))
+            .service(
+                web::resource("/users/me/profile")
+                    .route(web::get().to(get_my_profile))
+                    .route(web::put().to(put_my_profile)),
+            )
+            .service(
+                web::resource("/users/me/avatar")
+                    .route(web::put().to(put_my_avatar))
+                    .route(web::delete().to(delete_my_avatar)),
+            )
+            .service(
+                web::resource("/users/me/notifications")
+                    .route(web::get().to(get_my_notifications))
+                    .route(web::put().to(put_my_notifications)),
+            )
+            .service(
+                web::resource("/notifications/confirm")
+                    .route(web::get().to(confirm_notification_email)),
+            )
+            .service(
+                web::resource("/notifications/unsubscribe")
+                    .route(web::get().to(unsubscribe_notifications))
+                    .route(web::post().to(unsubscribe_notifications)),
+            )
+            .service(web::resource("/auth/email/start").route(web::post().to(email_login_start)))
+            .service(
+                web::resource("/auth/email/callback").route(web::get().to(email_login_callback)),
+            )
             .service(web::resource("/auth/refresh").route(web::post().to(refresh_token)))
             .service(web::resource("/auth/logout").route(web::post().to(logout)))
             .service(
@@ -81,6 +313,25 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                     .route(web::get().to(list_roles)),
             )
             .service(web::resource("/admin/roles/{subject}").route(web::delete().to(delete_role)))
+            .service(
+                web::resource("/admin/roles/{subject}/history").route(web::get().to(role_history)),
+            )
+            .service(web::resource("/admin/impersonate").route(web::post().to(impersonate)))
+            .service(
+                web::resource("/admin/service-accounts")
+                    .route(web::get().to(list_service_accounts))
+                    .route(web::post().to(create_service_account)),
+            )
+            .service(
+                web::resource("/admin/service-accounts/{id}")
+                    .route(web::delete().to(revoke_service_account)),
+            )
+            .service(
+                web::resource("/service/announcements").route(web::post().to(post_announcement)),
+            )
+            .service(
+                web::resource("/admin/impersonations").route(web::get().to(list_impersonations)),
+            )
             .service(
                 web::resource("/admin/bans")
                     .route(web::post().to(create_subject_ban))
@@ -89,6 +340,71 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .service(
                 web::resource("/admin/bans/{subject}").route(web::delete().to(delete_subject_ban)),
             )
+            .service(
+                web::resource("/admin/ip-bans")
+                    .route(web::post().to(create_ip_ban))
+                    .route(web::get().to(list_ip_bans)),
+            )
+            .service(web::resource("/admin/ip-bans/{id}").route(web::delete().to(delete_ip_ban)))
+            .service(
+                web::resource("/admin/moderation-log").route(web::get().to(list_moderation_log)),
+            )
+            .service(web::resource("/admin/deletion-log").route(web::get().to(list_deletion_log)))
+            .service(web::resource("/admin/appeals").route(web::get().to(list_appeals)))
+            .service(
+                web::resource("/admin/appeals/{id}/accept").route(web::post().to(accept_appeal)),
+            )
+            .service(web::resource("/admin/appeals/{id}/deny").route(web::post().to(deny_appeal)))
+            .service(web::resource("/admin/reports").route(web::get().to(list_reports)))
+            .service(
+                web::resource("/admin/reports/{id}/resolve").route(web::post().to(resolve_report)),
+            )
+            .service(
+                web::resource("/admin/reports/{id}/dismiss").route(web::post().to(dismiss_report)),
+            )
+            .service(
+                web::resource("/admin/scheduled-threads")
+                    .route(web::get().to(list_scheduled_threads))
+                    .route(web::post().to(create_scheduled_thread)),
+            )
+            .service(
+                web::resource("/admin/scheduled-threads/{id}")
+                    .route(web::delete().to(cancel_scheduled_thread)),
+            )
+            .service(
+                web::resource("/admin/boards/{id}/testers")
+                    .route(web::get().to(list_board_testers))
+                    .route(web::post().to(add_board_tester)),
+            )
+            .service(
+                web::resource("/admin/boards/{id}/testers/{subject}")
+                    .route(web::delete().to(remove_board_tester)),
+            )
+            .service(web::resource("/admin/bridges").route(web::get().to(list_board_bridges)))
+            .service(
+                web::resource("/admin/boards/{id}/bridges")
+                    .route(web::get().to(list_board_bridges_of_board))
+                    .route(web::post().to(create_board_bridge)),
+            )
+            .service(
+                web::resource("/admin/bridges/{id}")
+                    .route(web::patch().to(update_board_bridge))
+                    .route(web::delete().to(delete_board_bridge)),
+            )
+            .service(
+                web::resource("/admin/matrix-mirrors")
+                    .route(web::get().to(list_matrix_mirrors))
+                    .route(web::post().to(create_matrix_mirror)),
+            )
+            .service(
+                web::resource("/admin/matrix-mirrors/{id}")
+                    .route(web::patch().to(update_matrix_mirror))
+                    .route(web::delete().to(delete_matrix_mirror)),
+            )
+            .service(
+                web::resource("/admin/profiles/{subject}")
+                    .route(web::delete().to(reset_subject_profile)),
+            )
             .service(
                 web::resource("/admin/threads/{id}/author").route(web::get().to(get_thread_author)),
             )
@@ -96,6 +412,25 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                 web::resource("/admin/replies/{id}/author").route(web::get().to(get_reply_author)),
             )
             .service(web::resource("/auth/me").route(web::get().to(auth_me)))
+            .service(web::resource("/admin/system").route(web::get().to(admin_system)))
+            .service(
+                web::resource("/admin/config/reload").route(web::post().to(admin_reload_config)),
+            )
+            .service(
+                web::resource("/admin/system/migrations")
+                    .route(web::get().to(admin_migration_status)),
+            )
+            .service(web::resource("/admin/export").route(web::get().to(admin_export)))
+            .service(
+                web::resource("/admin/import")
+                    .app_data(web::PayloadConfig::new(crate::transfer::import_max_bytes()))
+                    .route(web::post().to(admin_import)),
+            )
+            .service(
+                web::resource("/admin/import/archive")
+                    .app_data(web::JsonConfig::default().limit(crate::transfer::import_max_bytes()))
+                    .route(web::post().to(admin_import_archive)),
+            )
             // Admin moderation endpoints
             .service(
                 web::resource("/admin/boards/{id}/soft-delete")
@@ -129,21 +464,220 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                 web::resource("/admin/replies/{id}/restore")
                     .route(web::post().to(admin_restore_reply)),
             )
+            .service(web::resource("/admin/held-posts").route(web::get().to(list_held_posts)))
+            .service(
+                web::resource("/admin/threads/{id}/approve")
+                    .route(web::post().to(approve_held_thread)),
+            )
+            .service(
+                web::resource("/admin/threads/{id}/reject")
+                    .route(web::post().to(reject_held_thread)),
+            )
+            .service(
+                web::resource("/admin/replies/{id}/approve")
+                    .route(web::post().to(approve_held_reply)),
+            )
+            .service(
+                web::resource("/admin/replies/{id}/reject")
+                    .route(web::post().to(reject_held_reply)),
+            )
+            .service(
+                web::resource("/admin/quarantine")
+                    .route(web::get().to(list_quarantine))
+                    .route(web::post().to(quarantine_image)),
+            )
+            .service(
+                web::resource("/admin/quarantine/{hash}/approve")
+                    .route(web::post().to(approve_quarantined_image)),
+            )
+            .service(
+                web::resource("/admin/quarantine/{hash}/destroy")
+                    .route(web::post().to(destroy_quarantined_image)),
+            )
+            .service(web::resource("/admin/images").route(web::get().to(list_uploaded_images)))
+            .service(
+                web::resource("/admin/images/{hash}")
+                    .route(web::get().to(get_image_details))
+                    .route(web::delete().to(take_down_image)),
+            )
+            .service(
+                web::resource("/admin/legal-holds")
+                    .route(web::get().to(list_legal_holds))
+                    .route(web::post().to(place_legal_hold)),
+            )
+            .service(
+                web::resource("/admin/legal-holds/{id}/release")
+                    .route(web::post().to(release_legal_hold)),
+            )
+            .service(
+                web::resource("/admin/trust/{subject}").route(web::get().to(get_subject_trust)),
+            )
             .service(
                 web::resource("/admin/replies/{id}")
                     .route(web::delete().to(admin_hard_delete_reply)),
             ),
     );
+    crate::api_v2::config(cfg);
+    crate::activitypub::config(cfg);
+    crate::sitemap::config(cfg);
+    crate::ssr::config(cfg);
+    #[cfg(feature = "graphql")]
+    crate::graphql::config(cfg);
     // Public fetch route (no /api/v1 prefix so <img src="/images/{hash}"> works)
-    cfg.route("/images/{hash}", web::get().to(get_image));
-    // Simple health endpoint for k8s liveness/readiness (lighter than /docs)
+    cfg.service(
+        web::resource("/images/{hash}")
+            .route(web::get().to(get_image))
+            .route(web::head().to(head_image))
+            .route(allow("GET, HEAD, OPTIONS")),
+    );
+    cfg.route(
+        "/images/{hash}/thumbnail",
+        web::get().to(get_image_thumbnail),
+    );
+    cfg.route("/images/{hash}/listing", web::get().to(get_archive_listing));
+    cfg.route("/images/{hash}/preview", web::get().to(get_text_preview));
+    // Liveness (always 200) and readiness (503 until warmup finishes) for k8s
     cfg.route("/healthz", web::get().to(health));
+    cfg.route("/readyz", web::get().to(crate::readiness::readyz));
+    cfg.route(
+        "/docs/openapi.yaml",
+        web::get().to(crate::openapi::openapi_yaml),
+    );
 }
 
 pub struct AppState {
     pub repo: Arc<dyn Repo>,
     pub image_store: Arc<dyn ImageStore>,
     pub rate_limiter: Option<crate::rate_limit::RateLimiterFacade>,
+    pub search: Option<Arc<dyn SearchBackend>>, // external engine; Postgres FTS when None
+    pub live: LiveHub,
+    pub board_cache: BoardCache,
+    pub pow: Arc<ProofOfWork>,
+    pub challenges: Arc<dyn ChallengeStore>,
+    pub mailer: Option<Arc<dyn Mailer>>, // email login disabled when None
+    pub previews: Option<Arc<dyn PreviewRenderer>>, // no document thumbnails when None
+    pub thumbnails: ThumbnailConfig,
+    pub duplicates: Arc<DuplicateGuard>,
+    pub trust: TrustConfig,
+    pub uploads: Reloadable<UploadConfig>,
+    pub features: Reloadable<FeatureFlags>,
+    pub system: Arc<crate::system::SystemInfo>,
+    pub reloader: Option<ConfigReloader>, // config reload endpoint disabled when None
+    pub readiness: Readiness,
+    pub federation: Option<Arc<crate::activitypub::Federation>>, // ActivityPub routes 404 when None
+}
+
+impl AppState {
+    pub fn new(
+        repo: Arc<dyn Repo>,
+        image_store: Arc<dyn ImageStore>,
+        rate_limiter: Option<crate::rate_limit::RateLimiterFacade>,
+    ) -> Self {
+        Self {
+            repo,
+            image_store,
+            rate_limiter,
+            search: None,
+            live: LiveHub::default(),
+            board_cache: BoardCache::default(),
+            pow: Arc::new(ProofOfWork::new(PowConfig::from_env())),
+            challenges: crate::challenges::MemoryChallengeStore::shared(),
+            mailer: None,
+            previews: None,
+            thumbnails: ThumbnailConfig::disabled(),
+            duplicates: Arc::new(DuplicateGuard::new(DuplicateConfig::disabled())),
+            trust: TrustConfig::disabled(),
+            uploads: UploadConfig::disabled().into(),
+            features: FeatureFlags::default().into(),
+            system: Arc::new(crate::system::SystemInfo::default()),
+            reloader: None,
+            readiness: Readiness::default(),
+            federation: None,
+        }
+    }
+
+    pub fn with_system(mut self, system: Arc<crate::system::SystemInfo>) -> Self {
+        self.system = system;
+        self
+    }
+
+    pub fn with_trust(mut self, trust: TrustConfig) -> Self {
+        self.trust = trust;
+        self
+    }
+
+    pub fn with_uploads(mut self, uploads: impl Into<Reloadable<UploadConfig>>) -> Self {
+        self.uploads = uploads.into();
+        self
+    }
+
+    pub fn with_features(mut self, features: impl Into<Reloadable<FeatureFlags>>) -> Self {
+        self.features = features.into();
+        self
+    }
+
+    /// Also used by proof of work to remember redeemed challenges.
+    pub fn with_challenges(mut self, challenges: Arc<dyn ChallengeStore>) -> Self {
+        self.pow = Arc::new(ProofOfWork::with_store(
+            self.pow.config().clone(),
+            challenges.clone(),
+        ));
+        self.challenges = challenges;
+        self
+    }
+
+    pub fn with_readiness(mut self, readiness: Readiness) -> Self {
+        self.readiness = readiness;
+        self
+    }
+
+    pub fn with_federation(
+        mut self,
+        federation: Option<Arc<crate::activitypub::Federation>>,
+    ) -> Self {
+        self.federation = federation;
+        self
+    }
+
+    pub fn with_reloader(mut self, reloader: Option<ConfigReloader>) -> Self {
+        self.reloader = reloader;
+        self
+    }
+
+    pub fn with_duplicates(mut self, duplicates: Arc<DuplicateGuard>) -> Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    pub fn with_mailer(mut self, mailer: Option<Arc<dyn Mailer>>) -> Self {
+        self.mailer = mailer;
+        self
+    }
+
+    pub fn with_previews(mut self, previews: Option<Arc<dyn PreviewRenderer>>) -> Self {
+        self.previews = previews;
+        self
+    }
+
+    pub fn with_thumbnails(mut self, thumbnails: ThumbnailConfig) -> Self {
+        self.thumbnails = thumbnails;
+        self
+    }
+
+    pub fn with_search(mut self, search: Option<Arc<dyn SearchBackend>>) -> Self {
+        self.search = search;
+        self
+    }
+
+    pub fn with_live(mut self, live: LiveHub) -> Self {
+        self.live = live;
+        self
+    }
+
+    pub fn with_board_cache(mut self, board_cache: BoardCache) -> Self {
+        self.board_cache = board_cache;
+        self
+    }
 }
 
 #[utoipa::path(
@@ -152,19 +686,16 @@ pub struct AppState {
     params(("include_deleted" = Option<bool>, Query, description = "Admin only: include soft-deleted")),
     responses(
         (status = 200, description = "List boards", body = [Board])
-    )
+    ),
+    security((), ("bearer_auth" = []))
 )]
 pub async fn list_boards(
     req: HttpRequest,
     auth: Option<Auth>,
     data: web::Data<AppState>,
 ) -> Result<HttpResponse, ApiError> {
-    let want_deleted = req.query_string().contains("include_deleted=1");
-    let is_admin = auth
-        .as_ref()
-        .map(|a| a.0.roles.iter().any(|r| matches!(r, Role::Admin)))
-        .unwrap_or(false);
-    let boards = data.repo.list_boards(is_admin && want_deleted).await?;
+    let boards =
+        service::list_boards(&data, include_deleted(&req, auth.as_ref()), auth.as_ref()).await?;
     Ok(HttpResponse::Ok().json(boards))
 }
 
@@ -174,25 +705,19 @@ pub async fn list_boards(
     request_body = NewBoard,
     responses(
         (status = 201, description = "Board created", body = Board),
+        (status = 401, description = "Sign-in required"),
         (status = 403, description = "Forbidden - Admins only"),   // UPDATED
-        (status = 409, description = "Conflict")
-    )
+        (status = 409, description = "Conflict"),
+        (status = 503, description = "Board creation disabled for maintenance (`feature_disabled`)")
+    ),
+    security(("bearer_auth" = []))
 )]
 pub async fn create_board(
     auth: Auth,
     data: web::Data<AppState>,
     payload: web::Json<NewBoard>,
 ) -> Result<HttpResponse, ApiError> {
-    // ── admin-only guard ───────────────────────────────────────────
-    if !auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
-        return Err(ApiError::Forbidden);
-    }
-    // ───────────────────────────────────────────────────────────────
-    let mut new = payload.into_inner();
-    new.slug = new.slug.trim().to_string();
-    new.title = new.title.trim().to_string();
-    validate_board_fields(&new.slug, &new.title)?;
-    let board = data.repo.create_board(new).await?;
+    let board = service::create_board(&data, &auth, payload.into_inner()).await?;
     Ok(HttpResponse::Created().json(board))
 }
 
@@ -201,38 +726,123 @@ pub async fn create_board(
     path = "/api/v1/boards/{id}/threads",
     params(
         ("id" = Id, Path, description = "Board id"),
-        ("include_deleted" = Option<bool>, Query, description = "Admin only: include soft-deleted")
+        ("include_deleted" = Option<bool>, Query, description = "Admin only: include soft-deleted"),
+        ("apply_filters" = Option<bool>, Query, description = "Signed-in callers: leave out threads matching their mute list"),
+        ("tag" = Option<String>, Query, description = "Only threads carrying this tag"),
+        ("sort" = Option<ThreadSort>, Query, description = "Order by `bump_time` (default), `created_at` or `reply_count`; ties go by thread id"),
+        ("order" = Option<SortOrder>, Query, description = "`desc` (default) or `asc`"),
+        ("fields" = Option<String>, Query, description = "Comma-separated thread fields to keep in JSON and NDJSON output, e.g. `id,subject,bump_time`")
     ),
     responses(
-        (status = 200, description = "List threads", body = [Thread]),
+        (status = 200, description = "List threads; `Accept: text/plain` or `text/tab-separated-values` for a text dump, `application/x-ndjson` for a stream of one thread per line", content(
+            ("application/json" = [Thread]),
+            ("application/x-ndjson" = Thread),
+            ("text/plain" = String),
+            ("text/tab-separated-values" = String)
+        )),
+        (status = 400, description = "Unknown `sort` or `order`"),
         (status = 404, description = "Board not found")
-    )
+    ),
+    security((), ("bearer_auth" = []))
 )]
 pub async fn list_threads(
     req: HttpRequest,
     auth: Option<Auth>,
     data: web::Data<AppState>,
     path: web::Path<Id>,
+    query: web::Query<TagQuery>,
 ) -> Result<HttpResponse, ApiError> {
     let board_id = path.into_inner();
-    let want_deleted = req.query_string().contains("include_deleted=1");
-    let is_admin = auth
-        .as_ref()
-        .map(|a| a.0.roles.iter().any(|r| matches!(r, Role::Admin)))
-        .unwrap_or(false);
-    let board = data
-        .repo
-        .get_board(board_id)
-        .await
-        .map_err(|_| ApiError::NotFound)?;
-    if board.deleted_at.is_some() && !(is_admin && want_deleted) {
-        return Err(ApiError::NotFound);
+    let include_deleted = include_deleted(&req, auth.as_ref());
+    let order = thread_order(&req)?;
+    if query.tag.is_none() && negotiate::Format::from_request(&req) == negotiate::Format::Ndjson {
+        let fields = negotiate::Fields::from_request::<Thread>(&req)?;
+        let filters = requested_filters(&req, auth.as_ref(), &data).await?;
+        let threads =
+            service::stream_threads(&data, board_id, include_deleted, order, auth.as_ref()).await?;
+        return Ok(negotiate::stream_ndjson(
+            fields,
+            threads.try_filter(move |thread| {
+                futures_util::future::ready(
+                    !filters.as_ref().is_some_and(|f| f.hides_thread(thread)),
+                )
+            }),
+        ));
     }
-    let mut threads = data
-        .repo
-        .list_threads(board_id, is_admin && want_deleted)
-        .await?;
-    threads.sort_by_key(|thread| std::cmp::Reverse(thread.bump_time));
+    let mut threads = match query.tag.as_deref() {
+        Some(tag) => {
+            service::list_tagged_threads(
+                &data,
+                board_id,
+                tag,
+                include_deleted,
+                order,
+                auth.as_ref(),
+            )
+            .await?
+        }
+        None => {
+            service::list_threads(&data, board_id, include_deleted, order, auth.as_ref()).await?
+        }
+    };
+    if let Some(filters) = requested_filters(&req, auth.as_ref(), &data).await? {
+        threads.retain(|thread| !filters.hides_thread(thread));
+    }
+    negotiate::respond(&req, &threads)
+}
+
+#[derive(serde::Deserialize)]
+pub struct TagQuery {
+    tag: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/boards/{id}/tags",
+    params(("id" = Id, Path, description = "Board id")),
+    responses(
+        (status = 200, description = "Tags on the board's active threads with usage counts, most used first; unused vocabulary tags count zero", body = [TagCount]),
+        (status = 404, description = "Board not found")
+    )
+)]
+pub async fn list_board_tags(
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    let tags = service::tag_counts(&data, path.into_inner(), auth.as_ref()).await?;
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+const MAX_ARCHIVE_PAGE: i64 = 500;
+
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+pub struct ArchiveQuery {
+    /// Threads to return (default and maximum 500)
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/boards/{id}/archive",
+    params(("id" = Id, Path, description = "Board id"), ArchiveQuery),
+    responses(
+        (status = 200, description = "Threads that fell off the board's page limit, most recently archived first", body = [Thread]),
+        (status = 404, description = "Board not found")
+    )
+)]
+pub async fn list_archived_threads(
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    query: web::Query<ArchiveQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let limit = query
+        .limit
+        .unwrap_or(MAX_ARCHIVE_PAGE)
+        .clamp(1, MAX_ARCHIVE_PAGE);
+    let threads =
+        service::list_archived_threads(&data, path.into_inner(), limit, auth.as_ref()).await?;
     Ok(HttpResponse::Ok().json(threads))
 }
 
@@ -242,54 +852,27 @@ pub async fn list_threads(
     request_body = NewThread,
     responses(
         (status = 201, description = "Thread created", body = Thread),
+        (status = 202, description = "Thread held for staff review; hidden until approved", body = Thread),
+        (status = 401, description = "Invalid bearer token or session"),
         (status = 404, description = "Board not found"),
-        (status = 403, description = "Forbidden")
-    )
+        (status = 403, description = "Forbidden, or banned: `code` is `banned` and `ban` gives the public reason and expiry"),
+        (status = 409, description = "Body duplicates a recent thread by the same poster")
+    ),
+    security((), ("bearer_auth" = []))
 )]
 pub async fn create_thread(
-    auth: Auth,
+    auth: Result<Auth, actix_web::Error>,
     req: HttpRequest,
     data: web::Data<AppState>,
     payload: web::Json<NewThread>,
 ) -> Result<HttpResponse, ApiError> {
-    let (subject_key, created_by) = private_author_attribution(&auth)?;
-    ensure_subject_can_post(data.get_ref(), &auth, &subject_key).await?;
-    if let Some(rl) = &data.rate_limiter {
-        let ip = extract_client_ip(&req);
-        if !rl.allow_thread(&ip) {
-            metrics::increment_counter!("rate_limit_denied", "action" => "thread_create");
-            return Err(ApiError::RateLimited {
-                retry_after: rl.cfg.thread_window.as_secs(),
-            });
-        }
-        metrics::increment_counter!("rate_limit_allowed", "action" => "thread_create");
-    }
-    if !auth
-        .0
-        .roles
-        .iter()
-        .any(|r| matches!(r, Role::User | Role::Moderator | Role::Admin))
-    {
-        return Err(ApiError::Forbidden);
-    }
-    let mut new = payload.into_inner();
-    new.subject = new.subject.trim().to_string();
-    new.body = new.body.trim().to_string();
-    validate_thread_payload(&new)?;
-    let board = data
-        .repo
-        .get_board(new.board_id)
-        .await
-        .map_err(|_| ApiError::NotFound)?;
-    if board.deleted_at.is_some() {
-        return Err(ApiError::NotFound);
+    let client_ip = extract_client_ip(&req);
+    let poster = http_poster(&req, auth.as_ref().ok(), &client_ip)?;
+    let thread = service::create_thread(&data, poster, payload.into_inner()).await?;
+    // Only held posts come back deleted.
+    if thread.deleted_at.is_some() {
+        return Ok(HttpResponse::Accepted().json(thread));
     }
-    let public_identity =
-        derive_public_identity(new.author_name.take(), new.tripcode_password.take())?;
-    let thread = data
-        .repo
-        .create_thread(new, created_by, public_identity)
-        .await?;
     Ok(HttpResponse::Created().json(thread))
 }
 
@@ -300,7 +883,8 @@ pub async fn create_thread(
     responses(
         (status = 200, description = "Thread", body = Thread),
         (status = 404, description = "Thread not found")
-    )
+    ),
+    security((), ("bearer_auth" = []))
 )]
 pub async fn get_thread(
     req: HttpRequest,
@@ -308,39 +892,85 @@ pub async fn get_thread(
     data: web::Data<AppState>,
     path: web::Path<Id>,
 ) -> Result<HttpResponse, ApiError> {
-    let want_deleted = req.query_string().contains("include_deleted=1");
-    let is_admin = auth
-        .as_ref()
-        .map(|a| a.0.roles.iter().any(|r| matches!(r, Role::Admin)))
-        .unwrap_or(false);
-    let th = data
-        .repo
-        .get_thread(path.into_inner())
-        .await
-        .map_err(|e| match e {
-            crate::repo::RepoError::NotFound => ApiError::NotFound,
-            _ => ApiError::Internal,
-        })?;
-    if th.deleted_at.is_some() && !(is_admin && want_deleted) {
+    let thread = service::get_thread(
+        &data,
+        path.into_inner(),
+        include_deleted(&req, auth.as_ref()),
+        auth.as_ref(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(thread))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/boards/{board}/threads/{thread}",
+    params(
+        ("board" = String, Path, description = "Board slug"),
+        ("thread" = String, Path, description = "`{id}-{slug}`, or the bare thread id"),
+        ("include_deleted" = Option<bool>, Query, description = "Admin only: include soft-deleted")
+    ),
+    responses(
+        (status = 200, description = "Thread, when the URL is its canonical one", body = Thread),
+        (status = 301, description = "Bare id, or an earlier thread or board slug; `Location` is the canonical URL"),
+        (status = 404, description = "Thread not found, not on this board, or the slug was never the thread's")
+    ),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn get_thread_by_slug(
+    req: HttpRequest,
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let (board_slug, handle) = path.into_inner();
+    let (id, slug) = crate::slugs::parse_thread_handle(&handle).ok_or(ApiError::NotFound)?;
+    let include_deleted = include_deleted(&req, auth.as_ref());
+    let thread = service::get_thread(&data, id, include_deleted, auth.as_ref()).await?;
+    let board = service::get_board(&data, thread.board_id, include_deleted, auth.as_ref()).await?;
+    let current_board = board_slug == board.slug;
+    if !current_board && data.repo.board_for_old_slug(&board_slug).await? != Some(board.id) {
         return Err(ApiError::NotFound);
     }
-    let board = data.repo.get_board(th.board_id).await?;
-    if board.deleted_at.is_some() && !(is_admin && want_deleted) {
+    if slug == thread.slug {
+        if current_board {
+            return Ok(HttpResponse::Ok().json(thread));
+        }
+    } else if !slug.is_empty() && !data.repo.thread_had_slug(id, slug).await? {
         return Err(ApiError::NotFound);
     }
-    Ok(HttpResponse::Ok().json(th))
+    let mut location = crate::slugs::thread_url(&board, &thread);
+    if !req.query_string().is_empty() {
+        location = format!("{location}?{}", req.query_string());
+    }
+    let kind = if current_board { "thread" } else { "board" };
+    metrics::increment_counter!("slug_redirects", "kind" => kind);
+    Ok(HttpResponse::MovedPermanently()
+        .insert_header(("Location", location))
+        .finish())
 }
 
 #[utoipa::path(
     get,
     path = "/api/v1/threads/{id}/replies",
     params(
-        ("id" = Id, Path, description = "Thread id")
+        ("id" = Id, Path, description = "Thread id"),
+        ("apply_filters" = Option<bool>, Query, description = "Signed-in callers: leave out replies matching their mute list"),
+        ("fields" = Option<String>, Query, description = "Comma-separated reply fields to keep in JSON and NDJSON output, e.g. `id,created_at`"),
+        ("sort" = Option<ReplySort>, Query, description = "Order by `created_at`, the only key for replies; ties go by reply id"),
+        ("order" = Option<SortOrder>, Query, description = "`asc` (default) or `desc`")
     ),
     responses(
-        (status = 200, description = "List replies", body = [Reply]),
+        (status = 200, description = "List replies; `Accept: text/plain` or `text/tab-separated-values` for a text dump, `application/x-ndjson` for a stream of one reply per line", content(
+            ("application/json" = [Reply]),
+            ("application/x-ndjson" = Reply),
+            ("text/plain" = String),
+            ("text/tab-separated-values" = String)
+        )),
+        (status = 400, description = "Unknown `sort` or `order`"),
         (status = 404, description = "Thread not found")
-    )
+    ),
+    security((), ("bearer_auth" = []))
 )]
 pub async fn list_replies(
     req: HttpRequest,
@@ -349,29 +979,345 @@ pub async fn list_replies(
     path: web::Path<Id>,
 ) -> Result<HttpResponse, ApiError> {
     let thread_id = path.into_inner();
-    let want_deleted = req.query_string().contains("include_deleted=1");
-    let is_admin = auth
-        .as_ref()
-        .map(|a| a.0.roles.iter().any(|r| matches!(r, Role::Admin)))
-        .unwrap_or(false);
-    let thread = data
-        .repo
-        .get_thread(thread_id)
-        .await
-        .map_err(|_| ApiError::NotFound)?;
-    if thread.deleted_at.is_some() && !(is_admin && want_deleted) {
-        return Err(ApiError::NotFound);
+    let include_deleted = include_deleted(&req, auth.as_ref());
+    let order = reply_order(&req)?;
+    if negotiate::Format::from_request(&req) == negotiate::Format::Ndjson {
+        let fields = negotiate::Fields::from_request::<Reply>(&req)?;
+        let filters = requested_filters(&req, auth.as_ref(), &data).await?;
+        let replies =
+            service::stream_replies(&data, thread_id, include_deleted, order, auth.as_ref())
+                .await?;
+        return Ok(negotiate::stream_ndjson(
+            fields,
+            replies.try_filter(move |reply| {
+                futures_util::future::ready(!filters.as_ref().is_some_and(|f| f.hides_reply(reply)))
+            }),
+        ));
     }
-    let board = data.repo.get_board(thread.board_id).await?;
-    if board.deleted_at.is_some() && !(is_admin && want_deleted) {
-        return Err(ApiError::NotFound);
+    let mut replies =
+        service::list_replies(&data, thread_id, include_deleted, order, auth.as_ref()).await?;
+    if let Some(filters) = requested_filters(&req, auth.as_ref(), &data).await? {
+        replies.retain(|reply| !filters.hides_reply(reply));
     }
-    let mut replies = data
-        .repo
-        .list_replies(thread_id, is_admin && want_deleted)
-        .await?;
-    replies.sort_by_key(|reply| reply.created_at);
-    Ok(HttpResponse::Ok().json(replies))
+    negotiate::respond(&req, &replies)
+}
+
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+pub struct SearchQuery {
+    /// Search terms (web-search syntax: quoted phrases, `-exclusion`, `or`)
+    pub(crate) q: String,
+    pub(crate) board_id: Option<Id>,
+    pub(crate) limit: Option<i64>,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct SearchResults {
+    pub(crate) backend: String,
+    pub(crate) hits: Vec<SearchHit>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/search",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Matching threads and replies", body = SearchResults),
+        (status = 400, description = "Missing or oversized query"),
+        (status = 503, description = "Search disabled for maintenance (`feature_disabled`)")
+    )
+)]
+pub async fn search(
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    query: web::Query<SearchQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let SearchQuery { q, board_id, limit } = query.into_inner();
+    let results = service::search(&data, &q, board_id, limit, auth.as_ref()).await?;
+    Ok(HttpResponse::Ok().json(results))
+}
+
+const MAX_BATCH_THREADS: usize = 100;
+const MAX_PREVIEW_REPLIES: i64 = 10;
+
+fn default_preview_replies() -> i64 {
+    3
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct BatchRequest {
+    /// Thread ids to fetch (at most 100)
+    thread_ids: Vec<Id>,
+    /// Oldest replies to include per thread (0-10, default 3)
+    #[serde(default = "default_preview_replies")]
+    replies: i64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/batch",
+    request_body = BatchRequest,
+    responses(
+        (status = 200, description = "Visible threads in request order with their first replies; unknown ids are omitted", body = [ThreadPreview]),
+        (status = 400, description = "Too many thread ids")
+    )
+)]
+pub async fn batch_threads(
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    payload: web::Json<BatchRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let BatchRequest {
+        thread_ids,
+        replies,
+    } = payload.into_inner();
+    if thread_ids.len() > MAX_BATCH_THREADS {
+        return Err(ApiError::Invalid(format!(
+            "at most {MAX_BATCH_THREADS} thread ids per batch"
+        )));
+    }
+    let previews = service::thread_previews(
+        &data,
+        &thread_ids,
+        replies.clamp(0, MAX_PREVIEW_REPLIES),
+        auth.as_ref(),
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(previews))
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct DeletePassword {
+    /// Deletion password given when the post was created
+    password: String,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/threads/{id}",
+    params(("id" = Id, Path, description = "Thread id")),
+    request_body = DeletePassword,
+    responses(
+        (status = 204, description = "Thread soft-deleted"),
+        (status = 403, description = "Wrong password, or the thread has none"),
+        (status = 404, description = "Thread not found")
+    )
+)]
+pub async fn delete_thread_with_password(
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<DeletePassword>,
+) -> Result<HttpResponse, ApiError> {
+    service::delete_thread_with_password(
+        &data,
+        path.into_inner(),
+        payload.into_inner().password,
+        auth.as_ref(),
+    )
+    .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/replies/{id}",
+    params(("id" = Id, Path, description = "Reply id")),
+    request_body = DeletePassword,
+    responses(
+        (status = 204, description = "Reply soft-deleted"),
+        (status = 403, description = "Wrong password, or the reply has none"),
+        (status = 404, description = "Reply not found")
+    )
+)]
+pub async fn delete_reply_with_password(
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<DeletePassword>,
+) -> Result<HttpResponse, ApiError> {
+    service::delete_reply_with_password(
+        &data,
+        path.into_inner(),
+        payload.into_inner().password,
+        auth.as_ref(),
+    )
+    .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/threads/{id}/close",
+    params(("id" = Id, Path, description = "Thread id")),
+    responses(
+        (status = 200, description = "Thread closed to new replies", body = Thread),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Not staff, and not the creator of a thread on a board with `op_moderation`"),
+        (status = 404, description = "Thread not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn close_thread(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    let thread = service::set_thread_closed(&data, &auth, path.into_inner(), true).await?;
+    Ok(HttpResponse::Ok().json(thread))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/threads/{id}/reopen",
+    params(("id" = Id, Path, description = "Thread id")),
+    responses(
+        (status = 200, description = "Thread accepts replies again", body = Thread),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Not staff, and not the creator of a thread on a board with `op_moderation`"),
+        (status = 404, description = "Thread not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn reopen_thread(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    let thread = service::set_thread_closed(&data, &auth, path.into_inner(), false).await?;
+    Ok(HttpResponse::Ok().json(thread))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/threads/{id}/pinned-reply",
+    params(("id" = Id, Path, description = "Thread id")),
+    request_body = PinReply,
+    responses(
+        (status = 200, description = "Reply pinned; replaces any earlier pin", body = Thread),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Not staff and not the thread's creator"),
+        (status = 404, description = "Thread not found, or the reply is not a visible reply in it")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn pin_reply(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<PinReply>,
+) -> Result<HttpResponse, ApiError> {
+    let thread =
+        service::set_pinned_reply(&data, &auth, path.into_inner(), Some(payload.reply_id)).await?;
+    Ok(HttpResponse::Ok().json(thread))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/threads/{id}/pinned-reply",
+    params(("id" = Id, Path, description = "Thread id")),
+    responses(
+        (status = 200, description = "Pin cleared", body = Thread),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Not staff and not the thread's creator"),
+        (status = 404, description = "Thread not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn unpin_reply(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    let thread = service::set_pinned_reply(&data, &auth, path.into_inner(), None).await?;
+    Ok(HttpResponse::Ok().json(thread))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/threads/{id}/replies/{reply_id}",
+    params(
+        ("id" = Id, Path, description = "Thread id"),
+        ("reply_id" = Id, Path, description = "Reply id")
+    ),
+    request_body = DeletionReason,
+    responses(
+        (status = 204, description = "Reply soft-deleted and the action logged"),
+        (status = 400, description = "Missing or overlong reason"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Not staff, and not the creator of a thread on a board with `op_moderation`"),
+        (status = 404, description = "Thread or reply not found, or the reply is in another thread")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn moderate_delete_reply(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<(Id, Id)>,
+    payload: web::Json<DeletionReason>,
+) -> Result<HttpResponse, ApiError> {
+    let (thread_id, reply_id) = path.into_inner();
+    let reason = deletion_reason(&payload.reason)?;
+    service::moderate_delete_reply(&data, &auth, thread_id, reply_id, reason).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+pub struct LiveQuery {
+    /// Only stream events for this thread
+    thread_id: Option<Id>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/live",
+    params(LiveQuery),
+    responses((status = 200, description = "Server-sent stream of post events", content_type = "text/event-stream")),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn live_events(
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    query: web::Query<LiveQuery>,
+) -> Result<HttpResponse, ApiError> {
+    use tokio::sync::broadcast::error::RecvError;
+    let thread_id = query.thread_id;
+    // Posts on beta boards the subscriber may not see are left out.
+    let hidden = service::hidden_beta_boards(&data, auth.as_ref()).await?;
+    let rx = data.live.subscribe();
+    let stream = futures_util::stream::unfold(rx, move |mut rx| {
+        let data = data.clone();
+        let hidden = hidden.clone();
+        async move {
+            loop {
+                let frame =
+                    match tokio::time::timeout(std::time::Duration::from_secs(15), rx.recv()).await
+                    {
+                        // Comment frames keep idle connections open through proxies.
+                        Err(_) => ": keep-alive\n\n".to_string(),
+                        Ok(Ok(event)) => {
+                            if thread_id.is_some_and(|id| event.payload["thread_id"] != id) {
+                                continue;
+                            }
+                            if !service::live_event_visible(&data, &event, &hidden).await {
+                                continue;
+                            }
+                            sse_frame(&event)
+                        }
+                        Ok(Err(RecvError::Lagged(skipped))) => {
+                            log::debug!("live client lagged; skipped {skipped} events");
+                            continue;
+                        }
+                        Ok(Err(RecvError::Closed)) => return None,
+                    };
+                return Some((Ok::<_, actix_web::Error>(web::Bytes::from(frame)), rx));
+            }
+        }
+    });
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(stream))
 }
 
 // ---------------- Admin moderation handlers -----------------------
@@ -395,7 +1341,9 @@ macro_rules! ensure_moderator_or_admin {
     };
 }
 
-fn private_author_attribution(auth: &Auth) -> Result<(String, serde_json::Value), ApiError> {
+pub(crate) fn private_author_attribution(
+    auth: &Auth,
+) -> Result<(String, serde_json::Value), ApiError> {
     let subject = role_subject_key(&auth.0.sub).ok_or(ApiError::Forbidden)?;
     let details = if let Some(address) = auth.0.sub.strip_prefix("btc:") {
         serde_json::json!({
@@ -404,6 +1352,26 @@ fn private_author_attribution(auth: &Auth) -> Result<(String, serde_json::Value)
             "provider": "bitcoin",
             "address": address,
         })
+    } else if let Some(address) = auth.0.sub.strip_prefix("eth:") {
+        serde_json::json!({
+            "v": 1,
+            "subject": subject,
+            "provider": "ethereum",
+            "address": address,
+        })
+    } else if let Some(npub) = auth.0.sub.strip_prefix("nostr:") {
+        serde_json::json!({
+            "v": 1,
+            "subject": subject,
+            "provider": "nostr",
+            "npub": npub,
+        })
+    } else if auth.0.sub.starts_with("email:") {
+        serde_json::json!({
+            "v": 1,
+            "subject": subject,
+            "provider": "email",
+        })
     } else {
         let (discord_id, username) = auth.0.sub.split_once(':').ok_or(ApiError::Forbidden)?;
         serde_json::json!({
@@ -417,7 +1385,7 @@ fn private_author_attribution(auth: &Auth) -> Result<(String, serde_json::Value)
     Ok((subject, details))
 }
 
-fn validate_board_fields(slug: &str, title: &str) -> Result<(), ApiError> {
+pub(crate) fn validate_board_fields(slug: &str, title: &str) -> Result<(), ApiError> {
     let valid_slug = !slug.is_empty()
         && slug.len() <= 64
         && slug.bytes().all(|byte| {
@@ -433,7 +1401,7 @@ fn validate_attachment(image_hash: &Option<String>, mime: &Option<String>) -> Re
     match (image_hash, mime) {
         (None, None) => Ok(()),
         (Some(hash), Some(mime))
-            if is_valid_content_hash(hash) && ALLOWED_MIME.contains(&mime.as_str()) =>
+            if is_valid_content_hash(hash) && crate::uploads::is_mime_type(mime) =>
         {
             Ok(())
         }
@@ -441,7 +1409,7 @@ fn validate_attachment(image_hash: &Option<String>, mime: &Option<String>) -> Re
     }
 }
 
-fn validate_thread_payload(new: &NewThread) -> Result<(), ApiError> {
+pub(crate) fn validate_thread_payload(new: &NewThread) -> Result<(), ApiError> {
     if new.subject.is_empty()
         || new.subject.chars().count() > 200
         || new.body.chars().count() > 2000
@@ -451,7 +1419,7 @@ fn validate_thread_payload(new: &NewThread) -> Result<(), ApiError> {
     validate_attachment(&new.image_hash, &new.mime)
 }
 
-fn validate_reply_payload(new: &NewReply) -> Result<(), ApiError> {
+pub(crate) fn validate_reply_payload(new: &NewReply) -> Result<(), ApiError> {
     if new.content.chars().count() > 2000 || (new.content.is_empty() && new.image_hash.is_none()) {
         return Err(ApiError::BadRequest);
     }
@@ -462,13 +1430,47 @@ fn is_valid_subject_key(subject: &str) -> bool {
     let Some((provider, identifier)) = subject.split_once(':') else {
         return false;
     };
-    matches!(provider, "discord" | "btc")
-        && !identifier.is_empty()
+    matches!(
+        provider,
+        "discord" | "btc" | "eth" | "nostr" | "email" | "anon"
+    ) && !identifier.is_empty()
         && !identifier.contains(':')
         && identifier.chars().count() <= 128
 }
 
-fn derive_public_identity(
+/// Key for public poster identifiers (tripcodes, anonymous subjects).
+fn tripcode_secret() -> Result<String, ApiError> {
+    std::env::var("TRIPCODE_SECRET")
+        .or_else(|_| {
+            if cfg!(debug_assertions) {
+                std::env::var("JWT_SECRET")
+            } else {
+                Err(std::env::VarError::NotPresent)
+            }
+        })
+        .map_err(|_| ApiError::Internal)
+}
+
+/// Subject and `created_by` for a post without an account. The subject is a
+/// keyed hash of the client IP, so moderators can look up and ban it like
+/// any other subject without the raw address being stored.
+pub(crate) fn anonymous_author_attribution(
+    client_ip: &str,
+) -> Result<(String, serde_json::Value), ApiError> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(tripcode_secret()?.as_bytes())
+        .map_err(|_| ApiError::Internal)?;
+    mac.update(b"rib-anon-v1\0");
+    mac.update(client_ip.as_bytes());
+    let subject = format!("anon:{}", hex::encode(&mac.finalize().into_bytes()[..12]));
+    let details = serde_json::json!({
+        "v": 1,
+        "subject": subject,
+        "provider": "anonymous",
+    });
+    Ok((subject, details))
+}
+
+pub(crate) fn derive_public_identity(
     author_name: Option<String>,
     tripcode_password: Option<String>,
 ) -> Result<PublicIdentity, ApiError> {
@@ -484,16 +1486,7 @@ fn derive_public_identity(
 
     let tripcode = match tripcode_password {
         Some(password) if (4..=128).contains(&password.chars().count()) => {
-            let secret = std::env::var("TRIPCODE_SECRET")
-                .or_else(|_| {
-                    if cfg!(debug_assertions) {
-                        std::env::var("JWT_SECRET")
-                    } else {
-                        Err(std::env::VarError::NotPresent)
-                    }
-                })
-                .map_err(|_| ApiError::Internal)?;
-            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            let mut mac = Hmac::<Sha256>::new_from_slice(tripcode_secret()?.as_bytes())
                 .map_err(|_| ApiError::Internal)?;
             mac.update(b"rib-tripcode-v1\0");
             mac.update(password.as_bytes());
@@ -510,20 +1503,70 @@ fn derive_public_identity(
     })
 }
 
-async fn ensure_subject_not_banned(data: &AppState, subject: &str) -> Result<(), ApiError> {
-    if data.repo.is_subject_banned(subject).await? {
-        return Err(ApiError::Forbidden);
+/// Argon2 PHC string for a post's deletion password; same length rules as tripcodes.
+pub(crate) fn hash_delete_password(password: &str) -> Result<String, ApiError> {
+    use argon2::password_hash::{PasswordHasher, SaltString};
+    if !(4..=128).contains(&password.chars().count()) {
+        return Err(ApiError::BadRequest);
+    }
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    argon2::Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|_| ApiError::Internal)
+}
+
+pub(crate) fn verify_delete_password(password: &str, stored: &str) -> bool {
+    use argon2::password_hash::{PasswordHash, PasswordVerifier};
+    PasswordHash::new(stored).is_ok_and(|hash| {
+        argon2::Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+pub(crate) async fn ensure_subject_not_banned(
+    data: &AppState,
+    subject: &str,
+) -> Result<(), ApiError> {
+    match data.repo.active_subject_ban(subject).await? {
+        Some(ban) => Err(ApiError::Banned(ban)),
+        None => Ok(()),
     }
-    Ok(())
 }
 
-async fn ensure_subject_can_post(
+/// IP bans cover everyone posting or uploading from the address but staff,
+/// who must be able to lift a range they are caught in.
+pub(crate) async fn ensure_ip_not_banned(data: &AppState, client_ip: &str) -> Result<(), ApiError> {
+    match data.repo.active_ip_ban(client_ip).await? {
+        Some(ban) => {
+            metrics::increment_counter!("ip_ban_denied");
+            Err(ApiError::Banned(ban))
+        }
+        None => Ok(()),
+    }
+}
+
+/// `network` as an address or CIDR range; `None` when malformed or when the
+/// prefix is longer than the address.
+pub(crate) fn parse_ban_network(network: &str) -> Option<String> {
+    let (address, prefix) = match network.split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix.parse::<u8>().ok()?)),
+        None => (network, None),
+    };
+    let address: std::net::IpAddr = address.parse().ok()?;
+    let max = if address.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(max);
+    (prefix <= max).then(|| format!("{address}/{prefix}"))
+}
+
+pub(crate) async fn ensure_subject_can_post(
     data: &AppState,
     auth: &Auth,
     subject: &str,
 ) -> Result<(), ApiError> {
     ensure_subject_not_banned(data, subject).await?;
-    if !auth.0.sub.starts_with("btc:") {
+    if !is_self_serve_subject(&auth.0.sub) {
         let (discord_id, _) = auth.0.sub.split_once(':').ok_or(ApiError::Forbidden)?;
         let assigned_role = data.repo.get_subject_role(subject).await;
         if discord_admission_role(assigned_role, is_bootstrap_discord_id(discord_id)).is_none() {
@@ -554,6 +1597,14 @@ fn author_attribution(details: serde_json::Value) -> Result<AuthorAttribution, A
                     .get("address")
                     .and_then(serde_json::Value::as_str)
                     .map(|address| format!("btc:{address}")),
+                Some("ethereum") => details
+                    .get("address")
+                    .and_then(serde_json::Value::as_str)
+                    .map(|address| format!("eth:{address}")),
+                Some("nostr") => details
+                    .get("npub")
+                    .and_then(serde_json::Value::as_str)
+                    .map(|npub| format!("nostr:{npub}")),
                 _ => None,
             },
         )
@@ -567,6 +1618,7 @@ fn author_attribution(details: serde_json::Value) -> Result<AuthorAttribution, A
     params(("id" = Id, Path, description = "Thread id")),
     responses(
         (status = 200, description = "Private author attribution", body = AuthorAttribution),
+        (status = 401, description = "Sign-in required"),
         (status = 403, description = "Moderator role required"),
         (status = 404, description = "Thread or attribution not found")
     ),
@@ -588,6 +1640,7 @@ pub async fn get_thread_author(
     params(("id" = Id, Path, description = "Reply id")),
     responses(
         (status = 200, description = "Private author attribution", body = AuthorAttribution),
+        (status = 401, description = "Sign-in required"),
         (status = 403, description = "Moderator role required"),
         (status = 404, description = "Reply or attribution not found")
     ),
@@ -610,6 +1663,7 @@ pub async fn get_reply_author(
     responses(
         (status = 201, description = "Subject banned", body = SubjectBan),
         (status = 400, description = "Invalid subject or reason"),
+        (status = 401, description = "Sign-in required"),
         (status = 403, description = "Moderator role required")
     ),
     security(("bearer_auth" = []))
@@ -623,6 +1677,7 @@ pub async fn create_subject_ban(
     let mut new = payload.into_inner();
     new.subject = new.subject.trim().to_string();
     new.reason = new.reason.trim().to_string();
+    new.public_reason = public_ban_reason(new.public_reason)?;
     if !is_valid_subject_key(&new.subject)
         || new.reason.is_empty()
         || new.reason.chars().count() > 500
@@ -633,11 +1688,23 @@ pub async fn create_subject_ban(
     Ok(HttpResponse::Created().json(ban))
 }
 
+/// Trimmed public reason; blank is none.
+fn public_ban_reason(reason: Option<String>) -> Result<Option<String>, ApiError> {
+    let reason = reason
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty());
+    if reason.as_ref().is_some_and(|r| r.chars().count() > 500) {
+        return Err(ApiError::BadRequest);
+    }
+    Ok(reason)
+}
+
 #[utoipa::path(
     get,
     path = "/api/v1/admin/bans",
     responses(
         (status = 200, description = "Active subject bans", body = [SubjectBan]),
+        (status = 401, description = "Sign-in required"),
         (status = 403, description = "Moderator role required")
     ),
     security(("bearer_auth" = []))
@@ -656,6 +1723,7 @@ pub async fn list_subject_bans(
     params(("subject" = String, Path, description = "Provider subject key")),
     responses(
         (status = 204, description = "Ban removed"),
+        (status = 401, description = "Sign-in required"),
         (status = 403, description = "Moderator role required"),
         (status = 404, description = "Ban not found")
     ),
@@ -671,530 +1739,2448 @@ pub async fn delete_subject_ban(
     Ok(HttpResponse::NoContent().finish())
 }
 
-pub async fn admin_soft_delete_board(
-    auth: Auth,
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/ip-bans",
+    request_body = NewIpBan,
+    responses(
+        (status = 201, description = "Address or range banned", body = IpBan),
+        (status = 400, description = "Invalid address, range or reason"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_ip_ban(
+    auth: Auth,
     data: web::Data<AppState>,
-    path: web::Path<Id>,
+    payload: web::Json<NewIpBan>,
 ) -> Result<HttpResponse, ApiError> {
-    ensure_admin!(auth);
-    data.repo.soft_delete_board(path.into_inner()).await?;
-    Ok(HttpResponse::Ok().json(serde_json::json!({"status":"ok"})))
+    ensure_moderator_or_admin!(auth);
+    let mut new = payload.into_inner();
+    new.network = parse_ban_network(new.network.trim()).ok_or(ApiError::BadRequest)?;
+    new.reason = new.reason.trim().to_string();
+    new.public_reason = public_ban_reason(new.public_reason)?;
+    if new.reason.is_empty() || new.reason.chars().count() > 500 {
+        return Err(ApiError::BadRequest);
+    }
+    let ban = data.repo.create_ip_ban(new, &auth.0.sub).await?;
+    log::info!("ip ban {} on {} by {}", ban.id, ban.network, auth.0.sub);
+    Ok(HttpResponse::Created().json(ban))
 }
-pub async fn admin_restore_board(
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/ip-bans",
+    responses(
+        (status = 200, description = "Active IP bans, newest first", body = [IpBan]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_ip_bans(auth: Auth, data: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    Ok(HttpResponse::Ok().json(data.repo.list_ip_bans().await?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/ip-bans/{id}",
+    params(("id" = Id, Path, description = "IP ban id")),
+    responses(
+        (status = 204, description = "Ban lifted"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required"),
+        (status = 404, description = "Ban not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_ip_ban(
     auth: Auth,
     data: web::Data<AppState>,
     path: web::Path<Id>,
 ) -> Result<HttpResponse, ApiError> {
-    ensure_admin!(auth);
-    data.repo.restore_board(path.into_inner()).await?;
-    Ok(HttpResponse::Ok().json(serde_json::json!({"status":"ok"})))
+    ensure_moderator_or_admin!(auth);
+    data.repo.delete_ip_ban(path.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
 }
-pub async fn admin_hard_delete_board(
+
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+pub struct AppealQueueQuery {
+    /// `pending` (default), `accepted` or `denied`
+    status: Option<String>,
+    /// At most 100 (the default)
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/appeals",
+    params(AppealQueueQuery),
+    responses(
+        (status = 200, description = "Appeals in the requested status, oldest first", body = [Appeal]),
+        (status = 400, description = "Unknown status"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_appeals(
     auth: Auth,
     data: web::Data<AppState>,
-    path: web::Path<Id>,
+    query: web::Query<AppealQueueQuery>,
 ) -> Result<HttpResponse, ApiError> {
-    ensure_admin!(auth);
-    let id = path.into_inner();
-    let hashes = data.repo.list_board_image_hashes(id).await?;
-    data.repo.hard_delete_board(id).await?;
-    delete_unreferenced_images(data.get_ref(), hashes).await?;
-    Ok(HttpResponse::NoContent().finish())
+    use crate::appeals::MAX_APPEAL_QUEUE;
+    ensure_moderator_or_admin!(auth);
+    let status = match query.status.as_deref() {
+        None => AppealStatus::Pending,
+        Some(status) => AppealStatus::parse(status).ok_or(ApiError::BadRequest)?,
+    };
+    let limit = query
+        .limit
+        .unwrap_or(MAX_APPEAL_QUEUE)
+        .clamp(1, MAX_APPEAL_QUEUE);
+    Ok(HttpResponse::Ok().json(data.repo.list_appeals(status, limit).await?))
 }
 
-pub async fn admin_soft_delete_thread(
+async fn decide_appeal(
     auth: Auth,
     data: web::Data<AppState>,
-    path: web::Path<Id>,
+    id: Id,
+    status: AppealStatus,
+    decision: AppealDecision,
 ) -> Result<HttpResponse, ApiError> {
+    use crate::appeals::MAX_NOTE_CHARS;
     ensure_moderator_or_admin!(auth);
-    data.repo.soft_delete_thread(path.into_inner()).await?;
-    Ok(HttpResponse::Ok().json(serde_json::json!({"status":"ok"})))
+    let note = decision
+        .note
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+    if note
+        .as_ref()
+        .is_some_and(|note| note.chars().count() > MAX_NOTE_CHARS)
+    {
+        return Err(ApiError::Invalid(format!(
+            "notes are at most {MAX_NOTE_CHARS} characters"
+        )));
+    }
+    let appeal = service::decide_appeal(&data, &auth, id, status, note).await?;
+    Ok(HttpResponse::Ok().json(appeal))
 }
-pub async fn admin_restore_thread(
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/appeals/{id}/accept",
+    params(("id" = Id, Path, description = "Appeal id")),
+    request_body = AppealDecision,
+    responses(
+        (status = 200, description = "Appeal accepted: the ban is lifted or the post restored, and the user notified", body = Appeal),
+        (status = 400, description = "Overlong note"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required"),
+        (status = 404, description = "Appeal not found"),
+        (status = 409, description = "Appeal already decided")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn accept_appeal(
     auth: Auth,
     data: web::Data<AppState>,
     path: web::Path<Id>,
+    payload: web::Json<AppealDecision>,
 ) -> Result<HttpResponse, ApiError> {
-    ensure_moderator_or_admin!(auth);
-    data.repo.restore_thread(path.into_inner()).await?;
-    Ok(HttpResponse::Ok().json(serde_json::json!({"status":"ok"})))
+    decide_appeal(
+        auth,
+        data,
+        path.into_inner(),
+        AppealStatus::Accepted,
+        payload.into_inner(),
+    )
+    .await
 }
-pub async fn admin_hard_delete_thread(
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/appeals/{id}/deny",
+    params(("id" = Id, Path, description = "Appeal id")),
+    request_body = AppealDecision,
+    responses(
+        (status = 200, description = "Appeal denied and the user notified", body = Appeal),
+        (status = 400, description = "Overlong note"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required"),
+        (status = 404, description = "Appeal not found"),
+        (status = 409, description = "Appeal already decided")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn deny_appeal(
     auth: Auth,
     data: web::Data<AppState>,
     path: web::Path<Id>,
+    payload: web::Json<AppealDecision>,
 ) -> Result<HttpResponse, ApiError> {
-    ensure_admin!(auth);
-    let id = path.into_inner();
-    let hashes = data.repo.list_thread_image_hashes(id).await?;
-    data.repo.hard_delete_thread(id).await?;
-    delete_unreferenced_images(data.get_ref(), hashes).await?;
-    Ok(HttpResponse::NoContent().finish())
+    decide_appeal(
+        auth,
+        data,
+        path.into_inner(),
+        AppealStatus::Denied,
+        payload.into_inner(),
+    )
+    .await
 }
 
-pub async fn admin_soft_delete_reply(
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+pub struct ReportQueueQuery {
+    /// `open` (default), `resolved` or `dismissed`
+    status: Option<String>,
+    /// At most 100 (the default)
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/reports",
+    params(ReportQueueQuery),
+    responses(
+        (status = 200, description = "Reports in the requested status, oldest first", body = [Report]),
+        (status = 400, description = "Unknown status"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_reports(
     auth: Auth,
     data: web::Data<AppState>,
-    path: web::Path<Id>,
+    query: web::Query<ReportQueueQuery>,
 ) -> Result<HttpResponse, ApiError> {
+    use crate::reports::MAX_REPORT_QUEUE;
     ensure_moderator_or_admin!(auth);
-    data.repo.soft_delete_reply(path.into_inner()).await?;
-    Ok(HttpResponse::Ok().json(serde_json::json!({"status":"ok"})))
+    let status = match query.status.as_deref() {
+        None => ReportStatus::Open,
+        Some(status) => ReportStatus::parse(status).ok_or(ApiError::BadRequest)?,
+    };
+    let limit = query
+        .limit
+        .unwrap_or(MAX_REPORT_QUEUE)
+        .clamp(1, MAX_REPORT_QUEUE);
+    Ok(HttpResponse::Ok().json(data.repo.list_reports(status, limit).await?))
 }
-pub async fn admin_restore_reply(
+
+async fn decide_report(
     auth: Auth,
     data: web::Data<AppState>,
-    path: web::Path<Id>,
+    id: Id,
+    status: ReportStatus,
+    decision: ReportDecision,
 ) -> Result<HttpResponse, ApiError> {
+    use crate::reports::MAX_NOTE_CHARS;
     ensure_moderator_or_admin!(auth);
-    data.repo.restore_reply(path.into_inner()).await?;
-    Ok(HttpResponse::Ok().json(serde_json::json!({"status":"ok"})))
+    let note = decision
+        .note
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+    if note
+        .as_ref()
+        .is_some_and(|note| note.chars().count() > MAX_NOTE_CHARS)
+    {
+        return Err(ApiError::Invalid(format!(
+            "notes are at most {MAX_NOTE_CHARS} characters"
+        )));
+    }
+    let report = data
+        .repo
+        .decide_report(id, &auth.0.sub, status, note)
+        .await?;
+    log::info!(
+        "report {id} on {} {} {} by {}",
+        report.kind.as_str(),
+        report.target_id,
+        status.as_str(),
+        auth.0.sub
+    );
+    Ok(HttpResponse::Ok().json(report))
 }
-pub async fn admin_hard_delete_reply(
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/reports/{id}/resolve",
+    params(("id" = Id, Path, description = "Report id")),
+    request_body = ReportDecision,
+    responses(
+        (status = 200, description = "Report resolved, with the other open reports on the same post", body = Report),
+        (status = 400, description = "Overlong note"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required"),
+        (status = 404, description = "Report not found"),
+        (status = 409, description = "Report already decided")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn resolve_report(
     auth: Auth,
     data: web::Data<AppState>,
     path: web::Path<Id>,
+    payload: web::Json<ReportDecision>,
 ) -> Result<HttpResponse, ApiError> {
-    ensure_admin!(auth);
-    let id = path.into_inner();
-    // Fetch reply to capture image hash before deletion
-    let reply = data.repo.get_reply(id).await.ok();
-    data.repo.hard_delete_reply(id).await?;
-    if let Some(r) = reply {
-        if let Some(hash) = r.image_hash {
-            delete_unreferenced_images(data.get_ref(), vec![hash]).await?;
-        }
-    }
-    Ok(HttpResponse::NoContent().finish())
+    decide_report(
+        auth,
+        data,
+        path.into_inner(),
+        ReportStatus::Resolved,
+        payload.into_inner(),
+    )
+    .await
 }
 
-async fn delete_unreferenced_images(data: &AppState, hashes: Vec<String>) -> Result<(), ApiError> {
-    let unique_hashes: std::collections::HashSet<String> = hashes.into_iter().collect();
-    for hash in unique_hashes {
-        if !data.repo.is_image_referenced(&hash).await? {
-            if let Err(error) = data.image_store.delete(&hash).await {
-                log::error!("failed to delete unreferenced image {hash}: {error}");
-            }
-        }
-    }
-    Ok(())
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/reports/{id}/dismiss",
+    params(("id" = Id, Path, description = "Report id")),
+    request_body = ReportDecision,
+    responses(
+        (status = 200, description = "Report dismissed, with the other open reports on the same post", body = Report),
+        (status = 400, description = "Overlong note"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required"),
+        (status = 404, description = "Report not found"),
+        (status = 409, description = "Report already decided")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn dismiss_report(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<ReportDecision>,
+) -> Result<HttpResponse, ApiError> {
+    decide_report(
+        auth,
+        data,
+        path.into_inner(),
+        ReportStatus::Dismissed,
+        payload.into_inner(),
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/system",
+    responses(
+        (status = 200, description = "Uptime, build, pools, image store, background jobs and redacted configuration", body = crate::system::SystemReport),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn admin_system(auth: Auth, data: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    Ok(HttpResponse::Ok()
+        .insert_header((actix_web::http::header::CACHE_CONTROL, "no-store"))
+        .json(data.system.report(data.image_store.as_ref())))
 }
-// ------------------------------------------------------------------
 
 #[utoipa::path(
     post,
-    path = "/api/v1/replies",
-    request_body = NewReply,
+    path = "/api/v1/admin/config/reload",
     responses(
-        (status = 201, description = "Reply created", body = Reply),
-        (status = 404, description = "Thread not found"),
-        (status = 403, description = "Forbidden")
-    )
+        (status = 200, description = "Rate limits, throttle caps, CORS origins and upload policy rebuilt from the environment", body = crate::reload::ReloadReport),
+        (status = 400, description = "The env file could not be read; previous settings stay in force"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 503, description = "Live reload is not enabled on this server")
+    ),
+    security(("bearer_auth" = []))
 )]
-pub async fn create_reply(
+pub async fn admin_reload_config(
     auth: Auth,
-    req: HttpRequest,
     data: web::Data<AppState>,
-    payload: web::Json<NewReply>,
 ) -> Result<HttpResponse, ApiError> {
-    let (subject_key, created_by) = private_author_attribution(&auth)?;
-    ensure_subject_can_post(data.get_ref(), &auth, &subject_key).await?;
-    if let Some(rl) = &data.rate_limiter {
-        let ip = extract_client_ip(&req);
-        if !rl.allow_reply(&ip) {
-            metrics::increment_counter!("rate_limit_denied", "action" => "reply_create");
-            return Err(ApiError::RateLimited {
-                retry_after: rl.cfg.reply_window.as_secs(),
-            });
-        }
-        metrics::increment_counter!("rate_limit_allowed", "action" => "reply_create");
-    }
-    if !auth
-        .0
-        .roles
-        .iter()
-        .any(|r| matches!(r, Role::User | Role::Moderator | Role::Admin))
-    {
-        return Err(ApiError::Forbidden);
-    }
-    let mut new = payload.into_inner();
-    new.content = new.content.trim().to_string();
-    validate_reply_payload(&new)?;
-    let thread = data
-        .repo
-        .get_thread(new.thread_id)
-        .await
-        .map_err(|_| ApiError::NotFound)?;
-    if thread.deleted_at.is_some() {
-        return Err(ApiError::NotFound);
-    }
-    let public_identity =
-        derive_public_identity(new.author_name.take(), new.tripcode_password.take())?;
-    let reply = data
-        .repo
-        .create_reply(new, created_by, public_identity)
-        .await?;
-    Ok(HttpResponse::Created().json(reply))
+    ensure_admin!(auth);
+    let reloader = data.reloader.as_ref().ok_or(ApiError::Unavailable)?;
+    let report = reloader.reload_logged("api").map_err(ApiError::Invalid)?;
+    log::info!("configuration reload requested by {}", auth.0.sub);
+    Ok(HttpResponse::Ok().json(report))
 }
 
-#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
-pub struct FileUploadResponse {
-    pub hash: String,
-    pub mime: String,
-    pub size: usize,
-    pub duplicate: bool, // true when upload was a duplicate (idempotent)
-}
-
-const FILE_SIZE_LIMIT: usize = 25 * 1024 * 1024; // 25 MB
-
-const ALLOWED_MIME: &[&str] = &[
-    // Images
-    "image/png",
-    "image/jpeg",
-    "image/gif",
-    "image/webp",
-    "image/bmp",
-    "image/tiff",
-    "image/svg+xml",
-    // Videos
-    "video/mp4",
-    "video/webm",
-    "video/avi",
-    "video/mov",
-    "video/wmv",
-    "video/flv",
-    // Audio
-    "audio/mpeg",
-    "audio/wav",
-    "audio/ogg",
-    "audio/flac",
-    "audio/aac",
-    "audio/m4a",
-    // Documents
-    "application/pdf",
-    "application/msword",
-    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
-    "application/vnd.ms-excel",
-    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
-    "application/vnd.ms-powerpoint",
-    "application/vnd.openxmlformats-officedocument.presentationml.presentation",
-    "application/rtf",
-    "application/vnd.oasis.opendocument.text",
-    "application/vnd.oasis.opendocument.spreadsheet",
-    "application/vnd.oasis.opendocument.presentation",
-    // Plain text and code
-    "text/plain",
-    "text/csv",
-    "text/html",
-    "text/css",
-    "text/javascript",
-    "application/json",
-    "application/xml",
-    "text/xml",
-    "application/yaml",
-    // Archives
-    "application/zip",
-    "application/x-rar-compressed",
-    "application/x-7z-compressed",
-    "application/x-tar",
-    "application/gzip",
-    "application/x-bzip2",
-    // Other common formats
-    "application/octet-stream", // Generic binary
-];
-
-fn detect_upload_mime(bytes: &[u8]) -> String {
-    if let Some(kind) = infer::get(bytes) {
-        return kind.mime_type().to_string();
-    }
-    if std::str::from_utf8(bytes).is_ok() && !bytes.contains(&0) {
-        return "text/plain".to_string();
-    }
-    "application/octet-stream".to_string()
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/system/migrations",
+    responses(
+        (status = 200, description = "Applied and pending migrations and the current schema version", body = MigrationStatus),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn admin_migration_status(
+    auth: Auth,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let applied = data.repo.applied_migrations().await?;
+    Ok(HttpResponse::Ok().json(MigrationStatus::compare(&crate::db::MIGRATOR, &applied)))
 }
 
-fn is_inline_preview_mime(mime: &str) -> bool {
-    (mime.starts_with("image/") && mime != "image/svg+xml")
-        || mime.starts_with("video/")
-        || mime.starts_with("audio/")
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/export",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Versioned dump of boards, threads, replies, images, and roles", content_type = "application/x-ndjson"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role, or a service account with the `export` scope, required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn admin_export(
+    req: HttpRequest,
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    query: web::Query<ExportQuery>,
+) -> Result<HttpResponse, ApiError> {
+    if crate::service_accounts::authorize(&req, &data, ServiceScope::Export)
+        .await?
+        .is_none()
+    {
+        let auth = auth.ok_or(ApiError::Unauthorized)?;
+        ensure_admin!(auth);
+    }
+    let format = query.format;
+    let filename = format!(
+        "rib-export-{}.{}",
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ"),
+        format.extension()
+    );
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{filename}\""),
+        ))
+        .streaming(crate::transfer::export_stream(data.repo.clone(), format)))
 }
 
 #[utoipa::path(
     post,
-    path = "/api/v1/images",
+    path = "/api/v1/admin/import",
+    params(ImportOptions),
+    request_body(content = String, description = "Dump produced by /api/v1/admin/export", content_type = "application/x-ndjson"),
     responses(
-    (status = 201, description = "File stored (new)", body = FileUploadResponse),
-    (status = 200, description = "File already existed (idempotent)", body = FileUploadResponse),
-        (status = 415, description = "Unsupported media type"),
-        (status = 413, description = "Payload too large"),
-    )
+        (status = 200, description = "Import applied, or dry run completed", body = ImportReport),
+        (status = 400, description = "Malformed, truncated, or inconsistent dump"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 409, description = "Conflicts found with on_conflict=fail; nothing imported", body = ImportReport)
+    ),
+    security(("bearer_auth" = []))
 )]
-pub async fn upload_image(
+pub async fn admin_import(
     auth: Auth,
-    req: HttpRequest,
     data: web::Data<AppState>,
-    mut payload: Multipart,
+    query: web::Query<ImportOptions>,
+    body: web::Bytes,
 ) -> Result<HttpResponse, ApiError> {
-    use actix_web::http::StatusCode;
-    let subject_key = role_subject_key(&auth.0.sub).ok_or(ApiError::Forbidden)?;
-    ensure_subject_can_post(data.get_ref(), &auth, &subject_key).await?;
-    if let Some(rl) = &data.rate_limiter {
-        let ip = extract_client_ip(&req);
-        if !rl.allow_image(&ip) {
-            metrics::increment_counter!("rate_limit_denied", "action" => "image_upload");
-            return Err(ApiError::RateLimited {
-                retry_after: rl.cfg.image_window.as_secs(),
-            });
-        }
-        metrics::increment_counter!("rate_limit_allowed", "action" => "image_upload");
-    }
-    let mut bytes: Vec<u8> = Vec::new();
-    while let Some(field) = payload.try_next().await.map_err(|e| {
-        log::error!("multipart error: {e}");
-        ApiError::Internal
-    })? {
-        if let Some(name) = field.content_disposition().get_name() {
-            if name != "file" {
-                continue;
-            }
-        } else {
-            continue;
-        }
-        let mut field_stream = field;
-        let mut hasher = Sha256::new();
-        while let Some(chunk) = field_stream.try_next().await.map_err(|e| {
-            log::error!("stream read error: {e}");
-            ApiError::Internal
-        })? {
-            if bytes.len() + chunk.len() > FILE_SIZE_LIMIT {
-                return Ok(HttpResponse::build(StatusCode::PAYLOAD_TOO_LARGE).finish());
-            }
-            hasher.update(&chunk);
-            bytes.extend_from_slice(&chunk);
-        }
-        let hash = format!("{:x}", hasher.finalize());
-        // Infer MIME
-        let mime = detect_upload_mime(&bytes);
-        if !ALLOWED_MIME.contains(&mime.as_str()) {
-            return Ok(HttpResponse::UnsupportedMediaType().finish());
-        }
-        // Attempt to persist (idempotent semantics)
-        let (status_code, duplicate_flag) = match data.image_store.save(&hash, &mime, &bytes).await
-        {
-            Ok(()) => (actix_web::http::StatusCode::CREATED, false),
-            Err(ImageStoreError::Duplicate) => (actix_web::http::StatusCode::OK, true),
-            Err(e) => {
-                log::error!("image_store save error: {e}");
-                return Err(ApiError::Internal);
-            }
-        };
-        let resp = FileUploadResponse {
-            hash,
-            mime,
-            size: bytes.len(),
-            duplicate: duplicate_flag,
-        };
-        return Ok(HttpResponse::build(status_code).json(resp));
-    }
-    Ok(HttpResponse::BadRequest().finish())
-}
-
-// Serve stored image / video by hash
-pub async fn get_image(
-    req: HttpRequest,
-    data: web::Data<AppState>,
-    path: web::Path<String>,
-) -> Result<HttpResponse, ApiError> {
-    let hash = path.into_inner();
-    if !is_valid_content_hash(&hash) {
-        return Err(ApiError::NotFound);
-    }
-    let etag = format!("\"{hash}\"");
-    if req
-        .headers()
-        .get(actix_web::http::header::IF_NONE_MATCH)
-        .and_then(|value| value.to_str().ok())
-        == Some(etag.as_str())
-    {
-        return Ok(HttpResponse::NotModified().finish());
-    }
-    match data.image_store.load(&hash).await {
-        Ok((bytes, mime)) => {
-            let mut response = HttpResponse::Ok();
-            response
-                .insert_header(("Content-Type", mime.as_str()))
-                .insert_header(("ETag", etag))
-                .insert_header(("Cache-Control", "public, max-age=31536000, immutable"));
-            if !is_inline_preview_mime(&mime) {
-                response.insert_header((
-                    "Content-Disposition",
-                    format!("attachment; filename=\"{hash}\""),
-                ));
-            }
-            Ok(response.body(bytes))
-        }
-        Err(ImageStoreError::NotFound) => Err(ApiError::NotFound),
-        Err(e) => {
-            log::error!("image_store load error: {e}");
-            Err(ApiError::Internal)
-        }
+    ensure_admin!(auth);
+    let dump = Dump::parse(&body).map_err(|e| ApiError::Invalid(e.to_string()))?;
+    let report = data.repo.import_dump(&dump, query.into_inner()).await?;
+    log::info!(
+        "import by {} (dry_run={}, committed={}): {} boards, {} threads, {} replies",
+        auth.0.sub,
+        report.dry_run,
+        report.committed,
+        report.boards.created,
+        report.threads.created,
+        report.replies.created
+    );
+    if report.conflicts.is_empty() {
+        Ok(HttpResponse::Ok().json(report))
+    } else {
+        Ok(HttpResponse::Conflict().json(report))
     }
 }
 
-// ---------------------------------------------------------------------
 #[utoipa::path(
-    patch,
-    path = "/api/v1/boards/{id}",
-    request_body = UpdateBoard,
-    params(("id" = Id, Path, description = "Board id")),
+    post,
+    path = "/api/v1/admin/import/archive",
+    params(ImportOptions),
+    request_body = ArchiveImportRequest,
     responses(
-        (status = 200, description = "Board updated", body = Board),
-        (status = 404, description = "Board not found"),
-        (status = 409, description = "Conflict")
-    )
+        (status = 200, description = "Archive imported, or dry run completed", body = ArchiveImportReport),
+        (status = 400, description = "Thread documents do not match the mapping"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 409, description = "Conflicts found with on_conflict=fail; nothing imported", body = ArchiveImportReport)
+    ),
+    security(("bearer_auth" = []))
 )]
-pub async fn update_board(
+pub async fn admin_import_archive(
     auth: Auth,
     data: web::Data<AppState>,
-    path: web::Path<Id>,
-    payload: web::Json<UpdateBoard>,
+    query: web::Query<ImportOptions>,
+    payload: web::Json<ArchiveImportRequest>,
 ) -> Result<HttpResponse, ApiError> {
-    // ── admin-only guard ────────────────────────────────────────────
-    if !auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
-        return Err(ApiError::Forbidden); // 403
-    }
-    // ────────────────────────────────────────────────────────────────
-    let mut update = payload.into_inner();
-    update.slug = update.slug.map(|slug| slug.trim().to_string());
-    update.title = update.title.map(|title| title.trim().to_string());
-    if update.slug.as_ref().is_some_and(|slug| {
-        validate_board_fields(slug, update.title.as_deref().unwrap_or("x")).is_err()
-    }) || update
-        .title
-        .as_ref()
-        .is_some_and(|title| title.is_empty() || title.chars().count() > 100)
-    {
-        return Err(ApiError::BadRequest);
-    }
-    let board = data.repo.update_board(path.into_inner(), update).await?;
-    Ok(HttpResponse::Ok().json(board))
-}
-// ---------------------------------------------------------------------
-
-// Discord OAuth endpoints
-pub async fn discord_login() -> Result<HttpResponse, ApiError> {
-    // Graceful degradation: return 503 JSON if Discord OAuth isn't configured
-    let client_id = match std::env::var("DISCORD_CLIENT_ID") {
-        Ok(v) => v,
-        Err(_) => {
-            return Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-                "error": "discord_oauth_not_configured",
-                "message": "Set DISCORD_CLIENT_ID / DISCORD_CLIENT_SECRET to enable Discord login"
-            })));
+    ensure_admin!(auth);
+    let request = payload.into_inner();
+    validate_board_fields(&request.board.slug, &request.board.title)?;
+    let options = query.into_inner();
+    let mut archive = request.convert().map_err(ApiError::Invalid)?;
+    // Dry runs must not write to the image store.
+    let media = if request.download_media && !options.dry_run {
+        crate::archive::download_media(
+            data.image_store.as_ref(),
+            &data.uploads.current().allowed,
+            &mut archive,
+        )
+        .await
+    } else {
+        crate::archive::MediaSummary {
+            skipped: archive.media.len() as u64,
+            ..Default::default()
         }
     };
-    // Prefer explicit env; otherwise synthesize from FRONTEND_URL replacing scheme/host
-    let redirect_uri = std::env::var("DISCORD_REDIRECT_URI")
-        .ok()
-        .or_else(|| {
-            std::env::var("FRONTEND_URL").ok().map(|f| {
-                // Normalize trailing slash
-                let base = f.trim_end_matches('/');
-                format!("{}/api/v1/auth/discord/callback", base)
-            })
-        })
-        .unwrap_or_else(|| "http://localhost:8080/api/v1/auth/discord/callback".to_string());
-
-    let transaction = create_oauth_transaction().map_err(|_| ApiError::Internal)?;
-    let auth_url = format!(
-        "https://discord.com/api/oauth2/authorize?client_id={}&redirect_uri={}&response_type=code&scope=identify&state={}&code_challenge={}&code_challenge_method=S256",
-        client_id,
-        urlencoding::encode(&redirect_uri),
-        transaction.state,
-        transaction.code_challenge,
+    let import = data.repo.import_dump(&archive.dump, options).await?;
+    log::info!(
+        "archive import by {} into /{}/ (dry_run={}, committed={}): {} threads, {} replies, {} media",
+        auth.0.sub,
+        request.board.slug,
+        import.dry_run,
+        import.committed,
+        import.threads.created,
+        import.replies.created,
+        media.downloaded
     );
-
-    Ok(HttpResponse::Found()
-        .insert_header(("Location", auth_url))
-        .cookie(transaction.cookie)
-        .finish())
+    let conflicted = !import.conflicts.is_empty();
+    let report = ArchiveImportReport {
+        import,
+        media,
+        truncated_posts: archive.truncated_posts,
+    };
+    if conflicted {
+        Ok(HttpResponse::Conflict().json(report))
+    } else {
+        Ok(HttpResponse::Ok().json(report))
+    }
 }
 
-#[derive(serde::Deserialize)]
-pub struct DiscordCallback {
-    code: String,
-    state: String,
-}
+/// Longest reason accepted for a removal; it may be shown on a tombstone.
+const MAX_DELETION_REASON_CHARS: usize = 200;
 
-#[derive(serde::Deserialize)]
-struct DiscordTokenResponse {
-    access_token: String,
-    #[allow(dead_code)]
-    token_type: String, // Keep for completeness even if unused
+/// Trimmed removal reason, or `BadRequest` when empty or too long.
+fn deletion_reason(reason: &str) -> Result<String, ApiError> {
+    let reason = reason.trim();
+    if reason.is_empty() || reason.chars().count() > MAX_DELETION_REASON_CHARS {
+        return Err(ApiError::BadRequest);
+    }
+    Ok(reason.to_string())
 }
 
-#[derive(serde::Deserialize)]
-struct DiscordUser {
-    id: String,
-    username: String,
-    #[allow(dead_code)]
-    discriminator: String, // Keep for completeness even if unused
+fn deletion_entry(
+    auth: &Auth,
+    kind: DeletionKind,
+    target_id: Id,
+    hard: bool,
+    reason: String,
+) -> NewDeletionEntry {
+    NewDeletionEntry {
+        kind,
+        target_id,
+        hard,
+        actor: auth.0.sub.clone(),
+        reason,
+    }
 }
 
-fn discord_admission_role(assigned_role: Option<Role>, is_bootstrap_admin: bool) -> Option<Role> {
-    if is_bootstrap_admin {
-        Some(Role::Admin)
-    } else {
-        assigned_role
-    }
+pub async fn admin_soft_delete_board(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<DeletionReason>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let id = path.into_inner();
+    let reason = deletion_reason(&payload.reason)?;
+    data.repo.soft_delete_board(id).await?;
+    data.repo
+        .record_deletion(deletion_entry(
+            &auth,
+            DeletionKind::Board,
+            id,
+            false,
+            reason,
+        ))
+        .await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"status":"ok"})))
+}
+pub async fn admin_restore_board(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    data.repo.restore_board(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"status":"ok"})))
+}
+pub async fn admin_hard_delete_board(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<DeletionReason>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let id = path.into_inner();
+    let reason = deletion_reason(&payload.reason)?;
+    let hashes = data.repo.list_board_image_hashes(id).await?;
+    data.repo.hard_delete_board(id).await?;
+    data.repo
+        .record_deletion(deletion_entry(&auth, DeletionKind::Board, id, true, reason))
+        .await?;
+    delete_unreferenced_images(data.get_ref(), hashes).await?;
+    Ok(HttpResponse::NoContent().finish())
 }
 
-pub async fn discord_callback(
-    req: HttpRequest,
-    query: web::Query<DiscordCallback>,
+pub async fn admin_soft_delete_thread(
+    auth: Auth,
     data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<DeletionReason>,
 ) -> Result<HttpResponse, ApiError> {
-    let client_id = match std::env::var("DISCORD_CLIENT_ID") {
-        Ok(v) => v,
-        Err(_) => {
-            return Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-                "error": "discord_oauth_not_configured",
-                "stage": "client_id"
-            })));
-        }
-    };
-    let client_secret = match std::env::var("DISCORD_CLIENT_SECRET") {
-        Ok(v) => v,
-        Err(_) => {
-            return Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
-                "error": "discord_oauth_not_configured",
-                "stage": "client_secret"
-            })));
-        }
-    };
-    let redirect_uri = std::env::var("DISCORD_REDIRECT_URI")
-        .ok()
-        .or_else(|| {
-            std::env::var("FRONTEND_URL").ok().map(|f| {
-                let base = f.trim_end_matches('/');
-                format!("{}/api/v1/auth/discord/callback", base)
-            })
+    ensure_moderator_or_admin!(auth);
+    let id = path.into_inner();
+    let reason = deletion_reason(&payload.reason)?;
+    let entry = deletion_entry(&auth, DeletionKind::Thread, id, false, reason);
+    // Logged in the same transaction, so tombstones find the reason.
+    transaction(&*data.repo, |tx| {
+        let entry = entry.clone();
+        Box::pin(async move {
+            tx.soft_delete_thread(id).await?;
+            tx.record_deletion(entry).await
         })
-        .unwrap_or_else(|| "http://localhost:8080/api/v1/auth/discord/callback".to_string());
-
-    let transaction_cookie = req
-        .cookie(OAUTH_TRANSACTION_COOKIE_NAME)
-        .ok_or(ApiError::BadRequest)?;
-    let pkce_verifier = consume_oauth_transaction(transaction_cookie.value(), &query.state)
-        .map_err(|_| ApiError::BadRequest)?;
-
-    // Exchange code for token
-    let client = reqwest::Client::builder()
-        .connect_timeout(std::time::Duration::from_secs(3))
-        .timeout(std::time::Duration::from_secs(10))
-        .build()
-        .map_err(|_| ApiError::Internal)?;
+    })
+    .await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"status":"ok"})))
+}
+pub async fn admin_restore_thread(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    data.repo.restore_thread(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"status":"ok"})))
+}
+pub async fn admin_hard_delete_thread(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<DeletionReason>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let id = path.into_inner();
+    let reason = deletion_reason(&payload.reason)?;
+    let hashes = data.repo.list_thread_image_hashes(id).await?;
+    data.repo.hard_delete_thread(id).await?;
+    data.repo
+        .record_deletion(deletion_entry(
+            &auth,
+            DeletionKind::Thread,
+            id,
+            true,
+            reason,
+        ))
+        .await?;
+    delete_unreferenced_images(data.get_ref(), hashes).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub async fn admin_soft_delete_reply(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<DeletionReason>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    let id = path.into_inner();
+    let reason = deletion_reason(&payload.reason)?;
+    let entry = deletion_entry(&auth, DeletionKind::Reply, id, false, reason);
+    // Logged in the same transaction, so tombstones find the reason.
+    transaction(&*data.repo, |tx| {
+        let entry = entry.clone();
+        Box::pin(async move {
+            tx.soft_delete_reply(id).await?;
+            tx.record_deletion(entry).await
+        })
+    })
+    .await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"status":"ok"})))
+}
+pub async fn admin_restore_reply(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    data.repo.restore_reply(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"status":"ok"})))
+}
+pub async fn admin_hard_delete_reply(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<DeletionReason>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let id = path.into_inner();
+    let reason = deletion_reason(&payload.reason)?;
+    // Fetch reply to capture image hash before deletion
+    let reply = data.repo.get_reply(id).await.ok();
+    data.repo.hard_delete_reply(id).await?;
+    data.repo
+        .record_deletion(deletion_entry(&auth, DeletionKind::Reply, id, true, reason))
+        .await?;
+    if let Some(r) = reply {
+        if let Some(hash) = r.image_hash {
+            delete_unreferenced_images(data.get_ref(), vec![hash]).await?;
+        }
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+pub struct HeldPostsQuery {
+    /// At most 100 (the default)
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/held-posts",
+    params(HeldPostsQuery),
+    responses(
+        (status = 200, description = "Posts from low-trust subjects awaiting review, oldest first", body = [HeldPost]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_held_posts(
+    auth: Auth,
+    data: web::Data<AppState>,
+    query: web::Query<HeldPostsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    use crate::trust::MAX_HELD_QUEUE;
+    ensure_moderator_or_admin!(auth);
+    let limit = query
+        .limit
+        .unwrap_or(MAX_HELD_QUEUE)
+        .clamp(1, MAX_HELD_QUEUE);
+    Ok(HttpResponse::Ok().json(data.repo.list_held_posts(limit).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/threads/{id}/approve",
+    params(("id" = Id, Path, description = "Thread id")),
+    responses(
+        (status = 204, description = "Held thread published"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required"),
+        (status = 404, description = "No held thread with this id")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn approve_held_thread(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    data.repo
+        .release_held_thread(path.into_inner(), true)
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/threads/{id}/reject",
+    params(("id" = Id, Path, description = "Thread id")),
+    responses(
+        (status = 204, description = "Held thread stays deleted and counts as a removal"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required"),
+        (status = 404, description = "No held thread with this id")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn reject_held_thread(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    data.repo
+        .release_held_thread(path.into_inner(), false)
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/replies/{id}/approve",
+    params(("id" = Id, Path, description = "Reply id")),
+    responses(
+        (status = 204, description = "Held reply published"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required"),
+        (status = 404, description = "No held reply with this id")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn approve_held_reply(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    data.repo
+        .release_held_reply(path.into_inner(), true)
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/replies/{id}/reject",
+    params(("id" = Id, Path, description = "Reply id")),
+    responses(
+        (status = 204, description = "Held reply stays deleted and counts as a removal"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required"),
+        (status = 404, description = "No held reply with this id")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn reject_held_reply(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    data.repo
+        .release_held_reply(path.into_inner(), false)
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Longest reason staff may give when quarantining a blob.
+const MAX_QUARANTINE_REASON_CHARS: usize = 500;
+/// Most quarantined blobs listed at once.
+const MAX_QUARANTINE_QUEUE: i64 = 100;
+
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+pub struct QuarantineQuery {
+    /// At most 100 (the default)
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/quarantine",
+    params(QuarantineQuery),
+    responses(
+        (status = 200, description = "Quarantined blobs awaiting review, oldest first", body = [QuarantinedImage]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_quarantine(
+    auth: Auth,
+    data: web::Data<AppState>,
+    query: web::Query<QuarantineQuery>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    let limit = query
+        .limit
+        .unwrap_or(MAX_QUARANTINE_QUEUE)
+        .clamp(1, MAX_QUARANTINE_QUEUE);
+    Ok(HttpResponse::Ok().json(data.repo.list_quarantined_images(limit).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/quarantine",
+    request_body = NewQuarantine,
+    responses(
+        (status = 201, description = "Blob withheld from non-staff until reviewed", body = QuarantinedImage),
+        (status = 400, description = "Invalid hash or reason"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required"),
+        (status = 409, description = "Blob already quarantined")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn quarantine_image(
+    auth: Auth,
+    data: web::Data<AppState>,
+    payload: web::Json<NewQuarantine>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    let source = role_subject_key(&auth.0.sub).ok_or(ApiError::Forbidden)?;
+    let NewQuarantine { hash, reason } = payload.into_inner();
+    let reason = reason.trim();
+    if !is_valid_content_hash(&hash)
+        || reason.is_empty()
+        || reason.chars().count() > MAX_QUARANTINE_REASON_CHARS
+    {
+        return Err(ApiError::BadRequest);
+    }
+    if data.repo.get_quarantined_image(&hash).await?.is_some() {
+        return Err(ApiError::Conflict);
+    }
+    data.repo.quarantine_image(&hash, reason, &source).await?;
+    log::info!("{source} quarantined image {hash}: {reason}");
+    let quarantined = data
+        .repo
+        .get_quarantined_image(&hash)
+        .await?
+        .ok_or(ApiError::Internal)?;
+    Ok(HttpResponse::Created().json(quarantined))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/quarantine/{hash}/approve",
+    params(("hash" = String, Path, description = "SHA-256 of the blob")),
+    responses(
+        (status = 204, description = "Blob served to everyone again"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required"),
+        (status = 404, description = "Blob is not awaiting review")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn approve_quarantined_image(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    let hash = path.into_inner();
+    data.repo.release_quarantined_image(&hash).await?;
+    metrics::increment_counter!("quarantine_reviewed", "decision" => "approve");
+    log::info!("{} approved quarantined image {hash}", auth.0.sub);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/quarantine/{hash}/destroy",
+    params(("hash" = String, Path, description = "SHA-256 of the blob")),
+    responses(
+        (status = 204, description = "Blob deleted and detached from every post; re-uploads are refused"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required"),
+        (status = 404, description = "Blob is not awaiting review"),
+        (status = 409, description = "Blob is under legal hold")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn destroy_quarantined_image(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    let hash = path.into_inner();
+    data.repo.destroy_quarantined_image(&hash).await?;
+    if let Err(error) = data.image_store.delete(&hash).await {
+        log::error!("failed to delete destroyed image {hash}: {error}");
+    }
+    delete_thumbnail(&data, &hash).await;
+    metrics::increment_counter!("quarantine_reviewed", "decision" => "destroy");
+    log::info!("{} destroyed quarantined image {hash}", auth.0.sub);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Delete the rendered preview of a blob that is gone.
+async fn delete_thumbnail(data: &AppState, hash: &str) {
+    let thumbnail = match data.repo.image_thumbnail(hash).await {
+        Ok(Some(thumbnail)) => thumbnail,
+        Ok(None) => return,
+        Err(e) => {
+            log::error!("failed to look up the preview of {hash}: {e}");
+            return;
+        }
+    };
+    match data.image_store.delete(&thumbnail).await {
+        Ok(()) | Err(ImageStoreError::NotFound) => {}
+        Err(e) => log::error!("failed to delete the preview of {hash}: {e}"),
+    }
+}
+
+/// Most uploads listed per subject at once.
+const MAX_UPLOAD_LISTING: i64 = 200;
+
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+pub struct UploaderQuery {
+    /// Subject key, e.g. `discord:1234`
+    uploader: String,
+    /// At most 200 (the default)
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/images",
+    params(UploaderQuery),
+    responses(
+        (status = 200, description = "The subject's uploads, newest first", body = [UploadRecord]),
+        (status = 400, description = "Invalid subject"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_uploaded_images(
+    auth: Auth,
+    data: web::Data<AppState>,
+    query: web::Query<UploaderQuery>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    let uploader = query.uploader.trim();
+    if !is_valid_subject_key(uploader) {
+        return Err(ApiError::BadRequest);
+    }
+    let limit = query
+        .limit
+        .unwrap_or(MAX_UPLOAD_LISTING)
+        .clamp(1, MAX_UPLOAD_LISTING);
+    Ok(HttpResponse::Ok().json(data.repo.list_uploads_by(uploader, limit).await?))
+}
+
+/// Uploads, attachments, quarantine and legal hold state of a blob; `NotFound` when the
+/// database knows nothing about it.
+async fn image_details(data: &AppState, hash: String) -> Result<ImageDetails, ApiError> {
+    if !is_valid_content_hash(&hash) {
+        return Err(ApiError::NotFound);
+    }
+    let uploads = data.repo.image_uploads(&hash).await?;
+    let attachments = data.repo.image_attachments(&hash).await?;
+    let quarantine = data.repo.get_quarantined_image(&hash).await?;
+    let legal_hold = data.repo.image_legal_hold(&hash).await?;
+    let archive = data.repo.archive_listing(&hash).await?;
+    if uploads.is_empty() && attachments.is_empty() && quarantine.is_none() && legal_hold.is_none()
+    {
+        return Err(ApiError::NotFound);
+    }
+    Ok(ImageDetails {
+        hash,
+        uploads,
+        attachments,
+        quarantine,
+        legal_hold,
+        archive,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/images/{hash}",
+    params(("hash" = String, Path, description = "SHA-256 of the blob")),
+    responses(
+        (status = 200, description = "Uploaders, attaching posts, quarantine and legal hold state", body = ImageDetails),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required"),
+        (status = 404, description = "Blob unknown")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_image_details(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    Ok(HttpResponse::Ok().json(image_details(&data, path.into_inner()).await?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/images/{hash}",
+    params(("hash" = String, Path, description = "SHA-256 of the blob")),
+    responses(
+        (status = 204, description = "Blob deleted from storage and detached; posts attaching it are soft-deleted"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Blob unknown"),
+        (status = 409, description = "Blob is under legal hold")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn take_down_image(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let details = image_details(&data, path.into_inner()).await?;
+    let hash = details.hash;
+    let hidden = data.repo.take_down_image(&hash).await?;
+    // Retrying after a storage failure is safe: the uploads still name the blob.
+    match data.image_store.delete(&hash).await {
+        Ok(()) | Err(ImageStoreError::NotFound) => {}
+        Err(e) => {
+            log::error!("failed to delete taken down image {hash}: {e}");
+            return Err(ApiError::Internal);
+        }
+    }
+    delete_thumbnail(&data, &hash).await;
+    metrics::increment_counter!("images_taken_down");
+    log::info!(
+        "{} took down image {hash}, hiding {hidden} posts",
+        auth.0.sub
+    );
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/trust/{subject}",
+    params(("subject" = String, Path, description = "Provider subject key")),
+    responses(
+        (status = 200, description = "The subject's history, score and resulting friction", body = crate::trust::TrustReport),
+        (status = 400, description = "Invalid subject key"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_subject_trust(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    let subject = path.into_inner();
+    if !is_valid_subject_key(&subject) {
+        return Err(ApiError::BadRequest);
+    }
+    let history = data.repo.subject_trust(&subject).await?;
+    let score = data.trust.score(history.as_ref(), chrono::Utc::now());
+    let friction = data.trust.friction(score);
+    Ok(HttpResponse::Ok().json(crate::trust::TrustReport {
+        subject,
+        history,
+        score,
+        rate_factor: friction.rate_factor,
+        skip_pow: friction.skip_pow,
+        hold: friction.hold,
+    }))
+}
+
+/// Longest reason accepted when placing or releasing a legal hold.
+const MAX_HOLD_REASON_CHARS: usize = 2000;
+/// Most legal holds listed at once.
+const MAX_LEGAL_HOLDS: i64 = 200;
+
+/// Trimmed reason, or `BadRequest` when empty or too long.
+fn hold_reason(reason: &str) -> Result<&str, ApiError> {
+    let reason = reason.trim();
+    if reason.is_empty() || reason.chars().count() > MAX_HOLD_REASON_CHARS {
+        return Err(ApiError::BadRequest);
+    }
+    Ok(reason)
+}
+
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+pub struct LegalHoldQuery {
+    /// Include released holds
+    #[serde(default)]
+    released: bool,
+    /// At most 200 (the default)
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/legal-holds",
+    params(LegalHoldQuery),
+    responses(
+        (status = 200, description = "Legal holds with who placed and released them and why, newest first", body = [LegalHold]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_legal_holds(
+    auth: Auth,
+    data: web::Data<AppState>,
+    query: web::Query<LegalHoldQuery>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let limit = query
+        .limit
+        .unwrap_or(MAX_LEGAL_HOLDS)
+        .clamp(1, MAX_LEGAL_HOLDS);
+    Ok(HttpResponse::Ok().json(data.repo.list_legal_holds(query.released, limit).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/legal-holds",
+    request_body = NewLegalHold,
+    responses(
+        (status = 201, description = "Target kept from hard deletion; a held blob is served to staff only", body = LegalHold),
+        (status = 400, description = "Not exactly one target, invalid hash, or missing reason"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Thread or reply not found"),
+        (status = 409, description = "Target already held")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn place_legal_hold(
+    auth: Auth,
+    data: web::Data<AppState>,
+    payload: web::Json<NewLegalHold>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let placed_by = role_subject_key(&auth.0.sub).ok_or(ApiError::Forbidden)?;
+    let mut new = payload.into_inner();
+    let targets = [
+        new.thread_id.is_some(),
+        new.reply_id.is_some(),
+        new.image_hash.is_some(),
+    ];
+    if targets.iter().filter(|set| **set).count() != 1
+        || new
+            .image_hash
+            .as_deref()
+            .is_some_and(|hash| !is_valid_content_hash(hash))
+    {
+        return Err(ApiError::BadRequest);
+    }
+    new.reason = hold_reason(&new.reason)?.to_string();
+    let hold = data.repo.place_legal_hold(new, &placed_by).await?;
+    log::info!(
+        "{placed_by} placed legal hold {} ({}): {}",
+        hold.id,
+        hold_target(&hold),
+        hold.reason
+    );
+    Ok(HttpResponse::Created().json(hold))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/legal-holds/{id}/release",
+    params(("id" = Id, Path, description = "Hold id")),
+    request_body = ReleaseLegalHold,
+    responses(
+        (status = 200, description = "Hold released; the target may be deleted again", body = LegalHold),
+        (status = 400, description = "Missing reason"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "No active hold with that id")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn release_legal_hold(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<ReleaseLegalHold>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let released_by = role_subject_key(&auth.0.sub).ok_or(ApiError::Forbidden)?;
+    let reason = hold_reason(&payload.reason)?;
+    let hold = data
+        .repo
+        .release_legal_hold(path.into_inner(), &released_by, reason)
+        .await?;
+    log::info!(
+        "{released_by} released legal hold {} ({}): {reason}",
+        hold.id,
+        hold_target(&hold)
+    );
+    Ok(HttpResponse::Ok().json(hold))
+}
+
+fn hold_target(hold: &LegalHold) -> String {
+    match (hold.thread_id, hold.reply_id, &hold.image_hash) {
+        (Some(id), _, _) => format!("thread {id}"),
+        (_, Some(id), _) => format!("reply {id}"),
+        (_, _, Some(hash)) => format!("image {hash}"),
+        _ => "nothing".to_string(),
+    }
+}
+
+async fn delete_unreferenced_images(data: &AppState, hashes: Vec<String>) -> Result<(), ApiError> {
+    let unique_hashes: std::collections::HashSet<String> = hashes.into_iter().collect();
+    for hash in unique_hashes {
+        if !data.repo.is_image_referenced(&hash).await?
+            && data.repo.image_legal_hold(&hash).await?.is_none()
+        {
+            if let Err(error) = data.image_store.delete(&hash).await {
+                log::error!("failed to delete unreferenced image {hash}: {error}");
+            }
+        }
+    }
+    Ok(())
+}
+// ------------------------------------------------------------------
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/replies",
+    request_body = NewReply,
+    responses(
+        (status = 201, description = "Reply created", body = Reply),
+        (status = 202, description = "Reply held for staff review; hidden until approved", body = Reply),
+        (status = 401, description = "Invalid bearer token or session"),
+        (status = 404, description = "Thread not found"),
+        (status = 403, description = "Forbidden, or banned: `code` is `banned` and `ban` gives the public reason and expiry"),
+        (status = 409, description = "Thread is closed or archived, or the reply duplicates a recent one"),
+        (status = 429, description = "Rate limited, or the board's reply cooldown in this thread has not passed")
+    ),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn create_reply(
+    auth: Result<Auth, actix_web::Error>,
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    payload: web::Json<NewReply>,
+) -> Result<HttpResponse, ApiError> {
+    let client_ip = extract_client_ip(&req);
+    let poster = http_poster(&req, auth.as_ref().ok(), &client_ip)?;
+    let reply = service::create_reply(&data, poster, payload.into_inner()).await?;
+    if reply.deleted_at.is_some() {
+        return Ok(HttpResponse::Accepted().json(reply));
+    }
+    Ok(HttpResponse::Created().json(reply))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/replies/{id}/reactions",
+    params(("id" = Id, Path, description = "Reply id")),
+    request_body = NewReaction,
+    responses(
+        (status = 200, description = "Reaction set, replacing the caller's earlier one; the reply's counts", body = [ReactionCount]),
+        (status = 400, description = "Emoji not in the board's reaction set"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Reply not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn react_to_reply(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<NewReaction>,
+) -> Result<HttpResponse, ApiError> {
+    let counts =
+        service::set_reaction(&data, &auth, path.into_inner(), Some(payload.emoji.trim())).await?;
+    Ok(HttpResponse::Ok().json(counts))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/replies/{id}/reactions",
+    params(("id" = Id, Path, description = "Reply id")),
+    responses(
+        (status = 200, description = "The caller's reaction removed; the reply's counts", body = [ReactionCount]),
+        (status = 401, description = "Sign-in required"),
+        (status = 404, description = "Reply not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn remove_reaction(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    let counts = service::set_reaction(&data, &auth, path.into_inner(), None).await?;
+    Ok(HttpResponse::Ok().json(counts))
+}
+
+/// Poster for a create request. Requests without credentials post
+/// anonymously; credentials that fail to verify are rejected rather than
+/// silently downgraded.
+pub(crate) fn http_poster<'a>(
+    req: &'a HttpRequest,
+    auth: Option<&'a Auth>,
+    client_ip: &'a str,
+) -> Result<Poster<'a>, ApiError> {
+    if auth.is_none()
+        && (req
+            .headers()
+            .contains_key(actix_web::http::header::AUTHORIZATION)
+            || req.cookie(crate::auth::AUTH_COOKIE_NAME).is_some())
+    {
+        return Err(ApiError::Unauthorized);
+    }
+    Ok(Poster {
+        auth,
+        client_ip,
+        pow: req
+            .headers()
+            .get(POW_HEADER)
+            .and_then(|value| value.to_str().ok()),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pow",
+    responses(
+        (status = 200, description = "Proof-of-work challenge for anonymous posting; send `{challenge}:{nonce}` in `X-Proof-Of-Work`", body = PowChallenge)
+    )
+)]
+pub async fn pow_challenge(data: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok()
+        .insert_header((actix_web::http::header::CACHE_CONTROL, "no-store"))
+        .json(data.pow.issue()?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/pow/clearance",
+    params(("X-Proof-Of-Work" = String, Header, description = "Solved `{challenge}:{nonce}`")),
+    responses(
+        (status = 200, description = "Clearance cookie set; access policy challenges pass until it expires", body = PowClearance),
+        (status = 400, description = "Missing, invalid, expired or already redeemed solution")
+    )
+)]
+pub async fn pow_clearance(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let token = req
+        .headers()
+        .get(POW_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| ApiError::Invalid("missing proof of work".into()))?;
+    let clearance = data.pow.clearance(token, &extract_client_ip(&req)).await?;
+    metrics::increment_counter!("pow_clearances_issued");
+    Ok(HttpResponse::Ok()
+        .insert_header((actix_web::http::header::CACHE_CONTROL, "no-store"))
+        .cookie(crate::auth::clearance_cookie(
+            &clearance.value,
+            data.pow.config().clearance_ttl,
+        ))
+        .json(clearance))
+}
+
+#[derive(Debug, serde::Serialize, utoipa::ToSchema)]
+pub struct FileUploadResponse {
+    pub hash: String,
+    pub mime: String,
+    pub size: usize,
+    pub duplicate: bool,   // true when upload was a duplicate (idempotent)
+    pub quarantined: bool, // true while staff review the blob; only they can fetch it
+    /// `/images/{hash}/thumbnail` when a downscaled copy of an image or a
+    /// first-page preview of a document was stored
+    pub thumbnail: Option<String>,
+    /// Files inside an inspected zip, tar or gzip upload
+    pub archive: Option<ArchiveListing>,
+    /// `/images/{hash}/preview` when the opening lines of a text file were kept
+    pub preview: Option<String>,
+    /// Visible posts already attaching a duplicate, newest first, so clients
+    /// can link to the existing discussion; empty for new uploads
+    pub references: Vec<ImageReference>,
+}
+
+/// Most existing posts listed for a duplicate upload.
+const MAX_DUPLICATE_REFERENCES: i64 = 20;
+
+pub(crate) const FILE_SIZE_LIMIT: usize = 25 * 1024 * 1024; // 25 MB
+
+pub(crate) fn detect_upload_mime(bytes: &[u8]) -> String {
+    if let Some(kind) = infer::get(bytes) {
+        return kind.mime_type().to_string();
+    }
+    if std::str::from_utf8(bytes).is_ok() && !bytes.contains(&0) {
+        return "text/plain".to_string();
+    }
+    "application/octet-stream".to_string()
+}
+
+fn is_inline_preview_mime(mime: &str) -> bool {
+    (mime.starts_with("image/") && mime != "image/svg+xml")
+        || mime.starts_with("video/")
+        || mime.starts_with("audio/")
+}
+
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+pub struct UploadQuery {
+    /// Let posts by anyone attach the file, not only the uploader's own
+    #[serde(default)]
+    shareable: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/images",
+    params(UploadQuery),
+    responses(
+        (status = 201, description = "File stored (new)", body = FileUploadResponse),
+        (status = 200, description = "File already existed (idempotent)", body = FileUploadResponse),
+        (status = 401, description = "Sign-in required"),
+        (status = 415, description = "Unsupported media type for the caller's role, or a name that contradicts the content under the reject policy"),
+        (status = 413, description = "Payload too large for the caller's role"),
+        (status = 403, description = "Role below UPLOAD_MIN_ROLE, or banned: `code` is `banned` and `ban` gives the public reason and expiry"),
+        (status = 429, description = "Upload quota used up; see Retry-After"),
+        (status = 503, description = "Uploads disabled for maintenance (`feature_disabled`)"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn upload_image(
+    auth: Auth,
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    query: web::Query<UploadQuery>,
+    mut payload: Multipart,
+) -> Result<HttpResponse, ApiError> {
+    use actix_web::http::StatusCode;
+    data.features.current().ensure_enabled(Feature::Uploads)?;
+    let subject_key = role_subject_key(&auth.0.sub).ok_or(ApiError::Forbidden)?;
+    ensure_uploader_not_banned(&data, &req, &auth, &subject_key).await?;
+    let uploads = data.uploads.current();
+    let Some(policy) = uploads.roles.for_roles(&auth.0.roles) else {
+        metrics::increment_counter!("upload_role_denied");
+        return Err(ApiError::Forbidden);
+    };
+    let size_limit = policy
+        .max_bytes
+        .map_or(FILE_SIZE_LIMIT, |max| max.min(FILE_SIZE_LIMIT));
+    // Refuse what is certainly too large before touching the database or the body.
+    let declared = declared_length(req.headers());
+    if request_exceeds(declared, size_limit as u64) {
+        metrics::increment_counter!("upload_rejected_early", "reason" => "request");
+        return Ok(HttpResponse::PayloadTooLarge().finish());
+    }
+    let quota = upload_allowance(&data, &req, &auth, &subject_key, &uploads).await?;
+    if let Some((left, retry_after)) = quota {
+        if request_exceeds(declared, left) {
+            metrics::increment_counter!("upload_quota_denied");
+            return Err(ApiError::RateLimited { retry_after });
+        }
+    }
+    let mut bytes: Vec<u8> = Vec::new();
+    while let Some(field) = payload.try_next().await.map_err(|e| {
+        log::error!("multipart error: {e}");
+        ApiError::Internal
+    })? {
+        if let Some(name) = field.content_disposition().get_name() {
+            if name != "file" {
+                continue;
+            }
+        } else {
+            continue;
+        }
+        if declared_length(field.headers()).is_some_and(|len| len > size_limit as u64) {
+            metrics::increment_counter!("upload_rejected_early", "reason" => "part");
+            return Ok(HttpResponse::PayloadTooLarge().finish());
+        }
+        let file_name = field
+            .content_disposition()
+            .get_filename()
+            .map(|name| name.chars().take(MAX_FILE_NAME_CHARS).collect::<String>());
+        let mut field_stream = field;
+        while let Some(chunk) = field_stream.try_next().await.map_err(|e| {
+            log::error!("stream read error: {e}");
+            ApiError::Internal
+        })? {
+            if bytes.len() + chunk.len() > size_limit {
+                return Ok(HttpResponse::build(StatusCode::PAYLOAD_TOO_LARGE).finish());
+            }
+            if let Some((left, retry_after)) = quota {
+                if (bytes.len() + chunk.len()) as u64 > left {
+                    metrics::increment_counter!("upload_quota_denied");
+                    return Err(ApiError::RateLimited { retry_after });
+                }
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        return store_upload(
+            &data,
+            &uploads,
+            policy,
+            &subject_key,
+            bytes,
+            file_name,
+            query.shareable,
+        )
+        .await;
+    }
+    Ok(HttpResponse::BadRequest().finish())
+}
+
+/// Refuse uploads from a banned subject, or from a banned IP unless the uploader is staff.
+async fn ensure_uploader_not_banned(
+    data: &AppState,
+    req: &HttpRequest,
+    auth: &Auth,
+    subject_key: &str,
+) -> Result<(), ApiError> {
+    ensure_subject_not_banned(data, subject_key).await?;
+    if !auth
+        .0
+        .roles
+        .iter()
+        .any(|r| matches!(r, Role::Moderator | Role::Admin))
+    {
+        ensure_ip_not_banned(data, &extract_client_ip(req)).await?;
+    }
+    Ok(())
+}
+
+/// Rate limit and quota checks before an upload's bytes are read. Returns
+/// the bytes the subject may still upload and when more frees up.
+async fn upload_allowance(
+    data: &AppState,
+    req: &HttpRequest,
+    auth: &Auth,
+    subject_key: &str,
+    uploads: &UploadConfig,
+) -> Result<Option<(u64, u64)>, ApiError> {
+    ensure_subject_can_post(data, auth, subject_key).await?;
+    if let Some(rl) = &data.rate_limiter {
+        let ip = extract_client_ip(req);
+        if !rl.allow_image(&ip) {
+            metrics::increment_counter!("rate_limit_denied", "action" => "image_upload");
+            return Err(ApiError::RateLimited {
+                retry_after: rl.config().image_window.as_secs(),
+            });
+        }
+        metrics::increment_counter!("rate_limit_allowed", "action" => "image_upload");
+    }
+    Ok(match uploads.quota.max_bytes {
+        Some(_) => {
+            let now = chrono::Utc::now();
+            let usage = data
+                .repo
+                .upload_usage(subject_key, uploads.quota.since(now))
+                .await?;
+            uploads
+                .quota
+                .remaining(&usage)
+                .map(|left| (left, uploads.quota.retry_after(&usage, now)))
+        }
+        None => None,
+    })
+}
+
+/// Type checks, storage and bookkeeping shared by multipart uploads and
+/// uploads by URL.
+async fn store_upload(
+    data: &AppState,
+    uploads: &UploadConfig,
+    policy: &RolePolicy,
+    subject_key: &str,
+    bytes: Vec<u8>,
+    file_name: Option<String>,
+    shareable: bool,
+) -> Result<HttpResponse, ApiError> {
+    // Infer MIME
+    let mime = detect_upload_mime(&bytes);
+    if !uploads.accepts_upload(policy, &mime) {
+        return Ok(HttpResponse::UnsupportedMediaType().finish());
+    }
+    // Stripped before hashing, so the stored blob is what its hash names.
+    let bytes = if uploads.strip_metadata {
+        strip_metadata(bytes, &mime)
+    } else {
+        bytes
+    };
+    let hash = format!("{:x}", Sha256::digest(&bytes));
+    let mut flag_reason = None;
+    let mut quarantine = false;
+    if uploads.mismatch != MismatchPolicy::Off {
+        if let Some(reason) = content_mismatch(file_name.as_deref(), &mime, &bytes) {
+            log::warn!("upload {hash} by {subject_key}: {reason}");
+            metrics::increment_counter!("upload_mismatch");
+            if let Err(refused) =
+                apply_content_policy(uploads.mismatch, reason, &mut flag_reason, &mut quarantine)
+            {
+                return Ok(refused);
+            }
+        }
+    }
+    let (bytes, archive) =
+        if uploads.archives.action != MismatchPolicy::Off && crate::archives::inspects(&mime) {
+            let (policy, archive_mime) = (uploads.archives.clone(), mime.clone());
+            // Inflating a gzip stream is CPU work; keep it off the async workers.
+            let (bytes, inspection) = web::block(move || {
+                let inspection = crate::archives::inspect(&bytes, &archive_mime, &policy);
+                (bytes, inspection)
+            })
+            .await
+            .map_err(|_| ApiError::Internal)?;
+            if let Some(reason) = inspection.danger {
+                log::warn!("upload {hash} by {subject_key}: {reason}");
+                metrics::increment_counter!("upload_archive_dangerous");
+                if let Err(refused) = apply_content_policy(
+                    uploads.archives.action,
+                    reason,
+                    &mut flag_reason,
+                    &mut quarantine,
+                ) {
+                    return Ok(refused);
+                }
+            }
+            (bytes, inspection.listing)
+        } else {
+            (bytes, None)
+        };
+    let excerpt = crate::excerpts::excerpt(&bytes, &mime, file_name.as_deref());
+    let mut quarantined = match data.repo.get_quarantined_image(&hash).await? {
+        Some(q) if q.destroyed_at.is_some() => {
+            metrics::increment_counter!("upload_rejected_destroyed");
+            return Err(ApiError::Withheld(
+                "this file was removed after review".to_string(),
+            ));
+        }
+        existing => existing.is_some(),
+    };
+    // Attempt to persist (idempotent semantics)
+    let (status_code, duplicate_flag) = match data.image_store.save(&hash, &mime, &bytes).await {
+        Ok(()) => (actix_web::http::StatusCode::CREATED, false),
+        Err(ImageStoreError::Duplicate) => (actix_web::http::StatusCode::OK, true),
+        Err(e) => {
+            log::error!("image_store save error: {e}");
+            return Err(ApiError::Internal);
+        }
+    };
+    data.repo
+        .record_upload(&NewUpload {
+            hash: hash.clone(),
+            uploaded_by: subject_key.to_string(),
+            mime: mime.clone(),
+            size_bytes: bytes.len() as i64,
+            file_name,
+            flag_reason: flag_reason.clone(),
+            shareable,
+        })
+        .await?;
+    if let Some(listing) = &archive {
+        data.repo.set_archive_listing(&hash, listing).await?;
+    }
+    if let Some(excerpt) = &excerpt {
+        data.repo.set_text_excerpt(&hash, excerpt).await?;
+    }
+    if quarantine && !quarantined {
+        let reason = flag_reason.unwrap_or_default();
+        data.repo.quarantine_image(&hash, &reason, "upload").await?;
+        metrics::increment_counter!("upload_quarantined");
+        quarantined = true;
+    }
+    let rendered = if quarantined {
+        None
+    } else if data.thumbnails.renders(&mime) {
+        image_thumbnail(data, &hash, &bytes).await?
+    } else {
+        document_thumbnail(data, &hash, &mime, &bytes).await?
+    };
+    let thumbnail = rendered.map(|_| format!("/images/{hash}/thumbnail"));
+    let references = if duplicate_flag {
+        data.repo
+            .image_references(&hash, MAX_DUPLICATE_REFERENCES)
+            .await?
+    } else {
+        Vec::new()
+    };
+    let preview = excerpt.map(|_| format!("/images/{hash}/preview"));
+    let resp = FileUploadResponse {
+        hash,
+        mime,
+        size: bytes.len(),
+        duplicate: duplicate_flag,
+        quarantined,
+        thumbnail,
+        archive,
+        preview,
+        references,
+    };
+    Ok(HttpResponse::build(status_code).json(resp))
+}
+
+/// Cut EXIF, XMP and text metadata out of an image upload. A file too
+/// malformed to walk is stored as it came.
+fn strip_metadata(bytes: Vec<u8>, mime: &str) -> Vec<u8> {
+    if !crate::metadata::strips(mime) {
+        return bytes;
+    }
+    match crate::metadata::strip(&bytes, mime) {
+        Ok(Some(stripped)) => {
+            metrics::increment_counter!("upload_metadata", "result" => "stripped");
+            stripped
+        }
+        Ok(None) => bytes,
+        Err(e) => {
+            metrics::increment_counter!("upload_metadata", "result" => "unparsed");
+            log::warn!("could not strip metadata from a {mime} upload: {e}");
+            bytes
+        }
+    }
+}
+
+/// Apply `policy` to an upload failing a content check: note the first
+/// reason for staff, mark it for quarantine, or refuse it with `415`.
+fn apply_content_policy(
+    policy: MismatchPolicy,
+    reason: String,
+    flag_reason: &mut Option<String>,
+    quarantine: &mut bool,
+) -> Result<(), HttpResponse> {
+    match policy {
+        MismatchPolicy::Reject => {
+            return Err(
+                HttpResponse::UnsupportedMediaType().json(crate::error::ApiErrorBody {
+                    error: reason,
+                    code: None,
+                }),
+            );
+        }
+        MismatchPolicy::Flag => {
+            flag_reason.get_or_insert(reason);
+        }
+        MismatchPolicy::Quarantine => {
+            *quarantine = true;
+            flag_reason.get_or_insert(reason);
+        }
+        MismatchPolicy::Warn | MismatchPolicy::Off => {}
+    }
+    Ok(())
+}
+
+/// Render and store the first-page preview of a document upload, unless one
+/// exists already. A failed render only costs the thumbnail.
+async fn document_thumbnail(
+    data: &AppState,
+    hash: &str,
+    mime: &str,
+    bytes: &[u8],
+) -> Result<Option<String>, ApiError> {
+    let Some(renderer) = data
+        .previews
+        .as_ref()
+        .filter(|_| crate::previews::renders(mime))
+    else {
+        return Ok(None);
+    };
+    if let Some(existing) = data.repo.image_thumbnail(hash).await? {
+        return Ok(Some(existing));
+    }
+    let image = match renderer.render(bytes, mime).await {
+        Ok(image) => image,
+        Err(e) => {
+            metrics::increment_counter!("document_previews", "result" => "failed");
+            log::warn!("rendering a preview of {hash} failed: {e}");
+            return Ok(None);
+        }
+    };
+    let Some(thumbnail_mime) = crate::previews::thumbnail_mime(&image) else {
+        metrics::increment_counter!("document_previews", "result" => "invalid");
+        log::warn!("the preview rendered for {hash} is not a PNG, JPEG or WebP image");
+        return Ok(None);
+    };
+    let thumbnail_hash = format!("{:x}", Sha256::digest(&image));
+    match data
+        .image_store
+        .save(&thumbnail_hash, thumbnail_mime, &image)
+        .await
+    {
+        Ok(()) | Err(ImageStoreError::Duplicate) => {}
+        Err(e) => {
+            log::error!("image_store save error for the preview of {hash}: {e}");
+            return Ok(None);
+        }
+    }
+    data.repo.set_image_thumbnail(hash, &thumbnail_hash).await?;
+    metrics::increment_counter!("document_previews", "result" => "rendered");
+    Ok(Some(thumbnail_hash))
+}
+
+/// Downscale and store a thumbnail of an image upload, unless one exists
+/// already. An image that fails to decode only costs the thumbnail.
+async fn image_thumbnail(
+    data: &AppState,
+    hash: &str,
+    bytes: &[u8],
+) -> Result<Option<String>, ApiError> {
+    if let Some(existing) = data.repo.image_thumbnail(hash).await? {
+        return Ok(Some(existing));
+    }
+    let source = bytes.to_vec();
+    let max_dim = data.thumbnails.max_dim;
+    let rendered = web::block(move || crate::thumbnails::render(&source, max_dim))
+        .await
+        .map_err(|e| {
+            log::error!("thumbnail task failed: {e}");
+            ApiError::Internal
+        })?;
+    let (image, thumbnail_mime) = match rendered {
+        Ok(rendered) => rendered,
+        Err(e) => {
+            metrics::increment_counter!("image_thumbnails", "result" => "failed");
+            log::warn!("making a thumbnail of {hash} failed: {e}");
+            return Ok(None);
+        }
+    };
+    let thumbnail_hash = format!("{:x}", Sha256::digest(&image));
+    match data
+        .image_store
+        .save(&thumbnail_hash, thumbnail_mime, &image)
+        .await
+    {
+        Ok(()) | Err(ImageStoreError::Duplicate) => {}
+        Err(e) => {
+            log::error!("image_store save error for the thumbnail of {hash}: {e}");
+            return Ok(None);
+        }
+    }
+    data.repo.set_image_thumbnail(hash, &thumbnail_hash).await?;
+    metrics::increment_counter!("image_thumbnails", "result" => "rendered");
+    Ok(Some(thumbnail_hash))
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct RemoteUpload {
+    /// `http` or `https` URL of the file to fetch
+    pub url: String,
+    /// Let posts by anyone attach the file, not only the uploader's own
+    #[serde(default)]
+    pub shareable: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/images/remote",
+    request_body = RemoteUpload,
+    responses(
+        (status = 201, description = "File fetched and stored (new)", body = FileUploadResponse),
+        (status = 200, description = "File already existed (idempotent)", body = FileUploadResponse),
+        (status = 400, description = "Invalid URL, a private or reserved address, or the fetch failed"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Role below UPLOAD_MIN_ROLE, or banned: `code` is `banned` and `ban` gives the public reason and expiry"),
+        (status = 404, description = "Uploads by URL are disabled"),
+        (status = 413, description = "File too large for the caller's role"),
+        (status = 415, description = "Unsupported media type for the caller's role, or a name that contradicts the content under the reject policy"),
+        (status = 429, description = "Upload quota used up; see Retry-After"),
+        (status = 503, description = "Uploads disabled for maintenance (`feature_disabled`)"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn upload_remote_image(
+    auth: Auth,
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    payload: web::Json<RemoteUpload>,
+) -> Result<HttpResponse, ApiError> {
+    data.features.current().ensure_enabled(Feature::Uploads)?;
+    let uploads = data.uploads.current();
+    if !uploads.remote.enabled {
+        return Err(ApiError::NotFound);
+    }
+    let subject_key = role_subject_key(&auth.0.sub).ok_or(ApiError::Forbidden)?;
+    ensure_uploader_not_banned(&data, &req, &auth, &subject_key).await?;
+    let Some(policy) = uploads.roles.for_roles(&auth.0.roles) else {
+        metrics::increment_counter!("upload_role_denied");
+        return Err(ApiError::Forbidden);
+    };
+    let size_limit = policy
+        .max_bytes
+        .map_or(FILE_SIZE_LIMIT, |max| max.min(FILE_SIZE_LIMIT));
+    let quota = upload_allowance(&data, &req, &auth, &subject_key, &uploads).await?;
+    let RemoteUpload { url, shareable } = payload.into_inner();
+    let fetched = match crate::remote_media::fetch(&url, &uploads.remote, size_limit).await {
+        Ok(fetched) => fetched,
+        Err(FetchError::TooLarge) => {
+            metrics::increment_counter!("upload_remote_failed", "reason" => "size");
+            return Ok(HttpResponse::PayloadTooLarge().finish());
+        }
+        Err(e) => {
+            let reason = match e {
+                FetchError::Blocked => "blocked",
+                FetchError::Invalid(_) => "invalid",
+                _ => "fetch",
+            };
+            metrics::increment_counter!("upload_remote_failed", "reason" => reason);
+            log::info!("upload of {url} by {subject_key} not fetched: {e}");
+            return Err(ApiError::Invalid(e.to_string()));
+        }
+    };
+    if let Some((left, retry_after)) = quota {
+        if fetched.bytes.len() as u64 > left {
+            metrics::increment_counter!("upload_quota_denied");
+            return Err(ApiError::RateLimited { retry_after });
+        }
+    }
+    metrics::increment_counter!("upload_remote_fetched");
+    store_upload(
+        &data,
+        &uploads,
+        policy,
+        &subject_key,
+        fetched.bytes,
+        fetched.file_name,
+        shareable,
+    )
+    .await
+}
+
+// Serve stored image / video by hash
+pub async fn get_image(
+    req: HttpRequest,
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let hash = path.into_inner();
+    if !is_valid_content_hash(&hash) {
+        return Err(ApiError::NotFound);
+    }
+    let public = image_access(&data, auth.as_ref(), &hash).await?;
+    let etag = format!("\"{hash}\"");
+    if is_not_modified(&req, &etag) {
+        return Ok(HttpResponse::NotModified().finish());
+    }
+    match data.image_store.load(&hash).await {
+        Ok((bytes, mime)) => Ok(image_response(&hash, etag, &mime, public).body(bytes)),
+        Err(ImageStoreError::NotFound) => Err(ApiError::NotFound),
+        Err(e) => {
+            log::error!("image_store load error: {e}");
+            Err(ApiError::Internal)
+        }
+    }
+}
+
+// Same headers as `get_image` from the store's metadata, without the bytes
+pub async fn head_image(
+    req: HttpRequest,
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let hash = path.into_inner();
+    if !is_valid_content_hash(&hash) {
+        return Err(ApiError::NotFound);
+    }
+    let public = image_access(&data, auth.as_ref(), &hash).await?;
+    let etag = format!("\"{hash}\"");
+    if is_not_modified(&req, &etag) {
+        return Ok(HttpResponse::NotModified().finish());
+    }
+    match data.image_store.head(&hash).await {
+        // An empty stream keeps the declared length; HEAD never sends a body.
+        Ok(meta) => Ok(image_response(&hash, etag, &meta.mime, public)
+            .no_chunking(meta.size)
+            .streaming(futures_util::stream::empty::<
+                Result<web::Bytes, std::io::Error>,
+            >())),
+        Err(ImageStoreError::NotFound) => Err(ApiError::NotFound),
+        Err(e) => {
+            log::error!("image_store head error: {e}");
+            Err(ApiError::Internal)
+        }
+    }
+}
+
+// Serve the thumbnail of an image or the rendered preview of a document under
+// the source's own access rules
+pub async fn get_image_thumbnail(
+    req: HttpRequest,
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let hash = path.into_inner();
+    if !is_valid_content_hash(&hash) {
+        return Err(ApiError::NotFound);
+    }
+    let public = image_access(&data, auth.as_ref(), &hash).await?;
+    let thumbnail = data
+        .repo
+        .image_thumbnail(&hash)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let etag = format!("\"{thumbnail}\"");
+    if is_not_modified(&req, &etag) {
+        return Ok(HttpResponse::NotModified().finish());
+    }
+    match data.image_store.load(&thumbnail).await {
+        Ok((bytes, mime)) => Ok(image_response(&thumbnail, etag, &mime, public).body(bytes)),
+        Err(ImageStoreError::NotFound) => Err(ApiError::NotFound),
+        Err(e) => {
+            log::error!("image_store load error: {e}");
+            Err(ApiError::Internal)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/images/{hash}/listing",
+    params(("hash" = String, Path, description = "SHA-256 of the archive")),
+    responses(
+        (status = 200, description = "Files inside the archive", body = ArchiveListing),
+        (status = 404, description = "Not an inspected archive"),
+        (status = 451, description = "Withheld pending review, removed, or under legal hold")
+    )
+)]
+pub async fn get_archive_listing(
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let hash = path.into_inner();
+    if !is_valid_content_hash(&hash) {
+        return Err(ApiError::NotFound);
+    }
+    let public = image_access(&data, auth.as_ref(), &hash).await?;
+    let listing = data
+        .repo
+        .archive_listing(&hash)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", metadata_cache(public)))
+        .json(listing))
+}
+
+/// `Cache-Control` for JSON describing a blob; it never changes once stored.
+fn metadata_cache(public: bool) -> &'static str {
+    if public {
+        "public, max-age=31536000, immutable"
+    } else {
+        "private, no-store"
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/images/{hash}/preview",
+    params(("hash" = String, Path, description = "SHA-256 of the text file")),
+    responses(
+        (status = 200, description = "Opening lines and detected language", body = TextExcerpt),
+        (status = 404, description = "No excerpt kept for this blob"),
+        (status = 451, description = "Withheld pending review, removed, or under legal hold")
+    )
+)]
+pub async fn get_text_preview(
+    auth: Option<Auth>,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let hash = path.into_inner();
+    if !is_valid_content_hash(&hash) {
+        return Err(ApiError::NotFound);
+    }
+    let public = image_access(&data, auth.as_ref(), &hash).await?;
+    let excerpt = data
+        .repo
+        .text_excerpt(&hash)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", metadata_cache(public)))
+        .json(excerpt))
+}
+
+fn is_not_modified(req: &HttpRequest, etag: &str) -> bool {
+    req.headers()
+        .get(actix_web::http::header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        == Some(etag)
+}
+
+/// Whether a blob may be cached publicly. Quarantined blobs and blobs under
+/// legal hold are withheld from everyone but staff, who get them uncached.
+async fn image_access(data: &AppState, auth: Option<&Auth>, hash: &str) -> Result<bool, ApiError> {
+    let quarantined = data.repo.get_quarantined_image(hash).await?;
+    if quarantined
+        .as_ref()
+        .is_some_and(|q| q.destroyed_at.is_some())
+    {
+        return Err(ApiError::Withheld(
+            "this file was removed after review".to_string(),
+        ));
+    }
+    let withheld = if quarantined.is_some() {
+        "this file is awaiting moderator review"
+    } else if data.repo.image_legal_hold(hash).await?.is_some() {
+        "this file is withheld under legal hold"
+    } else {
+        return Ok(true);
+    };
+    let staff = auth.is_some_and(|a| {
+        a.0.roles
+            .iter()
+            .any(|r| matches!(r, Role::Moderator | Role::Admin))
+    });
+    if !staff {
+        return Err(ApiError::Withheld(withheld.to_string()));
+    }
+    Ok(false)
+}
+
+fn image_response(
+    hash: &str,
+    etag: String,
+    mime: &str,
+    public: bool,
+) -> actix_web::HttpResponseBuilder {
+    let cache = if public {
+        "public, max-age=31536000, immutable"
+    } else {
+        "private, no-store"
+    };
+    let mut response = HttpResponse::Ok();
+    response
+        .insert_header(("Content-Type", mime))
+        .insert_header(("ETag", etag))
+        .insert_header(("Cache-Control", cache));
+    if !is_inline_preview_mime(mime) {
+        response.insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{hash}\""),
+        ));
+    }
+    response
+}
+
+// ---------------------------------------------------------------------
+/// Upper bound for a board's page limit.
+const MAX_THREADS_PER_BOARD: i32 = 10_000;
+/// Longest per-thread reply cooldown a board may set.
+const MAX_REPLY_COOLDOWN_SECS: i32 = 3600;
+/// Most reaction emoji a board may offer.
+const MAX_BOARD_REACTIONS: usize = 16;
+/// Longest reaction, in characters; room for multi-codepoint emoji.
+const MAX_REACTION_CHARS: usize = 8;
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/boards/{id}",
+    request_body = UpdateBoard,
+    params(("id" = Id, Path, description = "Board id")),
+    responses(
+        (status = 200, description = "Board updated", body = Board),
+        (status = 400, description = "Invalid slug, title, max_threads, reply_cooldown_secs, reactions, tag_vocabulary or allowed_mime"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Board not found"),
+        (status = 409, description = "Conflict")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_board(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<UpdateBoard>,
+) -> Result<HttpResponse, ApiError> {
+    // ── admin-only guard ────────────────────────────────────────────
+    if !auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
+        return Err(ApiError::Forbidden); // 403
+    }
+    // ────────────────────────────────────────────────────────────────
+    let mut update = payload.into_inner();
+    update.slug = update.slug.map(|slug| slug.trim().to_string());
+    update.title = update.title.map(|title| title.trim().to_string());
+    if update.slug.as_ref().is_some_and(|slug| {
+        validate_board_fields(slug, update.title.as_deref().unwrap_or("x")).is_err()
+    }) || update
+        .title
+        .as_ref()
+        .is_some_and(|title| title.is_empty() || title.chars().count() > 100)
+    {
+        return Err(ApiError::BadRequest);
+    }
+    if update
+        .max_threads
+        .is_some_and(|max| !(0..=MAX_THREADS_PER_BOARD).contains(&max))
+    {
+        return Err(ApiError::Invalid(format!(
+            "max_threads must be 0 (no limit) to {MAX_THREADS_PER_BOARD}"
+        )));
+    }
+    if update
+        .reply_cooldown_secs
+        .is_some_and(|secs| !(0..=MAX_REPLY_COOLDOWN_SECS).contains(&secs))
+    {
+        return Err(ApiError::Invalid(format!(
+            "reply_cooldown_secs must be 0 (off) to {MAX_REPLY_COOLDOWN_SECS}"
+        )));
+    }
+    if let Some(vocabulary) = update.tag_vocabulary.as_mut() {
+        *vocabulary = crate::tags::vocabulary(vocabulary).map_err(ApiError::Invalid)?;
+    }
+    if let Some(allowed) = update.allowed_mime.as_mut() {
+        *allowed = crate::uploads::normalize_mime_entries(allowed).map_err(ApiError::Invalid)?;
+    }
+    if let Some(reactions) = update.reactions.as_mut() {
+        for emoji in reactions.iter_mut() {
+            *emoji = emoji.trim().to_string();
+        }
+        let mut seen = std::collections::HashSet::new();
+        if reactions.len() > MAX_BOARD_REACTIONS
+            || reactions.iter().any(|emoji| {
+                emoji.is_empty()
+                    || emoji.chars().count() > MAX_REACTION_CHARS
+                    || emoji.chars().any(char::is_whitespace)
+                    || !seen.insert(emoji.clone())
+            })
+        {
+            return Err(ApiError::Invalid(format!(
+                "reactions takes up to {MAX_BOARD_REACTIONS} distinct emoji of at most {MAX_REACTION_CHARS} characters"
+            )));
+        }
+    }
+    let board = data.repo.update_board(path.into_inner(), update).await?;
+    Ok(HttpResponse::Ok().json(board))
+}
+// ---------------------------------------------------------------------
+
+// Discord OAuth endpoints
+pub async fn discord_login() -> Result<HttpResponse, ApiError> {
+    // Graceful degradation: return 503 JSON if Discord OAuth isn't configured
+    let client_id = match std::env::var("DISCORD_CLIENT_ID") {
+        Ok(v) => v,
+        Err(_) => {
+            return Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                "error": "discord_oauth_not_configured",
+                "message": "Set DISCORD_CLIENT_ID / DISCORD_CLIENT_SECRET to enable Discord login"
+            })));
+        }
+    };
+    // Prefer explicit env; otherwise synthesize from FRONTEND_URL replacing scheme/host
+    let redirect_uri = std::env::var("DISCORD_REDIRECT_URI")
+        .ok()
+        .or_else(|| {
+            std::env::var("FRONTEND_URL").ok().map(|f| {
+                // Normalize trailing slash
+                let base = f.trim_end_matches('/');
+                format!("{}/api/v1/auth/discord/callback", base)
+            })
+        })
+        .unwrap_or_else(|| "http://localhost:8080/api/v1/auth/discord/callback".to_string());
+
+    let transaction = create_oauth_transaction().map_err(|_| ApiError::Internal)?;
+    let auth_url = format!(
+        "https://discord.com/api/oauth2/authorize?client_id={}&redirect_uri={}&response_type=code&scope=identify&state={}&code_challenge={}&code_challenge_method=S256",
+        client_id,
+        urlencoding::encode(&redirect_uri),
+        transaction.state,
+        transaction.code_challenge,
+    );
+
+    Ok(HttpResponse::Found()
+        .insert_header(("Location", auth_url))
+        .cookie(transaction.cookie)
+        .finish())
+}
+
+#[derive(serde::Deserialize)]
+pub struct DiscordCallback {
+    code: String,
+    state: String,
+}
+
+#[derive(serde::Deserialize)]
+struct DiscordTokenResponse {
+    access_token: String,
+    #[allow(dead_code)]
+    token_type: String, // Keep for completeness even if unused
+}
+
+#[derive(serde::Deserialize)]
+struct DiscordUser {
+    id: String,
+    username: String,
+    #[allow(dead_code)]
+    discriminator: String, // Keep for completeness even if unused
+}
+
+fn discord_admission_role(assigned_role: Option<Role>, is_bootstrap_admin: bool) -> Option<Role> {
+    if is_bootstrap_admin {
+        Some(Role::Admin)
+    } else {
+        assigned_role
+    }
+}
+
+pub async fn discord_callback(
+    req: HttpRequest,
+    query: web::Query<DiscordCallback>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let client_id = match std::env::var("DISCORD_CLIENT_ID") {
+        Ok(v) => v,
+        Err(_) => {
+            return Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                "error": "discord_oauth_not_configured",
+                "stage": "client_id"
+            })));
+        }
+    };
+    let client_secret = match std::env::var("DISCORD_CLIENT_SECRET") {
+        Ok(v) => v,
+        Err(_) => {
+            return Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                "error": "discord_oauth_not_configured",
+                "stage": "client_secret"
+            })));
+        }
+    };
+    let redirect_uri = std::env::var("DISCORD_REDIRECT_URI")
+        .ok()
+        .or_else(|| {
+            std::env::var("FRONTEND_URL").ok().map(|f| {
+                let base = f.trim_end_matches('/');
+                format!("{}/api/v1/auth/discord/callback", base)
+            })
+        })
+        .unwrap_or_else(|| "http://localhost:8080/api/v1/auth/discord/callback".to_string());
+
+    let transaction_cookie = req
+        .cookie(OAUTH_TRANSACTION_COOKIE_NAME)
+        .ok_or(ApiError::BadRequest)?;
+    let pkce_verifier = consume_oauth_transaction(transaction_cookie.value(), &query.state)
+        .map_err(|_| ApiError::BadRequest)?;
+    // The transaction cookie is stateless; remember its state until the cookie
+    // expires so a captured callback cannot be replayed.
+    let fresh = data
+        .challenges
+        .insert_new(
+            "oauth_state",
+            &query.state,
+            "",
+            StdDuration::from_secs(crate::auth::OAUTH_TRANSACTION_TTL_MINUTES as u64 * 60),
+        )
+        .await
+        .map_err(|e| {
+            log::error!("recording used OAuth state failed: {e}");
+            ApiError::Internal
+        })?;
+    if !fresh {
+        return Err(ApiError::BadRequest);
+    }
+
+    // Exchange code for token
+    let client = reqwest::Client::builder()
+        .connect_timeout(std::time::Duration::from_secs(3))
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .map_err(|_| ApiError::Internal)?;
     let token_http_response = client
         .post("https://discord.com/api/oauth2/token")
         .form(&[
@@ -1208,587 +4194,2613 @@ pub async fn discord_callback(
         .send()
         .await
         .map_err(|_| ApiError::Internal)?;
-    if !token_http_response.status().is_success() {
-        log::warn!(
-            "Discord token exchange failed with status {}",
-            token_http_response.status()
-        );
+    if !token_http_response.status().is_success() {
+        log::warn!(
+            "Discord token exchange failed with status {}",
+            token_http_response.status()
+        );
+        return Err(ApiError::BadRequest);
+    }
+    let token_response = token_http_response
+        .json::<DiscordTokenResponse>()
+        .await
+        .map_err(|_| ApiError::Internal)?;
+
+    // Get user info
+    let user_http_response = client
+        .get("https://discord.com/api/users/@me")
+        .header(
+            reqwest::header::AUTHORIZATION,
+            format!("Bearer {}", token_response.access_token),
+        )
+        .send()
+        .await
+        .map_err(|_| ApiError::Internal)?;
+    if !user_http_response.status().is_success() {
+        log::warn!(
+            "Discord user lookup failed with status {}",
+            user_http_response.status()
+        );
+        return Err(ApiError::BadRequest);
+    }
+    let user = user_http_response
+        .json::<DiscordUser>()
+        .await
+        .map_err(|_| ApiError::Internal)?;
+
+    // Only explicitly assigned Discord subjects may post. Bootstrap admins are
+    // the recovery path when no role assignment is available.
+    let bootstrap_admins = std::env::var("BOOTSTRAP_ADMIN_DISCORD_IDS").unwrap_or_default();
+    let is_bootstrap_admin = bootstrap_admins
+        .split(',')
+        .filter(|s| !s.trim().is_empty())
+        .any(|s| s.trim() == user.id);
+
+    let subject_key = format!("discord:{}", user.id);
+    let assigned_role = data.repo.get_subject_role(&subject_key).await;
+    let frontend_url =
+        std::env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:5173".to_string());
+    let Some(role) = discord_admission_role(assigned_role, is_bootstrap_admin) else {
+        return Ok(HttpResponse::Found()
+            .insert_header((
+                "Location",
+                format!(
+                    "{}/login?error=discord_not_allowlisted",
+                    frontend_url.trim_end_matches('/')
+                ),
+            ))
+            .cookie(clear_oauth_transaction_cookie())
+            .finish());
+    };
+
+    // Generate JWT
+    let jwt = crate::auth::create_jwt(&user.id, &user.username, vec![role])
+        .map_err(|_| ApiError::Internal)?;
+
+    // Redirect to frontend with token
+    Ok(HttpResponse::Found()
+        .insert_header((
+            "Location",
+            format!("{}/", frontend_url.trim_end_matches('/')),
+        ))
+        .cookie(session_cookie(&jwt))
+        .cookie(clear_oauth_transaction_cookie())
+        .finish())
+}
+
+/// Bitcoin and email subjects sign themselves up; Discord subjects need an explicit role.
+fn is_self_serve_subject(jwt_subject: &str) -> bool {
+    jwt_subject.starts_with("btc:")
+        || jwt_subject.starts_with("eth:")
+        || jwt_subject.starts_with("nostr:")
+        || jwt_subject.starts_with("email:")
+}
+
+pub(crate) fn role_subject_key(jwt_subject: &str) -> Option<String> {
+    if is_self_serve_subject(jwt_subject) {
+        Some(jwt_subject.to_string())
+    } else {
+        jwt_subject
+            .split_once(':')
+            .map(|(discord_id, _)| format!("discord:{discord_id}"))
+    }
+}
+
+fn is_bootstrap_discord_id(discord_id: &str) -> bool {
+    std::env::var("BOOTSTRAP_ADMIN_DISCORD_IDS")
+        .unwrap_or_default()
+        .split(',')
+        .filter(|value| !value.trim().is_empty())
+        .any(|value| value.trim() == discord_id)
+}
+
+pub async fn refresh_token(
+    auth: Auth,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    if auth.0.impersonated_by.is_some() {
+        return Err(ApiError::Forbidden);
+    }
+    let subject_key = role_subject_key(&auth.0.sub).ok_or(ApiError::Forbidden)?;
+    ensure_subject_not_banned(data.get_ref(), &subject_key).await?;
+    let assigned_role = data.repo.get_subject_role(&subject_key).await;
+    let role = if is_self_serve_subject(&auth.0.sub) {
+        assigned_role.unwrap_or(Role::User)
+    } else {
+        let (discord_id, _) = auth.0.sub.split_once(':').ok_or(ApiError::Forbidden)?;
+        discord_admission_role(assigned_role, is_bootstrap_discord_id(discord_id))
+            .ok_or(ApiError::Forbidden)?
+    };
+    let jwt = match auth.0.sub.strip_prefix("btc:") {
+        Some(address) => {
+            let (roles, verified_at) = reverify_bitcoin_session(&auth.0, address, role).await?;
+            crate::auth::reissue_bitcoin_jwt(&auth.0.sub, roles, verified_at)
+        }
+        None => crate::auth::create_jwt(&auth.0.sub, &auth.0.sub, vec![role]),
+    }
+    .map_err(|_| ApiError::Internal)?;
+
+    Ok(HttpResponse::Ok()
+        .cookie(session_cookie(&jwt))
+        .json(serde_json::json!({ "token": jwt })))
+}
+
+/// Roles and balance verification time for a refreshed Bitcoin session.
+/// Once `BTC_REVERIFY_SECS` (default one hour) have passed since the balance
+/// was last verified it is looked up again; an address below the threshold
+/// keeps its session but loses the `user` role, and with it posting, until
+/// a later refresh finds the funds back. Staff roles assigned by an admin do
+/// not depend on the balance. A failed lookup keeps the current roles and
+/// retries on the next refresh.
+async fn reverify_bitcoin_session(
+    claims: &crate::auth::Claims,
+    address: &str,
+    role: Role,
+) -> Result<(Vec<Role>, usize), ApiError> {
+    let now = chrono::Utc::now().timestamp() as usize;
+    let verified_at = claims.balance_verified_at.unwrap_or(0);
+    let reverify_after = std::env::var("BTC_REVERIFY_SECS")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(3600);
+    if role != Role::User || now.saturating_sub(verified_at) < reverify_after {
+        return Ok((vec![role], verified_at));
+    }
+    if debug_test_flag("BTC_AUTH_TEST_SKIP_BALANCE") {
+        return Ok((vec![role], now));
+    }
+    let network = configured_btc_network()?;
+    match fetch_btc_balance_sats(address, network).await {
+        Ok(sats) if sats >= btc_min_balance_sats() => {
+            metrics::increment_counter!("btc_reverifications", "result" => "ok");
+            Ok((vec![role], now))
+        }
+        Ok(sats) => {
+            metrics::increment_counter!("btc_reverifications", "result" => "insufficient");
+            log::info!("bitcoin session {address} downgraded: balance {sats} sats below threshold");
+            Ok((Vec::new(), verified_at))
+        }
+        Err(e) => {
+            metrics::increment_counter!("btc_reverifications", "result" => "error");
+            log::warn!("bitcoin balance re-check for {address} failed: {e}");
+            Ok((claims.roles.clone(), verified_at))
+        }
+    }
+}
+
+pub async fn logout() -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::NoContent()
+        .cookie(clear_session_cookie())
+        .finish())
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct SetSubjectRoleRequest {
+    subject: String,
+    role: String,
+    /// Demote a moderator or admin back to user at this time; omit for a lasting role
+    #[serde(default)]
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Kept in the role history and mailed to the subject
+    #[serde(default)]
+    reason: Option<String>,
+    /// Required to demote yourself or put an expiry on your own admin role
+    #[serde(default)]
+    confirm: bool,
+}
+
+fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::Admin => "admin",
+        Role::Moderator => "moderator",
+        Role::User => "user",
+    }
+}
+
+fn role_change_reason(reason: Option<&str>) -> Result<Option<String>, ApiError> {
+    let reason = reason.map(str::trim).filter(|r| !r.is_empty());
+    if reason.is_some_and(|r| r.chars().count() > crate::roles::MAX_ROLE_REASON_CHARS) {
+        return Err(ApiError::BadRequest);
+    }
+    Ok(reason.map(Into::into))
+}
+
+/// Admins taking away their own admin role must confirm it.
+fn ensure_self_demotion_confirmed(
+    auth: &Auth,
+    subject: &str,
+    confirm: bool,
+) -> Result<(), ApiError> {
+    let is_self = role_subject_key(&auth.0.sub).is_some_and(|own| own == subject);
+    if is_self && !confirm {
+        return Err(ApiError::ConfirmationRequired);
+    }
+    Ok(())
+}
+
+/// Record a role change made by the caller and mail it to the subject.
+async fn record_role_change(
+    data: &AppState,
+    auth: &Auth,
+    subject: &str,
+    from_role: Option<Role>,
+    to_role: Option<Role>,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    reason: Option<String>,
+) -> Result<(), ApiError> {
+    let change = data
+        .repo
+        .record_role_change(NewRoleChange {
+            subject: subject.into(),
+            changed_by: Some(auth.0.sub.clone()),
+            from_role: from_role.map(|r| role_name(r).into()),
+            to_role: to_role.map(|r| role_name(r).into()),
+            expires_at,
+            reason,
+        })
+        .await?;
+    if let Some(mailer) = &data.mailer {
+        crate::roles::notify(&*data.repo, &**mailer, &change).await;
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/roles",
+    request_body = SetSubjectRoleRequest,
+    responses(
+        (status = 200, description = "Role updated"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Forbidden - Admin only"),
+        (status = 400, description = "Invalid role/subject, an expiry that is past or on the user role, or an overlong reason"),
+        (status = 409, description = "`last_admin`: would demote the only admin; `confirmation_required`: demoting yourself without `confirm`")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_subject_role(
+    auth: Auth,
+    data: web::Data<AppState>,
+    payload: web::Json<SetSubjectRoleRequest>,
+) -> Result<HttpResponse, ApiError> {
+    if !auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
+        return Err(ApiError::Forbidden);
+    }
+    let subj = payload.subject.trim();
+    if !is_valid_subject_key(subj) {
+        return Err(ApiError::BadRequest);
+    }
+    let role = match payload.role.to_lowercase().as_str() {
+        "user" => Role::User,
+        "moderator" => Role::Moderator,
+        "admin" => Role::Admin,
+        _ => return Err(ApiError::BadRequest),
+    };
+    if let Some(expires_at) = payload.expires_at {
+        if matches!(role, Role::User) || expires_at <= chrono::Utc::now() {
+            return Err(ApiError::BadRequest);
+        }
+    }
+    let reason = role_change_reason(payload.reason.as_deref())?;
+    if !matches!(role, Role::Admin) || payload.expires_at.is_some() {
+        ensure_self_demotion_confirmed(&auth, subj, payload.confirm)?;
+    }
+    let previous = data.repo.get_subject_role(subj).await;
+    match payload.expires_at {
+        Some(expires_at) => {
+            data.repo
+                .set_subject_role_until(subj, role.clone(), expires_at)
+                .await?
+        }
+        None => data.repo.set_subject_role(subj, role.clone()).await?,
+    }
+    record_role_change(
+        &data,
+        &auth,
+        subj,
+        previous,
+        Some(role),
+        payload.expires_at,
+        reason,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Role updated",
+        "subject": subj,
+        "role": payload.role,
+        "expires_at": payload.expires_at,
+    })))
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct RoleAssignment {
+    subject: String,
+    role: String,
+    /// When a temporary role lapses back to user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/roles",
+    responses(
+        (status = 200, description = "List role assignments", body = [RoleAssignment]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Forbidden")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_roles(auth: Auth, data: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    if !auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
+        return Err(ApiError::Forbidden);
+    }
+    let rows = data.repo.list_roles().await?;
+    let resp: Vec<RoleAssignment> = rows
+        .into_iter()
+        .map(|(s, r, expires_at)| RoleAssignment {
+            subject: s,
+            role: role_name(r).into(),
+            expires_at,
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(resp))
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct RemoveRoleRequest {
+    /// Kept in the role history and mailed to the subject
+    #[serde(default)]
+    reason: Option<String>,
+    /// Required to remove your own role
+    #[serde(default)]
+    confirm: bool,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/roles/{subject}",
+    params(("subject"=String, Path, description="Subject key to delete")),
+    request_body(content = Option<RemoveRoleRequest>, description = "Optional removal reason"),
+    responses(
+        (status = 400, description = "Overlong reason"),
+        (status = 409, description = "`last_admin`: the subject is the only admin; `confirmation_required`: removing your own role without `confirm`"),
+        (status = 204, description = "Deleted"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_role(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    payload: Option<web::Json<RemoveRoleRequest>>,
+) -> Result<HttpResponse, ApiError> {
+    if !auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
+        return Err(ApiError::Forbidden);
+    }
+    let subj = path.into_inner();
+    let reason = role_change_reason(payload.as_ref().and_then(|p| p.reason.as_deref()))?;
+    ensure_self_demotion_confirmed(&auth, &subj, payload.as_ref().is_some_and(|p| p.confirm))?;
+    let previous = data.repo.get_subject_role(&subj).await;
+    data.repo.delete_role(&subj).await?;
+    record_role_change(&data, &auth, &subj, previous, None, None, reason).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+pub struct RoleHistoryQuery {
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/roles/{subject}/history",
+    params(
+        ("subject"=String, Path, description="Subject key"),
+        RoleHistoryQuery
+    ),
+    responses(
+        (status = 200, description = "Role changes, newest first", body = [RoleChange]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Forbidden - Admin only")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn role_history(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<RoleHistoryQuery>,
+) -> Result<HttpResponse, ApiError> {
+    if !auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
+        return Err(ApiError::Forbidden);
+    }
+    let limit = query
+        .limit
+        .unwrap_or(100)
+        .clamp(1, crate::roles::MAX_ROLE_HISTORY);
+    let changes = data.repo.role_history(&path.into_inner(), limit).await?;
+    Ok(HttpResponse::Ok().json(changes))
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct ImpersonateRequest {
+    /// Subject key to act as, e.g. `discord:1234`; not an admin
+    subject: String,
+    /// Why, e.g. a support ticket; kept in the impersonation log
+    reason: String,
+    /// Token lifetime, up to `IMPERSONATION_MAX_MINUTES` (default 15)
+    #[serde(default)]
+    minutes: Option<i64>,
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct ImpersonationToken {
+    /// Bearer token acting as `act_as`; not set as a cookie
+    token: String,
+    act_as: String,
+    role: String,
+    expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/impersonate",
+    request_body = ImpersonateRequest,
+    responses(
+        (status = 200, description = "Impersonation token", body = ImpersonationToken),
+        (status = 400, description = "Invalid subject, missing or overlong reason, or lifetime out of range"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Forbidden - Admin only, and admins cannot be impersonated"),
+        (status = 404, description = "Subject has no role assignment and cannot sign in")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn impersonate(
+    auth: Auth,
+    data: web::Data<AppState>,
+    payload: web::Json<ImpersonateRequest>,
+) -> Result<HttpResponse, ApiError> {
+    if !auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) || auth.0.impersonated_by.is_some() {
+        return Err(ApiError::Forbidden);
+    }
+    let subject = payload.subject.trim();
+    let jwt_subject = is_valid_subject_key(subject)
+        .then(|| crate::impersonation::jwt_subject(subject))
+        .flatten()
+        .ok_or(ApiError::BadRequest)?;
+    let reason = payload.reason.trim();
+    if reason.is_empty() || reason.chars().count() > crate::impersonation::MAX_REASON_CHARS {
+        return Err(ApiError::BadRequest);
+    }
+    let minutes = payload
+        .minutes
+        .unwrap_or(crate::impersonation::DEFAULT_MINUTES);
+    if !(1..=crate::impersonation::max_minutes()).contains(&minutes) {
+        return Err(ApiError::BadRequest);
+    }
+    let role = match data.repo.get_subject_role(subject).await {
+        Some(Role::Admin) => return Err(ApiError::Forbidden),
+        Some(role) => role,
+        None if is_self_serve_subject(&jwt_subject) => Role::User,
+        None => return Err(ApiError::NotFound),
+    };
+    let expires_at = chrono::Utc::now() + chrono::Duration::minutes(minutes);
+    let token = crate::auth::create_impersonation_jwt(
+        &auth.0.sub,
+        &jwt_subject,
+        vec![role.clone()],
+        expires_at,
+    )
+    .map_err(|_| ApiError::Internal)?;
+    data.repo
+        .record_impersonation(NewImpersonationEntry::Token {
+            actor: auth.0.sub.clone(),
+            subject: subject.to_string(),
+            reason: reason.to_string(),
+            expires_at,
+        })
+        .await?;
+    log::warn!(
+        "{} is impersonating {subject} until {expires_at}: {reason}",
+        auth.0.sub
+    );
+    Ok(HttpResponse::Ok().json(ImpersonationToken {
+        token,
+        act_as: subject.to_string(),
+        role: role_name(role).into(),
+        expires_at,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/service-accounts",
+    request_body = NewServiceAccount,
+    responses(
+        (status = 201, description = "Account created; the token is shown only now", body = CreatedServiceAccount),
+        (status = 400, description = "Invalid name, or no scopes"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Forbidden - Admin only"),
+        (status = 409, description = "Name taken")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_service_account(
+    auth: Auth,
+    data: web::Data<AppState>,
+    payload: web::Json<NewServiceAccount>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let mut new = payload.into_inner();
+    new.scopes.sort_by_key(|s| s.as_str());
+    new.scopes.dedup();
+    if !crate::service_accounts::valid_name(&new.name) || new.scopes.is_empty() {
+        return Err(ApiError::BadRequest);
+    }
+    let token = crate::service_accounts::generate_token();
+    let account = data
+        .repo
+        .create_service_account(
+            &new,
+            &crate::service_accounts::hash_token(&token),
+            &auth.0.sub,
+        )
+        .await?;
+    log::info!(
+        "service account {} created by {} with scopes {:?}",
+        account.name,
+        auth.0.sub,
+        account.scopes
+    );
+    Ok(HttpResponse::Created().json(CreatedServiceAccount { account, token }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/service-accounts",
+    responses(
+        (status = 200, description = "Service accounts by name, revoked ones included", body = [ServiceAccount]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Forbidden - Admin only")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_service_accounts(
+    auth: Auth,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    Ok(HttpResponse::Ok().json(data.repo.list_service_accounts().await?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/service-accounts/{id}",
+    params(("id" = Id, Path, description = "Service account id")),
+    responses(
+        (status = 204, description = "Revoked; its token stops working at once"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Forbidden - Admin only"),
+        (status = 404, description = "No such active account")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn revoke_service_account(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let id = path.into_inner();
+    data.repo.revoke_service_account(id).await?;
+    log::info!("service account {id} revoked by {}", auth.0.sub);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/service/announcements",
+    request_body = NewAnnouncement,
+    responses(
+        (status = 201, description = "Thread posted as the service account", body = Thread),
+        (status = 400, description = "Invalid subject or body"),
+        (status = 401, description = "Service account token required"),
+        (status = 403, description = "The account lacks the `announce` scope"),
+        (status = 404, description = "Board not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn post_announcement(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    payload: web::Json<NewAnnouncement>,
+) -> Result<HttpResponse, ApiError> {
+    let account = crate::service_accounts::require(&req, &data, ServiceScope::Announce).await?;
+    let new = NewThread {
+        board_id: payload.board_id,
+        subject: payload.subject.trim().to_string(),
+        body: payload.body.trim().to_string(),
+        image_hash: None,
+        mime: None,
+        author_name: None,
+        tripcode_password: None,
+        delete_password: None,
+        tags: Vec::new(),
+    };
+    validate_thread_payload(&new)?;
+    let board = data.repo.get_board(new.board_id).await?;
+    if board.deleted_at.is_some() {
+        return Err(ApiError::NotFound);
+    }
+    let subject = format!("service:{}", account.name);
+    let created_by = serde_json::json!({
+        "v": 1,
+        "subject": subject,
+        "provider": "service",
+    });
+    let identity = PublicIdentity {
+        author_name: Some(account.name.clone()),
+        tripcode: None,
+    };
+    let thread = data.repo.create_thread(new, created_by, identity).await?;
+    log::info!(
+        "announcement {} posted on /{}/ by {subject}",
+        thread.id,
+        board.slug
+    );
+    Ok(HttpResponse::Created().json(thread))
+}
+
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+pub struct ImpersonationLogQuery {
+    /// Only entries acting as this subject key
+    subject: Option<String>,
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/impersonations",
+    params(ImpersonationLogQuery),
+    responses(
+        (status = 200, description = "Impersonation tokens and impersonated requests, newest first", body = [ImpersonationEntry]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Forbidden - Admin only")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_impersonations(
+    auth: Auth,
+    data: web::Data<AppState>,
+    query: web::Query<ImpersonationLogQuery>,
+) -> Result<HttpResponse, ApiError> {
+    if !auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
+        return Err(ApiError::Forbidden);
+    }
+    let limit = query
+        .limit
+        .unwrap_or(100)
+        .clamp(1, crate::impersonation::MAX_LOG_ENTRIES);
+    let entries = data
+        .repo
+        .list_impersonations(query.subject.as_deref(), limit)
+        .await?;
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+#[derive(serde::Serialize, utoipa::ToSchema)]
+struct MeResponse {
+    id: String,
+    username: String,
+    discord_id: String,
+    role: String,
+    /// Admin behind an impersonation token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    impersonated_by: Option<String>,
+}
+
+// Return authenticated user info
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/me",
+    responses(
+        (status = 200, description = "Current user info or null when anonymous", body = Option<MeResponse>)
+    ),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn auth_me(auth: Option<Auth>) -> Result<HttpResponse, ApiError> {
+    let Some(auth) = auth else {
+        return Ok(HttpResponse::Ok().json(Option::<MeResponse>::None));
+    };
+    // choose highest privilege (Admin > Moderator > User); claims already vetted
+    let role = if auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
+        "admin"
+    } else if auth.0.roles.iter().any(|r| matches!(r, Role::Moderator)) {
+        "moderator"
+    } else {
+        "user"
+    };
+    let sub = &auth.0.sub;
+    let (id, username, discord_id) = if let Some(rest) = sub
+        .strip_prefix("btc:")
+        .or_else(|| sub.strip_prefix("eth:"))
+        .or_else(|| sub.strip_prefix("nostr:"))
+        .or_else(|| sub.strip_prefix("email:"))
+    {
+        (sub.clone(), rest.to_string(), String::new())
+    } else if let Some((id, u)) = sub.split_once(':') {
+        (sub.clone(), u.to_string(), id.to_string())
+    } else {
+        (sub.clone(), sub.clone(), sub.clone())
+    };
+    let me = MeResponse {
+        id,
+        username,
+        discord_id,
+        role: role.to_string(),
+        impersonated_by: auth.0.impersonated_by.clone(),
+    };
+    Ok(HttpResponse::Ok().json(me))
+}
+
+// Very lightweight health handler (no DB ping yet; fast fail if process unhealthy)
+pub async fn health() -> Result<HttpResponse, ApiError> {
+    Ok(HttpResponse::Ok().finish())
+}
+
+// (Removed bandcamp_oembed_proxy)
+
+// ---------------- Bitcoin Proof-of-Value Auth --------------------
+use rand::RngCore;
+use std::time::Duration as StdDuration;
+
+const BTC_CHALLENGE_TTL_SECS: u64 = 300; // 5 minutes
+const BTC_MIN_BALANCE_SATS: u64 = 1_000_000; // 0.01 BTC
+
+/// `BTC_NETWORK`: `mainnet` (the default), `testnet`, `signet` or `regtest`.
+/// Addresses must belong to it and balances are looked up on it, so staging
+/// and tests can sign in with coins that are worth nothing.
+pub fn btc_network() -> Result<bitcoin::Network, String> {
+    use bitcoin::Network;
+    match std::env::var("BTC_NETWORK")
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+        .as_str()
+    {
+        "" | "mainnet" | "bitcoin" | "main" => Ok(Network::Bitcoin),
+        "testnet" | "test" => Ok(Network::Testnet),
+        "signet" => Ok(Network::Signet),
+        "regtest" => Ok(Network::Regtest),
+        other => Err(format!("unsupported BTC_NETWORK '{other}'")),
+    }
+}
+
+fn configured_btc_network() -> Result<bitcoin::Network, ApiError> {
+    btc_network().map_err(|e| {
+        log::error!("{e}");
+        ApiError::Internal
+    })
+}
+
+/// Env override for the sign-in balance threshold.
+fn btc_min_balance_sats() -> u64 {
+    std::env::var("BTC_MIN_BALANCE_SATS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(BTC_MIN_BALANCE_SATS)
+}
+
+/// `BTC_MIN_CONFIRMATIONS`: how deep a UTXO must be buried to count towards
+/// the balance; default 1 (confirmed), 0 also counts mempool outputs.
+fn btc_min_confirmations() -> u32 {
+    std::env::var("BTC_MIN_CONFIRMATIONS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(1)
+}
+
+/// Confirmations of an esplora UTXO given the chain tip, when it is mined.
+fn utxo_confirmations(utxo: &serde_json::Value, tip_height: Option<u64>) -> u32 {
+    let status = utxo.get("status");
+    let confirmed = status
+        .and_then(|status| status.get("confirmed"))
+        .and_then(serde_json::Value::as_bool)
+        .unwrap_or(false);
+    if !confirmed {
+        return 0;
+    }
+    let height = status
+        .and_then(|status| status.get("block_height"))
+        .and_then(serde_json::Value::as_u64);
+    match (height, tip_height) {
+        (Some(height), Some(tip)) if tip >= height => {
+            (tip - height + 1).min(u32::MAX as u64) as u32
+        }
+        // Without heights all we know is that it is mined.
+        _ => 1,
+    }
+}
+
+/// Esplora API used for balances unless `BTC_BLOCKSTREAM_API_BASE` is set;
+/// regtest expects a local electrs/esplora.
+fn default_esplora_base(network: bitcoin::Network) -> &'static str {
+    use bitcoin::Network;
+    match network {
+        Network::Testnet => "https://mempool.space/testnet/api",
+        Network::Signet => "https://mempool.space/signet/api",
+        Network::Regtest => "http://127.0.0.1:3002",
+        _ => "https://blockstream.info/api",
+    }
+}
+
+/// BlockCypher chain path for the fallback balance lookup, where it has one.
+fn blockcypher_chain(network: bitcoin::Network) -> Option<&'static str> {
+    use bitcoin::Network;
+    match network {
+        Network::Bitcoin => Some("btc/main"),
+        Network::Testnet => Some("btc/test3"),
+        _ => None,
+    }
+}
+
+/// Challenges are kept twice as long as they are valid so a late answer gets
+/// `410 Gone` rather than looking like one that was never issued.
+async fn store_btc_challenge(
+    store: &dyn ChallengeStore,
+    address: &str,
+    challenge: &str,
+) -> Result<(), ApiError> {
+    let issued = chrono::Utc::now().timestamp();
+    store
+        .put(
+            "btc",
+            address,
+            &format!("{issued}|{challenge}"),
+            StdDuration::from_secs(2 * BTC_CHALLENGE_TTL_SECS),
+        )
+        .await
+        .map_err(|e| {
+            log::error!("storing bitcoin challenge failed: {e}");
+            ApiError::Internal
+        })
+}
+
+// Internal helper (used in tests) to insert a deterministic challenge for an address.
+// Not exposed via HTTP, safe for production build though only called from tests.
+// Writes to the shared in-memory store, which `AppState::new` uses by default.
+pub async fn btc_test_insert_challenge(address: &str, challenge: &str) {
+    store_btc_challenge(
+        crate::challenges::MemoryChallengeStore::shared().as_ref(),
+        address,
+        challenge,
+    )
+    .await
+    .expect("in-memory challenge store");
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct BitcoinChallengeRequest {
+    pub address: String,
+}
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct BitcoinChallengeResponse {
+    pub challenge: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/bitcoin/challenge",
+    request_body = BitcoinChallengeRequest,
+    responses(
+        (status = 200, description = "Challenge issued", body = BitcoinChallengeResponse),
+        (status = 400, description = "Bad request"),
+        (status = 503, description = "Bitcoin sign-in disabled for maintenance (`feature_disabled`)")
+    )
+)]
+pub async fn bitcoin_challenge(
+    payload: web::Json<BitcoinChallengeRequest>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    data.features
+        .current()
+        .ensure_enabled(Feature::BitcoinAuth)?;
+    let address = payload.address.trim();
+    if address.is_empty() {
+        return Err(ApiError::BadRequest);
+    }
+    // Basic length sanity
+    if address.len() < 26 || address.len() > 100 {
+        return Err(ApiError::BadRequest);
+    }
+    // Reject syntactically invalid addresses, and ones for another network, early
+    let network = configured_btc_network()?;
+    if !Address::from_str(address).is_ok_and(|addr| addr.is_valid_for_network(network)) {
+        return Err(ApiError::BadRequest);
+    }
+    // ───────────────────────────────────────────────────────────────────
+    // Generate 32 random bytes hex for nonce
+    let mut nonce_bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let nonce = hex::encode(nonce_bytes);
+    let challenge = if network == bitcoin::Network::Bitcoin {
+        format!("Prove you own Bitcoin address {address} (nonce {nonce})")
+    } else {
+        // Name the network so a test signature is never mistaken for a mainnet one.
+        format!("Prove you own Bitcoin {network} address {address} (nonce {nonce})")
+    };
+    store_btc_challenge(data.challenges.as_ref(), address, &challenge).await?;
+    Ok(HttpResponse::Ok().json(BitcoinChallengeResponse { challenge }))
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct BitcoinVerifyRequest {
+    pub address: String,
+    pub signature: String,
+}
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct BitcoinVerifyResponse {
+    pub token: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/bitcoin/verify",
+    request_body = BitcoinVerifyRequest,
+    responses(
+        (status = 200, description = "JWT token", body = BitcoinVerifyResponse),
+        (status = 400, description = "Bad request"),
+        (status = 403, description = "Forbidden / insufficient balance"),
+        (status = 410, description = "Challenge expired"),
+        (status = 503, description = "Bitcoin sign-in disabled for maintenance (`feature_disabled`)")
+    )
+)]
+pub async fn bitcoin_verify(
+    payload: web::Json<BitcoinVerifyRequest>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    use actix_web::http::StatusCode;
+    data.features
+        .current()
+        .ensure_enabled(Feature::BitcoinAuth)?;
+    // Retrieve *and* remove challenge (single-use)
+    let stored = data
+        .challenges
+        .take("btc", &payload.address)
+        .await
+        .map_err(|e| {
+            log::error!("loading bitcoin challenge failed: {e}");
+            ApiError::Internal
+        })?
+        .ok_or(ApiError::BadRequest)?;
+    let (issued, challenge) = stored
+        .split_once('|')
+        .and_then(|(issued, challenge)| Some((issued.parse::<i64>().ok()?, challenge)))
+        .ok_or(ApiError::Internal)?;
+    if chrono::Utc::now().timestamp() - issued > BTC_CHALLENGE_TTL_SECS as i64 {
+        return Ok(HttpResponse::build(StatusCode::GONE).finish());
+    }
+    let network = configured_btc_network()?;
+    // Test helpers (never set in production): granular skips instead of monolithic BTC_AUTH_TEST_ACCEPT
+    let skip_balance = debug_test_flag("BTC_AUTH_TEST_SKIP_BALANCE");
+    // Skip signature verification (used when we only want to test balance aggregation with a mock UTXO response)
+    let test_skip_sig = debug_test_flag("BTC_AUTH_TEST_SKIP_SIG");
+    let min_balance = btc_min_balance_sats();
+    // ───────────────────────────────────────────────────────────────────
+    // Signature verification (unless explicitly skipped)
+    if !test_skip_sig {
+        if let Err(e) =
+            verify_bitcoin_message(&payload.address, challenge, &payload.signature, network).await
+        {
+            log::warn!("bitcoin signature verify failed: {e}");
+            return Err(ApiError::BadRequest);
+        }
+    }
+    // Balance check (unless explicitly skipped)
+    if !skip_balance {
+        match fetch_btc_balance_sats(&payload.address, network).await {
+            Ok(sats) if sats >= min_balance => {}
+            Ok(_) => return Err(ApiError::InsufficientFunds),
+            Err(_) => return Err(ApiError::Internal),
+        }
+    }
+    // Issue JWT (User role)
+    let jwt = crate::auth::create_bitcoin_jwt(&payload.address, vec![Role::User])
+        .map_err(|_| ApiError::Internal)?;
+    Ok(HttpResponse::Ok()
+        .cookie(session_cookie(&jwt))
+        .json(BitcoinVerifyResponse { token: jwt }))
+}
+
+async fn verify_bitcoin_message(
+    address: &str,
+    message: &str,
+    signature_b64: &str,
+    network: bitcoin::Network,
+) -> anyhow::Result<()> {
+    use base64::Engine;
+    use bitcoin::address::Payload;
+    use bitcoin::Address;
+    use secp256k1::{
+        ecdsa::RecoverableSignature, ecdsa::RecoveryId, Message as SecpMessage, Secp256k1,
+    };
+    use sha2::{Digest, Sha256};
+    use std::str::FromStr;
+
+    // 1. Decode base64 signature (65 bytes: header + 64) ---------------------
+    let raw = base64::engine::general_purpose::STANDARD
+        .decode(signature_b64.as_bytes())
+        .map_err(|e| anyhow::anyhow!(e))?;
+    if raw.len() != 65 {
+        anyhow::bail!("unexpected sig length (want 65)");
+    }
+
+    let header = raw[0]; // 27..34 allowed by Core (27 + recid + (4 if compressed))
+    if !(27..=34).contains(&header) {
+        anyhow::bail!("invalid header byte");
+    }
+    let rec_id = RecoveryId::from_i32(((header - 27) & 0x03) as i32)?;
+    let is_compressed = ((header - 27) & 0x04) != 0;
+    let sig = RecoverableSignature::from_compact(&raw[1..65], rec_id)?;
+
+    // 2. Build Core-compatible preimage: varint(len(magic)) + magic + varint(len(msg)) + msg
+    //    Magic string per Bitcoin Core: "Bitcoin Signed Message:\n"
+    const MAGIC: &str = "Bitcoin Signed Message:\n"; // includes trailing newline
+    fn ser_varint(n: usize) -> Vec<u8> {
+        if n < 253 {
+            vec![n as u8]
+        } else {
+            vec![
+                253,
+                (n & 0xff) as u8,
+                ((n >> 8) & 0xff) as u8,
+                ((n >> 16) & 0xff) as u8,
+            ]
+        }
+    }
+    let mut data = Vec::with_capacity(1 + MAGIC.len() + 9 + message.len());
+    // magic length (always <253)
+    data.push(MAGIC.len() as u8);
+    data.extend_from_slice(MAGIC.as_bytes());
+    // message length + message
+    data.extend_from_slice(&ser_varint(message.len()));
+    data.extend_from_slice(message.as_bytes());
+
+    // Double SHA256
+    let hash = {
+        let d1 = Sha256::digest(&data);
+        Sha256::digest(d1)
+    };
+
+    // 3. Recover public key
+    let secp = Secp256k1::new();
+    let msg = SecpMessage::from_digest_slice(&hash)?;
+    let pubkey = secp.recover_ecdsa(&msg, &sig)?;
+    let pk_compressed = pubkey.serialize();
+    let pk_uncompressed = pubkey.serialize_uncompressed();
+    let pk_bytes: &[u8] = if is_compressed {
+        &pk_compressed
+    } else {
+        &pk_uncompressed
+    };
+
+    // 4. Compare derived address
+    let addr = Address::from_str(address)?;
+    match &addr.payload {
+        Payload::PubkeyHash(pkh) => {
+            use bitcoin::hashes::{hash160, Hash};
+            let derived = hash160::Hash::hash(pk_bytes);
+            let derived_bytes: &[u8] = derived.as_ref();
+            let pkh_bytes: &[u8] = pkh.as_ref();
+            if derived_bytes != pkh_bytes {
+                anyhow::bail!("address mismatch");
+            }
+        }
+        Payload::WitnessProgram(wp) => {
+            // Support only native segwit v0 P2WPKH (program = HASH160(compressed pubkey))
+            if wp.version().to_num() != 0 || wp.program().len() != 20 {
+                anyhow::bail!("unsupported witness program for signing");
+            }
+            if !is_compressed {
+                anyhow::bail!("segwit signature must use compressed key");
+            }
+            use bitcoin::hashes::{hash160, Hash};
+            let derived = hash160::Hash::hash(&pk_compressed);
+            let derived_bytes: &[u8] = derived.as_ref();
+            if wp.program().as_bytes() != derived_bytes {
+                anyhow::bail!("address mismatch");
+            }
+        }
+        _ => anyhow::bail!("unsupported address type for signing"),
+    }
+
+    // 5. Address must belong to the configured network (BTC_NETWORK) ------
+    if !addr.is_valid_for_network(network) {
+        anyhow::bail!("wrong network");
+    }
+    Ok(())
+}
+
+async fn fetch_btc_balance_sats(address: &str, network: bitcoin::Network) -> anyhow::Result<u64> {
+    // Test override (avoids network) ----------------------------------------
+    if let Some(sats) = debug_balance_override() {
+        return Ok(sats);
+    }
+    // Blockstream API (no key) fallback to BlockCypher
+    let client = reqwest::Client::builder()
+        .connect_timeout(std::time::Duration::from_secs(3))
+        .timeout(std::time::Duration::from_secs(10))
+        .build()?;
+    // Allow overriding Blockstream base for tests (defaults to the network's public endpoint)
+    let blockstream_base = std::env::var("BTC_BLOCKSTREAM_API_BASE")
+        .unwrap_or_else(|_| default_esplora_base(network).to_string());
+    let base = blockstream_base.trim_end_matches('/');
+    let min_confirmations = btc_min_confirmations();
+    // Depth beyond "mined" needs the chain tip to count confirmations.
+    let tip_height = if min_confirmations > 1 {
+        let tip = client
+            .get(format!("{base}/blocks/tip/height"))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        Some(tip.trim().parse::<u64>()?)
+    } else {
+        None
+    };
+    // Try Blockstream first
+    if let Ok(r) = client
+        .get(format!("{base}/address/{address}/utxo"))
+        .send()
+        .await
+    {
+        if r.status().is_success() {
+            let utxos: serde_json::Value = r.json().await?;
+            let mut total: u64 = 0;
+            if let Some(arr) = utxos.as_array() {
+                for utxo in arr {
+                    if utxo_confirmations(utxo, tip_height) >= min_confirmations {
+                        if let Some(value) = utxo.get("value").and_then(serde_json::Value::as_u64) {
+                            total = total.saturating_add(value);
+                        }
+                    }
+                }
+            }
+            return Ok(total);
+        }
+    }
+    // Fallback BlockCypher; it reports confirmed and unconfirmed totals only,
+    // so deeper confirmation requirements cannot be checked there.
+    let Some(chain) = blockcypher_chain(network).filter(|_| min_confirmations <= 1) else {
+        anyhow::bail!("balance api fail");
+    };
+    #[derive(serde::Deserialize)]
+    struct BalanceResp {
+        balance: u64,
+        final_balance: u64,
+    }
+    let resp = client
+        .get(format!(
+            "https://api.blockcypher.com/v1/{chain}/addrs/{}/balance",
+            address
+        ))
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        anyhow::bail!("balance api fail");
+    }
+    let b: BalanceResp = resp.json().await?;
+    Ok(if min_confirmations == 0 {
+        b.final_balance
+    } else {
+        b.balance
+    })
+}
+// -----------------------------------------------------------------
+
+// ---------------- Email magic-link auth --------------------------
+fn email_login_ttl() -> StdDuration {
+    StdDuration::from_secs(
+        std::env::var("EMAIL_LOGIN_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(900)
+            .max(60),
+    )
+}
+
+/// Lower-cased address if it looks deliverable; the SMTP layer does the strict parse.
+fn normalize_email(email: &str) -> Option<String> {
+    let email = email.trim().to_lowercase();
+    let (local, domain) = email.split_once('@')?;
+    let valid = email.len() <= 254
+        && !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !domain.contains('@')
+        && !email.chars().any(|c| c.is_whitespace() || c.is_control());
+    valid.then_some(email)
+}
+
+/// `email:<hex>` subject for a normalized address; the address itself is never stored.
+pub(crate) fn email_subject(email: &str) -> Result<String, ApiError> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(tripcode_secret()?.as_bytes())
+        .map_err(|_| ApiError::Internal)?;
+    mac.update(b"rib-email-v1\0");
+    mac.update(email.as_bytes());
+    Ok(format!(
+        "email:{}",
+        hex::encode(&mac.finalize().into_bytes()[..16])
+    ))
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct EmailLoginStartRequest {
+    pub email: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/email/start",
+    request_body = EmailLoginStartRequest,
+    responses(
+        (status = 202, description = "Sign-in link sent"),
+        (status = 400, description = "Invalid email address"),
+        (status = 429, description = "Too many links requested for this address"),
+        (status = 503, description = "Email login not configured")
+    )
+)]
+pub async fn email_login_start(
+    data: web::Data<AppState>,
+    payload: web::Json<EmailLoginStartRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let Some(mailer) = &data.mailer else {
+        return Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "error": "email_login_not_configured",
+            "message": "Set SMTP_URL to enable email login"
+        })));
+    };
+    let email = normalize_email(&payload.email).ok_or(ApiError::BadRequest)?;
+    if let Some(rl) = &data.rate_limiter {
+        if !rl.allow_email_login(&email_subject(&email)?) {
+            metrics::increment_counter!("rate_limit_denied", "action" => "email_login");
+            return Err(ApiError::RateLimited {
+                retry_after: rl.config().email_login_window.as_secs(),
+            });
+        }
+    }
+    let ttl = email_login_ttl();
+    let token =
+        crate::auth::create_email_login_token(&email, ttl).map_err(|_| ApiError::Internal)?;
+    let link = format!(
+        "{}/api/v1/auth/email/callback?token={}",
+        crate::digest::public_base_url(),
+        urlencoding::encode(&token)
+    );
+    let email = crate::mailer::Email {
+        to: email,
+        subject: "Your sign-in link".into(),
+        body: format!(
+            "Open this link to sign in. It works once and expires in {} minutes.\n\n{link}\n\nIf you did not ask for it, ignore this email.\n",
+            ttl.as_secs() / 60
+        ),
+        unsubscribe_url: None,
+    };
+    if let Err(e) = mailer.send(&email).await {
+        log::warn!("sending sign-in email via {} failed: {e}", mailer.name());
+        return Err(ApiError::Unavailable);
+    }
+    Ok(HttpResponse::Accepted().finish())
+}
+
+#[derive(serde::Deserialize)]
+pub struct EmailLoginCallback {
+    token: String,
+}
+
+pub async fn email_login_callback(
+    query: web::Query<EmailLoginCallback>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let frontend_url =
+        std::env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:5173".to_string());
+    let frontend_url = frontend_url.trim_end_matches('/');
+    let invalid = || {
+        HttpResponse::Found()
+            .insert_header((
+                "Location",
+                format!("{frontend_url}/login?error=email_link_invalid"),
+            ))
+            .finish()
+    };
+    let Ok(login) = crate::auth::decode_email_login_token(&query.token) else {
+        return Ok(invalid());
+    };
+    // Remember the link until it expires so it can only be exchanged once.
+    let remaining = (login.exp as i64 - chrono::Utc::now().timestamp()).max(1) as u64;
+    let fresh = data
+        .challenges
+        .insert_new(
+            "email_login",
+            &login.nonce,
+            "",
+            StdDuration::from_secs(remaining),
+        )
+        .await
+        .map_err(|e| {
+            log::error!("recording used email login link failed: {e}");
+            ApiError::Internal
+        })?;
+    if !fresh {
+        return Ok(invalid());
+    }
+    let subject_key = email_subject(&login.email)?;
+    ensure_subject_not_banned(data.get_ref(), &subject_key).await?;
+    let role = data
+        .repo
+        .get_subject_role(&subject_key)
+        .await
+        .unwrap_or(Role::User);
+    let jwt = crate::auth::create_jwt(&subject_key, &subject_key, vec![role])
+        .map_err(|_| ApiError::Internal)?;
+    Ok(HttpResponse::Found()
+        .insert_header(("Location", format!("{frontend_url}/")))
+        .cookie(session_cookie(&jwt))
+        .finish())
+}
+// -----------------------------------------------------------------
+
+// ---------------- Thread subscriptions and digests ---------------
+fn caller_subject(auth: &Auth) -> Result<String, ApiError> {
+    role_subject_key(&auth.0.sub).ok_or(ApiError::Forbidden)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/threads/{id}/subscription",
+    params(("id" = Id, Path, description = "Thread id")),
+    responses(
+        (status = 204, description = "Watching the thread"),
+        (status = 401, description = "Sign-in required"),
+        (status = 404, description = "Thread not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn subscribe_thread(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    let thread = service::get_thread(data.get_ref(), path.into_inner(), false, Some(&auth)).await?;
+    data.repo.subscribe_thread(&subject, thread.id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/threads/{id}/subscription",
+    params(("id" = Id, Path, description = "Thread id")),
+    responses(
+        (status = 204, description = "No longer watching the thread"),
+        (status = 401, description = "Sign-in required"),
+        (status = 404, description = "Not watching this thread")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn unsubscribe_thread(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    data.repo
+        .unsubscribe_thread(&subject, path.into_inner())
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/subscriptions",
+    responses(
+        (status = 200, description = "Watched threads, newest first", body = [ThreadSubscription]),
+        (status = 401, description = "Sign-in required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_my_subscriptions(
+    auth: Auth,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    Ok(HttpResponse::Ok().json(data.repo.list_thread_subscriptions(&subject).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/notifications",
+    responses(
+        (status = 200, description = "Digest settings", body = NotificationSettings),
+        (status = 401, description = "Sign-in required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_my_notifications(
+    auth: Auth,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    let settings = data
+        .repo
+        .get_notification_settings(&subject)
+        .await?
+        .unwrap_or_default();
+    Ok(HttpResponse::Ok().json(settings))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/me/notifications",
+    request_body = UpdateNotificationSettings,
+    responses(
+        (status = 200, description = "Settings saved; a new address gets a confirmation email", body = NotificationSettings),
+        (status = 400, description = "Invalid address, or digests enabled without one"),
+        (status = 401, description = "Sign-in required"),
+        (status = 503, description = "Email is not configured")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn put_my_notifications(
+    auth: Auth,
+    data: web::Data<AppState>,
+    payload: web::Json<UpdateNotificationSettings>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    let mut update = payload.into_inner();
+    update.email = match update.email.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(email) => Some(normalize_email(email).ok_or(ApiError::BadRequest)?),
+    };
+    if update.digest != DigestFrequency::Off && update.email.is_none() {
+        return Err(ApiError::BadRequest);
+    }
+    if update.email.is_some() && data.mailer.is_none() {
+        return Err(ApiError::Unavailable);
+    }
+    let settings = data
+        .repo
+        .put_notification_settings(&subject, update)
+        .await?;
+    if let (Some(mailer), Some(email), false) =
+        (&data.mailer, &settings.email, settings.email_verified)
+    {
+        let link = crate::digest::confirm_url(&crate::digest::public_base_url(), &subject, email)
+            .map_err(|_| ApiError::Internal)?;
+        let message = crate::mailer::Email {
+            to: email.clone(),
+            subject: "Confirm your notification address".into(),
+            body: format!(
+                "Open this link to receive reply digests at this address:\n\n{link}\n\nIf you did not ask for it, ignore this email.\n"
+            ),
+            unsubscribe_url: None,
+        };
+        if let Err(e) = mailer.send(&message).await {
+            log::warn!(
+                "sending confirmation email via {} failed: {e}",
+                mailer.name()
+            );
+            return Err(ApiError::Unavailable);
+        }
+    }
+    Ok(HttpResponse::Ok().json(settings))
+}
+
+#[derive(serde::Deserialize)]
+pub struct NotificationLink {
+    token: String,
+}
+
+pub async fn confirm_notification_email(
+    query: web::Query<NotificationLink>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = crate::auth::decode_notification_token(
+        &query.token,
+        crate::auth::NotificationAction::ConfirmEmail,
+    )
+    .map_err(|_| ApiError::BadRequest)?;
+    if !data
+        .repo
+        .verify_notification_email(&claims.sub, &claims.email)
+        .await?
+    {
         return Err(ApiError::BadRequest);
     }
-    let token_response = token_http_response
-        .json::<DiscordTokenResponse>()
-        .await
-        .map_err(|_| ApiError::Internal)?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .body("Address confirmed. Digests of watched threads will be sent here.\n"))
+}
+
+/// GET for the link in the mail body, POST for RFC 8058 one-click unsubscribe.
+pub async fn unsubscribe_notifications(
+    query: web::Query<NotificationLink>,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = crate::auth::decode_notification_token(
+        &query.token,
+        crate::auth::NotificationAction::Unsubscribe,
+    )
+    .map_err(|_| ApiError::BadRequest)?;
+    data.repo.disable_digests(&claims.sub).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .body("Unsubscribed. No more digests will be sent.\n"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/preferences",
+    responses(
+        (status = 200, description = "Stored preference blob, `{}` when none was saved", body = Object),
+        (status = 401, description = "Sign-in required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_my_preferences(
+    auth: Auth,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    let preferences = data
+        .repo
+        .get_preferences(&subject)
+        .await?
+        .unwrap_or_else(|| serde_json::json!({}));
+    Ok(HttpResponse::Ok()
+        .insert_header((actix_web::http::header::CACHE_CONTROL, "private, no-cache"))
+        .json(preferences))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/me/preferences",
+    request_body = Object,
+    responses(
+        (status = 200, description = "Preferences replaced", body = Object),
+        (status = 400, description = "Not an object, too large, or a known key has the wrong shape"),
+        (status = 401, description = "Sign-in required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn put_my_preferences(
+    auth: Auth,
+    data: web::Data<AppState>,
+    payload: web::Json<serde_json::Value>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    let preferences = payload.into_inner();
+    crate::preferences::validate(&preferences).map_err(ApiError::Invalid)?;
+    data.repo
+        .put_preferences(&subject, preferences.clone())
+        .await?;
+    Ok(HttpResponse::Ok().json(preferences))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/filters",
+    responses(
+        (status = 200, description = "Mute list, oldest first", body = [UserFilter]),
+        (status = 401, description = "Sign-in required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_my_filters(
+    auth: Auth,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    Ok(HttpResponse::Ok()
+        .insert_header((actix_web::http::header::CACHE_CONTROL, "private, no-cache"))
+        .json(data.repo.list_filters(&subject).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/filters",
+    request_body = NewUserFilter,
+    responses(
+        (status = 201, description = "Filter added", body = UserFilter),
+        (status = 400, description = "Malformed value, muting yourself, or the list is full"),
+        (status = 401, description = "Sign-in required"),
+        (status = 404, description = "Post to mute the author of not found"),
+        (status = 409, description = "Already on the list")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_my_filter(
+    auth: Auth,
+    data: web::Data<AppState>,
+    payload: web::Json<NewUserFilter>,
+) -> Result<HttpResponse, ApiError> {
+    use crate::filters::{author_subject, normalize, PostRef, MAX_FILTERS};
+    let subject = caller_subject(&auth)?;
+    let mut new = payload.into_inner();
+    new.value = normalize(new.kind, &new.value).map_err(ApiError::Invalid)?;
+    let target_subject = match new.kind {
+        FilterKind::Subject => {
+            let created_by = match PostRef::parse(&new.value).ok_or(ApiError::BadRequest)? {
+                PostRef::Thread(id) => {
+                    service::get_thread(&data, id, false, Some(&auth))
+                        .await?
+                        .created_by
+                }
+                PostRef::Reply(id) => {
+                    service::get_reply(&data, id, false, Some(&auth))
+                        .await?
+                        .created_by
+                }
+            };
+            let author = author_subject(&created_by)
+                .ok_or(ApiError::Unprocessable)?
+                .to_string();
+            if author == subject {
+                return Err(ApiError::Invalid("cannot mute yourself".into()));
+            }
+            Some(author)
+        }
+        _ => None,
+    };
+    if data.repo.list_filters(&subject).await?.len() >= MAX_FILTERS {
+        return Err(ApiError::Invalid(format!(
+            "at most {MAX_FILTERS} filters are kept"
+        )));
+    }
+    let filter = data
+        .repo
+        .create_filter(&subject, new, target_subject)
+        .await?;
+    Ok(HttpResponse::Created().json(filter))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/filters/{id}",
+    params(("id" = Id, Path, description = "Filter id")),
+    responses(
+        (status = 204, description = "Filter removed"),
+        (status = 401, description = "Sign-in required"),
+        (status = 404, description = "No such filter on the caller's list")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_my_filter(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    data.repo.delete_filter(&subject, path.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/saved-searches",
+    responses(
+        (status = 200, description = "Saved searches, oldest first", body = [SavedSearch]),
+        (status = 401, description = "Sign-in required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_my_saved_searches(
+    auth: Auth,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    Ok(HttpResponse::Ok()
+        .insert_header((actix_web::http::header::CACHE_CONTROL, "private, no-cache"))
+        .json(data.repo.list_saved_searches(&subject).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/saved-searches",
+    request_body = NewSavedSearch,
+    responses(
+        (status = 201, description = "Search saved; posts made from now on are matched", body = SavedSearch),
+        (status = 400, description = "Empty or overlong query, or the list is full"),
+        (status = 401, description = "Sign-in required"),
+        (status = 404, description = "Board not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_my_saved_search(
+    auth: Auth,
+    data: web::Data<AppState>,
+    payload: web::Json<NewSavedSearch>,
+) -> Result<HttpResponse, ApiError> {
+    use crate::saved_searches::{MAX_QUERY_CHARS, MAX_SAVED_SEARCHES};
+    let subject = caller_subject(&auth)?;
+    let mut new = payload.into_inner();
+    new.query = new.query.trim().to_string();
+    if new.query.is_empty() || new.query.chars().count() > MAX_QUERY_CHARS {
+        return Err(ApiError::Invalid(format!(
+            "queries must be 1 to {MAX_QUERY_CHARS} characters"
+        )));
+    }
+    if let Some(board_id) = new.board_id {
+        let board = data
+            .repo
+            .get_board(board_id)
+            .await
+            .map_err(|_| ApiError::NotFound)?;
+        if board.deleted_at.is_some() {
+            return Err(ApiError::NotFound);
+        }
+    }
+    if data.repo.list_saved_searches(&subject).await?.len() >= MAX_SAVED_SEARCHES {
+        return Err(ApiError::Invalid(format!(
+            "at most {MAX_SAVED_SEARCHES} searches are kept"
+        )));
+    }
+    let search = data.repo.create_saved_search(&subject, new).await?;
+    Ok(HttpResponse::Created().json(search))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/saved-searches/{id}",
+    params(("id" = Id, Path, description = "Saved search id")),
+    responses(
+        (status = 204, description = "Search and its matches removed"),
+        (status = 401, description = "Sign-in required"),
+        (status = 404, description = "No such search of the caller's")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_my_saved_search(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    data.repo
+        .delete_saved_search(&subject, path.into_inner())
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Matches returned per saved search.
+const SAVED_SEARCH_MATCHES_PAGE: i64 = 100;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/saved-searches/{id}/matches",
+    params(("id" = Id, Path, description = "Saved search id")),
+    responses(
+        (status = 200, description = "The 100 newest matches, newest first", body = [SavedSearchMatch]),
+        (status = 401, description = "Sign-in required"),
+        (status = 404, description = "No such search of the caller's")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_my_saved_search_matches(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    let matches = data
+        .repo
+        .list_saved_search_matches(&subject, path.into_inner(), SAVED_SEARCH_MATCHES_PAGE)
+        .await?;
+    Ok(HttpResponse::Ok()
+        .insert_header((actix_web::http::header::CACHE_CONTROL, "private, no-cache"))
+        .json(matches))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/appeals",
+    request_body = NewAppeal,
+    responses(
+        (status = 201, description = "Appeal queued for moderators", body = Appeal),
+        (status = 400, description = "Empty or overlong message, or a target id missing for a post or given for a ban"),
+        (status = 401, description = "Sign-in required"),
+        (status = 404, description = "No active ban, or no deleted post of the caller's with that id"),
+        (status = 409, description = "This action was already appealed")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn file_appeal(
+    auth: Auth,
+    data: web::Data<AppState>,
+    payload: web::Json<NewAppeal>,
+) -> Result<HttpResponse, ApiError> {
+    use crate::appeals::MAX_APPEAL_CHARS;
+    let subject = caller_subject(&auth)?;
+    let mut new = payload.into_inner();
+    new.message = new.message.trim().to_string();
+    if new.message.is_empty() || new.message.chars().count() > MAX_APPEAL_CHARS {
+        return Err(ApiError::Invalid(format!(
+            "messages must be 1 to {MAX_APPEAL_CHARS} characters"
+        )));
+    }
+    if (new.kind == AppealKind::Ban) != new.target_id.is_none() {
+        return Err(ApiError::Invalid(
+            "thread and reply appeals take a target_id; ban appeals do not".into(),
+        ));
+    }
+    let appeal = data.repo.create_appeal(&subject, new).await?;
+    Ok(HttpResponse::Created().json(appeal))
+}
 
-    // Get user info
-    let user_http_response = client
-        .get("https://discord.com/api/users/@me")
-        .header(
-            reqwest::header::AUTHORIZATION,
-            format!("Bearer {}", token_response.access_token),
-        )
-        .send()
-        .await
-        .map_err(|_| ApiError::Internal)?;
-    if !user_http_response.status().is_success() {
-        log::warn!(
-            "Discord user lookup failed with status {}",
-            user_http_response.status()
-        );
-        return Err(ApiError::BadRequest);
+#[utoipa::path(
+    post,
+    path = "/api/v1/reports",
+    request_body = NewReport,
+    responses(
+        (status = 201, description = "Report queued for moderators", body = Report),
+        (status = 400, description = "Empty or overlong reason"),
+        (status = 401, description = "Sign-in required"),
+        (status = 404, description = "No visible thread or reply with that id"),
+        (status = 409, description = "The caller already has an open report on this post")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn file_report(
+    auth: Auth,
+    data: web::Data<AppState>,
+    payload: web::Json<NewReport>,
+) -> Result<HttpResponse, ApiError> {
+    use crate::reports::MAX_REASON_CHARS;
+    let reporter = caller_subject(&auth)?;
+    let mut new = payload.into_inner();
+    new.reason = new.reason.trim().to_string();
+    if new.reason.is_empty() || new.reason.chars().count() > MAX_REASON_CHARS {
+        return Err(ApiError::Invalid(format!(
+            "reasons must be 1 to {MAX_REASON_CHARS} characters"
+        )));
     }
-    let user = user_http_response
-        .json::<DiscordUser>()
-        .await
-        .map_err(|_| ApiError::Internal)?;
+    let report = data.repo.create_report(&reporter, new).await?;
+    Ok(HttpResponse::Created().json(report))
+}
 
-    // Only explicitly assigned Discord subjects may post. Bootstrap admins are
-    // the recovery path when no role assignment is available.
-    let bootstrap_admins = std::env::var("BOOTSTRAP_ADMIN_DISCORD_IDS").unwrap_or_default();
-    let is_bootstrap_admin = bootstrap_admins
-        .split(',')
-        .filter(|s| !s.trim().is_empty())
-        .any(|s| s.trim() == user.id);
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/appeals",
+    responses(
+        (status = 200, description = "The caller's appeals and their decisions, newest first", body = [Appeal]),
+        (status = 401, description = "Sign-in required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_my_appeals(
+    auth: Auth,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    Ok(HttpResponse::Ok().json(data.repo.list_subject_appeals(&subject).await?))
+}
 
-    let subject_key = format!("discord:{}", user.id);
-    let assigned_role = data.repo.get_subject_role(&subject_key).await;
-    let frontend_url =
-        std::env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:5173".to_string());
-    let Some(role) = discord_admission_role(assigned_role, is_bootstrap_admin) else {
-        return Ok(HttpResponse::Found()
-            .insert_header((
-                "Location",
-                format!(
-                    "{}/login?error=discord_not_allowlisted",
-                    frontend_url.trim_end_matches('/')
-                ),
-            ))
-            .cookie(clear_oauth_transaction_cookie())
-            .finish());
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/profile",
+    responses(
+        (status = 200, description = "Display name and avatar shown on the caller's posts", body = AuthorProfile),
+        (status = 401, description = "Sign-in required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_my_profile(
+    auth: Auth,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    let profile = data.repo.get_profile(&subject).await?.unwrap_or_default();
+    Ok(HttpResponse::Ok().json(profile))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/me/profile",
+    request_body = UpdateProfile,
+    responses(
+        (status = 200, description = "Display name saved", body = AuthorProfile),
+        (status = 400, description = "Name too short or long, disallowed characters, or reserved"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Banned or not allowed to post"),
+        (status = 409, description = "Name taken")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn put_my_profile(
+    auth: Auth,
+    data: web::Data<AppState>,
+    payload: web::Json<UpdateProfile>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    ensure_subject_can_post(data.get_ref(), &auth, &subject).await?;
+    let display_name = match payload.into_inner().display_name {
+        Some(name) => {
+            Some(crate::profiles::normalize_display_name(&name).map_err(ApiError::Invalid)?)
+        }
+        None => None,
     };
+    let profile = data
+        .repo
+        .set_display_name(&subject, display_name.as_deref())
+        .await?;
+    Ok(HttpResponse::Ok().json(profile))
+}
 
-    // Generate JWT
-    let jwt = crate::auth::create_jwt(&user.id, &user.username, vec![role])
-        .map_err(|_| ApiError::Internal)?;
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/me/avatar",
+    request_body(content = String, content_type = "multipart/form-data", description = "Image in a `file` field: PNG, JPEG, GIF or WebP up to 256 KiB"),
+    responses(
+        (status = 200, description = "Avatar stored", body = AuthorProfile),
+        (status = 400, description = "No `file` field"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Banned or not allowed to post"),
+        (status = 413, description = "Image too large"),
+        (status = 415, description = "Not a supported image type"),
+        (status = 429, description = "Upload rate limit exceeded")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn put_my_avatar(
+    auth: Auth,
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    mut payload: Multipart,
+) -> Result<HttpResponse, ApiError> {
+    use crate::profiles::{AVATAR_MIME, AVATAR_SIZE_LIMIT};
+    let subject = caller_subject(&auth)?;
+    if request_exceeds(declared_length(req.headers()), AVATAR_SIZE_LIMIT as u64) {
+        return Ok(HttpResponse::PayloadTooLarge().finish());
+    }
+    ensure_subject_can_post(data.get_ref(), &auth, &subject).await?;
+    if let Some(rl) = &data.rate_limiter {
+        if !rl.allow_image(&extract_client_ip(&req)) {
+            metrics::increment_counter!("rate_limit_denied", "action" => "avatar_upload");
+            return Err(ApiError::RateLimited {
+                retry_after: rl.config().image_window.as_secs(),
+            });
+        }
+    }
+    while let Some(mut field) = payload.try_next().await.map_err(|e| {
+        log::error!("multipart error: {e}");
+        ApiError::BadRequest
+    })? {
+        if field.content_disposition().get_name() != Some("file") {
+            continue;
+        }
+        if declared_length(field.headers()).is_some_and(|len| len > AVATAR_SIZE_LIMIT as u64) {
+            return Ok(HttpResponse::PayloadTooLarge().finish());
+        }
+        let mut bytes: Vec<u8> = Vec::new();
+        while let Some(chunk) = field.try_next().await.map_err(|e| {
+            log::error!("stream read error: {e}");
+            ApiError::BadRequest
+        })? {
+            if bytes.len() + chunk.len() > AVATAR_SIZE_LIMIT {
+                return Ok(HttpResponse::PayloadTooLarge().finish());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        let mime = detect_upload_mime(&bytes);
+        if !AVATAR_MIME.contains(&mime.as_str()) {
+            return Ok(HttpResponse::UnsupportedMediaType().finish());
+        }
+        let hash = format!("{:x}", Sha256::digest(&bytes));
+        match data.image_store.save(&hash, &mime, &bytes).await {
+            Ok(()) | Err(ImageStoreError::Duplicate) => {}
+            Err(e) => {
+                log::error!("image_store save error: {e}");
+                return Err(ApiError::Internal);
+            }
+        }
+        let profile = data.repo.set_avatar(&subject, Some((&hash, &mime))).await?;
+        return Ok(HttpResponse::Ok().json(profile));
+    }
+    Err(ApiError::BadRequest)
+}
 
-    // Redirect to frontend with token
-    Ok(HttpResponse::Found()
-        .insert_header((
-            "Location",
-            format!("{}/", frontend_url.trim_end_matches('/')),
-        ))
-        .cookie(session_cookie(&jwt))
-        .cookie(clear_oauth_transaction_cookie())
-        .finish())
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/avatar",
+    responses(
+        (status = 204, description = "Avatar removed"),
+        (status = 401, description = "Sign-in required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_my_avatar(
+    auth: Auth,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    let subject = caller_subject(&auth)?;
+    data.repo.set_avatar(&subject, None).await?;
+    Ok(HttpResponse::NoContent().finish())
 }
 
-fn role_subject_key(jwt_subject: &str) -> Option<String> {
-    if jwt_subject.starts_with("btc:") {
-        Some(jwt_subject.to_string())
-    } else {
-        jwt_subject
-            .split_once(':')
-            .map(|(discord_id, _)| format!("discord:{discord_id}"))
-    }
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/profiles/{subject}",
+    params(("subject" = String, Path, description = "Provider subject key")),
+    responses(
+        (status = 204, description = "Display name and avatar cleared"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required"),
+        (status = 404, description = "Subject has no display name or avatar")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn reset_subject_profile(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    let subject = path.into_inner();
+    data.repo.reset_profile(&subject).await?;
+    log::info!("profile of {subject} reset by {}", auth.0.sub);
+    Ok(HttpResponse::NoContent().finish())
 }
 
-fn is_bootstrap_discord_id(discord_id: &str) -> bool {
-    std::env::var("BOOTSTRAP_ADMIN_DISCORD_IDS")
-        .unwrap_or_default()
-        .split(',')
-        .filter(|value| !value.trim().is_empty())
-        .any(|value| value.trim() == discord_id)
+const MAX_MODERATION_LOG: i64 = 500;
+
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+pub struct ModerationLogQuery {
+    /// Only actions in this thread
+    thread_id: Option<Id>,
+    /// Entries to return, newest first (default and maximum 500)
+    limit: Option<i64>,
 }
 
-pub async fn refresh_token(
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/moderation-log",
+    params(ModerationLogQuery),
+    responses(
+        (status = 200, description = "Thread moderation actions by staff and thread creators, newest first", body = [ModerationEntry]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_moderation_log(
     auth: Auth,
     data: web::Data<AppState>,
+    query: web::Query<ModerationLogQuery>,
 ) -> Result<HttpResponse, ApiError> {
-    let subject_key = role_subject_key(&auth.0.sub).ok_or(ApiError::Forbidden)?;
-    ensure_subject_not_banned(data.get_ref(), &subject_key).await?;
-    let assigned_role = data.repo.get_subject_role(&subject_key).await;
-    let role = if let Some(address) = auth.0.sub.strip_prefix("btc:") {
-        let _ = address;
-        assigned_role.unwrap_or(Role::User)
-    } else {
-        let (discord_id, _) = auth.0.sub.split_once(':').ok_or(ApiError::Forbidden)?;
-        discord_admission_role(assigned_role, is_bootstrap_discord_id(discord_id))
-            .ok_or(ApiError::Forbidden)?
-    };
-    let jwt = crate::auth::create_jwt(&auth.0.sub, &auth.0.sub, vec![role])
-        .map_err(|_| ApiError::Internal)?;
+    ensure_moderator_or_admin!(auth);
+    let limit = query
+        .limit
+        .unwrap_or(MAX_MODERATION_LOG)
+        .clamp(1, MAX_MODERATION_LOG);
+    Ok(HttpResponse::Ok().json(
+        data.repo
+            .list_moderation_log(query.thread_id, limit)
+            .await?,
+    ))
+}
 
-    Ok(HttpResponse::Ok()
-        .cookie(session_cookie(&jwt))
-        .json(serde_json::json!({ "token": jwt })))
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+pub struct DeletionLogQuery {
+    /// Only removals of this kind of post
+    kind: Option<DeletionKind>,
+    /// Entries to return, newest first (default and maximum 500)
+    limit: Option<i64>,
 }
 
-pub async fn logout() -> Result<HttpResponse, ApiError> {
-    Ok(HttpResponse::NoContent()
-        .cookie(clear_session_cookie())
-        .finish())
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/deletion-log",
+    params(DeletionLogQuery),
+    responses(
+        (status = 200, description = "Staff and thread-creator removals with their reasons, newest first", body = [DeletionEntry]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Moderator role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_deletion_log(
+    auth: Auth,
+    data: web::Data<AppState>,
+    query: web::Query<DeletionLogQuery>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_moderator_or_admin!(auth);
+    let limit = query
+        .limit
+        .unwrap_or(MAX_MODERATION_LOG)
+        .clamp(1, MAX_MODERATION_LOG);
+    Ok(HttpResponse::Ok().json(data.repo.list_deletion_log(query.kind, limit).await?))
 }
 
-#[derive(serde::Deserialize, utoipa::ToSchema)]
-pub struct SetSubjectRoleRequest {
-    subject: String,
-    role: String,
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/scheduled-threads",
+    request_body = NewScheduledThread,
+    responses(
+        (status = 201, description = "Thread scheduled; it is posted under the caller's account", body = ScheduledThread),
+        (status = 400, description = "Invalid subject, body, name, time or repeat interval"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Board not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_scheduled_thread(
+    auth: Auth,
+    data: web::Data<AppState>,
+    payload: web::Json<NewScheduledThread>,
+) -> Result<HttpResponse, ApiError> {
+    use crate::scheduled::MAX_SCHEDULE_DAYS;
+    ensure_admin!(auth);
+    let mut new = payload.into_inner();
+    new.subject = new.subject.trim().to_string();
+    new.body = new.body.trim().to_string();
+    validate_thread_payload(&NewThread {
+        board_id: new.board_id,
+        subject: new.subject.clone(),
+        body: new.body.clone(),
+        image_hash: None,
+        mime: None,
+        author_name: None,
+        tripcode_password: None,
+        delete_password: None,
+        tags: Vec::new(),
+    })?;
+    new.author_name = derive_public_identity(new.author_name.take(), None)?.author_name;
+    let now = chrono::Utc::now();
+    if new.publish_at <= now || new.publish_at > now + chrono::Duration::days(MAX_SCHEDULE_DAYS) {
+        return Err(ApiError::Invalid(format!(
+            "publish_at must be in the next {MAX_SCHEDULE_DAYS} days"
+        )));
+    }
+    if new
+        .repeat_days
+        .is_some_and(|days| !(1..=MAX_SCHEDULE_DAYS).contains(&i64::from(days)))
+    {
+        return Err(ApiError::Invalid(format!(
+            "repeat_days must be 1 to {MAX_SCHEDULE_DAYS}"
+        )));
+    }
+    let board = data.repo.get_board(new.board_id).await?;
+    if board.deleted_at.is_some() {
+        return Err(ApiError::NotFound);
+    }
+    let (_, created_by) = private_author_attribution(&auth)?;
+    let scheduled = data.repo.create_scheduled_thread(new, created_by).await?;
+    log::info!(
+        "thread scheduled on /{}/ for {} by {}",
+        board.slug,
+        scheduled.publish_at,
+        auth.0.sub
+    );
+    Ok(HttpResponse::Created().json(scheduled))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/scheduled-threads",
+    responses(
+        (status = 200, description = "Pending and repeating schedules, soonest first", body = [ScheduledThread]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_scheduled_threads(
+    auth: Auth,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    Ok(HttpResponse::Ok().json(data.repo.list_scheduled_threads().await?))
 }
 
 #[utoipa::path(
-    post,
-    path = "/api/v1/admin/roles",
-    request_body = SetSubjectRoleRequest,
+    delete,
+    path = "/api/v1/admin/scheduled-threads/{id}",
+    params(("id" = Id, Path, description = "Schedule id")),
     responses(
-        (status = 200, description = "Role updated"),
-        (status = 403, description = "Forbidden - Admin only"),
-        (status = 400, description = "Invalid role/subject")
-    )
+        (status = 204, description = "Schedule cancelled; threads it already posted stay"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "No pending schedule with this id")
+    ),
+    security(("bearer_auth" = []))
 )]
-pub async fn set_subject_role(
+pub async fn cancel_scheduled_thread(
     auth: Auth,
     data: web::Data<AppState>,
-    payload: web::Json<SetSubjectRoleRequest>,
+    path: web::Path<Id>,
 ) -> Result<HttpResponse, ApiError> {
-    if !auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
-        return Err(ApiError::Forbidden);
-    }
-    let subj = payload.subject.trim();
-    if !is_valid_subject_key(subj) {
-        return Err(ApiError::BadRequest);
-    }
-    let role = match payload.role.to_lowercase().as_str() {
-        "user" => Role::User,
-        "moderator" => Role::Moderator,
-        "admin" => Role::Admin,
-        _ => return Err(ApiError::BadRequest),
-    };
-    data.repo.set_subject_role(subj, role).await?;
-    Ok(HttpResponse::Ok()
-        .json(serde_json::json!({"message":"Role updated","subject":subj,"role":payload.role})))
+    ensure_admin!(auth);
+    let id = path.into_inner();
+    data.repo.cancel_scheduled_thread(id).await?;
+    log::info!("scheduled thread {id} cancelled by {}", auth.0.sub);
+    Ok(HttpResponse::NoContent().finish())
 }
 
-#[derive(serde::Serialize, utoipa::ToSchema)]
-pub struct RoleAssignment {
-    subject: String,
-    role: String,
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/boards/{id}/testers",
+    params(("id" = Id, Path, description = "Board id")),
+    responses(
+        (status = 200, description = "Subjects allowed onto the board while it is in beta, oldest first", body = [BoardTester]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Board not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_board_testers(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let board_id = path.into_inner();
+    data.repo.get_board(board_id).await?;
+    Ok(HttpResponse::Ok().json(data.repo.list_board_testers(board_id).await?))
 }
 
 #[utoipa::path(
-    get,
-    path = "/api/v1/admin/roles",
+    post,
+    path = "/api/v1/admin/boards/{id}/testers",
+    params(("id" = Id, Path, description = "Board id")),
+    request_body = NewBoardTester,
     responses(
-        (status = 200, description = "List role assignments", body = [RoleAssignment]),
-        (status = 403, description = "Forbidden")
-    )
+        (status = 201, description = "Tester added; adding one twice keeps the first entry", body = BoardTester),
+        (status = 400, description = "Invalid subject"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Board not found")
+    ),
+    security(("bearer_auth" = []))
 )]
-pub async fn list_roles(auth: Auth, data: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
-    if !auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
-        return Err(ApiError::Forbidden);
+pub async fn add_board_tester(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<NewBoardTester>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let board_id = path.into_inner();
+    let subject = payload.subject.trim();
+    if !is_valid_subject_key(subject) {
+        return Err(ApiError::BadRequest);
     }
-    let rows = data.repo.list_roles().await?;
-    let resp: Vec<RoleAssignment> = rows
-        .into_iter()
-        .map(|(s, r)| RoleAssignment {
-            subject: s,
-            role: match r {
-                Role::Admin => "admin".into(),
-                Role::Moderator => "moderator".into(),
-                Role::User => "user".into(),
-            },
-        })
-        .collect();
-    Ok(HttpResponse::Ok().json(resp))
+    let board = data.repo.get_board(board_id).await?;
+    if board.deleted_at.is_some() {
+        return Err(ApiError::NotFound);
+    }
+    let tester = data
+        .repo
+        .add_board_tester(board_id, subject, &auth.0.sub)
+        .await?;
+    log::info!(
+        "{} added as tester of /{}/ by {}",
+        tester.subject,
+        board.slug,
+        auth.0.sub
+    );
+    Ok(HttpResponse::Created().json(tester))
 }
 
 #[utoipa::path(
     delete,
-    path = "/api/v1/admin/roles/{subject}",
-    params(("subject"=String, Path, description="Subject key to delete")),
+    path = "/api/v1/admin/boards/{id}/testers/{subject}",
+    params(
+        ("id" = Id, Path, description = "Board id"),
+        ("subject" = String, Path, description = "Provider subject key")
+    ),
     responses(
-        (status = 204, description = "Deleted"),
-        (status = 403, description = "Forbidden"),
-        (status = 404, description = "Not found")
-    )
+        (status = 204, description = "Tester removed"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Not a tester of the board")
+    ),
+    security(("bearer_auth" = []))
 )]
-pub async fn delete_role(
+pub async fn remove_board_tester(
     auth: Auth,
     data: web::Data<AppState>,
-    path: web::Path<String>,
+    path: web::Path<(Id, String)>,
 ) -> Result<HttpResponse, ApiError> {
-    if !auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
-        return Err(ApiError::Forbidden);
-    }
-    let subj = path.into_inner();
-    data.repo.delete_role(&subj).await.map_err(|e| match e {
-        crate::repo::RepoError::NotFound => ApiError::NotFound,
-        _ => ApiError::Internal,
-    })?;
+    ensure_admin!(auth);
+    let (board_id, subject) = path.into_inner();
+    data.repo.remove_board_tester(board_id, &subject).await?;
     Ok(HttpResponse::NoContent().finish())
 }
 
-#[derive(serde::Serialize, utoipa::ToSchema)]
-struct MeResponse {
-    id: String,
-    username: String,
-    discord_id: String,
-    role: String,
-}
-
-// Return authenticated user info
 #[utoipa::path(
     get,
-    path = "/api/v1/auth/me",
+    path = "/api/v1/admin/bridges",
     responses(
-        (status = 200, description = "Current user info or null when anonymous", body = Option<MeResponse>)
-    )
+        (status = 200, description = "Bridges of every board, oldest first", body = [BoardBridge]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required")
+    ),
+    security(("bearer_auth" = []))
 )]
-pub async fn auth_me(auth: Option<Auth>) -> Result<HttpResponse, ApiError> {
-    let Some(auth) = auth else {
-        return Ok(HttpResponse::Ok().json(Option::<MeResponse>::None));
-    };
-    // choose highest privilege (Admin > Moderator > User); claims already vetted
-    let role = if auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
-        "admin"
-    } else if auth.0.roles.iter().any(|r| matches!(r, Role::Moderator)) {
-        "moderator"
-    } else {
-        "user"
-    };
-    let sub = &auth.0.sub;
-    let (id, username, discord_id) = if let Some(rest) = sub.strip_prefix("btc:") {
-        (sub.clone(), rest.to_string(), String::new())
-    } else if let Some((id, u)) = sub.split_once(':') {
-        (sub.clone(), u.to_string(), id.to_string())
-    } else {
-        (sub.clone(), sub.clone(), sub.clone())
-    };
-    let me = MeResponse {
-        id,
-        username,
-        discord_id,
-        role: role.to_string(),
-    };
-    Ok(HttpResponse::Ok().json(me))
+pub async fn list_board_bridges(
+    auth: Auth,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    Ok(HttpResponse::Ok().json(data.repo.list_board_bridges(None).await?))
 }
 
-// Very lightweight health handler (no DB ping yet; fast fail if process unhealthy)
-pub async fn health() -> Result<HttpResponse, ApiError> {
-    Ok(HttpResponse::Ok().finish())
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/boards/{id}/bridges",
+    params(("id" = Id, Path, description = "Board id")),
+    responses(
+        (status = 200, description = "The board's bridges, oldest first", body = [BoardBridge]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_board_bridges_of_board(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    Ok(HttpResponse::Ok().json(
+        data.repo
+            .list_board_bridges(Some(path.into_inner()))
+            .await?,
+    ))
 }
 
-// (Removed bandcamp_oembed_proxy)
-
-// ---------------- Bitcoin Proof-of-Value Auth --------------------
-use once_cell::sync::Lazy;
-use rand::RngCore;
-use std::collections::HashMap;
-use std::time::{Duration as StdDuration, SystemTime};
-use tokio::sync::Mutex;
-
-static BTC_CHALLENGES: Lazy<Mutex<HashMap<String, (String, SystemTime)>>> =
-    Lazy::new(|| Mutex::new(HashMap::new()));
-const BTC_CHALLENGE_TTL_SECS: u64 = 300; // 5 minutes
-const BTC_MIN_BALANCE_SATS: u64 = 1_000_000; // 0.01 BTC
-
-// Internal helper (used in tests) to insert a deterministic challenge for an address.
-// Not exposed via HTTP, safe for production build though only called from tests.
-pub async fn btc_test_insert_challenge(address: &str, challenge: &str) {
-    let mut map = BTC_CHALLENGES.lock().await;
-    map.insert(
-        address.to_string(),
-        (challenge.to_string(), SystemTime::now()),
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/boards/{id}/bridges",
+    params(("id" = Id, Path, description = "Board id")),
+    request_body = NewBoardBridge,
+    responses(
+        (status = 201, description = "Bridge created; threads posted from now on are announced", body = BoardBridge),
+        (status = 400, description = "Invalid target, or the platform's credentials are not configured"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Board not found"),
+        (status = 409, description = "The board already bridges to this target")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_board_bridge(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<NewBoardBridge>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let board_id = path.into_inner();
+    let mut new = payload.into_inner();
+    new.target = new.target.trim().to_string();
+    crate::bridges::BridgeConfig::from_env()
+        .validate_target(new.kind, &new.target)
+        .map_err(ApiError::Invalid)?;
+    let board = data.repo.get_board(board_id).await?;
+    if board.deleted_at.is_some() {
+        return Err(ApiError::NotFound);
+    }
+    let bridge = data
+        .repo
+        .create_board_bridge(board_id, new, &auth.0.sub)
+        .await?;
+    log::info!(
+        "{} bridge {} added to /{}/ by {}",
+        bridge.kind.as_str(),
+        bridge.id,
+        board.slug,
+        auth.0.sub
     );
-}
-
-#[derive(serde::Deserialize, utoipa::ToSchema)]
-pub struct BitcoinChallengeRequest {
-    pub address: String,
-}
-#[derive(serde::Serialize, utoipa::ToSchema)]
-pub struct BitcoinChallengeResponse {
-    pub challenge: String,
+    Ok(HttpResponse::Created().json(bridge))
 }
 
 #[utoipa::path(
-    post,
-    path = "/api/v1/auth/bitcoin/challenge",
-    request_body = BitcoinChallengeRequest,
+    patch,
+    path = "/api/v1/admin/bridges/{id}",
+    params(("id" = Id, Path, description = "Bridge id")),
+    request_body = UpdateBoardBridge,
     responses(
-        (status = 200, description = "Challenge issued", body = BitcoinChallengeResponse),
-        (status = 400, description = "Bad request")
-    )
+        (status = 200, description = "Bridge updated", body = BoardBridge),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Bridge not found")
+    ),
+    security(("bearer_auth" = []))
 )]
-pub async fn bitcoin_challenge(
-    payload: web::Json<BitcoinChallengeRequest>,
+pub async fn update_board_bridge(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<UpdateBoardBridge>,
 ) -> Result<HttpResponse, ApiError> {
-    let address = payload.address.trim();
-    if address.is_empty() {
-        return Err(ApiError::BadRequest);
-    }
-    // Basic length sanity
-    if address.len() < 26 || address.len() > 100 {
-        return Err(ApiError::BadRequest);
-    }
-    // Reject syntactically invalid addresses early
-    if Address::from_str(address).is_err() {
-        return Err(ApiError::BadRequest);
-    }
-    // ───────────────────────────────────────────────────────────────────
-    // Generate 32 random bytes hex for nonce
-    let mut nonce_bytes = [0u8; 32];
-    rand::thread_rng().fill_bytes(&mut nonce_bytes);
-    let nonce = hex::encode(nonce_bytes);
-    let challenge = format!(
-        "Prove you own Bitcoin address {} (nonce {})",
-        address, nonce
+    ensure_admin!(auth);
+    let bridge = data
+        .repo
+        .set_board_bridge_enabled(path.into_inner(), payload.enabled)
+        .await?;
+    log::info!(
+        "bridge {} {} by {}",
+        bridge.id,
+        if bridge.enabled {
+            "enabled"
+        } else {
+            "disabled"
+        },
+        auth.0.sub
     );
-    {
-        let mut map = BTC_CHALLENGES.lock().await;
-        map.retain(|_, (_, issued)| {
-            issued.elapsed().unwrap_or_default() <= StdDuration::from_secs(BTC_CHALLENGE_TTL_SECS)
-        });
-        map.insert(address.to_string(), (challenge.clone(), SystemTime::now()));
-    }
-    Ok(HttpResponse::Ok().json(BitcoinChallengeResponse { challenge }))
+    Ok(HttpResponse::Ok().json(bridge))
 }
 
-#[derive(serde::Deserialize, utoipa::ToSchema)]
-pub struct BitcoinVerifyRequest {
-    pub address: String,
-    pub signature: String,
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/bridges/{id}",
+    params(("id" = Id, Path, description = "Bridge id")),
+    responses(
+        (status = 204, description = "Bridge removed"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Bridge not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_board_bridge(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let id = path.into_inner();
+    data.repo.delete_board_bridge(id).await?;
+    log::info!("bridge {id} removed by {}", auth.0.sub);
+    Ok(HttpResponse::NoContent().finish())
 }
-#[derive(serde::Serialize, utoipa::ToSchema)]
-pub struct BitcoinVerifyResponse {
-    pub token: String,
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/matrix-mirrors",
+    responses(
+        (status = 200, description = "Matrix mirrors, oldest first", body = [MatrixMirror]),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_matrix_mirrors(
+    auth: Auth,
+    data: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    Ok(HttpResponse::Ok().json(data.repo.list_matrix_mirrors().await?))
 }
 
 #[utoipa::path(
     post,
-    path = "/api/v1/auth/bitcoin/verify",
-    request_body = BitcoinVerifyRequest,
+    path = "/api/v1/admin/matrix-mirrors",
+    request_body = NewMatrixMirror,
     responses(
-        (status = 200, description = "JWT token", body = BitcoinVerifyResponse),
-        (status = 400, description = "Bad request"),
-        (status = 403, description = "Forbidden / insufficient balance"),
-        (status = 410, description = "Challenge expired")
-    )
+        (status = 201, description = "Mirror created; posts made from now on are mirrored", body = MatrixMirror),
+        (status = 400, description = "Not exactly one of board_id and thread_id, an invalid room id, or Matrix is not configured"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Board or thread not found"),
+        (status = 409, description = "The board or thread is already mirrored into this room")
+    ),
+    security(("bearer_auth" = []))
 )]
-pub async fn bitcoin_verify(
-    payload: web::Json<BitcoinVerifyRequest>,
+pub async fn create_matrix_mirror(
+    auth: Auth,
+    data: web::Data<AppState>,
+    payload: web::Json<NewMatrixMirror>,
 ) -> Result<HttpResponse, ApiError> {
-    use actix_web::http::StatusCode;
-    // Retrieve *and* remove challenge (single-use)
-    let (challenge, issued) = {
-        let mut map = BTC_CHALLENGES.lock().await;
-        map.remove(&payload.address).ok_or(ApiError::BadRequest)?
-    };
-    if issued.elapsed().unwrap_or_default() > StdDuration::from_secs(BTC_CHALLENGE_TTL_SECS) {
-        return Ok(HttpResponse::build(StatusCode::GONE).finish());
-    }
-    // Test helpers (never set in production): granular skips instead of monolithic BTC_AUTH_TEST_ACCEPT
-    let skip_balance = debug_test_flag("BTC_AUTH_TEST_SKIP_BALANCE");
-    // Skip signature verification (used when we only want to test balance aggregation with a mock UTXO response)
-    let test_skip_sig = debug_test_flag("BTC_AUTH_TEST_SKIP_SIG");
-    // Env override for min balance
-    let min_balance = std::env::var("BTC_MIN_BALANCE_SATS")
-        .ok()
-        .and_then(|v| v.parse::<u64>().ok())
-        .unwrap_or(BTC_MIN_BALANCE_SATS);
-    // ───────────────────────────────────────────────────────────────────
-    // Signature verification (unless explicitly skipped)
-    if !test_skip_sig {
-        if let Err(e) =
-            verify_bitcoin_message(&payload.address, &challenge, &payload.signature).await
-        {
-            log::warn!("bitcoin signature verify failed: {e}");
-            return Err(ApiError::BadRequest);
+    ensure_admin!(auth);
+    let mut new = payload.into_inner();
+    new.room_id = new.room_id.trim().to_string();
+    crate::matrix::MatrixConfig::from_env()
+        .validate_room(&new.room_id)
+        .map_err(ApiError::Invalid)?;
+    let target = match (new.board_id, new.thread_id) {
+        (Some(board_id), None) => {
+            let board = service::get_board(&data, board_id, false, Some(&auth)).await?;
+            format!("/{}/", board.slug)
         }
-    }
-    // Balance check (unless explicitly skipped)
-    if !skip_balance {
-        match fetch_btc_balance_sats(&payload.address).await {
-            Ok(sats) if sats >= min_balance => {}
-            Ok(_) => return Err(ApiError::InsufficientFunds),
-            Err(_) => return Err(ApiError::Internal),
+        (None, Some(thread_id)) => {
+            service::get_thread(&data, thread_id, false, Some(&auth)).await?;
+            format!("thread {thread_id}")
         }
-    }
-    // Issue JWT (User role)
-    let jwt = crate::auth::create_bitcoin_jwt(&payload.address, vec![Role::User])
-        .map_err(|_| ApiError::Internal)?;
-    Ok(HttpResponse::Ok()
-        .cookie(session_cookie(&jwt))
-        .json(BitcoinVerifyResponse { token: jwt }))
-}
-
-async fn verify_bitcoin_message(
-    address: &str,
-    message: &str,
-    signature_b64: &str,
-) -> anyhow::Result<()> {
-    use base64::Engine;
-    use bitcoin::address::Payload;
-    use bitcoin::{Address, Network};
-    use secp256k1::{
-        ecdsa::RecoverableSignature, ecdsa::RecoveryId, Message as SecpMessage, Secp256k1,
-    };
-    use sha2::{Digest, Sha256};
-    use std::str::FromStr;
-
-    // 1. Decode base64 signature (65 bytes: header + 64) ---------------------
-    let raw = base64::engine::general_purpose::STANDARD
-        .decode(signature_b64.as_bytes())
-        .map_err(|e| anyhow::anyhow!(e))?;
-    if raw.len() != 65 {
-        anyhow::bail!("unexpected sig length (want 65)");
-    }
-
-    let header = raw[0]; // 27..34 allowed by Core (27 + recid + (4 if compressed))
-    if !(27..=34).contains(&header) {
-        anyhow::bail!("invalid header byte");
-    }
-    let rec_id = RecoveryId::from_i32(((header - 27) & 0x03) as i32)?;
-    let is_compressed = ((header - 27) & 0x04) != 0;
-    let sig = RecoverableSignature::from_compact(&raw[1..65], rec_id)?;
-
-    // 2. Build Core-compatible preimage: varint(len(magic)) + magic + varint(len(msg)) + msg
-    //    Magic string per Bitcoin Core: "Bitcoin Signed Message:\n"
-    const MAGIC: &str = "Bitcoin Signed Message:\n"; // includes trailing newline
-    fn ser_varint(n: usize) -> Vec<u8> {
-        if n < 253 {
-            vec![n as u8]
-        } else {
-            vec![
-                253,
-                (n & 0xff) as u8,
-                ((n >> 8) & 0xff) as u8,
-                ((n >> 16) & 0xff) as u8,
-            ]
+        _ => {
+            return Err(ApiError::Invalid(
+                "set exactly one of board_id and thread_id".into(),
+            ))
         }
-    }
-    let mut data = Vec::with_capacity(1 + MAGIC.len() + 9 + message.len());
-    // magic length (always <253)
-    data.push(MAGIC.len() as u8);
-    data.extend_from_slice(MAGIC.as_bytes());
-    // message length + message
-    data.extend_from_slice(&ser_varint(message.len()));
-    data.extend_from_slice(message.as_bytes());
-
-    // Double SHA256
-    let hash = {
-        let d1 = Sha256::digest(&data);
-        Sha256::digest(d1)
-    };
-
-    // 3. Recover public key
-    let secp = Secp256k1::new();
-    let msg = SecpMessage::from_digest_slice(&hash)?;
-    let pubkey = secp.recover_ecdsa(&msg, &sig)?;
-    let pk_compressed = pubkey.serialize();
-    let pk_uncompressed = pubkey.serialize_uncompressed();
-    let pk_bytes: &[u8] = if is_compressed {
-        &pk_compressed
-    } else {
-        &pk_uncompressed
     };
-
-    // 4. Compare derived address
-    let addr = Address::from_str(address)?;
-    match &addr.payload {
-        Payload::PubkeyHash(pkh) => {
-            use bitcoin::hashes::{hash160, Hash};
-            let derived = hash160::Hash::hash(pk_bytes);
-            let derived_bytes: &[u8] = derived.as_ref();
-            let pkh_bytes: &[u8] = pkh.as_ref();
-            if derived_bytes != pkh_bytes {
-                anyhow::bail!("address mismatch");
-            }
-        }
-        Payload::WitnessProgram(wp) => {
-            // Support only native segwit v0 P2WPKH (program = HASH160(compressed pubkey))
-            if wp.version().to_num() != 0 || wp.program().len() != 20 {
-                anyhow::bail!("unsupported witness program for signing");
-            }
-            if !is_compressed {
-                anyhow::bail!("segwit signature must use compressed key");
-            }
-            use bitcoin::hashes::{hash160, Hash};
-            let derived = hash160::Hash::hash(&pk_compressed);
-            let derived_bytes: &[u8] = derived.as_ref();
-            if wp.program().as_bytes() != derived_bytes {
-                anyhow::bail!("address mismatch");
-            }
-        }
-        _ => anyhow::bail!("unsupported address type for signing"),
-    }
-
-    // 5. Network must be mainnet (adjust if you later support testnet) ------
-    if addr.network != Network::Bitcoin {
-        anyhow::bail!("wrong network");
-    }
-    Ok(())
+    let mirror = data.repo.create_matrix_mirror(new, &auth.0.sub).await?;
+    log::info!(
+        "matrix mirror {} of {target} into {} added by {}",
+        mirror.id,
+        mirror.room_id,
+        auth.0.sub
+    );
+    Ok(HttpResponse::Created().json(mirror))
 }
 
-async fn fetch_btc_balance_sats(address: &str) -> anyhow::Result<u64> {
-    // Test override (avoids network) ----------------------------------------
-    if let Some(sats) = debug_balance_override() {
-        return Ok(sats);
-    }
-    // Blockstream API (no key) fallback to BlockCypher
-    let client = reqwest::Client::builder()
-        .connect_timeout(std::time::Duration::from_secs(3))
-        .timeout(std::time::Duration::from_secs(10))
-        .build()?;
-    // Allow overriding Blockstream base for tests (defaults to production endpoint)
-    let blockstream_base = std::env::var("BTC_BLOCKSTREAM_API_BASE")
-        .unwrap_or_else(|_| "https://blockstream.info/api".to_string());
-    // Try Blockstream first
-    if let Ok(r) = client
-        .get(format!(
-            "{}/address/{}/utxo",
-            blockstream_base.trim_end_matches('/'),
-            address
-        ))
-        .send()
-        .await
-    {
-        if r.status().is_success() {
-            let utxos: serde_json::Value = r.json().await?;
-            let mut total: u64 = 0;
-            if let Some(arr) = utxos.as_array() {
-                for utxo in arr {
-                    let confirmed = utxo
-                        .get("status")
-                        .and_then(|status| status.get("confirmed"))
-                        .and_then(serde_json::Value::as_bool)
-                        .unwrap_or(false);
-                    if confirmed {
-                        if let Some(value) = utxo.get("value").and_then(serde_json::Value::as_u64) {
-                            total = total.saturating_add(value);
-                        }
-                    }
-                }
-            }
-            return Ok(total);
-        }
-    }
-    // Fallback BlockCypher
-    #[derive(serde::Deserialize)]
-    struct BalanceResp {
-        balance: u64,
-    }
-    let resp = client
-        .get(format!(
-            "https://api.blockcypher.com/v1/btc/main/addrs/{}/balance",
-            address
-        ))
-        .send()
+#[utoipa::path(
+    patch,
+    path = "/api/v1/admin/matrix-mirrors/{id}",
+    params(("id" = Id, Path, description = "Mirror id")),
+    request_body = UpdateMatrixMirror,
+    responses(
+        (status = 200, description = "Mirror updated", body = MatrixMirror),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Mirror not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_matrix_mirror(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+    payload: web::Json<UpdateMatrixMirror>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let mirror = data
+        .repo
+        .set_matrix_mirror_enabled(path.into_inner(), payload.enabled)
         .await?;
-    if !resp.status().is_success() {
-        anyhow::bail!("balance api fail");
-    }
-    let b: BalanceResp = resp.json().await?;
-    Ok(b.balance)
+    log::info!(
+        "matrix mirror {} {} by {}",
+        mirror.id,
+        if mirror.enabled {
+            "enabled"
+        } else {
+            "disabled"
+        },
+        auth.0.sub
+    );
+    Ok(HttpResponse::Ok().json(mirror))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/matrix-mirrors/{id}",
+    params(("id" = Id, Path, description = "Mirror id")),
+    responses(
+        (status = 204, description = "Mirror removed; events already in the room stay"),
+        (status = 401, description = "Sign-in required"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Mirror not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_matrix_mirror(
+    auth: Auth,
+    data: web::Data<AppState>,
+    path: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    ensure_admin!(auth);
+    let id = path.into_inner();
+    data.repo.delete_matrix_mirror(id).await?;
+    log::info!("matrix mirror {id} removed by {}", auth.0.sub);
+    Ok(HttpResponse::NoContent().finish())
 }
 // -----------------------------------------------------------------
 
@@ -1819,9 +6831,10 @@ fn debug_balance_override() -> Option<u64> {
 #[cfg(test)]
 mod tests {
     use super::{
-        derive_public_identity, detect_upload_mime, discord_admission_role, is_inline_preview_mime,
-        is_valid_subject_key, role_subject_key, trusted_forwarded_ip, validate_board_fields,
-        validate_reply_payload, validate_thread_payload,
+        derive_public_identity, detect_upload_mime, discord_admission_role, hash_delete_password,
+        is_inline_preview_mime, is_valid_subject_key, normalize_email, parse_ban_network,
+        role_subject_key, trusted_forwarded_ip, validate_board_fields, validate_reply_payload,
+        validate_thread_payload, verify_delete_password,
     };
     use crate::auth::Role;
     use crate::models::{NewReply, NewThread};
@@ -1914,6 +6927,17 @@ mod tests {
         assert!(derive_public_identity(Some("a".repeat(41)), None).is_err());
     }
 
+    #[test]
+    fn delete_passwords_are_salted_and_verified() {
+        let first = hash_delete_password("hunter22").expect("hash");
+        let second = hash_delete_password("hunter22").expect("hash");
+        assert_ne!(first, second);
+        assert!(verify_delete_password("hunter22", &first));
+        assert!(!verify_delete_password("hunter23", &first));
+        assert!(!verify_delete_password("hunter22", "not a hash"));
+        assert!(hash_delete_password("abc").is_err());
+    }
+
     #[test]
     fn content_payload_validation_enforces_lengths_and_attachment_pairs() {
         let valid_thread = NewThread {
@@ -1924,6 +6948,8 @@ mod tests {
             mime: None,
             author_name: None,
             tripcode_password: None,
+            delete_password: None,
+            tags: Vec::new(),
         };
         assert!(validate_thread_payload(&valid_thread).is_ok());
         assert!(validate_thread_payload(&NewThread {
@@ -1939,6 +6965,7 @@ mod tests {
             mime: None,
             author_name: None,
             tripcode_password: None,
+            delete_password: None,
         };
         assert!(validate_reply_payload(&valid_reply).is_ok());
         assert!(validate_reply_payload(&NewReply {
@@ -1954,10 +6981,33 @@ mod tests {
         assert!(validate_board_fields("Bad Slug", "Title").is_err());
         assert!(is_valid_subject_key("discord:123456"));
         assert!(is_valid_subject_key("btc:bc1qexample"));
+        assert!(is_valid_subject_key(
+            "eth:0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+        ));
+        assert!(is_valid_subject_key(
+            "nostr:npub10elfcs4fr0l0r8af98jlmgdh9c8tcxjvz9qkw038js35mp4dma8qzvjptg"
+        ));
+        assert!(is_valid_subject_key("email:0123abcd"));
         assert!(!is_valid_subject_key("discord:"));
         assert!(!is_valid_subject_key("other:value"));
     }
 
+    #[test]
+    fn email_addresses_are_normalized_before_hashing() {
+        assert_eq!(
+            normalize_email("  Someone@Example.ORG ").as_deref(),
+            Some("someone@example.org")
+        );
+        assert_eq!(normalize_email("no-at-sign.example.org"), None);
+        assert_eq!(normalize_email("a@localhost"), None);
+        assert_eq!(normalize_email("a b@example.org"), None);
+        assert_eq!(normalize_email("a@b@example.org"), None);
+        assert_eq!(
+            role_subject_key("email:0123abcd"),
+            Some("email:0123abcd".to_string())
+        );
+    }
+
     #[test]
     fn forwarded_ip_uses_validated_chain_from_the_right() {
         assert_eq!(
@@ -1970,4 +7020,19 @@ mod tests {
         );
         assert_eq!(trusted_forwarded_ip("spoofed", 0), None);
     }
+
+    #[test]
+    fn ban_networks_are_addresses_or_ranges() {
+        assert_eq!(
+            parse_ban_network("203.0.113.7").as_deref(),
+            Some("203.0.113.7/32")
+        );
+        assert_eq!(
+            parse_ban_network("2001:db8::/32").as_deref(),
+            Some("2001:db8::/32")
+        );
+        assert_eq!(parse_ban_network("203.0.113.0/33"), None);
+        assert_eq!(parse_ban_network("203.0.113/24"), None);
+        assert_eq!(parse_ban_network("example.org"), None);
+    }
 }
@@ -0,0 +1,91 @@
+//! Saved searches and their matcher.
+//!
+//! Signed-in users save queries under `/api/v1/users/me/saved-searches`.
+//! This runner evaluates each query only against threads and replies posted
+//! since its previous pass and records the matches, which the email digest
+//! then delivers to subscribers with a confirmed address.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::repo::Repo;
+
+/// Saved searches a subject may keep.
+pub const MAX_SAVED_SEARCHES: usize = 20;
+/// Longest saved query, in characters.
+pub const MAX_QUERY_CHARS: usize = 200;
+
+#[derive(Clone, Debug)]
+pub struct SavedSearchConfig {
+    pub enabled: bool,
+    pub poll_interval: Duration,
+    /// Searches evaluated per poll at most.
+    pub batch_size: i64,
+    /// Age a post must reach before it is matched, so posts still being
+    /// committed are not skipped.
+    pub settle: Duration,
+}
+
+impl SavedSearchConfig {
+    pub fn from_env() -> Self {
+        fn u64_env(name: &str, default: u64) -> u64 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        Self {
+            enabled: std::env::var("SAVED_SEARCHES_ENABLED")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(true),
+            poll_interval: Duration::from_secs(u64_env("SAVED_SEARCH_POLL_SECS", 60).max(1)),
+            batch_size: u64_env("SAVED_SEARCH_BATCH_SIZE", 200).max(1) as i64,
+            settle: Duration::from_secs(u64_env("SAVED_SEARCH_SETTLE_SECS", 5)),
+        }
+    }
+}
+
+/// Polls for new posts and records saved search matches.
+#[derive(Clone)]
+pub struct SavedSearchMatcher {
+    repo: Arc<dyn Repo>,
+    cfg: SavedSearchConfig,
+}
+
+impl SavedSearchMatcher {
+    pub fn new(repo: Arc<dyn Repo>, cfg: SavedSearchConfig) -> Self {
+        Self { repo, cfg }
+    }
+
+    /// Evaluate searches with unseen posts up to the batch size; returns the
+    /// matches recorded.
+    pub async fn run_once(&self) -> u64 {
+        match self
+            .repo
+            .match_saved_searches(self.cfg.batch_size, self.cfg.settle.as_secs() as i64)
+            .await
+        {
+            Ok(matched) => {
+                metrics::counter!("saved_search_matches", matched);
+                matched
+            }
+            Err(e) => {
+                metrics::increment_counter!("saved_search_runs_failed");
+                log::error!("saved search run failed: {e}");
+                0
+            }
+        }
+    }
+
+    /// Spawn the polling loop on the current runtime.
+    pub fn spawn(self) {
+        crate::system::job_started("saved_searches", self.cfg.poll_interval);
+        actix_web::rt::spawn(async move {
+            loop {
+                self.run_once().await;
+                crate::system::job_ran("saved_searches");
+                tokio::time::sleep(self.cfg.poll_interval).await;
+            }
+        });
+    }
+}
@@ -0,0 +1,86 @@
+//! Threads staff schedule ahead of time, such as a weekly general.
+//!
+//! Admins queue them through `/api/v1/admin/scheduled-threads`; this runner
+//! polls for due entries and posts them through the normal thread insert, so
+//! page limits and outbox events apply as for any other thread.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::repo::Repo;
+
+/// Longest a schedule may repeat or be set ahead, in days.
+pub const MAX_SCHEDULE_DAYS: i64 = 365;
+
+#[derive(Clone, Debug)]
+pub struct ScheduleConfig {
+    pub enabled: bool,
+    pub poll_interval: Duration,
+    /// Threads posted per poll at most.
+    pub batch_size: i64,
+}
+
+impl ScheduleConfig {
+    pub fn from_env() -> Self {
+        fn u64_env(name: &str, default: u64) -> u64 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        Self {
+            enabled: std::env::var("SCHEDULED_THREADS_ENABLED")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(true),
+            poll_interval: Duration::from_secs(u64_env("SCHEDULED_THREADS_POLL_SECS", 30).max(1)),
+            batch_size: u64_env("SCHEDULED_THREADS_BATCH_SIZE", 20).max(1) as i64,
+        }
+    }
+}
+
+/// Polls for due scheduled threads and posts them.
+#[derive(Clone)]
+pub struct ScheduledThreadRunner {
+    repo: Arc<dyn Repo>,
+    cfg: ScheduleConfig,
+}
+
+impl ScheduledThreadRunner {
+    pub fn new(repo: Arc<dyn Repo>, cfg: ScheduleConfig) -> Self {
+        Self { repo, cfg }
+    }
+
+    /// Post every due thread up to the batch size; returns how many were posted.
+    pub async fn run_once(&self) -> usize {
+        match self.repo.publish_due_threads(self.cfg.batch_size).await {
+            Ok(threads) => {
+                for thread in &threads {
+                    log::info!(
+                        "scheduled thread {} posted on board {}",
+                        thread.id,
+                        thread.board_id
+                    );
+                }
+                metrics::counter!("scheduled_threads_posted", threads.len() as u64);
+                threads.len()
+            }
+            Err(e) => {
+                metrics::increment_counter!("scheduled_threads_failed");
+                log::error!("scheduled thread run failed: {e}");
+                0
+            }
+        }
+    }
+
+    /// Spawn the polling loop on the current runtime.
+    pub fn spawn(self) {
+        crate::system::job_started("scheduled_threads", self.cfg.poll_interval);
+        actix_web::rt::spawn(async move {
+            loop {
+                self.run_once().await;
+                crate::system::job_ran("scheduled_threads");
+                tokio::time::sleep(self.cfg.poll_interval).await;
+            }
+        });
+    }
+}
@@ -0,0 +1,378 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::models::{Id, OutboxEvent, Reply, SearchHit, Thread};
+use crate::outbox::{events, EventSink};
+use crate::repo::{Repo, RepoError};
+
+/// Flattened post representation mirrored into external search engines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchDocument {
+    pub id: String, // "<kind>-<post id>", unique across threads and replies
+    pub kind: String,
+    pub post_id: Id,
+    pub thread_id: Id,
+    pub board_id: Id,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SearchDocument {
+    pub fn from_thread(thread: &Thread) -> Self {
+        Self {
+            id: document_id("thread", thread.id),
+            kind: "thread".into(),
+            post_id: thread.id,
+            thread_id: thread.id,
+            board_id: thread.board_id,
+            text: format!("{} {}", thread.subject, thread.body),
+            created_at: thread.created_at,
+        }
+    }
+
+    pub fn from_reply(reply: &Reply, board_id: Id) -> Self {
+        Self {
+            id: document_id("reply", reply.id),
+            kind: "reply".into(),
+            post_id: reply.id,
+            thread_id: reply.thread_id,
+            board_id,
+            text: reply.content.clone(),
+            created_at: reply.created_at,
+        }
+    }
+
+    fn into_hit(self) -> SearchHit {
+        SearchHit {
+            kind: self.kind,
+            id: self.post_id,
+            thread_id: self.thread_id,
+            board_id: self.board_id,
+            excerpt: self.text.chars().take(200).collect(),
+            created_at: self.created_at,
+        }
+    }
+}
+
+fn document_id(kind: &str, id: Id) -> String {
+    format!("{kind}-{id}")
+}
+
+/// External full-text engine kept in sync through the outbox.
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn index(&self, doc: &SearchDocument) -> anyhow::Result<()>;
+    async fn remove(&self, kind: &str, id: Id) -> anyhow::Result<()>;
+    async fn search(
+        &self,
+        query: &str,
+        board_id: Option<Id>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<SearchHit>>;
+}
+
+fn http_client() -> anyhow::Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(3))
+        .timeout(Duration::from_secs(10))
+        .build()?)
+}
+
+fn ensure_success(response: reqwest::Response) -> anyhow::Result<reqwest::Response> {
+    if !response.status().is_success() {
+        anyhow::bail!("search backend responded with {}", response.status());
+    }
+    Ok(response)
+}
+
+// ---------------- Meilisearch ----------------
+pub struct MeilisearchBackend {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    index: String,
+}
+
+impl MeilisearchBackend {
+    pub fn new(base_url: &str, api_key: Option<String>, index: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            index: index.to_string(),
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(
+            method,
+            format!("{}/indexes/{}{}", self.base_url, self.index, path),
+        );
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+}
+
+#[async_trait]
+impl SearchBackend for MeilisearchBackend {
+    fn name(&self) -> &'static str {
+        "meilisearch"
+    }
+
+    async fn index(&self, doc: &SearchDocument) -> anyhow::Result<()> {
+        let response = self
+            .request(reqwest::Method::POST, "/documents?primaryKey=id")
+            .json(&[doc])
+            .send()
+            .await?;
+        ensure_success(response)?;
+        Ok(())
+    }
+
+    async fn remove(&self, kind: &str, id: Id) -> anyhow::Result<()> {
+        let path = format!("/documents/{}", document_id(kind, id));
+        let response = self.request(reqwest::Method::DELETE, &path).send().await?;
+        ensure_success(response)?;
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        board_id: Option<Id>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<SearchHit>> {
+        #[derive(Deserialize)]
+        struct MeiliResponse {
+            hits: Vec<SearchDocument>,
+        }
+        let mut body = serde_json::json!({ "q": query, "limit": limit });
+        if let Some(board_id) = board_id {
+            body["filter"] = serde_json::json!(format!("board_id = {board_id}"));
+        }
+        let response = self
+            .request(reqwest::Method::POST, "/search")
+            .json(&body)
+            .send()
+            .await?;
+        let parsed: MeiliResponse = ensure_success(response)?.json().await?;
+        Ok(parsed
+            .hits
+            .into_iter()
+            .map(SearchDocument::into_hit)
+            .collect())
+    }
+}
+
+// ---------------- Elasticsearch ----------------
+pub struct ElasticsearchBackend {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    index: String,
+}
+
+impl ElasticsearchBackend {
+    pub fn new(base_url: &str, api_key: Option<String>, index: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            index: index.to_string(),
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}/{}{}", self.base_url, self.index, path));
+        match &self.api_key {
+            Some(key) => request.header(reqwest::header::AUTHORIZATION, format!("ApiKey {key}")),
+            None => request,
+        }
+    }
+}
+
+#[async_trait]
+impl SearchBackend for ElasticsearchBackend {
+    fn name(&self) -> &'static str {
+        "elasticsearch"
+    }
+
+    async fn index(&self, doc: &SearchDocument) -> anyhow::Result<()> {
+        let response = self
+            .request(reqwest::Method::PUT, &format!("/_doc/{}", doc.id))
+            .json(doc)
+            .send()
+            .await?;
+        ensure_success(response)?;
+        Ok(())
+    }
+
+    async fn remove(&self, kind: &str, id: Id) -> anyhow::Result<()> {
+        let response = self
+            .request(
+                reqwest::Method::DELETE,
+                &format!("/_doc/{}", document_id(kind, id)),
+            )
+            .send()
+            .await?;
+        // Already-absent documents are not an error for idempotent redelivery.
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        ensure_success(response)?;
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        board_id: Option<Id>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<SearchHit>> {
+        #[derive(Deserialize)]
+        struct Hit {
+            _source: SearchDocument,
+        }
+        #[derive(Deserialize)]
+        struct Hits {
+            hits: Vec<Hit>,
+        }
+        #[derive(Deserialize)]
+        struct EsResponse {
+            hits: Hits,
+        }
+        let filter: Vec<serde_json::Value> = board_id
+            .map(|id| serde_json::json!({ "term": { "board_id": id } }))
+            .into_iter()
+            .collect();
+        let body = serde_json::json!({
+            "size": limit,
+            "query": {
+                "bool": {
+                    "must": { "match": { "text": query } },
+                    "filter": filter,
+                }
+            }
+        });
+        let response = self
+            .request(reqwest::Method::POST, "/_search")
+            .json(&body)
+            .send()
+            .await?;
+        let parsed: EsResponse = ensure_success(response)?.json().await?;
+        Ok(parsed
+            .hits
+            .hits
+            .into_iter()
+            .map(|hit| hit._source.into_hit())
+            .collect())
+    }
+}
+
+/// Search backend settings derived from env.
+#[derive(Clone, Debug, Default)]
+pub struct SearchConfig {
+    pub backend: Option<String>,
+    pub url: Option<String>,
+    pub api_key: Option<String>,
+    pub index: String,
+}
+
+impl SearchConfig {
+    pub fn from_env() -> Self {
+        fn opt_env(name: &str) -> Option<String> {
+            std::env::var(name).ok().filter(|v| !v.trim().is_empty())
+        }
+        Self {
+            backend: opt_env("SEARCH_BACKEND").map(|v| v.to_lowercase()),
+            url: opt_env("SEARCH_URL"),
+            api_key: opt_env("SEARCH_API_KEY"),
+            index: opt_env("SEARCH_INDEX").unwrap_or_else(|| "rib-posts".into()),
+        }
+    }
+
+    /// Returns `None` when no external backend is configured (Postgres FTS only).
+    pub fn build(&self) -> anyhow::Result<Option<Arc<dyn SearchBackend>>> {
+        let Some(backend) = self.backend.as_deref() else {
+            return Ok(None);
+        };
+        let url = self
+            .url
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("SEARCH_URL must be set when SEARCH_BACKEND is"))?;
+        let backend: Arc<dyn SearchBackend> = match backend {
+            "meilisearch" => Arc::new(MeilisearchBackend::new(
+                url,
+                self.api_key.clone(),
+                &self.index,
+            )?),
+            "elasticsearch" => Arc::new(ElasticsearchBackend::new(
+                url,
+                self.api_key.clone(),
+                &self.index,
+            )?),
+            other => anyhow::bail!("unsupported SEARCH_BACKEND '{other}'"),
+        };
+        Ok(Some(backend))
+    }
+}
+
+/// Outbox sink mirroring post lifecycle events into a search backend.
+pub struct SearchIndexSink {
+    repo: Arc<dyn Repo>,
+    backend: Arc<dyn SearchBackend>,
+}
+
+impl SearchIndexSink {
+    pub fn new(repo: Arc<dyn Repo>, backend: Arc<dyn SearchBackend>) -> Self {
+        Self { repo, backend }
+    }
+}
+
+#[async_trait]
+impl EventSink for SearchIndexSink {
+    fn name(&self) -> &'static str {
+        "search"
+    }
+
+    async fn deliver(&self, event: &OutboxEvent) -> anyhow::Result<()> {
+        let id_field = |name: &str| {
+            event.payload[name]
+                .as_i64()
+                .ok_or_else(|| anyhow::anyhow!("event {} missing {name}", event.id))
+        };
+        match event.event_type.as_str() {
+            events::THREAD_CREATED => match self.repo.get_thread(id_field("thread_id")?).await {
+                Ok(thread) => {
+                    self.backend
+                        .index(&SearchDocument::from_thread(&thread))
+                        .await
+                }
+                // Hard-deleted before delivery; the matching delete event cleans up.
+                Err(RepoError::NotFound) => Ok(()),
+                Err(e) => Err(e.into()),
+            },
+            events::REPLY_CREATED => match self.repo.get_reply(id_field("reply_id")?).await {
+                Ok(reply) => {
+                    let thread = self.repo.get_thread(reply.thread_id).await?;
+                    self.backend
+                        .index(&SearchDocument::from_reply(&reply, thread.board_id))
+                        .await
+                }
+                Err(RepoError::NotFound) => Ok(()),
+                Err(e) => Err(e.into()),
+            },
+            events::THREAD_DELETED => self.backend.remove("thread", id_field("thread_id")?).await,
+            events::REPLY_DELETED => self.backend.remove("reply", id_field("reply_id")?).await,
+            _ => Ok(()),
+        }
+    }
+}
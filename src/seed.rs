@@ -0,0 +1,257 @@
+use serde_json::json;
+
+use crate::auth::Role;
+use crate::models::{NewBoard, NewReply, NewThread, PublicIdentity};
+use crate::repo::Repo;
+use crate::storage::{ImageStore, ImageStoreError};
+
+/// Slug of the first seeded board; its presence marks the database as seeded.
+pub const SEED_MARKER_BOARD: &str = "demo";
+
+struct SeedThread {
+    subject: &'static str,
+    body: &'static str,
+    author: Option<&'static str>,
+    /// Colour of the generated attachment, if any.
+    image: Option<[u8; 3]>,
+    replies: &'static [(&'static str, Option<[u8; 3]>)],
+}
+
+struct SeedBoard {
+    slug: &'static str,
+    title: &'static str,
+    threads: &'static [SeedThread],
+}
+
+const BOARDS: &[SeedBoard] = &[
+    SeedBoard {
+        slug: SEED_MARKER_BOARD,
+        title: "Demo",
+        threads: &[
+            SeedThread {
+                subject: "Welcome to rib",
+                body: "This board was created by `rib seed`. Post a reply to see live updates.",
+                author: Some("rib"),
+                image: Some([0x3b, 0x82, 0xf6]),
+                replies: &[
+                    ("First!", None),
+                    (
+                        "Replies can carry attachments too.",
+                        Some([0x10, 0xb9, 0x81]),
+                    ),
+                    (">>1\nQuote earlier posts with >>id.", None),
+                ],
+            },
+            SeedThread {
+                subject: "Formatting and tripcodes",
+                body: "Set a name with an optional #password to get a stable tripcode.",
+                author: None,
+                image: None,
+                replies: &[("Testing my tripcode", None), ("Looks good.", None)],
+            },
+        ],
+    },
+    SeedBoard {
+        slug: "tech",
+        title: "Technology",
+        threads: &[
+            SeedThread {
+                subject: "What is everyone self-hosting?",
+                body: "Share your homelab setup.",
+                author: None,
+                image: Some([0x64, 0x74, 0x8b]),
+                replies: &[
+                    ("Postgres, MinIO, and a small k8s cluster.", None),
+                    ("A single Raspberry Pi.", Some([0xdc, 0x26, 0x26])),
+                ],
+            },
+            SeedThread {
+                subject: "Rust async runtimes",
+                body: "Tokio everywhere, or are there good reasons to pick something else?",
+                author: Some("ferris"),
+                image: None,
+                replies: &[("Tokio, unless you are on embedded.", None)],
+            },
+        ],
+    },
+    SeedBoard {
+        slug: "art",
+        title: "Art & Design",
+        threads: &[SeedThread {
+            subject: "Colour palettes",
+            body: "Post a swatch you like.",
+            author: None,
+            image: Some([0xf5, 0x9e, 0x0b]),
+            replies: &[
+                ("Warm tones.", Some([0xef, 0x44, 0x44])),
+                ("Cool tones.", Some([0x06, 0xb6, 0xd4])),
+                ("Monochrome.", Some([0x52, 0x52, 0x52])),
+            ],
+        }],
+    },
+];
+
+/// Role assignments for local testing with matching `discord:` subjects.
+const ROLES: &[(&str, Role)] = &[
+    ("discord:demo-admin", Role::Admin),
+    ("discord:demo-moderator", Role::Moderator),
+    ("discord:demo-user", Role::User),
+];
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SeedSummary {
+    /// False when the marker board already existed and nothing was written.
+    pub seeded: bool,
+    pub boards: usize,
+    pub threads: usize,
+    pub replies: usize,
+    pub images: usize,
+    pub roles: usize,
+}
+
+/// 64x64 24-bit BMP with a diagonal gradient towards `rgb`; deterministic so
+/// reseeding yields identical hashes.
+pub fn swatch_bmp(rgb: [u8; 3]) -> Vec<u8> {
+    const SIDE: u32 = 64;
+    let row = SIDE * 3; // already 4-byte aligned
+    let pixels = row * SIDE;
+    let mut bmp = Vec::with_capacity(54 + pixels as usize);
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&(54 + pixels).to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&54u32.to_le_bytes());
+    bmp.extend_from_slice(&40u32.to_le_bytes());
+    bmp.extend_from_slice(&(SIDE as i32).to_le_bytes());
+    bmp.extend_from_slice(&(SIDE as i32).to_le_bytes());
+    bmp.extend_from_slice(&1u16.to_le_bytes());
+    bmp.extend_from_slice(&24u16.to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]); // BI_RGB
+    bmp.extend_from_slice(&pixels.to_le_bytes());
+    bmp.extend_from_slice(&[0; 16]); // resolution and palette counts
+    for y in 0..SIDE {
+        for x in 0..SIDE {
+            let shade = 128 + (x + y) as u16;
+            for channel in [rgb[2], rgb[1], rgb[0]] {
+                bmp.push((channel as u16 * shade / 255).min(255) as u8);
+            }
+        }
+    }
+    bmp
+}
+
+async fn store_swatch(store: &dyn ImageStore, rgb: [u8; 3]) -> anyhow::Result<(String, String)> {
+    use sha2::{Digest, Sha256};
+    let bytes = swatch_bmp(rgb);
+    let hash = format!("{:x}", Sha256::digest(&bytes));
+    let mime = "image/bmp".to_string();
+    match store.save(&hash, &mime, &bytes).await {
+        Ok(()) | Err(ImageStoreError::Duplicate) => Ok((hash, mime)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Populate demo boards, threads with attachments, replies, and roles.
+///
+/// Idempotent: does nothing when the `demo` board already exists, so it is
+/// safe to leave `SEED_DEMO_DATA` enabled across restarts.
+pub async fn seed_demo_data(
+    repo: &dyn Repo,
+    store: &dyn ImageStore,
+) -> anyhow::Result<SeedSummary> {
+    let mut summary = SeedSummary::default();
+    let existing = repo.list_boards(true).await?;
+    if existing.iter().any(|b| b.slug == SEED_MARKER_BOARD) {
+        return Ok(summary);
+    }
+    summary.seeded = true;
+    let created_by = json!({"provider": "seed", "subject": "seed:demo"});
+    let identity = |author: Option<&str>| PublicIdentity {
+        author_name: author.map(str::to_string),
+        tripcode: None,
+    };
+    for spec in BOARDS {
+        let board = match existing.iter().find(|b| b.slug == spec.slug) {
+            Some(board) => board.clone(),
+            None => {
+                summary.boards += 1;
+                repo.create_board(NewBoard {
+                    slug: spec.slug.into(),
+                    title: spec.title.into(),
+                })
+                .await?
+            }
+        };
+        for t in spec.threads {
+            let attachment = match t.image {
+                Some(rgb) => {
+                    summary.images += 1;
+                    Some(store_swatch(store, rgb).await?)
+                }
+                None => None,
+            };
+            let (image_hash, mime) = attachment.unzip();
+            let thread = repo
+                .create_thread(
+                    NewThread {
+                        board_id: board.id,
+                        subject: t.subject.into(),
+                        body: t.body.into(),
+                        image_hash,
+                        mime,
+                        author_name: None,
+                        tripcode_password: None,
+                        delete_password: None,
+                        tags: Vec::new(),
+                    },
+                    created_by.clone(),
+                    identity(t.author),
+                )
+                .await?;
+            summary.threads += 1;
+            for (content, image) in t.replies {
+                let attachment = match image {
+                    Some(rgb) => {
+                        summary.images += 1;
+                        Some(store_swatch(store, *rgb).await?)
+                    }
+                    None => None,
+                };
+                let (image_hash, mime) = attachment.unzip();
+                repo.create_reply(
+                    NewReply {
+                        thread_id: thread.id,
+                        content: (*content).into(),
+                        image_hash,
+                        mime,
+                        author_name: None,
+                        tripcode_password: None,
+                        delete_password: None,
+                    },
+                    created_by.clone(),
+                    identity(None),
+                )
+                .await?;
+                summary.replies += 1;
+            }
+        }
+    }
+    for (subject, role) in ROLES {
+        repo.set_subject_role(subject, role.clone()).await?;
+        summary.roles += 1;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swatch_is_a_deterministic_bmp() {
+        let a = swatch_bmp([1, 2, 3]);
+        assert_eq!(a, swatch_bmp([1, 2, 3]));
+        assert_ne!(a, swatch_bmp([3, 2, 1]));
+        assert_eq!(infer::get(&a).map(|k| k.mime_type()), Some("image/bmp"));
+        assert_eq!(a.len(), 54 + 64 * 64 * 3);
+    }
+}
@@ -0,0 +1,1128 @@
+use std::collections::{HashMap, HashSet};
+
+use futures_util::{StreamExt, TryStreamExt};
+
+use crate::auth::{Auth, Role};
+use crate::beta;
+use crate::duplicates::Claim;
+use crate::error::ApiError;
+use crate::features::Feature;
+use crate::models::*;
+use crate::repo::{transaction, RepoError, RepoTx, RowStream};
+use crate::routes::{
+    anonymous_author_attribution, derive_public_identity, ensure_ip_not_banned,
+    ensure_subject_can_post, ensure_subject_not_banned, hash_delete_password,
+    private_author_attribution, validate_board_fields, validate_reply_payload,
+    validate_thread_payload, verify_delete_password, AppState, SearchResults,
+};
+use crate::trust::Friction;
+
+fn ensure_can_post(auth: &Auth) -> Result<(), ApiError> {
+    if !auth
+        .0
+        .roles
+        .iter()
+        .any(|r| matches!(r, Role::User | Role::Moderator | Role::Admin))
+    {
+        return Err(ApiError::Forbidden);
+    }
+    Ok(())
+}
+
+/// `include_deleted` must already be restricted to admins by the caller.
+/// Beta boards are left out unless `viewer` may see them.
+pub async fn list_boards(
+    data: &AppState,
+    include_deleted: bool,
+    viewer: Option<&Auth>,
+) -> Result<Vec<Board>, ApiError> {
+    if include_deleted {
+        return Ok(data.repo.list_boards(true).await?);
+    }
+    beta::retain_accessible(data, cached_boards(data).await?, viewer).await
+}
+
+/// Boards that are not soft-deleted, beta ones included, from the cache.
+/// Reloads read the primary: they follow an invalidation at once, before a
+/// replica may have caught up with the change.
+async fn cached_boards(data: &AppState) -> Result<Vec<Board>, ApiError> {
+    if let Some(boards) = data.board_cache.get() {
+        return Ok(boards);
+    }
+    let generation = data.board_cache.generation();
+    let boards = data.repo.list_boards_from_primary().await?;
+    data.board_cache.store(generation, boards.clone());
+    Ok(boards)
+}
+
+/// Beta boards `viewer` may not see.
+pub async fn hidden_beta_boards(
+    data: &AppState,
+    viewer: Option<&Auth>,
+) -> Result<Vec<Id>, ApiError> {
+    let boards = cached_boards(data).await?;
+    let beta: Vec<Id> = boards.iter().filter(|b| b.beta).map(|b| b.id).collect();
+    if beta.is_empty() {
+        return Ok(beta);
+    }
+    let visible: HashSet<Id> = beta::retain_accessible(data, boards, viewer)
+        .await?
+        .into_iter()
+        .map(|b| b.id)
+        .collect();
+    Ok(beta
+        .into_iter()
+        .filter(|id| !visible.contains(id))
+        .collect())
+}
+
+/// Whether a live event may go to a subscriber who cannot see the `hidden`
+/// boards. Events about posts that no longer exist pass; an event whose
+/// board cannot be looked up otherwise is withheld.
+pub async fn live_event_visible(data: &AppState, event: &OutboxEvent, hidden: &[Id]) -> bool {
+    if hidden.is_empty() {
+        return true;
+    }
+    let payload = &event.payload;
+    let board_id = if let Some(id) = payload["board_id"].as_i64() {
+        Ok(id)
+    } else if let Some(id) = payload["thread_id"].as_i64() {
+        data.repo.get_thread(id).await.map(|t| t.board_id)
+    } else if let Some(id) = payload["reply_id"].as_i64() {
+        match data.repo.get_reply(id).await {
+            Ok(reply) => data
+                .repo
+                .get_thread(reply.thread_id)
+                .await
+                .map(|t| t.board_id),
+            Err(e) => Err(e),
+        }
+    } else {
+        return true;
+    };
+    match board_id {
+        Ok(id) => !hidden.contains(&id),
+        Err(RepoError::NotFound) => true,
+        Err(_) => false,
+    }
+}
+
+pub async fn get_board(
+    data: &AppState,
+    id: Id,
+    include_deleted: bool,
+    viewer: Option<&Auth>,
+) -> Result<Board, ApiError> {
+    let board = data.repo.get_board(id).await?;
+    if board.deleted_at.is_some() && !include_deleted {
+        return Err(ApiError::NotFound);
+    }
+    beta::ensure_access(data, &board, viewer).await?;
+    Ok(board)
+}
+
+pub async fn create_board(data: &AppState, auth: &Auth, new: NewBoard) -> Result<Board, ApiError> {
+    if !auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
+        return Err(ApiError::Forbidden);
+    }
+    data.features.current().ensure_enabled(Feature::NewBoards)?;
+    let mut new = new;
+    new.slug = new.slug.trim().to_string();
+    new.title = new.title.trim().to_string();
+    validate_board_fields(&new.slug, &new.title)?;
+    Ok(data.repo.create_board(new).await?)
+}
+
+/// Threads of a visible board in `order`.
+pub async fn list_threads(
+    data: &AppState,
+    board_id: Id,
+    include_deleted: bool,
+    order: ThreadOrder,
+    viewer: Option<&Auth>,
+) -> Result<Vec<Thread>, ApiError> {
+    let board = data
+        .repo
+        .get_board(board_id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    if board.deleted_at.is_some() && !include_deleted {
+        return Err(ApiError::NotFound);
+    }
+    beta::ensure_access(data, &board, viewer).await?;
+    Ok(data
+        .repo
+        .list_threads(board_id, include_deleted, order)
+        .await?)
+}
+
+/// Threads of a visible board in `order`, streamed.
+pub async fn stream_threads(
+    data: &AppState,
+    board_id: Id,
+    include_deleted: bool,
+    order: ThreadOrder,
+    viewer: Option<&Auth>,
+) -> Result<RowStream<Thread>, ApiError> {
+    let board = data
+        .repo
+        .get_board(board_id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    if board.deleted_at.is_some() && !include_deleted {
+        return Err(ApiError::NotFound);
+    }
+    beta::ensure_access(data, &board, viewer).await?;
+    Ok(data.repo.stream_threads(board_id, include_deleted, order))
+}
+
+/// Threads of a board carrying `tag`; a malformed tag matches none.
+pub async fn list_tagged_threads(
+    data: &AppState,
+    board_id: Id,
+    tag: &str,
+    include_deleted: bool,
+    order: ThreadOrder,
+    viewer: Option<&Auth>,
+) -> Result<Vec<Thread>, ApiError> {
+    let board = data
+        .repo
+        .get_board(board_id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    if board.deleted_at.is_some() && !include_deleted {
+        return Err(ApiError::NotFound);
+    }
+    beta::ensure_access(data, &board, viewer).await?;
+    let Some(tag) = crate::tags::normalize(tag) else {
+        return Ok(Vec::new());
+    };
+    Ok(data
+        .repo
+        .list_tagged_threads(board_id, &tag, include_deleted, order)
+        .await?)
+}
+
+/// Tag usage on a visible board's active threads, most used first; tags in
+/// the board's vocabulary that no thread carries yet are listed with zero.
+pub async fn tag_counts(
+    data: &AppState,
+    board_id: Id,
+    viewer: Option<&Auth>,
+) -> Result<Vec<TagCount>, ApiError> {
+    let board = data
+        .repo
+        .get_board(board_id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    if board.deleted_at.is_some() {
+        return Err(ApiError::NotFound);
+    }
+    beta::ensure_access(data, &board, viewer).await?;
+    let mut counts = data.repo.tag_counts(board_id).await?;
+    for tag in board.tag_vocabulary {
+        if !counts.iter().any(|count| count.tag == tag) {
+            counts.push(TagCount { tag, count: 0 });
+        }
+    }
+    Ok(counts)
+}
+
+/// Archived threads of a visible board, most recently archived first.
+pub async fn list_archived_threads(
+    data: &AppState,
+    board_id: Id,
+    limit: i64,
+    viewer: Option<&Auth>,
+) -> Result<Vec<Thread>, ApiError> {
+    let board = data
+        .repo
+        .get_board(board_id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    if board.deleted_at.is_some() {
+        return Err(ApiError::NotFound);
+    }
+    beta::ensure_access(data, &board, viewer).await?;
+    Ok(data.repo.list_archived_threads(board_id, limit).await?)
+}
+
+/// Who is creating a post, as established by the transport.
+pub struct Poster<'a> {
+    /// `None` for anonymous posts, accepted only on boards with `anonymous_posting`.
+    pub auth: Option<&'a Auth>,
+    pub client_ip: &'a str,
+    /// Solved [`crate::pow`] challenge; required for anonymous posts.
+    pub pow: Option<&'a str>,
+}
+
+#[derive(Clone, Copy)]
+enum PostKind {
+    Thread,
+    Reply,
+}
+
+/// Friction for a poster from its trust score; staff are fully trusted.
+async fn trust_friction(data: &AppState, subject: &str, staff: bool) -> Result<Friction, ApiError> {
+    let cfg = &data.trust;
+    if !cfg.is_active() {
+        return Ok(Friction::default());
+    }
+    if staff {
+        return Ok(Friction {
+            hold: false,
+            ..cfg.friction(1.0)
+        });
+    }
+    let history = data.repo.subject_trust(subject).await?;
+    Ok(cfg.friction(cfg.score(history.as_ref(), chrono::Utc::now())))
+}
+
+/// Admission checks for signed-in posters; returns the private `created_by`
+/// attribution and the poster's friction.
+async fn admit_authenticated(
+    data: &AppState,
+    auth: &Auth,
+    client_ip: &str,
+    kind: PostKind,
+) -> Result<(serde_json::Value, Friction), ApiError> {
+    let (subject_key, created_by) = private_author_attribution(auth)?;
+    ensure_subject_can_post(data, auth, &subject_key).await?;
+    let staff = auth
+        .0
+        .roles
+        .iter()
+        .any(|r| matches!(r, Role::Moderator | Role::Admin));
+    if !staff {
+        ensure_ip_not_banned(data, client_ip).await?;
+    }
+    let friction = trust_friction(data, &subject_key, staff).await?;
+    if let Some(rl) = &data.rate_limiter {
+        let (allowed, window, action) = match kind {
+            PostKind::Thread => (
+                rl.allow_thread(client_ip, friction.rate_factor),
+                rl.config().thread_window,
+                "thread_create",
+            ),
+            PostKind::Reply => (
+                rl.allow_reply(client_ip, friction.rate_factor),
+                rl.config().reply_window,
+                "reply_create",
+            ),
+        };
+        if !allowed {
+            metrics::increment_counter!("rate_limit_denied", "action" => action);
+            return Err(ApiError::RateLimited {
+                retry_after: window.as_secs(),
+            });
+        }
+        metrics::increment_counter!("rate_limit_allowed", "action" => action);
+    }
+    ensure_can_post(auth)?;
+    Ok((created_by, friction))
+}
+
+/// Admission checks for posts without an account on `board`: the board must
+/// opt in, the client must solve a proof of work unless trusted, and the
+/// anonymous rate limits apply. Bans and trust work on the synthesized
+/// `anon:` subject.
+async fn admit_anonymous(
+    data: &AppState,
+    board: &Board,
+    client_ip: &str,
+    pow: Option<&str>,
+    kind: PostKind,
+) -> Result<(serde_json::Value, Friction), ApiError> {
+    if board.deleted_at.is_some() {
+        return Err(ApiError::NotFound);
+    }
+    beta::ensure_access(data, board, None).await?;
+    if !board.anonymous_posting {
+        return Err(ApiError::Unauthorized);
+    }
+    ensure_ip_not_banned(data, client_ip).await?;
+    let (subject_key, created_by) = anonymous_author_attribution(client_ip)?;
+    ensure_subject_not_banned(data, &subject_key).await?;
+    let friction = trust_friction(data, &subject_key, false).await?;
+    if !friction.skip_pow {
+        data.pow
+            .redeem(pow.ok_or_else(|| ApiError::Invalid("proof of work required".into()))?)
+            .await?;
+    }
+    if let Some(rl) = &data.rate_limiter {
+        let (allowed, window, action) = match kind {
+            PostKind::Thread => (
+                rl.allow_anon_thread(client_ip, friction.rate_factor),
+                rl.config().anon_thread_window,
+                "anon_thread_create",
+            ),
+            PostKind::Reply => (
+                rl.allow_anon_reply(client_ip, friction.rate_factor),
+                rl.config().anon_reply_window,
+                "anon_reply_create",
+            ),
+        };
+        if !allowed {
+            metrics::increment_counter!("rate_limit_denied", "action" => action);
+            return Err(ApiError::RateLimited {
+                retry_after: window.as_secs(),
+            });
+        }
+        metrics::increment_counter!("rate_limit_allowed", "action" => action);
+    }
+    Ok((created_by, friction))
+}
+
+pub async fn create_thread(
+    data: &AppState,
+    poster: Poster<'_>,
+    new: NewThread,
+) -> Result<Thread, ApiError> {
+    let (created_by, friction) = match poster.auth {
+        Some(auth) => admit_authenticated(data, auth, poster.client_ip, PostKind::Thread).await?,
+        None => {
+            let board = data
+                .repo
+                .get_board(new.board_id)
+                .await
+                .map_err(|_| ApiError::NotFound)?;
+            admit_anonymous(data, &board, poster.client_ip, poster.pow, PostKind::Thread).await?
+        }
+    };
+    let mut new = new;
+    new.subject = new.subject.trim().to_string();
+    new.body = new.body.trim().to_string();
+    validate_thread_payload(&new)?;
+    let board = data
+        .repo
+        .get_board(new.board_id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    if board.deleted_at.is_some() {
+        return Err(ApiError::NotFound);
+    }
+    beta::ensure_access(data, &board, poster.auth).await?;
+    ensure_attachment_allowed(data, &board, new.mime.as_deref())?;
+    new.tags =
+        crate::tags::thread_tags(&new.tags, &board.tag_vocabulary).map_err(ApiError::Invalid)?;
+    let public_identity =
+        derive_public_identity(new.author_name.take(), new.tripcode_password.take())?;
+    let claim = claim_content(data, &created_by, poster.client_ip, "thread", &new.body)?;
+    let stored = store_thread(data, new, created_by, public_identity, friction.hold).await;
+    if stored.is_err() {
+        data.duplicates.release(claim);
+    }
+    stored
+}
+
+/// Held threads are stored deleted until staff approve them.
+/// Attachments must be of a type both the deployment and the board accept.
+fn ensure_attachment_allowed(
+    data: &AppState,
+    board: &Board,
+    mime: Option<&str>,
+) -> Result<(), ApiError> {
+    match mime {
+        Some(mime) if !data.uploads.current().accepts(&board.allowed_mime, mime) => {
+            metrics::increment_counter!("attachment_type_denied");
+            Err(ApiError::Invalid(format!(
+                "{mime} attachments are not accepted on this board"
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn store_thread(
+    data: &AppState,
+    mut new: NewThread,
+    created_by: serde_json::Value,
+    public_identity: PublicIdentity,
+    hold: bool,
+) -> Result<Thread, ApiError> {
+    let delete_hash = hash_password_off_thread(new.delete_password.take()).await?;
+    if delete_hash.is_none() && !hold && new.image_hash.is_none() {
+        return Ok(data
+            .repo
+            .create_thread(new, created_by, public_identity)
+            .await?);
+    }
+    if hold {
+        metrics::increment_counter!("trust_posts_held", "kind" => "thread");
+    }
+    transaction(&*data.repo, |tx| {
+        // Owned per attempt: a rolled-back transaction runs again.
+        let (new, created_by) = (new.clone(), created_by.clone());
+        let (public_identity, delete_hash) = (public_identity.clone(), delete_hash.clone());
+        Box::pin(async move {
+            if !attachable(tx, new.image_hash.as_deref(), &created_by).await? {
+                return Ok(None);
+            }
+            let thread = tx.create_thread(new, created_by, public_identity).await?;
+            if let Some(delete_hash) = &delete_hash {
+                tx.set_thread_delete_password(thread.id, delete_hash)
+                    .await?;
+            }
+            if hold {
+                tx.hold_thread(thread.id).await?;
+                return tx.get_thread(thread.id).await.map(Some);
+            }
+            Ok(Some(thread))
+        })
+    })
+    .await?
+    .ok_or_else(attachment_not_owned)
+}
+
+/// A post may attach a blob its poster uploaded, or one an uploader shared.
+/// Checked in the post's transaction, which keeps the upload rows locked.
+async fn attachable(
+    tx: &mut dyn RepoTx,
+    hash: Option<&str>,
+    created_by: &serde_json::Value,
+) -> Result<bool, RepoError> {
+    let Some(hash) = hash else {
+        return Ok(true);
+    };
+    let subject = crate::filters::author_subject(created_by);
+    let attachable = tx.upload_attachable(hash, subject).await?;
+    if !attachable {
+        metrics::increment_counter!("attachment_ownership_denied");
+    }
+    Ok(attachable)
+}
+
+fn attachment_not_owned() -> ApiError {
+    ApiError::Invalid("attach a file you uploaded, or one its uploader shared".into())
+}
+
+/// Reject a post identical to one the same subject or client IP made within
+/// the duplicate window; the claim is released if the post is not stored.
+fn claim_content(
+    data: &AppState,
+    created_by: &serde_json::Value,
+    client_ip: &str,
+    kind: &str,
+    content: &str,
+) -> Result<Claim, ApiError> {
+    let mut posters = Vec::with_capacity(2);
+    posters.extend(crate::filters::author_subject(created_by));
+    if client_ip != "unknown" {
+        posters.push(client_ip);
+    }
+    data.duplicates.claim(kind, &posters, content)
+}
+
+/// A thread, hidden when it or its board is soft-deleted, or when its board
+/// is in beta and `viewer` may not see it.
+pub async fn get_thread(
+    data: &AppState,
+    id: Id,
+    include_deleted: bool,
+    viewer: Option<&Auth>,
+) -> Result<Thread, ApiError> {
+    let thread = data.repo.get_thread(id).await?;
+    if thread.deleted_at.is_some() && !include_deleted {
+        return Err(ApiError::NotFound);
+    }
+    let board = data.repo.get_board(thread.board_id).await?;
+    if board.deleted_at.is_some() && !include_deleted {
+        return Err(ApiError::NotFound);
+    }
+    beta::ensure_access(data, &board, viewer).await?;
+    Ok(thread)
+}
+
+/// Replies of a visible thread by creation time in `order`. On boards with
+/// `tombstones`, replies staff removed with a reason stay in place as tombstones.
+pub async fn list_replies(
+    data: &AppState,
+    thread_id: Id,
+    include_deleted: bool,
+    order: SortOrder,
+    viewer: Option<&Auth>,
+) -> Result<Vec<Reply>, ApiError> {
+    let board = reply_listing_board(data, thread_id, include_deleted, viewer).await?;
+    let replies = if board.tombstones && !include_deleted {
+        data.repo
+            .list_replies(thread_id, true, order)
+            .await?
+            .into_iter()
+            .filter_map(with_tombstone)
+            .collect()
+    } else {
+        data.repo
+            .list_replies(thread_id, include_deleted, order)
+            .await?
+    };
+    Ok(replies)
+}
+
+/// The board of a thread whose replies `viewer` may list.
+async fn reply_listing_board(
+    data: &AppState,
+    thread_id: Id,
+    include_deleted: bool,
+    viewer: Option<&Auth>,
+) -> Result<Board, ApiError> {
+    let thread = data
+        .repo
+        .get_thread(thread_id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    if thread.deleted_at.is_some() && !include_deleted {
+        return Err(ApiError::NotFound);
+    }
+    let board = data.repo.get_board(thread.board_id).await?;
+    if board.deleted_at.is_some() && !include_deleted {
+        return Err(ApiError::NotFound);
+    }
+    beta::ensure_access(data, &board, viewer).await?;
+    Ok(board)
+}
+
+/// A listed reply as shown on a board with tombstones: a tombstone when staff
+/// removed it with a reason, nothing for any other deletion.
+fn with_tombstone(reply: Reply) -> Option<Reply> {
+    match reply.deleted_at {
+        None => Some(reply),
+        // Held posts and posts their authors deleted carry no reason.
+        Some(_) => reply.removal_reason.is_some().then(|| tombstone(reply)),
+    }
+}
+
+/// A removed reply reduced to its place in the thread and the removal reason.
+fn tombstone(reply: Reply) -> Reply {
+    Reply {
+        content: String::new(),
+        image_hash: None,
+        mime: None,
+        image_size: None,
+        author_name: None,
+        tripcode: None,
+        created_by: serde_json::Value::Null,
+        author: None,
+        reactions: sqlx::types::Json(Vec::new()),
+        ..reply
+    }
+}
+
+/// Replies of a visible thread by creation time in `order`, streamed, with
+/// tombstones as in [`list_replies`].
+pub async fn stream_replies(
+    data: &AppState,
+    thread_id: Id,
+    include_deleted: bool,
+    order: SortOrder,
+    viewer: Option<&Auth>,
+) -> Result<RowStream<Reply>, ApiError> {
+    let board = reply_listing_board(data, thread_id, include_deleted, viewer).await?;
+    if board.tombstones && !include_deleted {
+        return Ok(data
+            .repo
+            .stream_replies(thread_id, true, order)
+            .try_filter_map(|reply| futures_util::future::ready(Ok(with_tombstone(reply))))
+            .boxed());
+    }
+    Ok(data.repo.stream_replies(thread_id, include_deleted, order))
+}
+
+/// A reply, hidden when it or any ancestor is soft-deleted.
+pub async fn get_reply(
+    data: &AppState,
+    id: Id,
+    include_deleted: bool,
+    viewer: Option<&Auth>,
+) -> Result<Reply, ApiError> {
+    let reply = data.repo.get_reply(id).await?;
+    if reply.deleted_at.is_some() && !include_deleted {
+        return Err(ApiError::NotFound);
+    }
+    get_thread(data, reply.thread_id, include_deleted, viewer).await?;
+    Ok(reply)
+}
+
+/// React to a visible reply with one of its board's emoji, or remove the
+/// caller's reaction with `None`. Returns the reply's reaction counts.
+pub async fn set_reaction(
+    data: &AppState,
+    auth: &Auth,
+    reply_id: Id,
+    emoji: Option<&str>,
+) -> Result<Vec<ReactionCount>, ApiError> {
+    let (subject, _) = private_author_attribution(auth)?;
+    let reply = get_reply(data, reply_id, false, Some(auth)).await?;
+    let Some(emoji) = emoji else {
+        return Ok(data.repo.clear_reaction(reply_id, &subject).await?);
+    };
+    ensure_can_post(auth)?;
+    ensure_subject_can_post(data, auth, &subject).await?;
+    let thread = data.repo.get_thread(reply.thread_id).await?;
+    let board = data.repo.get_board(thread.board_id).await?;
+    if !board.reactions.iter().any(|allowed| allowed == emoji) {
+        return Err(ApiError::Invalid(if board.reactions.is_empty() {
+            "reactions are off on this board".into()
+        } else {
+            format!("react with one of: {}", board.reactions.join(" "))
+        }));
+    }
+    Ok(data.repo.set_reaction(reply_id, &subject, emoji).await?)
+}
+
+/// Enforce the board's per-thread cooldown between one poster's replies.
+/// Moderators and admins are exempt.
+async fn ensure_reply_cooldown(
+    data: &AppState,
+    auth: Option<&Auth>,
+    thread: &Thread,
+    created_by: &serde_json::Value,
+) -> Result<(), ApiError> {
+    if auth.is_some_and(|auth| {
+        auth.0
+            .roles
+            .iter()
+            .any(|r| matches!(r, Role::Moderator | Role::Admin))
+    }) {
+        return Ok(());
+    }
+    let Some(subject) = crate::filters::author_subject(created_by) else {
+        return Ok(());
+    };
+    let board = data.repo.get_board(thread.board_id).await?;
+    if board.reply_cooldown_secs <= 0 {
+        return Ok(());
+    }
+    let Some(last) = data.repo.last_reply_at(thread.id, subject).await? else {
+        return Ok(());
+    };
+    let wait =
+        last + chrono::Duration::seconds(board.reply_cooldown_secs.into()) - chrono::Utc::now();
+    if wait > chrono::Duration::zero() {
+        metrics::increment_counter!("rate_limit_denied", "action" => "reply_cooldown");
+        return Err(ApiError::RateLimited {
+            retry_after: (wait.num_seconds() + 1) as u64,
+        });
+    }
+    Ok(())
+}
+
+pub async fn create_reply(
+    data: &AppState,
+    poster: Poster<'_>,
+    new: NewReply,
+) -> Result<Reply, ApiError> {
+    let (created_by, friction) = match poster.auth {
+        Some(auth) => admit_authenticated(data, auth, poster.client_ip, PostKind::Reply).await?,
+        None => {
+            let thread = data
+                .repo
+                .get_thread(new.thread_id)
+                .await
+                .map_err(|_| ApiError::NotFound)?;
+            if thread.deleted_at.is_some() {
+                return Err(ApiError::NotFound);
+            }
+            let board = data.repo.get_board(thread.board_id).await?;
+            admit_anonymous(data, &board, poster.client_ip, poster.pow, PostKind::Reply).await?
+        }
+    };
+    let mut new = new;
+    new.content = new.content.trim().to_string();
+    validate_reply_payload(&new)?;
+    let thread = data
+        .repo
+        .get_thread(new.thread_id)
+        .await
+        .map_err(|_| ApiError::NotFound)?;
+    if thread.deleted_at.is_some() {
+        return Err(ApiError::NotFound);
+    }
+    let board = data.repo.get_board(thread.board_id).await?;
+    beta::ensure_access(data, &board, poster.auth).await?;
+    if thread.closed_at.is_some() || thread.archived_at.is_some() {
+        return Err(ApiError::Conflict);
+    }
+    ensure_attachment_allowed(data, &board, new.mime.as_deref())?;
+    ensure_reply_cooldown(data, poster.auth, &thread, &created_by).await?;
+    let public_identity =
+        derive_public_identity(new.author_name.take(), new.tripcode_password.take())?;
+    let claim = claim_content(data, &created_by, poster.client_ip, "reply", &new.content)?;
+    let stored = store_reply(data, new, created_by, public_identity, friction.hold).await;
+    if stored.is_err() {
+        data.duplicates.release(claim);
+    }
+    stored
+}
+
+/// Held replies are stored deleted until staff approve them.
+async fn store_reply(
+    data: &AppState,
+    mut new: NewReply,
+    created_by: serde_json::Value,
+    public_identity: PublicIdentity,
+    hold: bool,
+) -> Result<Reply, ApiError> {
+    let delete_hash = hash_password_off_thread(new.delete_password.take()).await?;
+    if delete_hash.is_none() && !hold && new.image_hash.is_none() {
+        return Ok(data
+            .repo
+            .create_reply(new, created_by, public_identity)
+            .await?);
+    }
+    if hold {
+        metrics::increment_counter!("trust_posts_held", "kind" => "reply");
+    }
+    transaction(&*data.repo, |tx| {
+        let (new, created_by) = (new.clone(), created_by.clone());
+        let (public_identity, delete_hash) = (public_identity.clone(), delete_hash.clone());
+        Box::pin(async move {
+            if !attachable(tx, new.image_hash.as_deref(), &created_by).await? {
+                return Ok(None);
+            }
+            let reply = tx.create_reply(new, created_by, public_identity).await?;
+            if let Some(delete_hash) = &delete_hash {
+                tx.set_reply_delete_password(reply.id, delete_hash).await?;
+            }
+            if hold {
+                tx.hold_reply(reply.id).await?;
+                return tx.get_reply(reply.id).await.map(Some);
+            }
+            Ok(Some(reply))
+        })
+    })
+    .await?
+    .ok_or_else(attachment_not_owned)
+}
+
+/// Argon2 is deliberately slow, so hashing and verification run on the blocking pool.
+async fn hash_password_off_thread(password: Option<String>) -> Result<Option<String>, ApiError> {
+    let Some(password) = password else {
+        return Ok(None);
+    };
+    actix_web::web::block(move || hash_delete_password(&password))
+        .await
+        .map_err(|_| ApiError::Internal)?
+        .map(Some)
+}
+
+async fn password_matches(password: String, stored: Option<String>) -> Result<bool, ApiError> {
+    let Some(stored) = stored else {
+        return Ok(false);
+    };
+    actix_web::web::block(move || verify_delete_password(&password, &stored))
+        .await
+        .map_err(|_| ApiError::Internal)
+}
+
+/// Soft-delete a visible thread whose poster set a matching deletion password.
+pub async fn delete_thread_with_password(
+    data: &AppState,
+    id: Id,
+    password: String,
+    viewer: Option<&Auth>,
+) -> Result<(), ApiError> {
+    get_thread(data, id, false, viewer).await?;
+    let stored = data.repo.thread_delete_password(id).await?;
+    if !password_matches(password, stored).await? {
+        metrics::increment_counter!("delete_password_rejected", "kind" => "thread");
+        return Err(ApiError::Forbidden);
+    }
+    // The author removing their own post is not a moderation action.
+    transaction(&*data.repo, |tx| {
+        Box::pin(async move {
+            tx.exempt_from_trust().await?;
+            tx.soft_delete_thread(id).await
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Soft-delete a visible reply whose poster set a matching deletion password.
+pub async fn delete_reply_with_password(
+    data: &AppState,
+    id: Id,
+    password: String,
+    viewer: Option<&Auth>,
+) -> Result<(), ApiError> {
+    get_reply(data, id, false, viewer).await?;
+    let stored = data.repo.reply_delete_password(id).await?;
+    if !password_matches(password, stored).await? {
+        metrics::increment_counter!("delete_password_rejected", "kind" => "reply");
+        return Err(ApiError::Forbidden);
+    }
+    // The author removing their own post is not a moderation action.
+    transaction(&*data.repo, |tx| {
+        Box::pin(async move {
+            tx.exempt_from_trust().await?;
+            tx.soft_delete_reply(id).await
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Staff may moderate any thread. A thread's creator may pin replies in it,
+/// and take the other actions on boards with `op_moderation`. Returns the
+/// actor recorded in the moderation log.
+async fn thread_moderator(
+    data: &AppState,
+    auth: &Auth,
+    thread: &Thread,
+    action: ModerationAction,
+) -> Result<(String, ModerationActor), ApiError> {
+    if auth.0.roles.iter().any(|r| matches!(r, Role::Admin)) {
+        return Ok((auth.0.sub.clone(), ModerationActor::Admin));
+    }
+    if auth.0.roles.iter().any(|r| matches!(r, Role::Moderator)) {
+        return Ok((auth.0.sub.clone(), ModerationActor::Moderator));
+    }
+    let (subject, _) = private_author_attribution(auth)?;
+    if crate::filters::author_subject(&thread.created_by) != Some(subject.as_str()) {
+        return Err(ApiError::Forbidden);
+    }
+    if !matches!(
+        action,
+        ModerationAction::PinReply | ModerationAction::UnpinReply
+    ) && !data.repo.get_board(thread.board_id).await?.op_moderation
+    {
+        return Err(ApiError::Forbidden);
+    }
+    ensure_subject_can_post(data, auth, &subject).await?;
+    Ok((subject, ModerationActor::Op))
+}
+
+/// Close a visible thread to new replies, or reopen it, and audit the change.
+pub async fn set_thread_closed(
+    data: &AppState,
+    auth: &Auth,
+    id: Id,
+    closed: bool,
+) -> Result<Thread, ApiError> {
+    let thread = get_thread(data, id, false, Some(auth)).await?;
+    let action = if closed {
+        ModerationAction::CloseThread
+    } else {
+        ModerationAction::ReopenThread
+    };
+    let (actor, actor_role) = thread_moderator(data, auth, &thread, action).await?;
+    let entry = NewModerationEntry {
+        actor,
+        actor_role,
+        action,
+        thread_id: id,
+        reply_id: None,
+    };
+    Ok(transaction(&*data.repo, |tx| {
+        let entry = entry.clone();
+        Box::pin(async move {
+            tx.set_thread_closed(id, closed).await?;
+            tx.record_moderation(entry).await?;
+            tx.get_thread(id).await
+        })
+    })
+    .await?)
+}
+
+/// Soft-delete a visible reply of a thread the caller may moderate, and
+/// audit it along with the caller's reason.
+pub async fn moderate_delete_reply(
+    data: &AppState,
+    auth: &Auth,
+    thread_id: Id,
+    reply_id: Id,
+    reason: String,
+) -> Result<(), ApiError> {
+    let thread = get_thread(data, thread_id, false, Some(auth)).await?;
+    let reply = data.repo.get_reply(reply_id).await?;
+    if reply.thread_id != thread_id || reply.deleted_at.is_some() {
+        return Err(ApiError::NotFound);
+    }
+    let action = ModerationAction::DeleteReply;
+    let (actor, actor_role) = thread_moderator(data, auth, &thread, action).await?;
+    let deletion = NewDeletionEntry {
+        kind: DeletionKind::Reply,
+        target_id: reply_id,
+        hard: false,
+        actor: actor.clone(),
+        reason,
+    };
+    let entry = NewModerationEntry {
+        actor,
+        actor_role,
+        action,
+        thread_id,
+        reply_id: Some(reply_id),
+    };
+    transaction(&*data.repo, |tx| {
+        let (entry, deletion) = (entry.clone(), deletion.clone());
+        Box::pin(async move {
+            tx.soft_delete_reply(reply_id).await?;
+            tx.record_moderation(entry).await?;
+            tx.record_deletion(deletion).await
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Pin a visible reply of the thread, or clear the pin with `None`, and audit it.
+pub async fn set_pinned_reply(
+    data: &AppState,
+    auth: &Auth,
+    thread_id: Id,
+    reply_id: Option<Id>,
+) -> Result<Thread, ApiError> {
+    let thread = get_thread(data, thread_id, false, Some(auth)).await?;
+    let action = match reply_id {
+        Some(_) => ModerationAction::PinReply,
+        None => ModerationAction::UnpinReply,
+    };
+    let (actor, actor_role) = thread_moderator(data, auth, &thread, action).await?;
+    let entry = NewModerationEntry {
+        actor,
+        actor_role,
+        action,
+        thread_id,
+        reply_id: reply_id.or(thread.pinned_reply_id),
+    };
+    Ok(transaction(&*data.repo, |tx| {
+        let entry = entry.clone();
+        Box::pin(async move {
+            tx.set_pinned_reply(thread_id, reply_id).await?;
+            tx.record_moderation(entry).await?;
+            tx.get_thread(thread_id).await
+        })
+    })
+    .await?)
+}
+
+/// Decide a pending appeal and mail the outcome to the appellant's confirmed
+/// address. The decision stands even if the mail cannot be sent.
+pub async fn decide_appeal(
+    data: &AppState,
+    auth: &Auth,
+    id: Id,
+    status: AppealStatus,
+    note: Option<String>,
+) -> Result<Appeal, ApiError> {
+    let appeal = data
+        .repo
+        .decide_appeal(id, &auth.0.sub, status, note)
+        .await?;
+    let Some(mailer) = &data.mailer else {
+        return Ok(appeal);
+    };
+    let settings = match data.repo.get_notification_settings(&appeal.subject).await {
+        Ok(settings) => settings,
+        Err(e) => {
+            log::warn!("loading notification settings for appeal {id} failed: {e}");
+            None
+        }
+    };
+    if let Some(NotificationSettings {
+        email: Some(email),
+        email_verified: true,
+        ..
+    }) = settings
+    {
+        if let Some(message) = crate::appeals::decision_email(&appeal, &email) {
+            if let Err(e) = mailer.send(&message).await {
+                log::warn!("sending appeal decision via {} failed: {e}", mailer.name());
+            }
+        }
+    }
+    Ok(appeal)
+}
+
+/// Visible threads among `ids`, in request order, each with its first
+/// `per_thread` replies. Unknown and hidden ids are left out.
+pub async fn thread_previews(
+    data: &AppState,
+    ids: &[Id],
+    per_thread: i64,
+    viewer: Option<&Auth>,
+) -> Result<Vec<ThreadPreview>, ApiError> {
+    let mut unique = Vec::with_capacity(ids.len());
+    for id in ids {
+        if !unique.contains(id) {
+            unique.push(*id);
+        }
+    }
+    let mut threads: HashMap<Id, Thread> = data
+        .repo
+        .get_threads(&unique)
+        .await?
+        .into_iter()
+        .filter(|t| t.deleted_at.is_none())
+        .map(|t| (t.id, t))
+        .collect();
+    let board_ids: Vec<Id> = threads.values().map(|t| t.board_id).collect();
+    let boards = data.repo.get_boards(&board_ids).await?;
+    let visible_boards: HashSet<Id> = beta::retain_accessible(data, boards, viewer)
+        .await?
+        .into_iter()
+        .filter(|b| b.deleted_at.is_none())
+        .map(|b| b.id)
+        .collect();
+    threads.retain(|_, t| visible_boards.contains(&t.board_id));
+    let mut replies: HashMap<Id, Vec<Reply>> = HashMap::new();
+    if per_thread > 0 && !threads.is_empty() {
+        let visible: Vec<Id> = threads.keys().copied().collect();
+        for reply in data.repo.list_first_replies(&visible, per_thread).await? {
+            replies.entry(reply.thread_id).or_default().push(reply);
+        }
+    }
+    Ok(unique
+        .into_iter()
+        .filter_map(|id| threads.remove(&id))
+        .map(|thread| ThreadPreview {
+            replies: replies.remove(&thread.id).unwrap_or_default(),
+            thread,
+        })
+        .collect())
+}
+
+/// Search the external backend when configured, falling back to Postgres.
+/// Posts on beta boards `viewer` may not see are left out.
+pub async fn search(
+    data: &AppState,
+    q: &str,
+    board_id: Option<Id>,
+    limit: Option<i64>,
+    viewer: Option<&Auth>,
+) -> Result<SearchResults, ApiError> {
+    data.features.current().ensure_enabled(Feature::Search)?;
+    let q = q.trim();
+    if q.is_empty() || q.chars().count() > 200 {
+        return Err(ApiError::BadRequest);
+    }
+    let limit = limit.unwrap_or(25).clamp(1, 100);
+    let hidden_boards = hidden_beta_boards(data, viewer).await?;
+    if let Some(backend) = &data.search {
+        match backend.search(q, board_id, limit).await {
+            Ok(mut hits) => {
+                hits.retain(|hit| !hidden_boards.contains(&hit.board_id));
+                return Ok(SearchResults {
+                    backend: backend.name().to_string(),
+                    hits,
+                });
+            }
+            Err(e) => {
+                metrics::increment_counter!("search_backend_fallback", "backend" => backend.name());
+                log::warn!(
+                    "{} search failed, falling back to postgres: {e}",
+                    backend.name()
+                );
+            }
+        }
+    }
+    let hits = data
+        .repo
+        .search_posts(q, board_id, &hidden_boards, limit)
+        .await?;
+    Ok(SearchResults {
+        backend: "postgres".to_string(),
+        hits,
+    })
+}
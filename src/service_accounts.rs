@@ -0,0 +1,108 @@
+//! Service accounts for automation.
+//!
+//! Admins create accounts under `/api/v1/admin/service-accounts`, each with
+//! a fixed set of [`ServiceScope`]s. An account authenticates with an opaque
+//! `rib_sa_` bearer token that never expires; only its SHA-256 is stored,
+//! and revoking the account stops it at once. Service tokens are not JWTs
+//! and are accepted only by the endpoints their scopes name: the export,
+//! `/metrics` (when `METRICS_REQUIRE_SERVICE_ACCOUNT` is set) and
+//! `POST /api/v1/service/announcements`.
+
+use actix_web::http::header;
+use actix_web::HttpRequest;
+use base64::Engine;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+use crate::error::ApiError;
+use crate::models::{ServiceAccount, ServiceScope};
+use crate::routes::AppState;
+
+pub const TOKEN_PREFIX: &str = "rib_sa_";
+pub const MAX_NAME_CHARS: usize = 64;
+
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_CHARS
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// A fresh token; returned to the admin once and stored only as its hash.
+pub fn generate_token() -> String {
+    let mut bytes = [0_u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!(
+        "{TOKEN_PREFIX}{}",
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+    )
+}
+
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Whether `/metrics` needs a service account with the `metrics` scope.
+pub fn metrics_require_account() -> bool {
+    std::env::var("METRICS_REQUIRE_SERVICE_ACCOUNT")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// The service account presenting a token in `req`, which must hold `scope`.
+/// `None` when the request carries no service token; `Unauthorized` for an
+/// unknown or revoked one and `Forbidden` when the scope is missing.
+pub async fn authorize(
+    req: &HttpRequest,
+    data: &AppState,
+    scope: ServiceScope,
+) -> Result<Option<ServiceAccount>, ApiError> {
+    let Some(token) = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .filter(|t| t.starts_with(TOKEN_PREFIX))
+    else {
+        return Ok(None);
+    };
+    let account = data
+        .repo
+        .use_service_account(&hash_token(token))
+        .await?
+        .ok_or(ApiError::Unauthorized)?;
+    if !account.scopes.contains(&scope) {
+        return Err(ApiError::Forbidden);
+    }
+    metrics::increment_counter!("service_account_requests", "scope" => scope.as_str());
+    Ok(Some(account))
+}
+
+/// Like [`authorize`], but a service token is required.
+pub async fn require(
+    req: &HttpRequest,
+    data: &AppState,
+    scope: ServiceScope,
+) -> Result<ServiceAccount, ApiError> {
+    authorize(req, data, scope)
+        .await?
+        .ok_or(ApiError::Unauthorized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_are_prefixed_and_hashed() {
+        let token = generate_token();
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_ne!(token, generate_token());
+        assert_eq!(hash_token(&token).len(), 64);
+        assert!(valid_name("backup-job-2"));
+        assert!(!valid_name("Backup"));
+        assert!(!valid_name(""));
+        assert!(!valid_name(&"a".repeat(65)));
+    }
+}
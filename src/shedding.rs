@@ -0,0 +1,281 @@
+//! Load shedding driven by database pool saturation.
+//!
+//! A probe times how long it takes to acquire a pooled connection. While the
+//! time stays above `SHED_ACQUIRE_MS` the shed level climbs step by step, and
+//! it falls back at half that pace once acquires are fast again. At level `p`
+//! the [`LoadShedder`] middleware answers that fraction of low-priority reads
+//! (listings and search) with `503`, so writes and sign-ins keep the pool.
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::Method;
+use actix_web::{Error, HttpResponse};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use sqlx::PgPool;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+use crate::error::ApiErrorBody;
+use crate::http_metrics::route_label;
+
+/// Reads that may be shed; everything else is always served.
+const LOW_PRIORITY_ROUTES: &[&str] = &[
+    "/api/v1/boards/{id}/threads",
+    "/api/v1/boards/{id}/archive",
+    "/api/v1/threads/{id}/replies",
+    "/api/v1/search",
+    "/api/v2/boards/{id}/threads",
+    "/api/v2/threads/{id}/replies",
+    "/api/v2/search",
+];
+
+/// Levels are kept in thousandths so they fit an atomic.
+const SCALE: f64 = 1000.0;
+
+#[derive(Clone, Debug)]
+pub struct ShedConfig {
+    /// Acquire time above which shedding starts; unset disables shedding.
+    pub acquire_threshold: Option<Duration>,
+    pub probe_interval: Duration,
+    /// Level added per slow probe.
+    pub step: f64,
+    /// Highest fraction of low-priority reads shed.
+    pub max_level: f64,
+}
+
+impl ShedConfig {
+    pub fn from_env() -> Self {
+        fn f64_env(name: &str) -> Option<f64> {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v: &f64| v.is_finite())
+        }
+        Self {
+            acquire_threshold: std::env::var("SHED_ACQUIRE_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis),
+            probe_interval: Duration::from_millis(
+                std::env::var("SHED_PROBE_MS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(250u64)
+                    .max(10),
+            ),
+            step: f64_env("SHED_STEP").unwrap_or(0.1).clamp(0.01, 1.0),
+            max_level: f64_env("SHED_MAX_FRACTION").unwrap_or(0.9).clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Shed level shared by the probe and every worker's middleware.
+#[derive(Clone)]
+pub struct LoadShedder {
+    cfg: Arc<ShedConfig>,
+    level: Arc<AtomicU32>,
+}
+
+impl LoadShedder {
+    pub fn new(cfg: ShedConfig) -> Self {
+        Self {
+            cfg: Arc::new(cfg),
+            level: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    /// Fraction of low-priority reads currently shed.
+    pub fn level(&self) -> f64 {
+        self.level.load(Ordering::Relaxed) as f64 / SCALE
+    }
+
+    fn set_level(&self, level: f64) {
+        self.level
+            .store((level * SCALE).round() as u32, Ordering::Relaxed);
+        metrics::gauge!("load_shed_level", level);
+    }
+
+    /// Feed one probe result; `None` means the acquire failed or timed out.
+    pub fn observe(&self, acquire: Option<Duration>) {
+        let Some(threshold) = self.cfg.acquire_threshold else {
+            return;
+        };
+        let current = self.level();
+        let next = match acquire {
+            Some(elapsed) if elapsed <= threshold => (current - self.cfg.step / 2.0).max(0.0),
+            _ => (current + self.cfg.step).min(self.cfg.max_level),
+        };
+        if next != current {
+            if current == 0.0 {
+                log::warn!("database pool saturated; shedding low-priority reads");
+            } else if next == 0.0 {
+                log::info!("database pool recovered; load shedding stopped");
+            }
+            self.set_level(next);
+        }
+    }
+
+    /// Probe `pool` until the task is aborted; does nothing when disabled.
+    pub fn spawn_probe(&self, pool: PgPool) -> Option<JoinHandle<()>> {
+        let threshold = self.cfg.acquire_threshold?;
+        let shedder = self.clone();
+        Some(actix_web::rt::spawn(async move {
+            loop {
+                let started = Instant::now();
+                // Waiting much past the threshold tells nothing new.
+                let acquired = tokio::time::timeout(threshold * 4, pool.acquire()).await;
+                let elapsed = match acquired {
+                    Ok(Ok(conn)) => {
+                        drop(conn);
+                        Some(started.elapsed())
+                    }
+                    _ => None,
+                };
+                shedder.observe(elapsed);
+                tokio::time::sleep(shedder.cfg.probe_interval).await;
+            }
+        }))
+    }
+
+    fn sheds(&self, req: &ServiceRequest) -> Option<String> {
+        let level = self.level();
+        if level <= 0.0 || !matches!(*req.method(), Method::GET | Method::HEAD) {
+            return None;
+        }
+        let route = route_label(req);
+        (LOW_PRIORITY_ROUTES.contains(&route.as_str()) && rand::random::<f64>() < level)
+            .then_some(route)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for LoadShedder
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = LoadShedderMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(LoadShedderMiddleware {
+            service: Rc::new(service),
+            shedder: self.clone(),
+        }))
+    }
+}
+
+pub struct LoadShedderMiddleware<S> {
+    service: Rc<S>,
+    shedder: LoadShedder,
+}
+
+impl<S, B> Service<ServiceRequest> for LoadShedderMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Some(route) = self.shedder.sheds(&req) {
+            metrics::increment_counter!("requests_shed", "route" => route);
+            let response = HttpResponse::ServiceUnavailable()
+                .insert_header(("Retry-After", "1"))
+                .json(ApiErrorBody {
+                    error: "server busy, retry shortly".to_string(),
+                    code: None,
+                });
+            return Box::pin(ready(Ok(req.into_response(response).map_into_right_body())));
+        }
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App};
+
+    fn shedder(max_level: f64) -> LoadShedder {
+        LoadShedder::new(ShedConfig {
+            acquire_threshold: Some(Duration::from_millis(50)),
+            probe_interval: Duration::from_millis(250),
+            step: 0.25,
+            max_level,
+        })
+    }
+
+    #[actix_web::test]
+    async fn level_climbs_on_slow_acquires_and_decays_when_fast() {
+        let shedder = shedder(0.5);
+        shedder.observe(Some(Duration::from_millis(10)));
+        assert_eq!(shedder.level(), 0.0);
+        shedder.observe(Some(Duration::from_millis(80)));
+        assert_eq!(shedder.level(), 0.25);
+        shedder.observe(None);
+        shedder.observe(None);
+        assert_eq!(shedder.level(), 0.5);
+        shedder.observe(Some(Duration::from_millis(10)));
+        assert_eq!(shedder.level(), 0.375);
+
+        let disabled = LoadShedder::new(ShedConfig {
+            acquire_threshold: None,
+            ..shedder.cfg.as_ref().clone()
+        });
+        disabled.observe(None);
+        assert_eq!(disabled.level(), 0.0);
+    }
+
+    #[actix_web::test]
+    async fn only_low_priority_reads_are_shed() {
+        let shedder = shedder(1.0);
+        for _ in 0..4 {
+            shedder.observe(None);
+        }
+        assert_eq!(shedder.level(), 1.0);
+        let ok = || async { HttpResponse::Ok().finish() };
+        let app = test::init_service(
+            App::new()
+                .wrap(shedder)
+                .route("/api/v1/search", web::get().to(ok))
+                .route("/api/v1/threads/{id}", web::get().to(ok))
+                .route("/api/v1/replies", web::post().to(ok)),
+        )
+        .await;
+        let status = |req: test::TestRequest| {
+            let app = &app;
+            async move { test::call_service(app, req.to_request()).await.status() }
+        };
+        assert_eq!(
+            status(test::TestRequest::get().uri("/api/v1/search?q=x")).await,
+            503
+        );
+        assert_eq!(
+            status(test::TestRequest::get().uri("/api/v1/threads/1")).await,
+            200
+        );
+        assert_eq!(
+            status(test::TestRequest::post().uri("/api/v1/replies")).await,
+            200
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use actix_web::http::header;
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use crate::error::ApiError;
+use crate::models::Id;
+use crate::routes::AppState;
+
+/// Protocol limit on URLs per sitemap file.
+const MAX_PAGE_SIZE: i64 = 50_000;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SitemapBoard {
+    pub slug: String,
+    /// Latest bump among the board's threads; `None` for an empty board.
+    pub lastmod: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SitemapThread {
+    pub id: Id,
+    pub bump_time: DateTime<Utc>,
+}
+
+/// Public origin of the frontend: `SITE_URL`, else `FRONTEND_URL`, without a trailing slash.
+pub fn site_url() -> String {
+    std::env::var("SITE_URL")
+        .or_else(|_| std::env::var("FRONTEND_URL"))
+        .unwrap_or_else(|_| "http://localhost:5173".to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+#[derive(Debug, Clone)]
+pub struct SitemapConfig {
+    /// Public origin of the frontend that sitemap URLs point at.
+    pub base_url: String,
+    /// Thread URLs per `sitemap-threads-N.xml` file.
+    pub page_size: i64,
+    /// Only threads bumped within this many days are listed; 0 lists all.
+    pub thread_days: i64,
+    /// Served verbatim as `/robots.txt` when set.
+    pub robots_file: Option<PathBuf>,
+    /// Ask all crawlers to stay away, e.g. on staging.
+    pub disallow_all: bool,
+}
+
+impl SitemapConfig {
+    pub fn from_env() -> Self {
+        fn i64_env(name: &str) -> Option<i64> {
+            std::env::var(name).ok().and_then(|v| v.parse().ok())
+        }
+        Self {
+            base_url: site_url(),
+            page_size: i64_env("SITEMAP_PAGE_SIZE")
+                .unwrap_or(10_000)
+                .clamp(1, MAX_PAGE_SIZE),
+            thread_days: i64_env("SITEMAP_THREAD_DAYS").unwrap_or(90).max(0),
+            robots_file: std::env::var("ROBOTS_TXT_FILE")
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .map(PathBuf::from),
+            disallow_all: std::env::var("ROBOTS_DISALLOW_ALL")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+        }
+    }
+
+    fn threads_since(&self) -> DateTime<Utc> {
+        if self.thread_days == 0 {
+            DateTime::<Utc>::UNIX_EPOCH
+        } else {
+            Utc::now() - chrono::Duration::days(self.thread_days)
+        }
+    }
+
+    /// Built-in robots.txt: keep crawlers out of API and admin paths and
+    /// point them at the sitemap.
+    pub fn default_robots(&self) -> String {
+        if self.disallow_all {
+            return "User-agent: *\nDisallow: /\n".to_string();
+        }
+        format!(
+            "User-agent: *\nDisallow: /api/\nDisallow: /admin/\nDisallow: /login\nDisallow: /graphql\n\nSitemap: {}/sitemap.xml\n",
+            self.base_url
+        )
+    }
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("/robots.txt", web::get().to(robots_txt))
+        .route("/sitemap.xml", web::get().to(sitemap_index))
+        .route("/sitemap-boards.xml", web::get().to(sitemap_boards))
+        .route(
+            r"/sitemap-threads-{page:\d+}.xml",
+            web::get().to(sitemap_threads),
+        );
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn w3c(ts: DateTime<Utc>) -> String {
+    ts.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Render a `<urlset>` of `(loc, lastmod)` entries.
+pub fn render_urlset(entries: impl IntoIterator<Item = (String, Option<DateTime<Utc>>)>) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for (loc, lastmod) in entries {
+        let _ = write!(xml, "  <url><loc>{}</loc>", xml_escape(&loc));
+        if let Some(ts) = lastmod {
+            let _ = write!(xml, "<lastmod>{}</lastmod>", w3c(ts));
+        }
+        xml.push_str("</url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+fn xml_response(body: String) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/xml; charset=utf-8")
+        .insert_header((header::CACHE_CONTROL, "public, max-age=900"))
+        .body(body)
+}
+
+async fn robots_txt() -> HttpResponse {
+    let cfg = SitemapConfig::from_env();
+    let mut body = None;
+    if let Some(path) = cfg.robots_file.clone() {
+        match web::block(move || std::fs::read_to_string(path)).await {
+            Ok(Ok(text)) => body = Some(text),
+            Ok(Err(e)) => log::warn!("ROBOTS_TXT_FILE unreadable, serving default: {e}"),
+            Err(e) => log::warn!("ROBOTS_TXT_FILE read failed, serving default: {e}"),
+        }
+    }
+    HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .insert_header((header::CACHE_CONTROL, "public, max-age=3600"))
+        .body(body.unwrap_or_else(|| cfg.default_robots()))
+}
+
+/// Sitemap index listing the board sitemap and one file per page of threads.
+async fn sitemap_index(data: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let cfg = SitemapConfig::from_env();
+    let threads = data.repo.count_sitemap_threads(cfg.threads_since()).await?;
+    let pages = ((threads + cfg.page_size - 1) / cfg.page_size).max(1);
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    let _ = writeln!(
+        xml,
+        "  <sitemap><loc>{}</loc></sitemap>",
+        xml_escape(&format!("{}/sitemap-boards.xml", cfg.base_url))
+    );
+    for page in 1..=pages {
+        let _ = writeln!(
+            xml,
+            "  <sitemap><loc>{}</loc></sitemap>",
+            xml_escape(&format!("{}/sitemap-threads-{page}.xml", cfg.base_url))
+        );
+    }
+    xml.push_str("</sitemapindex>\n");
+    Ok(xml_response(xml))
+}
+
+async fn sitemap_boards(data: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let cfg = SitemapConfig::from_env();
+    let boards = data.repo.sitemap_boards().await?;
+    let mut entries = vec![(format!("{}/", cfg.base_url), None)];
+    entries.extend(
+        boards
+            .into_iter()
+            .map(|b| (format!("{}/{}", cfg.base_url, b.slug), b.lastmod)),
+    );
+    Ok(xml_response(render_urlset(entries)))
+}
+
+async fn sitemap_threads(
+    data: web::Data<AppState>,
+    page: web::Path<i64>,
+) -> Result<HttpResponse, ApiError> {
+    let cfg = SitemapConfig::from_env();
+    let page = page.into_inner();
+    if page < 1 {
+        return Err(ApiError::NotFound);
+    }
+    let threads = data
+        .repo
+        .list_sitemap_threads(
+            cfg.threads_since(),
+            cfg.page_size,
+            (page - 1).saturating_mul(cfg.page_size),
+        )
+        .await?;
+    // Page 1 always exists so the index never links to a 404.
+    if threads.is_empty() && page > 1 {
+        return Err(ApiError::NotFound);
+    }
+    Ok(xml_response(render_urlset(threads.into_iter().map(|t| {
+        (
+            format!("{}/thread/{}", cfg.base_url, t.id),
+            Some(t.bump_time),
+        )
+    }))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urlset_escapes_locations_and_formats_lastmod() {
+        let ts = DateTime::parse_from_rfc3339("2026-10-16T12:34:56.789Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let xml = render_urlset([
+            ("https://rib.example/a&b".to_string(), Some(ts)),
+            ("https://rib.example/".to_string(), None),
+        ]);
+        assert!(xml.contains(
+            "<url><loc>https://rib.example/a&amp;b</loc><lastmod>2026-10-16T12:34:56Z</lastmod></url>"
+        ));
+        assert!(xml.contains("<url><loc>https://rib.example/</loc></url>"));
+    }
+}
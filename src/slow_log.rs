@@ -0,0 +1,220 @@
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::Error;
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::auth::{request_token, subject_from_token};
+use crate::http_metrics::route_label;
+
+/// Thresholds above which requests and repository operations are logged at WARN.
+#[derive(Clone, Debug)]
+pub struct SlowLogConfig {
+    pub request_threshold: Option<Duration>,
+    pub query_threshold: Option<Duration>,
+}
+
+impl Default for SlowLogConfig {
+    fn default() -> Self {
+        Self {
+            request_threshold: Some(Duration::from_millis(1000)),
+            query_threshold: Some(Duration::from_millis(250)),
+        }
+    }
+}
+
+impl SlowLogConfig {
+    pub fn from_env() -> Self {
+        // 0 disables the corresponding log.
+        fn threshold(name: &str, default: Option<Duration>) -> Option<Duration> {
+            match std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok()) {
+                Some(0) => None,
+                Some(ms) => Some(Duration::from_millis(ms)),
+                None => default,
+            }
+        }
+        let defaults = Self::default();
+        Self {
+            request_threshold: threshold("SLOW_REQUEST_MS", defaults.request_threshold),
+            query_threshold: threshold("SLOW_QUERY_MS", defaults.query_threshold),
+        }
+    }
+}
+
+/// Request attributes visible to repository calls made while serving it.
+#[derive(Clone, Debug)]
+pub(crate) struct RequestContext {
+    pub(crate) method: String,
+    pub(crate) route: String,
+    /// Raw bearer token or session cookie; only verified when something is logged.
+    credential: Option<String>,
+}
+
+impl RequestContext {
+    fn from_request(req: &ServiceRequest) -> Self {
+        Self {
+            method: req.method().to_string(),
+            route: route_label(req),
+            credential: request_token(req),
+        }
+    }
+
+    pub(crate) fn subject(&self) -> String {
+        self.credential
+            .as_deref()
+            .and_then(subject_from_token)
+            .unwrap_or_else(|| "anonymous".to_string())
+    }
+}
+
+tokio::task_local! {
+    static REQUEST: RequestContext;
+}
+
+/// Route pattern of the request being served on this task, if any.
+pub fn current_route() -> Option<String> {
+    REQUEST.try_with(|ctx| ctx.route.clone()).ok()
+}
+
+pub(crate) fn current_request() -> Option<RequestContext> {
+    REQUEST.try_with(Clone::clone).ok()
+}
+
+/// Log and count a repository operation that exceeded the query threshold.
+pub fn observe_query(op: &'static str, elapsed: Duration, threshold: Option<Duration>) {
+    match threshold {
+        Some(t) if elapsed >= t => {}
+        _ => return,
+    }
+    metrics::increment_counter!("repo_slow_operations", "op" => op);
+    metrics::histogram!("repo_slow_operation_seconds", elapsed.as_secs_f64(), "op" => op);
+    let ctx = current_request();
+    tracing::warn!(
+        op,
+        method = ctx.as_ref().map(|c| c.method.as_str()).unwrap_or("-"),
+        route = ctx.as_ref().map(|c| c.route.as_str()).unwrap_or("-"),
+        subject = ctx.as_ref().map(|c| c.subject()).as_deref().unwrap_or("-"),
+        duration_ms = elapsed.as_millis() as u64,
+        "slow repository operation"
+    );
+}
+
+/// Middleware timing each request up to its response head and scoping a
+/// [`RequestContext`] so slow repository operations can name their route.
+#[derive(Clone, Default)]
+pub struct SlowRequestLog {
+    cfg: SlowLogConfig,
+}
+
+impl SlowRequestLog {
+    pub fn new(cfg: SlowLogConfig) -> Self {
+        Self { cfg }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for SlowRequestLog
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = SlowRequestLogMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SlowRequestLogMiddleware {
+            service: Rc::new(service),
+            cfg: self.cfg.clone(),
+        }))
+    }
+}
+
+pub struct SlowRequestLogMiddleware<S> {
+    service: Rc<S>,
+    cfg: SlowLogConfig,
+}
+
+impl<S, B> Service<ServiceRequest> for SlowRequestLogMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let ctx = RequestContext::from_request(&req);
+        let threshold = self.cfg.request_threshold;
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let started = Instant::now();
+            let res = REQUEST.scope(ctx.clone(), fut).await;
+            let elapsed = started.elapsed();
+            if threshold.is_some_and(|t| elapsed >= t) {
+                let status = res.as_ref().map(|r| r.status().as_u16()).unwrap_or(500);
+                metrics::increment_counter!(
+                    "http_slow_requests",
+                    "method" => ctx.method.clone(),
+                    "route" => ctx.route.clone()
+                );
+                metrics::histogram!(
+                    "http_slow_request_seconds",
+                    elapsed.as_secs_f64(),
+                    "route" => ctx.route.clone()
+                );
+                tracing::warn!(
+                    method = ctx.method.as_str(),
+                    route = ctx.route.as_str(),
+                    subject = ctx.subject().as_str(),
+                    status,
+                    duration_ms = elapsed.as_millis() as u64,
+                    "slow request"
+                );
+            }
+            res
+        })
+    }
+}
+
+/// Histogram buckets (seconds) for the slow request and operation histograms.
+pub const SLOW_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App, HttpResponse};
+
+    #[actix_web::test]
+    async fn handlers_see_the_matched_route() {
+        let app = test::init_service(
+            App::new()
+                .wrap(SlowRequestLog::new(SlowLogConfig {
+                    request_threshold: Some(Duration::ZERO),
+                    query_threshold: None,
+                }))
+                .route(
+                    "/things/{id}",
+                    web::get().to(|| async {
+                        HttpResponse::Ok().body(current_route().unwrap_or_default())
+                    }),
+                ),
+        )
+        .await;
+        let req = test::TestRequest::get().uri("/things/42").to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_eq!(body, "/things/{id}");
+        assert_eq!(current_route(), None);
+    }
+}
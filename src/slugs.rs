@@ -0,0 +1,79 @@
+//! Readable thread URLs.
+//!
+//! Besides `/api/v1/threads/{id}`, a thread answers at
+//! `/api/v1/boards/{board}/threads/{id}-{slug}`, where the slug is derived
+//! from the subject by the database (see the `thread_slug` SQL function).
+//! The id decides which thread is meant; the slugs only have to be ones the
+//! board and thread carry or once carried, and requests that use an old
+//! one, or the bare id, are redirected to the current URL. Board slugs given
+//! up in a rename are kept the same way (`board_slugs`), so board pages
+//! follow renames too.
+
+use crate::auth::Auth;
+use crate::error::ApiError;
+use crate::models::{Board, Id, Thread};
+use crate::routes::AppState;
+use crate::service;
+
+/// The last path segment for `thread`: `{id}-{slug}`, or just the id when
+/// the subject yields no slug.
+pub fn thread_handle(thread: &Thread) -> String {
+    if thread.slug.is_empty() {
+        thread.id.to_string()
+    } else {
+        format!("{}-{}", thread.id, thread.slug)
+    }
+}
+
+/// Splits a `{id}-{slug}` or `{id}` path segment.
+pub fn parse_thread_handle(handle: &str) -> Option<(Id, &str)> {
+    let (id, slug) = handle.split_once('-').unwrap_or((handle, ""));
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((id.parse().ok()?, slug))
+}
+
+/// Canonical readable URL of `thread` on `board`.
+pub fn thread_url(board: &Board, thread: &Thread) -> String {
+    format!(
+        "/api/v1/boards/{}/threads/{}",
+        board.slug,
+        thread_handle(thread)
+    )
+}
+
+/// The board that gave up `slug` in a rename, if `viewer` may see it.
+pub async fn renamed_board(
+    data: &AppState,
+    slug: &str,
+    viewer: Option<&Auth>,
+) -> Result<Option<Board>, ApiError> {
+    let Some(id) = data.repo.board_for_old_slug(slug).await? else {
+        return Ok(None);
+    };
+    match service::get_board(data, id, false, viewer).await {
+        Ok(board) => Ok(Some(board)),
+        Err(ApiError::NotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handles_split_into_id_and_slug() {
+        assert_eq!(
+            parse_thread_handle("42-hello-world"),
+            Some((42, "hello-world"))
+        );
+        assert_eq!(parse_thread_handle("42"), Some((42, "")));
+        assert_eq!(parse_thread_handle("42-"), Some((42, "")));
+        assert_eq!(parse_thread_handle("-42"), None);
+        assert_eq!(parse_thread_handle("+42-x"), None);
+        assert_eq!(parse_thread_handle("hello-42"), None);
+        assert_eq!(parse_thread_handle("99999999999999999999-x"), None);
+    }
+}
@@ -0,0 +1,264 @@
+//! Read-only HTML views of boards and threads for crawlers and clients
+//! without JavaScript. Served at `/_ssr/...` and, for crawler user agents,
+//! at the SPA's own URLs.
+
+use actix_web::dev::RequestHead;
+use actix_web::http::header;
+use actix_web::{guard, web, HttpRequest, HttpResponse};
+use askama::Template;
+
+use crate::bots::wants_rendered_pages;
+use crate::error::ApiError;
+use crate::models::{Board, Id, Reply, SortOrder, Thread, ThreadOrder};
+use crate::routes::AppState;
+use crate::service;
+use crate::sitemap::site_url;
+
+/// Threads shown on a board page, most recently bumped first.
+const BOARD_PAGE_THREADS: usize = 100;
+
+/// First path segments owned by the SPA or other services rather than boards.
+const RESERVED_SEGMENTS: &[&str] = &[
+    "about", "login", "admin", "docs", "metrics", "healthz", "images", "api",
+];
+
+fn is_board_path(head: &RequestHead) -> bool {
+    let segment = head.uri.path().trim_matches('/');
+    !segment.is_empty() && !RESERVED_SEGMENTS.contains(&segment) && !segment.contains('.')
+}
+
+/// Explicit `/_ssr/...` routes. Part of the public router so they work
+/// whether or not the SPA is embedded.
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/_ssr")
+            .route("", web::get().to(index))
+            .route("/", web::get().to(index))
+            .route("/thread/{id}", web::get().to(thread))
+            .route("/{slug}", web::get().to(board)),
+    );
+}
+
+/// Crawler fallback at the SPA's URLs; register just before the SPA catch-all.
+pub fn crawler_fallback(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/")
+            .guard(guard::fn_guard(|ctx| wants_rendered_pages(ctx.head())))
+            .route(web::get().to(index)),
+    )
+    .service(
+        web::resource(r"/thread/{id:\d+}")
+            .guard(guard::fn_guard(|ctx| wants_rendered_pages(ctx.head())))
+            .route(web::get().to(thread)),
+    )
+    .service(
+        web::resource("/{slug}")
+            .guard(guard::fn_guard(|ctx| {
+                wants_rendered_pages(ctx.head()) && is_board_path(ctx.head())
+            }))
+            .route(web::get().to(board)),
+    );
+}
+
+/// Helpers callable from templates.
+trait Attachments {
+    /// Attachment hash when it is an image the page can inline.
+    fn image<'b>(&self, hash: &'b Option<String>, mime: &Option<String>) -> Option<&'b str> {
+        match mime.as_deref() {
+            Some(m) if m.starts_with("image/") => hash.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "ssr/index.html")]
+struct IndexPage {
+    canonical: String,
+    boards: Vec<Board>,
+}
+
+#[derive(Template)]
+#[template(path = "ssr/board.html")]
+struct BoardPage {
+    canonical: String,
+    board: Board,
+    threads: Vec<Thread>,
+}
+
+impl Attachments for BoardPage {}
+
+#[derive(Template)]
+#[template(path = "ssr/thread.html")]
+struct ThreadPage {
+    canonical: String,
+    board: Board,
+    thread: Thread,
+    replies: Vec<Reply>,
+    /// Meta description: the opening of the thread body.
+    summary: String,
+}
+
+impl Attachments for ThreadPage {}
+
+#[derive(Template)]
+#[template(path = "ssr/not_found.html")]
+struct NotFoundPage {
+    canonical: String,
+}
+
+fn html(
+    status: actix_web::http::StatusCode,
+    page: impl Template,
+) -> Result<HttpResponse, ApiError> {
+    let body = page.render().map_err(|e| {
+        log::error!("ssr template failed: {e}");
+        ApiError::Internal
+    })?;
+    Ok(HttpResponse::build(status)
+        .content_type("text/html; charset=utf-8")
+        .insert_header((header::CACHE_CONTROL, "public, max-age=60"))
+        .insert_header((header::VARY, "User-Agent"))
+        .body(body))
+}
+
+/// Render a page, turning a missing resource into the HTML 404 page.
+fn page_or_404(
+    req: &HttpRequest,
+    result: Result<impl Template, ApiError>,
+) -> Result<HttpResponse, ApiError> {
+    use actix_web::http::StatusCode;
+    match result {
+        Ok(page) => html(StatusCode::OK, page),
+        Err(ApiError::NotFound) => html(
+            StatusCode::NOT_FOUND,
+            NotFoundPage {
+                canonical: canonical(req),
+            },
+        ),
+        Err(e) => Err(e),
+    }
+}
+
+/// Canonical URL at the SPA path, so `/_ssr/...` pages do not compete with it.
+fn canonical(req: &HttpRequest) -> String {
+    let path = req.path();
+    let path = path.strip_prefix("/_ssr").unwrap_or(path);
+    let path = if path.is_empty() { "/" } else { path };
+    format!("{}{path}", site_url())
+}
+
+fn summarize(text: &str) -> String {
+    const MAX: usize = 160;
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match flat.char_indices().nth(MAX) {
+        Some((cut, _)) => format!("{}…", &flat[..cut]),
+        None => flat,
+    }
+}
+
+async fn index(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let boards = service::list_boards(&data, false, None).await;
+    page_or_404(
+        &req,
+        boards.map(|boards| IndexPage {
+            canonical: canonical(&req),
+            boards,
+        }),
+    )
+}
+
+async fn board(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    slug: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let slug = slug.into_inner();
+    let board = service::list_boards(&data, false, None)
+        .await?
+        .into_iter()
+        .find(|b| b.slug == slug);
+    let Some(board) = board else {
+        // A renamed board's old address leads to its new one.
+        if let Some(board) = crate::slugs::renamed_board(&data, &slug, None).await? {
+            let (parent, _) = req.path().rsplit_once('/').unwrap_or_default();
+            metrics::increment_counter!("slug_redirects", "kind" => "board");
+            return Ok(HttpResponse::MovedPermanently()
+                .insert_header((header::LOCATION, format!("{parent}/{}", board.slug)))
+                .finish());
+        }
+        return page_or_404(&req, Err::<BoardPage, _>(ApiError::NotFound));
+    };
+    let result = async {
+        let mut threads =
+            service::list_threads(&data, board.id, false, ThreadOrder::default(), None).await?;
+        threads.truncate(BOARD_PAGE_THREADS);
+        Ok(BoardPage {
+            canonical: canonical(&req),
+            board,
+            threads,
+        })
+    }
+    .await;
+    page_or_404(&req, result)
+}
+
+async fn thread(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    id: web::Path<Id>,
+) -> Result<HttpResponse, ApiError> {
+    let id = id.into_inner();
+    let result = async {
+        let thread = service::get_thread(&data, id, false, None).await?;
+        let board = service::get_board(&data, thread.board_id, false, None).await?;
+        let replies = service::list_replies(&data, id, false, SortOrder::Asc, None).await?;
+        Ok(ThreadPage {
+            canonical: canonical(&req),
+            summary: summarize(&thread.body),
+            board,
+            thread,
+            replies,
+        })
+    }
+    .await;
+    page_or_404(&req, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn recognises_crawlers_and_board_paths() {
+        let bot = TestRequest::get()
+            .uri("/tech")
+            .insert_header((
+                header::USER_AGENT,
+                "Mozilla/5.0 (compatible; Googlebot/2.1)",
+            ))
+            .to_srv_request();
+        assert!(wants_rendered_pages(bot.head()));
+        assert!(is_board_path(bot.head()));
+        let browser = TestRequest::get()
+            .uri("/about")
+            .insert_header((header::USER_AGENT, "Mozilla/5.0 Firefox/130.0"))
+            .to_srv_request();
+        assert!(!wants_rendered_pages(browser.head()));
+        assert!(!is_board_path(browser.head()));
+        assert!(!is_board_path(
+            TestRequest::get()
+                .uri("/favicon.ico")
+                .to_srv_request()
+                .head()
+        ));
+    }
+
+    #[test]
+    fn summaries_are_flattened_and_truncated() {
+        assert_eq!(summarize("a\n\n b"), "a b");
+        let long = "x".repeat(200);
+        assert_eq!(summarize(&long).chars().count(), 161);
+    }
+}
@@ -13,11 +13,143 @@ pub enum ImageStoreError {
     Other(String),
 }
 
+/// Size and type of a stored object, known without fetching its bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageMeta {
+    pub size: u64,
+    pub mime: String,
+}
+
+/// Where a stored object should live: with fresh uploads or in the colder
+/// class kept for media of archived threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageTier {
+    Hot,
+    Archive,
+}
+
+/// Tag marking objects moved to the archive tier; lifecycle rules filter on it.
+pub const ARCHIVE_TAG: (&str, &str) = ("rib-tier", "archive");
+
+/// Tags, storage classes and the lifecycle rule applied to stored objects.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObjectPolicy {
+    /// Tags set on every object, e.g. for cost allocation.
+    pub tags: Vec<(String, String)>,
+    /// Storage class of new uploads; the bucket default when unset.
+    pub storage_class: Option<String>,
+    /// Storage class media of archived threads is moved to, e.g.
+    /// `STANDARD_IA`. Media stay in place when unset.
+    pub archive_storage_class: Option<String>,
+    /// Days after which archived media transition to `lifecycle_storage_class`.
+    pub lifecycle_days: Option<i32>,
+    pub lifecycle_storage_class: String,
+}
+
+impl ObjectPolicy {
+    pub fn from_env() -> anyhow::Result<Self> {
+        fn opt_env(name: &str) -> Option<String> {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        }
+        let tags = opt_env("S3_OBJECT_TAGS")
+            .map(|v| parse_tags(&v))
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("S3_OBJECT_TAGS: {e}"))?
+            .unwrap_or_default();
+        let lifecycle_days = opt_env("S3_LIFECYCLE_ARCHIVE_DAYS")
+            .map(|v| v.parse::<i32>().ok().filter(|days| *days > 0))
+            .map(|days| {
+                days.ok_or_else(|| {
+                    anyhow::anyhow!("S3_LIFECYCLE_ARCHIVE_DAYS must be a positive number")
+                })
+            })
+            .transpose()?;
+        Ok(Self {
+            tags,
+            storage_class: opt_env("S3_STORAGE_CLASS"),
+            archive_storage_class: opt_env("S3_ARCHIVE_STORAGE_CLASS"),
+            lifecycle_days,
+            lifecycle_storage_class: opt_env("S3_LIFECYCLE_STORAGE_CLASS")
+                .unwrap_or_else(|| "GLACIER_IR".to_string()),
+        })
+    }
+
+    /// `x-amz-tagging` value for an object in `tier`.
+    pub fn tagging(&self, tier: StorageTier) -> String {
+        let archive = (tier == StorageTier::Archive).then_some(ARCHIVE_TAG);
+        self.tags
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .filter(|(key, _)| *key != ARCHIVE_TAG.0)
+            .chain(archive)
+            .map(|(key, value)| {
+                format!(
+                    "{}={}",
+                    urlencoding::encode(key),
+                    urlencoding::encode(value)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// Storage class for objects in `tier`; `None` keeps the bucket default.
+    pub fn storage_class(&self, tier: StorageTier) -> Option<&str> {
+        match tier {
+            StorageTier::Hot => self.storage_class.as_deref(),
+            StorageTier::Archive => self.archive_storage_class.as_deref(),
+        }
+    }
+}
+
+/// Parse `key=value` pairs separated by commas.
+fn parse_tags(value: &str) -> Result<Vec<(String, String)>, String> {
+    let tags = value
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() && key.trim().len() <= 128 => {
+                Ok((key.trim().to_string(), value.trim().to_string()))
+            }
+            _ => Err(format!("expected key=value, got `{pair}`")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    // S3 allows ten tags per object and one is reserved for the archive tier.
+    if tags.len() > 9 {
+        return Err("at most 9 tags".into());
+    }
+    Ok(tags)
+}
+
 #[async_trait]
 pub trait ImageStore: Send + Sync {
     async fn save(&self, hash: &str, mime: &str, bytes: &[u8]) -> Result<(), ImageStoreError>;
     async fn load(&self, hash: &str) -> Result<(Vec<u8>, String), ImageStoreError>;
     async fn delete(&self, hash: &str) -> Result<(), ImageStoreError>;
+    /// Metadata for `HEAD` requests; stores that cannot do better load the object.
+    async fn head(&self, hash: &str) -> Result<ImageMeta, ImageStoreError> {
+        let (bytes, mime) = self.load(hash).await?;
+        Ok(ImageMeta {
+            size: bytes.len() as u64,
+            mime,
+        })
+    }
+    /// Where blobs live, for operators; never includes credentials.
+    fn describe(&self) -> String {
+        "custom".to_string()
+    }
+    /// Confirm the backing store is reachable without changing it.
+    async fn check(&self) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    /// Move an object to `tier`; stores without storage classes ignore it.
+    async fn set_tier(&self, _hash: &str, _tier: StorageTier) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
 }
 
 pub fn is_valid_content_hash(hash: &str) -> bool {
@@ -42,15 +174,41 @@ fn resolve_content_type(content_type: Option<&str>, bytes: &[u8]) -> String {
         })
 }
 
+/// Id of the lifecycle rule rib manages; rules with other ids are the operator's.
+const LIFECYCLE_RULE_ID: &str = "rib-archived-media";
+
+/// `existing` lifecycle rules with rib's own replaced by `rule`, or `rule`
+/// appended when there was none.
+fn upsert_lifecycle_rule(
+    existing: Vec<aws_sdk_s3::types::LifecycleRule>,
+    rule: aws_sdk_s3::types::LifecycleRule,
+) -> Vec<aws_sdk_s3::types::LifecycleRule> {
+    let mut rules: Vec<_> = existing
+        .into_iter()
+        .filter(|r| r.id() != Some(LIFECYCLE_RULE_ID))
+        .collect();
+    rules.push(rule);
+    rules
+}
+
 // ---------------- S3 Implementation (MinIO compatible; ONLY supported backend) ----------------
 pub struct S3ImageStore {
     bucket: String,
     client: aws_sdk_s3::Client,
     prefix: String,
+    policy: ObjectPolicy,
 }
 
 impl S3ImageStore {
     pub async fn new() -> anyhow::Result<Self> {
+        let store = Self::connect().await?;
+        store.ensure_bucket().await?;
+        store.ensure_lifecycle().await?;
+        Ok(store)
+    }
+
+    /// A client for the configured endpoint and bucket; nothing is sent yet.
+    pub async fn connect() -> anyhow::Result<Self> {
         use aws_credential_types::provider::SharedCredentialsProvider;
         use aws_credential_types::Credentials;
 
@@ -58,9 +216,9 @@ impl S3ImageStore {
         let endpoint = std::env::var("S3_ENDPOINT")
             .map_err(|_| anyhow::anyhow!("S3_ENDPOINT must be set (MinIO / S3 endpoint)"))?;
         let region = std::env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".into());
-        let region_clone_for_hint = region.clone();
         let access = std::env::var("S3_ACCESS_KEY").unwrap_or_default();
         let secret = std::env::var("S3_SECRET_KEY").unwrap_or_default();
+        let policy = ObjectPolicy::from_env()?;
 
         // Use new defaults builder (avoids deprecation warning from from_env)
         let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest())
@@ -77,22 +235,36 @@ impl S3ImageStore {
             .build();
         let client = aws_sdk_s3::Client::from_conf(s3_conf);
         info!("Initialized S3/MinIO client (path-style addressing enabled)");
+        Ok(Self {
+            bucket,
+            client,
+            prefix: "images".into(),
+            policy,
+        })
+    }
 
-        // Ensure bucket exists (create if missing)
-        if let Err(e) = client.head_bucket().bucket(&bucket).send().await {
+    /// Create the bucket if it is missing.
+    async fn ensure_bucket(&self) -> anyhow::Result<()> {
+        let (client, bucket) = (&self.client, &self.bucket);
+        let region = client
+            .config()
+            .region()
+            .map(|r| r.to_string())
+            .unwrap_or_default();
+        if let Err(e) = client.head_bucket().bucket(bucket).send().await {
             warn!("head_bucket failed for '{bucket}' (will attempt create): {e:?}");
             let mut attempt = 0u32;
             let max_attempts = 8;
             loop {
                 attempt += 1;
-                match client.create_bucket().bucket(&bucket).send().await {
+                match client.create_bucket().bucket(bucket).send().await {
                     Ok(_) => {
                         info!("created bucket '{bucket}' (attempt {attempt})");
                         break;
                     }
                     Err(e2) => {
                         if attempt >= max_attempts {
-                            let region_hint = if region_clone_for_hint != "us-east-1" {
+                            let region_hint = if region != "us-east-1" {
                                 " (if this is not MinIO you may need a CreateBucketConfiguration for non-us-east-1 regions)"
                             } else {
                                 ""
@@ -111,13 +283,92 @@ impl S3ImageStore {
                 }
             }
         }
+        Ok(())
+    }
 
-        Ok(Self {
-            bucket,
-            client,
-            prefix: "images".into(),
-        })
+    /// Put the rule moving archived media to the lifecycle storage class
+    /// after `S3_LIFECYCLE_ARCHIVE_DAYS`. The bucket's other lifecycle rules
+    /// are read back and kept; the configuration is only written when the
+    /// days are set and the rule is missing or changed.
+    async fn ensure_lifecycle(&self) -> anyhow::Result<()> {
+        use aws_sdk_s3::types::{
+            BucketLifecycleConfiguration, ExpirationStatus, LifecycleRule,
+            LifecycleRuleAndOperator, LifecycleRuleFilter, Tag, Transition, TransitionStorageClass,
+        };
+        let Some(days) = self.policy.lifecycle_days else {
+            return Ok(());
+        };
+        let tag = Tag::builder()
+            .key(ARCHIVE_TAG.0)
+            .value(ARCHIVE_TAG.1)
+            .build()?;
+        let filter = LifecycleRuleFilter::builder()
+            .and(
+                LifecycleRuleAndOperator::builder()
+                    .prefix(format!("{}/", self.prefix))
+                    .tags(tag)
+                    .build(),
+            )
+            .build();
+        let rule = LifecycleRule::builder()
+            .id(LIFECYCLE_RULE_ID)
+            .status(ExpirationStatus::Enabled)
+            .filter(filter)
+            .transitions(
+                Transition::builder()
+                    .days(days)
+                    .storage_class(TransitionStorageClass::from(
+                        self.policy.lifecycle_storage_class.as_str(),
+                    ))
+                    .build(),
+            )
+            .build()?;
+        let (existing, minimum_size) = match self
+            .client
+            .get_bucket_lifecycle_configuration()
+            .bucket(&self.bucket)
+            .send()
+            .await
+        {
+            Ok(current) => (
+                current.rules().to_vec(),
+                current.transition_default_minimum_object_size().cloned(),
+            ),
+            Err(error)
+                if error
+                    .as_service_error()
+                    .is_some_and(|e| e.meta().code() == Some("NoSuchLifecycleConfiguration")) =>
+            {
+                (Vec::new(), None)
+            }
+            Err(e) => {
+                return Err(anyhow::anyhow!(
+                    "get_bucket_lifecycle_configuration failed: {e}"
+                ))
+            }
+        };
+        if existing.contains(&rule) {
+            return Ok(());
+        }
+        self.client
+            .put_bucket_lifecycle_configuration()
+            .bucket(&self.bucket)
+            .set_transition_default_minimum_object_size(minimum_size)
+            .lifecycle_configuration(
+                BucketLifecycleConfiguration::builder()
+                    .set_rules(Some(upsert_lifecycle_rule(existing, rule)))
+                    .build()?,
+            )
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("put_bucket_lifecycle_configuration failed: {e}"))?;
+        info!(
+            "archived media move to {} after {days} days",
+            self.policy.lifecycle_storage_class
+        );
+        Ok(())
     }
+
     fn key_for(&self, hash: &str) -> Result<String, ImageStoreError> {
         if !is_valid_content_hash(hash) {
             return Err(ImageStoreError::NotFound);
@@ -128,6 +379,28 @@ impl S3ImageStore {
 
 #[async_trait]
 impl ImageStore for S3ImageStore {
+    fn describe(&self) -> String {
+        format!("s3://{}/{}", self.bucket, self.prefix)
+    }
+
+    async fn check(&self) -> Result<(), ImageStoreError> {
+        self.client
+            .head_bucket()
+            .bucket(&self.bucket)
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|e| {
+                let mut message = e.to_string();
+                let mut source = std::error::Error::source(&e);
+                while let Some(cause) = source {
+                    message = format!("{message}: {cause}");
+                    source = cause.source();
+                }
+                ImageStoreError::Other(format!("bucket '{}': {message}", self.bucket))
+            })
+    }
+
     async fn save(&self, hash: &str, mime: &str, bytes: &[u8]) -> Result<(), ImageStoreError> {
         use aws_sdk_s3::primitives::ByteStream;
         let key = self.key_for(hash)?;
@@ -149,7 +422,9 @@ impl ImageStore for S3ImageStore {
             .bucket(&self.bucket)
             .key(&key)
             .body(ByteStream::from(bytes.to_vec()))
-            .content_type(mime);
+            .content_type(mime)
+            .set_tagging(Some(self.policy.tagging(StorageTier::Hot)).filter(|t| !t.is_empty()))
+            .set_storage_class(self.policy.storage_class(StorageTier::Hot).map(Into::into));
         if let Err(e) = put.send().await {
             // Log full debug (including SDK classification) but return concise error upstream
             error!(
@@ -188,6 +463,31 @@ impl ImageStore for S3ImageStore {
         let mime = resolve_content_type(content_type.as_deref(), &bytes);
         Ok((bytes, mime))
     }
+    async fn head(&self, hash: &str) -> Result<ImageMeta, ImageStoreError> {
+        let key = self.key_for(hash)?;
+        let obj = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .send()
+            .await
+            .map_err(|_| ImageStoreError::NotFound)?;
+        match (obj.content_length(), obj.content_type()) {
+            (Some(size), Some(mime)) if size >= 0 && !mime.trim().is_empty() => Ok(ImageMeta {
+                size: size as u64,
+                mime: mime.to_string(),
+            }),
+            // Objects stored without a type are sniffed from their bytes, as on GET.
+            _ => {
+                let (bytes, mime) = self.load(hash).await?;
+                Ok(ImageMeta {
+                    size: bytes.len() as u64,
+                    mime,
+                })
+            }
+        }
+    }
     async fn delete(&self, hash: &str) -> Result<(), ImageStoreError> {
         let key = self.key_for(hash)?;
         self.client
@@ -199,6 +499,38 @@ impl ImageStore for S3ImageStore {
             .map_err(|error| ImageStoreError::Other(error.to_string()))?;
         Ok(())
     }
+    /// Copy the object onto itself with the tier's storage class and tags;
+    /// S3 cannot change either in place.
+    async fn set_tier(&self, hash: &str, tier: StorageTier) -> Result<(), ImageStoreError> {
+        use aws_sdk_s3::types::{MetadataDirective, StorageClass, TaggingDirective};
+        let key = self.key_for(hash)?;
+        let class = self
+            .policy
+            .storage_class(tier)
+            .map_or(StorageClass::Standard, StorageClass::from);
+        self.client
+            .copy_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .copy_source(format!("{}/{}", self.bucket, key))
+            .metadata_directive(MetadataDirective::Copy)
+            .storage_class(class)
+            .tagging_directive(TaggingDirective::Replace)
+            .tagging(self.policy.tagging(tier))
+            .send()
+            .await
+            .map_err(|error| {
+                if error
+                    .as_service_error()
+                    .is_some_and(|e| e.meta().code() == Some("NoSuchKey"))
+                {
+                    ImageStoreError::NotFound
+                } else {
+                    ImageStoreError::Other(error.to_string())
+                }
+            })?;
+        Ok(())
+    }
 }
 
 // Factory helper used in main (now S3-only; panic early if misconfigured)
@@ -213,7 +545,57 @@ pub async fn build_image_store() -> Arc<dyn ImageStore> {
 
 #[cfg(test)]
 mod tests {
-    use super::resolve_content_type;
+    use super::{
+        parse_tags, resolve_content_type, upsert_lifecycle_rule, ObjectPolicy, StorageTier,
+        LIFECYCLE_RULE_ID,
+    };
+
+    #[test]
+    fn tagging_marks_archived_objects() {
+        let policy = ObjectPolicy {
+            tags: parse_tags("app=rib, cost center=media,rib-tier=hot").unwrap(),
+            archive_storage_class: Some("STANDARD_IA".into()),
+            ..ObjectPolicy::default()
+        };
+        assert_eq!(
+            policy.tagging(StorageTier::Hot),
+            "app=rib&cost%20center=media"
+        );
+        assert_eq!(
+            policy.tagging(StorageTier::Archive),
+            "app=rib&cost%20center=media&rib-tier=archive"
+        );
+        assert_eq!(policy.storage_class(StorageTier::Hot), None);
+        assert_eq!(
+            policy.storage_class(StorageTier::Archive),
+            Some("STANDARD_IA")
+        );
+        assert!(parse_tags("novalue").is_err());
+        assert!(parse_tags(&"k=v,".repeat(10)).is_err());
+    }
+
+    #[test]
+    fn lifecycle_rule_is_upserted_beside_the_operators_rules() {
+        use aws_sdk_s3::types::{ExpirationStatus, LifecycleRule, LifecycleRuleFilter};
+        let rule = |id: &str, prefix: &str| {
+            LifecycleRule::builder()
+                .id(id)
+                .filter(LifecycleRuleFilter::builder().prefix(prefix).build())
+                .status(ExpirationStatus::Enabled)
+                .build()
+                .unwrap()
+        };
+        let operators = rule("expire-logs", "logs/");
+        let merged = upsert_lifecycle_rule(
+            vec![operators.clone(), rule(LIFECYCLE_RULE_ID, "old/")],
+            rule(LIFECYCLE_RULE_ID, "images/"),
+        );
+        assert_eq!(merged, vec![operators, rule(LIFECYCLE_RULE_ID, "images/")]);
+        assert_eq!(
+            upsert_lifecycle_rule(Vec::new(), rule(LIFECYCLE_RULE_ID, "images/")).len(),
+            1
+        );
+    }
 
     #[test]
     fn content_type_prefers_stored_metadata() {
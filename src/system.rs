@@ -0,0 +1,262 @@
+//! Operational snapshot served at `GET /api/v1/admin/system`: uptime, build,
+//! database pools, image store, background jobs and the configuration read
+//! from the environment with secrets redacted.
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::storage::ImageStore;
+use crate::version::VersionInfo;
+
+/// Background jobs that report in, whether or not this process runs them.
+const JOBS: &[&str] = &[
+    "digests",
+    "scheduled_threads",
+    "saved_searches",
+    "outbox_relay",
+    "bridges",
+    "activitypub",
+    "matrix_mirrors",
+    "media_archive",
+    "role_expiry",
+];
+
+/// Environment prefixes reported in the configuration summary.
+const CONFIG_PREFIXES: &[&str] = &[
+    "ACCESS_POLICY_",
+    "ACTIVITYPUB_",
+    "BOT_",
+    "BRIDGE",
+    "BTC_",
+    "CHALLENGE_",
+    "CONFIG_",
+    "COOKIE_",
+    "CORS_",
+    "DATABASE_",
+    "DB_",
+    "DIGEST",
+    "DISABLED_FEATURES",
+    "DISCORD_",
+    "DUPLICATE_",
+    "EMAIL_",
+    "ENABLE_",
+    "ETH_",
+    "FRONTEND_",
+    "GRPC_",
+    "IMPERSONATION_",
+    "IMPORT_",
+    "JWT_",
+    "LIVE_",
+    "MAIL",
+    "MATRIX_",
+    "MEDIA_ARCHIVE_",
+    "METRICS_",
+    "NOSTR_",
+    "OUTBOX_",
+    "PG_",
+    "POW_",
+    "PREVIEW_",
+    "REPO_",
+    "RL_",
+    "ROBOTS_",
+    "ROLE_EXPIRY_",
+    "S3_",
+    "SAVED_SEARCH",
+    "SCHEDULED_",
+    "SEARCH_",
+    "SEED_",
+    "SENTRY_",
+    "SHED_",
+    "SITE",
+    "SLOW_",
+    "SMTP_",
+    "THROTTLE_",
+    "THUMBNAIL",
+    "TRIPCODE_",
+    "TRUST",
+    "UPLOAD_",
+];
+
+/// Name fragments whose values are never shown.
+const SECRET_MARKERS: &[&str] = &["SECRET", "PASSWORD", "TOKEN", "_KEY", "DSN"];
+
+struct JobState {
+    interval: Duration,
+    runs: u64,
+    last_run_at: Option<DateTime<Utc>>,
+}
+
+static JOB_STATES: Lazy<DashMap<&'static str, JobState>> = Lazy::new(DashMap::new);
+
+/// Record that a background job started polling every `interval`.
+pub fn job_started(name: &'static str, interval: Duration) {
+    JOB_STATES.insert(
+        name,
+        JobState {
+            interval,
+            runs: 0,
+            last_run_at: None,
+        },
+    );
+}
+
+/// Record one completed pass of a background job.
+pub fn job_ran(name: &'static str) {
+    if let Some(mut state) = JOB_STATES.get_mut(name) {
+        state.runs += 1;
+        state.last_run_at = Some(Utc::now());
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct JobStatus {
+    pub name: String,
+    /// Whether this process runs the job
+    pub running: bool,
+    pub interval_secs: Option<u64>,
+    /// Completed passes since startup
+    pub runs: u64,
+    pub last_run_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct PoolStats {
+    pub name: String,
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    pub max_connections: u32,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct SystemReport {
+    pub started_at: DateTime<Utc>,
+    pub uptime_secs: u64,
+    pub build: VersionInfo,
+    pub pools: Vec<PoolStats>,
+    pub image_store: String,
+    pub jobs: Vec<JobStatus>,
+    /// Configuration from the environment; secrets show as `[redacted]`
+    /// and passwords in URLs as `***`.
+    pub config: BTreeMap<String, String>,
+}
+
+/// Process facts the report needs beyond [`crate::routes::AppState`].
+pub struct SystemInfo {
+    started_at: DateTime<Utc>,
+    started: Instant,
+    pools: Vec<(&'static str, PgPool)>,
+}
+
+impl Default for SystemInfo {
+    fn default() -> Self {
+        Self {
+            started_at: Utc::now(),
+            started: Instant::now(),
+            pools: Vec::new(),
+        }
+    }
+}
+
+impl SystemInfo {
+    pub fn with_pool(mut self, name: &'static str, pool: PgPool) -> Self {
+        self.pools.push((name, pool));
+        self
+    }
+
+    pub fn report(&self, image_store: &dyn ImageStore) -> SystemReport {
+        SystemReport {
+            started_at: self.started_at,
+            uptime_secs: self.started.elapsed().as_secs(),
+            build: VersionInfo::current(),
+            pools: self
+                .pools
+                .iter()
+                .map(|(name, pool)| {
+                    let size = pool.size();
+                    let idle = pool.num_idle() as u32;
+                    PoolStats {
+                        name: name.to_string(),
+                        size,
+                        idle,
+                        in_use: size.saturating_sub(idle),
+                        max_connections: pool.options().get_max_connections(),
+                    }
+                })
+                .collect(),
+            image_store: image_store.describe(),
+            jobs: JOBS
+                .iter()
+                .map(|name| match JOB_STATES.get(name) {
+                    Some(state) => JobStatus {
+                        name: name.to_string(),
+                        running: true,
+                        interval_secs: Some(state.interval.as_secs()),
+                        runs: state.runs,
+                        last_run_at: state.last_run_at,
+                    },
+                    None => JobStatus {
+                        name: name.to_string(),
+                        running: false,
+                        interval_secs: None,
+                        runs: 0,
+                        last_run_at: None,
+                    },
+                })
+                .collect(),
+            config: config_summary(std::env::vars()),
+        }
+    }
+}
+
+fn config_summary(vars: impl Iterator<Item = (String, String)>) -> BTreeMap<String, String> {
+    vars.filter(|(name, _)| CONFIG_PREFIXES.iter().any(|p| name.starts_with(p)))
+        .map(|(name, value)| {
+            let value = redact(&name, value);
+            (name, value)
+        })
+        .collect()
+}
+
+fn redact(name: &str, value: String) -> String {
+    if SECRET_MARKERS.iter().any(|marker| name.contains(marker)) {
+        return "[redacted]".to_string();
+    }
+    match reqwest::Url::parse(&value) {
+        Ok(mut url) if url.password().is_some() => {
+            let _ = url.set_password(Some("***"));
+            url.to_string()
+        }
+        _ => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_keeps_rib_settings_and_hides_secrets() {
+        let vars = [
+            ("JWT_SECRET", "hunter2hunter2"),
+            ("S3_ACCESS_KEY", "AKIA"),
+            ("SENTRY_DSN", "https://key@sentry.example/1"),
+            ("DATABASE_URL", "postgres://rib:pw@db:5432/rib"),
+            ("DB_MAX_CONNECTIONS", "20"),
+            ("HOME", "/root"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        let summary = config_summary(vars.into_iter());
+        assert_eq!(summary["JWT_SECRET"], "[redacted]");
+        assert_eq!(summary["S3_ACCESS_KEY"], "[redacted]");
+        assert_eq!(summary["SENTRY_DSN"], "[redacted]");
+        assert_eq!(summary["DATABASE_URL"], "postgres://rib:***@db:5432/rib");
+        assert_eq!(summary["DB_MAX_CONNECTIONS"], "20");
+        assert!(!summary.contains_key("HOME"));
+    }
+}
@@ -0,0 +1,92 @@
+//! Thread tags.
+//!
+//! Tags are short lowercase words chosen when a thread is created. A board
+//! with a tag vocabulary accepts only its listed tags; a board without one
+//! takes any well-formed tag.
+
+/// Tags a thread may carry.
+pub const MAX_THREAD_TAGS: usize = 5;
+/// Tags a board's vocabulary may list.
+pub const MAX_VOCABULARY: usize = 100;
+const MAX_TAG_CHARS: usize = 32;
+
+/// Canonical form of a tag: lowercase ASCII letters, digits and dashes.
+pub fn normalize(tag: &str) -> Option<String> {
+    let tag = tag.trim().to_ascii_lowercase();
+    let well_formed = !tag.is_empty()
+        && tag.len() <= MAX_TAG_CHARS
+        && tag
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-');
+    well_formed.then_some(tag)
+}
+
+fn normalize_all(tags: &[String], max: usize) -> Result<Vec<String>, String> {
+    let mut out: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = normalize(tag).ok_or_else(|| {
+            format!("tags are 1 to {MAX_TAG_CHARS} letters, digits or dashes; got {tag:?}")
+        })?;
+        if !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+    if out.len() > max {
+        return Err(format!("at most {max} tags"));
+    }
+    Ok(out)
+}
+
+/// A new thread's tags in canonical form, deduplicated in order and checked
+/// against the board's `vocabulary`.
+pub fn thread_tags(tags: &[String], vocabulary: &[String]) -> Result<Vec<String>, String> {
+    let tags = normalize_all(tags, MAX_THREAD_TAGS)?;
+    if let Some(tag) = tags
+        .iter()
+        .find(|tag| !vocabulary.is_empty() && !vocabulary.contains(tag))
+    {
+        return Err(format!(
+            "{tag:?} is not a tag on this board; use one of: {}",
+            vocabulary.join(", ")
+        ));
+    }
+    Ok(tags)
+}
+
+/// A board's tag vocabulary in canonical form.
+pub fn vocabulary(tags: &[String]) -> Result<Vec<String>, String> {
+    normalize_all(tags, MAX_VOCABULARY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn tags_are_normalized_and_deduplicated() {
+        assert_eq!(normalize(" Help-Wanted "), Some("help-wanted".into()));
+        assert_eq!(normalize("two words"), None);
+        assert_eq!(normalize(""), None);
+        assert_eq!(normalize(&"x".repeat(33)), None);
+        assert_eq!(
+            thread_tags(&strings(&["Rust", "rust", "async"]), &[]).unwrap(),
+            strings(&["rust", "async"])
+        );
+        assert!(thread_tags(&strings(&["a", "b", "c", "d", "e", "f"]), &[]).is_err());
+    }
+
+    #[test]
+    fn vocabulary_limits_thread_tags() {
+        let vocabulary = strings(&["news", "meta"]);
+        assert_eq!(
+            thread_tags(&strings(&["META"]), &vocabulary).unwrap(),
+            strings(&["meta"])
+        );
+        assert!(thread_tags(&strings(&["other"]), &vocabulary).is_err());
+        assert!(thread_tags(&[], &vocabulary).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,240 @@
+//! Global concurrency limits, separate from the per-action rate limits.
+//!
+//! [`Throttle`] caps requests in flight across all workers and per client IP.
+//! Requests over either cap get `503` with `Retry-After` before they reach a
+//! handler, so a thundering herd queues at the edge instead of on the
+//! database pool. Health checks and metrics are never throttled.
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{Error, HttpResponse};
+use dashmap::DashMap;
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::error::ApiErrorBody;
+use crate::reload::Reloadable;
+use crate::routes::extract_client_ip;
+
+/// Paths answered even under overload.
+const EXEMPT_PATHS: &[&str] = &["/healthz", "/readyz", "/metrics"];
+
+#[derive(Clone, Debug)]
+pub struct ThrottleConfig {
+    /// Requests in flight across the process; unset disables the cap.
+    pub max_inflight: Option<usize>,
+    /// Requests in flight per client IP; unset disables the cap.
+    pub max_per_ip: Option<usize>,
+    /// Seconds sent in `Retry-After` with a throttled response.
+    pub retry_after: u64,
+}
+
+impl ThrottleConfig {
+    pub fn from_env() -> Self {
+        fn cap(name: &str) -> Option<usize> {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v| *v > 0)
+        }
+        Self {
+            max_inflight: cap("THROTTLE_MAX_INFLIGHT"),
+            max_per_ip: cap("THROTTLE_MAX_PER_IP"),
+            retry_after: std::env::var("THROTTLE_RETRY_AFTER_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1)
+                .max(1),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_inflight.is_some() || self.max_per_ip.is_some()
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    inflight: AtomicUsize,
+    per_ip: DashMap<String, usize>,
+}
+
+/// Middleware enforcing [`ThrottleConfig`]. Build it once and clone it into
+/// each worker's app so the counts are shared.
+#[derive(Clone)]
+pub struct Throttle {
+    cfg: Reloadable<ThrottleConfig>,
+    counters: Arc<Counters>,
+}
+
+impl Throttle {
+    pub fn new(cfg: impl Into<Reloadable<ThrottleConfig>>) -> Self {
+        Self {
+            cfg: cfg.into(),
+            counters: Arc::new(Counters::default()),
+        }
+    }
+
+    /// Take a slot for `ip` under `cfg`, or name the cap that is full.
+    fn acquire(&self, cfg: &ThrottleConfig, ip: Option<String>) -> Result<Slot, &'static str> {
+        let inflight = self.counters.inflight.fetch_add(1, Ordering::AcqRel) + 1;
+        let mut slot = Slot {
+            counters: self.counters.clone(),
+            ip: None,
+        };
+        if cfg.max_inflight.is_some_and(|max| inflight > max) {
+            return Err("global");
+        }
+        if let (Some(max), Some(ip)) = (cfg.max_per_ip, ip) {
+            let mut count = self.counters.per_ip.entry(ip.clone()).or_insert(0);
+            *count += 1;
+            let over = *count > max;
+            drop(count);
+            slot.ip = Some(ip);
+            if over {
+                return Err("ip");
+            }
+        }
+        Ok(slot)
+    }
+}
+
+/// Held while a request is served; gives its slots back on drop.
+struct Slot {
+    counters: Arc<Counters>,
+    ip: Option<String>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.counters.inflight.fetch_sub(1, Ordering::AcqRel);
+        if let Some(ip) = self.ip.take() {
+            self.counters.per_ip.remove_if_mut(&ip, |_, count| {
+                *count -= 1;
+                *count == 0
+            });
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Throttle
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ThrottleMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ThrottleMiddleware {
+            service: Rc::new(service),
+            throttle: self.clone(),
+        }))
+    }
+}
+
+pub struct ThrottleMiddleware<S> {
+    service: Rc<S>,
+    throttle: Throttle,
+}
+
+impl<S, B> Service<ServiceRequest> for ThrottleMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let cfg = self.throttle.cfg.current();
+        if !cfg.is_enabled() || EXEMPT_PATHS.contains(&req.path()) {
+            let fut = self.service.call(req);
+            return Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) });
+        }
+        let ip = cfg.max_per_ip.map(|_| extract_client_ip(req.request()));
+        match self.throttle.acquire(&cfg, ip) {
+            Ok(slot) => {
+                let fut = self.service.call(req);
+                Box::pin(async move {
+                    let res = fut.await;
+                    drop(slot);
+                    res.map(ServiceResponse::map_into_left_body)
+                })
+            }
+            Err(reason) => {
+                metrics::increment_counter!("requests_throttled", "reason" => reason);
+                let response = HttpResponse::ServiceUnavailable()
+                    .insert_header(("Retry-After", cfg.retry_after.to_string()))
+                    .json(ApiErrorBody {
+                        error: "server busy, retry shortly".to_string(),
+                        code: None,
+                    });
+                Box::pin(ready(Ok(req.into_response(response).map_into_right_body())))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App};
+    use std::time::Duration;
+
+    #[actix_web::test]
+    async fn requests_over_a_cap_are_turned_away() {
+        let throttle = Throttle::new(ThrottleConfig {
+            max_inflight: Some(3),
+            max_per_ip: Some(1),
+            retry_after: 7,
+        });
+        let app = test::init_service(App::new().wrap(throttle.clone()).route(
+            "/slow",
+            web::get().to(|| async {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                HttpResponse::Ok().finish()
+            }),
+        ))
+        .await;
+        let request = |ip: &str| {
+            test::TestRequest::get()
+                .uri("/slow")
+                .peer_addr(format!("{ip}:1000").parse().unwrap())
+                .to_request()
+        };
+        let (first, second, other) = futures_util::join!(
+            test::call_service(&app, request("10.0.0.1")),
+            test::call_service(&app, request("10.0.0.1")),
+            test::call_service(&app, request("10.0.0.2")),
+        );
+        assert_eq!(first.status(), 200);
+        assert_eq!(second.status(), 503);
+        assert_eq!(second.headers().get("Retry-After").unwrap(), "7");
+        assert_eq!(other.status(), 200);
+        assert_eq!(throttle.counters.inflight.load(Ordering::Acquire), 0);
+        assert!(throttle.counters.per_ip.is_empty());
+
+        let responses = futures_util::future::join_all(
+            (1..=5).map(|n| test::call_service(&app, request(&format!("10.0.1.{n}")))),
+        )
+        .await;
+        let busy = responses.iter().filter(|res| res.status() == 503).count();
+        assert_eq!(busy, 2);
+    }
+}
@@ -0,0 +1,118 @@
+//! Thumbnails of image uploads.
+//!
+//! Catalogs show many images at once, so each uploaded JPEG, PNG, WebP or GIF
+//! gets a small copy no larger than `THUMBNAIL_MAX_DIM` on either side. It is
+//! stored as a blob of its own and served as the image's thumbnail, the same
+//! way rendered document previews are.
+
+use std::io::Cursor;
+
+use image::{DynamicImage, ImageFormat, ImageReader, Limits};
+
+/// Image types that get a thumbnail; the first frame of a GIF is used.
+const THUMBNAIL_SOURCES: &[&str] = &["image/jpeg", "image/png", "image/webp", "image/gif"];
+
+/// Largest source image decoded, so a small file cannot claim a huge canvas.
+const MAX_SOURCE_DIM: u32 = 16_384;
+const MAX_DECODE_BYTES: u64 = 512 * 1024 * 1024;
+
+const JPEG_QUALITY: u8 = 80;
+
+#[derive(Clone, Debug)]
+pub struct ThumbnailConfig {
+    pub enabled: bool,
+    /// Longest side of a thumbnail in pixels; smaller images keep their size.
+    pub max_dim: u32,
+}
+
+impl ThumbnailConfig {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: std::env::var("THUMBNAILS_ENABLED")
+                .map(|v| !matches!(v.trim(), "0" | "false" | "off"))
+                .unwrap_or(true),
+            max_dim: std::env::var("THUMBNAIL_MAX_DIM")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(250u32)
+                .clamp(16, 2048),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            max_dim: 250,
+        }
+    }
+
+    /// Whether uploads of `mime` get a thumbnail.
+    pub fn renders(&self, mime: &str) -> bool {
+        self.enabled && THUMBNAIL_SOURCES.contains(&mime)
+    }
+}
+
+/// A thumbnail of `bytes` with its MIME type: a JPEG, or a PNG when the
+/// image has transparency. CPU bound; run it off the async executor.
+pub fn render(bytes: &[u8], max_dim: u32) -> anyhow::Result<(Vec<u8>, &'static str)> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_DIM);
+    limits.max_image_height = Some(MAX_SOURCE_DIM);
+    limits.max_alloc = Some(MAX_DECODE_BYTES);
+    let mut reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+    reader.limits(limits);
+    let image = reader.decode()?;
+    let image = if image.width() > max_dim || image.height() > max_dim {
+        image.thumbnail(max_dim, max_dim)
+    } else {
+        image
+    };
+    let mut out = Cursor::new(Vec::new());
+    if image.color().has_alpha() {
+        image.write_to(&mut out, ImageFormat::Png)?;
+        return Ok((out.into_inner(), "image/png"));
+    }
+    let rgb = DynamicImage::ImageRgb8(image.to_rgb8());
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY)
+        .encode_image(&rgb)?;
+    Ok((out.into_inner(), "image/jpeg"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgb, RgbImage, Rgba, RgbaImage};
+
+    fn encode(image: DynamicImage, format: ImageFormat) -> Vec<u8> {
+        let mut out = Cursor::new(Vec::new());
+        image.write_to(&mut out, format).unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn thumbnails_shrink_to_the_bound_keeping_the_aspect_ratio() {
+        let photo = encode(
+            DynamicImage::ImageRgb8(RgbImage::from_pixel(1000, 500, Rgb([200, 10, 10]))),
+            ImageFormat::Png,
+        );
+        let (thumb, mime) = render(&photo, 250).unwrap();
+        assert_eq!(mime, "image/jpeg");
+        assert_eq!(
+            image::load_from_memory(&thumb).unwrap().dimensions(),
+            (250, 125)
+        );
+
+        let icon = encode(
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(40, 30, Rgba([0, 0, 0, 0]))),
+            ImageFormat::Png,
+        );
+        let (thumb, mime) = render(&icon, 250).unwrap();
+        assert_eq!(mime, "image/png");
+        assert_eq!(
+            image::load_from_memory(&thumb).unwrap().dimensions(),
+            (40, 30)
+        );
+
+        assert!(render(b"not an image", 250).is_err());
+    }
+}
@@ -0,0 +1,586 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::auth::Role as AuthRole;
+use crate::models::{Board, Id, Image, Reply, SortOrder, Thread, ThreadOrder};
+use crate::repo::Repo;
+
+/// Value of `format` in the dump header.
+pub const DUMP_FORMAT: &str = "rib-export";
+/// Highest dump version this build writes and reads.
+pub const DUMP_VERSION: u32 = 1;
+
+const IMAGE_PAGE: i64 = 500;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpBoard {
+    pub id: Id,
+    pub slug: String,
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpThread {
+    pub id: Id,
+    pub board_id: Id,
+    pub subject: String,
+    pub body: String,
+    pub author_name: Option<String>,
+    pub tripcode: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub bump_time: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub created_by: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpReply {
+    pub id: Id,
+    pub thread_id: Id,
+    pub content: String,
+    pub author_name: Option<String>,
+    pub tripcode: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub created_by: Value,
+}
+
+/// Image metadata only; blobs are copied between image stores separately.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpImage {
+    pub hash: String,
+    pub mime: String,
+    pub thread_id: Option<Id>,
+    pub reply_id: Option<Id>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpRole {
+    pub subject: String,
+    pub role: String,
+}
+
+/// One line of an NDJSON dump (or one element of a JSON array dump).
+///
+/// Records are ordered so every reference points backwards: boards before
+/// their threads, threads before their replies, posts before their images.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DumpRecord {
+    Header {
+        format: String,
+        version: u32,
+        exported_at: DateTime<Utc>,
+    },
+    Role(DumpRole),
+    Board(DumpBoard),
+    Thread(DumpThread),
+    Reply(DumpReply),
+    Image(DumpImage),
+    /// Trailer carrying the number of data records, so truncated dumps are rejected.
+    End {
+        records: u64,
+    },
+}
+
+impl From<Board> for DumpBoard {
+    fn from(b: Board) -> Self {
+        Self {
+            id: b.id,
+            slug: b.slug,
+            title: b.title,
+            created_at: b.created_at,
+            deleted_at: b.deleted_at,
+        }
+    }
+}
+
+impl From<Thread> for DumpThread {
+    fn from(t: Thread) -> Self {
+        Self {
+            id: t.id,
+            board_id: t.board_id,
+            subject: t.subject,
+            body: t.body,
+            author_name: t.author_name,
+            tripcode: t.tripcode,
+            created_at: t.created_at,
+            bump_time: t.bump_time,
+            deleted_at: t.deleted_at,
+            created_by: t.created_by,
+        }
+    }
+}
+
+impl From<Reply> for DumpReply {
+    fn from(r: Reply) -> Self {
+        Self {
+            id: r.id,
+            thread_id: r.thread_id,
+            content: r.content,
+            author_name: r.author_name,
+            tripcode: r.tripcode,
+            created_at: r.created_at,
+            deleted_at: r.deleted_at,
+            created_by: r.created_by,
+        }
+    }
+}
+
+impl From<Image> for DumpImage {
+    fn from(i: Image) -> Self {
+        Self {
+            hash: i.hash,
+            mime: i.mime,
+            thread_id: i.thread_id,
+            reply_id: i.reply_id,
+        }
+    }
+}
+
+fn role_name(role: &AuthRole) -> &'static str {
+    match role {
+        AuthRole::Admin => "admin",
+        AuthRole::Moderator => "moderator",
+        AuthRole::User => "user",
+    }
+}
+
+pub fn parse_role(role: &str) -> Option<AuthRole> {
+    match role {
+        "admin" => Some(AuthRole::Admin),
+        "moderator" => Some(AuthRole::Moderator),
+        "user" => Some(AuthRole::User),
+        _ => None,
+    }
+}
+
+/// A validated dump, grouped by entity in import order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dump {
+    pub roles: Vec<DumpRole>,
+    pub boards: Vec<DumpBoard>,
+    pub threads: Vec<DumpThread>,
+    pub replies: Vec<DumpReply>,
+    pub images: Vec<DumpImage>,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum DumpError {
+    #[error("record {0}: {1}")]
+    Malformed(usize, String),
+    #[error("dump must start with a {DUMP_FORMAT} header")]
+    MissingHeader,
+    #[error("unsupported dump version {0}")]
+    UnsupportedVersion(u32),
+    #[error("dump is truncated: missing or mismatched end record")]
+    Truncated,
+    #[error("{0}")]
+    Invalid(String),
+}
+
+impl Dump {
+    /// Parse an NDJSON dump, or a JSON array of records when the body starts with `[`.
+    pub fn parse(body: &[u8]) -> Result<Self, DumpError> {
+        let text = std::str::from_utf8(body)
+            .map_err(|_| DumpError::Invalid("dump is not valid UTF-8".into()))?;
+        let records = if text.trim_start().starts_with('[') {
+            serde_json::from_str::<Vec<DumpRecord>>(text)
+                .map_err(|e| DumpError::Malformed(e.line(), e.to_string()))?
+        } else {
+            text.lines()
+                .enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(n, line)| {
+                    serde_json::from_str(line)
+                        .map_err(|e| DumpError::Malformed(n + 1, e.to_string()))
+                })
+                .collect::<Result<_, _>>()?
+        };
+        Self::from_records(records)
+    }
+
+    pub fn from_records(records: Vec<DumpRecord>) -> Result<Self, DumpError> {
+        let mut iter = records.into_iter();
+        match iter.next() {
+            Some(DumpRecord::Header {
+                format, version, ..
+            }) if format == DUMP_FORMAT => {
+                if version == 0 || version > DUMP_VERSION {
+                    return Err(DumpError::UnsupportedVersion(version));
+                }
+            }
+            _ => return Err(DumpError::MissingHeader),
+        }
+        let mut dump = Dump::default();
+        let mut count = 0u64;
+        let mut ended = false;
+        for (n, record) in iter.enumerate() {
+            if ended {
+                return Err(DumpError::Malformed(n + 2, "record after end".into()));
+            }
+            match record {
+                DumpRecord::Header { .. } => {
+                    return Err(DumpError::Malformed(n + 2, "duplicate header".into()))
+                }
+                DumpRecord::End { records } => {
+                    if records != count {
+                        return Err(DumpError::Truncated);
+                    }
+                    ended = true;
+                    continue;
+                }
+                DumpRecord::Role(r) => dump.roles.push(r),
+                DumpRecord::Board(b) => dump.boards.push(b),
+                DumpRecord::Thread(t) => dump.threads.push(t),
+                DumpRecord::Reply(r) => dump.replies.push(r),
+                DumpRecord::Image(i) => dump.images.push(i),
+            }
+            count += 1;
+        }
+        if !ended {
+            return Err(DumpError::Truncated);
+        }
+        dump.validate()?;
+        Ok(dump)
+    }
+
+    /// Check ids are unique per entity and every reference resolves within the dump.
+    pub fn validate(&self) -> Result<(), DumpError> {
+        fn unique(kind: &str, ids: impl Iterator<Item = Id>) -> Result<HashSet<Id>, DumpError> {
+            let mut seen = HashSet::new();
+            for id in ids {
+                if !seen.insert(id) {
+                    return Err(DumpError::Invalid(format!("duplicate {kind} id {id}")));
+                }
+            }
+            Ok(seen)
+        }
+        for role in &self.roles {
+            if parse_role(&role.role).is_none() {
+                return Err(DumpError::Invalid(format!(
+                    "unknown role '{}' for {}",
+                    role.role, role.subject
+                )));
+            }
+        }
+        let boards = unique("board", self.boards.iter().map(|b| b.id))?;
+        let threads = unique("thread", self.threads.iter().map(|t| t.id))?;
+        let replies = unique("reply", self.replies.iter().map(|r| r.id))?;
+        if let Some(t) = self.threads.iter().find(|t| !boards.contains(&t.board_id)) {
+            return Err(DumpError::Invalid(format!(
+                "thread {} references unknown board {}",
+                t.id, t.board_id
+            )));
+        }
+        if let Some(r) = self
+            .replies
+            .iter()
+            .find(|r| !threads.contains(&r.thread_id))
+        {
+            return Err(DumpError::Invalid(format!(
+                "reply {} references unknown thread {}",
+                r.id, r.thread_id
+            )));
+        }
+        let mut owners = HashSet::new();
+        for image in &self.images {
+            let owner_known = match (image.thread_id, image.reply_id) {
+                (Some(t), None) => threads.contains(&t),
+                (None, Some(r)) => replies.contains(&r),
+                _ => false,
+            };
+            if !owner_known {
+                return Err(DumpError::Invalid(format!(
+                    "image {} must reference exactly one known thread or reply",
+                    image.hash
+                )));
+            }
+            if !owners.insert((image.thread_id, image.reply_id)) {
+                return Err(DumpError::Invalid(format!(
+                    "post has more than one image: {}",
+                    image.hash
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What to do when an imported row collides with existing data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Report every conflict and import nothing.
+    #[default]
+    Fail,
+    /// Keep existing rows; skip conflicting boards with all their posts.
+    Skip,
+    /// Import posts into the existing board with the same slug and overwrite roles.
+    Merge,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, IntoParams)]
+pub struct ImportOptions {
+    /// Validate and apply inside a transaction that is always rolled back
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Conflict handling: `fail` (default), `skip`, or `merge`
+    #[serde(default)]
+    pub on_conflict: ConflictStrategy,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ImportCounts {
+    pub created: u64,
+    pub merged: u64,
+    pub skipped: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub on_conflict: ConflictStrategy,
+    /// False when the import was rolled back (dry run or conflicts under `fail`).
+    pub committed: bool,
+    pub boards: ImportCounts,
+    pub threads: ImportCounts,
+    pub replies: ImportCounts,
+    pub images: ImportCounts,
+    pub roles: ImportCounts,
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Ndjson,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, IntoParams)]
+pub struct ExportQuery {
+    /// `ndjson` (default, one record per line) or `json` (array of records)
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+impl ExportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Ndjson => "application/x-ndjson",
+            ExportFormat::Json => "application/json",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+type Chunk = Result<actix_web::web::Bytes, std::io::Error>;
+
+struct DumpWriter {
+    tx: tokio::sync::mpsc::Sender<Chunk>,
+    format: ExportFormat,
+    written: u64,
+}
+
+impl DumpWriter {
+    /// Returns false once the client has gone away.
+    async fn write(&mut self, record: &DumpRecord) -> bool {
+        let mut line = match self.format {
+            ExportFormat::Ndjson => String::new(),
+            ExportFormat::Json if self.written == 0 => "[\n".to_string(),
+            ExportFormat::Json => ",\n".to_string(),
+        };
+        line.push_str(&serde_json::to_string(record).unwrap_or_default());
+        if self.format == ExportFormat::Ndjson {
+            line.push('\n');
+        }
+        self.written += 1;
+        self.tx.send(Ok(line.into())).await.is_ok()
+    }
+
+    async fn fail(&self, error: impl std::fmt::Display) {
+        log::error!("export aborted: {error}");
+        // An error chunk makes the server drop the connection instead of ending
+        // the body cleanly, so clients cannot mistake it for a complete dump.
+        let _ = self
+            .tx
+            .send(Err(std::io::Error::other(error.to_string())))
+            .await;
+    }
+}
+
+async fn write_dump(repo: Arc<dyn Repo>, out: &mut DumpWriter) -> anyhow::Result<()> {
+    macro_rules! emit {
+        ($record:expr) => {
+            if !out.write(&$record).await {
+                return Ok(());
+            }
+        };
+    }
+    emit!(DumpRecord::Header {
+        format: DUMP_FORMAT.into(),
+        version: DUMP_VERSION,
+        exported_at: Utc::now(),
+    });
+    for (subject, role, _) in repo.list_roles().await? {
+        emit!(DumpRecord::Role(DumpRole {
+            subject,
+            role: role_name(&role).into(),
+        }));
+    }
+    // One board's threads and one thread's replies are held at a time.
+    for board in repo.list_boards(true).await? {
+        let board_id = board.id;
+        emit!(DumpRecord::Board(board.into()));
+        for thread in repo
+            .list_threads(board_id, true, ThreadOrder::default())
+            .await?
+        {
+            let thread_id = thread.id;
+            emit!(DumpRecord::Thread(thread.into()));
+            for reply in repo.list_replies(thread_id, true, SortOrder::Asc).await? {
+                emit!(DumpRecord::Reply(reply.into()));
+            }
+        }
+    }
+    let mut after = 0;
+    loop {
+        let page = repo.list_images_after(after, IMAGE_PAGE).await?;
+        let Some(last) = page.last() else { break };
+        after = last.id;
+        for image in page {
+            emit!(DumpRecord::Image(image.into()));
+        }
+    }
+    let records = out.written - 1;
+    emit!(DumpRecord::End { records });
+    if out.format == ExportFormat::Json {
+        let _ = out.tx.send(Ok("\n]\n".into())).await;
+    }
+    Ok(())
+}
+
+/// Stream a full dump; the export runs in a background task fed back through a
+/// small channel, so memory stays bounded by one thread's replies.
+pub fn export_stream(
+    repo: Arc<dyn Repo>,
+    format: ExportFormat,
+) -> impl futures_util::Stream<Item = Chunk> {
+    let (tx, rx) = tokio::sync::mpsc::channel(32);
+    actix_web::rt::spawn(async move {
+        let mut out = DumpWriter {
+            tx,
+            format,
+            written: 0,
+        };
+        if let Err(e) = write_dump(repo, &mut out).await {
+            out.fail(e).await;
+        }
+    });
+    futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    })
+}
+
+/// Upper bound on import bodies, from `IMPORT_MAX_BYTES` (default 64 MiB).
+pub fn import_max_bytes() -> usize {
+    std::env::var("IMPORT_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(64 * 1024 * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> DumpRecord {
+        DumpRecord::Header {
+            format: DUMP_FORMAT.into(),
+            version: DUMP_VERSION,
+            exported_at: Utc::now(),
+        }
+    }
+
+    fn board(id: Id) -> DumpRecord {
+        DumpRecord::Board(DumpBoard {
+            id,
+            slug: format!("b{id}"),
+            title: "Board".into(),
+            created_at: Utc::now(),
+            deleted_at: None,
+        })
+    }
+
+    fn thread(id: Id, board_id: Id) -> DumpRecord {
+        DumpRecord::Thread(DumpThread {
+            id,
+            board_id,
+            subject: "s".into(),
+            body: "b".into(),
+            author_name: None,
+            tripcode: None,
+            created_at: Utc::now(),
+            bump_time: Utc::now(),
+            deleted_at: None,
+            created_by: Value::Null,
+        })
+    }
+
+    fn ndjson(records: &[DumpRecord]) -> Vec<u8> {
+        records
+            .iter()
+            .map(|r| serde_json::to_string(r).unwrap() + "\n")
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    #[test]
+    fn ndjson_and_json_arrays_parse_to_the_same_dump() {
+        let records = vec![
+            header(),
+            board(1),
+            thread(2, 1),
+            DumpRecord::End { records: 2 },
+        ];
+        let from_lines = Dump::parse(&ndjson(&records)).unwrap();
+        let from_array = Dump::parse(&serde_json::to_vec(&records).unwrap()).unwrap();
+        assert_eq!(from_lines, from_array);
+        assert_eq!(from_lines.threads.len(), 1);
+    }
+
+    #[test]
+    fn rejects_truncated_and_dangling_dumps() {
+        let truncated = Dump::parse(&ndjson(&[header(), board(1)]));
+        assert_eq!(truncated, Err(DumpError::Truncated));
+        let dangling = Dump::parse(&ndjson(&[
+            header(),
+            thread(2, 9),
+            DumpRecord::End { records: 1 },
+        ]));
+        assert!(matches!(dangling, Err(DumpError::Invalid(_))));
+        let future = Dump::parse(&ndjson(&[DumpRecord::Header {
+            format: DUMP_FORMAT.into(),
+            version: DUMP_VERSION + 1,
+            exported_at: Utc::now(),
+        }]));
+        assert_eq!(future, Err(DumpError::UnsupportedVersion(DUMP_VERSION + 1)));
+    }
+}
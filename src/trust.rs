@@ -0,0 +1,193 @@
+//! Per-subject trust scores that scale posting friction.
+//!
+//! Database triggers keep each subject's history in `subject_trust`: when it
+//! was first seen, how many posts it made, how many of those staff removed,
+//! and how often it was banned. [`TrustConfig::score`] weighs that history
+//! into a score from 0 to 1. The score scales the posting rate limits, lets
+//! trusted anonymous posters skip the proof of work, and holds posts from
+//! low-trust subjects for review under `/api/v1/admin/held-posts`. Staff
+//! count as fully trusted. The defaults change no friction at all.
+
+use chrono::{DateTime, Utc};
+
+use crate::models::SubjectTrust;
+
+/// Held posts returned per queue page.
+pub const MAX_HELD_QUEUE: i64 = 100;
+
+#[derive(Debug, Clone)]
+pub struct TrustConfig {
+    /// Days since first seen that earn the full age credit.
+    pub full_age_days: f64,
+    /// Posts that earn the full activity credit.
+    pub full_posts: f64,
+    pub age_weight: f64,
+    pub posts_weight: f64,
+    /// Subtracted per removed post.
+    pub removal_penalty: f64,
+    /// Subtracted per ban.
+    pub ban_penalty: f64,
+    /// Rate limits are multiplied by a factor from `min_rate_factor` at
+    /// score 0 to `max_rate_factor` at score 1.
+    pub min_rate_factor: f64,
+    pub max_rate_factor: f64,
+    /// Anonymous posters scoring at least this skip the proof of work.
+    pub skip_pow_at: Option<f64>,
+    /// Posts from subjects scoring below this are held for review.
+    pub hold_below: Option<f64>,
+}
+
+/// How a post is admitted, derived from its poster's score.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Friction {
+    pub rate_factor: f64,
+    pub skip_pow: bool,
+    pub hold: bool,
+}
+
+impl Default for Friction {
+    fn default() -> Self {
+        Self {
+            rate_factor: 1.0,
+            skip_pow: false,
+            hold: false,
+        }
+    }
+}
+
+impl TrustConfig {
+    /// Default weights with no effect on friction; the default for states
+    /// built without [`crate::routes::AppState::with_trust`].
+    pub fn disabled() -> Self {
+        Self {
+            full_age_days: 30.0,
+            full_posts: 50.0,
+            age_weight: 0.5,
+            posts_weight: 0.5,
+            removal_penalty: 0.1,
+            ban_penalty: 0.25,
+            min_rate_factor: 1.0,
+            max_rate_factor: 1.0,
+            skip_pow_at: None,
+            hold_below: None,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        fn f64_env(name: &str) -> Option<f64> {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v: &f64| v.is_finite() && *v >= 0.0)
+        }
+        let defaults = Self::disabled();
+        Self {
+            full_age_days: f64_env("TRUST_FULL_AGE_DAYS").unwrap_or(defaults.full_age_days),
+            full_posts: f64_env("TRUST_FULL_POSTS").unwrap_or(defaults.full_posts),
+            age_weight: f64_env("TRUST_AGE_WEIGHT").unwrap_or(defaults.age_weight),
+            posts_weight: f64_env("TRUST_POSTS_WEIGHT").unwrap_or(defaults.posts_weight),
+            removal_penalty: f64_env("TRUST_REMOVAL_PENALTY").unwrap_or(defaults.removal_penalty),
+            ban_penalty: f64_env("TRUST_BAN_PENALTY").unwrap_or(defaults.ban_penalty),
+            min_rate_factor: f64_env("TRUST_RATE_FACTOR_MIN").unwrap_or(defaults.min_rate_factor),
+            max_rate_factor: f64_env("TRUST_RATE_FACTOR_MAX").unwrap_or(defaults.max_rate_factor),
+            skip_pow_at: f64_env("TRUST_SKIP_POW_SCORE"),
+            hold_below: f64_env("TRUST_HOLD_BELOW"),
+        }
+    }
+
+    /// Whether scores change any friction; they are not looked up otherwise.
+    pub fn is_active(&self) -> bool {
+        self.min_rate_factor != 1.0
+            || self.max_rate_factor != 1.0
+            || self.skip_pow_at.is_some()
+            || self.hold_below.is_some()
+    }
+
+    /// Score from 0 to 1; subjects without history score 0.
+    pub fn score(&self, history: Option<&SubjectTrust>, now: DateTime<Utc>) -> f64 {
+        let Some(history) = history else {
+            return 0.0;
+        };
+        fn credit(value: f64, full: f64) -> f64 {
+            if full <= 0.0 {
+                1.0
+            } else {
+                (value / full).clamp(0.0, 1.0)
+            }
+        }
+        let age_days = (now - history.first_seen_at).num_seconds() as f64 / 86_400.0;
+        let score = self.age_weight * credit(age_days, self.full_age_days)
+            + self.posts_weight * credit(history.posts as f64, self.full_posts)
+            - self.removal_penalty * history.removals as f64
+            - self.ban_penalty * history.bans as f64;
+        score.clamp(0.0, 1.0)
+    }
+
+    pub fn friction(&self, score: f64) -> Friction {
+        Friction {
+            rate_factor: self.min_rate_factor
+                + (self.max_rate_factor - self.min_rate_factor) * score,
+            skip_pow: self.skip_pow_at.is_some_and(|at| score >= at),
+            hold: self.hold_below.is_some_and(|below| score < below),
+        }
+    }
+}
+
+/// A subject's history with its current score, for staff.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct TrustReport {
+    pub subject: String,
+    /// Unset when the subject was never seen
+    pub history: Option<SubjectTrust>,
+    pub score: f64,
+    pub rate_factor: f64,
+    pub skip_pow: bool,
+    pub hold: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn history(age_days: i64, posts: i64, removals: i64, bans: i64) -> SubjectTrust {
+        SubjectTrust {
+            subject: "discord:1".into(),
+            first_seen_at: Utc::now() - Duration::days(age_days),
+            posts,
+            removals,
+            bans,
+        }
+    }
+
+    #[test]
+    fn scores_weigh_age_posts_and_moderation() {
+        let cfg = TrustConfig::disabled();
+        let now = Utc::now();
+        assert_eq!(cfg.score(None, now), 0.0);
+        assert!((cfg.score(Some(&history(15, 25, 0, 0)), now) - 0.5).abs() < 0.01);
+        assert_eq!(cfg.score(Some(&history(90, 500, 0, 0)), now), 1.0);
+        assert!((cfg.score(Some(&history(90, 500, 2, 1)), now) - 0.55).abs() < 0.01);
+        assert_eq!(cfg.score(Some(&history(1, 1, 0, 5)), now), 0.0);
+    }
+
+    #[test]
+    fn friction_follows_the_configured_thresholds() {
+        assert!(!TrustConfig::disabled().is_active());
+        assert_eq!(TrustConfig::disabled().friction(0.0), Friction::default());
+        let cfg = TrustConfig {
+            min_rate_factor: 0.5,
+            max_rate_factor: 2.0,
+            skip_pow_at: Some(0.8),
+            hold_below: Some(0.2),
+            ..TrustConfig::disabled()
+        };
+        assert!(cfg.is_active());
+        let low = cfg.friction(0.1);
+        assert!((low.rate_factor - 0.65).abs() < 1e-9);
+        assert!(low.hold && !low.skip_pow);
+        let high = cfg.friction(1.0);
+        assert_eq!(high.rate_factor, 2.0);
+        assert!(high.skip_pow && !high.hold);
+    }
+}
@@ -0,0 +1,903 @@
+//! Upload bookkeeping and quotas.
+//!
+//! Every accepted upload is recorded in `uploads` with its uploader and byte
+//! size. Posts copy the size onto their `images` row, so attachment JSON
+//! reports it without asking object storage. When
+//! `UPLOAD_QUOTA_BYTES` is set, each subject may upload at most that many
+//! bytes per `UPLOAD_QUOTA_WINDOW_SECS`; a blob is counted once per uploader.
+//!
+//! The client's file name is kept next to the sniffed type. When the name's
+//! extension points at a different kind of file than the bytes do, or media
+//! carries markup that a browser could run, [`MismatchPolicy`] decides whether
+//! the upload is logged, flagged for review, quarantined, or refused.
+//!
+//! Accepted types come from `UPLOAD_ALLOWED_MIME` ([`MimeAllowlist`]); a board
+//! can narrow them with its own `allowed_mime` list.
+//!
+//! Uploading takes at least `UPLOAD_MIN_ROLE`, and each role may get its own
+//! size limit and type list ([`RolePolicies`]) within the deployment's.
+//!
+//! With `UPLOAD_REMOTE_ENABLED`, files can also be uploaded by URL; the
+//! server fetches them under [`RemoteFetchPolicy`] (see `remote_media`).
+//!
+//! Zip, tar and gzip uploads are opened and listed before they are stored;
+//! [`ArchivePolicy`] bounds what they may unpack to (see `archives`).
+
+use actix_web::http::header::{HeaderMap, CONTENT_LENGTH};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+use crate::auth::Role;
+use crate::models::UploadUsage;
+
+/// Room for multipart boundaries and part headers when comparing a request's
+/// `Content-Length` with a file size budget.
+pub const MULTIPART_OVERHEAD: u64 = 16 * 1024;
+
+/// `Content-Length` declared by a request or a multipart part, if any.
+pub fn declared_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Whether a multipart request declaring `declared` bytes must carry a file
+/// larger than `limit`; such requests are refused before the body is read.
+pub fn request_exceeds(declared: Option<u64>, limit: u64) -> bool {
+    declared.is_some_and(|len| len > limit.saturating_add(MULTIPART_OVERHEAD))
+}
+
+/// Longest client file name kept.
+pub const MAX_FILE_NAME_CHARS: usize = 255;
+
+/// What happens to an upload whose name and content disagree, or to a
+/// dangerous archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MismatchPolicy {
+    Off,
+    /// Log and count the mismatch only.
+    Warn,
+    /// Also record the reason on the upload for staff.
+    Flag,
+    /// Record the reason and quarantine the blob until staff review it.
+    Quarantine,
+    /// Refuse the upload with `415`.
+    Reject,
+}
+
+impl MismatchPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "off" => Some(Self::Off),
+            "warn" => Some(Self::Warn),
+            "flag" => Some(Self::Flag),
+            "quarantine" => Some(Self::Quarantine),
+            "reject" => Some(Self::Reject),
+            _ => None,
+        }
+    }
+
+    /// `UPLOAD_EXTENSION_POLICY`, `warn` when unset or unknown.
+    pub fn from_env() -> Self {
+        match std::env::var("UPLOAD_EXTENSION_POLICY") {
+            Ok(value) => Self::parse(&value).unwrap_or_else(|| {
+                log::warn!("unknown UPLOAD_EXTENSION_POLICY {value:?}; using warn");
+                Self::Warn
+            }),
+            Err(_) => Self::Warn,
+        }
+    }
+}
+
+/// Broad kinds of file; names and content only have to agree on these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Family {
+    Image,
+    Video,
+    Audio,
+    Document,
+    Text,
+    Archive,
+    Executable,
+}
+
+fn extension_family(extension: &str) -> Option<Family> {
+    use Family::*;
+    Some(match extension {
+        "png" | "jpg" | "jpeg" | "jfif" | "gif" | "webp" | "bmp" | "tif" | "tiff" | "ico"
+        | "avif" | "heic" => Image,
+        "mp4" | "m4v" | "webm" | "mkv" | "avi" | "mov" | "wmv" | "flv" => Video,
+        "mp3" | "wav" | "ogg" | "oga" | "opus" | "flac" | "aac" | "m4a" => Audio,
+        "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "rtf" | "odt" | "ods"
+        | "odp" | "epub" => Document,
+        "txt" | "md" | "csv" | "tsv" | "log" | "json" | "xml" | "yaml" | "yml" | "toml"
+        | "html" | "htm" | "css" | "js" | "svg" | "ini" => Text,
+        "zip" | "rar" | "7z" | "tar" | "gz" | "tgz" | "bz2" | "xz" => Archive,
+        "exe" | "dll" | "msi" | "com" | "scr" | "elf" | "so" | "dylib" | "apk" | "dex" => {
+            Executable
+        }
+        _ => return None,
+    })
+}
+
+fn mime_family(mime: &str) -> Option<Family> {
+    use Family::*;
+    Some(match mime {
+        "image/svg+xml" => Text,
+        m if m.starts_with("image/") => Image,
+        m if m.starts_with("video/") => Video,
+        m if m.starts_with("audio/") => Audio,
+        m if m.starts_with("text/") => Text,
+        "application/json" | "application/xml" | "application/yaml" => Text,
+        "application/pdf"
+        | "application/msword"
+        | "application/rtf"
+        | "application/epub+zip"
+        | "application/vnd.ms-excel"
+        | "application/vnd.ms-powerpoint" => Document,
+        m if m.starts_with("application/vnd.openxmlformats-officedocument")
+            || m.starts_with("application/vnd.oasis.opendocument") =>
+        {
+            Document
+        }
+        "application/zip"
+        | "application/x-rar-compressed"
+        | "application/x-7z-compressed"
+        | "application/x-tar"
+        | "application/gzip"
+        | "application/x-bzip2"
+        | "application/x-xz" => Archive,
+        "application/vnd.microsoft.portable-executable"
+        | "application/x-msdownload"
+        | "application/x-executable"
+        | "application/x-sharedlib"
+        | "application/x-mach-binary"
+        | "application/vnd.android.dex"
+        | "application/vnd.android.package-archive" => Executable,
+        _ => return None,
+    })
+}
+
+/// Markup that makes a browser run code when a file is rendered as a page.
+const ACTIVE_MARKERS: &[&[u8]] = &[b"<script", b"<html", b"<?php", b"<iframe", b"javascript:"];
+/// Bytes at each end of a media file searched for [`ACTIVE_MARKERS`].
+const MARKER_WINDOW: usize = 1024;
+
+fn has_active_markup(bytes: &[u8]) -> bool {
+    let head = &bytes[..bytes.len().min(MARKER_WINDOW)];
+    let tail = &bytes[bytes.len().saturating_sub(MARKER_WINDOW)..];
+    [head, tail].iter().any(|window| {
+        let lowered = window.to_ascii_lowercase();
+        ACTIVE_MARKERS
+            .iter()
+            .any(|marker| lowered.windows(marker.len()).any(|w| w == *marker))
+    })
+}
+
+/// Why a file named `file_name` does not look like its sniffed `mime`, if it
+/// does not. Unknown extensions and content that sniffs as nothing in
+/// particular are given the benefit of the doubt.
+pub fn content_mismatch(file_name: Option<&str>, mime: &str, bytes: &[u8]) -> Option<String> {
+    let sniffed = mime_family(mime);
+    if matches!(sniffed, Some(Family::Image | Family::Video | Family::Audio))
+        && has_active_markup(bytes)
+    {
+        return Some(format!("{mime} content carries HTML or script markup"));
+    }
+    let extension = file_name?
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())?;
+    let named = extension_family(&extension);
+    match (named, sniffed) {
+        (named, Some(Family::Executable)) if named != Some(Family::Executable) => {
+            Some(format!("executable content ({mime}) named .{extension}"))
+        }
+        // Office documents are zip containers underneath.
+        (Some(Family::Document), Some(Family::Archive))
+        | (Some(Family::Archive), Some(Family::Document)) => None,
+        (Some(named), Some(sniffed)) if named != sniffed => Some(format!(
+            "extension .{extension} does not match {mime} content"
+        )),
+        _ => None,
+    }
+}
+
+/// Types accepted when `UPLOAD_ALLOWED_MIME` is unset (the `default` category).
+pub const DEFAULT_MIME: &[&str] = &[
+    // Images
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "image/bmp",
+    "image/tiff",
+    "image/svg+xml",
+    // Videos
+    "video/mp4",
+    "video/webm",
+    "video/avi",
+    "video/mov",
+    "video/wmv",
+    "video/flv",
+    // Audio
+    "audio/mpeg",
+    "audio/wav",
+    "audio/ogg",
+    "audio/flac",
+    "audio/aac",
+    "audio/m4a",
+    // Documents
+    "application/pdf",
+    "application/msword",
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    "application/vnd.ms-excel",
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    "application/vnd.ms-powerpoint",
+    "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    "application/rtf",
+    "application/vnd.oasis.opendocument.text",
+    "application/vnd.oasis.opendocument.spreadsheet",
+    "application/vnd.oasis.opendocument.presentation",
+    // Plain text and code
+    "text/plain",
+    "text/csv",
+    "text/html",
+    "text/css",
+    "text/javascript",
+    "application/json",
+    "application/xml",
+    "text/xml",
+    "application/yaml",
+    // Archives
+    "application/zip",
+    "application/x-rar-compressed",
+    "application/x-7z-compressed",
+    "application/x-tar",
+    "application/gzip",
+    "application/x-bzip2",
+    // Other common formats
+    "application/octet-stream", // Generic binary
+];
+
+const IMAGES: &[&str] = &[
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "image/bmp",
+    "image/tiff",
+];
+const VIDEO: &[&str] = &[
+    "video/mp4",
+    "video/webm",
+    "video/avi",
+    "video/mov",
+    "video/wmv",
+    "video/flv",
+];
+const AUDIO: &[&str] = &[
+    "audio/mpeg",
+    "audio/wav",
+    "audio/ogg",
+    "audio/flac",
+    "audio/aac",
+    "audio/m4a",
+];
+pub(crate) const DOCUMENTS: &[&str] = &[
+    "application/pdf",
+    "application/msword",
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    "application/vnd.ms-excel",
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    "application/vnd.ms-powerpoint",
+    "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    "application/rtf",
+    "application/vnd.oasis.opendocument.text",
+    "application/vnd.oasis.opendocument.spreadsheet",
+    "application/vnd.oasis.opendocument.presentation",
+];
+/// Plain text and data formats; markup a browser would run is left out.
+const TEXT: &[&str] = &[
+    "text/plain",
+    "text/csv",
+    "application/json",
+    "application/xml",
+    "text/xml",
+    "application/yaml",
+];
+const ARCHIVES: &[&str] = &[
+    "application/zip",
+    "application/x-rar-compressed",
+    "application/x-7z-compressed",
+    "application/x-tar",
+    "application/gzip",
+    "application/x-bzip2",
+];
+
+/// Types named by a category shortcut, if `name` is one.
+fn category(name: &str) -> Option<Vec<&'static str>> {
+    Some(match name {
+        "images" => IMAGES.to_vec(),
+        "video" => VIDEO.to_vec(),
+        "audio" => AUDIO.to_vec(),
+        "media" => [IMAGES, VIDEO, AUDIO].concat(),
+        "documents" => DOCUMENTS.to_vec(),
+        "text" => TEXT.to_vec(),
+        "archives" => ARCHIVES.to_vec(),
+        "default" => DEFAULT_MIME.to_vec(),
+        _ => return None,
+    })
+}
+
+/// Most entries one allowlist may name.
+pub const MAX_MIME_ENTRIES: usize = 64;
+
+fn is_token(part: &str) -> bool {
+    !part.is_empty()
+        && part.len() <= 127
+        && part
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$&-^_.+".contains(&b))
+}
+
+/// Whether `mime` has the `type/subtype` shape.
+pub fn is_mime_type(mime: &str) -> bool {
+    mime.split_once('/')
+        .is_some_and(|(kind, subtype)| is_token(kind) && is_token(subtype))
+}
+
+/// Trim, lowercase and de-duplicate allowlist entries, rejecting anything
+/// that is neither a category, a MIME type nor a `type/*` wildcard.
+pub fn normalize_mime_entries<S: AsRef<str>>(entries: &[S]) -> Result<Vec<String>, String> {
+    let mut out: Vec<String> = Vec::new();
+    for entry in entries {
+        let entry = entry.as_ref().trim().to_ascii_lowercase();
+        if entry.is_empty() || out.contains(&entry) {
+            continue;
+        }
+        let wildcard = entry.strip_suffix("/*").is_some_and(is_token);
+        if category(&entry).is_none() && !wildcard && !is_mime_type(&entry) {
+            return Err(format!(
+                "{entry:?} is not a MIME type, type/* wildcard or category (images, video, audio, media, documents, text, archives, default)"
+            ));
+        }
+        out.push(entry);
+    }
+    if out.len() > MAX_MIME_ENTRIES {
+        return Err(format!("at most {MAX_MIME_ENTRIES} MIME entries"));
+    }
+    Ok(out)
+}
+
+/// Attachment types a deployment or board accepts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MimeAllowlist {
+    /// Exact types and `type/*` wildcards, categories expanded.
+    patterns: Vec<String>,
+}
+
+impl Default for MimeAllowlist {
+    fn default() -> Self {
+        Self {
+            patterns: DEFAULT_MIME.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+}
+
+impl MimeAllowlist {
+    pub fn parse<S: AsRef<str>>(entries: &[S]) -> Result<Self, String> {
+        let mut patterns: Vec<String> = Vec::new();
+        for entry in normalize_mime_entries(entries)? {
+            let expanded = match category(&entry) {
+                Some(types) => types.into_iter().map(str::to_string).collect(),
+                None => vec![entry],
+            };
+            for pattern in expanded {
+                if !patterns.contains(&pattern) {
+                    patterns.push(pattern);
+                }
+            }
+        }
+        Ok(Self { patterns })
+    }
+
+    /// `UPLOAD_ALLOWED_MIME`, comma-separated; the default list when unset,
+    /// empty or invalid.
+    pub fn from_env() -> Self {
+        let Ok(value) = std::env::var("UPLOAD_ALLOWED_MIME") else {
+            return Self::default();
+        };
+        let entries: Vec<&str> = value.split(',').collect();
+        match Self::parse(&entries) {
+            Ok(list) if !list.patterns.is_empty() => list,
+            Ok(_) => Self::default(),
+            Err(e) => {
+                log::warn!("invalid UPLOAD_ALLOWED_MIME: {e}; using the default list");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn allows(&self, mime: &str) -> bool {
+        let kind = mime.split_once('/').map(|(kind, _)| kind);
+        self.patterns
+            .iter()
+            .any(|pattern| match pattern.strip_suffix("/*") {
+                Some(wildcard) => kind == Some(wildcard),
+                None => pattern == mime,
+            })
+    }
+}
+
+/// Limits for uploads by one role, within the deployment's.
+#[derive(Debug, Clone, Default)]
+pub struct RolePolicy {
+    /// Largest file in bytes; the global file size limit still applies.
+    pub max_bytes: Option<usize>,
+    /// Types the role may upload; unset leaves the deployment list.
+    pub allowed: Option<MimeAllowlist>,
+}
+
+impl RolePolicy {
+    /// `UPLOAD_MAX_BYTES_<ROLE>` and `UPLOAD_ALLOWED_MIME_<ROLE>`.
+    fn from_env(role: &str) -> Self {
+        let allowed = std::env::var(format!("UPLOAD_ALLOWED_MIME_{role}"))
+            .ok()
+            .and_then(|value| {
+                let entries: Vec<&str> = value.split(',').collect();
+                match MimeAllowlist::parse(&entries) {
+                    Ok(list) if !list.patterns.is_empty() => Some(list),
+                    Ok(_) => None,
+                    Err(e) => {
+                        log::warn!("invalid UPLOAD_ALLOWED_MIME_{role}: {e}; ignoring it");
+                        None
+                    }
+                }
+            });
+        Self {
+            max_bytes: std::env::var(format!("UPLOAD_MAX_BYTES_{role}"))
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v| *v > 0),
+            allowed,
+        }
+    }
+}
+
+/// Who may upload, and what each role may upload.
+#[derive(Debug, Clone)]
+pub struct RolePolicies {
+    /// Lowest role allowed to upload at all.
+    pub min_role: Role,
+    pub user: RolePolicy,
+    pub moderator: RolePolicy,
+    pub admin: RolePolicy,
+}
+
+fn rank(role: &Role) -> u8 {
+    match role {
+        Role::User => 0,
+        Role::Moderator => 1,
+        Role::Admin => 2,
+    }
+}
+
+impl RolePolicies {
+    /// Any signed-in user; no per-role limits.
+    pub fn open() -> Self {
+        Self {
+            min_role: Role::User,
+            user: RolePolicy::default(),
+            moderator: RolePolicy::default(),
+            admin: RolePolicy::default(),
+        }
+    }
+
+    /// `UPLOAD_MIN_ROLE` (`user` when unset or unknown) and the per-role
+    /// `UPLOAD_MAX_BYTES_*` and `UPLOAD_ALLOWED_MIME_*` variables.
+    pub fn from_env() -> Self {
+        let min_role = match std::env::var("UPLOAD_MIN_ROLE") {
+            Ok(value) => match value.trim().to_ascii_lowercase().as_str() {
+                "user" => Role::User,
+                "moderator" => Role::Moderator,
+                "admin" => Role::Admin,
+                _ => {
+                    log::warn!("unknown UPLOAD_MIN_ROLE {value:?}; using user");
+                    Role::User
+                }
+            },
+            Err(_) => Role::User,
+        };
+        Self {
+            min_role,
+            user: RolePolicy::from_env("USER"),
+            moderator: RolePolicy::from_env("MODERATOR"),
+            admin: RolePolicy::from_env("ADMIN"),
+        }
+    }
+
+    /// The policy of the highest of `roles`, or `None` when that is below
+    /// [`min_role`](Self::min_role).
+    pub fn for_roles(&self, roles: &[Role]) -> Option<&RolePolicy> {
+        let top = roles.iter().max_by_key(|role| rank(role))?;
+        if rank(top) < rank(&self.min_role) {
+            return None;
+        }
+        Some(match top {
+            Role::User => &self.user,
+            Role::Moderator => &self.moderator,
+            Role::Admin => &self.admin,
+        })
+    }
+}
+
+/// Upload rules for a deployment.
+#[derive(Debug, Clone)]
+pub struct UploadConfig {
+    pub quota: UploadQuota,
+    pub mismatch: MismatchPolicy,
+    /// Types accepted anywhere; boards may narrow it further.
+    pub allowed: MimeAllowlist,
+    pub roles: RolePolicies,
+    pub remote: RemoteFetchPolicy,
+    pub archives: ArchivePolicy,
+    /// Cut EXIF, XMP and text metadata out of JPEG, PNG and WebP uploads.
+    pub strip_metadata: bool,
+}
+
+impl UploadConfig {
+    /// No quota; mismatches are only logged; the default type list; any
+    /// signed-in user may upload; files are stored as they came.
+    pub fn disabled() -> Self {
+        Self {
+            quota: UploadQuota::disabled(),
+            mismatch: MismatchPolicy::Warn,
+            allowed: MimeAllowlist::default(),
+            roles: RolePolicies::open(),
+            remote: RemoteFetchPolicy::disabled(),
+            archives: ArchivePolicy::default(),
+            strip_metadata: false,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self {
+            quota: UploadQuota::from_env(),
+            mismatch: MismatchPolicy::from_env(),
+            allowed: MimeAllowlist::from_env(),
+            roles: RolePolicies::from_env(),
+            remote: RemoteFetchPolicy::from_env(),
+            archives: ArchivePolicy::from_env(),
+            strip_metadata: std::env::var("UPLOAD_STRIP_METADATA")
+                .map_or(true, |v| !(v == "0" || v.eq_ignore_ascii_case("false"))),
+        }
+    }
+
+    /// Whether a caller under `policy` may upload a file of type `mime`.
+    pub fn accepts_upload(&self, policy: &RolePolicy, mime: &str) -> bool {
+        self.allowed.allows(mime) && policy.allowed.as_ref().is_none_or(|list| list.allows(mime))
+    }
+
+    /// Whether an attachment of type `mime` may be posted to a board whose
+    /// own list is `board_allowed` (empty defers to the deployment).
+    pub fn accepts(&self, board_allowed: &[String], mime: &str) -> bool {
+        self.allowed.allows(mime)
+            && (board_allowed.is_empty()
+                || MimeAllowlist::parse(board_allowed).is_ok_and(|list| list.allows(mime)))
+    }
+}
+
+/// How files uploaded by URL are fetched.
+#[derive(Debug, Clone)]
+pub struct RemoteFetchPolicy {
+    /// Accept uploads by URL at all.
+    pub enabled: bool,
+    /// Whole fetch, redirects included.
+    pub timeout: Duration,
+    pub max_redirects: usize,
+    /// Fetch from loopback, private and other non-public addresses; only
+    /// for local development.
+    pub allow_private: bool,
+}
+
+impl RemoteFetchPolicy {
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            timeout: Duration::from_secs(15),
+            max_redirects: 3,
+            allow_private: false,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        fn flag(name: &str) -> bool {
+            std::env::var(name).is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        }
+        fn u64_env(name: &str, default: u64) -> u64 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        Self {
+            enabled: flag("UPLOAD_REMOTE_ENABLED"),
+            timeout: Duration::from_secs(u64_env("UPLOAD_REMOTE_TIMEOUT_SECS", 15).max(1)),
+            max_redirects: u64_env("UPLOAD_REMOTE_MAX_REDIRECTS", 3).min(10) as usize,
+            allow_private: flag("UPLOAD_REMOTE_ALLOW_PRIVATE"),
+        }
+    }
+}
+
+/// Extensions of entries that make an archive dangerous by default.
+pub const BLOCKED_ARCHIVE_EXTENSIONS: &[&str] = &[
+    "apk", "bat", "cmd", "com", "cpl", "dll", "dmg", "exe", "hta", "jar", "jse", "lnk", "msi",
+    "msp", "pif", "ps1", "reg", "scr", "vbe", "vbs", "wsf", "wsh",
+];
+
+/// Limits on what an uploaded archive may hold.
+#[derive(Debug, Clone)]
+pub struct ArchivePolicy {
+    /// What happens to a dangerous archive.
+    pub action: MismatchPolicy,
+    /// Most an archive may expand relative to its own size.
+    pub max_ratio: u64,
+    pub max_entries: usize,
+    pub max_unpacked_bytes: u64,
+    /// Lower-case extensions of entries that may not be inside an archive.
+    pub blocked_extensions: Vec<String>,
+    /// Treat archives that cannot be opened (RAR, bzip2, xz, corrupt or
+    /// encrypted files) as dangerous.
+    pub require_listing: bool,
+}
+
+impl Default for ArchivePolicy {
+    fn default() -> Self {
+        Self {
+            action: MismatchPolicy::Reject,
+            max_ratio: 100,
+            max_entries: 10_000,
+            max_unpacked_bytes: 1024 * 1024 * 1024,
+            blocked_extensions: BLOCKED_ARCHIVE_EXTENSIONS
+                .iter()
+                .map(|e| e.to_string())
+                .collect(),
+            require_listing: true,
+        }
+    }
+}
+
+impl ArchivePolicy {
+    pub fn from_env() -> Self {
+        fn u64_env(name: &str, default: u64) -> u64 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        let defaults = Self::default();
+        let action = match std::env::var("UPLOAD_ARCHIVE_POLICY") {
+            Ok(value) => MismatchPolicy::parse(&value).unwrap_or_else(|| {
+                log::warn!("unknown UPLOAD_ARCHIVE_POLICY {value:?}; using reject");
+                MismatchPolicy::Reject
+            }),
+            Err(_) => defaults.action,
+        };
+        let blocked_extensions = match std::env::var("UPLOAD_ARCHIVE_BLOCKED_EXTENSIONS") {
+            Ok(list) => list
+                .split(',')
+                .map(|e| e.trim().trim_start_matches('.').to_ascii_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect(),
+            Err(_) => defaults.blocked_extensions,
+        };
+        Self {
+            action,
+            max_ratio: u64_env("UPLOAD_ARCHIVE_MAX_RATIO", defaults.max_ratio).max(1),
+            max_entries: u64_env("UPLOAD_ARCHIVE_MAX_ENTRIES", defaults.max_entries as u64).max(1)
+                as usize,
+            max_unpacked_bytes: u64_env(
+                "UPLOAD_ARCHIVE_MAX_UNPACKED_BYTES",
+                defaults.max_unpacked_bytes,
+            )
+            .max(1),
+            blocked_extensions,
+            require_listing: std::env::var("UPLOAD_ARCHIVE_REQUIRE_LISTING")
+                .map_or(true, |v| !(v == "0" || v.eq_ignore_ascii_case("false"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UploadQuota {
+    /// Bytes per subject per window; unset disables the quota.
+    pub max_bytes: Option<u64>,
+    pub window: Duration,
+}
+
+impl UploadQuota {
+    pub fn disabled() -> Self {
+        Self {
+            max_bytes: None,
+            window: Duration::from_secs(86_400),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self {
+            max_bytes: std::env::var("UPLOAD_QUOTA_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|v| *v > 0),
+            window: Duration::from_secs(
+                std::env::var("UPLOAD_QUOTA_WINDOW_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(86_400u64)
+                    .max(60),
+            ),
+        }
+    }
+
+    /// Start of the window counted against a subject at `now`.
+    pub fn since(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - chrono::Duration::from_std(self.window).unwrap_or(chrono::Duration::days(1))
+    }
+
+    /// Bytes still allowed given `usage`; `None` when there is no quota.
+    pub fn remaining(&self, usage: &UploadUsage) -> Option<u64> {
+        self.max_bytes
+            .map(|max| max.saturating_sub(usage.bytes.max(0) as u64))
+    }
+
+    /// Seconds until the oldest counted upload leaves the window.
+    pub fn retry_after(&self, usage: &UploadUsage, now: DateTime<Utc>) -> u64 {
+        let window = self.window.as_secs();
+        usage
+            .oldest_at
+            .map(|oldest| {
+                let elapsed = (now - oldest).num_seconds().max(0) as u64;
+                window.saturating_sub(elapsed).max(1)
+            })
+            .unwrap_or(window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::HeaderValue;
+
+    #[test]
+    fn declared_lengths_leave_room_for_multipart_framing() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(declared_length(&headers), None);
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("2048"));
+        assert_eq!(declared_length(&headers), Some(2048));
+        assert!(!request_exceeds(None, 10));
+        assert!(!request_exceeds(Some(1000 + MULTIPART_OVERHEAD), 1000));
+        assert!(request_exceeds(Some(1001 + MULTIPART_OVERHEAD), 1000));
+    }
+
+    #[test]
+    fn mismatched_names_and_active_markup_are_caught() {
+        let png = b"\x89PNG\r\n\x1a\n....";
+        assert_eq!(content_mismatch(Some("cat.png"), "image/png", png), None);
+        assert_eq!(content_mismatch(Some("cat.PNG"), "image/png", png), None);
+        assert_eq!(content_mismatch(None, "image/png", png), None);
+        assert_eq!(content_mismatch(Some("README"), "text/plain", b"hi"), None);
+        assert_eq!(content_mismatch(Some("a.weird"), "video/mp4", b""), None);
+        assert_eq!(
+            content_mismatch(Some("a.bin"), "application/octet-stream", b""),
+            None
+        );
+        assert_eq!(
+            content_mismatch(Some("report.docx"), "application/zip", b"PK"),
+            None
+        );
+        assert!(content_mismatch(
+            Some("cat.png"),
+            "application/vnd.microsoft.portable-executable",
+            b"MZ"
+        )
+        .unwrap()
+        .starts_with("executable content"));
+        assert!(content_mismatch(Some("clip.mp4"), "image/gif", b"GIF89a").is_some());
+        assert!(content_mismatch(Some("cat.png"), "text/plain", b"<html>").is_some());
+        let mut polyglot = png.to_vec();
+        polyglot.extend_from_slice(b"<SCRIPT>alert(1)</script>");
+        assert!(content_mismatch(Some("cat.png"), "image/png", &polyglot).is_some());
+    }
+
+    #[test]
+    fn allowlists_expand_categories_and_wildcards() {
+        let default = MimeAllowlist::default();
+        assert!(default.allows("image/png"));
+        assert!(default.allows("text/html"));
+
+        let list = MimeAllowlist::parse(&["Images", " audio/* ", "application/pdf"]).unwrap();
+        assert!(list.allows("image/webp"));
+        assert!(!list.allows("image/svg+xml"));
+        assert!(list.allows("audio/anything"));
+        assert!(list.allows("application/pdf"));
+        assert!(!list.allows("text/html"));
+        assert!(MimeAllowlist::parse(&["media"])
+            .unwrap()
+            .allows("video/webm"));
+        assert!(!MimeAllowlist::parse(&["text"]).unwrap().allows("text/html"));
+
+        assert!(MimeAllowlist::parse(&["pictures"]).is_err());
+        assert!(MimeAllowlist::parse(&["*/*"]).is_err());
+        assert_eq!(
+            normalize_mime_entries(&["Images", "images", ""]).unwrap(),
+            vec!["images"]
+        );
+
+        let config = UploadConfig::disabled();
+        assert!(config.accepts(&[], "text/html"));
+        assert!(!config.accepts(&["images".to_string()], "text/html"));
+        assert!(config.accepts(&["images".to_string()], "image/gif"));
+        assert!(!config.accepts(
+            &["application/x-msdownload".to_string()],
+            "application/x-msdownload"
+        ));
+    }
+
+    #[test]
+    fn policies_parse() {
+        assert_eq!(
+            MismatchPolicy::parse(" Reject "),
+            Some(MismatchPolicy::Reject)
+        );
+        assert_eq!(MismatchPolicy::parse("flag"), Some(MismatchPolicy::Flag));
+        assert_eq!(
+            MismatchPolicy::parse("quarantine"),
+            Some(MismatchPolicy::Quarantine)
+        );
+        assert_eq!(MismatchPolicy::parse("loud"), None);
+    }
+
+    #[test]
+    fn uploads_follow_the_highest_role() {
+        let mut roles = RolePolicies::open();
+        roles.min_role = Role::Moderator;
+        roles.moderator.allowed = Some(MimeAllowlist::parse(&["images"]).unwrap());
+        roles.admin.max_bytes = Some(10);
+        assert!(roles.for_roles(&[]).is_none());
+        assert!(roles.for_roles(&[Role::User]).is_none());
+        let moderator = roles.for_roles(&[Role::User, Role::Moderator]).unwrap();
+        assert_eq!(moderator.max_bytes, None);
+        let admin = roles.for_roles(&[Role::Admin, Role::Moderator]).unwrap();
+        assert_eq!(admin.max_bytes, Some(10));
+
+        let config = UploadConfig::disabled();
+        assert!(config.accepts_upload(moderator, "image/png"));
+        assert!(!config.accepts_upload(moderator, "application/pdf"));
+        assert!(config.accepts_upload(admin, "application/pdf"));
+        assert!(!config.accepts_upload(admin, "application/x-msdownload"));
+    }
+
+    #[test]
+    fn quota_counts_down_and_frees_with_the_oldest_upload() {
+        let quota = UploadQuota {
+            max_bytes: Some(1000),
+            window: Duration::from_secs(3600),
+        };
+        let now = Utc::now();
+        let usage = UploadUsage {
+            bytes: 400,
+            oldest_at: Some(now - chrono::Duration::seconds(600)),
+        };
+        assert_eq!(quota.remaining(&usage), Some(600));
+        assert_eq!(quota.retry_after(&usage, now), 3000);
+        let over = UploadUsage {
+            bytes: 1500,
+            ..usage
+        };
+        assert_eq!(quota.remaining(&over), Some(0));
+        assert_eq!(quota.remaining(&UploadUsage::default()), Some(1000));
+        assert_eq!(quota.retry_after(&UploadUsage::default(), now), 3600);
+        assert_eq!(UploadQuota::disabled().remaining(&usage), None);
+    }
+}
@@ -0,0 +1,92 @@
+//! Build identity: crate version, commit and build time (set by `build.rs`),
+//! and the cargo features compiled in. Served at `/api/v1/version` and
+//! stamped into the OpenAPI document's `info` block.
+
+use actix_web::HttpResponse;
+use chrono::{DateTime, Utc};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Short commit hash, or "unknown" when built outside a checkout without
+/// `GIT_SHA`.
+pub const GIT_SHA: &str = env!("RIB_GIT_SHA");
+
+/// When the build script last ran, honouring `SOURCE_DATE_EPOCH`.
+pub fn build_time() -> Option<DateTime<Utc>> {
+    env!("RIB_BUILD_TIME")
+        .parse()
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+}
+
+/// Optional cargo features this binary was built with.
+pub fn features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "embed-frontend") {
+        features.push("embed-frontend");
+    }
+    if cfg!(feature = "graphql") {
+        features.push("graphql");
+    }
+    if cfg!(feature = "grpc") {
+        features.push("grpc");
+    }
+    features
+}
+
+/// `{version}+{sha}`, the semver form used as the OpenAPI document version.
+pub fn spec_version() -> String {
+    format!("{VERSION}+{GIT_SHA}")
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct VersionInfo {
+    /// Crate version
+    pub version: String,
+    pub git_sha: String,
+    pub build_time: Option<DateTime<Utc>>,
+    /// Optional cargo features compiled in: `embed-frontend`, `graphql`, `grpc`
+    pub features: Vec<String>,
+}
+
+impl VersionInfo {
+    pub fn current() -> Self {
+        Self {
+            version: VERSION.to_string(),
+            git_sha: GIT_SHA.to_string(),
+            build_time: build_time(),
+            features: features().into_iter().map(str::to_string).collect(),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/version",
+    responses(
+        (status = 200, description = "Version, commit, build time and compiled-in features", body = VersionInfo)
+    )
+)]
+pub async fn version() -> HttpResponse {
+    HttpResponse::Ok().json(VersionInfo::current())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn reports_the_build() {
+        let body = actix_web::body::to_bytes(version().await.into_body())
+            .await
+            .unwrap();
+        let info: VersionInfo = serde_json::from_slice(&body).unwrap();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.git_sha.is_empty());
+        assert!(info.build_time.is_some());
+        assert_eq!(
+            info.features.contains(&"graphql".to_string()),
+            cfg!(feature = "graphql")
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use actix_web::{test, App};
+use rib::access_policy::{AccessPolicy, PolicyConfig, PolicySet};
+use rib::auth::{create_jwt, Role};
+use rib::models::Board;
+use rib::pow::{is_solution, PowChallenge, CLEARANCE_COOKIE, POW_HEADER};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn write_policy_challenges_untrusted_callers_until_they_solve_a_proof_of_work() {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    std::env::set_var("POW_DIFFICULTY", "4");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let user_id = format!("policy-{}", &suffix[..8]);
+    let repo = PgRepo::new(pool);
+    repo.set_subject_role(&format!("discord:{user_id}"), Role::User)
+        .await
+        .expect("allowlist poster");
+    let policy = AccessPolicy::with_policy(
+        PolicyConfig {
+            file: None,
+            reload_interval: std::time::Duration::from_secs(10),
+            country_header: "cf-ipcountry".to_string(),
+            asn_header: "x-client-asn".to_string(),
+            tor_header: "x-client-tor".to_string(),
+            interstitial: false,
+        },
+        PolicySet::parse(r#"{"write": [{"trust_below": 0.5, "action": "challenge"}]}"#).unwrap(),
+    );
+    let app = test::init_service(
+        App::new()
+            .wrap(policy)
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("policy-admin", "policy-admin", vec![Role::Admin]).unwrap();
+    let user = create_jwt(&user_id, &user_id, vec![Role::User]).unwrap();
+
+    // Staff count as fully trusted.
+    let board: Board = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(json!({"slug": format!("ap{}", &suffix[..8]), "title": "Policy"}))
+            .to_request(),
+    )
+    .await;
+
+    let post = || {
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .insert_header(("Authorization", format!("Bearer {user}")))
+            .set_json(
+                json!({"board_id": board.id, "subject": "hi", "body": format!("policy {suffix}")}),
+            )
+    };
+    let resp = test::call_service(&app, post().to_request()).await;
+    assert_eq!(resp.status(), 403);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["error"].as_str().unwrap().contains("proof of work"));
+
+    // Reads are governed by their own, empty, rule list.
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/boards/{}/threads", board.id))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+
+    let challenge: PowChallenge = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get().uri("/api/v1/pow").to_request(),
+    )
+    .await;
+    let nonce = (0u64..)
+        .map(|n| n.to_string())
+        .find(|nonce| is_solution(&challenge.challenge, nonce, challenge.difficulty))
+        .unwrap();
+    let wrong = (0u64..)
+        .map(|n| n.to_string())
+        .find(|nonce| !is_solution(&challenge.challenge, nonce, challenge.difficulty))
+        .unwrap();
+    let with_nonce = |nonce: &str| {
+        post()
+            .insert_header((POW_HEADER, format!("{}:{nonce}", challenge.challenge)))
+            .to_request()
+    };
+    let resp = test::call_service(&app, with_nonce(&wrong)).await;
+    assert_eq!(resp.status(), 403);
+    let resp = test::call_service(&app, with_nonce(&nonce)).await;
+    assert_eq!(resp.status(), 201);
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn challenged_browsers_get_a_solving_page_and_a_clearance_cookie() {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    std::env::set_var("POW_DIFFICULTY", "4");
+    let policy = AccessPolicy::with_policy(
+        PolicyConfig {
+            file: None,
+            reload_interval: std::time::Duration::from_secs(10),
+            country_header: "cf-ipcountry".to_string(),
+            asn_header: "x-client-asn".to_string(),
+            tor_header: "x-client-tor".to_string(),
+            interstitial: true,
+        },
+        PolicySet::parse(r#"{"read": [{"paths": ["/api/v1/search"], "action": "challenge"}]}"#)
+            .unwrap(),
+    );
+    let app = test::init_service(
+        App::new()
+            .wrap(policy)
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(PgRepo::new(pool)),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let search = |accept: &str, ip: &str| {
+        test::TestRequest::get()
+            .uri("/api/v1/search?q=clearance")
+            .peer_addr(format!("{ip}:1000").parse().unwrap())
+            .insert_header(("Accept", accept.to_string()))
+    };
+
+    let resp = test::call_service(&app, search("text/html", "10.9.0.1").to_request()).await;
+    assert_eq!(resp.status(), 403);
+    let csp = resp
+        .headers()
+        .get("Content-Security-Policy")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    let page = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(page.contains("/api/v1/pow/clearance"));
+    let nonce = csp
+        .split("'nonce-")
+        .nth(1)
+        .and_then(|rest| rest.split('\'').next())
+        .unwrap();
+    assert!(page.contains(&format!("nonce=\"{nonce}\"")));
+
+    // API callers keep the JSON refusal; other paths are not challenged.
+    let resp = test::call_service(&app, search("application/json", "10.9.0.1").to_request()).await;
+    assert_eq!(resp.status(), 403);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["error"].as_str().unwrap().contains("clearance"));
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get().uri("/api/v1/boards").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+
+    let challenge: PowChallenge = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get().uri("/api/v1/pow").to_request(),
+    )
+    .await;
+    let nonce = (0u64..)
+        .map(|n| n.to_string())
+        .find(|nonce| is_solution(&challenge.challenge, nonce, challenge.difficulty))
+        .unwrap();
+    let clear = || {
+        test::TestRequest::post()
+            .uri("/api/v1/pow/clearance")
+            .peer_addr("10.9.0.1:1000".parse().unwrap())
+            .insert_header((POW_HEADER, format!("{}:{nonce}", challenge.challenge)))
+            .to_request()
+    };
+    let resp = test::call_service(&app, clear()).await;
+    assert_eq!(resp.status(), 200);
+    let cookie = resp
+        .response()
+        .cookies()
+        .find(|c| c.name() == CLEARANCE_COOKIE)
+        .unwrap()
+        .into_owned();
+    assert!(cookie.http_only().unwrap_or(false));
+    // Solutions buy one clearance.
+    let resp = test::call_service(&app, clear()).await;
+    assert_eq!(resp.status(), 400);
+
+    let resp = test::call_service(
+        &app,
+        search("text/html", "10.9.0.1")
+            .cookie(cookie.clone())
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+    // The clearance is bound to the client it was issued to.
+    let resp = test::call_service(
+        &app,
+        search("application/json", "10.9.0.2")
+            .cookie(cookie)
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 403);
+}
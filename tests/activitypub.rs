@@ -0,0 +1,318 @@
+use actix_web::{test, web, App};
+use aws_lc_rs::encoding::AsDer;
+use aws_lc_rs::rsa::KeySize;
+use aws_lc_rs::signature::{KeyPair, RsaKeyPair};
+use rib::activitypub::{pem_encode, sign_request, Federation, FederationConfig, FederationRunner};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+use std::time::Duration;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+fn runner_config() -> FederationConfig {
+    FederationConfig {
+        enabled: true,
+        base_url: "https://rib.example".into(),
+        private_key: None,
+        poll_interval: Duration::from_secs(1),
+        batch_size: 1000,
+        settle: Duration::ZERO,
+        retry: Duration::from_secs(60),
+        max_attempts: 3,
+    }
+}
+
+/// A signed inbox POST from `key_id`, optionally with a tampered body.
+fn signed_post(
+    key: &RsaKeyPair,
+    key_id: &str,
+    uri: &str,
+    activity: &Value,
+    tamper: bool,
+) -> test::TestRequest {
+    let body = serde_json::to_vec(activity).unwrap();
+    let url = reqwest::Url::parse(&format!("http://localhost{uri}")).unwrap();
+    let mut req = test::TestRequest::post()
+        .uri(uri)
+        .insert_header(("Content-Type", "application/activity+json"));
+    for (name, value) in sign_request(key, key_id, "POST", &url, Some(&body)).unwrap() {
+        req = req.insert_header((name, value));
+    }
+    let body = if tamper {
+        serde_json::to_vec(&json!({"type": "Follow", "actor": "x"})).unwrap()
+    } else {
+        body
+    };
+    req.set_payload(body)
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn boards_are_followable_and_deliver_new_threads() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    // Deliveries left by earlier runs point at servers that are gone.
+    sqlx::query("DELETE FROM ap_deliveries")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let poster_id = format!("fedi-{}", &suffix[..8]);
+    let repo = Arc::new(PgRepo::new(pool.clone()));
+    repo.set_subject_role(&format!("discord:{poster_id}"), Role::User)
+        .await
+        .expect("allowlist poster");
+    let federation = Arc::new(
+        Federation::new(
+            "https://rib.example",
+            RsaKeyPair::generate(KeySize::Rsa2048).unwrap(),
+        )
+        .unwrap(),
+    );
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                AppState::new(repo.clone(), Arc::new(NoImages), None)
+                    .with_federation(Some(federation.clone())),
+            ))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("ap-admin", "ap-admin", vec![Role::Admin]).unwrap();
+    let poster = create_jwt(&poster_id, &poster_id, vec![Role::User]).unwrap();
+    let slug = format!("ap{}", &suffix[..8]);
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(json!({"slug": slug, "title": "Federated"}))
+            .to_request(),
+    )
+    .await;
+    let board: Board = test::read_body_json(resp).await;
+    let actor_id = format!("https://rib.example/ap/boards/{slug}");
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!(
+                "/.well-known/webfinger?resource=acct:{slug}@rib.example"
+            ))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+    let jrd: Value = test::read_body_json(resp).await;
+    assert_eq!(jrd["links"][0]["href"], actor_id);
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri("/.well-known/webfinger?resource=acct:nope@other.example")
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 404);
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/ap/boards/{slug}"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/activity+json"
+    );
+    let actor: Value = test::read_body_json(resp).await;
+    assert_eq!(actor["type"], "Group");
+    assert_eq!(actor["inbox"], format!("{actor_id}/inbox"));
+    assert!(actor["publicKey"]["publicKeyPem"]
+        .as_str()
+        .unwrap()
+        .starts_with("-----BEGIN PUBLIC KEY-----"));
+
+    // A remote actor with its own inbox and a shared one.
+    let server = MockServer::start().await;
+    let remote_id = format!("{}/users/alice", server.uri());
+    let key_id = format!("{remote_id}#main-key");
+    let key = RsaKeyPair::generate(KeySize::Rsa2048).unwrap();
+    let pem = pem_encode("PUBLIC KEY", key.public_key().as_der().unwrap().as_ref());
+    Mock::given(method("GET"))
+        .and(path("/users/alice"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": remote_id,
+            "type": "Person",
+            "inbox": format!("{remote_id}/inbox"),
+            "endpoints": {"sharedInbox": format!("{}/inbox", server.uri())},
+            "publicKey": {"id": key_id, "owner": remote_id, "publicKeyPem": pem},
+        })))
+        .mount(&server)
+        .await;
+
+    let inbox = format!("/ap/boards/{slug}/inbox");
+    let follow = json!({
+        "id": format!("{remote_id}#follows/1"),
+        "type": "Follow",
+        "actor": remote_id,
+        "object": actor_id,
+    });
+    let resp = test::call_service(
+        &app,
+        signed_post(&key, &key_id, &inbox, &follow, true).to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 401);
+    let impostor =
+        json!({"type": "Follow", "actor": "https://evil.example/u/x", "object": actor_id});
+    let resp = test::call_service(
+        &app,
+        signed_post(&key, &key_id, &inbox, &impostor, false).to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 401);
+    let resp = test::call_service(
+        &app,
+        signed_post(&key, &key_id, &inbox, &follow, false).to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 202);
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/ap/boards/{slug}/followers"))
+            .to_request(),
+    )
+    .await;
+    let followers: Value = test::read_body_json(resp).await;
+    assert_eq!(followers["totalItems"], 1);
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .insert_header(("Authorization", format!("Bearer {poster}")))
+            .set_json(
+                json!({"board_id": board.id, "subject": "Hello <fediverse>", "body": "a & b"}),
+            )
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 201);
+    let thread: Thread = test::read_body_json(resp).await;
+    let note_id = format!("https://rib.example/ap/threads/{}", thread.id);
+    Mock::given(method("POST"))
+        .and(path("/users/alice/inbox"))
+        .and(body_string_contains("\"Accept\""))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/inbox"))
+        .and(body_string_contains(note_id.as_str()))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let runner = FederationRunner::new(repo.clone(), federation.clone(), runner_config());
+    assert_eq!(runner.run_once().await, 2);
+    assert_eq!(runner.run_once().await, 0);
+    let signed = server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|r| r.method.as_str() == "POST")
+        .all(|r| r.headers.contains_key("signature") && r.headers.contains_key("digest"));
+    assert!(signed);
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/ap/threads/{}", thread.id))
+            .to_request(),
+    )
+    .await;
+    let note: Value = test::read_body_json(resp).await;
+    assert_eq!(note["attributedTo"], actor_id);
+    assert_eq!(
+        note["content"],
+        "<p><strong>Hello &lt;fediverse&gt;</strong></p><p>a &amp; b</p>"
+    );
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/ap/boards/{slug}/outbox"))
+            .to_request(),
+    )
+    .await;
+    let outbox: Value = test::read_body_json(resp).await;
+    assert_eq!(outbox["totalItems"], 1);
+    assert_eq!(outbox["orderedItems"][0]["object"]["id"], note_id);
+
+    let undo = json!({"type": "Undo", "actor": remote_id, "object": follow});
+    let resp = test::call_service(
+        &app,
+        signed_post(&key, &key_id, &inbox, &undo, false).to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 202);
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/ap/boards/{slug}/followers"))
+            .to_request(),
+    )
+    .await;
+    let followers: Value = test::read_body_json(resp).await;
+    assert_eq!(followers["totalItems"], 0);
+
+    // Without a signing key the routes do not exist.
+    let plain = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::new(
+                repo.clone(),
+                Arc::new(NoImages),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let resp = test::call_service(
+        &plain,
+        test::TestRequest::get()
+            .uri(&format!("/ap/boards/{slug}"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 404);
+}
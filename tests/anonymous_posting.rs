@@ -0,0 +1,185 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, Reply, Thread};
+use rib::pow::{is_solution, PowChallenge, POW_HEADER};
+use rib::repo::pg::PgRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+fn solve(challenge: &PowChallenge) -> String {
+    let nonce = (0u64..)
+        .map(|n| n.to_string())
+        .find(|nonce| is_solution(&challenge.challenge, nonce, challenge.difficulty))
+        .unwrap();
+    format!("{}:{nonce}", challenge.challenge)
+}
+
+macro_rules! pow_token {
+    ($app:expr) => {{
+        let challenge: PowChallenge = test::call_and_read_body_json(
+            &$app,
+            test::TestRequest::get().uri("/api/v1/pow").to_request(),
+        )
+        .await;
+        solve(&challenge)
+    }};
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn opted_in_boards_accept_anonymous_posts_with_proof_of_work() {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    std::env::set_var("POW_DIFFICULTY", "4");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(PgRepo::new(pool)),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    std::env::remove_var("POW_DIFFICULTY");
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+
+    let board: Board = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(json!({"slug": format!("an{}", &suffix[..8]), "title": "Anon"}))
+            .to_request(),
+    )
+    .await;
+    assert!(!board.anonymous_posting);
+    let new_thread = json!({"board_id": board.id, "subject": "hello", "body": "no account"});
+
+    // Boards that have not opted in still require signing in.
+    let token = pow_token!(app);
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .insert_header((POW_HEADER, token))
+            .set_json(&new_thread)
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 401);
+
+    let board: Board = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::patch()
+            .uri(&format!("/api/v1/boards/{}", board.id))
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(json!({"anonymous_posting": true}))
+            .to_request(),
+    )
+    .await;
+    assert!(board.anonymous_posting);
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .set_json(&new_thread)
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 400, "proof of work is required");
+
+    let token = pow_token!(app);
+    let thread: Thread = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .insert_header((POW_HEADER, token.clone()))
+            .set_json(&new_thread)
+            .to_request(),
+    )
+    .await;
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .insert_header((POW_HEADER, token))
+            .set_json(&new_thread)
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 400, "solutions cannot be replayed");
+
+    // A bad bearer token is not silently downgraded to anonymous posting.
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .insert_header(("Authorization", "Bearer not-a-jwt"))
+            .insert_header((POW_HEADER, pow_token!(app)))
+            .set_json(json!({"thread_id": thread.id, "content": "reply"}))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 401);
+
+    let reply: Reply = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .insert_header((POW_HEADER, pow_token!(app)))
+            .set_json(json!({"thread_id": thread.id, "content": "reply"}))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(reply.thread_id, thread.id);
+
+    // Moderators still see a stable per-poster subject.
+    let thread_author: serde_json::Value = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/admin/threads/{}/author", thread.id))
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .to_request(),
+    )
+    .await;
+    let reply_author: serde_json::Value = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/admin/replies/{}/author", reply.id))
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .to_request(),
+    )
+    .await;
+    let subject = thread_author["subject"].as_str().unwrap();
+    assert!(subject.starts_with("anon:"));
+    assert_eq!(reply_author["subject"], subject);
+}
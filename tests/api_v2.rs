@@ -0,0 +1,183 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+fn token(id: &str, role: Role) -> String {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    create_jwt(id, id, vec![role]).expect("test token")
+}
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let repo = PgRepo::new(pool);
+    repo.set_subject_role("discord:v2-user", Role::User)
+        .await
+        .expect("allowlist v2 user");
+    repo
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn v2_wraps_resources_in_envelopes_with_location_headers() {
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(test_repo().await),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = token("v2-admin", Role::Admin);
+    let user = token("v2-user", Role::User);
+    let slug = format!("v2{}", &uuid::Uuid::new_v4().simple().to_string()[..10]);
+
+    let req = test::TestRequest::post()
+        .uri("/api/v2/boards")
+        .insert_header(("Authorization", format!("Bearer {admin}")))
+        .set_json(json!({"slug": slug, "title": "Version two"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    let location = resp
+        .headers()
+        .get("location")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    let created: Value = test::read_body_json(resp).await;
+    let board_id = created["data"]["id"].as_i64().unwrap();
+    assert_eq!(location, format!("/api/v2/boards/{board_id}"));
+    assert_eq!(created["meta"]["api_version"], "v2");
+
+    let resp = test::call_service(&app, test::TestRequest::get().uri(&location).to_request()).await;
+    assert_eq!(resp.status(), 200);
+    let fetched: Value = test::read_body_json(resp).await;
+    assert_eq!(fetched["data"]["slug"], slug.as_str());
+
+    for subject in ["first", "second", "third"] {
+        let req = test::TestRequest::post()
+            .uri("/api/v2/threads")
+            .insert_header(("Authorization", format!("Bearer {user}")))
+            .set_json(json!({"board_id": board_id, "subject": subject, "body": "body"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        assert!(resp
+            .headers()
+            .get("location")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("/api/v2/threads/"));
+    }
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v2/boards/{board_id}/threads?limit=2"))
+        .to_request();
+    let page: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(page["data"].as_array().unwrap().len(), 2);
+    assert_eq!(page["pagination"]["total"], 3);
+    assert_eq!(page["pagination"]["next_offset"], 2);
+    let req = test::TestRequest::get()
+        .uri(&format!(
+            "/api/v2/boards/{board_id}/threads?limit=2&offset=2"
+        ))
+        .to_request();
+    let page: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(page["data"][0]["subject"], "first");
+    assert!(page["pagination"]["next_offset"].is_null());
+
+    // v1 keeps returning bare arrays.
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/boards/{board_id}/threads"))
+        .to_request();
+    let bare: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(bare.as_array().unwrap().len(), 3);
+}
+
+#[actix_web::test]
+async fn v2_errors_use_the_standard_envelope() {
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(test_repo().await),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v2/threads/999999999")
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 404);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "not_found");
+    assert_eq!(body["error"]["status"], 404);
+
+    let req = test::TestRequest::post()
+        .uri("/api/v2/threads")
+        .set_json(json!({"board_id": 1, "subject": "x", "body": "y"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 401);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "unauthorized");
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v2/threads/abc")
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 400);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "bad_request");
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get().uri("/api/v2/nope").to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 404);
+    let body: Value = test::read_body_json(resp).await;
+    assert_eq!(body["meta"]["api_version"], "v2");
+}
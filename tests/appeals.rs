@@ -0,0 +1,328 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::mailer::{Email, Mailer};
+use rib::models::{Appeal, AppealStatus, Board, Reply, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::{Repo, RoleRepo};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::{Arc, Mutex};
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct RecordingMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+impl RecordingMailer {
+    fn take(&self) -> Vec<Email> {
+        std::mem::take(&mut *self.sent.lock().unwrap())
+    }
+}
+
+#[async_trait::async_trait]
+impl Mailer for RecordingMailer {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    async fn send(&self, email: &Email) -> anyhow::Result<()> {
+        self.sent.lock().unwrap().push(email.clone());
+        Ok(())
+    }
+}
+
+fn link_path(text: &str, prefix: &str) -> String {
+    let start = text.find(prefix).expect("link in email");
+    text[start..].split_whitespace().next().unwrap().to_string()
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn appeals_are_filed_once_and_decided_by_moderators() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    std::env::set_var("FRONTEND_URL", "http://localhost:5173");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let user_id = format!("appeal-{}", &suffix[..8]);
+    let subject = format!("discord:{user_id}");
+    let address = format!("appeal{}@example.org", &suffix[..8]);
+    let repo = PgRepo::new(pool);
+    repo.set_subject_role(&subject, Role::User)
+        .await
+        .expect("allowlist poster");
+    let repo: Arc<dyn Repo> = Arc::new(repo);
+    let mailer = Arc::new(RecordingMailer::default());
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(
+                AppState::new(repo, Arc::new(MockImageStore), None)
+                    .with_mailer(Some(mailer.clone())),
+            ))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt("mod-id", "mod-id", vec![Role::Moderator]).unwrap();
+    let user = create_jwt(&user_id, &user_id, vec![Role::User]).unwrap();
+
+    // Confirm an address so decisions are mailed.
+    let resp = call!(
+        app,
+        test::TestRequest::put()
+            .uri("/api/v1/users/me/notifications")
+            .set_json(json!({"email": address, "digest": "off"})),
+        user
+    );
+    assert_eq!(resp.status(), 200);
+    let confirmation = mailer.take().pop().expect("confirmation email");
+    let confirm = link_path(&confirmation.body, "http://localhost:5173/");
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(confirm.trim_start_matches("http://localhost:5173"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": format!("ap{}", &suffix[..8]), "title": "Appeals"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .set_json(json!({"board_id": board.id, "subject": "mine", "body": "op"})),
+        user
+    );
+    let thread: Thread = test::read_body_json(resp).await;
+    let reply = |content: &str| {
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .set_json(json!({"thread_id": thread.id, "content": content}))
+    };
+    let resp = call!(app, reply("deleted by staff"), user);
+    let removed: Reply = test::read_body_json(resp).await;
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/replies/{}/soft-delete", removed.id))
+            .set_json(json!({"reason": "Rule 2: spam"})),
+        moderator
+    );
+    assert_eq!(resp.status(), 200);
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/admin/bans")
+            .set_json(json!({"subject": subject, "reason": "spam"})),
+        moderator
+    );
+    assert_eq!(resp.status(), 201);
+    assert_eq!(call!(app, reply("still banned?"), user).status(), 403);
+
+    let appeal = |body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri("/api/v1/appeals")
+            .set_json(body)
+    };
+    let reply_appeal =
+        json!({"kind": "reply", "target_id": removed.id, "message": "it was on topic"});
+    assert_eq!(
+        call!(app, appeal(json!({"kind": "reply", "message": "x"})), user).status(),
+        400
+    );
+    assert_eq!(
+        call!(
+            app,
+            appeal(json!({"kind": "ban", "target_id": 1, "message": "x"})),
+            user
+        )
+        .status(),
+        400
+    );
+    assert_eq!(
+        call!(
+            app,
+            appeal(json!({"kind": "reply", "target_id": removed.id, "message": " "})),
+            user
+        )
+        .status(),
+        400
+    );
+    assert_eq!(
+        call!(
+            app,
+            appeal(json!({"kind": "thread", "target_id": thread.id, "message": "x"})),
+            user
+        )
+        .status(),
+        404,
+        "visible posts cannot be appealed"
+    );
+    assert_eq!(
+        call!(app, appeal(reply_appeal.clone()), admin).status(),
+        404
+    );
+    let resp = call!(app, appeal(reply_appeal.clone()), user);
+    assert_eq!(resp.status(), 201);
+    let reply_case: Appeal = test::read_body_json(resp).await;
+    assert_eq!(reply_case.status, AppealStatus::Pending);
+    assert_eq!(call!(app, appeal(reply_appeal), user).status(), 409);
+    let resp = call!(
+        app,
+        appeal(json!({"kind": "ban", "message": "sorry"})),
+        user
+    );
+    assert_eq!(resp.status(), 201, "banned users can appeal");
+    let ban_case: Appeal = test::read_body_json(resp).await;
+
+    let queue = |status: &str| {
+        test::TestRequest::get().uri(&format!("/api/v1/admin/appeals?status={status}"))
+    };
+    assert_eq!(call!(app, queue("pending"), user).status(), 403);
+    assert_eq!(call!(app, queue("open"), moderator).status(), 400);
+    let resp = call!(app, queue("pending"), moderator);
+    let pending: Vec<Appeal> = test::read_body_json(resp).await;
+    let mine: Vec<_> = pending
+        .iter()
+        .filter(|a| a.subject == subject)
+        .map(|a| a.id)
+        .collect();
+    assert_eq!(mine, vec![reply_case.id, ban_case.id]);
+
+    // Accepting restores the post and tells the user.
+    let decide = |id: i64, verdict: &str, body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/appeals/{id}/{verdict}"))
+            .set_json(body)
+    };
+    assert_eq!(
+        call!(app, decide(reply_case.id, "accept", json!({})), user).status(),
+        403
+    );
+    let resp = call!(
+        app,
+        decide(reply_case.id, "accept", json!({"note": "fair enough"})),
+        moderator
+    );
+    assert_eq!(resp.status(), 200);
+    let decided: Appeal = test::read_body_json(resp).await;
+    assert_eq!(decided.status, AppealStatus::Accepted);
+    assert_eq!(decided.note.as_deref(), Some("fair enough"));
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/threads/{}/replies", thread.id))
+            .to_request(),
+    )
+    .await;
+    let replies: Vec<Reply> = test::read_body_json(resp).await;
+    assert!(replies.iter().any(|r| r.id == removed.id));
+    let sent = mailer.take();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to, address);
+    assert_eq!(sent[0].subject, "Your appeal was accepted");
+    assert!(sent[0].body.contains("fair enough"));
+    assert_eq!(
+        call!(app, decide(reply_case.id, "deny", json!({})), moderator).status(),
+        409
+    );
+    assert_eq!(
+        call!(app, decide(i64::MAX, "deny", json!({})), moderator).status(),
+        404
+    );
+
+    // Denying keeps the ban.
+    let resp = call!(app, decide(ban_case.id, "deny", json!({})), moderator);
+    assert_eq!(resp.status(), 200);
+    assert_eq!(mailer.take()[0].subject, "Your appeal was denied");
+    assert_eq!(call!(app, reply("still banned?"), user).status(), 403);
+    assert_eq!(
+        call!(
+            app,
+            appeal(json!({"kind": "ban", "message": "again"})),
+            user
+        )
+        .status(),
+        409,
+        "one appeal per ban"
+    );
+
+    // A new ban is a new action and may be appealed; accepting lifts it.
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/admin/bans")
+            .set_json(json!({"subject": subject, "reason": "spam again"})),
+        moderator
+    );
+    assert_eq!(resp.status(), 201);
+    let resp = call!(
+        app,
+        appeal(json!({"kind": "ban", "message": "new ban"})),
+        user
+    );
+    assert_eq!(resp.status(), 201);
+    let rebanned: Appeal = test::read_body_json(resp).await;
+    let resp = call!(app, decide(rebanned.id, "accept", json!({})), admin);
+    assert_eq!(resp.status(), 200);
+    assert_eq!(call!(app, reply("back again"), user).status(), 201);
+
+    let resp = call!(
+        app,
+        test::TestRequest::get().uri("/api/v1/users/me/appeals"),
+        user
+    );
+    let history: Vec<Appeal> = test::read_body_json(resp).await;
+    let statuses: Vec<_> = history.iter().map(|a| (a.id, a.status)).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            (rebanned.id, AppealStatus::Accepted),
+            (ban_case.id, AppealStatus::Denied),
+            (reply_case.id, AppealStatus::Accepted),
+        ]
+    );
+}
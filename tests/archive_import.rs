@@ -0,0 +1,146 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{SortOrder, ThreadOrder};
+use rib::repo::pg::PgRepo;
+use rib::repo::{BoardRepo, ReplyRepo, ThreadRepo};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[derive(Default)]
+struct MockImageStore {
+    inner: Mutex<HashMap<String, (Vec<u8>, String)>>,
+}
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, hash: &str, mime: &str, bytes: &[u8]) -> Result<(), ImageStoreError> {
+        let mut m = self.inner.lock().unwrap();
+        if m.contains_key(hash) {
+            return Err(ImageStoreError::Duplicate);
+        }
+        m.insert(hash.to_string(), (bytes.to_vec(), mime.to_string()));
+        Ok(())
+    }
+
+    async fn load(&self, hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        let m = self.inner.lock().unwrap();
+        m.get(hash).cloned().ok_or(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, hash: &str) -> Result<(), ImageStoreError> {
+        self.inner.lock().unwrap().remove(hash);
+        Ok(())
+    }
+}
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+fn admin_token() -> String {
+    if std::env::var("JWT_SECRET").is_err() {
+        std::env::set_var("JWT_SECRET", "testsecret");
+    }
+    create_jwt("admin", "admin", vec![Role::Admin]).unwrap()
+}
+
+#[actix_web::test]
+async fn imports_4chan_thread_with_media() {
+    let media = MockServer::start().await;
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    png.extend_from_slice(&[0u8; 32]);
+    Mock::given(method("GET"))
+        .and(path("/g/1700000000123.png"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(png))
+        .mount(&media)
+        .await;
+
+    let store = Arc::new(MockImageStore::default());
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(test_repo().await),
+                store.clone(),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let slug = format!("arc{}", &uuid::Uuid::new_v4().simple().to_string()[..12]);
+    let payload = json!({
+        "board": {"slug": slug, "title": "Archive"},
+        "source_board": "g",
+        "mapping": {"media_url": format!("{}/{{board}}/{{tim}}{{ext}}", media.uri())},
+        "threads": [{"posts": [
+            {"no": 500, "resto": 0, "time": 1_700_000_000, "name": "Anonymous",
+             "sub": "Archived", "com": "op body", "tim": 1_700_000_000_123_i64, "ext": ".png"},
+            {"no": 501, "resto": 500, "time": 1_700_000_100, "com": "&gt;&gt;500<br>reply",
+             "tim": 1_700_000_000_999_i64, "ext": ".png"}
+        ]}]
+    });
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/admin/import/archive?dry_run=true")
+        .insert_header(("Authorization", format!("Bearer {}", admin_token())))
+        .set_json(&payload)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let report: Value = test::read_body_json(resp).await;
+    assert_eq!(report["import"]["committed"], false);
+    assert_eq!(report["media"]["skipped"], 2);
+    assert!(store.inner.lock().unwrap().is_empty());
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/admin/import/archive")
+        .insert_header(("Authorization", format!("Bearer {}", admin_token())))
+        .set_json(&payload)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let report: Value = test::read_body_json(resp).await;
+    assert_eq!(report["import"]["committed"], true);
+    assert_eq!(report["import"]["threads"]["created"], 1);
+    assert_eq!(report["media"]["downloaded"], 1);
+    // The reply's media is missing upstream; the reply is imported without it.
+    assert_eq!(report["media"]["failed"].as_array().unwrap().len(), 1);
+
+    let repo = test_repo().await;
+    let board = repo
+        .list_boards(false)
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|b| b.slug == slug)
+        .expect("board created");
+    let threads = repo
+        .list_threads(board.id, false, ThreadOrder::default())
+        .await
+        .unwrap();
+    assert_eq!(threads[0].subject, "Archived");
+    assert_eq!(threads[0].created_at.timestamp(), 1_700_000_000);
+    assert_eq!(threads[0].mime.as_deref(), Some("image/png"));
+    assert!(store
+        .inner
+        .lock()
+        .unwrap()
+        .contains_key(threads[0].image_hash.as_ref().unwrap()));
+    let replies = repo
+        .list_replies(threads[0].id, false, SortOrder::Asc)
+        .await
+        .unwrap();
+    assert_eq!(replies[0].content, ">>500\nreply");
+    assert!(replies[0].image_hash.is_none());
+}
@@ -117,11 +117,17 @@ async fn require_role_macro_enforces_roles() {
         sub: "1:a".into(),
         exp: usize::MAX,
         roles: vec![Role::Admin],
+        balance_verified_at: None,
+        act_as: None,
+        impersonated_by: None,
     });
     let user = Auth(Claims {
         sub: "2:u".into(),
         exp: usize::MAX,
         roles: vec![Role::User],
+        balance_verified_at: None,
+        act_as: None,
+        impersonated_by: None,
     });
 
     // Admin passes the guard.
@@ -0,0 +1,147 @@
+use actix_web::{test, web, App};
+use rib::models::{NewBoard, NewReply, NewThread, PublicIdentity, SortOrder, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::{BoardRepo, ReplyRepo, ThreadRepo};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+async fn board(repo: &PgRepo) -> i64 {
+    repo.create_board(NewBoard {
+        slug: format!("bt{}", &uuid::Uuid::new_v4().simple().to_string()[..10]),
+        title: "Batch".into(),
+    })
+    .await
+    .unwrap()
+    .id
+}
+
+async fn thread(repo: &PgRepo, board_id: i64, replies: usize) -> Thread {
+    let thread = repo
+        .create_thread(
+            NewThread {
+                board_id,
+                subject: "preview".into(),
+                body: "body".into(),
+                image_hash: None,
+                mime: None,
+                author_name: None,
+                tripcode_password: None,
+                delete_password: None,
+                tags: Vec::new(),
+            },
+            json!({"provider": "test", "subject": "test:batch"}),
+            PublicIdentity {
+                author_name: None,
+                tripcode: None,
+            },
+        )
+        .await
+        .unwrap();
+    for n in 0..replies {
+        repo.create_reply(
+            NewReply {
+                thread_id: thread.id,
+                content: format!("reply {n}"),
+                image_hash: None,
+                mime: None,
+                author_name: None,
+                tripcode_password: None,
+                delete_password: None,
+            },
+            json!({"provider": "test", "subject": "test:batch"}),
+            PublicIdentity {
+                author_name: None,
+                tripcode: None,
+            },
+        )
+        .await
+        .unwrap();
+    }
+    thread
+}
+
+#[actix_web::test]
+async fn batch_returns_visible_threads_with_first_replies() {
+    let repo = test_repo().await;
+    let live_board = board(&repo).await;
+    let busy = thread(&repo, live_board, 4).await;
+    let quiet = thread(&repo, live_board, 0).await;
+    let removed = thread(&repo, live_board, 1).await;
+    repo.soft_delete_thread(removed.id).await.unwrap();
+    let gone_board = board(&repo).await;
+    let orphan = thread(&repo, gone_board, 1).await;
+    repo.soft_delete_board(gone_board).await.unwrap();
+    let replies = repo
+        .list_replies(busy.id, false, SortOrder::Asc)
+        .await
+        .unwrap();
+    repo.soft_delete_reply(replies[0].id).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let req = test::TestRequest::post()
+        .uri("/api/v1/batch")
+        .set_json(json!({
+            "thread_ids": [quiet.id, i64::MAX, busy.id, removed.id, quiet.id, orphan.id],
+            "replies": 2,
+        }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let previews = body.as_array().unwrap();
+    assert_eq!(previews.len(), 2);
+    assert_eq!(previews[0]["thread"]["id"], quiet.id);
+    assert_eq!(previews[0]["replies"].as_array().unwrap().len(), 0);
+    assert_eq!(previews[1]["thread"]["id"], busy.id);
+    let first: Vec<&str> = previews[1]["replies"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["content"].as_str().unwrap())
+        .collect();
+    assert_eq!(first, ["reply 1", "reply 2"]);
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/batch")
+        .set_json(json!({"thread_ids": (0..101).collect::<Vec<i64>>()}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+}
@@ -0,0 +1,402 @@
+use actix_web::body::MessageBody;
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::live::LiveHub;
+use rib::models::{
+    Board, BridgeKind, NewApFollower, NewBoardBridge, NewMatrixMirror, OutboxEvent, Reply, Thread,
+};
+use rib::repo::pg::PgRepo;
+use rib::repo::{BridgeRepo, FederationRepo, MatrixRepo, RoleRepo};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::pin::Pin;
+use std::sync::Arc;
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn beta_boards_are_only_open_to_staff_and_testers() {
+    std::env::set_var("JWT_SECRET", "testsecret");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let repo = Arc::new(PgRepo::new(pool));
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                repo.clone(),
+                Arc::new(NoImages),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let tester_id = format!("tester-{}", &suffix[..8]);
+    let outsider_id = format!("outsider-{}", &suffix[..8]);
+    let tester_subject = format!("discord:{tester_id}");
+    let outsider_subject = format!("discord:{outsider_id}");
+    for subject in [&tester_subject, &outsider_subject] {
+        repo.set_subject_role(subject, Role::User).await.unwrap();
+    }
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt("mod-id", "mod-id", vec![Role::Moderator]).unwrap();
+    let tester = create_jwt(&tester_id, &tester_id, vec![Role::User]).unwrap();
+    let outsider = create_jwt(&outsider_id, &outsider_id, vec![Role::User]).unwrap();
+
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": format!("bt{}", &suffix[..8]), "title": "Beta"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    assert!(!board.beta);
+    let set_beta = |beta: bool| {
+        test::TestRequest::patch()
+            .uri(&format!("/api/v1/boards/{}", board.id))
+            .set_json(json!({ "beta": beta }))
+    };
+    let resp = call!(app, set_beta(true), admin);
+    assert_eq!(resp.status(), 200);
+    let updated: Board = test::read_body_json(resp).await;
+    assert!(updated.beta);
+
+    let listed = |boards: Vec<Board>| boards.iter().any(|b| b.id == board.id);
+    let boards = || test::TestRequest::get().uri("/api/v1/boards");
+    let threads = || test::TestRequest::get().uri(&format!("/api/v1/boards/{}/threads", board.id));
+    let marker = format!("marker{}", &suffix[..8]);
+    let post = || {
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .set_json(json!({"board_id": board.id, "subject": "trial", "body": marker}))
+    };
+    let resp = test::call_service(&app, boards().to_request()).await;
+    assert!(!listed(test::read_body_json(resp).await));
+    assert_eq!(
+        test::call_service(&app, threads().to_request())
+            .await
+            .status(),
+        404
+    );
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v2/boards/{}", board.id))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 404);
+    assert!(listed(
+        test::read_body_json(call!(app, boards(), moderator)).await
+    ));
+    assert_eq!(call!(app, threads(), moderator).status(), 200);
+    assert!(!listed(
+        test::read_body_json(call!(app, boards(), tester)).await
+    ));
+    assert_eq!(call!(app, post(), tester).status(), 404);
+
+    // Only admins manage the allowlist.
+    let testers = || {
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/boards/{}/testers", board.id))
+            .set_json(json!({ "subject": tester_subject }))
+    };
+    assert_eq!(call!(app, testers(), moderator).status(), 403);
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/boards/{}/testers", board.id))
+            .set_json(json!({"subject": "nobody"})),
+        admin
+    );
+    assert_eq!(resp.status(), 400);
+    assert_eq!(call!(app, testers(), admin).status(), 201);
+    let resp = call!(app, testers(), admin);
+    assert_eq!(resp.status(), 201);
+    let entry: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(entry["added_by"], "admin-id:admin-id");
+    let resp = call!(
+        app,
+        test::TestRequest::get().uri(&format!("/api/v1/admin/boards/{}/testers", board.id)),
+        admin
+    );
+    let listed_testers: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(listed_testers.len(), 1);
+
+    assert!(listed(
+        test::read_body_json(call!(app, boards(), tester)).await
+    ));
+    let resp = call!(app, post(), tester);
+    assert_eq!(resp.status(), 201);
+    let thread: Thread = test::read_body_json(resp).await;
+    assert_eq!(call!(app, threads(), tester).status(), 200);
+    assert!(!listed(
+        test::read_body_json(call!(app, boards(), outsider)).await
+    ));
+    assert_eq!(call!(app, post(), outsider).status(), 404);
+    let reply = || {
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .set_json(json!({"thread_id": thread.id, "content": "can I see this?"}))
+    };
+    assert_eq!(call!(app, reply(), outsider).status(), 404);
+    assert_eq!(call!(app, reply(), tester).status(), 201);
+
+    // Threads and replies cannot be read by id, streamed or found by search.
+    let thread_get = || test::TestRequest::get().uri(&format!("/api/v1/threads/{}", thread.id));
+    let search = || test::TestRequest::get().uri(&format!("/api/v1/search?q={marker}"));
+    let hits = |body: serde_json::Value| body["hits"].as_array().unwrap().len();
+    let replies_uri = format!("/api/v1/threads/{}/replies", thread.id);
+    for (uri, accept) in [
+        (format!("/api/v1/threads/{}", thread.id), "application/json"),
+        (replies_uri.clone(), "application/json"),
+        (replies_uri, "application/x-ndjson"),
+    ] {
+        let read = || {
+            test::TestRequest::get()
+                .uri(&uri)
+                .insert_header(("Accept", accept))
+        };
+        assert_eq!(call!(app, read(), outsider).status(), 404);
+        assert_eq!(
+            test::call_service(&app, read().to_request()).await.status(),
+            404
+        );
+        assert_eq!(call!(app, read(), tester).status(), 200);
+    }
+    assert_eq!(
+        hits(test::read_body_json(call!(app, search(), outsider)).await),
+        0
+    );
+    let resp = test::call_service(&app, search().to_request()).await;
+    assert_eq!(hits(test::read_body_json(resp).await), 0);
+    assert_eq!(
+        hits(test::read_body_json(call!(app, search(), tester)).await),
+        1
+    );
+
+    let remove = || {
+        test::TestRequest::delete().uri(&format!(
+            "/api/v1/admin/boards/{}/testers/{tester_subject}",
+            board.id
+        ))
+    };
+    assert_eq!(call!(app, remove(), admin).status(), 204);
+    assert_eq!(call!(app, remove(), admin).status(), 404);
+    assert_eq!(call!(app, threads(), tester).status(), 404);
+
+    // Launching opens the board to everyone.
+    assert_eq!(call!(app, set_beta(false), admin).status(), 200);
+    assert!(listed(
+        test::read_body_json(call!(app, boards(), outsider)).await
+    ));
+    assert_eq!(call!(app, reply(), outsider).status(), 201);
+    assert_eq!(call!(app, thread_get(), outsider).status(), 200);
+    assert_eq!(
+        hits(test::read_body_json(call!(app, search(), outsider)).await),
+        1
+    );
+
+    for subject in [&tester_subject, &outsider_subject] {
+        repo.delete_role(subject).await.unwrap();
+    }
+}
+
+async fn next_frame<B>(body: &mut B) -> String
+where
+    B: MessageBody + Unpin,
+    B::Error: std::fmt::Debug,
+{
+    let chunk = futures_util::future::poll_fn(|cx| Pin::new(&mut *body).poll_next(cx))
+        .await
+        .expect("stream open")
+        .expect("chunk");
+    String::from_utf8(chunk.to_vec()).unwrap()
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn beta_boards_stay_out_of_outbound_queues() {
+    std::env::set_var("JWT_SECRET", "testsecret");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let repo = Arc::new(PgRepo::new(pool));
+    let hub = LiveHub::default();
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(
+                AppState::new(repo.clone(), Arc::new(NoImages), None).with_live(hub.clone()),
+            ))
+            .configure(config),
+    )
+    .await;
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": format!("bq{}", &suffix[..8]), "title": "Beta queues"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    let set_beta = |beta: bool| {
+        test::TestRequest::patch()
+            .uri(&format!("/api/v1/boards/{}", board.id))
+            .set_json(json!({ "beta": beta }))
+    };
+    assert_eq!(call!(app, set_beta(true), admin).status(), 200);
+    let bridge = repo
+        .create_board_bridge(
+            board.id,
+            NewBoardBridge {
+                kind: BridgeKind::Discord,
+                target: format!("https://discord.example/api/webhooks/{suffix}"),
+            },
+            "admin-id:admin-id",
+        )
+        .await
+        .unwrap();
+    let mirror = repo
+        .create_matrix_mirror(
+            NewMatrixMirror {
+                board_id: Some(board.id),
+                thread_id: None,
+                room_id: format!("!{suffix}:matrix.example"),
+            },
+            "admin-id:admin-id",
+        )
+        .await
+        .unwrap();
+    let actor = format!("https://remote.example/users/{suffix}");
+    repo.add_ap_follower(
+        board.id,
+        NewApFollower {
+            actor: actor.clone(),
+            inbox: format!("https://remote.example/users/{suffix}/inbox"),
+            shared_inbox: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .set_json(json!({"board_id": board.id, "subject": "trial", "body": "not yet"})),
+        admin
+    );
+    assert_eq!(resp.status(), 201);
+    let thread: Thread = test::read_body_json(resp).await;
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .set_json(json!({"thread_id": thread.id, "content": "still not yet"})),
+        admin
+    );
+    assert_eq!(resp.status(), 201);
+    let reply: Reply = test::read_body_json(resp).await;
+
+    let bridged = || async {
+        repo.pending_bridge_threads(&bridge, 10, 0)
+            .await
+            .unwrap()
+            .iter()
+            .map(|t| t.id)
+            .collect::<Vec<_>>()
+    };
+    let mirrored = || async {
+        repo.pending_matrix_posts(&mirror, 10, 0)
+            .await
+            .unwrap()
+            .iter()
+            .map(|p| (p.thread_id, p.reply_id))
+            .collect::<Vec<_>>()
+    };
+    let federated = || async {
+        repo.pending_ap_threads(1000, 0)
+            .await
+            .unwrap()
+            .iter()
+            .filter(|t| t.board_id == board.id)
+            .map(|t| t.id)
+            .collect::<Vec<_>>()
+    };
+    // Live subscribers who may not see the board skip its events.
+    let live = || test::TestRequest::get().uri("/api/v1/live");
+    let mut anonymous = test::call_service(&app, live().to_request())
+        .await
+        .into_body();
+    let mut staff = call!(app, live(), admin).into_body();
+    let event = |id: i64, thread_id: i64| OutboxEvent {
+        id,
+        event_type: "reply.created".into(),
+        payload: json!({"reply_id": id, "thread_id": thread_id}),
+        created_at: chrono::Utc::now(),
+        attempts: 0,
+    };
+    hub.publish_local(event(reply.id, thread.id));
+    hub.publish_local(event(-1, -1));
+    let frame = next_frame(&mut anonymous).await;
+    assert!(frame.starts_with("id: -1\n"), "{frame}");
+    let frame = next_frame(&mut staff).await;
+    assert!(frame.starts_with(&format!("id: {}\n", reply.id)), "{frame}");
+
+    assert!(bridged().await.is_empty());
+    assert!(mirrored().await.is_empty());
+    assert!(federated().await.is_empty());
+
+    // Launching releases what was posted during the trial.
+    assert_eq!(call!(app, set_beta(false), admin).status(), 200);
+    assert_eq!(bridged().await, vec![thread.id]);
+    assert_eq!(
+        mirrored().await,
+        vec![(thread.id, None), (thread.id, Some(reply.id))]
+    );
+    assert_eq!(federated().await, vec![thread.id]);
+
+    repo.delete_board_bridge(bridge.id).await.unwrap();
+    repo.delete_matrix_mirror(mirror.id).await.unwrap();
+    repo.remove_ap_follower(board.id, &actor).await.unwrap();
+}
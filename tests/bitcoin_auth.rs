@@ -1,4 +1,5 @@
 use actix_web::{test, App};
+use rib::auth::Role;
 use rib::repo::pg::PgRepo;
 use rib::storage::{ImageStore, ImageStoreError};
 use rib::{config, AppState};
@@ -56,11 +57,7 @@ async fn bitcoin_auth_happy_path_with_test_bypass() {
     // Bypass both signature + balance by setting both granular skips
     std::env::set_var("BTC_AUTH_TEST_SKIP_SIG", "1");
     std::env::set_var("BTC_AUTH_TEST_SKIP_BALANCE", "1");
-    let state = AppState {
-        repo: Arc::new(repo),
-        image_store: Arc::new(MockImageStore::default()),
-        rate_limiter: None,
-    };
+    let state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
     let app = test::init_service(
         App::new()
             .app_data(actix_web::web::Data::new(state))
@@ -103,11 +100,7 @@ async fn bitcoin_auth_verify_bech32_real_signature() {
     // Ensure we exercise signature path (not bypass) but skip external balance HTTP
     std::env::remove_var("BTC_AUTH_TEST_SKIP_SIG");
     std::env::set_var("BTC_AUTH_TEST_SKIP_BALANCE", "1"); // skip external balance HTTP
-    let state = AppState {
-        repo: Arc::new(repo),
-        image_store: Arc::new(MockImageStore::default()),
-        rate_limiter: None,
-    };
+    let state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
     let app = test::init_service(
         App::new()
             .app_data(actix_web::web::Data::new(state))
@@ -147,11 +140,7 @@ async fn bitcoin_auth_verify_bech32_real_signature_case2() {
     // Exercise real signature path, skip external balance HTTP
     std::env::remove_var("BTC_AUTH_TEST_SKIP_SIG");
     std::env::set_var("BTC_AUTH_TEST_SKIP_BALANCE", "1"); // skip external balance HTTP
-    let state = AppState {
-        repo: Arc::new(repo),
-        image_store: Arc::new(MockImageStore::default()),
-        rate_limiter: None,
-    };
+    let state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
     let app = test::init_service(
         App::new()
             .app_data(actix_web::web::Data::new(state))
@@ -182,3 +171,94 @@ async fn bitcoin_auth_verify_bech32_real_signature_case2() {
     let token = body.get("token").and_then(|v| v.as_str()).expect("token");
     assert!(token.starts_with("ey")); // JWT header base64
 }
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn bitcoin_auth_challenges_follow_the_configured_network() {
+    let repo = pg_repo().await;
+    ensure_secret();
+    std::env::set_var("BTC_NETWORK", "testnet");
+    let state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(state))
+            .configure(config),
+    )
+    .await;
+
+    // Mainnet addresses are refused on testnet.
+    let req = test::TestRequest::post()
+        .uri("/api/v1/auth/bitcoin/challenge")
+        .set_json(json!({"address": "1BoatSLRHtKNngkdXEeobR76b53LETtpyT"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+
+    let address = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+    let req = test::TestRequest::post()
+        .uri("/api/v1/auth/bitcoin/challenge")
+        .set_json(json!({"address": address}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    let challenge = body["challenge"].as_str().expect("challenge str");
+    assert!(challenge.starts_with(&format!("Prove you own Bitcoin testnet address {address}")));
+    std::env::remove_var("BTC_NETWORK");
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn bitcoin_refresh_rechecks_stale_balances() {
+    let repo = pg_repo().await;
+    ensure_secret();
+    std::env::remove_var("BTC_AUTH_TEST_SKIP_BALANCE");
+    let state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(state))
+            .configure(config),
+    )
+    .await;
+    let subject = "btc:bc1qs39xhnvs4fapud7hteh6anyr8dl09e5e8km875";
+    let refresh = |token: &str| {
+        test::TestRequest::post()
+            .uri("/api/v1/auth/refresh")
+            .insert_header(("Authorization", format!("Bearer {token}")))
+            .to_request()
+    };
+    let refreshed_claims = |body: &serde_json::Value| {
+        rib::auth::decode_jwt(body["token"].as_str().expect("token")).expect("valid token")
+    };
+
+    // Verified long ago and the funds are gone: posting rights are dropped.
+    std::env::set_var("BTC_AUTH_TEST_BALANCE_OVERRIDE", "0");
+    let stale = rib::auth::reissue_bitcoin_jwt(subject, vec![Role::User], 0).unwrap();
+    let resp = test::call_service(&app, refresh(&stale)).await;
+    assert_eq!(resp.status(), 200);
+    let claims = refreshed_claims(&test::read_body_json::<serde_json::Value, _>(resp).await);
+    assert!(claims.roles.is_empty());
+    assert_eq!(claims.balance_verified_at, Some(0));
+
+    // Funds are back: the role returns and the verification time moves on.
+    std::env::set_var("BTC_AUTH_TEST_BALANCE_OVERRIDE", "5000000");
+    let downgraded = rib::auth::reissue_bitcoin_jwt(subject, Vec::new(), 0).unwrap();
+    let resp = test::call_service(&app, refresh(&downgraded)).await;
+    let claims = refreshed_claims(&test::read_body_json::<serde_json::Value, _>(resp).await);
+    assert_eq!(claims.roles, vec![Role::User]);
+    assert!(claims.balance_verified_at.unwrap() > 0);
+
+    // Recently verified sessions are not looked up again.
+    std::env::set_var("BTC_AUTH_TEST_BALANCE_OVERRIDE", "0");
+    let fresh = rib::auth::create_bitcoin_jwt(
+        "bc1qs39xhnvs4fapud7hteh6anyr8dl09e5e8km875",
+        vec![Role::User],
+    )
+    .unwrap();
+    let resp = test::call_service(&app, refresh(&fresh)).await;
+    assert_eq!(
+        refreshed_claims(&test::read_body_json::<serde_json::Value, _>(resp).await).roles,
+        vec![Role::User]
+    );
+    std::env::remove_var("BTC_AUTH_TEST_BALANCE_OVERRIDE");
+}
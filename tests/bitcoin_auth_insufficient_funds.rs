@@ -58,11 +58,7 @@ async fn bitcoin_auth_insufficient_funds() {
     std::env::set_var("BTC_MIN_BALANCE_SATS", "1000000"); // 1_000_000 sats threshold
     std::env::set_var("BTC_AUTH_TEST_BALANCE_OVERRIDE", "5000"); // only 5k sats (< threshold)
 
-    let state = AppState {
-        repo: Arc::new(repo),
-        image_store: Arc::new(MockImageStore::default()),
-        rate_limiter: None,
-    };
+    let state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
     let app = test::init_service(
         App::new()
             .app_data(actix_web::web::Data::new(state))
@@ -85,11 +85,7 @@ async fn bitcoin_auth_mocked_blockstream_balance_allows_login() {
         .mount(&mock_server)
         .await;
 
-    let state = AppState {
-        repo: Arc::new(repo),
-        image_store: Arc::new(MockImageStore::default()),
-        rate_limiter: None,
-    };
+    let state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
     let app = test::init_service(
         App::new()
             .app_data(actix_web::web::Data::new(state))
@@ -120,3 +116,61 @@ async fn bitcoin_auth_mocked_blockstream_balance_allows_login() {
             > 10
     );
 }
+
+// With BTC_MIN_CONFIRMATIONS above one, only UTXOs buried deep enough below the tip count.
+#[actix_web::test]
+#[serial_test::serial]
+async fn bitcoin_auth_requires_min_confirmations() {
+    let repo = pg_repo().await;
+    ensure_secret();
+    std::env::remove_var("BTC_AUTH_TEST_BALANCE_OVERRIDE");
+    std::env::set_var("BTC_AUTH_TEST_SKIP_BALANCE", "0");
+    std::env::set_var("BTC_AUTH_TEST_SKIP_SIG", "1");
+    std::env::set_var("BTC_MIN_CONFIRMATIONS", "6");
+
+    let mock_server = MockServer::start().await;
+    std::env::set_var("BTC_BLOCKSTREAM_API_BASE", mock_server.uri());
+    let address = "bc1qs39xhnvs4fapud7hteh6anyr8dl09e5e8km875";
+    Mock::given(method("GET"))
+        .and(path("/blocks/tip/height"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("900000"))
+        .mount(&mock_server)
+        .await;
+    // 2 and 6 confirmations: only the smaller, older output counts.
+    let utxos = serde_json::json!([
+        {"txid":"abcd","vout":0,"status":{"confirmed":true,"block_height":899999},"value":5000000},
+        {"txid":"efgh","vout":1,"status":{"confirmed":true,"block_height":899995},"value":500000}
+    ]);
+    Mock::given(method("GET"))
+        .and(path(format!("/address/{}/utxo", address)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(utxos))
+        .mount(&mock_server)
+        .await;
+
+    let state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(state))
+            .configure(config),
+    )
+    .await;
+    let verify = |min_balance: &str| {
+        std::env::set_var("BTC_MIN_BALANCE_SATS", min_balance);
+        test::TestRequest::post()
+            .uri("/api/v1/auth/bitcoin/verify")
+            .set_json(json!({"address": address, "signature": "dummysig"}))
+            .to_request()
+    };
+
+    rib::btc_test_insert_challenge(address, "challenge").await;
+    let resp = test::call_service(&app, verify("1000000")).await;
+    assert_eq!(resp.status(), 403, "shallow UTXOs must not count");
+
+    rib::btc_test_insert_challenge(address, "challenge").await;
+    let resp = test::call_service(&app, verify("500000")).await;
+    assert_eq!(resp.status(), 200, "deep enough UTXOs count");
+
+    std::env::remove_var("BTC_MIN_CONFIRMATIONS");
+    std::env::remove_var("BTC_MIN_BALANCE_SATS");
+    std::env::remove_var("BTC_AUTH_TEST_SKIP_SIG");
+}
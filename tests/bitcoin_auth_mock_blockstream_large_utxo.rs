@@ -97,11 +97,7 @@ async fn bitcoin_auth_ignores_large_unconfirmed_utxo_balance() {
         .mount(&mock_server)
         .await;
 
-    let state = AppState {
-        repo: Arc::new(repo),
-        image_store: Arc::new(MockImageStore::default()),
-        rate_limiter: None,
-    };
+    let state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
     let app = test::init_service(
         App::new()
             .app_data(actix_web::web::Data::new(state))
@@ -0,0 +1,157 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, Thread};
+use rib::repo::pg::PgRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn renamed_boards_redirect_from_their_old_slugs() {
+    std::env::set_var("JWT_SECRET", "testsecret");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(PgRepo::new(pool)),
+                Arc::new(NoImages),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let suffix = &uuid::Uuid::new_v4().simple().to_string()[..8];
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let (first, second, third) = (
+        format!("old{suffix}"),
+        format!("new{suffix}"),
+        format!("newer{suffix}"),
+    );
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": first, "title": "Renames"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .set_json(json!({"board_id": board.id, "subject": "Moving day", "body": "hi"})),
+        admin
+    );
+    let thread: Thread = test::read_body_json(resp).await;
+    let rename = |slug: &str| {
+        test::TestRequest::patch()
+            .uri(&format!("/api/v1/boards/{}", board.id))
+            .set_json(json!({ "slug": slug }))
+    };
+    let get = |path: String| test::TestRequest::get().uri(&path).to_request();
+    let location = |resp: &actix_web::dev::ServiceResponse| {
+        resp.headers()
+            .get("Location")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string()
+    };
+    let thread_path =
+        |slug: &str| format!("/api/v1/boards/{slug}/threads/{}-moving-day", thread.id);
+
+    assert_eq!(call!(app, rename(&second), admin).status(), 200);
+    let resp = test::call_service(&app, get(thread_path(&first))).await;
+    assert_eq!(resp.status(), 301);
+    assert_eq!(location(&resp), thread_path(&second));
+    // The bare id behind an old board slug goes straight to the canonical URL.
+    let resp = test::call_service(
+        &app,
+        get(format!("/api/v1/boards/{first}/threads/{}", thread.id)),
+    )
+    .await;
+    assert_eq!(resp.status(), 301);
+    assert_eq!(location(&resp), thread_path(&second));
+    let resp = test::call_service(&app, get(format!("/_ssr/{first}"))).await;
+    assert_eq!(resp.status(), 301);
+    assert_eq!(location(&resp), format!("/_ssr/{second}"));
+    assert_eq!(
+        test::call_service(&app, get(format!("/_ssr/{second}")))
+            .await
+            .status(),
+        200
+    );
+
+    // Every earlier slug follows later renames.
+    assert_eq!(call!(app, rename(&third), admin).status(), 200);
+    for old in [&first, &second] {
+        let resp = test::call_service(&app, get(thread_path(old))).await;
+        assert_eq!(resp.status(), 301);
+        assert_eq!(location(&resp), thread_path(&third));
+        let resp = test::call_service(&app, get(format!("/_ssr/{old}"))).await;
+        assert_eq!(location(&resp), format!("/_ssr/{third}"));
+    }
+    assert_eq!(
+        test::call_service(&app, get(thread_path(&third)))
+            .await
+            .status(),
+        200
+    );
+    assert_eq!(
+        test::call_service(&app, get(thread_path(&format!("nope{suffix}"))))
+            .await
+            .status(),
+        404
+    );
+
+    // A board that takes over an old slug owns it from then on.
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": first, "title": "Squatter"})),
+        admin
+    );
+    assert_eq!(resp.status(), 201);
+    assert_eq!(
+        test::call_service(&app, get(format!("/_ssr/{first}")))
+            .await
+            .status(),
+        200
+    );
+}
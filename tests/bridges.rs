@@ -0,0 +1,247 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::bridges::{BridgeConfig, BridgeRunner};
+use rib::models::{Board, BoardBridge, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+use std::time::Duration;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+fn runner_config(telegram_api_url: String) -> BridgeConfig {
+    BridgeConfig {
+        enabled: true,
+        poll_interval: Duration::from_secs(1),
+        batch_size: 1000,
+        max_per_minute: 1,
+        settle: Duration::ZERO,
+        lease: Duration::from_secs(60),
+        retry: Duration::ZERO,
+        base_url: "https://rib.example".into(),
+        telegram_bot_token: Some("123:abc".into()),
+        telegram_api_url,
+        nostr_secret_key: None,
+    }
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn bridges_announce_new_threads_within_the_rate_limit() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    std::env::remove_var("BRIDGE_TELEGRAM_BOT_TOKEN");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let poster_id = format!("bridged-{}", &suffix[..8]);
+    let repo = Arc::new(PgRepo::new(pool.clone()));
+    repo.set_subject_role(&format!("discord:{poster_id}"), Role::User)
+        .await
+        .expect("allowlist poster");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                repo.clone(),
+                Arc::new(NoImages),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("bridge-admin", "bridge-admin", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt("mod-id", "mod-id", vec![Role::Moderator]).unwrap();
+    let poster = create_jwt(&poster_id, &poster_id, vec![Role::User]).unwrap();
+    let slug = format!("br{}", &suffix[..8]);
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": slug, "title": "Bridged"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+
+    let server = MockServer::start().await;
+    let webhook = format!("{}/webhook", server.uri());
+    let add = |body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/boards/{}/bridges", board.id))
+            .set_json(body)
+    };
+    let discord = json!({"kind": "discord", "target": webhook});
+    assert_eq!(call!(app, add(discord.clone()), moderator).status(), 403);
+    for invalid in [
+        json!({"kind": "discord", "target": "not a url"}),
+        json!({"kind": "telegram", "target": "@rib_news"}),
+        json!({"kind": "nostr", "target": "wss://relay.example"}),
+    ] {
+        assert_eq!(
+            call!(app, add(invalid.clone()), admin).status(),
+            400,
+            "{invalid}"
+        );
+    }
+    let resp = call!(app, add(discord.clone()), admin);
+    assert_eq!(resp.status(), 201);
+    let bridge: BoardBridge = test::read_body_json(resp).await;
+    assert_eq!(call!(app, add(discord), admin).status(), 409);
+
+    let mut threads = Vec::new();
+    for subject in ["First bridged", "Second bridged"] {
+        let resp = call!(
+            app,
+            test::TestRequest::post()
+                .uri("/api/v1/threads")
+                .set_json(json!({"board_id": board.id, "subject": subject, "body": "hello"})),
+            poster
+        );
+        assert_eq!(resp.status(), 201);
+        let thread: Thread = test::read_body_json(resp).await;
+        threads.push(thread);
+    }
+    for thread in &threads {
+        Mock::given(method("POST"))
+            .and(path("/webhook"))
+            .and(body_string_contains(format!(
+                "https://rib.example/thread/{}",
+                thread.id
+            )))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    // One post a minute: the second thread waits for the next window.
+    let runner = BridgeRunner::new(repo.clone(), runner_config(server.uri())).unwrap();
+    assert_eq!(runner.run_once().await, 1);
+    assert_eq!(runner.run_once().await, 0);
+    sqlx::query(
+        "UPDATE board_bridges SET window_started_at = now() - interval '2 minutes' WHERE id = $1",
+    )
+    .bind(bridge.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    assert_eq!(runner.run_once().await, 1);
+
+    let resp = call!(
+        app,
+        test::TestRequest::get().uri(&format!("/api/v1/admin/boards/{}/bridges", board.id)),
+        admin
+    );
+    let listed: Vec<BoardBridge> = test::read_body_json(resp).await;
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].last_thread_id, threads[1].id);
+    assert!(listed[0].last_sent_at.is_some());
+    assert!(listed[0].last_error.is_none());
+
+    // Threads posted while a bridge is off are not announced once it is back.
+    let toggle = |enabled: bool| {
+        test::TestRequest::patch()
+            .uri(&format!("/api/v1/admin/bridges/{}", bridge.id))
+            .set_json(json!({ "enabled": enabled }))
+    };
+    assert_eq!(call!(app, toggle(false), admin).status(), 200);
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .set_json(json!({"board_id": board.id, "subject": "While off", "body": "x"})),
+        poster
+    );
+    let skipped: Thread = test::read_body_json(resp).await;
+    let resp = call!(app, toggle(true), admin);
+    let enabled: BoardBridge = test::read_body_json(resp).await;
+    assert!(enabled.last_thread_id >= skipped.id);
+    sqlx::query("UPDATE board_bridges SET window_started_at = NULL WHERE id = $1")
+        .bind(bridge.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(runner.run_once().await, 0);
+
+    // A rejected post keeps the cursor and records the error.
+    let chat = json!({"kind": "telegram", "target": "-100123"});
+    std::env::set_var("BRIDGE_TELEGRAM_BOT_TOKEN", "123:abc");
+    let resp = call!(app, add(chat), admin);
+    std::env::remove_var("BRIDGE_TELEGRAM_BOT_TOKEN");
+    assert_eq!(resp.status(), 201);
+    let telegram: BoardBridge = test::read_body_json(resp).await;
+    Mock::given(method("POST"))
+        .and(path("/bot123:abc/sendMessage"))
+        .and(body_string_contains("-100123"))
+        .respond_with(ResponseTemplate::new(429))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .set_json(json!({"board_id": board.id, "subject": "Rate limited", "body": "x"})),
+        poster
+    );
+    assert_eq!(resp.status(), 201);
+    Mock::given(method("POST"))
+        .and(path("/webhook"))
+        .and(body_string_contains("Rate limited"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    assert_eq!(runner.run_once().await, 1);
+    let resp = call!(
+        app,
+        test::TestRequest::get().uri("/api/v1/admin/bridges"),
+        admin
+    );
+    let listed: Vec<BoardBridge> = test::read_body_json(resp).await;
+    let failed = listed.iter().find(|b| b.id == telegram.id).unwrap();
+    assert_eq!(failed.last_thread_id, telegram.last_thread_id);
+    assert_eq!(
+        failed.last_error.as_deref(),
+        Some("responded with 429 Too Many Requests")
+    );
+
+    for id in [bridge.id, telegram.id] {
+        let delete = || test::TestRequest::delete().uri(&format!("/api/v1/admin/bridges/{id}"));
+        assert_eq!(call!(app, delete(), admin).status(), 204);
+        assert_eq!(call!(app, delete(), admin).status(), 404);
+    }
+}
@@ -0,0 +1,63 @@
+use rib::challenges::{ChallengeStore, PgChallengeStore};
+use std::time::Duration;
+
+async fn test_store() -> PgChallengeStore {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgChallengeStore::new(pool)
+}
+
+#[actix_web::test]
+async fn postgres_challenges_are_single_use() {
+    let store = test_store().await;
+    let key = uuid::Uuid::new_v4().to_string();
+    let ttl = Duration::from_secs(60);
+
+    store.put("btc", &key, "first", ttl).await.unwrap();
+    store.put("btc", &key, "second", ttl).await.unwrap();
+    assert_eq!(
+        store.take("btc", &key).await.unwrap().as_deref(),
+        Some("second")
+    );
+    assert_eq!(store.take("btc", &key).await.unwrap(), None);
+
+    assert!(store
+        .insert_new("oauth_state", &key, "", ttl)
+        .await
+        .unwrap());
+    assert!(!store
+        .insert_new("oauth_state", &key, "", ttl)
+        .await
+        .unwrap());
+    assert!(store
+        .insert_new("email_login", &key, "", ttl)
+        .await
+        .unwrap());
+}
+
+#[actix_web::test]
+async fn expired_postgres_challenges_are_ignored() {
+    let store = test_store().await;
+    let key = uuid::Uuid::new_v4().to_string();
+
+    store
+        .put("btc", &key, "stale", Duration::from_millis(1))
+        .await
+        .unwrap();
+    assert!(store
+        .insert_new("pow", &key, "", Duration::from_millis(1))
+        .await
+        .unwrap());
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(store.take("btc", &key).await.unwrap(), None);
+    // An expired entry no longer blocks the key.
+    assert!(store
+        .insert_new("pow", &key, "", Duration::from_secs(60))
+        .await
+        .unwrap());
+}
@@ -103,11 +103,7 @@ async fn private_attribution_persists_for_thread_and_reply() {
         Err(_) => None,
     }
     .expect("pool");
-    let state = AppState {
-        repo: Arc::new(repo),
-        image_store: Arc::new(MockImageStore::default()),
-        rate_limiter: None,
-    };
+    let state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
     let app = test::init_service(
         App::new()
             .app_data(actix_web::web::Data::new(state))
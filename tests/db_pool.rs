@@ -0,0 +1,30 @@
+use rib::db::{sample_pool_metrics, PoolConfig};
+use std::time::Duration;
+
+fn database_url() -> String {
+    std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests")
+}
+
+#[actix_web::test]
+async fn statement_timeout_applies_to_pooled_connections() {
+    let cfg = PoolConfig {
+        max_connections: 2,
+        min_connections: 1,
+        statement_timeout: Some(Duration::from_millis(50)),
+        ..PoolConfig::default()
+    };
+    let pool = cfg.connect(&database_url()).await.expect("connect pool");
+    let result = sqlx::query("SELECT pg_sleep(1)").execute(&pool).await;
+    let err = result.expect_err("statement should be cancelled");
+    assert!(err.to_string().contains("statement timeout"), "{err}");
+
+    // Sampling must not hold on to a connection.
+    sample_pool_metrics(&pool, "test").await;
+    for _ in 0..50 {
+        if pool.size() as usize == pool.num_idle() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("probe connection was not returned to the pool");
+}
@@ -0,0 +1,156 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, Reply, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+fn token(id: &str, role: Role) -> String {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    create_jwt(id, id, vec![role]).expect("test token")
+}
+
+macro_rules! delete_with {
+    ($app:expr, $uri:expr, $password:expr) => {
+        test::call_service(
+            &$app,
+            test::TestRequest::delete()
+                .uri(&$uri)
+                .set_json(json!({ "password": $password }))
+                .to_request(),
+        )
+        .await
+        .status()
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn posters_delete_their_posts_with_the_deletion_password() {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let poster_id = format!("delpw-{}", &suffix[..8]);
+    let repo = PgRepo::new(pool);
+    repo.set_subject_role(&format!("discord:{poster_id}"), Role::User)
+        .await
+        .expect("allowlist poster");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = token("admin-id", Role::Admin);
+    let poster = token(&poster_id, Role::User);
+
+    let board: Board = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(json!({"slug": format!("dp{}", &suffix[..8]), "title": "Delete"}))
+            .to_request(),
+    )
+    .await;
+    let thread: Thread = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .insert_header(("Authorization", format!("Bearer {poster}")))
+            .set_json(json!({
+                "board_id": board.id,
+                "subject": "deletable",
+                "body": "body",
+                "delete_password": "thread-pass"
+            }))
+            .to_request(),
+    )
+    .await;
+    let reply_body = test::call_and_read_body(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .insert_header(("Authorization", format!("Bearer {poster}")))
+            .set_json(json!({
+                "thread_id": thread.id,
+                "content": "mine",
+                "delete_password": "reply-pass"
+            }))
+            .to_request(),
+    )
+    .await;
+    assert!(!String::from_utf8_lossy(&reply_body).contains("reply-pass"));
+    let reply: Reply = serde_json::from_slice(&reply_body).unwrap();
+    let unprotected: Reply = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .insert_header(("Authorization", format!("Bearer {poster}")))
+            .set_json(json!({"thread_id": thread.id, "content": "no password"}))
+            .to_request(),
+    )
+    .await;
+
+    let reply_uri = format!("/api/v1/replies/{}", reply.id);
+    assert_eq!(delete_with!(app, reply_uri, "thread-pass"), 403);
+    assert_eq!(
+        delete_with!(
+            app,
+            format!("/api/v1/replies/{}", unprotected.id),
+            "anything"
+        ),
+        403
+    );
+    assert_eq!(delete_with!(app, reply_uri, "reply-pass"), 204);
+    // Deleted posts are gone for everyone, including a repeat attempt.
+    assert_eq!(delete_with!(app, reply_uri, "reply-pass"), 404);
+    let replies: Vec<Reply> = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/threads/{}/replies", thread.id))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(
+        replies.iter().map(|r| r.id).collect::<Vec<_>>(),
+        vec![unprotected.id]
+    );
+
+    let thread_uri = format!("/api/v1/threads/{}", thread.id);
+    assert_eq!(delete_with!(app, thread_uri, "wrong-pass"), 403);
+    assert_eq!(delete_with!(app, thread_uri, "thread-pass"), 204);
+    let resp =
+        test::call_service(&app, test::TestRequest::get().uri(&thread_uri).to_request()).await;
+    assert_eq!(resp.status(), 404);
+}
@@ -75,11 +75,7 @@ fn uniq(prefix: &str) -> String {
 #[serial_test::serial]
 async fn test_board_soft_delete_and_restore() {
     let repo = pg_repo().await;
-    let app_state = AppState {
-        repo: Arc::new(repo),
-        image_store: Arc::new(MockImageStore::default()),
-        rate_limiter: None,
-    };
+    let app_state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
     let app = test::init_service(
         App::new()
             .app_data(actix_web::web::Data::new(app_state))
@@ -113,6 +109,7 @@ async fn test_board_soft_delete_and_restore() {
     // soft delete
     let req = test::TestRequest::post()
         .uri(&format!("/api/v1/admin/boards/{}/soft-delete", board.id))
+        .set_json(json!({"reason": "Rule 1: off topic"}))
         .insert_header(("Authorization", format!("Bearer {admin}")))
         .to_request();
     let resp = test::call_service(&app, req).await;
@@ -164,11 +161,7 @@ async fn test_board_soft_delete_and_restore() {
 #[serial_test::serial]
 async fn test_thread_soft_then_hard_delete() {
     let repo = pg_repo().await;
-    let app_state = AppState {
-        repo: Arc::new(repo),
-        image_store: Arc::new(MockImageStore::default()),
-        rate_limiter: None,
-    };
+    let app_state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
     let app = test::init_service(
         App::new()
             .app_data(actix_web::web::Data::new(app_state))
@@ -200,6 +193,7 @@ async fn test_thread_soft_then_hard_delete() {
     // soft delete thread
     let req = test::TestRequest::post()
         .uri(&format!("/api/v1/admin/threads/{}/soft-delete", thread.id))
+        .set_json(json!({"reason": "Rule 1: off topic"}))
         .insert_header(("Authorization", format!("Bearer {admin}")))
         .to_request();
     let resp = test::call_service(&app, req).await;
@@ -226,6 +220,7 @@ async fn test_thread_soft_then_hard_delete() {
     // hard delete
     let req = test::TestRequest::delete()
         .uri(&format!("/api/v1/admin/threads/{}", thread.id))
+        .set_json(json!({"reason": "Rule 1: off topic"}))
         .insert_header(("Authorization", format!("Bearer {admin}")))
         .to_request();
     let resp = test::call_service(&app, req).await;
@@ -245,11 +240,7 @@ async fn test_thread_soft_then_hard_delete() {
 #[serial_test::serial]
 async fn test_reply_soft_delete_visibility() {
     let repo = pg_repo().await;
-    let app_state = AppState {
-        repo: Arc::new(repo),
-        image_store: Arc::new(MockImageStore::default()),
-        rate_limiter: None,
-    };
+    let app_state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
     let app = test::init_service(
         App::new()
             .app_data(actix_web::web::Data::new(app_state))
@@ -289,6 +280,7 @@ async fn test_reply_soft_delete_visibility() {
     // soft delete reply
     let req = test::TestRequest::post()
         .uri(&format!("/api/v1/admin/replies/{}/soft-delete", reply.id))
+        .set_json(json!({"reason": "Rule 1: off topic"}))
         .insert_header(("Authorization", format!("Bearer {admin}")))
         .to_request();
     let resp = test::call_service(&app, req).await;
@@ -326,11 +318,7 @@ async fn test_reply_soft_delete_visibility() {
 #[serial_test::serial]
 async fn test_create_thread_blocked_by_soft_deleted_board() {
     let repo = pg_repo().await;
-    let app_state = AppState {
-        repo: Arc::new(repo),
-        image_store: Arc::new(MockImageStore::default()),
-        rate_limiter: None,
-    };
+    let app_state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
     let app = test::init_service(
         App::new()
             .app_data(actix_web::web::Data::new(app_state))
@@ -350,6 +338,7 @@ async fn test_create_thread_blocked_by_soft_deleted_board() {
     // soft delete board
     let req = test::TestRequest::post()
         .uri(&format!("/api/v1/admin/boards/{}/soft-delete", board.id))
+        .set_json(json!({"reason": "Rule 1: off topic"}))
         .insert_header(("Authorization", format!("Bearer {admin}")))
         .to_request();
     let resp = test::call_service(&app, req).await;
@@ -369,11 +358,7 @@ async fn test_create_thread_blocked_by_soft_deleted_board() {
 #[serial_test::serial]
 async fn test_soft_delete_idempotent() {
     let repo = pg_repo().await;
-    let app_state = AppState {
-        repo: Arc::new(repo),
-        image_store: Arc::new(MockImageStore::default()),
-        rate_limiter: None,
-    };
+    let app_state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
     let app = test::init_service(
         App::new()
             .app_data(actix_web::web::Data::new(app_state))
@@ -392,6 +377,7 @@ async fn test_soft_delete_idempotent() {
     // first soft delete
     let req = test::TestRequest::post()
         .uri(&format!("/api/v1/admin/boards/{}/soft-delete", board.id))
+        .set_json(json!({"reason": "Rule 1: off topic"}))
         .insert_header(("Authorization", format!("Bearer {admin}")))
         .to_request();
     let _ = test::call_service(&app, req).await;
@@ -406,6 +392,7 @@ async fn test_soft_delete_idempotent() {
     // second soft delete (idempotent)
     let req = test::TestRequest::post()
         .uri(&format!("/api/v1/admin/boards/{}/soft-delete", board.id))
+        .set_json(json!({"reason": "Rule 1: off topic"}))
         .insert_header(("Authorization", format!("Bearer {admin}")))
         .to_request();
     let _ = test::call_service(&app, req).await;
@@ -425,11 +412,11 @@ async fn soft_deleted_board_hides_threads_by_direct_id() {
     let repo = pg_repo().await;
     let app = test::init_service(
         App::new()
-            .app_data(actix_web::web::Data::new(AppState {
-                repo: Arc::new(repo),
-                image_store: Arc::new(MockImageStore::default()),
-                rate_limiter: None,
-            }))
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore::default()),
+                None,
+            )))
             .configure(config),
     )
     .await;
@@ -456,6 +443,7 @@ async fn soft_deleted_board_hides_threads_by_direct_id() {
 
     let request = test::TestRequest::post()
         .uri(&format!("/api/v1/admin/boards/{}/soft-delete", board.id))
+        .set_json(json!({"reason": "Rule 1: off topic"}))
         .insert_header(("Authorization", format!("Bearer {admin}")))
         .to_request();
     assert_eq!(test::call_service(&app, request).await.status(), 200);
@@ -478,3 +466,198 @@ async fn soft_deleted_board_hides_threads_by_direct_id() {
         .to_request();
     assert_eq!(test::call_service(&app, request).await.status(), 200);
 }
+
+// Removals need a reason, which boards with tombstones show in place of the reply
+#[actix_web::test]
+#[serial_test::serial]
+async fn test_removal_reasons_and_tombstones() {
+    let repo = pg_repo().await;
+    let app_state = AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None);
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(app_state))
+            .configure(config),
+    )
+    .await;
+    let admin = admin_token();
+    let user = user_token();
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/boards")
+        .insert_header(("Authorization", format!("Bearer {admin}")))
+        .set_json(json!({"slug":uniq("tb-"),"title":"Tombstones"}))
+        .to_request();
+    let board: Board = test::read_body_json(test::call_service(&app, req).await).await;
+    let set_tombstones = |on: bool| {
+        test::TestRequest::patch()
+            .uri(&format!("/api/v1/boards/{}", board.id))
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(json!({"tombstones": on}))
+            .to_request()
+    };
+    let updated: Board =
+        test::read_body_json(test::call_service(&app, set_tombstones(true)).await).await;
+    assert!(updated.tombstones);
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/threads")
+        .insert_header(("Authorization", format!("Bearer {user}")))
+        .set_json(json!({"board_id":board.id,"subject":"S","body":"B"}))
+        .to_request();
+    let thread: Thread = test::read_body_json(test::call_service(&app, req).await).await;
+    let mut replies = Vec::new();
+    for content in ["kept", "spam", "regretted"] {
+        let req = test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .insert_header(("Authorization", format!("Bearer {user}")))
+            .set_json(json!({"thread_id":thread.id,"content":content,"delete_password":"hunter22"}))
+            .to_request();
+        let reply: Reply = test::read_body_json(test::call_service(&app, req).await).await;
+        replies.push(reply);
+    }
+    let (kept, removed, regretted) = (&replies[0], &replies[1], &replies[2]);
+
+    let soft_delete = |body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/replies/{}/soft-delete", removed.id))
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(body)
+            .to_request()
+    };
+    for body in [
+        json!({}),
+        json!({"reason": "   "}),
+        json!({"reason": "x".repeat(201)}),
+    ] {
+        assert_eq!(
+            test::call_service(&app, soft_delete(body)).await.status(),
+            400
+        );
+    }
+    let resp = test::call_service(&app, soft_delete(json!({"reason": " Rule 3: spam "}))).await;
+    assert_eq!(resp.status(), 200);
+    // Posters deleting their own replies give no reason and leave no stub.
+    let req = test::TestRequest::delete()
+        .uri(&format!("/api/v1/replies/{}", regretted.id))
+        .set_json(json!({"password": "hunter22"}))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 204);
+
+    let list = |token: &str, query: &str| {
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/threads/{}/replies{query}", thread.id))
+            .insert_header(("Authorization", format!("Bearer {token}")))
+            .to_request()
+    };
+    let listed: Vec<Reply> =
+        test::read_body_json(test::call_service(&app, list(&user, "")).await).await;
+    assert_eq!(
+        listed.iter().map(|r| r.id).collect::<Vec<_>>(),
+        vec![kept.id, removed.id]
+    );
+    let stub = &listed[1];
+    assert!(stub.deleted_at.is_some());
+    assert_eq!(stub.content, "");
+    assert_eq!(stub.author_name, None);
+    assert_eq!(stub.removal_reason.as_deref(), Some("Rule 3: spam"));
+    assert_eq!(listed[0].removal_reason, None);
+
+    // Streamed listings show the same tombstones.
+    let stream = |token: &str| {
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/threads/{}/replies", thread.id))
+            .insert_header(("Authorization", format!("Bearer {token}")))
+            .insert_header(("Accept", "application/x-ndjson"))
+            .to_request()
+    };
+    let streamed = |body: actix_web::web::Bytes| -> Vec<Reply> {
+        std::str::from_utf8(&body)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    };
+    let lines = streamed(test::call_and_read_body(&app, stream(&user)).await);
+    assert_eq!(
+        lines.iter().map(|r| r.id).collect::<Vec<_>>(),
+        vec![kept.id, removed.id]
+    );
+    assert_eq!(lines[1].content, "");
+    assert_eq!(lines[1].removal_reason.as_deref(), Some("Rule 3: spam"));
+
+    // Staff see the reason on the full reply.
+    let all: Vec<Reply> =
+        test::read_body_json(test::call_service(&app, list(&admin, "?include_deleted=1")).await)
+            .await;
+    let full = all.iter().find(|r| r.id == removed.id).unwrap();
+    assert_eq!(full.content, "spam");
+    assert_eq!(full.removal_reason.as_deref(), Some("Rule 3: spam"));
+    assert_eq!(
+        all.iter()
+            .find(|r| r.id == regretted.id)
+            .unwrap()
+            .removal_reason,
+        None
+    );
+
+    // Without tombstones removed replies disappear as before.
+    assert!(test::call_service(&app, set_tombstones(false))
+        .await
+        .status()
+        .is_success());
+    let listed: Vec<Reply> =
+        test::read_body_json(test::call_service(&app, list(&user, "")).await).await;
+    assert_eq!(
+        listed.iter().map(|r| r.id).collect::<Vec<_>>(),
+        vec![kept.id]
+    );
+    let lines = streamed(test::call_and_read_body(&app, stream(&user)).await);
+    assert_eq!(
+        lines.iter().map(|r| r.id).collect::<Vec<_>>(),
+        vec![kept.id]
+    );
+
+    // Hard deletes need a reason too, and every removal is logged.
+    let hard_delete = |body: serde_json::Value| {
+        test::TestRequest::delete()
+            .uri(&format!("/api/v1/admin/threads/{}", thread.id))
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(body)
+            .to_request()
+    };
+    assert_eq!(
+        test::call_service(&app, hard_delete(json!({})))
+            .await
+            .status(),
+        400
+    );
+    let resp = test::call_service(&app, hard_delete(json!({"reason": "Rule 1: raid"}))).await;
+    assert_eq!(resp.status(), 204);
+    let req = test::TestRequest::get()
+        .uri("/api/v1/admin/deletion-log?limit=10")
+        .insert_header(("Authorization", format!("Bearer {admin}")))
+        .to_request();
+    let log: Vec<serde_json::Value> =
+        test::read_body_json(test::call_service(&app, req).await).await;
+    let entry = |kind: &str, id: i64| {
+        log.iter()
+            .find(|e| e["kind"] == kind && e["target_id"] == id)
+            .cloned()
+            .unwrap()
+    };
+    let thread_entry = entry("thread", thread.id);
+    assert_eq!(thread_entry["reason"], "Rule 1: raid");
+    assert_eq!(thread_entry["hard"], true);
+    let reply_entry = entry("reply", removed.id);
+    assert_eq!(reply_entry["reason"], "Rule 3: spam");
+    assert_eq!(reply_entry["hard"], false);
+    assert_eq!(reply_entry["actor"], "admin:admin");
+    assert!(!log
+        .iter()
+        .any(|e| e["kind"] == "reply" && e["target_id"] == regretted.id));
+    let req = test::TestRequest::get()
+        .uri("/api/v1/admin/deletion-log")
+        .insert_header(("Authorization", format!("Bearer {user}")))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 403);
+}
@@ -0,0 +1,247 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::digest::{DigestConfig, DigestWorker};
+use rib::mailer::{Email, Mailer};
+use rib::models::{Board, NotificationSettings, Thread, ThreadSubscription};
+use rib::repo::pg::PgRepo;
+use rib::repo::{Repo, RoleRepo};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct RecordingMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+impl RecordingMailer {
+    fn take(&self) -> Vec<Email> {
+        std::mem::take(&mut *self.sent.lock().unwrap())
+    }
+}
+
+#[async_trait::async_trait]
+impl Mailer for RecordingMailer {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    async fn send(&self, email: &Email) -> anyhow::Result<()> {
+        self.sent.lock().unwrap().push(email.clone());
+        Ok(())
+    }
+}
+
+fn link_path(text: &str, prefix: &str) -> String {
+    let start = text.find(prefix).expect("link in email");
+    text[start..].split_whitespace().next().unwrap().to_string()
+}
+
+macro_rules! reply_as {
+    ($app:expr, $token:expr, $thread:expr, $content:expr) => {
+        assert_eq!(
+            test::call_service(
+                &$app,
+                test::TestRequest::post()
+                    .uri("/api/v1/replies")
+                    .insert_header(("Authorization", format!("Bearer {}", $token)))
+                    .set_json(json!({"thread_id": $thread, "content": $content}))
+                    .to_request(),
+            )
+            .await
+            .status(),
+            201
+        )
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn watched_threads_are_mailed_as_digests_until_unsubscribed() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    std::env::set_var("FRONTEND_URL", "http://localhost:5173");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let watcher_id = format!("watch-{}", &suffix[..8]);
+    let poster_id = format!("post-{}", &suffix[..8]);
+    let repo = PgRepo::new(pool);
+    for id in [&watcher_id, &poster_id] {
+        repo.set_subject_role(&format!("discord:{id}"), Role::User)
+            .await
+            .expect("allowlist poster");
+    }
+    let repo: Arc<dyn Repo> = Arc::new(repo);
+    let mailer = Arc::new(RecordingMailer::default());
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(
+                AppState::new(repo.clone(), Arc::new(MockImageStore), None)
+                    .with_mailer(Some(mailer.clone())),
+            ))
+            .configure(config),
+    )
+    .await;
+    let worker = DigestWorker::new(
+        repo,
+        mailer.clone(),
+        DigestConfig {
+            enabled: true,
+            poll_interval: Duration::from_secs(60),
+            batch_size: 1000,
+            base_url: "http://localhost:5173".into(),
+        },
+    );
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let watcher = create_jwt(&watcher_id, &watcher_id, vec![Role::User]).unwrap();
+    let poster = create_jwt(&poster_id, &poster_id, vec![Role::User]).unwrap();
+
+    let board: Board = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(json!({"slug": format!("dg{}", &suffix[..8]), "title": "Digests"}))
+            .to_request(),
+    )
+    .await;
+    let thread: Thread = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .insert_header(("Authorization", format!("Bearer {watcher}")))
+            .set_json(json!({"board_id": board.id, "subject": "watched", "body": "op"}))
+            .to_request(),
+    )
+    .await;
+    reply_as!(app, poster, thread.id, "before subscribing");
+
+    let subscription_uri = format!("/api/v1/threads/{}/subscription", thread.id);
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::put()
+            .uri(&subscription_uri)
+            .insert_header(("Authorization", format!("Bearer {watcher}")))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 204);
+    let subscriptions: Vec<ThreadSubscription> = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v1/users/me/subscriptions")
+            .insert_header(("Authorization", format!("Bearer {watcher}")))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(subscriptions.len(), 1);
+    assert_eq!(subscriptions[0].thread_id, thread.id);
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::put()
+            .uri("/api/v1/users/me/notifications")
+            .insert_header(("Authorization", format!("Bearer {watcher}")))
+            .set_json(json!({"email": null, "digest": "immediate"}))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 400, "digests need an address");
+    let settings: NotificationSettings = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::put()
+            .uri("/api/v1/users/me/notifications")
+            .insert_header(("Authorization", format!("Bearer {watcher}")))
+            .set_json(json!({"email": "Watcher@Example.org", "digest": "immediate"}))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(settings.email.as_deref(), Some("watcher@example.org"));
+    assert!(!settings.email_verified);
+
+    reply_as!(app, poster, thread.id, "first new reply");
+    reply_as!(app, watcher, thread.id, "my own reply");
+    assert_eq!(
+        worker.run_once().await,
+        0,
+        "unconfirmed addresses get nothing"
+    );
+
+    let confirmation = mailer.take().pop().expect("confirmation email");
+    let confirm = link_path(&confirmation.body, "http://localhost:5173/");
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(confirm.trim_start_matches("http://localhost:5173"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+
+    reply_as!(app, poster, thread.id, "second new reply");
+    assert_eq!(worker.run_once().await, 1);
+    let digest = mailer.take().pop().unwrap();
+    assert_eq!(digest.to, "watcher@example.org");
+    assert!(digest.body.contains("first new reply"));
+    assert!(digest.body.contains("second new reply"));
+    assert!(!digest.body.contains("before subscribing"));
+    assert!(!digest.body.contains("my own reply"));
+    assert_eq!(worker.run_once().await, 0, "replies are mailed once");
+
+    let unsubscribe = digest.unsubscribe_url.expect("one-click unsubscribe");
+    assert!(digest.body.contains(&unsubscribe));
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri(unsubscribe.trim_start_matches("http://localhost:5173"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+    let settings: NotificationSettings = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v1/users/me/notifications")
+            .insert_header(("Authorization", format!("Bearer {watcher}")))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(settings.digest, rib::models::DigestFrequency::Off);
+    reply_as!(app, poster, thread.id, "after unsubscribing");
+    assert_eq!(worker.run_once().await, 0);
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::delete()
+            .uri(&subscription_uri)
+            .insert_header(("Authorization", format!("Bearer {watcher}")))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 204);
+}
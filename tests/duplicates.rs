@@ -0,0 +1,132 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::duplicates::{DuplicateConfig, DuplicateGuard};
+use rib::models::{Board, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Default)]
+struct MockImageStore {
+    inner: Mutex<HashMap<String, (Vec<u8>, String)>>,
+}
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, hash: &str, mime: &str, bytes: &[u8]) -> Result<(), ImageStoreError> {
+        let mut map = self.inner.lock().unwrap();
+        if map.contains_key(hash) {
+            return Err(ImageStoreError::Duplicate);
+        }
+        map.insert(hash.to_string(), (bytes.to_vec(), mime.to_string()));
+        Ok(())
+    }
+
+    async fn load(&self, hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        let map = self.inner.lock().unwrap();
+        map.get(hash).cloned().ok_or(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, hash: &str) -> Result<(), ImageStoreError> {
+        self.inner.lock().unwrap().remove(hash);
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn identical_posts_within_the_window_are_rejected() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let ids: Vec<String> = ["copy", "pasta"]
+        .iter()
+        .map(|name| format!("{name}-{}", &suffix[..8]))
+        .collect();
+    let repo = PgRepo::new(pool);
+    for id in &ids {
+        repo.set_subject_role(&format!("discord:{id}"), Role::User)
+            .await
+            .expect("allowlist poster");
+    }
+    let duplicates = Arc::new(DuplicateGuard::new(DuplicateConfig {
+        window: Duration::from_secs(60),
+        capacity: 100,
+    }));
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(
+                AppState::new(Arc::new(repo), Arc::new(MockImageStore::default()), None)
+                    .with_duplicates(duplicates),
+            ))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let first = create_jwt(&ids[0], &ids[0], vec![Role::User]).unwrap();
+    let second = create_jwt(&ids[1], &ids[1], vec![Role::User]).unwrap();
+
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": format!("dp{}", &suffix[..8]), "title": "Dupes"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    let new_thread = |subject: &str| {
+        test::TestRequest::post().uri("/api/v1/threads").set_json(
+            json!({"board_id": board.id, "subject": subject, "body": format!("body {suffix}")}),
+        )
+    };
+    let resp = call!(app, new_thread("one"), first);
+    assert_eq!(resp.status(), 201);
+    let thread: Thread = test::read_body_json(resp).await;
+    let resp = call!(app, new_thread("two"), first);
+    assert_eq!(resp.status(), 409);
+    let body: Value = test::read_body_json(resp).await;
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("identical thread"));
+    let resp = call!(app, new_thread("two"), second);
+    assert_eq!(resp.status(), 201);
+    let other: Thread = test::read_body_json(resp).await;
+
+    let reply = |thread_id: i64, content: &str| {
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .set_json(json!({"thread_id": thread_id, "content": content}))
+    };
+    assert_eq!(call!(app, reply(thread.id, "first!"), first).status(), 201);
+    assert_eq!(call!(app, reply(thread.id, "first!"), first).status(), 409);
+    assert_eq!(
+        call!(app, reply(other.id, "  first!  "), first).status(),
+        409
+    );
+    assert_eq!(call!(app, reply(thread.id, "first!!"), first).status(), 201);
+    assert_eq!(call!(app, reply(thread.id, "first!"), second).status(), 201);
+}
@@ -0,0 +1,178 @@
+use actix_web::{test, App};
+use rib::mailer::{Email, Mailer};
+use rib::repo::pg::PgRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::{Arc, Mutex};
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct RecordingMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+#[async_trait::async_trait]
+impl Mailer for RecordingMailer {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    async fn send(&self, email: &Email) -> anyhow::Result<()> {
+        self.sent.lock().unwrap().push(email.clone());
+        Ok(())
+    }
+}
+
+fn callback_path(email: &Email) -> String {
+    let start = email.body.find("/api/v1/auth/email/callback").unwrap();
+    email.body[start..]
+        .split_whitespace()
+        .next()
+        .unwrap()
+        .to_string()
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn magic_links_sign_in_once_with_an_email_subject() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    std::env::set_var("FRONTEND_URL", "http://localhost:5173");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let mailer = Arc::new(RecordingMailer::default());
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(
+                AppState::new(Arc::new(PgRepo::new(pool)), Arc::new(MockImageStore), None)
+                    .with_mailer(Some(mailer.clone())),
+            ))
+            .configure(config),
+    )
+    .await;
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/auth/email/start")
+            .set_json(json!({"email": "not-an-address"}))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 400);
+
+    for address in [" Poster@Example.org", "poster@example.org"] {
+        let resp = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/v1/auth/email/start")
+                .set_json(json!({ "email": address }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), 202);
+    }
+    let sent = mailer.sent.lock().unwrap().clone();
+    assert_eq!(sent.len(), 2);
+    assert!(sent.iter().all(|email| email.to == "poster@example.org"));
+
+    let mut subjects = Vec::new();
+    for email in &sent {
+        let link = callback_path(email);
+        let resp = test::call_service(&app, test::TestRequest::get().uri(&link).to_request()).await;
+        assert_eq!(resp.status(), 302);
+        assert_eq!(
+            resp.headers().get("location").unwrap(),
+            "http://localhost:5173/"
+        );
+        let session = resp
+            .response()
+            .cookies()
+            .find(|c| c.name() == "rib_session")
+            .expect("session cookie")
+            .value()
+            .to_string();
+        let me: serde_json::Value = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::get()
+                .uri("/api/v1/auth/me")
+                .insert_header(("Authorization", format!("Bearer {session}")))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(me["role"], "user");
+        subjects.push(me["id"].as_str().unwrap().to_string());
+
+        // Links are single use.
+        let resp = test::call_service(&app, test::TestRequest::get().uri(&link).to_request()).await;
+        assert_eq!(resp.status(), 302);
+        assert_eq!(
+            resp.headers().get("location").unwrap(),
+            "http://localhost:5173/login?error=email_link_invalid"
+        );
+    }
+    assert!(subjects[0].starts_with("email:"));
+    assert!(!subjects[0].contains("example"));
+    assert_eq!(subjects[0], subjects[1]);
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v1/auth/email/callback?token=forged")
+            .to_request(),
+    )
+    .await;
+    assert_eq!(
+        resp.headers().get("location").unwrap(),
+        "http://localhost:5173/login?error=email_link_invalid"
+    );
+}
+
+#[actix_web::test]
+async fn email_login_reports_when_unconfigured() {
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect_lazy("postgres://localhost/unused")
+        .unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(PgRepo::new(pool)),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/auth/email/start")
+            .set_json(json!({"email": "poster@example.org"}))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 503);
+}
@@ -0,0 +1,156 @@
+use actix_web::{test, App};
+use rib::repo::pg::PgRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use secp256k1::{Message, Secp256k1, SecretKey};
+use serde_json::json;
+use sha3::{Digest, Keccak256};
+use std::sync::Arc;
+use wiremock::matchers::{body_partial_json, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+async fn pg_repo() -> PgRepo {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+// Key and address from the web3.js `eth.accounts` examples.
+const KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+const ADDRESS: &str = "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23";
+
+fn personal_sign(message: &str) -> String {
+    let secret = SecretKey::from_slice(&hex::decode(KEY).unwrap()).unwrap();
+    let mut hasher = Keccak256::new();
+    hasher.update(format!("\x19Ethereum Signed Message:\n{}", message.len()).as_bytes());
+    hasher.update(message.as_bytes());
+    let digest = Message::from_digest_slice(&hasher.finalize()).unwrap();
+    let (recid, compact) = Secp256k1::new()
+        .sign_ecdsa_recoverable(&digest, &secret)
+        .serialize_compact();
+    let mut raw = compact.to_vec();
+    raw.push(27 + recid.to_i32() as u8);
+    format!("0x{}", hex::encode(raw))
+}
+
+fn setup_env() {
+    std::env::set_var("JWT_SECRET", "testsecret-abcdefghijklmnopqrstuvwxyz012345");
+    std::env::set_var("FRONTEND_URL", "https://rib.example");
+    std::env::remove_var("ETH_GATE_CONTRACT");
+    std::env::remove_var("ETH_RPC_URL");
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn ethereum_sign_in_mints_an_eth_subject_once() {
+    setup_env();
+    let state = AppState::new(Arc::new(pg_repo().await), Arc::new(MockImageStore), None);
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(state))
+            .configure(config),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/auth/ethereum/challenge")
+        .set_json(json!({"address": ADDRESS.to_lowercase()}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let message = body["message"].as_str().unwrap().to_string();
+    assert!(message.starts_with(&format!(
+        "rib.example wants you to sign in with your Ethereum account:\n{ADDRESS}\n"
+    )));
+
+    // A message edited after signing, or signed by someone else, is refused.
+    let tampered = message.replace("Chain ID: 1", "Chain ID: 5");
+    let req = test::TestRequest::post()
+        .uri("/api/v1/auth/ethereum/verify")
+        .set_json(json!({"message": tampered, "signature": personal_sign(&tampered)}))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+    let signature = personal_sign(&message);
+    let verify = || {
+        test::TestRequest::post()
+            .uri("/api/v1/auth/ethereum/verify")
+            .set_json(json!({"message": message, "signature": signature}))
+            .to_request()
+    };
+    let resp = test::call_service(&app, verify()).await;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let claims = rib::auth::decode_jwt(body["token"].as_str().unwrap()).unwrap();
+    assert_eq!(claims.sub, format!("eth:{}", ADDRESS.to_lowercase()));
+
+    // The nonce was spent.
+    assert_eq!(test::call_service(&app, verify()).await.status(), 400);
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn ethereum_sign_in_can_require_a_token_balance() {
+    setup_env();
+    let rpc = MockServer::start().await;
+    std::env::set_var("ETH_RPC_URL", rpc.uri());
+    std::env::set_var(
+        "ETH_GATE_CONTRACT",
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+    );
+    std::env::set_var("ETH_GATE_MIN_BALANCE", "1000");
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({"method": "eth_call"})))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": "0x3e7"})),
+        )
+        .mount(&rpc)
+        .await;
+    let state = AppState::new(Arc::new(pg_repo().await), Arc::new(MockImageStore), None);
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(state))
+            .configure(config),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/auth/ethereum/challenge")
+        .set_json(json!({"address": ADDRESS}))
+        .to_request();
+    let body: serde_json::Value = test::read_body_json(test::call_service(&app, req).await).await;
+    let message = body["message"].as_str().unwrap();
+    let req = test::TestRequest::post()
+        .uri("/api/v1/auth/ethereum/verify")
+        .set_json(json!({"message": message, "signature": personal_sign(message)}))
+        .to_request();
+    // 999 tokens held, 1000 required.
+    assert_eq!(test::call_service(&app, req).await.status(), 403);
+
+    std::env::remove_var("ETH_RPC_URL");
+    std::env::remove_var("ETH_GATE_CONTRACT");
+    std::env::remove_var("ETH_GATE_MIN_BALANCE");
+}
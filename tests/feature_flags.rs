@@ -0,0 +1,85 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::features::FeatureFlags;
+use rib::reload::Reloadable;
+use rib::repo::pg::PgRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn disabled_features_answer_503_until_switched_back_on() {
+    std::env::set_var("JWT_SECRET", "testsecret");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let features = Reloadable::new(FeatureFlags::parse("search,new_boards,bitcoin_auth"));
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(
+                AppState::new(Arc::new(PgRepo::new(pool)), Arc::new(NoImages), None)
+                    .with_features(features.clone()),
+            ))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("admin", "admin", vec![Role::Admin]).unwrap();
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let search = || test::TestRequest::get().uri("/api/v1/search?q=hello");
+    let new_board = |version: &str| {
+        test::TestRequest::post()
+            .uri(&format!("/api/{version}/boards"))
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(json!({"slug": format!("ff{version}{}", &suffix[..8]), "title": "Flags"}))
+    };
+
+    let resp = test::call_service(&app, search().to_request()).await;
+    assert_eq!(resp.status(), 503);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "feature_disabled");
+    assert_eq!(body["error"], "search is disabled for maintenance");
+    let resp = test::call_service(&app, new_board("v1").to_request()).await;
+    assert_eq!(resp.status(), 503);
+    let resp = test::call_service(&app, new_board("v2").to_request()).await;
+    assert_eq!(resp.status(), 503);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "feature_disabled");
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/auth/bitcoin/challenge")
+            .set_json(json!({"address": "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq"}))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 503);
+
+    // Switching a feature back on takes effect for the next request.
+    features.replace(FeatureFlags::parse("bitcoin_auth"));
+    let resp = test::call_service(&app, search().to_request()).await;
+    assert_eq!(resp.status(), 200);
+    let resp = test::call_service(&app, new_board("v1").to_request()).await;
+    assert_eq!(resp.status(), 201);
+}
@@ -0,0 +1,209 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, Reply, Thread, UserFilter};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+macro_rules! post_as {
+    ($app:expr, $token:expr, $uri:expr, $body:expr) => {
+        test::call_service(
+            &$app,
+            test::TestRequest::post()
+                .uri($uri)
+                .insert_header(("Authorization", format!("Bearer {}", $token)))
+                .set_json($body)
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn mute_lists_hide_posts_only_when_asked() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let viewer_id = format!("view-{}", &suffix[..8]);
+    let noisy_id = format!("noisy-{}", &suffix[..8]);
+    let repo = PgRepo::new(pool);
+    for id in [&viewer_id, &noisy_id] {
+        repo.set_subject_role(&format!("discord:{id}"), Role::User)
+            .await
+            .expect("allowlist poster");
+    }
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let viewer = create_jwt(&viewer_id, &viewer_id, vec![Role::User]).unwrap();
+    let noisy = create_jwt(&noisy_id, &noisy_id, vec![Role::User]).unwrap();
+
+    let board: Board = test::read_body_json(post_as!(
+        app,
+        admin,
+        "/api/v1/boards",
+        json!({"slug": format!("mf{}", &suffix[..8]), "title": "Filters"})
+    ))
+    .await;
+    let mut threads = Vec::new();
+    for (token, subject) in [
+        (&noisy, "noise"),
+        (&viewer, "keep"),
+        (&viewer, "Spoiler ahead"),
+    ] {
+        let thread: Thread = test::read_body_json(post_as!(
+            app,
+            token,
+            "/api/v1/threads",
+            json!({"board_id": board.id, "subject": subject, "body": "op"})
+        ))
+        .await;
+        threads.push(thread);
+    }
+    let kept = &threads[1];
+    for (token, content) in [(&noisy, "loud"), (&viewer, "calm")] {
+        let reply: Reply = test::read_body_json(post_as!(
+            app,
+            token,
+            "/api/v1/replies",
+            json!({"thread_id": kept.id, "content": content})
+        ))
+        .await;
+        assert_eq!(reply.thread_id, kept.id);
+    }
+
+    let muted: Value = test::read_body_json(post_as!(
+        app,
+        viewer,
+        "/api/v1/users/me/filters",
+        json!({"kind": "subject", "value": format!("thread:{}", threads[0].id)})
+    ))
+    .await;
+    assert_eq!(muted["value"], format!("thread:{}", threads[0].id));
+    assert!(muted.get("target_subject").is_none());
+    let keyword: UserFilter = test::read_body_json(post_as!(
+        app,
+        viewer,
+        "/api/v1/users/me/filters",
+        json!({"kind": "keyword", "value": "  SPOILER "})
+    ))
+    .await;
+    assert_eq!(keyword.value, "spoiler");
+
+    let resp = post_as!(
+        app,
+        viewer,
+        "/api/v1/users/me/filters",
+        json!({"kind": "keyword", "value": "spoiler"})
+    );
+    assert_eq!(resp.status(), 409);
+    let resp = post_as!(
+        app,
+        viewer,
+        "/api/v1/users/me/filters",
+        json!({"kind": "subject", "value": format!("thread:{}", kept.id)})
+    );
+    assert_eq!(resp.status(), 400);
+    let resp = post_as!(
+        app,
+        viewer,
+        "/api/v1/users/me/filters",
+        json!({"kind": "tripcode", "value": "nope"})
+    );
+    assert_eq!(resp.status(), 400);
+
+    let list_threads = |query: &'static str, token: String| {
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/boards/{}/threads{query}", board.id))
+            .insert_header(("Authorization", format!("Bearer {token}")))
+            .to_request()
+    };
+    let all: Vec<Thread> =
+        test::call_and_read_body_json(&app, list_threads("", viewer.clone())).await;
+    assert_eq!(all.len(), 3);
+    let filtered: Vec<Thread> =
+        test::call_and_read_body_json(&app, list_threads("?apply_filters=1", viewer.clone())).await;
+    assert_eq!(
+        filtered.iter().map(|t| t.id).collect::<Vec<_>>(),
+        vec![kept.id]
+    );
+    let unfiltered: Vec<Thread> =
+        test::call_and_read_body_json(&app, list_threads("?apply_filters=1", noisy.clone())).await;
+    assert_eq!(unfiltered.len(), 3);
+
+    let replies: Vec<Reply> = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!(
+                "/api/v1/threads/{}/replies?apply_filters=1",
+                kept.id
+            ))
+            .insert_header(("Authorization", format!("Bearer {viewer}")))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(
+        replies
+            .iter()
+            .map(|r| r.content.as_str())
+            .collect::<Vec<_>>(),
+        vec!["calm"]
+    );
+
+    let delete = |id: i64, token: &String| {
+        test::TestRequest::delete()
+            .uri(&format!("/api/v1/users/me/filters/{id}"))
+            .insert_header(("Authorization", format!("Bearer {token}")))
+            .to_request()
+    };
+    let resp = test::call_service(&app, delete(keyword.id, &noisy)).await;
+    assert_eq!(resp.status(), 404);
+    let resp = test::call_service(&app, delete(keyword.id, &viewer)).await;
+    assert_eq!(resp.status(), 204);
+    let remaining: Vec<UserFilter> = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v1/users/me/filters")
+            .insert_header(("Authorization", format!("Bearer {viewer}")))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].value, format!("thread:{}", threads[0].id));
+}
@@ -0,0 +1,187 @@
+#![cfg(feature = "graphql")]
+
+use actix_web::{test, web, App};
+use rib::models::{NewBoard, NewReply, NewThread, PublicIdentity};
+use rib::repo::pg::PgRepo;
+use rib::repo::{BoardRepo, ReplyRepo, ThreadRepo};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+fn anonymous() -> PublicIdentity {
+    PublicIdentity {
+        author_name: None,
+        tripcode: None,
+    }
+}
+
+#[actix_web::test]
+async fn fetches_a_thread_view_in_one_request() {
+    let repo = test_repo().await;
+    let slug = format!("gq{}", &uuid::Uuid::new_v4().simple().to_string()[..10]);
+    let board = repo
+        .create_board(NewBoard {
+            slug: slug.clone(),
+            title: "GraphQL".into(),
+        })
+        .await
+        .unwrap();
+    let mut thread_ids = Vec::new();
+    for subject in ["older", "newer"] {
+        let thread = repo
+            .create_thread(
+                NewThread {
+                    board_id: board.id,
+                    subject: subject.into(),
+                    body: "body".into(),
+                    image_hash: None,
+                    mime: None,
+                    author_name: None,
+                    tripcode_password: None,
+                    delete_password: None,
+                    tags: Vec::new(),
+                },
+                json!({"provider": "test", "subject": "test:graphql"}),
+                anonymous(),
+            )
+            .await
+            .unwrap();
+        for n in 0..3 {
+            repo.create_reply(
+                NewReply {
+                    thread_id: thread.id,
+                    content: format!("{subject} reply {n}"),
+                    image_hash: None,
+                    mime: None,
+                    author_name: None,
+                    tripcode_password: None,
+                    delete_password: None,
+                },
+                json!({"provider": "test", "subject": "test:graphql"}),
+                anonymous(),
+            )
+            .await
+            .unwrap();
+        }
+        thread_ids.push(thread.id);
+    }
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let query = r#"
+        query($board: Int!) {
+            board(id: $board) {
+                slug
+                threads(first: 2) {
+                    totalCount
+                    edges { node {
+                        subject
+                        board { slug }
+                        replies(first: 2) {
+                            totalCount
+                            pageInfo { hasNextPage endCursor }
+                            nodes { content }
+                        }
+                    } }
+                }
+            }
+            missing: thread(id: 999999999) { id }
+        }
+    "#;
+    let req = test::TestRequest::post()
+        .uri("/graphql")
+        .set_json(json!({"query": query, "variables": {"board": board.id}}))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert!(body.get("errors").is_none(), "{body}");
+    let data = &body["data"];
+    assert_eq!(data["board"]["slug"], slug.as_str());
+    assert!(data["missing"].is_null());
+    let threads = data["board"]["threads"]["edges"].as_array().unwrap();
+    assert_eq!(threads.len(), 2);
+    // Most recently bumped first, each with its own replies and board.
+    for (edge, subject) in threads.iter().zip(["newer", "older"]) {
+        let node = &edge["node"];
+        assert_eq!(node["subject"], subject);
+        assert_eq!(node["board"]["slug"], slug.as_str());
+        assert_eq!(node["replies"]["totalCount"], 3);
+        assert_eq!(node["replies"]["pageInfo"]["hasNextPage"], true);
+        assert_eq!(
+            node["replies"]["nodes"][0]["content"],
+            format!("{subject} reply 0")
+        );
+    }
+
+    // Continue the reply list from the returned cursor.
+    let cursor = threads[0]["node"]["replies"]["pageInfo"]["endCursor"].clone();
+    let req = test::TestRequest::post()
+        .uri("/graphql")
+        .set_json(json!({
+            "query": "query($id: Int!, $after: String) { thread(id: $id) { replies(after: $after) { pageInfo { hasNextPage } nodes { content thread { id } } } } }",
+            "variables": {"id": thread_ids[1], "after": cursor},
+        }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let replies = &body["data"]["thread"]["replies"];
+    assert_eq!(replies["pageInfo"]["hasNextPage"], false);
+    assert_eq!(replies["nodes"].as_array().unwrap().len(), 1);
+    assert_eq!(replies["nodes"][0]["content"], "newer reply 2");
+    assert_eq!(replies["nodes"][0]["thread"]["id"], thread_ids[1]);
+}
+
+#[actix_web::test]
+async fn invalid_search_reports_an_error_code() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::new(
+                Arc::new(test_repo().await),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let req = test::TestRequest::post()
+        .uri("/graphql")
+        .set_json(json!({"query": "{ search(q: \"  \") { backend } }"}))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["errors"][0]["extensions"]["code"], "bad_request");
+}
@@ -0,0 +1,124 @@
+#![cfg(feature = "grpc")]
+
+use rib::auth::{create_jwt, Role};
+use rib::grpc::pb::rib_client::RibClient;
+use rib::grpc::pb::{CreateThreadRequest, GetThreadRequest, ListBoardsRequest, ListThreadsRequest};
+use rib::models::NewBoard;
+use rib::repo::pg::PgRepo;
+use rib::repo::{BoardRepo, RoleRepo};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::AppState;
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::Code;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+async fn connect(addr: std::net::SocketAddr) -> RibClient<tonic::transport::Channel> {
+    for _ in 0..50 {
+        if let Ok(client) = RibClient::connect(format!("http://{addr}")).await {
+            return client;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("gRPC server did not start on {addr}");
+}
+
+#[actix_web::test]
+async fn serves_reads_and_authenticated_posts() {
+    let repo = test_repo().await;
+    repo.set_subject_role("discord:grpc-user", Role::User)
+        .await
+        .unwrap();
+    let slug = format!("rpc{}", &uuid::Uuid::new_v4().simple().to_string()[..10]);
+    let board = repo
+        .create_board(NewBoard {
+            slug: slug.clone(),
+            title: "gRPC".into(),
+        })
+        .await
+        .unwrap();
+    let state = Arc::new(AppState::new(
+        Arc::new(repo),
+        Arc::new(MockImageStore),
+        None,
+    ));
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let server = tokio::spawn(rib::grpc::serve(state, addr));
+    let mut client = connect(addr).await;
+
+    let boards = client
+        .list_boards(ListBoardsRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .boards;
+    assert!(boards.iter().any(|b| b.slug == slug));
+
+    let new_thread = CreateThreadRequest {
+        board_id: board.id,
+        subject: "over grpc".into(),
+        body: "protobuf body".into(),
+        ..Default::default()
+    };
+    let err = client
+        .create_thread(new_thread.clone())
+        .await
+        .expect_err("anonymous posts are rejected");
+    assert_eq!(err.code(), Code::Unauthenticated);
+
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    let token = create_jwt("grpc-user", "grpc-user", vec![Role::User]).unwrap();
+    let mut request = tonic::Request::new(new_thread);
+    request
+        .metadata_mut()
+        .insert("authorization", format!("Bearer {token}").parse().unwrap());
+    let thread = client.create_thread(request).await.unwrap().into_inner();
+    assert_eq!(thread.board_id, board.id);
+    assert!(thread.tripcode.is_none());
+
+    let threads = client
+        .list_threads(ListThreadsRequest { board_id: board.id })
+        .await
+        .unwrap()
+        .into_inner()
+        .threads;
+    assert_eq!(threads.len(), 1);
+    assert_eq!(threads[0].subject, "over grpc");
+
+    let err = client
+        .get_thread(GetThreadRequest { id: i64::MAX })
+        .await
+        .expect_err("missing thread");
+    assert_eq!(err.code(), Code::NotFound);
+    server.abort();
+}
@@ -0,0 +1,159 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::impersonation::ImpersonationAudit;
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn admins_act_as_subjects_with_every_action_logged() {
+    std::env::set_var("JWT_SECRET", "testsecret");
+    std::env::remove_var("IMPERSONATION_MAX_MINUTES");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let repo = Arc::new(PgRepo::new(pool));
+    let app = test::init_service(
+        App::new()
+            .wrap(ImpersonationAudit)
+            .app_data(actix_web::web::Data::new(AppState::new(
+                repo.clone(),
+                Arc::new(NoImages),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let user_id = format!("support-{}", &suffix[..8]);
+    let subject = format!("discord:{user_id}");
+    let staff_subject = format!("discord:staff-{}", &suffix[..8]);
+    repo.set_subject_role(&subject, Role::User).await.unwrap();
+    repo.set_subject_role(&staff_subject, Role::Admin)
+        .await
+        .unwrap();
+    let admin = create_jwt("admin", "admin", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt("mod-id", "mod-id", vec![Role::Moderator]).unwrap();
+    let mint = |subject: &str, minutes: i64| {
+        test::TestRequest::post()
+            .uri("/api/v1/admin/impersonate")
+            .set_json(json!({"subject": subject, "reason": "ticket 42", "minutes": minutes}))
+    };
+
+    // Only admins mint, never for admins, and only for a bounded time.
+    assert_eq!(call!(app, mint(&subject, 5), moderator).status(), 403);
+    assert_eq!(call!(app, mint(&staff_subject, 5), admin).status(), 403);
+    assert_eq!(call!(app, mint(&subject, 61), admin).status(), 400);
+    assert_eq!(call!(app, mint("anon:abc", 5), admin).status(), 400);
+    let resp = call!(app, mint(&subject, 5), admin);
+    assert_eq!(resp.status(), 200);
+    let minted: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(minted["act_as"], subject);
+    assert_eq!(minted["role"], "user");
+    let token = minted["token"].as_str().unwrap().to_string();
+
+    // The token acts as the subject and says so.
+    let resp = call!(app, test::TestRequest::get().uri("/api/v1/auth/me"), token);
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("x-impersonating").unwrap(),
+        subject.as_str()
+    );
+    let me: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(me["discord_id"], user_id);
+    assert_eq!(me["role"], "user");
+    assert_eq!(me["impersonated_by"], "admin:admin");
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": format!("im{}", &suffix[..8]), "title": "Nope"})),
+        token
+    );
+    assert_eq!(resp.status(), 403);
+    assert_eq!(
+        call!(
+            app,
+            test::TestRequest::post().uri("/api/v1/auth/refresh"),
+            token
+        )
+        .status(),
+        403
+    );
+    assert_eq!(call!(app, mint(&subject, 5), token).status(), 403);
+
+    let resp = call!(
+        app,
+        test::TestRequest::get().uri(&format!("/api/v1/admin/impersonations?subject={subject}")),
+        admin
+    );
+    assert_eq!(resp.status(), 200);
+    let log: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    let kinds: Vec<_> = log
+        .iter()
+        .map(|e| {
+            (
+                e["kind"].as_str().unwrap(),
+                e["route"].as_str().unwrap_or("-"),
+                e["status"].as_i64().unwrap_or(0),
+            )
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ("request", "/api/v1/admin/impersonate", 403),
+            ("request", "/api/v1/auth/refresh", 403),
+            ("request", "/api/v1/boards", 403),
+            ("token", "-", 0),
+        ]
+    );
+    assert!(log.iter().all(|e| e["actor"] == "admin:admin"));
+    assert_eq!(log[3]["reason"], "ticket 42");
+    assert_eq!(
+        call!(
+            app,
+            test::TestRequest::get().uri("/api/v1/admin/impersonations"),
+            moderator
+        )
+        .status(),
+        403
+    );
+    repo.delete_role(&subject).await.unwrap();
+    repo.delete_role(&staff_subject).await.unwrap();
+}
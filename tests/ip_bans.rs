@@ -0,0 +1,162 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn banned_addresses_and_subjects_get_a_structured_403() {
+    std::env::set_var("JWT_SECRET", "testsecret");
+    std::env::remove_var("TRUST_PROXY_HEADERS");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let repo = Arc::new(PgRepo::new(pool));
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                repo.clone(),
+                Arc::new(NoImages),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let user_id = format!("banned-{}", &suffix[..8]);
+    let subject = format!("discord:{user_id}");
+    repo.set_subject_role(&subject, Role::User).await.unwrap();
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt("mod-id", "mod-id", vec![Role::Moderator]).unwrap();
+    let user = create_jwt(&user_id, &user_id, vec![Role::User]).unwrap();
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": format!("ib{}", &suffix[..8]), "title": "Bans"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    let banned_peer: SocketAddr = "198.51.100.77:4000".parse().unwrap();
+    let post = |peer: SocketAddr| {
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .peer_addr(peer)
+            .set_json(json!({"board_id": board.id, "subject": "hi", "body": "hello"}))
+    };
+    let ip_ban = |network: &str| {
+        test::TestRequest::post()
+            .uri("/api/v1/admin/ip-bans")
+            .set_json(json!({"network": network, "reason": "spam wave", "public_reason": "Spam from your network"}))
+    };
+
+    assert_eq!(call!(app, ip_ban("198.51.100.0/24"), user).status(), 403);
+    assert_eq!(
+        call!(app, ip_ban("198.51.100.0/40"), moderator).status(),
+        400
+    );
+    let resp = call!(app, ip_ban("198.51.100.9/24"), moderator);
+    assert_eq!(resp.status(), 201);
+    let ban: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(ban["network"], "198.51.100.0/24");
+    let ban_id = ban["id"].as_i64().unwrap();
+
+    let resp = call!(app, post(banned_peer), user);
+    assert_eq!(resp.status(), 403);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["code"], "banned");
+    assert_eq!(body["ban"]["scope"], "ip");
+    assert_eq!(body["ban"]["reason"], "Spam from your network");
+    assert!(body["ban"]["expires_at"].is_null());
+    // Staff are exempt, and other addresses unaffected.
+    assert_eq!(call!(app, post(banned_peer), admin).status(), 201);
+    let elsewhere: SocketAddr = "192.0.2.5:4000".parse().unwrap();
+    let resp = call!(app, post(elsewhere), user);
+    assert_eq!(resp.status(), 201);
+    let _: Thread = test::read_body_json(resp).await;
+
+    let resp = call!(
+        app,
+        test::TestRequest::get().uri("/api/v1/admin/ip-bans"),
+        moderator
+    );
+    let bans: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert!(bans.iter().any(|b| b["id"] == ban_id));
+    let lift = || test::TestRequest::delete().uri(&format!("/api/v1/admin/ip-bans/{ban_id}"));
+    assert_eq!(call!(app, lift(), moderator).status(), 204);
+    assert_eq!(call!(app, lift(), moderator).status(), 404);
+    assert_eq!(call!(app, post(banned_peer), user).status(), 201);
+
+    // Subject bans carry their public reason and expiry the same way.
+    let expires_at = chrono::Utc::now() + chrono::Duration::days(3);
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/admin/bans")
+            .set_json(json!({"subject": subject, "reason": "internal", "public_reason": "Rule 3", "expires_at": expires_at})),
+        moderator
+    );
+    assert_eq!(resp.status(), 201);
+    let resp = call!(app, post(elsewhere), user);
+    assert_eq!(resp.status(), 403);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["ban"]["scope"], "subject");
+    assert_eq!(body["ban"]["reason"], "Rule 3");
+    assert!(body["ban"]["expires_at"].is_string());
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v2/threads")
+            .set_json(json!({"board_id": board.id, "subject": "hi", "body": "hello"})),
+        user
+    );
+    assert_eq!(resp.status(), 403);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["error"]["code"], "banned");
+    assert_eq!(body["error"]["ban"]["reason"], "Rule 3");
+
+    let resp = call!(
+        app,
+        test::TestRequest::delete().uri(&format!("/api/v1/admin/bans/{subject}")),
+        moderator
+    );
+    assert_eq!(resp.status(), 204);
+    repo.delete_role(&subject).await.unwrap();
+}
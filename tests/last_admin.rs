@@ -0,0 +1,161 @@
+use actix_web::{test, App};
+use chrono::{Duration, Utc};
+use rib::auth::{create_jwt, Role};
+use rib::repo::pg::PgRepo;
+use rib::repo::{RepoError, RoleRepo};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::Row;
+use std::sync::Arc;
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn the_last_admin_cannot_be_demoted() {
+    std::env::set_var("JWT_SECRET", "testsecret");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    // Set other admins aside so the test controls who is last.
+    let others = sqlx::query("SELECT subject FROM user_roles WHERE role = 'admin'")
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|row| row.get::<String, _>("subject"))
+        .collect::<Vec<_>>();
+    sqlx::query("UPDATE user_roles SET role = 'moderator' WHERE subject = ANY($1)")
+        .bind(&others)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let repo = Arc::new(PgRepo::new(pool.clone()));
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                repo.clone(),
+                Arc::new(NoImages),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let (first_id, second_id) = (
+        format!("first-{}", &suffix[..8]),
+        format!("second-{}", &suffix[..8]),
+    );
+    let (first, second) = (
+        format!("discord:{first_id}"),
+        format!("discord:{second_id}"),
+    );
+    repo.set_subject_role(&first, Role::Admin).await.unwrap();
+    let first_token = create_jwt(&first_id, &first_id, vec![Role::Admin]).unwrap();
+    let second_token = create_jwt(&second_id, &second_id, vec![Role::Admin]).unwrap();
+    let demote = |subject: &str, body: serde_json::Value| {
+        let mut body = body;
+        body["subject"] = json!(subject);
+        test::TestRequest::post()
+            .uri("/api/v1/admin/roles")
+            .set_json(body)
+    };
+    let code = |body: serde_json::Value| body["code"].as_str().unwrap().to_string();
+
+    // Demoting yourself needs confirmation; the only admin stays regardless.
+    let resp = call!(app, demote(&first, json!({"role": "user"})), first_token);
+    assert_eq!(resp.status(), 409);
+    assert_eq!(
+        code(test::read_body_json(resp).await),
+        "confirmation_required"
+    );
+    let resp = call!(
+        app,
+        demote(&first, json!({"role": "user", "confirm": true})),
+        first_token
+    );
+    assert_eq!(resp.status(), 409);
+    assert_eq!(code(test::read_body_json(resp).await), "last_admin");
+    let resp = call!(
+        app,
+        demote(
+            &first,
+            json!({"role": "admin", "expires_at": Utc::now() + Duration::days(1), "confirm": true})
+        ),
+        first_token
+    );
+    assert_eq!(resp.status(), 409);
+    let resp = call!(
+        app,
+        test::TestRequest::delete()
+            .uri(&format!("/api/v1/admin/roles/{first}"))
+            .set_json(json!({"confirm": true})),
+        first_token
+    );
+    assert_eq!(resp.status(), 409);
+    assert!(matches!(
+        repo.delete_role(&first).await,
+        Err(RepoError::LastAdmin)
+    ));
+    assert_eq!(repo.get_subject_role(&first).await, Some(Role::Admin));
+
+    // With a second admin, the first can step down, leaving the second last.
+    let resp = call!(app, demote(&second, json!({"role": "admin"})), first_token);
+    assert_eq!(resp.status(), 200);
+    let resp = call!(
+        app,
+        demote(&first, json!({"role": "moderator", "confirm": true})),
+        first_token
+    );
+    assert_eq!(resp.status(), 200);
+    let resp = call!(
+        app,
+        test::TestRequest::delete()
+            .uri(&format!("/api/v1/admin/roles/{second}"))
+            .set_json(json!({"confirm": true})),
+        second_token
+    );
+    assert_eq!(resp.status(), 409);
+    assert_eq!(code(test::read_body_json(resp).await), "last_admin");
+
+    sqlx::query("UPDATE user_roles SET role = 'admin' WHERE subject = ANY($1)")
+        .bind(&others)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM user_roles WHERE subject = ANY($1)")
+        .bind(vec![first, second])
+        .execute(&pool)
+        .await
+        .unwrap();
+}
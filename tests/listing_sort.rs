@@ -0,0 +1,183 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, Reply, Thread};
+use rib::repo::pg::PgRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn listings_sort_by_the_requested_key_with_id_tiebreakers() {
+    std::env::set_var("JWT_SECRET", "testsecret");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(PgRepo::new(pool.clone())),
+                Arc::new(NoImages),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let post = |uri: &str, body: Value| {
+        test::TestRequest::post()
+            .uri(uri)
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(body)
+            .to_request()
+    };
+    let board: Board = test::call_and_read_body_json(
+        &app,
+        post(
+            "/api/v1/boards",
+            json!({"slug": format!("so{}", &suffix[..8]), "title": "Sorting"}),
+        ),
+    )
+    .await;
+    let mut ids = Vec::new();
+    for subject in ["first", "second", "third"] {
+        let thread: Thread = test::call_and_read_body_json(
+            &app,
+            post(
+                "/api/v1/threads",
+                json!({"board_id": board.id, "subject": subject, "body": "hi"}),
+            ),
+        )
+        .await;
+        ids.push(thread.id);
+    }
+    let (a, b, c) = (ids[0], ids[1], ids[2]);
+    let mut replies = Vec::new();
+    for content in ["older", "newer"] {
+        let reply: Reply = test::call_and_read_body_json(
+            &app,
+            post(
+                "/api/v1/replies",
+                json!({"thread_id": b, "content": content}),
+            ),
+        )
+        .await;
+        replies.push(reply.id);
+    }
+    // a: oldest, last bumped; b: most replies; c: newest, least recently
+    // bumped, tied with a on replies.
+    for (id, created, bumped, reply_count) in [(a, 3, 1, 1), (b, 2, 2, 3), (c, 1, 3, 1)] {
+        sqlx::query(
+            "UPDATE threads SET created_at = now() - make_interval(hours => $2),
+                bump_time = now() - make_interval(hours => $3), reply_count = $4 WHERE id = $1",
+        )
+        .bind(id)
+        .bind(created)
+        .bind(bumped)
+        .bind(reply_count)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let thread_ids = |query: &'static str| {
+        let app = &app;
+        async move {
+            let threads: Vec<Thread> = test::call_and_read_body_json(
+                app,
+                test::TestRequest::get()
+                    .uri(&format!("/api/v1/boards/{}/threads{query}", board.id))
+                    .to_request(),
+            )
+            .await;
+            threads.into_iter().map(|t| t.id).collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(thread_ids("").await, vec![a, b, c]);
+    assert_eq!(thread_ids("?sort=bump_time&order=asc").await, vec![c, b, a]);
+    assert_eq!(thread_ids("?sort=created_at").await, vec![c, b, a]);
+    assert_eq!(
+        thread_ids("?sort=created_at&order=asc").await,
+        vec![a, b, c]
+    );
+    assert_eq!(thread_ids("?sort=reply_count").await, vec![b, c, a]);
+    assert_eq!(
+        thread_ids("?sort=reply_count&order=asc").await,
+        vec![a, c, b]
+    );
+
+    let v2: Value = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!(
+                "/api/v2/boards/{}/threads?sort=reply_count&order=asc",
+                board.id
+            ))
+            .to_request(),
+    )
+    .await;
+    let v2_ids: Vec<i64> = v2["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["id"].as_i64().unwrap())
+        .collect();
+    assert_eq!(v2_ids, vec![a, c, b]);
+
+    let reply_ids = |uri: String| {
+        let app = &app;
+        async move {
+            let replies: Vec<Reply> =
+                test::call_and_read_body_json(app, test::TestRequest::get().uri(&uri).to_request())
+                    .await;
+            replies.into_iter().map(|r| r.id).collect::<Vec<_>>()
+        }
+    };
+    let listing = format!("/api/v1/threads/{b}/replies");
+    assert_eq!(reply_ids(listing.clone()).await, replies);
+    let newest_first: Vec<_> = replies.iter().rev().copied().collect();
+    assert_eq!(
+        reply_ids(format!("{listing}?sort=created_at&order=desc")).await,
+        newest_first
+    );
+    let v2: Value = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v2/threads/{b}/replies?order=desc"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(v2["data"][0]["id"].as_i64(), Some(newest_first[0]));
+
+    for uri in [
+        format!("/api/v1/boards/{}/threads?sort=popularity", board.id),
+        format!("/api/v1/boards/{}/threads?order=sideways", board.id),
+        format!("/api/v2/boards/{}/threads?sort=popularity", board.id),
+        format!("{listing}?sort=reply_count"),
+    ] {
+        let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(resp.status(), 400, "{uri}");
+    }
+}
@@ -0,0 +1,84 @@
+use actix_web::body::MessageBody;
+use actix_web::{test, App};
+use rib::live::LiveHub;
+use rib::models::OutboxEvent;
+use rib::repo::pg::PgRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use std::pin::Pin;
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+fn event(id: i64, thread_id: i64) -> OutboxEvent {
+    OutboxEvent {
+        id,
+        event_type: "reply.created".into(),
+        payload: serde_json::json!({"reply_id": id, "thread_id": thread_id}),
+        created_at: chrono::Utc::now(),
+        attempts: 0,
+    }
+}
+
+#[actix_web::test]
+async fn live_stream_forwards_events_for_requested_thread() {
+    let hub = LiveHub::default();
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(
+                AppState::new(Arc::new(test_repo().await), Arc::new(MockImageStore), None)
+                    .with_live(hub.clone()),
+            ))
+            .configure(config),
+    )
+    .await;
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/live?thread_id=5")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "text/event-stream"
+    );
+
+    hub.publish_local(event(1, 4));
+    hub.publish_local(event(2, 5));
+    let mut body = response.into_body();
+    let chunk = futures_util::future::poll_fn(|cx| Pin::new(&mut body).poll_next(cx))
+        .await
+        .expect("stream open")
+        .expect("chunk");
+    let frame = String::from_utf8(chunk.to_vec()).unwrap();
+    assert!(
+        frame.starts_with("id: 2\nevent: reply.created\n"),
+        "{frame}"
+    );
+}
@@ -0,0 +1,249 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::matrix::{MatrixConfig, MatrixMirrorRunner};
+use rib::models::{Board, MatrixMirror, Reply, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+use std::time::Duration;
+use wiremock::matchers::{body_string_contains, header, method, path_regex, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+fn runner_config(homeserver_url: String) -> MatrixConfig {
+    MatrixConfig {
+        enabled: true,
+        homeserver_url: Some(homeserver_url),
+        access_token: Some("as-token".into()),
+        appservice_user_id: Some("@rib:matrix.example".into()),
+        poll_interval: Duration::from_secs(1),
+        batch_size: 1000,
+        posts_per_pass: 20,
+        settle: Duration::ZERO,
+        lease: Duration::from_secs(60),
+        retry: Duration::ZERO,
+        base_url: "https://rib.example".into(),
+    }
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn mirrors_post_new_posts_and_redact_deleted_ones() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    // Mirrors left by earlier runs would post to this run's homeserver.
+    sqlx::query("DELETE FROM matrix_mirrors")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let poster_id = format!("mirrored-{}", &suffix[..8]);
+    let repo = Arc::new(PgRepo::new(pool.clone()));
+    repo.set_subject_role(&format!("discord:{poster_id}"), Role::User)
+        .await
+        .expect("allowlist poster");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                repo.clone(),
+                Arc::new(NoImages),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("matrix-admin", "matrix-admin", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt("mod-id", "mod-id", vec![Role::Moderator]).unwrap();
+    let poster = create_jwt(&poster_id, &poster_id, vec![Role::User]).unwrap();
+    let slug = format!("mx{}", &suffix[..8]);
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": slug, "title": "Mirrored"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+
+    let add = |body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri("/api/v1/admin/matrix-mirrors")
+            .set_json(body)
+    };
+    let room = "!room:matrix.example";
+    let board_mirror = json!({"board_id": board.id, "room_id": room});
+    std::env::remove_var("MATRIX_HOMESERVER_URL");
+    assert_eq!(call!(app, add(board_mirror.clone()), admin).status(), 400);
+    std::env::set_var("MATRIX_HOMESERVER_URL", "https://matrix.example");
+    std::env::set_var("MATRIX_ACCESS_TOKEN", "as-token");
+    assert_eq!(
+        call!(app, add(board_mirror.clone()), moderator).status(),
+        403
+    );
+    for invalid in [
+        json!({"board_id": board.id, "room_id": "#alias:matrix.example"}),
+        json!({"room_id": room}),
+        json!({"board_id": board.id, "thread_id": 1, "room_id": room}),
+    ] {
+        assert_eq!(
+            call!(app, add(invalid.clone()), admin).status(),
+            400,
+            "{invalid}"
+        );
+    }
+    assert_eq!(
+        call!(app, add(json!({"board_id": -1, "room_id": room})), admin).status(),
+        404
+    );
+    let resp = call!(app, add(board_mirror.clone()), admin);
+    assert_eq!(resp.status(), 201);
+    let by_board: MatrixMirror = test::read_body_json(resp).await;
+    assert_eq!(call!(app, add(board_mirror), admin).status(), 409);
+
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .set_json(json!({"board_id": board.id, "subject": "Mirrored", "body": "hello"})),
+        poster
+    );
+    let thread: Thread = test::read_body_json(resp).await;
+    let resp = call!(
+        app,
+        add(json!({"thread_id": thread.id, "room_id": "!other:matrix.example"})),
+        admin
+    );
+    assert_eq!(resp.status(), 201);
+    let by_thread: MatrixMirror = test::read_body_json(resp).await;
+    std::env::remove_var("MATRIX_HOMESERVER_URL");
+    std::env::remove_var("MATRIX_ACCESS_TOKEN");
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .set_json(json!({"thread_id": thread.id, "content": "first reply"})),
+        poster
+    );
+    assert_eq!(resp.status(), 201);
+    let reply: Reply = test::read_body_json(resp).await;
+
+    let server = MockServer::start().await;
+    let send = |mirror: &MatrixMirror, txn: String, event_id: &str, text: &str| {
+        Mock::given(method("PUT"))
+            .and(path_regex(format!(
+                "^/_matrix/client/v3/rooms/[^/]+/send/m.room.message/rib{}{txn}$",
+                mirror.id
+            )))
+            .and(header("authorization", "Bearer as-token"))
+            .and(query_param("user_id", "@rib:matrix.example"))
+            .and(body_string_contains(text))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"event_id": event_id})))
+            .expect(1)
+    };
+    send(
+        &by_board,
+        format!("t{}", thread.id),
+        "$thread",
+        "New thread on",
+    )
+    .mount(&server)
+    .await;
+    send(
+        &by_board,
+        format!("r{}", reply.id),
+        "$reply",
+        "Anonymous in",
+    )
+    .mount(&server)
+    .await;
+    send(
+        &by_thread,
+        format!("r{}", reply.id),
+        "$reply2",
+        "Anonymous: first reply",
+    )
+    .mount(&server)
+    .await;
+    let runner = MatrixMirrorRunner::new(repo.clone(), runner_config(server.uri())).unwrap();
+    assert_eq!(runner.run_once().await, 3);
+    assert_eq!(runner.run_once().await, 0);
+
+    // A moderator deleting the reply redacts it in both rooms.
+    let resp = call!(
+        app,
+        test::TestRequest::delete()
+            .uri(&format!(
+                "/api/v1/threads/{}/replies/{}",
+                thread.id, reply.id
+            ))
+            .set_json(json!({"reason": "Rule 4: no spoilers"})),
+        moderator
+    );
+    assert!(resp.status().is_success());
+    for (room, event) in [("%21room", "%24reply"), ("%21other", "%24reply2")] {
+        Mock::given(method("PUT"))
+            .and(path_regex(format!(
+                "^/_matrix/client/v3/rooms/{room}(%3A|:)matrix.example/redact/{event}/"
+            )))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"event_id": "$r"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    assert_eq!(runner.run_once().await, 2);
+    assert_eq!(runner.run_once().await, 0);
+
+    let resp = call!(
+        app,
+        test::TestRequest::get().uri("/api/v1/admin/matrix-mirrors"),
+        admin
+    );
+    let listed: Vec<MatrixMirror> = test::read_body_json(resp).await;
+    let board_state = listed.iter().find(|m| m.id == by_board.id).unwrap();
+    assert_eq!(board_state.last_thread_id, thread.id);
+    assert_eq!(board_state.last_reply_id, reply.id);
+    assert!(board_state.last_sent_at.is_some());
+    assert!(board_state.last_error.is_none());
+
+    for id in [by_board.id, by_thread.id] {
+        let delete =
+            || test::TestRequest::delete().uri(&format!("/api/v1/admin/matrix-mirrors/{id}"));
+        assert_eq!(call!(app, delete(), admin).status(), 204);
+        assert_eq!(call!(app, delete(), admin).status(), 404);
+    }
+}
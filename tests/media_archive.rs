@@ -0,0 +1,155 @@
+use rib::media_archive::{MediaArchiveConfig, MediaArchiver};
+use rib::models::{NewBoard, NewReply, NewThread, PublicIdentity};
+use rib::repo::pg::PgRepo;
+use rib::repo::{BoardRepo, ReplyRepo, ThreadRepo};
+use rib::storage::{ImageStore, ImageStoreError, StorageTier};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Records tier moves instead of storing anything.
+#[derive(Default)]
+struct TierLog {
+    moves: Mutex<Vec<(String, StorageTier)>>,
+}
+
+impl TierLog {
+    fn take(&self, hashes: &[&str]) -> Vec<(String, StorageTier)> {
+        let mut moves = std::mem::take(&mut *self.moves.lock().unwrap());
+        moves.retain(|(hash, _)| hashes.contains(&hash.as_str()));
+        moves.sort_by(|a, b| a.0.cmp(&b.0));
+        moves
+    }
+}
+
+#[async_trait::async_trait]
+impl ImageStore for TierLog {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn set_tier(&self, hash: &str, tier: StorageTier) -> Result<(), ImageStoreError> {
+        self.moves.lock().unwrap().push((hash.to_string(), tier));
+        Ok(())
+    }
+}
+
+fn random_hash() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn media_of_archived_threads_move_to_the_archive_tier_and_back() {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let repo = Arc::new(PgRepo::new(pool));
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let board = repo
+        .create_board(NewBoard {
+            slug: format!("ma{}", &suffix[..8]),
+            title: "Archived media".to_string(),
+        })
+        .await
+        .unwrap();
+    repo.update_board(
+        board.id,
+        serde_json::from_value(json!({"max_threads": 1})).unwrap(),
+    )
+    .await
+    .unwrap();
+    let post_thread = |image_hash: Option<String>| {
+        let repo = repo.clone();
+        async move {
+            repo.create_thread(
+                NewThread {
+                    board_id: board.id,
+                    subject: "media".to_string(),
+                    body: "attached".to_string(),
+                    mime: image_hash.as_ref().map(|_| "image/png".to_string()),
+                    image_hash,
+                    author_name: None,
+                    tripcode_password: None,
+                    delete_password: None,
+                    tags: Vec::new(),
+                },
+                json!({"subject": "discord:archiver"}),
+                PublicIdentity::default(),
+            )
+            .await
+            .unwrap()
+        }
+    };
+    let (op_image, reply_image) = (random_hash(), random_hash());
+    let first = post_thread(Some(op_image.clone())).await;
+    repo.create_reply(
+        NewReply {
+            thread_id: first.id,
+            content: "also attached".to_string(),
+            image_hash: Some(reply_image.clone()),
+            mime: Some("image/png".to_string()),
+            author_name: None,
+            tripcode_password: None,
+            delete_password: None,
+        },
+        json!({"subject": "discord:archiver"}),
+        PublicIdentity::default(),
+    )
+    .await
+    .unwrap();
+
+    let store = Arc::new(TierLog::default());
+    let archiver = MediaArchiver::new(
+        repo.clone(),
+        store.clone(),
+        MediaArchiveConfig {
+            enabled: true,
+            poll_interval: Duration::from_secs(1),
+            batch_size: 1000,
+        },
+    );
+    let ours = [op_image.as_str(), reply_image.as_str()];
+    // Media of a live thread stay where they are.
+    archiver.run_once().await;
+    assert!(store.take(&ours).is_empty());
+
+    // A newer thread pushes the first one into the archive.
+    post_thread(None).await;
+    archiver.run_once().await;
+    let mut expected = vec![
+        (op_image.clone(), StorageTier::Archive),
+        (reply_image.clone(), StorageTier::Archive),
+    ];
+    expected.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(store.take(&ours), expected);
+    archiver.run_once().await;
+    assert!(store.take(&ours).is_empty());
+
+    // Attaching an archived blob to a live post moves it back.
+    post_thread(Some(reply_image.clone())).await;
+    archiver.run_once().await;
+    assert_eq!(
+        store.take(&ours),
+        vec![(reply_image.clone(), StorageTier::Hot)]
+    );
+    archiver.run_once().await;
+    assert!(store.take(&ours).is_empty());
+
+    // Exports skip archived threads, so their attachments must not linger.
+    repo.hard_delete_board(board.id).await.unwrap();
+}
@@ -0,0 +1,80 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::db::MIGRATOR;
+use rib::repo::pg::PgRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::Value;
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+#[actix_web::test]
+async fn reports_migration_status_to_admins_only() {
+    if std::env::var("JWT_SECRET").is_err() {
+        std::env::set_var("JWT_SECRET", "testsecret");
+    }
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(test_repo().await),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+
+    let admin = create_jwt("admin", "admin", vec![Role::Admin]).unwrap();
+    let req = test::TestRequest::get()
+        .uri("/api/v1/admin/system/migrations")
+        .insert_header(("Authorization", format!("Bearer {admin}")))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let status: Value = test::read_body_json(resp).await;
+    let latest = MIGRATOR.iter().map(|m| m.version).max();
+    assert_eq!(status["latest_version"].as_i64(), latest);
+    // The test database may be migrated by sqlx or by plain psql (no bookkeeping
+    // table), so only check that every embedded migration is accounted for.
+    let applied = status["applied"].as_array().unwrap();
+    let pending = status["pending"].as_array().unwrap();
+    assert_eq!(applied.len() + pending.len(), MIGRATOR.iter().count());
+    assert_eq!(status["up_to_date"], pending.is_empty());
+    if pending.is_empty() {
+        assert_eq!(status["schema_version"].as_i64(), latest);
+    }
+
+    let user = create_jwt("user", "user", vec![Role::User]).unwrap();
+    let req = test::TestRequest::get()
+        .uri("/api/v1/admin/system/migrations")
+        .insert_header(("Authorization", format!("Bearer {user}")))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 403);
+}
@@ -49,11 +49,7 @@ async fn moderators_can_identify_and_ban_private_post_authors() {
     repo.set_subject_role(&subject, Role::User)
         .await
         .expect("allowlist poster");
-    let state = AppState {
-        repo: Arc::new(repo),
-        image_store: Arc::new(MockImageStore),
-        rate_limiter: None,
-    };
+    let state = AppState::new(Arc::new(repo), Arc::new(MockImageStore), None);
     let app = test::init_service(
         App::new()
             .app_data(actix_web::web::Data::new(state))
@@ -0,0 +1,117 @@
+use actix_web::{test, App};
+use rib::nostr::{NostrEvent, HTTP_AUTH_KIND};
+use rib::repo::pg::PgRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use secp256k1::{Keypair, Message, Secp256k1};
+use serde_json::json;
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+async fn pg_repo() -> PgRepo {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+fn signed_event(keypair: &Keypair, tags: Vec<Vec<String>>) -> NostrEvent {
+    let secp = Secp256k1::new();
+    let mut event = NostrEvent {
+        id: String::new(),
+        pubkey: hex::encode(keypair.x_only_public_key().0.serialize()),
+        created_at: chrono::Utc::now().timestamp(),
+        kind: HTTP_AUTH_KIND,
+        tags,
+        content: String::new(),
+        sig: String::new(),
+    };
+    let id = event.compute_id();
+    event.id = hex::encode(id);
+    event.sig = hex::encode(
+        secp.sign_schnorr_no_aux_rand(&Message::from_digest_slice(&id).unwrap(), keypair)
+            .as_ref(),
+    );
+    event
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn nostr_sign_in_mints_an_npub_subject_once() {
+    std::env::set_var("JWT_SECRET", "testsecret-abcdefghijklmnopqrstuvwxyz012345");
+    std::env::set_var("FRONTEND_URL", "https://rib.example");
+    std::env::remove_var("NOSTR_AUTH_URL");
+    let state = AppState::new(Arc::new(pg_repo().await), Arc::new(MockImageStore), None);
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(state))
+            .configure(config),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/auth/nostr/challenge")
+        .to_request();
+    let body: serde_json::Value = test::read_body_json(test::call_service(&app, req).await).await;
+    let challenge = body["challenge"].as_str().unwrap().to_string();
+    let url = body["url"].as_str().unwrap().to_string();
+    assert_eq!(url, "https://rib.example/api/v1/auth/nostr/verify");
+
+    let keypair = Keypair::from_seckey_slice(&Secp256k1::new(), &[7u8; 32]).unwrap();
+    let tags = |url: &str| {
+        vec![
+            vec!["u".to_string(), url.to_string()],
+            vec!["method".to_string(), "POST".to_string()],
+            vec!["challenge".to_string(), challenge.clone()],
+        ]
+    };
+    let verify = |event: &NostrEvent| {
+        test::TestRequest::post()
+            .uri("/api/v1/auth/nostr/verify")
+            .set_json(json!({ "event": event }))
+            .to_request()
+    };
+
+    // Signed for another site.
+    let elsewhere = signed_event(&keypair, tags("https://other.example/auth"));
+    assert_eq!(
+        test::call_service(&app, verify(&elsewhere)).await.status(),
+        400
+    );
+    // Tampered after signing.
+    let mut forged = signed_event(&keypair, tags(&url));
+    forged.content = "changed".into();
+    assert_eq!(
+        test::call_service(&app, verify(&forged)).await.status(),
+        400
+    );
+
+    let event = signed_event(&keypair, tags(&url));
+    let resp = test::call_service(&app, verify(&event)).await;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let claims = rib::auth::decode_jwt(body["token"].as_str().unwrap()).unwrap();
+    assert!(claims.sub.starts_with("nostr:npub1"));
+
+    // The challenge was spent.
+    assert_eq!(test::call_service(&app, verify(&event)).await.status(), 400);
+}
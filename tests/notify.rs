@@ -0,0 +1,95 @@
+use rib::cache::BoardCache;
+use rib::live::{LiveBus, LiveHub};
+use rib::models::{NewBoard, OutboxEvent};
+use rib::notify::{ChangeListener, PgLiveBus};
+use rib::repo::pg::PgRepo;
+use rib::repo::BoardRepo;
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn test_pool() -> sqlx::PgPool {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    sqlx::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database")
+}
+
+async fn eventually(mut check: impl FnMut() -> bool) -> bool {
+    for _ in 0..100 {
+        if check() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    false
+}
+
+/// Release listener connections while the test runtime is still alive.
+async fn stop(listener: tokio::task::JoinHandle<()>) {
+    listener.abort();
+    let _ = listener.await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+}
+
+#[actix_web::test]
+async fn board_writes_invalidate_listening_caches() {
+    let pool = test_pool().await;
+    let cache = BoardCache::default();
+    let wakeup = Arc::new(tokio::sync::Notify::new());
+    let listener = ChangeListener::new(pool.clone())
+        .with_board_cache(cache.clone())
+        .with_outbox_wakeup(wakeup)
+        .spawn();
+    // Entries are accepted only once the listener is subscribed.
+    assert!(
+        eventually(|| {
+            cache.store(cache.generation(), Vec::new());
+            cache.get().is_some()
+        })
+        .await
+    );
+
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    PgRepo::new(pool)
+        .create_board(NewBoard {
+            slug: format!("notify{}", &suffix[..8]),
+            title: "Notify test".to_string(),
+        })
+        .await
+        .expect("create board");
+    assert!(eventually(|| cache.get().is_none()).await);
+    stop(listener).await;
+}
+
+#[actix_web::test]
+async fn postgres_live_bus_reaches_other_listeners() {
+    let pool = test_pool().await;
+    let hub = LiveHub::default();
+    let mut rx = hub.subscribe();
+    let bus = Arc::new(PgLiveBus::new(pool));
+    let listener = bus.clone().spawn_listener(hub);
+    let event = OutboxEvent {
+        id: 11,
+        event_type: "thread.created".into(),
+        payload: serde_json::json!({"thread_id": 1, "board_id": 1}),
+        created_at: chrono::Utc::now(),
+        attempts: 0,
+    };
+    // Publish until the listener has subscribed; NOTIFY is not buffered for late listeners.
+    let received = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            bus.publish(&event).await.expect("publish");
+            if let Ok(Ok(event)) = tokio::time::timeout(Duration::from_millis(100), rx.recv()).await
+            {
+                return event;
+            }
+        }
+    })
+    .await
+    .expect("event relayed through postgres");
+    assert_eq!(received.id, 11);
+    stop(listener).await;
+}
@@ -0,0 +1,237 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, ModerationEntry, Reply, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct MockImageStore {
+    inner: Mutex<HashMap<String, (Vec<u8>, String)>>,
+}
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, hash: &str, mime: &str, bytes: &[u8]) -> Result<(), ImageStoreError> {
+        let mut map = self.inner.lock().unwrap();
+        if map.contains_key(hash) {
+            return Err(ImageStoreError::Duplicate);
+        }
+        map.insert(hash.to_string(), (bytes.to_vec(), mime.to_string()));
+        Ok(())
+    }
+
+    async fn load(&self, hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        let map = self.inner.lock().unwrap();
+        map.get(hash).cloned().ok_or(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, hash: &str) -> Result<(), ImageStoreError> {
+        self.inner.lock().unwrap().remove(hash);
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn thread_creators_moderate_their_threads_on_opted_in_boards() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let op_id = format!("op-{}", &suffix[..8]);
+    let guest_id = format!("guest-{}", &suffix[..8]);
+    let repo = PgRepo::new(pool);
+    for id in [&op_id, &guest_id] {
+        repo.set_subject_role(&format!("discord:{id}"), Role::User)
+            .await
+            .expect("allowlist poster");
+    }
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore::default()),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt("mod-id", "mod-id", vec![Role::Moderator]).unwrap();
+    let op = create_jwt(&op_id, &op_id, vec![Role::User]).unwrap();
+    let guest = create_jwt(&guest_id, &guest_id, vec![Role::User]).unwrap();
+
+    let mut boards = Vec::new();
+    for (slug, title) in [("om", "OP moderated"), ("pm", "Plain")] {
+        let resp = call!(
+            app,
+            test::TestRequest::post()
+                .uri("/api/v1/boards")
+                .set_json(json!({"slug": format!("{slug}{}", &suffix[..8]), "title": title})),
+            admin
+        );
+        let board: Board = test::read_body_json(resp).await;
+        boards.push(board);
+    }
+    let resp = call!(
+        app,
+        test::TestRequest::patch()
+            .uri(&format!("/api/v1/boards/{}", boards[0].id))
+            .set_json(json!({"op_moderation": true})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    assert!(board.op_moderation);
+    assert!(!boards[1].op_moderation);
+
+    let mut threads = Vec::new();
+    for board in &boards {
+        let resp = call!(
+            app,
+            test::TestRequest::post()
+                .uri("/api/v1/threads")
+                .set_json(json!({"board_id": board.id, "subject": "mine", "body": "op"})),
+            op
+        );
+        let thread: Thread = test::read_body_json(resp).await;
+        threads.push(thread);
+    }
+    let mut replies = Vec::new();
+    for thread in &threads {
+        let resp = call!(
+            app,
+            test::TestRequest::post()
+                .uri("/api/v1/replies")
+                .set_json(json!({"thread_id": thread.id, "content": "off topic"})),
+            guest
+        );
+        assert_eq!(resp.status(), 201);
+        let reply: Reply = test::read_body_json(resp).await;
+        replies.push(reply);
+    }
+    let delete = |thread: &Thread, reply: &Reply| {
+        test::TestRequest::delete()
+            .uri(&format!(
+                "/api/v1/threads/{}/replies/{}",
+                thread.id, reply.id
+            ))
+            .set_json(json!({"reason": "Off topic for this thread"}))
+    };
+    let close = |thread: &Thread| {
+        test::TestRequest::post().uri(&format!("/api/v1/threads/{}/close", thread.id))
+    };
+
+    // Only the creator, and only on the opted-in board.
+    assert_eq!(
+        call!(app, delete(&threads[0], &replies[0]), guest).status(),
+        403
+    );
+    assert_eq!(
+        call!(app, delete(&threads[1], &replies[1]), op).status(),
+        403
+    );
+    assert_eq!(call!(app, close(&threads[1]), op).status(), 403);
+    assert_eq!(
+        call!(app, delete(&threads[0], &replies[1]), op).status(),
+        404
+    );
+
+    assert_eq!(
+        call!(app, delete(&threads[0], &replies[0]), op).status(),
+        204
+    );
+    assert_eq!(
+        call!(app, delete(&threads[0], &replies[0]), op).status(),
+        404
+    );
+    let resp = call!(app, close(&threads[0]), op);
+    assert_eq!(resp.status(), 200);
+    let closed: Thread = test::read_body_json(resp).await;
+    assert!(closed.closed_at.is_some());
+    for token in [&guest, &op] {
+        let resp = call!(
+            app,
+            test::TestRequest::post()
+                .uri("/api/v1/replies")
+                .set_json(json!({"thread_id": threads[0].id, "content": "late"})),
+            token
+        );
+        assert_eq!(resp.status(), 409);
+    }
+
+    // Staff can act anywhere.
+    let resp = call!(
+        app,
+        test::TestRequest::post().uri(&format!("/api/v1/threads/{}/reopen", threads[0].id)),
+        moderator
+    );
+    let reopened: Thread = test::read_body_json(resp).await;
+    assert!(reopened.closed_at.is_none());
+    assert_eq!(
+        call!(app, delete(&threads[1], &replies[1]), moderator).status(),
+        204
+    );
+
+    let resp = call!(
+        app,
+        test::TestRequest::get().uri("/api/v1/admin/moderation-log"),
+        op
+    );
+    assert_eq!(resp.status(), 403);
+    let resp = call!(
+        app,
+        test::TestRequest::get().uri(&format!(
+            "/api/v1/admin/moderation-log?thread_id={}",
+            threads[0].id
+        )),
+        moderator
+    );
+    let log: Vec<ModerationEntry> = test::read_body_json(resp).await;
+    let log: Vec<_> = log
+        .iter()
+        .map(|e| {
+            (
+                e.actor.as_str(),
+                e.actor_role.as_str(),
+                e.action.as_str(),
+                e.reply_id,
+            )
+        })
+        .collect();
+    let op_subject = format!("discord:{op_id}");
+    assert_eq!(
+        log,
+        vec![
+            ("mod-id:mod-id", "moderator", "reopen_thread", None),
+            (op_subject.as_str(), "op", "close_thread", None),
+            (
+                op_subject.as_str(),
+                "op",
+                "delete_reply",
+                Some(replies[0].id)
+            ),
+        ]
+    );
+}
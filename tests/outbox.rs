@@ -0,0 +1,173 @@
+use rib::models::{NewBoard, NewReport, NewThread, OutboxEvent, PublicIdentity, ReportKind};
+use rib::outbox::{events, EventSink, OutboxConfig, OutboxRelay};
+use rib::repo::pg::PgRepo;
+use rib::repo::{BoardRepo, ReportRepo, ThreadRepo};
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct RecordingSink {
+    seen: Mutex<Vec<OutboxEvent>>,
+}
+
+#[async_trait::async_trait]
+impl EventSink for RecordingSink {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    async fn deliver(&self, event: &OutboxEvent) -> anyhow::Result<()> {
+        self.seen.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+struct FailingSink;
+
+#[async_trait::async_trait]
+impl EventSink for FailingSink {
+    fn name(&self) -> &'static str {
+        "failing"
+    }
+
+    async fn deliver(&self, _event: &OutboxEvent) -> anyhow::Result<()> {
+        anyhow::bail!("sink offline")
+    }
+}
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+async fn create_thread(repo: &PgRepo) -> i64 {
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let board = repo
+        .create_board(NewBoard {
+            slug: format!("outbox{}", &suffix[..8]),
+            title: "Outbox test".to_string(),
+        })
+        .await
+        .expect("create board");
+    repo.create_thread(
+        NewThread {
+            board_id: board.id,
+            subject: "outbox".to_string(),
+            body: "event".to_string(),
+            image_hash: None,
+            mime: None,
+            author_name: None,
+            tripcode_password: None,
+            delete_password: None,
+            tags: Vec::new(),
+        },
+        serde_json::json!({"provider":"test"}),
+        PublicIdentity::default(),
+    )
+    .await
+    .expect("create thread")
+    .id
+}
+
+fn has_event(events: &[OutboxEvent], event_type: &str, thread_id: i64) -> bool {
+    events
+        .iter()
+        .any(|event| event.event_type == event_type && event.payload["thread_id"] == thread_id)
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn thread_mutations_are_relayed_to_sinks() {
+    let repo = test_repo().await;
+    let thread_id = create_thread(&repo).await;
+    repo.soft_delete_thread(thread_id)
+        .await
+        .expect("soft delete");
+
+    let sink = Arc::new(RecordingSink::default());
+    let relay = OutboxRelay::new(Arc::new(repo), vec![sink.clone()], OutboxConfig::from_env());
+    for _ in 0..50 {
+        if relay.run_once().await == 0 {
+            break;
+        }
+    }
+
+    let seen = sink.seen.lock().unwrap();
+    assert!(has_event(&seen, events::THREAD_CREATED, thread_id));
+    assert!(has_event(&seen, events::THREAD_DELETED, thread_id));
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn reports_are_relayed_to_sinks() {
+    let repo = test_repo().await;
+    let thread_id = create_thread(&repo).await;
+    let report = repo
+        .create_report(
+            "discord:outbox-reporter",
+            NewReport {
+                kind: ReportKind::Thread,
+                target_id: thread_id,
+                reason: "spam".to_string(),
+            },
+        )
+        .await
+        .expect("create report");
+
+    let sink = Arc::new(RecordingSink::default());
+    let relay = OutboxRelay::new(Arc::new(repo), vec![sink.clone()], OutboxConfig::from_env());
+    for _ in 0..50 {
+        if relay.run_once().await == 0 {
+            break;
+        }
+    }
+
+    let seen = sink.seen.lock().unwrap();
+    assert!(seen
+        .iter()
+        .any(|event| event.event_type == events::REPORT_CREATED
+            && event.payload["report_id"] == report.id
+            && event.payload["thread_id"] == thread_id
+            && event.payload["kind"] == "thread"));
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn failed_deliveries_are_retried_later() {
+    let repo = Arc::new(test_repo().await);
+    // Earlier tests leave undelivered events behind; start from an empty backlog
+    // so this thread's event lands in the failing relay's first batch.
+    let drain = OutboxRelay::new(
+        repo.clone(),
+        vec![Arc::new(RecordingSink::default())],
+        OutboxConfig::from_env(),
+    );
+    while drain.run_once().await > 0 {}
+    let thread_id = create_thread(&repo).await;
+
+    let failing = OutboxRelay::new(
+        repo.clone(),
+        vec![Arc::new(FailingSink)],
+        OutboxConfig::from_env(),
+    );
+    for _ in 0..50 {
+        if failing.run_once().await == 0 {
+            break;
+        }
+    }
+
+    // The failed event is backed off rather than dropped or redelivered immediately.
+    let sink = Arc::new(RecordingSink::default());
+    let relay = OutboxRelay::new(repo, vec![sink.clone()], OutboxConfig::from_env());
+    relay.run_once().await;
+    assert!(!has_event(
+        &sink.seen.lock().unwrap(),
+        events::THREAD_CREATED,
+        thread_id
+    ));
+}
@@ -0,0 +1,200 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, ModerationEntry, Reply, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct MockImageStore {
+    inner: Mutex<HashMap<String, (Vec<u8>, String)>>,
+}
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, hash: &str, mime: &str, bytes: &[u8]) -> Result<(), ImageStoreError> {
+        let mut map = self.inner.lock().unwrap();
+        if map.contains_key(hash) {
+            return Err(ImageStoreError::Duplicate);
+        }
+        map.insert(hash.to_string(), (bytes.to_vec(), mime.to_string()));
+        Ok(())
+    }
+
+    async fn load(&self, hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        let map = self.inner.lock().unwrap();
+        map.get(hash).cloned().ok_or(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, hash: &str) -> Result<(), ImageStoreError> {
+        self.inner.lock().unwrap().remove(hash);
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn creators_and_staff_pin_a_reply_of_the_thread() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let op_id = format!("op-{}", &suffix[..8]);
+    let guest_id = format!("guest-{}", &suffix[..8]);
+    let repo = PgRepo::new(pool);
+    for id in [&op_id, &guest_id] {
+        repo.set_subject_role(&format!("discord:{id}"), Role::User)
+            .await
+            .expect("allowlist poster");
+    }
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore::default()),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt("mod-id", "mod-id", vec![Role::Moderator]).unwrap();
+    let op = create_jwt(&op_id, &op_id, vec![Role::User]).unwrap();
+    let guest = create_jwt(&guest_id, &guest_id, vec![Role::User]).unwrap();
+
+    // A plain board: pinning needs no op_moderation opt-in.
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": format!("pr{}", &suffix[..8]), "title": "Pins"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    assert!(!board.op_moderation);
+
+    let mut threads = Vec::new();
+    for subject in ["rules", "other"] {
+        let resp = call!(
+            app,
+            test::TestRequest::post()
+                .uri("/api/v1/threads")
+                .set_json(json!({"board_id": board.id, "subject": subject, "body": "op"})),
+            op
+        );
+        let thread: Thread = test::read_body_json(resp).await;
+        assert_eq!(thread.pinned_reply_id, None);
+        threads.push(thread);
+    }
+    let mut replies = Vec::new();
+    for (thread, content) in [
+        (&threads[0], "answer"),
+        (&threads[0], "better answer"),
+        (&threads[1], "elsewhere"),
+    ] {
+        let resp = call!(
+            app,
+            test::TestRequest::post()
+                .uri("/api/v1/replies")
+                .set_json(json!({"thread_id": thread.id, "content": content})),
+            guest
+        );
+        assert_eq!(resp.status(), 201);
+        let reply: Reply = test::read_body_json(resp).await;
+        replies.push(reply);
+    }
+    let pin = |reply: &Reply| {
+        test::TestRequest::put()
+            .uri(&format!("/api/v1/threads/{}/pinned-reply", threads[0].id))
+            .set_json(json!({"reply_id": reply.id}))
+    };
+    let unpin = || {
+        test::TestRequest::delete().uri(&format!("/api/v1/threads/{}/pinned-reply", threads[0].id))
+    };
+    let fetch = || test::TestRequest::get().uri(&format!("/api/v1/threads/{}", threads[0].id));
+
+    assert_eq!(call!(app, pin(&replies[0]), guest).status(), 403);
+    assert_eq!(call!(app, pin(&replies[2]), op).status(), 404);
+
+    let resp = call!(app, pin(&replies[0]), op);
+    assert_eq!(resp.status(), 200);
+    let pinned: Thread = test::read_body_json(resp).await;
+    assert_eq!(pinned.pinned_reply_id, Some(replies[0].id));
+    let resp = call!(app, fetch(), guest);
+    let fetched: Thread = test::read_body_json(resp).await;
+    assert_eq!(fetched.pinned_reply_id, Some(replies[0].id));
+
+    // Staff replace and clear the pin.
+    let resp = call!(app, pin(&replies[1]), moderator);
+    let pinned: Thread = test::read_body_json(resp).await;
+    assert_eq!(pinned.pinned_reply_id, Some(replies[1].id));
+    assert_eq!(call!(app, unpin(), guest).status(), 403);
+    let resp = call!(app, unpin(), moderator);
+    assert_eq!(resp.status(), 200);
+    let unpinned: Thread = test::read_body_json(resp).await;
+    assert_eq!(unpinned.pinned_reply_id, None);
+
+    // Deleting the pinned reply drops the pin.
+    let resp = call!(app, pin(&replies[0]), op);
+    assert_eq!(resp.status(), 200);
+    let resp = call!(
+        app,
+        test::TestRequest::delete()
+            .uri(&format!(
+                "/api/v1/threads/{}/replies/{}",
+                threads[0].id, replies[0].id
+            ))
+            .set_json(json!({"reason": "Rule 4: no spoilers"})),
+        moderator
+    );
+    assert_eq!(resp.status(), 204);
+    let resp = call!(app, fetch(), guest);
+    let fetched: Thread = test::read_body_json(resp).await;
+    assert_eq!(fetched.pinned_reply_id, None);
+    assert_eq!(call!(app, pin(&replies[0]), op).status(), 404);
+
+    let resp = call!(
+        app,
+        test::TestRequest::get().uri(&format!(
+            "/api/v1/admin/moderation-log?thread_id={}",
+            threads[0].id
+        )),
+        moderator
+    );
+    let log: Vec<ModerationEntry> = test::read_body_json(resp).await;
+    let log: Vec<_> = log
+        .iter()
+        .map(|e| (e.actor_role.as_str(), e.action.as_str(), e.reply_id))
+        .collect();
+    assert_eq!(
+        log,
+        vec![
+            ("moderator", "delete_reply", Some(replies[0].id)),
+            ("op", "pin_reply", Some(replies[0].id)),
+            ("moderator", "unpin_reply", Some(replies[1].id)),
+            ("moderator", "pin_reply", Some(replies[1].id)),
+            ("op", "pin_reply", Some(replies[0].id)),
+        ]
+    );
+}
@@ -0,0 +1,118 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::repo::pg::PgRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn preferences_round_trip_per_subject() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(PgRepo::new(pool)),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let alice = create_jwt(&format!("a{}", &suffix[..8]), "alice", vec![Role::User]).unwrap();
+    let bob = create_jwt(&format!("b{}", &suffix[..8]), "bob", vec![Role::User]).unwrap();
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v1/users/me/preferences")
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 401);
+
+    let empty: Value = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v1/users/me/preferences")
+            .insert_header(("Authorization", format!("Bearer {alice}")))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(empty, json!({}));
+
+    let prefs = json!({"theme": "dark", "hidden_threads": [4, 8], "filters": ["spoiler"]});
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::put()
+            .uri("/api/v1/users/me/preferences")
+            .insert_header(("Authorization", format!("Bearer {alice}")))
+            .set_json(&prefs)
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+
+    for invalid in [
+        json!(["not", "an", "object"]),
+        json!({"hidden_threads": "4,8"}),
+        json!({"blob": "x".repeat(20 * 1024)}),
+    ] {
+        let resp = test::call_service(
+            &app,
+            test::TestRequest::put()
+                .uri("/api/v1/users/me/preferences")
+                .insert_header(("Authorization", format!("Bearer {alice}")))
+                .set_json(&invalid)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    let stored: Value = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v1/users/me/preferences")
+            .insert_header(("Authorization", format!("Bearer {alice}")))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(stored, prefs);
+    let other: Value = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri("/api/v1/users/me/preferences")
+            .insert_header(("Authorization", format!("Bearer {bob}")))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(other, json!({}));
+}
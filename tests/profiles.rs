@@ -0,0 +1,227 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{AuthorProfile, Board, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct MockImageStore {
+    inner: Mutex<HashMap<String, (Vec<u8>, String)>>,
+}
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, hash: &str, mime: &str, bytes: &[u8]) -> Result<(), ImageStoreError> {
+        let mut map = self.inner.lock().unwrap();
+        if map.contains_key(hash) {
+            return Err(ImageStoreError::Duplicate);
+        }
+        map.insert(hash.to_string(), (bytes.to_vec(), mime.to_string()));
+        Ok(())
+    }
+
+    async fn load(&self, hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        let map = self.inner.lock().unwrap();
+        map.get(hash).cloned().ok_or(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, hash: &str) -> Result<(), ImageStoreError> {
+        self.inner.lock().unwrap().remove(hash);
+        Ok(())
+    }
+}
+
+fn multipart(bytes: &[u8]) -> (String, Vec<u8>) {
+    let boundary = "AVATARBOUNDARY";
+    let mut body = format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"avatar\"\r\nContent-Type: application/octet-stream\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(bytes);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    (format!("multipart/form-data; boundary={boundary}"), body)
+}
+
+fn sample_png() -> Vec<u8> {
+    vec![
+        0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, b'I', b'H', b'D',
+        b'R', 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1F,
+        0x15, 0xC4, 0x89, 0x00, 0x00, 0x00, 0x0A, b'I', b'D', b'A', b'T', 0x78, 0x9C, 0x63, 0x00,
+        0x01, 0x00, 0x00, 0x05, 0x00, 0x01, 0x0D, 0x0A, 0x2D, 0xB4, 0x00, 0x00, 0x00, 0x00, b'I',
+        b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82,
+    ]
+}
+
+macro_rules! put_name {
+    ($app:expr, $token:expr, $name:expr) => {
+        test::call_service(
+            &$app,
+            test::TestRequest::put()
+                .uri("/api/v1/users/me/profile")
+                .insert_header(("Authorization", format!("Bearer {}", $token)))
+                .set_json(json!({ "display_name": $name }))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn display_names_and_avatars_show_on_posts_until_reset() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let owl_id = format!("owl-{}", &suffix[..8]);
+    let lark_id = format!("lark-{}", &suffix[..8]);
+    let repo = PgRepo::new(pool);
+    for id in [&owl_id, &lark_id] {
+        repo.set_subject_role(&format!("discord:{id}"), Role::User)
+            .await
+            .expect("allowlist poster");
+    }
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore::default()),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt("mod-id", "mod-id", vec![Role::Moderator]).unwrap();
+    let owl = create_jwt(&owl_id, &owl_id, vec![Role::User]).unwrap();
+    let lark = create_jwt(&lark_id, &lark_id, vec![Role::User]).unwrap();
+    let name = format!("Night Owl {}", &suffix[..8]);
+
+    let resp = put_name!(app, owl, format!("  {name} "));
+    assert_eq!(resp.status(), 200);
+    let profile: AuthorProfile = test::read_body_json(resp).await;
+    assert_eq!(profile.display_name.as_deref(), Some(name.as_str()));
+    assert_eq!(put_name!(app, lark, name.to_uppercase()).status(), 409);
+    assert_eq!(put_name!(app, lark, "Moderator Lark").status(), 400);
+    assert_eq!(put_name!(app, lark, "x").status(), 400);
+
+    let (content_type, body) = multipart(b"not an image");
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::put()
+            .uri("/api/v1/users/me/avatar")
+            .insert_header(("Authorization", format!("Bearer {owl}")))
+            .insert_header(("Content-Type", content_type))
+            .set_payload(body)
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 415);
+    let (content_type, body) = multipart(&sample_png());
+    let profile: AuthorProfile = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::put()
+            .uri("/api/v1/users/me/avatar")
+            .insert_header(("Authorization", format!("Bearer {owl}")))
+            .insert_header(("Content-Type", content_type))
+            .set_payload(body)
+            .to_request(),
+    )
+    .await;
+    let avatar = profile.avatar_hash.expect("avatar stored");
+    assert_eq!(avatar.len(), 64);
+
+    let board: Board = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(json!({"slug": format!("pf{}", &suffix[..8]), "title": "Profiles"}))
+            .to_request(),
+    )
+    .await;
+    let mut threads = Vec::new();
+    for token in [&owl, &lark] {
+        let thread: Thread = test::call_and_read_body_json(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/v1/threads")
+                .insert_header(("Authorization", format!("Bearer {token}")))
+                .set_json(json!({"board_id": board.id, "subject": "hello", "body": "op"}))
+                .to_request(),
+        )
+        .await;
+        threads.push(thread);
+    }
+    let listed: Vec<Value> = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/boards/{}/threads", board.id))
+            .to_request(),
+    )
+    .await;
+    let by_id = |id| listed.iter().find(|t| t["id"] == id).unwrap().clone();
+    assert_eq!(
+        by_id(threads[0].id)["author"],
+        json!({"display_name": name, "avatar_hash": avatar})
+    );
+    assert!(by_id(threads[1].id).get("author").is_none());
+    let streamed = test::call_and_read_body(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/boards/{}/threads", board.id))
+            .insert_header(("Accept", "application/x-ndjson"))
+            .to_request(),
+    )
+    .await;
+    let streamed: Vec<Value> = std::str::from_utf8(&streamed)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(streamed.len(), 2);
+    for thread in &streamed {
+        assert_eq!(
+            thread.get("author"),
+            by_id(thread["id"].as_i64().unwrap()).get("author")
+        );
+    }
+
+    let reset = || {
+        test::TestRequest::delete()
+            .uri(&format!("/api/v1/admin/profiles/discord:{owl_id}"))
+            .insert_header(("Authorization", format!("Bearer {moderator}")))
+            .to_request()
+    };
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::delete()
+            .uri(&format!("/api/v1/admin/profiles/discord:{owl_id}"))
+            .insert_header(("Authorization", format!("Bearer {lark}")))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 403);
+    assert_eq!(test::call_service(&app, reset()).await.status(), 204);
+    assert_eq!(test::call_service(&app, reset()).await.status(), 404);
+    let thread: Value = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/threads/{}", threads[0].id))
+            .to_request(),
+    )
+    .await;
+    assert!(thread.get("author").is_none());
+    assert_eq!(put_name!(app, lark, name).status(), 200);
+}
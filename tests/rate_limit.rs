@@ -75,14 +75,20 @@ async fn rate_limit_thread_creation() {
         reply_window: std::time::Duration::from_secs(60),
         image_limit: 100,
         image_window: std::time::Duration::from_secs(3600),
+        anon_thread_limit: 1,
+        anon_thread_window: std::time::Duration::from_secs(900),
+        anon_reply_limit: 3,
+        anon_reply_window: std::time::Duration::from_secs(120),
+        email_login_limit: 3,
+        email_login_window: std::time::Duration::from_secs(900),
     };
     let limiter = RateLimiterFacade::new(InMemoryRateLimiter::new(true), cfg);
 
-    let state = AppState {
-        repo: Arc::new(repo),
-        image_store: Arc::new(MockImageStore::default()),
-        rate_limiter: Some(limiter),
-    };
+    let state = AppState::new(
+        Arc::new(repo),
+        Arc::new(MockImageStore::default()),
+        Some(limiter),
+    );
     let app = test::init_service(
         App::new()
             .app_data(actix_web::web::Data::new(state))
@@ -125,3 +131,78 @@ async fn rate_limit_thread_creation() {
     let resp = test::call_service(&app, req).await;
     assert_eq!(resp.status(), 429, "second thread should be rate limited");
 }
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn reloaded_limits_apply_without_a_restart() {
+    use rib::reload::ConfigReloader;
+
+    let repo = pg_repo().await;
+    std::env::remove_var("CONFIG_ENV_FILE");
+    std::env::set_var("RL_THREAD_LIMIT", "1");
+    let reloader = ConfigReloader::from_env();
+    let limiter =
+        RateLimiterFacade::new(InMemoryRateLimiter::new(true), reloader.rate_limits.clone());
+    let state = AppState::new(
+        Arc::new(repo),
+        Arc::new(MockImageStore::default()),
+        Some(limiter),
+    )
+    .with_reloader(Some(reloader));
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(state))
+            .configure(config),
+    )
+    .await;
+
+    let user = user_token();
+    let admin = create_jwt("admin", "admin", vec![Role::Admin]).unwrap();
+    let slug = format!(
+        "rl-reload-{}",
+        chrono::Utc::now().timestamp_nanos_opt().expect("timestamp")
+    );
+    let req = test::TestRequest::post()
+        .uri("/api/v1/boards")
+        .insert_header(("Authorization", format!("Bearer {admin}")))
+        .set_json(json!({"slug":slug, "title":"RL reload"}))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 201);
+    let board: Board = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    let create_thread = |subject: &str| {
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .insert_header(("Authorization", format!("Bearer {user}")))
+            .set_json(json!({"board_id":board.id, "subject":subject, "body":"B"}))
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, create_thread("S1")).await;
+    assert_eq!(resp.status(), 201);
+    let resp = test::call_service(&app, create_thread("S2")).await;
+    assert_eq!(resp.status(), 429);
+
+    std::env::set_var("RL_THREAD_LIMIT", "5");
+    let req = test::TestRequest::post()
+        .uri("/api/v1/admin/config/reload")
+        .insert_header(("Authorization", format!("Bearer {user}")))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 403, "only admins may reload");
+    let req = test::TestRequest::post()
+        .uri("/api/v1/admin/config/reload")
+        .insert_header(("Authorization", format!("Bearer {admin}")))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let report: serde_json::Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+    assert!(report["reloaded"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("rate_limits")));
+
+    let resp = test::call_service(&app, create_thread("S3")).await;
+    assert_eq!(resp.status(), 201, "raised limit applies immediately");
+    std::env::remove_var("RL_THREAD_LIMIT");
+}
@@ -0,0 +1,193 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, ReactionCount, Reply, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct MockImageStore {
+    inner: Mutex<HashMap<String, (Vec<u8>, String)>>,
+}
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, hash: &str, mime: &str, bytes: &[u8]) -> Result<(), ImageStoreError> {
+        let mut map = self.inner.lock().unwrap();
+        if map.contains_key(hash) {
+            return Err(ImageStoreError::Duplicate);
+        }
+        map.insert(hash.to_string(), (bytes.to_vec(), mime.to_string()));
+        Ok(())
+    }
+
+    async fn load(&self, hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        let map = self.inner.lock().unwrap();
+        map.get(hash).cloned().ok_or(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, hash: &str) -> Result<(), ImageStoreError> {
+        self.inner.lock().unwrap().remove(hash);
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+fn counts(pairs: &[(&str, i64)]) -> Vec<ReactionCount> {
+    pairs
+        .iter()
+        .map(|(emoji, count)| ReactionCount {
+            emoji: emoji.to_string(),
+            count: *count,
+        })
+        .collect()
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn subjects_hold_one_reaction_per_reply_from_the_board_set() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let ids: Vec<String> = ["a", "b"]
+        .iter()
+        .map(|p| format!("{p}-{}", &suffix[..8]))
+        .collect();
+    let repo = PgRepo::new(pool);
+    for id in &ids {
+        repo.set_subject_role(&format!("discord:{id}"), Role::User)
+            .await
+            .expect("allowlist poster");
+    }
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore::default()),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let a = create_jwt(&ids[0], &ids[0], vec![Role::User]).unwrap();
+    let b = create_jwt(&ids[1], &ids[1], vec![Role::User]).unwrap();
+
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": format!("re{}", &suffix[..8]), "title": "Reacts"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    assert!(board.reactions.is_empty());
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .set_json(json!({"board_id": board.id, "subject": "s", "body": "op"})),
+        a
+    );
+    let thread: Thread = test::read_body_json(resp).await;
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .set_json(json!({"thread_id": thread.id, "content": "react to me"})),
+        a
+    );
+    let reply: Reply = test::read_body_json(resp).await;
+    assert!(reply.reactions.is_empty());
+    let react = |emoji: &str| {
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/replies/{}/reactions", reply.id))
+            .set_json(json!({ "emoji": emoji }))
+    };
+
+    // Off until the board lists its emoji.
+    assert_eq!(call!(app, react("👍"), a).status(), 400);
+    for bad in [json!(["👍", "👍"]), json!(["two words"]), json!([""])] {
+        let resp = call!(
+            app,
+            test::TestRequest::patch()
+                .uri(&format!("/api/v1/boards/{}", board.id))
+                .set_json(json!({ "reactions": bad })),
+            admin
+        );
+        assert_eq!(resp.status(), 400);
+    }
+    let resp = call!(
+        app,
+        test::TestRequest::patch()
+            .uri(&format!("/api/v1/boards/{}", board.id))
+            .set_json(json!({"reactions": ["👍", " ❤️ "]})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    assert_eq!(board.reactions, vec!["👍", "❤️"]);
+
+    assert_eq!(call!(app, react("😂"), a).status(), 400);
+    let resp = test::call_service(&app, react("👍").to_request()).await;
+    assert_eq!(resp.status(), 401);
+
+    let resp = call!(app, react("👍"), a);
+    assert_eq!(resp.status(), 200);
+    let got: Vec<ReactionCount> = test::read_body_json(resp).await;
+    assert_eq!(got, counts(&[("👍", 1)]));
+    let resp = call!(app, react("👍"), b);
+    let got: Vec<ReactionCount> = test::read_body_json(resp).await;
+    assert_eq!(got, counts(&[("👍", 2)]));
+    // Reacting again replaces the earlier reaction.
+    let resp = call!(app, react("❤️"), a);
+    let got: Vec<ReactionCount> = test::read_body_json(resp).await;
+    assert_eq!(got, counts(&[("❤️", 1), ("👍", 1)]));
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/threads/{}/replies", thread.id))
+            .to_request(),
+    )
+    .await;
+    let replies: Vec<Reply> = test::read_body_json(resp).await;
+    assert_eq!(replies[0].reactions.0, counts(&[("❤️", 1), ("👍", 1)]));
+
+    let resp = call!(
+        app,
+        test::TestRequest::delete().uri(&format!("/api/v1/replies/{}/reactions", reply.id)),
+        b
+    );
+    assert_eq!(resp.status(), 200);
+    let got: Vec<ReactionCount> = test::read_body_json(resp).await;
+    assert_eq!(got, counts(&[("❤️", 1)]));
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/replies/999999999/reactions")
+            .set_json(json!({"emoji": "👍"})),
+        a
+    );
+    assert_eq!(resp.status(), 404);
+}
@@ -0,0 +1,143 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct MockImageStore {
+    inner: Mutex<HashMap<String, (Vec<u8>, String)>>,
+}
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, hash: &str, mime: &str, bytes: &[u8]) -> Result<(), ImageStoreError> {
+        let mut map = self.inner.lock().unwrap();
+        if map.contains_key(hash) {
+            return Err(ImageStoreError::Duplicate);
+        }
+        map.insert(hash.to_string(), (bytes.to_vec(), mime.to_string()));
+        Ok(())
+    }
+
+    async fn load(&self, hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        let map = self.inner.lock().unwrap();
+        map.get(hash).cloned().ok_or(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, hash: &str) -> Result<(), ImageStoreError> {
+        self.inner.lock().unwrap().remove(hash);
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn replies_in_one_thread_wait_for_the_board_cooldown() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let poster_id = format!("flood-{}", &suffix[..8]);
+    let repo = PgRepo::new(pool);
+    let moderator_id = format!("floodmod-{}", &suffix[..8]);
+    repo.set_subject_role(&format!("discord:{poster_id}"), Role::User)
+        .await
+        .expect("allowlist poster");
+    repo.set_subject_role(&format!("discord:{moderator_id}"), Role::Moderator)
+        .await
+        .expect("allowlist moderator");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore::default()),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt(&moderator_id, &moderator_id, vec![Role::Moderator]).unwrap();
+    let poster = create_jwt(&poster_id, &poster_id, vec![Role::User]).unwrap();
+
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": format!("cd{}", &suffix[..8]), "title": "Cooldown"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    let patch = |secs: i32| {
+        test::TestRequest::patch()
+            .uri(&format!("/api/v1/boards/{}", board.id))
+            .set_json(json!({ "reply_cooldown_secs": secs }))
+    };
+    assert_eq!(call!(app, patch(-5), admin).status(), 400);
+    assert_eq!(call!(app, patch(7200), admin).status(), 400);
+    let resp = call!(app, patch(30), admin);
+    let updated: Board = test::read_body_json(resp).await;
+    assert_eq!(updated.reply_cooldown_secs, 30);
+
+    let mut threads = Vec::new();
+    for n in 0..2 {
+        let resp = call!(
+            app,
+            test::TestRequest::post()
+                .uri("/api/v1/threads")
+                .set_json(json!({"board_id": board.id, "subject": format!("t{n}"), "body": "x"})),
+            poster
+        );
+        let thread: Thread = test::read_body_json(resp).await;
+        threads.push(thread.id);
+    }
+    let reply = |thread_id: i64| {
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .set_json(json!({"thread_id": thread_id, "content": "again"}))
+    };
+
+    assert_eq!(call!(app, reply(threads[0]), poster).status(), 201);
+    let resp = call!(app, reply(threads[0]), poster);
+    assert_eq!(resp.status(), 429);
+    let retry_after: u64 = resp
+        .headers()
+        .get("Retry-After")
+        .expect("Retry-After header")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=31).contains(&retry_after));
+    // The cooldown is per thread, and staff are exempt.
+    assert_eq!(call!(app, reply(threads[1]), poster).status(), 201);
+    for _ in 0..2 {
+        assert_eq!(call!(app, reply(threads[0]), moderator).status(), 201);
+    }
+
+    assert_eq!(call!(app, patch(0), admin).status(), 200);
+    assert_eq!(call!(app, reply(threads[0]), poster).status(), 201);
+}
@@ -0,0 +1,50 @@
+use rib::models::NewBoard;
+use rib::repo::pg::PgRepo;
+use rib::repo::{BoardRepo, RepoError};
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+#[actix_web::test]
+async fn database_errors_are_classified() {
+    let repo = test_repo().await;
+    let slug = format!("errs{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    let board = |slug: &str, title: &str| NewBoard {
+        slug: slug.to_string(),
+        title: title.to_string(),
+    };
+    repo.create_board(board(&slug, "Errors"))
+        .await
+        .expect("create board");
+
+    assert!(matches!(
+        repo.create_board(board(&slug, "Errors")).await,
+        Err(RepoError::Conflict)
+    ));
+    match repo.create_board(board("Not A Slug!", "Errors")).await {
+        Err(RepoError::Constraint(name)) => assert_eq!(name, "boards_slug_format"),
+        other => panic!("expected constraint violation, got {other:?}"),
+    }
+    assert!(matches!(
+        repo.get_board(i64::MAX).await,
+        Err(RepoError::NotFound)
+    ));
+}
+
+#[actix_web::test]
+async fn closed_pool_is_unavailable() {
+    let repo = test_repo().await;
+    repo.pool().close().await;
+    assert!(matches!(
+        repo.list_boards(false).await,
+        Err(RepoError::Unavailable(_))
+    ));
+}
@@ -32,6 +32,8 @@ async fn duplicate_blob_can_be_attached_to_multiple_threads() {
                 mime: Some("image/png".to_string()),
                 author_name: None,
                 tripcode_password: None,
+                delete_password: None,
+                tags: Vec::new(),
             },
             serde_json::json!({"provider":"test"}),
             PublicIdentity::default(),
@@ -48,6 +50,8 @@ async fn duplicate_blob_can_be_attached_to_multiple_threads() {
                 mime: Some("image/png".to_string()),
                 author_name: None,
                 tripcode_password: None,
+                delete_password: None,
+                tags: Vec::new(),
             },
             serde_json::json!({"provider":"test"}),
             PublicIdentity::default(),
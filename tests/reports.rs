@@ -0,0 +1,183 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, Reply, Thread};
+use rib::repo::pg::PgRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn reports_are_queued_and_decided_by_moderators() {
+    std::env::set_var("JWT_SECRET", "testsecret");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(PgRepo::new(pool)),
+                Arc::new(NoImages),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt("mod-id", "mod-id", vec![Role::Moderator]).unwrap();
+    let first_id = format!("reporter-{}", &suffix[..8]);
+    let second_id = format!("witness-{}", &suffix[..8]);
+    let first = create_jwt(&first_id, &first_id, vec![Role::User]).unwrap();
+    let second = create_jwt(&second_id, &second_id, vec![Role::User]).unwrap();
+
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": format!("rp{}", &suffix[..8]), "title": "Reports"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .set_json(json!({"board_id": board.id, "subject": "op", "body": "op"})),
+        admin
+    );
+    let thread: Thread = test::read_body_json(resp).await;
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .set_json(json!({"thread_id": thread.id, "content": "buy pills"})),
+        admin
+    );
+    let reply: Reply = test::read_body_json(resp).await;
+
+    let report = |body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri("/api/v1/reports")
+            .set_json(body)
+    };
+    let spam = json!({"kind": "reply", "target_id": reply.id, "reason": " spam "});
+    assert_eq!(
+        test::call_service(&app, report(spam.clone()).to_request())
+            .await
+            .status(),
+        401
+    );
+    assert_eq!(
+        call!(
+            app,
+            report(json!({"kind": "reply", "target_id": reply.id, "reason": "  "})),
+            first
+        )
+        .status(),
+        400
+    );
+    assert_eq!(
+        call!(
+            app,
+            report(json!({"kind": "thread", "target_id": reply.id + 1_000_000, "reason": "x"})),
+            first
+        )
+        .status(),
+        404
+    );
+    let resp = call!(app, report(spam.clone()), first);
+    assert_eq!(resp.status(), 201);
+    let filed: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(filed["reason"], "spam");
+    assert_eq!(filed["thread_id"], thread.id);
+    assert_eq!(filed["reporter"], format!("discord:{first_id}"));
+    assert_eq!(filed["status"], "open");
+    assert_eq!(call!(app, report(spam.clone()), first).status(), 409);
+    assert_eq!(call!(app, report(spam.clone()), second).status(), 201);
+    let resp = call!(
+        app,
+        report(json!({"kind": "thread", "target_id": thread.id, "reason": "off topic"})),
+        second
+    );
+    assert_eq!(resp.status(), 201);
+    let on_thread: serde_json::Value = test::read_body_json(resp).await;
+
+    let queue = |status: &str| {
+        test::TestRequest::get().uri(&format!("/api/v1/admin/reports?status={status}"))
+    };
+    let ours = |reports: Vec<serde_json::Value>| {
+        reports
+            .into_iter()
+            .filter(|r| r["thread_id"] == thread.id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(call!(app, queue("open"), first).status(), 403);
+    assert_eq!(call!(app, queue("closed"), moderator).status(), 400);
+    let open = ours(test::read_body_json(call!(app, queue("open"), moderator)).await);
+    assert_eq!(open.len(), 3);
+
+    // Deciding one report closes the others on the same post.
+    let decide = |id: &serde_json::Value, action: &str| {
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/reports/{id}/{action}"))
+            .set_json(json!({"note": "reply removed"}))
+    };
+    assert_eq!(
+        call!(app, decide(&filed["id"], "resolve"), first).status(),
+        403
+    );
+    let resp = call!(app, decide(&filed["id"], "resolve"), moderator);
+    assert_eq!(resp.status(), 200);
+    let resolved: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(resolved["status"], "resolved");
+    assert_eq!(resolved["decided_by"], "mod-id:mod-id");
+    assert_eq!(resolved["note"], "reply removed");
+    assert_eq!(
+        call!(app, decide(&filed["id"], "dismiss"), moderator).status(),
+        409
+    );
+    let resp = call!(app, decide(&on_thread["id"], "dismiss"), moderator);
+    assert_eq!(resp.status(), 200);
+    let open = ours(test::read_body_json(call!(app, queue("open"), moderator)).await);
+    assert!(open.is_empty());
+    let closed = ours(test::read_body_json(call!(app, queue("resolved"), moderator)).await);
+    assert_eq!(closed.len(), 2);
+    let closed = ours(test::read_body_json(call!(app, queue("dismissed"), moderator)).await);
+    assert_eq!(closed.len(), 1);
+
+    // With the earlier report closed, the same reporter may flag the post again.
+    assert_eq!(call!(app, report(spam), first).status(), 201);
+}
@@ -0,0 +1,117 @@
+use actix_web::{test, App};
+use chrono::{Duration, Utc};
+use rib::auth::{create_jwt, Role};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::role_expiry::{RoleExpiryConfig, RoleExpiryRunner};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn temporary_roles_lapse_back_to_user() {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let repo = Arc::new(PgRepo::new(pool));
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                repo.clone(),
+                Arc::new(NoImages),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    std::env::set_var("JWT_SECRET", "testsecret");
+    let admin = create_jwt("admin", "admin", vec![Role::Admin]).unwrap();
+    let subject = format!("discord:temp-mod-{}", uuid::Uuid::new_v4().simple());
+    let assign = |role: &str, expires_at: chrono::DateTime<Utc>| {
+        test::TestRequest::post()
+            .uri("/api/v1/admin/roles")
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(json!({"subject": subject, "role": role, "expires_at": expires_at}))
+            .to_request()
+    };
+
+    // Expiries must be in the future and only demote elevated roles.
+    let past = Utc::now() - Duration::hours(1);
+    let later = Utc::now() + Duration::hours(6);
+    assert_eq!(
+        test::call_service(&app, assign("moderator", past))
+            .await
+            .status(),
+        400
+    );
+    assert_eq!(
+        test::call_service(&app, assign("user", later))
+            .await
+            .status(),
+        400
+    );
+    assert_eq!(
+        test::call_service(&app, assign("moderator", later))
+            .await
+            .status(),
+        200
+    );
+    let req = test::TestRequest::get()
+        .uri("/api/v1/admin/roles")
+        .insert_header(("Authorization", format!("Bearer {admin}")))
+        .to_request();
+    let roles: Vec<serde_json::Value> =
+        test::read_body_json(test::call_service(&app, req).await).await;
+    let listed = roles.iter().find(|r| r["subject"] == subject).unwrap();
+    assert_eq!(listed["role"], "moderator");
+    assert!(listed["expires_at"].is_string());
+    assert_eq!(repo.get_subject_role(&subject).await, Some(Role::Moderator));
+
+    // A lapsed role already counts as user, and the runner makes it so.
+    repo.set_subject_role_until(&subject, Role::Moderator, Utc::now() - Duration::seconds(1))
+        .await
+        .unwrap();
+    assert_eq!(repo.get_subject_role(&subject).await, Some(Role::User));
+    let runner = RoleExpiryRunner::new(
+        repo.clone(),
+        None,
+        RoleExpiryConfig {
+            enabled: true,
+            poll_interval: std::time::Duration::from_secs(1),
+        },
+    );
+    assert!(runner.run_once().await >= 1);
+    let stored = repo.list_roles().await.unwrap();
+    let (_, role, expires_at) = stored.iter().find(|(s, _, _)| *s == subject).unwrap();
+    assert_eq!((role, expires_at), (&Role::User, &None));
+
+    // Assigning a role without an expiry keeps it.
+    repo.set_subject_role(&subject, Role::Moderator)
+        .await
+        .unwrap();
+    runner.run_once().await;
+    assert_eq!(repo.get_subject_role(&subject).await, Some(Role::Moderator));
+    repo.delete_role(&subject).await.unwrap();
+}
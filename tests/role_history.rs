@@ -0,0 +1,197 @@
+use actix_web::{test, App};
+use chrono::{Duration, Utc};
+use rib::auth::{create_jwt, Role};
+use rib::mailer::{Email, Mailer};
+use rib::repo::pg::PgRepo;
+use rib::repo::Repo;
+use rib::role_expiry::{RoleExpiryConfig, RoleExpiryRunner};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::{Arc, Mutex};
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct RecordingMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+impl RecordingMailer {
+    fn take(&self) -> Vec<Email> {
+        std::mem::take(&mut *self.sent.lock().unwrap())
+    }
+}
+
+#[async_trait::async_trait]
+impl Mailer for RecordingMailer {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    async fn send(&self, email: &Email) -> anyhow::Result<()> {
+        self.sent.lock().unwrap().push(email.clone());
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn role_changes_are_recorded_and_mailed() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    std::env::set_var("FRONTEND_URL", "http://localhost:5173");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let user_id = format!("roles-{}", &suffix[..8]);
+    let subject = format!("discord:{user_id}");
+    let repo: Arc<dyn Repo> = Arc::new(PgRepo::new(pool));
+    let mailer = Arc::new(RecordingMailer::default());
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(
+                AppState::new(repo.clone(), Arc::new(NoImages), None)
+                    .with_mailer(Some(mailer.clone())),
+            ))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("admin", "admin", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt("mod-id", "mod-id", vec![Role::Moderator]).unwrap();
+    let user = create_jwt(&user_id, &user_id, vec![Role::User]).unwrap();
+
+    // Confirm an address so changes are mailed.
+    let resp = call!(
+        app,
+        test::TestRequest::put()
+            .uri("/api/v1/users/me/notifications")
+            .set_json(json!({"email": format!("{user_id}@example.org"), "digest": "off"})),
+        user
+    );
+    assert_eq!(resp.status(), 200);
+    let confirmation = mailer.take().pop().expect("confirmation email");
+    let start = confirmation.body.find("http://localhost:5173/").unwrap();
+    let link = confirmation.body[start..]
+        .split_whitespace()
+        .next()
+        .unwrap();
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(link.trim_start_matches("http://localhost:5173"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/admin/roles")
+            .set_json(json!({"subject": subject, "role": "moderator", "reason": "x".repeat(501)})),
+        admin
+    );
+    assert_eq!(resp.status(), 400);
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/admin/roles")
+            .set_json(json!({
+                "subject": subject,
+                "role": "moderator",
+                "expires_at": Utc::now() + Duration::days(7),
+                "reason": "covering the holidays",
+            })),
+        admin
+    );
+    assert_eq!(resp.status(), 200);
+    let sent = mailer.take();
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].body.contains("made you a moderator"));
+    assert!(sent[0].body.contains("covering the holidays"));
+
+    let resp = call!(
+        app,
+        test::TestRequest::delete()
+            .uri(&format!("/api/v1/admin/roles/{subject}"))
+            .set_json(json!({"reason": "holidays are over"})),
+        admin
+    );
+    assert_eq!(resp.status(), 204);
+    assert!(mailer.take()[0]
+        .body
+        .contains("removed your moderator role"));
+
+    // Demotions by the expiry job are recorded without an actor.
+    repo.set_subject_role_until(&subject, Role::Moderator, Utc::now() - Duration::seconds(1))
+        .await
+        .unwrap();
+    let runner = RoleExpiryRunner::new(
+        repo.clone(),
+        Some(mailer.clone()),
+        RoleExpiryConfig {
+            enabled: true,
+            poll_interval: std::time::Duration::from_secs(1),
+        },
+    );
+    assert!(runner.run_once().await >= 1);
+    assert!(mailer
+        .take()
+        .iter()
+        .any(|m| m.body.contains("temporary moderator role has expired")));
+
+    let history_uri = format!("/api/v1/admin/roles/{subject}/history");
+    let resp = call!(app, test::TestRequest::get().uri(&history_uri), moderator);
+    assert_eq!(resp.status(), 403);
+    let resp = call!(app, test::TestRequest::get().uri(&history_uri), admin);
+    assert_eq!(resp.status(), 200);
+    let history: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0]["changed_by"], serde_json::Value::Null);
+    assert_eq!(history[0]["from_role"], "moderator");
+    assert_eq!(history[0]["to_role"], "user");
+    assert_eq!(history[1]["changed_by"], "admin:admin");
+    assert_eq!(history[1]["to_role"], serde_json::Value::Null);
+    assert_eq!(history[1]["reason"], "holidays are over");
+    assert_eq!(history[2]["from_role"], serde_json::Value::Null);
+    assert_eq!(history[2]["to_role"], "moderator");
+    assert!(history[2]["expires_at"].is_string());
+    let resp = call!(
+        app,
+        test::TestRequest::get().uri(&format!("{history_uri}?limit=1")),
+        admin
+    );
+    let history: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    assert_eq!(history.len(), 1);
+    repo.delete_role(&subject).await.unwrap();
+}
@@ -52,11 +52,11 @@ async fn test_repo() -> PgRepo {
 async fn api_rejects_invalid_board_post_and_attachment_inputs() {
     let app = test::init_service(
         App::new()
-            .app_data(actix_web::web::Data::new(AppState {
-                repo: Arc::new(test_repo().await),
-                image_store: Arc::new(MockImageStore),
-                rate_limiter: None,
-            }))
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(test_repo().await),
+                Arc::new(MockImageStore),
+                None,
+            )))
             .configure(config),
     )
     .await;
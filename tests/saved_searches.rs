@@ -0,0 +1,276 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::digest::{DigestConfig, DigestWorker};
+use rib::mailer::{Email, Mailer};
+use rib::models::{Board, SavedSearch, SavedSearchMatch, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::{Repo, RoleRepo};
+use rib::saved_searches::{SavedSearchConfig, SavedSearchMatcher};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct RecordingMailer {
+    sent: Mutex<Vec<Email>>,
+}
+
+impl RecordingMailer {
+    fn take(&self) -> Vec<Email> {
+        std::mem::take(&mut *self.sent.lock().unwrap())
+    }
+}
+
+#[async_trait::async_trait]
+impl Mailer for RecordingMailer {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    async fn send(&self, email: &Email) -> anyhow::Result<()> {
+        self.sent.lock().unwrap().push(email.clone());
+        Ok(())
+    }
+}
+
+fn link_path(text: &str, prefix: &str) -> String {
+    let start = text.find(prefix).expect("link in email");
+    text[start..].split_whitespace().next().unwrap().to_string()
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn saved_searches_match_new_posts_and_reach_the_digest() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    std::env::set_var("FRONTEND_URL", "http://localhost:5173");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let watcher_id = format!("seek-{}", &suffix[..8]);
+    let poster_id = format!("post-{}", &suffix[..8]);
+    let word = format!("kw{}", &suffix[..8]);
+    let address = format!("seek{}@example.org", &suffix[..8]);
+    let repo = PgRepo::new(pool);
+    for id in [&watcher_id, &poster_id] {
+        repo.set_subject_role(&format!("discord:{id}"), Role::User)
+            .await
+            .expect("allowlist poster");
+    }
+    let repo: Arc<dyn Repo> = Arc::new(repo);
+    let mailer = Arc::new(RecordingMailer::default());
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(
+                AppState::new(repo.clone(), Arc::new(MockImageStore), None)
+                    .with_mailer(Some(mailer.clone())),
+            ))
+            .configure(config),
+    )
+    .await;
+    let matcher = SavedSearchMatcher::new(
+        repo.clone(),
+        SavedSearchConfig {
+            enabled: true,
+            poll_interval: Duration::from_secs(60),
+            batch_size: 10_000,
+            settle: Duration::ZERO,
+        },
+    );
+    let worker = DigestWorker::new(
+        repo,
+        mailer.clone(),
+        DigestConfig {
+            enabled: true,
+            poll_interval: Duration::from_secs(60),
+            batch_size: 1000,
+            base_url: "http://localhost:5173".into(),
+        },
+    );
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let watcher = create_jwt(&watcher_id, &watcher_id, vec![Role::User]).unwrap();
+    let poster = create_jwt(&poster_id, &poster_id, vec![Role::User]).unwrap();
+
+    let mut boards = Vec::new();
+    for slug in ["sa", "sb"] {
+        let resp = call!(
+            app,
+            test::TestRequest::post()
+                .uri("/api/v1/boards")
+                .set_json(json!({"slug": format!("{slug}{}", &suffix[..8]), "title": "Saved"})),
+            admin
+        );
+        let board: Board = test::read_body_json(resp).await;
+        boards.push(board);
+    }
+    let post_thread = |board: &Board, body: String| {
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .set_json(json!({"board_id": board.id, "subject": "topic", "body": body}))
+    };
+    let resp = call!(app, post_thread(&boards[0], format!("old {word}")), poster);
+    assert_eq!(resp.status(), 201);
+
+    let saved = |body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri("/api/v1/users/me/saved-searches")
+            .set_json(body)
+    };
+    assert_eq!(
+        call!(app, saved(json!({"query": "  "})), watcher).status(),
+        400
+    );
+    assert_eq!(
+        call!(
+            app,
+            saved(json!({"query": word, "board_id": 999_999_999})),
+            watcher
+        )
+        .status(),
+        404
+    );
+    let resp = call!(app, saved(json!({"query": word})), watcher);
+    assert_eq!(resp.status(), 201);
+    let anywhere: SavedSearch = test::read_body_json(resp).await;
+    let resp = call!(
+        app,
+        saved(json!({"query": word, "board_id": boards[1].id})),
+        watcher
+    );
+    let board_only: SavedSearch = test::read_body_json(resp).await;
+    let resp = call!(
+        app,
+        test::TestRequest::get().uri("/api/v1/users/me/saved-searches"),
+        watcher
+    );
+    let list: Vec<SavedSearch> = test::read_body_json(resp).await;
+    assert_eq!(
+        list.iter().map(|s| s.id).collect::<Vec<_>>(),
+        vec![anywhere.id, board_only.id]
+    );
+
+    // New posts by others match; the watcher's own posts do not.
+    let resp = call!(
+        app,
+        post_thread(&boards[0], format!("fresh {word} here")),
+        poster
+    );
+    let thread: Thread = test::read_body_json(resp).await;
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .set_json(json!({"thread_id": thread.id, "content": format!("a {word} reply")})),
+        poster
+    );
+    assert_eq!(resp.status(), 201);
+    let reply: rib::models::Reply = test::read_body_json(resp).await;
+    let resp = call!(
+        app,
+        post_thread(&boards[0], format!("mine {word}")),
+        watcher
+    );
+    assert_eq!(resp.status(), 201);
+    let resp = call!(app, post_thread(&boards[0], "unrelated".into()), poster);
+    assert_eq!(resp.status(), 201);
+
+    assert!(matcher.run_once().await >= 2);
+    let matches_of = |search: &SavedSearch| {
+        test::TestRequest::get().uri(&format!(
+            "/api/v1/users/me/saved-searches/{}/matches",
+            search.id
+        ))
+    };
+    let resp = call!(app, matches_of(&anywhere), watcher);
+    let matches: Vec<SavedSearchMatch> = test::read_body_json(resp).await;
+    let found: Vec<_> = matches.iter().map(|m| (m.thread_id, m.reply_id)).collect();
+    assert_eq!(found, vec![(thread.id, Some(reply.id)), (thread.id, None)]);
+    let resp = call!(app, matches_of(&board_only), watcher);
+    let matches: Vec<SavedSearchMatch> = test::read_body_json(resp).await;
+    assert!(matches.is_empty());
+    assert_eq!(call!(app, matches_of(&anywhere), poster).status(), 404);
+    matcher.run_once().await;
+    let resp = call!(app, matches_of(&anywhere), watcher);
+    let matches: Vec<SavedSearchMatch> = test::read_body_json(resp).await;
+    assert_eq!(matches.len(), 2, "posts are evaluated once");
+
+    // Matches go out with the digest once the address is confirmed.
+    let resp = call!(
+        app,
+        test::TestRequest::put()
+            .uri("/api/v1/users/me/notifications")
+            .set_json(json!({"email": address, "digest": "immediate"})),
+        watcher
+    );
+    assert_eq!(resp.status(), 200);
+    let confirmation = mailer.take().pop().expect("confirmation email");
+    let confirm = link_path(&confirmation.body, "http://localhost:5173/");
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(confirm.trim_start_matches("http://localhost:5173"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+    worker.run_once().await;
+    let mine: Vec<_> = mailer
+        .take()
+        .into_iter()
+        .filter(|email| email.to == address)
+        .collect();
+    assert_eq!(mine.len(), 1);
+    assert_eq!(mine[0].subject, format!("New matches for \"{word}\""));
+    assert!(mine[0].body.contains(&format!("a {word} reply")));
+    assert!(mine[0].body.contains(&format!("fresh {word} here")));
+    assert!(!mine[0].body.contains("mine"));
+    worker.run_once().await;
+    assert!(mailer.take().iter().all(|email| email.to != address));
+    let resp = call!(app, matches_of(&anywhere), watcher);
+    let matches: Vec<SavedSearchMatch> = test::read_body_json(resp).await;
+    assert!(matches.iter().all(|m| m.notified_at.is_some()));
+
+    let resp = call!(
+        app,
+        test::TestRequest::delete()
+            .uri(&format!("/api/v1/users/me/saved-searches/{}", anywhere.id)),
+        watcher
+    );
+    assert_eq!(resp.status(), 204);
+    assert_eq!(call!(app, matches_of(&anywhere), watcher).status(), 404);
+}
@@ -0,0 +1,182 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, ScheduledThread, Thread};
+use rib::repo::pg::PgRepo;
+use rib::scheduled::{ScheduleConfig, ScheduledThreadRunner};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct MockImageStore {
+    inner: Mutex<HashMap<String, (Vec<u8>, String)>>,
+}
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, hash: &str, mime: &str, bytes: &[u8]) -> Result<(), ImageStoreError> {
+        let mut map = self.inner.lock().unwrap();
+        if map.contains_key(hash) {
+            return Err(ImageStoreError::Duplicate);
+        }
+        map.insert(hash.to_string(), (bytes.to_vec(), mime.to_string()));
+        Ok(())
+    }
+
+    async fn load(&self, hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        let map = self.inner.lock().unwrap();
+        map.get(hash).cloned().ok_or(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, hash: &str) -> Result<(), ImageStoreError> {
+        self.inner.lock().unwrap().remove(hash);
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn admins_schedule_threads_that_the_runner_posts() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let repo = Arc::new(PgRepo::new(pool.clone()));
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                repo.clone(),
+                Arc::new(MockImageStore::default()),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("sched-admin", "sched-admin", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt("mod-id", "mod-id", vec![Role::Moderator]).unwrap();
+
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": format!("sc{}", &suffix[..8]), "title": "Scheduled"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    let soon = chrono::Utc::now() + chrono::Duration::hours(1);
+    let schedule = |body: serde_json::Value| {
+        test::TestRequest::post()
+            .uri("/api/v1/admin/scheduled-threads")
+            .set_json(body)
+    };
+    let weekly = json!({
+        "board_id": board.id, "subject": "Weekly general", "body": "Talk here",
+        "author_name": "Staff", "publish_at": soon, "repeat_days": 7
+    });
+    assert_eq!(
+        call!(app, schedule(weekly.clone()), moderator).status(),
+        403
+    );
+    for (key, value) in [
+        (
+            "publish_at",
+            json!(chrono::Utc::now() - chrono::Duration::minutes(1)),
+        ),
+        ("repeat_days", json!(0)),
+        ("subject", json!("")),
+    ] {
+        let mut invalid = weekly.clone();
+        invalid[key] = value;
+        assert_eq!(call!(app, schedule(invalid), admin).status(), 400, "{key}");
+    }
+    let resp = call!(app, schedule(weekly), admin);
+    assert_eq!(resp.status(), 201);
+    let weekly: ScheduledThread = test::read_body_json(resp).await;
+    let mut ids = Vec::new();
+    for subject in ["Once", "Cancelled"] {
+        let resp = call!(
+            app,
+            schedule(json!({
+                "board_id": board.id, "subject": subject, "body": "x", "publish_at": soon
+            })),
+            admin
+        );
+        let scheduled: ScheduledThread = test::read_body_json(resp).await;
+        ids.push(scheduled.id);
+    }
+    let cancel =
+        |id: i64| test::TestRequest::delete().uri(&format!("/api/v1/admin/scheduled-threads/{id}"));
+    assert_eq!(call!(app, cancel(ids[1]), admin).status(), 204);
+    assert_eq!(call!(app, cancel(ids[1]), admin).status(), 404);
+
+    // Make both schedules due; the weekly one missed two runs.
+    sqlx::query(
+        "UPDATE scheduled_threads SET publish_at = now() - make_interval(days => 15) WHERE id = $1",
+    )
+    .bind(weekly.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query("UPDATE scheduled_threads SET publish_at = now() WHERE id = $1")
+        .bind(ids[0])
+        .execute(&pool)
+        .await
+        .unwrap();
+    let runner = ScheduledThreadRunner::new(
+        repo.clone(),
+        ScheduleConfig {
+            enabled: true,
+            poll_interval: std::time::Duration::from_secs(1),
+            batch_size: 10,
+        },
+    );
+    assert_eq!(runner.run_once().await, 2);
+    assert_eq!(runner.run_once().await, 0);
+
+    let listed: Vec<Thread> = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/boards/{}/threads", board.id))
+            .to_request(),
+    )
+    .await;
+    let mut subjects: Vec<_> = listed.iter().map(|t| t.subject.as_str()).collect();
+    subjects.sort();
+    assert_eq!(subjects, vec!["Once", "Weekly general"]);
+    let general = listed
+        .iter()
+        .find(|t| t.subject == "Weekly general")
+        .unwrap();
+    assert_eq!(general.author_name.as_deref(), Some("Staff"));
+
+    let resp = call!(
+        app,
+        test::TestRequest::get().uri("/api/v1/admin/scheduled-threads"),
+        admin
+    );
+    let pending: Vec<ScheduledThread> = test::read_body_json(resp).await;
+    let pending: Vec<_> = pending.iter().filter(|s| s.board_id == board.id).collect();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].last_thread_id, Some(general.id));
+    let next_in = pending[0].publish_at - chrono::Utc::now();
+    assert!(next_in > chrono::Duration::days(5) && next_in < chrono::Duration::days(7));
+}
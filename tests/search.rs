@@ -0,0 +1,169 @@
+use actix_web::{test, App};
+use rib::models::{NewBoard, NewThread, PublicIdentity};
+use rib::repo::pg::PgRepo;
+use rib::repo::{BoardRepo, ThreadRepo};
+use rib::search::MeilisearchBackend;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[derive(Default)]
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+/// Creates a thread whose body contains a unique searchable token.
+async fn seed_thread(repo: &PgRepo) -> (i64, String) {
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let token = format!("needle{}", &suffix[..12]);
+    let board = repo
+        .create_board(NewBoard {
+            slug: format!("search{}", &suffix[..8]),
+            title: "Search test".to_string(),
+        })
+        .await
+        .expect("create board");
+    let thread = repo
+        .create_thread(
+            NewThread {
+                board_id: board.id,
+                subject: "searchable".to_string(),
+                body: format!("haystack {token} haystack"),
+                image_hash: None,
+                mime: None,
+                author_name: None,
+                tripcode_password: None,
+                delete_password: None,
+                tags: Vec::new(),
+            },
+            json!({"provider":"test"}),
+            PublicIdentity::default(),
+        )
+        .await
+        .expect("create thread");
+    (thread.id, token)
+}
+
+#[actix_web::test]
+async fn postgres_search_finds_visible_threads_only() {
+    let repo = test_repo().await;
+    let (thread_id, token) = seed_thread(&repo).await;
+    let (hidden_id, hidden_token) = seed_thread(&repo).await;
+    repo.soft_delete_thread(hidden_id)
+        .await
+        .expect("soft delete");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/search?q={token}"))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(body["backend"], "postgres");
+    assert_eq!(body["hits"][0]["id"], thread_id);
+    assert_eq!(body["hits"][0]["kind"], "thread");
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/search?q={hidden_token}"))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(body["hits"].as_array().map(Vec::len), Some(0));
+
+    let request = test::TestRequest::get()
+        .uri("/api/v1/search?q=%20")
+        .to_request();
+    assert_eq!(test::call_service(&app, request).await.status(), 400);
+}
+
+#[actix_web::test]
+async fn configured_backend_serves_search_and_falls_back_on_failure() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/indexes/rib-posts/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "hits": [{
+                "id": "reply-42",
+                "kind": "reply",
+                "post_id": 42,
+                "thread_id": 7,
+                "board_id": 1,
+                "text": "from meilisearch",
+                "created_at": "2026-01-01T00:00:00Z"
+            }]
+        })))
+        .mount(&server)
+        .await;
+    let repo = test_repo().await;
+    let (thread_id, token) = seed_thread(&repo).await;
+    let repo = Arc::new(repo);
+    let backend = MeilisearchBackend::new(&server.uri(), None, "rib-posts").expect("backend");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(
+                AppState::new(repo.clone(), Arc::new(MockImageStore), None)
+                    .with_search(Some(Arc::new(backend))),
+            ))
+            .configure(config),
+    )
+    .await;
+    let request = test::TestRequest::get()
+        .uri("/api/v1/search?q=anything")
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(body["backend"], "meilisearch");
+    assert_eq!(body["hits"][0]["id"], 42);
+
+    // An unreachable backend degrades to Postgres FTS instead of failing the request.
+    let offline =
+        MeilisearchBackend::new("http://127.0.0.1:9", None, "rib-posts").expect("backend");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(
+                AppState::new(repo, Arc::new(MockImageStore), None)
+                    .with_search(Some(Arc::new(offline))),
+            ))
+            .configure(config),
+    )
+    .await;
+    let request = test::TestRequest::get()
+        .uri(&format!("/api/v1/search?q={token}"))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, request).await;
+    assert_eq!(body["backend"], "postgres");
+    assert_eq!(body["hits"][0]["id"], thread_id);
+}
@@ -54,11 +54,11 @@ async fn test_security_headers_present() {
     let app = test::init_service(
         App::new()
             .wrap(SecurityHeaders::from_env())
-            .app_data(actix_web::web::Data::new(AppState {
-                repo: Arc::new(repo),
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
                 image_store,
-                rate_limiter: None,
-            }))
+                None,
+            )))
             .configure(config),
     )
     .await;
@@ -80,11 +80,11 @@ async fn test_hsts_enabled_via_env() {
     let app = test::init_service(
         App::new()
             .wrap(sec)
-            .app_data(actix_web::web::Data::new(AppState {
-                repo: Arc::new(repo),
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
                 image_store,
-                rate_limiter: None,
-            }))
+                None,
+            )))
             .configure(config),
     )
     .await;
@@ -109,11 +109,11 @@ async fn test_env_var_enables_hsts_without_builder_override() {
     let app = test::init_service(
         App::new()
             .wrap(SecurityHeaders::from_env())
-            .app_data(actix_web::web::Data::new(AppState {
-                repo: Arc::new(repo),
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
                 image_store,
-                rate_limiter: None,
-            }))
+                None,
+            )))
             .configure(config),
     )
     .await;
@@ -134,11 +134,11 @@ async fn test_builder_can_disable_hsts_even_when_env_set() {
     let app = test::init_service(
         App::new()
             .wrap(SecurityHeaders::from_env().with_hsts(false))
-            .app_data(actix_web::web::Data::new(AppState {
-                repo: Arc::new(repo),
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
                 image_store,
-                rate_limiter: None,
-            }))
+                None,
+            )))
             .configure(config),
     )
     .await;
@@ -158,11 +158,11 @@ async fn test_existing_csp_header_preserved() {
     let app = test::init_service(
         App::new()
             .wrap(SecurityHeaders::from_env())
-            .app_data(actix_web::web::Data::new(AppState {
-                repo: Arc::new(repo),
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
                 image_store,
-                rate_limiter: None,
-            }))
+                None,
+            )))
             .route(
                 "/custom",
                 web::get().to(|| async {
@@ -0,0 +1,106 @@
+use rib::models::{SortOrder, ThreadOrder};
+use rib::repo::pg::PgRepo;
+use rib::repo::{BoardRepo, ReplyRepo, RoleRepo, ThreadRepo};
+use rib::seed::{seed_demo_data, SEED_MARKER_BOARD};
+use rib::storage::{ImageStore, ImageStoreError};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+#[derive(Default)]
+struct MockImageStore {
+    inner: Mutex<HashMap<String, (Vec<u8>, String)>>,
+}
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, hash: &str, mime: &str, bytes: &[u8]) -> Result<(), ImageStoreError> {
+        let mut m = self.inner.lock().unwrap();
+        if m.contains_key(hash) {
+            return Err(ImageStoreError::Duplicate);
+        }
+        m.insert(hash.to_string(), (bytes.to_vec(), mime.to_string()));
+        Ok(())
+    }
+
+    async fn load(&self, hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        let m = self.inner.lock().unwrap();
+        m.get(hash).cloned().ok_or(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, hash: &str) -> Result<(), ImageStoreError> {
+        self.inner.lock().unwrap().remove(hash);
+        Ok(())
+    }
+}
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn seeds_demo_content_once() {
+    let repo = test_repo().await;
+    let store = MockImageStore::default();
+    let already_seeded = repo
+        .list_boards(true)
+        .await
+        .unwrap()
+        .iter()
+        .any(|b| b.slug == SEED_MARKER_BOARD);
+
+    let first = seed_demo_data(&repo, &store).await.unwrap();
+    if !already_seeded {
+        assert!(first.seeded);
+        assert!(first.threads > 0 && first.replies > 0 && first.images > 0);
+        // Attachments are generated deterministically and shared by content hash.
+        assert!(!store.inner.lock().unwrap().is_empty());
+    }
+
+    let board = repo
+        .list_boards(false)
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|b| b.slug == SEED_MARKER_BOARD)
+        .expect("demo board");
+    let threads = repo
+        .list_threads(board.id, false, ThreadOrder::default())
+        .await
+        .unwrap();
+    let welcome = threads
+        .iter()
+        .find(|t| t.subject == "Welcome to rib")
+        .expect("welcome thread");
+    assert_eq!(welcome.mime.as_deref(), Some("image/bmp"));
+    assert_eq!(welcome.created_by["provider"], "seed");
+    assert!(!repo
+        .list_replies(welcome.id, false, SortOrder::Asc)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(repo
+        .list_roles()
+        .await
+        .unwrap()
+        .iter()
+        .any(|(subject, _, _)| subject == "discord:demo-admin"));
+
+    let second = seed_demo_data(&repo, &store).await.unwrap();
+    assert!(!second.seeded);
+    assert_eq!(second.threads, 0);
+    assert_eq!(
+        repo.list_threads(board.id, false, ThreadOrder::default())
+            .await
+            .unwrap()
+            .len(),
+        threads.len()
+    );
+}
@@ -0,0 +1,139 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::error::ApiError;
+use rib::models::ServiceScope;
+use rib::repo::pg::PgRepo;
+use rib::service_accounts;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn service_accounts_act_only_within_their_scopes() {
+    std::env::set_var("JWT_SECRET", "testsecret");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let state = actix_web::web::Data::new(AppState::new(
+        Arc::new(PgRepo::new(pool)),
+        Arc::new(NoImages),
+        None,
+    ));
+    let app = test::init_service(App::new().app_data(state.clone()).configure(config)).await;
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let admin = create_jwt("admin", "admin", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt("mod-id", "mod-id", vec![Role::Moderator]).unwrap();
+    let create = |name: &str, scopes: serde_json::Value| {
+        test::TestRequest::post()
+            .uri("/api/v1/admin/service-accounts")
+            .set_json(json!({"name": name, "scopes": scopes}))
+    };
+
+    let name = format!("bot-{}", &suffix[..8]);
+    assert_eq!(
+        call!(app, create(&name, json!(["export"])), moderator).status(),
+        403
+    );
+    assert_eq!(
+        call!(app, create("Not Valid", json!(["export"])), admin).status(),
+        400
+    );
+    assert_eq!(call!(app, create(&name, json!([])), admin).status(), 400);
+    let resp = call!(app, create(&name, json!(["export", "announce"])), admin);
+    assert_eq!(resp.status(), 201);
+    let created: serde_json::Value = test::read_body_json(resp).await;
+    let token = created["token"].as_str().unwrap().to_string();
+    let id = created["account"]["id"].as_i64().unwrap();
+    assert!(token.starts_with(service_accounts::TOKEN_PREFIX));
+    assert_eq!(
+        call!(app, create(&name, json!(["metrics"])), admin).status(),
+        409
+    );
+
+    // The token reaches exactly what its scopes name, and nothing a user JWT would.
+    let export = || test::TestRequest::get().uri("/api/v1/admin/export");
+    assert_eq!(call!(app, export(), token).status(), 200);
+    let slug = format!("sa{}", &suffix[..8]);
+    let new_board = || {
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": slug, "title": "Service"}))
+    };
+    assert_eq!(call!(app, new_board(), token).status(), 401);
+    let resp = call!(app, new_board(), admin);
+    assert_eq!(resp.status(), 201);
+    let board: serde_json::Value = test::read_body_json(resp).await;
+    let announce = || {
+        test::TestRequest::post()
+            .uri("/api/v1/service/announcements")
+            .set_json(
+                json!({"board_id": board["id"], "subject": " Maintenance ", "body": "Tonight."}),
+            )
+    };
+    let resp = call!(app, announce(), token);
+    assert_eq!(resp.status(), 201);
+    let thread: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(thread["subject"], "Maintenance");
+    assert_eq!(thread["author_name"], name.as_str());
+    assert_eq!(call!(app, announce(), admin).status(), 401);
+    let req = test::TestRequest::default()
+        .insert_header(("Authorization", format!("Bearer {token}")))
+        .to_http_request();
+    assert!(matches!(
+        service_accounts::require(&req, &state, ServiceScope::Metrics).await,
+        Err(ApiError::Forbidden)
+    ));
+
+    let resp = call!(
+        app,
+        test::TestRequest::get().uri("/api/v1/admin/service-accounts"),
+        admin
+    );
+    let accounts: Vec<serde_json::Value> = test::read_body_json(resp).await;
+    let listed = accounts
+        .iter()
+        .find(|a| a["name"] == name.as_str())
+        .unwrap();
+    assert!(listed["last_used_at"].is_string());
+    assert!(listed.get("token").is_none());
+
+    let revoke =
+        || test::TestRequest::delete().uri(&format!("/api/v1/admin/service-accounts/{id}"));
+    assert_eq!(call!(app, revoke(), admin).status(), 204);
+    assert_eq!(call!(app, revoke(), admin).status(), 404);
+    assert_eq!(call!(app, export(), token).status(), 401);
+    assert_eq!(call!(app, announce(), token).status(), 401);
+}
@@ -0,0 +1,138 @@
+use actix_web::{test, web, App};
+use rib::models::{NewBoard, NewThread, PublicIdentity};
+use rib::repo::pg::PgRepo;
+use rib::repo::{BoardRepo, ThreadRepo};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+macro_rules! get_text {
+    ($app:expr, $uri:expr) => {
+        String::from_utf8(
+            test::call_and_read_body(&$app, test::TestRequest::get().uri($uri).to_request())
+                .await
+                .to_vec(),
+        )
+        .unwrap()
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn sitemaps_list_live_boards_and_threads() {
+    std::env::set_var("SITE_URL", "https://rib.example/");
+    std::env::remove_var("ROBOTS_TXT_FILE");
+    std::env::remove_var("ROBOTS_DISALLOW_ALL");
+    std::env::set_var("SITEMAP_PAGE_SIZE", "50000");
+    let repo = test_repo().await;
+    let slug = format!("sm{}", &uuid::Uuid::new_v4().simple().to_string()[..10]);
+    let board = repo
+        .create_board(NewBoard {
+            slug: slug.clone(),
+            title: "Sitemap".into(),
+        })
+        .await
+        .unwrap();
+    let mut ids = Vec::new();
+    for subject in ["kept", "removed"] {
+        let thread = repo
+            .create_thread(
+                NewThread {
+                    board_id: board.id,
+                    subject: subject.into(),
+                    body: "body".into(),
+                    image_hash: None,
+                    mime: None,
+                    author_name: None,
+                    tripcode_password: None,
+                    delete_password: None,
+                    tags: Vec::new(),
+                },
+                json!({"provider": "test", "subject": "test:sitemap"}),
+                PublicIdentity {
+                    author_name: None,
+                    tripcode: None,
+                },
+            )
+            .await
+            .unwrap();
+        ids.push(thread);
+    }
+    repo.soft_delete_thread(ids[1].id).await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+
+    let robots = get_text!(app, "/robots.txt");
+    assert!(robots.contains("Disallow: /api/"));
+    assert!(robots.contains("Sitemap: https://rib.example/sitemap.xml"));
+
+    let index = get_text!(app, "/sitemap.xml");
+    assert!(index.contains("<loc>https://rib.example/sitemap-boards.xml</loc>"));
+    assert!(index.contains("<loc>https://rib.example/sitemap-threads-1.xml</loc>"));
+
+    let boards = get_text!(app, "/sitemap-boards.xml");
+    let board_entry = format!("<loc>https://rib.example/{slug}</loc><lastmod>");
+    assert!(boards.contains(&board_entry), "{boards}");
+
+    let threads = get_text!(app, "/sitemap-threads-1.xml");
+    assert!(threads.contains(&format!(
+        "<loc>https://rib.example/thread/{}</loc>",
+        ids[0].id
+    )));
+    assert!(!threads.contains(&format!("/thread/{}<", ids[1].id)));
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri("/sitemap-threads-999999.xml")
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 404);
+
+    std::env::set_var("ROBOTS_DISALLOW_ALL", "1");
+    assert_eq!(
+        get_text!(app, "/robots.txt"),
+        "User-agent: *\nDisallow: /\n"
+    );
+    std::env::remove_var("ROBOTS_DISALLOW_ALL");
+    std::env::remove_var("SITE_URL");
+    std::env::remove_var("SITEMAP_PAGE_SIZE");
+}
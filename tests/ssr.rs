@@ -0,0 +1,178 @@
+use actix_web::{test, web, App};
+use rib::models::{NewBoard, NewReply, NewThread, PublicIdentity};
+use rib::repo::pg::PgRepo;
+use rib::repo::{BoardRepo, ReplyRepo, ThreadRepo};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+const GOOGLEBOT: &str = "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)";
+
+async fn spa() -> &'static str {
+    "spa"
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn renders_boards_and_threads_as_html() {
+    std::env::set_var("SITE_URL", "https://rib.example");
+    let repo = test_repo().await;
+    let slug = format!("ssr{}", &uuid::Uuid::new_v4().simple().to_string()[..10]);
+    let board = repo
+        .create_board(NewBoard {
+            slug: slug.clone(),
+            title: "Rendered".into(),
+        })
+        .await
+        .unwrap();
+    let thread = repo
+        .create_thread(
+            NewThread {
+                board_id: board.id,
+                subject: "Fish & <chips>".into(),
+                body: "opening post".into(),
+                image_hash: Some("ab".repeat(32)),
+                mime: Some("video/webm".into()),
+                author_name: None,
+                tripcode_password: None,
+                delete_password: None,
+                tags: Vec::new(),
+            },
+            json!({"provider": "test", "subject": "test:ssr"}),
+            PublicIdentity {
+                author_name: None,
+                tripcode: None,
+            },
+        )
+        .await
+        .unwrap();
+    repo.create_reply(
+        NewReply {
+            thread_id: thread.id,
+            content: "first reply".into(),
+            image_hash: None,
+            mime: None,
+            author_name: Some("Named".into()),
+            tripcode_password: None,
+            delete_password: None,
+        },
+        json!({"provider": "test", "subject": "test:ssr"}),
+        PublicIdentity {
+            author_name: Some("Named".into()),
+            tripcode: None,
+        },
+    )
+    .await
+    .unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config)
+            .configure(rib::ssr::crawler_fallback)
+            .route("/{tail:.*}", web::get().to(spa)),
+    )
+    .await;
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/_ssr/thread/{}", thread.id))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "text/html; charset=utf-8"
+    );
+    let html = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(
+        html.contains("<h1>Fish &#38; &#60;chips&#62;</h1>"),
+        "{html}"
+    );
+    assert!(html.contains("first reply"));
+    assert!(html.contains("Named"));
+    assert!(html.contains(&format!(
+        "<link rel=\"canonical\" href=\"https://rib.example/thread/{}\">",
+        thread.id
+    )));
+    // Videos are not inlined as <img>.
+    assert!(!html.contains(&format!("/images/{}", "ab".repeat(32))));
+
+    let board_page = test::call_and_read_body(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/{slug}"))
+            .insert_header(("user-agent", GOOGLEBOT))
+            .to_request(),
+    )
+    .await;
+    let board_page = String::from_utf8(board_page.to_vec()).unwrap();
+    assert!(board_page.contains(&format!("href=\"/thread/{}\"", thread.id)));
+
+    // Browsers still get the SPA at the same URL.
+    let spa_page = test::call_and_read_body(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/thread/{}", thread.id))
+            .insert_header(("user-agent", "Mozilla/5.0 Firefox/130.0"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(spa_page, "spa");
+
+    // Reserved SPA paths are never rendered server-side.
+    let about = test::call_and_read_body(
+        &app,
+        test::TestRequest::get()
+            .uri("/about")
+            .insert_header(("user-agent", GOOGLEBOT))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(about, "spa");
+
+    let missing = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri("/thread/999999999")
+            .insert_header(("user-agent", GOOGLEBOT))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(missing.status(), 404);
+    std::env::remove_var("SITE_URL");
+}
@@ -0,0 +1,287 @@
+use actix_web::{test, web, App};
+use rib::models::{NewBoard, NewReply, NewThread, PublicIdentity};
+use rib::repo::pg::PgRepo;
+use rib::repo::{BoardRepo, ReplyRepo, ThreadRepo};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+macro_rules! get_as {
+    ($app:expr, $uri:expr, $accept:expr) => {{
+        let resp = test::call_service(
+            &$app,
+            test::TestRequest::get()
+                .uri($uri)
+                .insert_header(("accept", $accept))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), 200);
+        let content_type = resp
+            .headers()
+            .get("content-type")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        (content_type, body)
+    }};
+}
+
+#[actix_web::test]
+async fn listings_negotiate_text_and_tsv() {
+    let repo = test_repo().await;
+    let slug = format!("td{}", &uuid::Uuid::new_v4().simple().to_string()[..10]);
+    let board = repo
+        .create_board(NewBoard {
+            slug,
+            title: "Dumps".into(),
+        })
+        .await
+        .unwrap();
+    let thread = repo
+        .create_thread(
+            NewThread {
+                board_id: board.id,
+                subject: "Plain subject".into(),
+                body: "line one\nline two".into(),
+                image_hash: None,
+                mime: None,
+                author_name: None,
+                tripcode_password: None,
+                delete_password: None,
+                tags: Vec::new(),
+            },
+            json!({"provider": "test", "subject": "test:dump"}),
+            PublicIdentity {
+                author_name: None,
+                tripcode: None,
+            },
+        )
+        .await
+        .unwrap();
+    let reply = repo
+        .create_reply(
+            NewReply {
+                thread_id: thread.id,
+                content: "tab\there".into(),
+                image_hash: None,
+                mime: None,
+                author_name: Some("Named".into()),
+                tripcode_password: None,
+                delete_password: None,
+            },
+            json!({"provider": "test", "subject": "test:dump"}),
+            PublicIdentity {
+                author_name: Some("Named".into()),
+                tripcode: None,
+            },
+        )
+        .await
+        .unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let threads_uri = format!("/api/v1/boards/{}/threads", board.id);
+    let replies_uri = format!("/api/v1/threads/{}/replies", thread.id);
+
+    let (content_type, body) = get_as!(app, &threads_uri, "application/json");
+    assert_eq!(content_type, "application/json");
+    assert!(body.starts_with('['));
+
+    let (content_type, body) = get_as!(app, &threads_uri, "text/plain");
+    assert_eq!(content_type, "text/plain; charset=utf-8");
+    assert!(body.starts_with(&format!("#{} Plain subject\nAnonymous | ", thread.id)));
+    assert!(body.contains("\n    line one\n    line two\n"));
+
+    let (content_type, body) = get_as!(app, &replies_uri, "text/tab-separated-values");
+    assert_eq!(content_type, "text/tab-separated-values; charset=utf-8");
+    let lines: Vec<&str> = body.lines().collect();
+    assert_eq!(
+        lines[0],
+        "id\tthread_id\tcreated_at\tauthor_name\ttripcode\timage_hash\tcontent"
+    );
+    assert_eq!(lines.len(), 2);
+    let fields: Vec<&str> = lines[1].split('\t').collect();
+    assert_eq!(fields[0], reply.id.to_string());
+    assert_eq!(fields[3], "Named");
+    assert_eq!(fields[6], "tab\\there");
+
+    // Wildcards keep the JSON default.
+    let (content_type, _) = get_as!(app, &replies_uri, "*/*");
+    assert_eq!(content_type, "application/json");
+}
+
+#[actix_web::test]
+async fn listings_stream_ndjson() {
+    let repo = test_repo().await;
+    let slug = format!("nd{}", &uuid::Uuid::new_v4().simple().to_string()[..10]);
+    let board = repo
+        .create_board(NewBoard {
+            slug,
+            title: "Streams".into(),
+        })
+        .await
+        .unwrap();
+    let mut threads = Vec::new();
+    for subject in ["older", "newer"] {
+        let thread = repo
+            .create_thread(
+                NewThread {
+                    board_id: board.id,
+                    subject: subject.into(),
+                    body: "body".into(),
+                    image_hash: None,
+                    mime: None,
+                    author_name: None,
+                    tripcode_password: None,
+                    delete_password: None,
+                    tags: vec!["news".into()],
+                },
+                json!({"provider": "test", "subject": "test:ndjson"}),
+                PublicIdentity::default(),
+            )
+            .await
+            .unwrap();
+        threads.push(thread);
+    }
+    let mut replies = Vec::new();
+    for n in 0..3 {
+        let reply = repo
+            .create_reply(
+                NewReply {
+                    thread_id: threads[0].id,
+                    content: format!("line\n{n}"),
+                    image_hash: None,
+                    mime: None,
+                    author_name: None,
+                    tripcode_password: None,
+                    delete_password: None,
+                },
+                json!({"provider": "test", "subject": "test:ndjson"}),
+                PublicIdentity::default(),
+            )
+            .await
+            .unwrap();
+        replies.push(reply);
+    }
+    repo.soft_delete_reply(replies[1].id).await.unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let lines = |body: &str| -> Vec<serde_json::Value> {
+        body.lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    };
+
+    let replies_uri = format!("/api/v1/threads/{}/replies", threads[0].id);
+    let (content_type, body) = get_as!(app, &replies_uri, "application/x-ndjson");
+    assert_eq!(content_type, "application/x-ndjson");
+    let streamed = lines(&body);
+    assert_eq!(streamed.len(), 2);
+    assert_eq!(streamed[0]["id"], replies[0].id);
+    assert_eq!(streamed[0]["content"], "line\n0");
+    assert_eq!(streamed[1]["id"], replies[2].id);
+
+    // The reply above bumped the older thread.
+    let threads_uri = format!("/api/v1/boards/{}/threads", board.id);
+    let (_, body) = get_as!(app, &threads_uri, "application/x-ndjson");
+    let ids: Vec<_> = lines(&body).iter().map(|t| t["id"].clone()).collect();
+    assert_eq!(ids, [json!(threads[0].id), json!(threads[1].id)]);
+    assert_eq!(lines(&body)[0]["reply_count"], 2);
+
+    let (content_type, body) = get_as!(
+        app,
+        &format!("{threads_uri}?tag=news"),
+        "application/x-ndjson"
+    );
+    assert_eq!(content_type, "application/x-ndjson");
+    assert_eq!(lines(&body).len(), 2);
+
+    // Field selection trims JSON and NDJSON items alike.
+    let (_, body) = get_as!(
+        app,
+        &format!("{threads_uri}?fields=id,subject,reply_count"),
+        "application/json"
+    );
+    let trimmed: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        trimmed[0],
+        json!({"id": threads[0].id, "subject": "older", "reply_count": 2})
+    );
+    let (_, body) = get_as!(
+        app,
+        &format!("{replies_uri}?fields=id"),
+        "application/x-ndjson"
+    );
+    assert_eq!(
+        lines(&body),
+        [json!({"id": replies[0].id}), json!({"id": replies[2].id})]
+    );
+    for accept in ["application/json", "application/x-ndjson"] {
+        let resp = test::call_service(
+            &app,
+            test::TestRequest::get()
+                .uri(&format!("{replies_uri}?fields=id,created_by"))
+                .insert_header(("accept", accept))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/threads/{}/replies", i64::MAX))
+            .insert_header(("accept", "application/x-ndjson"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 404);
+}
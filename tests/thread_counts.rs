@@ -0,0 +1,83 @@
+use rib::models::{NewBoard, NewReply, NewThread, PublicIdentity, ThreadOrder};
+use rib::repo::pg::PgRepo;
+use rib::repo::{BoardRepo, ReplyRepo, ThreadRepo};
+
+#[actix_web::test]
+async fn reply_and_image_counts_follow_visible_replies() {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let repo = PgRepo::new(pool);
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let board = repo
+        .create_board(NewBoard {
+            slug: format!("cnt{}", &suffix[..8]),
+            title: "Thread counts".to_string(),
+        })
+        .await
+        .expect("create board");
+    let thread = repo
+        .create_thread(
+            NewThread {
+                board_id: board.id,
+                subject: "counted".to_string(),
+                body: "op image is not a reply image".to_string(),
+                image_hash: Some(format!("{suffix}{suffix}")),
+                mime: Some("image/png".to_string()),
+                author_name: None,
+                tripcode_password: None,
+                delete_password: None,
+                tags: Vec::new(),
+            },
+            serde_json::json!({"provider":"test"}),
+            PublicIdentity::default(),
+        )
+        .await
+        .expect("create thread");
+    assert_eq!((thread.reply_count, thread.image_count), (0, 0));
+
+    let mut replies = Vec::new();
+    for n in 0..3u8 {
+        let image_hash = (n < 2).then(|| format!("{:0>64}", format!("{n}{suffix}")));
+        let reply = repo
+            .create_reply(
+                NewReply {
+                    thread_id: thread.id,
+                    content: format!("reply {n}"),
+                    mime: image_hash.as_ref().map(|_| "image/png".to_string()),
+                    image_hash,
+                    author_name: None,
+                    tripcode_password: None,
+                    delete_password: None,
+                },
+                serde_json::json!({"provider":"test"}),
+                PublicIdentity::default(),
+            )
+            .await
+            .expect("create reply");
+        replies.push(reply);
+    }
+    let counts = |thread: rib::models::Thread| (thread.reply_count, thread.image_count);
+    assert_eq!(counts(repo.get_thread(thread.id).await.unwrap()), (3, 2));
+
+    repo.soft_delete_reply(replies[0].id).await.unwrap();
+    repo.soft_delete_reply(replies[2].id).await.unwrap();
+    assert_eq!(counts(repo.get_thread(thread.id).await.unwrap()), (1, 1));
+    repo.restore_reply(replies[0].id).await.unwrap();
+    assert_eq!(counts(repo.get_thread(thread.id).await.unwrap()), (2, 2));
+
+    repo.hard_delete_reply(replies[1].id).await.unwrap();
+    repo.hard_delete_reply(replies[2].id).await.unwrap();
+    let listed = repo
+        .list_threads(board.id, false, ThreadOrder::default())
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|t| t.id == thread.id)
+        .expect("thread listed");
+    assert_eq!(counts(listed), (1, 1));
+}
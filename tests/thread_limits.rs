@@ -0,0 +1,192 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct MockImageStore {
+    inner: Mutex<HashMap<String, (Vec<u8>, String)>>,
+}
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, hash: &str, mime: &str, bytes: &[u8]) -> Result<(), ImageStoreError> {
+        let mut map = self.inner.lock().unwrap();
+        if map.contains_key(hash) {
+            return Err(ImageStoreError::Duplicate);
+        }
+        map.insert(hash.to_string(), (bytes.to_vec(), mime.to_string()));
+        Ok(())
+    }
+
+    async fn load(&self, hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        let map = self.inner.lock().unwrap();
+        map.get(hash).cloned().ok_or(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, hash: &str) -> Result<(), ImageStoreError> {
+        self.inner.lock().unwrap().remove(hash);
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+fn ids(threads: &[Thread]) -> Vec<i64> {
+    threads.iter().map(|thread| thread.id).collect()
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn threads_past_the_page_limit_are_archived_or_pruned() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let poster_id = format!("pager-{}", &suffix[..8]);
+    let repo = PgRepo::new(pool);
+    repo.set_subject_role(&format!("discord:{poster_id}"), Role::User)
+        .await
+        .expect("allowlist poster");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore::default()),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let poster = create_jwt(&poster_id, &poster_id, vec![Role::User]).unwrap();
+
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": format!("pl{}", &suffix[..8]), "title": "Paged"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    assert_eq!(board.max_threads, 0);
+    let patch = |body: serde_json::Value| {
+        test::TestRequest::patch()
+            .uri(&format!("/api/v1/boards/{}", board.id))
+            .set_json(body)
+    };
+    assert_eq!(
+        call!(app, patch(json!({"max_threads": -1})), admin).status(),
+        400
+    );
+    assert_eq!(
+        call!(app, patch(json!({"max_threads": 2})), poster).status(),
+        403
+    );
+    let resp = call!(app, patch(json!({"max_threads": 2})), admin);
+    let updated: Board = test::read_body_json(resp).await;
+    assert_eq!(updated.max_threads, 2);
+    assert!(!updated.prune_overflow);
+
+    let mut threads = Vec::new();
+    for n in 0..4 {
+        if n == 3 {
+            // Bumping the oldest thread keeps it on the board.
+            let resp = call!(
+                app,
+                test::TestRequest::post()
+                    .uri("/api/v1/replies")
+                    .set_json(json!({"thread_id": threads[1], "content": "bump"})),
+                poster
+            );
+            assert_eq!(resp.status(), 201);
+        }
+        let resp = call!(
+            app,
+            test::TestRequest::post()
+                .uri("/api/v1/threads")
+                .set_json(json!({"board_id": board.id, "subject": format!("t{n}"), "body": "x"})),
+            poster
+        );
+        let thread: Thread = test::read_body_json(resp).await;
+        threads.push(thread.id);
+    }
+    let listed: Vec<Thread> = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/boards/{}/threads", board.id))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(ids(&listed), vec![threads[3], threads[1]]);
+    let archived: Vec<Thread> = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/boards/{}/archive", board.id))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(ids(&archived), vec![threads[2], threads[0]]);
+    assert!(archived.iter().all(|thread| thread.archived_at.is_some()));
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .set_json(json!({"thread_id": threads[0], "content": "too late"})),
+        poster
+    );
+    assert_eq!(resp.status(), 409);
+
+    // Lowering the limit with pruning soft-deletes the overflow right away.
+    let resp = call!(
+        app,
+        patch(json!({"max_threads": 1, "prune_overflow": true})),
+        admin
+    );
+    assert_eq!(resp.status(), 200);
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/threads/{}", threads[1]))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 404);
+    let listed: Vec<Thread> = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/boards/{}/threads", board.id))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(ids(&listed), vec![threads[3]]);
+    let archived: Vec<Thread> = test::call_and_read_body_json(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/boards/{}/archive", board.id))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(archived.len(), 2);
+}
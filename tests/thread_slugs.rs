@@ -0,0 +1,129 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, Thread};
+use rib::repo::pg::PgRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct NoImages;
+
+#[async_trait::async_trait]
+impl ImageStore for NoImages {
+    async fn save(&self, _: &str, _: &str, _: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+    async fn load(&self, _: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+    async fn delete(&self, _: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn readable_thread_urls_redirect_to_the_current_slug() {
+    std::env::set_var("JWT_SECRET", "testsecret");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(PgRepo::new(pool.clone())),
+                Arc::new(NoImages),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(json!({"slug": format!("sl{}", &suffix[..8]), "title": "Slugs"}))
+            .to_request(),
+    )
+    .await;
+    let board: Board = test::read_body_json(resp).await;
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .insert_header(("Authorization", format!("Bearer {admin}")))
+            .set_json(
+                json!({"board_id": board.id, "subject": "Hello, World! Rust 2026", "body": "hi"}),
+            )
+            .to_request(),
+    )
+    .await;
+    let thread: Thread = test::read_body_json(resp).await;
+    assert_eq!(thread.slug, "hello-world-rust-2026");
+
+    let get = |board_slug: &str, handle: &str| {
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/boards/{board_slug}/threads/{handle}"))
+            .to_request()
+    };
+    let location = |resp: &actix_web::dev::ServiceResponse| {
+        resp.headers()
+            .get("Location")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string()
+    };
+    let canonical = format!(
+        "/api/v1/boards/{}/threads/{}-hello-world-rust-2026",
+        board.slug, thread.id
+    );
+    let resp =
+        test::call_service(&app, test::TestRequest::get().uri(&canonical).to_request()).await;
+    assert_eq!(resp.status(), 200);
+    let fetched: Thread = test::read_body_json(resp).await;
+    assert_eq!(fetched.id, thread.id);
+
+    let resp = test::call_service(&app, get(&board.slug, &thread.id.to_string())).await;
+    assert_eq!(resp.status(), 301);
+    assert_eq!(location(&resp), canonical);
+    let resp = test::call_service(&app, get(&board.slug, &format!("{}-hello", thread.id))).await;
+    assert_eq!(resp.status(), 404);
+    let resp = test::call_service(&app, get(&board.slug, "hello-world-rust-2026")).await;
+    assert_eq!(resp.status(), 404);
+    let resp = test::call_service(&app, get("no-such-board", &thread.id.to_string())).await;
+    assert_eq!(resp.status(), 404);
+
+    // A new subject moves the thread to a new slug; the old one still leads there.
+    sqlx::query("UPDATE threads SET subject = 'Renamed: now with async' WHERE id = $1")
+        .bind(thread.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let renamed = format!(
+        "/api/v1/boards/{}/threads/{}-renamed-now-with-async",
+        board.slug, thread.id
+    );
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("{canonical}?include_deleted=1"))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 301);
+    assert_eq!(location(&resp), format!("{renamed}?include_deleted=1"));
+    let resp = test::call_service(&app, test::TestRequest::get().uri(&renamed).to_request()).await;
+    assert_eq!(resp.status(), 200);
+    let fetched: Thread = test::read_body_json(resp).await;
+    assert_eq!(fetched.slug, "renamed-now-with-async");
+}
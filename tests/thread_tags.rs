@@ -0,0 +1,156 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, TagCount, Thread};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct MockImageStore {
+    inner: Mutex<HashMap<String, (Vec<u8>, String)>>,
+}
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, hash: &str, mime: &str, bytes: &[u8]) -> Result<(), ImageStoreError> {
+        let mut map = self.inner.lock().unwrap();
+        if map.contains_key(hash) {
+            return Err(ImageStoreError::Duplicate);
+        }
+        map.insert(hash.to_string(), (bytes.to_vec(), mime.to_string()));
+        Ok(())
+    }
+
+    async fn load(&self, hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        let map = self.inner.lock().unwrap();
+        map.get(hash).cloned().ok_or(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, hash: &str) -> Result<(), ImageStoreError> {
+        self.inner.lock().unwrap().remove(hash);
+        Ok(())
+    }
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn threads_carry_tags_and_boards_list_them() {
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let user_id = format!("tagger-{}", &suffix[..8]);
+    let repo = PgRepo::new(pool);
+    repo.set_subject_role(&format!("discord:{user_id}"), Role::User)
+        .await
+        .expect("allowlist poster");
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(repo),
+                Arc::new(MockImageStore::default()),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let user = create_jwt(&user_id, &user_id, vec![Role::User]).unwrap();
+
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": format!("tg{}", &suffix[..8]), "title": "Tags"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    let post = |tags: serde_json::Value| {
+        test::TestRequest::post().uri("/api/v1/threads").set_json(
+            json!({"board_id": board.id, "subject": "s", "body": format!("{tags}"), "tags": tags}),
+        )
+    };
+
+    // Free-form until the board sets a vocabulary.
+    let resp = call!(app, post(json!(["Rust", "rust", "async"])), user);
+    assert_eq!(resp.status(), 201);
+    let first: Thread = test::read_body_json(resp).await;
+    assert_eq!(first.tags, vec!["rust", "async"]);
+    for bad in [json!(["two words"]), json!(["a", "b", "c", "d", "e", "f"])] {
+        assert_eq!(call!(app, post(bad), user).status(), 400);
+    }
+
+    let resp = call!(
+        app,
+        test::TestRequest::patch()
+            .uri(&format!("/api/v1/boards/{}", board.id))
+            .set_json(json!({"tag_vocabulary": ["Rust", "meta", "news"]})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+    assert_eq!(board.tag_vocabulary, vec!["rust", "meta", "news"]);
+    assert_eq!(call!(app, post(json!(["async"])), user).status(), 400);
+    let resp = call!(app, post(json!(["rust", "meta"])), user);
+    assert_eq!(resp.status(), 201);
+    let second: Thread = test::read_body_json(resp).await;
+    let resp = call!(app, post(json!([])), user);
+    assert_eq!(resp.status(), 201);
+
+    let list = |query: &str| {
+        test::TestRequest::get().uri(&format!("/api/v1/boards/{}/threads{query}", board.id))
+    };
+    let resp = test::call_service(&app, list("?tag=RUST").to_request()).await;
+    let tagged: Vec<Thread> = test::read_body_json(resp).await;
+    assert_eq!(
+        tagged.iter().map(|t| t.id).collect::<Vec<_>>(),
+        vec![second.id, first.id]
+    );
+    let resp = test::call_service(&app, list("?tag=async").to_request()).await;
+    let tagged: Vec<Thread> = test::read_body_json(resp).await;
+    assert_eq!(
+        tagged.iter().map(|t| t.id).collect::<Vec<_>>(),
+        vec![first.id]
+    );
+    let resp = test::call_service(&app, list("?tag=not%20a%20tag").to_request()).await;
+    let tagged: Vec<Thread> = test::read_body_json(resp).await;
+    assert!(tagged.is_empty());
+    let resp = test::call_service(&app, list("").to_request()).await;
+    let all: Vec<Thread> = test::read_body_json(resp).await;
+    assert_eq!(all.len(), 3);
+
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/boards/{}/tags", board.id))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 200);
+    let counts: Vec<TagCount> = test::read_body_json(resp).await;
+    let counts: Vec<_> = counts.iter().map(|c| (c.tag.as_str(), c.count)).collect();
+    assert_eq!(
+        counts,
+        vec![("rust", 2), ("async", 1), ("meta", 1), ("news", 0)]
+    );
+}
@@ -0,0 +1,273 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{NewBoard, NewReply, NewThread, PublicIdentity, SortOrder, ThreadOrder};
+use rib::repo::pg::PgRepo;
+use rib::repo::{BoardRepo, ReplyRepo, RoleRepo, ThreadRepo};
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::transfer::{Dump, DumpRecord};
+use rib::{config, AppState};
+use serde_json::Value;
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+fn admin_token() -> String {
+    if std::env::var("JWT_SECRET").is_err() {
+        std::env::set_var("JWT_SECRET", "testsecret");
+    }
+    create_jwt("admin", "admin", vec![Role::Admin]).unwrap()
+}
+
+fn unique(prefix: &str) -> String {
+    format!("{prefix}{}", uuid::Uuid::new_v4().simple())
+}
+
+async fn seed(repo: &PgRepo) -> (String, String) {
+    let slug = unique("xfer-")[..20].to_string();
+    let board = repo
+        .create_board(NewBoard {
+            slug: slug.clone(),
+            title: "Transfer".into(),
+        })
+        .await
+        .unwrap();
+    let hash = format!("{:0>64}", uuid::Uuid::new_v4().simple().to_string());
+    let thread = repo
+        .create_thread(
+            NewThread {
+                board_id: board.id,
+                subject: "exported".into(),
+                body: "thread body".into(),
+                image_hash: Some(hash.clone()),
+                mime: Some("image/png".into()),
+                author_name: None,
+                tripcode_password: None,
+                delete_password: None,
+                tags: Vec::new(),
+            },
+            serde_json::json!({"provider": "test"}),
+            PublicIdentity::default(),
+        )
+        .await
+        .unwrap();
+    repo.create_reply(
+        NewReply {
+            thread_id: thread.id,
+            content: "exported reply".into(),
+            image_hash: None,
+            mime: None,
+            author_name: None,
+            tripcode_password: None,
+            delete_password: None,
+        },
+        serde_json::json!({"provider": "test"}),
+        PublicIdentity::default(),
+    )
+    .await
+    .unwrap();
+    (slug, hash)
+}
+
+fn to_ndjson(records: &[DumpRecord]) -> String {
+    records
+        .iter()
+        .map(|r| serde_json::to_string(r).unwrap() + "\n")
+        .collect()
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn export_then_import_round_trips_with_conflict_strategies() {
+    let repo = test_repo().await;
+    let (slug, hash) = seed(&repo).await;
+    repo.set_subject_role("discord:transfer", Role::Moderator)
+        .await
+        .unwrap();
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(test_repo().await),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let admin = admin_token();
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/admin/export")
+        .insert_header(("Authorization", format!("Bearer {admin}")))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/x-ndjson"
+    );
+    let body = test::read_body(resp).await;
+    let dump = Dump::parse(&body).expect("export parses");
+    let board = dump.boards.iter().find(|b| b.slug == slug).unwrap().clone();
+    let thread = dump
+        .threads
+        .iter()
+        .find(|t| t.board_id == board.id)
+        .unwrap()
+        .clone();
+    let reply = dump
+        .replies
+        .iter()
+        .find(|r| r.thread_id == thread.id)
+        .unwrap()
+        .clone();
+    let image = dump.images.iter().find(|i| i.hash == hash).unwrap().clone();
+    assert_eq!(image.thread_id, Some(thread.id));
+    assert!(dump
+        .roles
+        .iter()
+        .any(|r| r.subject == "discord:transfer" && r.role == "moderator"));
+
+    // Re-importing into the same instance conflicts on every board slug.
+    let req = test::TestRequest::post()
+        .uri("/api/v1/admin/import")
+        .insert_header(("Authorization", format!("Bearer {admin}")))
+        .set_payload(body.clone())
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 409);
+    let report: Value = test::read_body_json(resp).await;
+    assert_eq!(report["committed"], false);
+    assert!(report["conflicts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|c| c.as_str().unwrap().contains(&slug)));
+
+    // A renamed copy of the board imports as new rows sharing the image blob.
+    let copy_slug = format!("{slug}-copy");
+    let mut renamed = board.clone();
+    renamed.slug = copy_slug.clone();
+    let records = vec![
+        DumpRecord::Header {
+            format: rib::transfer::DUMP_FORMAT.into(),
+            version: rib::transfer::DUMP_VERSION,
+            exported_at: chrono::Utc::now(),
+        },
+        DumpRecord::Board(renamed),
+        DumpRecord::Thread(thread.clone()),
+        DumpRecord::Reply(reply),
+        DumpRecord::Image(image),
+        DumpRecord::End { records: 4 },
+    ];
+    let payload = to_ndjson(&records);
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/admin/import?dry_run=true&on_conflict=skip")
+        .insert_header(("Authorization", format!("Bearer {admin}")))
+        .set_payload(payload.clone())
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let report: Value = test::read_body_json(resp).await;
+    assert_eq!(report["committed"], false);
+    assert_eq!(report["boards"]["created"], 1);
+    assert_eq!(report["images"]["created"], 1);
+    let boards = repo.list_boards(true).await.unwrap();
+    assert!(!boards.iter().any(|b| b.slug == copy_slug));
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/admin/import?on_conflict=skip")
+        .insert_header(("Authorization", format!("Bearer {admin}")))
+        .set_payload(payload)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 200);
+    let report: Value = test::read_body_json(resp).await;
+    assert_eq!(report["committed"], true);
+    assert_eq!(report["replies"]["created"], 1);
+
+    let copy = repo
+        .list_boards(true)
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|b| b.slug == copy_slug)
+        .expect("imported board");
+    let threads = repo
+        .list_threads(copy.id, true, ThreadOrder::default())
+        .await
+        .unwrap();
+    assert_eq!(threads.len(), 1);
+    assert_eq!(threads[0].created_at, thread.created_at);
+    assert_eq!(threads[0].created_by, thread.created_by);
+    let replies = repo
+        .list_replies(threads[0].id, true, SortOrder::Asc)
+        .await
+        .unwrap();
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0].content, "exported reply");
+}
+
+#[actix_web::test]
+async fn import_rejects_truncated_dumps_and_non_admins() {
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(AppState::new(
+                Arc::new(test_repo().await),
+                Arc::new(MockImageStore),
+                None,
+            )))
+            .configure(config),
+    )
+    .await;
+    let header = to_ndjson(&[DumpRecord::Header {
+        format: rib::transfer::DUMP_FORMAT.into(),
+        version: rib::transfer::DUMP_VERSION,
+        exported_at: chrono::Utc::now(),
+    }]);
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/admin/import")
+        .insert_header(("Authorization", format!("Bearer {}", admin_token())))
+        .set_payload(header.clone())
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 400);
+    let body: Value = test::read_body_json(resp).await;
+    assert!(body["error"].as_str().unwrap().contains("truncated"));
+
+    let user = create_jwt("user", "user", vec![Role::User]).unwrap();
+    let req = test::TestRequest::post()
+        .uri("/api/v1/admin/import")
+        .insert_header(("Authorization", format!("Bearer {user}")))
+        .set_payload(header)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), 403);
+}
@@ -0,0 +1,285 @@
+use actix_web::{test, App};
+use rib::auth::{create_jwt, Role};
+use rib::models::{Board, HeldPost, Reply, Thread};
+use rib::pow::{is_solution, PowChallenge, POW_HEADER};
+use rib::repo::pg::PgRepo;
+use rib::repo::RoleRepo;
+use rib::storage::{ImageStore, ImageStoreError};
+use rib::trust::{TrustConfig, TrustReport};
+use rib::{config, AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+struct MockImageStore;
+
+#[async_trait::async_trait]
+impl ImageStore for MockImageStore {
+    async fn save(&self, _hash: &str, _mime: &str, _bytes: &[u8]) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+
+    async fn load(&self, _hash: &str) -> Result<(Vec<u8>, String), ImageStoreError> {
+        Err(ImageStoreError::NotFound)
+    }
+
+    async fn delete(&self, _hash: &str) -> Result<(), ImageStoreError> {
+        Ok(())
+    }
+}
+
+fn solve(challenge: &PowChallenge) -> String {
+    let nonce = (0u64..)
+        .map(|n| n.to_string())
+        .find(|nonce| is_solution(&challenge.challenge, nonce, challenge.difficulty))
+        .unwrap();
+    format!("{}:{nonce}", challenge.challenge)
+}
+
+macro_rules! pow_token {
+    ($app:expr) => {{
+        let challenge: PowChallenge = test::call_and_read_body_json(
+            &$app,
+            test::TestRequest::get().uri("/api/v1/pow").to_request(),
+        )
+        .await;
+        solve(&challenge)
+    }};
+}
+
+macro_rules! call {
+    ($app:expr, $req:expr, $token:expr) => {
+        test::call_service(
+            &$app,
+            $req.insert_header(("Authorization", format!("Bearer {}", $token)))
+                .to_request(),
+        )
+        .await
+    };
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn trust_scores_hold_low_trust_posts_and_waive_proof_of_work() {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    std::env::set_var("JWT_SECRET", "testsecretabcdefghijklmnopqrstuvwxyz012345");
+    std::env::set_var("POW_DIFFICULTY", "4");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    let user_id = format!("trust-{}", &suffix[..8]);
+    let subject = format!("discord:{user_id}");
+    let repo = PgRepo::new(pool);
+    repo.set_subject_role(&subject, Role::User)
+        .await
+        .expect("allowlist poster");
+    // Two posts earn full trust; a removal costs half of it, a ban all of it.
+    let trust = TrustConfig {
+        full_posts: 2.0,
+        age_weight: 0.0,
+        posts_weight: 1.0,
+        removal_penalty: 0.5,
+        ban_penalty: 1.0,
+        skip_pow_at: Some(0.5),
+        hold_below: Some(0.5),
+        ..TrustConfig::disabled()
+    };
+    let app = test::init_service(
+        App::new()
+            .app_data(actix_web::web::Data::new(
+                AppState::new(Arc::new(repo), Arc::new(MockImageStore), None).with_trust(trust),
+            ))
+            .configure(config),
+    )
+    .await;
+    std::env::remove_var("POW_DIFFICULTY");
+    let admin = create_jwt("admin-id", "admin-id", vec![Role::Admin]).unwrap();
+    let moderator = create_jwt("mod-id", "mod-id", vec![Role::Moderator]).unwrap();
+    let user = create_jwt(&user_id, &user_id, vec![Role::User]).unwrap();
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/boards")
+            .set_json(json!({"slug": format!("tr{}", &suffix[..8]), "title": "Trust"})),
+        admin
+    );
+    let board: Board = test::read_body_json(resp).await;
+
+    // A subject without history is held for review.
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/threads")
+            .set_json(json!({"board_id": board.id, "subject": "first", "body": "hello"})),
+        user
+    );
+    assert_eq!(resp.status(), 202);
+    let thread: Thread = test::read_body_json(resp).await;
+    let thread_uri = format!("/api/v1/threads/{}", thread.id);
+    let get_thread =
+        |uri: &str| test::call_service(&app, test::TestRequest::get().uri(uri).to_request());
+    assert_eq!(get_thread(&thread_uri).await.status(), 404);
+    let held_queue = || test::TestRequest::get().uri("/api/v1/admin/held-posts");
+    assert_eq!(call!(app, held_queue(), user).status(), 403);
+    let resp = call!(app, held_queue(), moderator);
+    let held: Vec<HeldPost> = test::read_body_json(resp).await;
+    let entry = held
+        .iter()
+        .find(|post| post.thread_id == thread.id)
+        .expect("thread is held");
+    assert_eq!(entry.reply_id, None);
+    assert_eq!(entry.subject.as_deref(), Some(subject.as_str()));
+    let approve = format!("/api/v1/admin/threads/{}/approve", thread.id);
+    assert_eq!(
+        call!(app, test::TestRequest::post().uri(&approve), user).status(),
+        403
+    );
+    assert_eq!(
+        call!(app, test::TestRequest::post().uri(&approve), moderator).status(),
+        204
+    );
+    assert_eq!(
+        call!(app, test::TestRequest::post().uri(&approve), moderator).status(),
+        404
+    );
+    assert_eq!(get_thread(&thread_uri).await.status(), 200);
+
+    // One approved post brings the score to the threshold.
+    let reply = |content: &str| {
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .set_json(json!({"thread_id": thread.id, "content": content}))
+    };
+    let resp = call!(app, reply("second"), user);
+    assert_eq!(resp.status(), 201);
+    let second: Reply = test::read_body_json(resp).await;
+    let resp = call!(
+        app,
+        test::TestRequest::post().uri("/api/v1/replies").set_json(
+            json!({"thread_id": thread.id, "content": "oops", "delete_password": "hunter22"})
+        ),
+        user
+    );
+    assert_eq!(resp.status(), 201);
+    let oops: Reply = test::read_body_json(resp).await;
+    let resp = test::call_service(
+        &app,
+        test::TestRequest::delete()
+            .uri(&format!("/api/v1/replies/{}", oops.id))
+            .set_json(json!({"password": "hunter22"}))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 204);
+
+    let report = || test::TestRequest::get().uri(&format!("/api/v1/admin/trust/{subject}"));
+    assert_eq!(call!(app, report(), user).status(), 403);
+    let resp = call!(app, report(), moderator);
+    let trust: TrustReport = test::read_body_json(resp).await;
+    let history = trust.history.expect("history");
+    assert_eq!((history.posts, history.removals, history.bans), (3, 0, 0));
+    assert_eq!(trust.score, 1.0);
+
+    // Staff removals count until restored.
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/admin/replies/{}/soft-delete", second.id))
+            .set_json(json!({"reason": "Rule 2: spam"})),
+        moderator
+    );
+    assert_eq!(resp.status(), 200);
+    let trust: TrustReport = test::read_body_json(call!(app, report(), moderator)).await;
+    assert_eq!(trust.history.unwrap().removals, 1);
+    assert_eq!(trust.score, 0.5);
+    let resp = call!(
+        app,
+        test::TestRequest::post().uri(&format!("/api/v1/admin/replies/{}/restore", second.id)),
+        moderator
+    );
+    assert_eq!(resp.status(), 200);
+    let trust: TrustReport = test::read_body_json(call!(app, report(), moderator)).await;
+    assert_eq!(trust.history.unwrap().removals, 0);
+
+    // A ban, even once lifted, drops the score and holds posts again.
+    let resp = call!(
+        app,
+        test::TestRequest::post()
+            .uri("/api/v1/admin/bans")
+            .set_json(json!({"subject": subject, "reason": "spam"})),
+        moderator
+    );
+    assert_eq!(resp.status(), 201);
+    let resp = call!(
+        app,
+        test::TestRequest::delete().uri(&format!("/api/v1/admin/bans/{subject}")),
+        moderator
+    );
+    assert_eq!(resp.status(), 204);
+    let resp = call!(app, reply("after the ban"), user);
+    assert_eq!(resp.status(), 202);
+    let held_reply: Reply = test::read_body_json(resp).await;
+    let resp = call!(app, held_queue(), moderator);
+    let held: Vec<HeldPost> = test::read_body_json(resp).await;
+    assert!(held
+        .iter()
+        .any(|post| post.reply_id == Some(held_reply.id) && post.content == "after the ban"));
+    let resp = call!(
+        app,
+        test::TestRequest::post().uri(&format!("/api/v1/admin/replies/{}/reject", held_reply.id)),
+        moderator
+    );
+    assert_eq!(resp.status(), 204);
+    let trust: TrustReport = test::read_body_json(call!(app, report(), moderator)).await;
+    let history = trust.history.unwrap();
+    assert_eq!((history.posts, history.removals, history.bans), (4, 1, 1));
+    assert_eq!(trust.score, 0.0);
+    assert!(trust.hold);
+    let resp = call!(app, held_queue(), moderator);
+    let held: Vec<HeldPost> = test::read_body_json(resp).await;
+    assert!(held.iter().all(|post| post.reply_id != Some(held_reply.id)));
+
+    // Anonymous posters earn trust per address and then skip the proof of work.
+    let resp = call!(
+        app,
+        test::TestRequest::patch()
+            .uri(&format!("/api/v1/boards/{}", board.id))
+            .set_json(json!({"anonymous_posting": true})),
+        admin
+    );
+    assert_eq!(resp.status(), 200);
+    let bytes = uuid::Uuid::new_v4().into_bytes();
+    let peer: std::net::SocketAddr = format!("10.{}.{}.{}:4000", bytes[0], bytes[1], bytes[2])
+        .parse()
+        .unwrap();
+    let anon_reply = |content: &str| {
+        test::TestRequest::post()
+            .uri("/api/v1/replies")
+            .peer_addr(peer)
+            .set_json(json!({"thread_id": thread.id, "content": content}))
+    };
+    let resp = test::call_service(&app, anon_reply("anon one").to_request()).await;
+    assert_eq!(resp.status(), 400, "proof of work is required at first");
+    let token = pow_token!(app);
+    let resp = test::call_service(
+        &app,
+        anon_reply("anon one")
+            .insert_header((POW_HEADER, token))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(resp.status(), 202);
+    let anon: Reply = test::read_body_json(resp).await;
+    let resp = call!(
+        app,
+        test::TestRequest::post().uri(&format!("/api/v1/admin/replies/{}/approve", anon.id)),
+        moderator
+    );
+    assert_eq!(resp.status(), 204);
+    let resp = test::call_service(&app, anon_reply("anon two").to_request()).await;
+    assert_eq!(resp.status(), 201);
+}
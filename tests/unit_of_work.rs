@@ -0,0 +1,96 @@
+use rib::models::{NewBoard, NewThread, PublicIdentity, ThreadOrder};
+use rib::repo::pg::PgRepo;
+use rib::repo::{transaction, BoardRepo, ImageOwner, RepoError, ThreadRepo};
+
+async fn test_repo() -> PgRepo {
+    let database_url =
+        std::env::var("DATABASE_URL").expect("DATABASE_URL required for integration tests");
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await
+        .expect("connect test database");
+    PgRepo::new(pool)
+}
+
+fn new_thread(board_id: i64) -> NewThread {
+    NewThread {
+        board_id,
+        subject: "unit of work".to_string(),
+        body: "body".to_string(),
+        image_hash: None,
+        mime: None,
+        author_name: None,
+        tripcode_password: None,
+        delete_password: None,
+        tags: Vec::new(),
+    }
+}
+
+async fn create_board(repo: &PgRepo) -> i64 {
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    repo.create_board(NewBoard {
+        slug: format!("uow{}", &suffix[..8]),
+        title: "Unit of work".to_string(),
+    })
+    .await
+    .expect("create board")
+    .id
+}
+
+#[actix_web::test]
+async fn transaction_commits_all_steps() {
+    let repo = test_repo().await;
+    let board_id = create_board(&repo).await;
+    let hash = format!("{:0>64}", uuid::Uuid::new_v4().simple().to_string());
+    let image_hash = hash.clone();
+    let thread = transaction(&repo, |tx| {
+        let image_hash = image_hash.clone();
+        Box::pin(async move {
+            let thread = tx
+                .create_thread(
+                    new_thread(board_id),
+                    serde_json::json!({"provider":"test"}),
+                    PublicIdentity::default(),
+                )
+                .await?;
+            tx.attach_image(ImageOwner::Thread(thread.id), &image_hash, "image/png")
+                .await?;
+            tx.get_thread(thread.id).await
+        })
+    })
+    .await
+    .expect("transaction");
+    assert_eq!(thread.image_hash.as_deref(), Some(hash.as_str()));
+    assert_eq!(
+        repo.get_thread(thread.id)
+            .await
+            .expect("committed")
+            .image_hash,
+        Some(hash)
+    );
+}
+
+#[actix_web::test]
+async fn failed_step_rolls_back_earlier_writes() {
+    let repo = test_repo().await;
+    let board_id = create_board(&repo).await;
+    let result = transaction(&repo, |tx| {
+        Box::pin(async move {
+            tx.create_thread(
+                new_thread(board_id),
+                serde_json::json!({"provider":"test"}),
+                PublicIdentity::default(),
+            )
+            .await?;
+            tx.get_board(i64::MAX).await
+        })
+    })
+    .await;
+    assert!(matches!(result, Err(RepoError::NotFound)));
+    let threads = repo
+        .list_threads(board_id, true, ThreadOrder::default())
+        .await
+        .expect("list");
+    assert!(threads.is_empty());
+}